@@ -0,0 +1,248 @@
+use std::process::{Command, Stdio};
+
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{ActionCommand, Config},
+    processors::claude::structs::HookInput,
+};
+
+/// Action key of a click on the notification itself (the freedesktop default action).
+pub const CLICK: &str = "default";
+
+/// Action key of the "Dismiss all" button.
+pub const DISMISS_ALL: &str = "dismiss-all";
+
+/// Whether the configured `claude.actions` commands are attached to notifications. Handling
+/// an action means waiting for it, so that only happens in the background process of detached
+/// delivery; a blocking hook would keep Claude waiting until the notification is dismissed.
+pub fn should_register(config: &Config) -> bool {
+    if config.claude.actions.is_empty() {
+        return false;
+    }
+    if !config.background {
+        debug!("skipping notification actions outside detached delivery");
+        return false;
+    }
+    true
+}
+
+pub fn button_key(index: usize) -> String {
+    format!("button-{index}")
+}
+
+/// The `(key, label)` actions to add to a freedesktop notification. `capabilities` is only
+/// queried when there is something to add; servers without the `actions` capability get none.
+pub fn freedesktop_actions(
+    config: &Config,
+    capabilities: impl FnOnce() -> Vec<String>,
+) -> Vec<(String, String)> {
+    let actions = &config.claude.actions;
+    let wanted = should_register(config) || (config.background && actions.dismiss_all);
+    if !wanted {
+        return Vec::new();
+    }
+    if !capabilities().iter().any(|c| c == "actions") {
+        debug!("notification server doesn't support actions");
+        return Vec::new();
+    }
+
+    let mut keys = Vec::new();
+    if actions.on_click.is_some() {
+        keys.push((CLICK.to_string(), "Open".to_string()));
+    }
+    for (index, button) in actions.buttons.iter().enumerate() {
+        keys.push((button_key(index), button.label.clone()));
+    }
+    if actions.dismiss_all {
+        keys.push((DISMISS_ALL.to_string(), "Dismiss all".to_string()));
+    }
+    keys
+}
+
+/// Runs the command behind the action `key`: [`CLICK`] or a [`button_key`].
+pub fn run_action(config: &Config, hook_input: &HookInput, key: &str) {
+    let actions = &config.claude.actions;
+    let command = if key == CLICK {
+        actions.on_click.as_ref()
+    } else {
+        key.strip_prefix("button-")
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| actions.buttons.get(index))
+            .map(|button| &button.command)
+    };
+
+    match command {
+        Some(command) => run(command, hook_input),
+        None => debug!(action = key, "notification closed without a command"),
+    }
+}
+
+/// The command for `command`, with placeholders filled in. Shell commands get the values
+/// quoted for `sh`; argv commands get them as-is, since no shell parses them.
+fn build_command(command: &ActionCommand, hook_input: &HookInput) -> Option<Command> {
+    match command {
+        ActionCommand::Shell(template) => {
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(substitute(template, hook_input, shell_quote));
+            Some(command)
+        }
+        ActionCommand::Argv(argv) => {
+            let argv = render_argv(argv, hook_input);
+            let (program, args) = argv.split_first()?;
+            let mut command = Command::new(program);
+            command.args(args);
+            Some(command)
+        }
+    }
+}
+
+fn render_argv(argv: &[String], hook_input: &HookInput) -> Vec<String> {
+    argv.iter()
+        .map(|arg| substitute(arg, hook_input, str::to_string))
+        .collect()
+}
+
+fn substitute(template: &str, hook_input: &HookInput, quote: fn(&str) -> String) -> String {
+    [
+        ("{transcript_path}", hook_input.transcript_path.as_str()),
+        ("{session_id}", hook_input.session_id.as_str()),
+        ("{cwd}", hook_input.cwd.as_deref().unwrap_or_default()),
+    ]
+    .iter()
+    .fold(template.to_string(), |command, (placeholder, value)| {
+        command.replace(placeholder, &quote(value))
+    })
+}
+
+fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Starts the action command without waiting for it. Failures are only logged.
+fn run(command: &ActionCommand, hook_input: &HookInput) {
+    let Some(mut child) = build_command(command, hook_input) else {
+        warn!("empty notification action command");
+        return;
+    };
+
+    match child
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => debug!(pid = child.id(), command = ?command, "started notification action"),
+        Err(e) => warn!(command = ?command, error = %e, "failed to run notification action"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{ActionButton, ClaudeActions};
+
+    fn hook_input(transcript_path: &str) -> HookInput {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": transcript_path,
+            "hook_event_name": "Stop",
+            "cwd": "/work/my project",
+        }))
+        .unwrap()
+    }
+
+    fn config_with_actions(background: bool) -> Config {
+        let mut config = Config {
+            background,
+            ..Config::default()
+        };
+        config.claude.actions = ClaudeActions {
+            on_click: Some(ActionCommand::Shell("open -a iTerm".to_string())),
+            buttons: vec![ActionButton {
+                label: "Open project".to_string(),
+                command: ActionCommand::Argv(vec!["code".to_string(), "{cwd}".to_string()]),
+            }],
+            ..ClaudeActions::default()
+        };
+        config
+    }
+
+    fn capabilities(names: &[&str]) -> Vec<String> {
+        names.iter().map(|c| c.to_string()).collect()
+    }
+
+    #[test]
+    fn renders_quoted_shell_placeholders() {
+        let input = hook_input("/tmp/it's here.jsonl");
+
+        assert_eq!(
+            substitute("\"$EDITOR\" {transcript_path}", &input, shell_quote),
+            r#""$EDITOR" '/tmp/it'\''s here.jsonl'"#
+        );
+        assert_eq!(
+            substitute("cd {cwd} && echo {session_id}", &input, shell_quote),
+            "cd '/work/my project' && echo 'abc123'"
+        );
+        assert_eq!(
+            substitute("open -a iTerm", &input, shell_quote),
+            "open -a iTerm"
+        );
+    }
+
+    #[test]
+    fn builds_argv_without_a_shell() {
+        let input = hook_input("/tmp/t.jsonl");
+        let argv = ["code", "{cwd}", "--goto", "{transcript_path}"].map(String::from);
+
+        assert_eq!(
+            render_argv(&argv, &input),
+            ["code", "/work/my project", "--goto", "/tmp/t.jsonl"]
+        );
+
+        let command = build_command(&ActionCommand::Argv(argv.to_vec()), &input).unwrap();
+        assert_eq!(command.get_program(), "code");
+        assert_eq!(command.get_args().count(), 3);
+        assert!(build_command(&ActionCommand::Argv(Vec::new()), &input).is_none());
+    }
+
+    #[test]
+    fn blocking_delivery_skips_actions() {
+        assert!(!should_register(&config_with_actions(false)));
+        assert!(should_register(&config_with_actions(true)));
+        assert!(!should_register(&Config {
+            background: true,
+            ..Config::default()
+        }));
+        assert!(
+            freedesktop_actions(&config_with_actions(false), || capabilities(&["actions"]))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn freedesktop_actions_follow_server_capabilities() {
+        let config = config_with_actions(true);
+
+        assert_eq!(
+            freedesktop_actions(&config, || capabilities(&["body", "actions"])),
+            [
+                (CLICK.to_string(), "Open".to_string()),
+                (button_key(0), "Open project".to_string()),
+                (DISMISS_ALL.to_string(), "Dismiss all".to_string()),
+            ]
+        );
+        assert!(freedesktop_actions(&config, || capabilities(&["body"])).is_empty());
+
+        let dismiss_only = Config {
+            background: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            freedesktop_actions(&dismiss_only, || capabilities(&["actions"])),
+            [(DISMISS_ALL.to_string(), "Dismiss all".to_string())]
+        );
+    }
+}
@@ -0,0 +1,18 @@
+pub mod desktop;
+// The HTTP client shared by everything that POSTs to a server, such as `forward`.
+#[cfg(feature = "http")]
+pub mod http;
+pub mod statusfile;
+pub mod terminal_escape;
+pub mod tmux;
+pub mod wsl;
+
+/// Collapses a notification into the single line shown by text-based backends.
+pub fn one_line(title: &str, body: &str) -> String {
+    let body = body.split_whitespace().collect::<Vec<_>>().join(" ");
+    if body.is_empty() {
+        title.to_string()
+    } else {
+        format!("{title}: {body}")
+    }
+}
@@ -0,0 +1,99 @@
+use std::fmt;
+
+use anyhow::Error;
+
+/// Desktop notifications can't be shown in this session (no notification daemon to talk to).
+#[derive(Debug)]
+pub struct DesktopUnavailable(pub String);
+
+impl fmt::Display for DesktopUnavailable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "desktop notifications are unavailable: {}", self.0)
+    }
+}
+
+impl std::error::Error for DesktopUnavailable {}
+
+/// Checks for a D-Bus session bus before trying to show a notification, so headless machines
+/// fail fast instead of waiting on a connection.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn check_available() -> Result<(), DesktopUnavailable> {
+    if std::env::var_os("DBUS_SESSION_BUS_ADDRESS").is_some() {
+        return Ok(());
+    }
+
+    // systemd user sessions expose the bus at a well-known path without setting the variable.
+    if let Some(runtime_dir) = std::env::var_os("XDG_RUNTIME_DIR")
+        && std::path::Path::new(&runtime_dir).join("bus").exists()
+    {
+        return Ok(());
+    }
+
+    Err(DesktopUnavailable("no D-Bus session bus".to_string()))
+}
+
+#[cfg(not(all(unix, not(target_os = "macos"))))]
+pub fn check_available() -> Result<(), DesktopUnavailable> {
+    Ok(())
+}
+
+/// Shows a plain notification, without an agent's icon or app identity. Used by `anot test`.
+#[cfg(feature = "desktop")]
+pub fn show(title: &str, body: &str) -> Result<(), Error> {
+    check_available()?;
+    #[cfg(target_os = "macos")]
+    mac_notification_sys::Notification::new()
+        .title(title)
+        .message(body)
+        .send()?;
+    #[cfg(not(target_os = "macos"))]
+    notify_rust::Notification::new()
+        .summary(title)
+        .body(body)
+        .show()?;
+    Ok(())
+}
+
+#[cfg(not(feature = "desktop"))]
+pub fn show(_title: &str, _body: &str) -> Result<(), Error> {
+    Err(DesktopUnavailable("this build of anot has no desktop support".to_string()).into())
+}
+
+/// Whether a desktop notification failed because there's no notification service to reach,
+/// as opposed to a problem with the notification itself.
+pub fn is_unavailable(error: &Error) -> bool {
+    if error.downcast_ref::<DesktopUnavailable>().is_some() {
+        return true;
+    }
+
+    let message = error.to_string().to_lowercase();
+    [
+        "i/o error",
+        "connection refused",
+        "org.freedesktop.dbus.error.serviceunknown",
+        "org.freedesktop.dbus.error.noserver",
+        "org.freedesktop.dbus.error.spawn",
+    ]
+    .iter()
+    .any(|needle| message.contains(needle))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_connection_errors_as_unavailable() {
+        assert!(is_unavailable(&Error::new(DesktopUnavailable(
+            "no D-Bus session bus".to_string()
+        ))));
+        assert!(is_unavailable(&Error::msg(
+            "I/O error: No such file or directory (os error 2)"
+        )));
+        assert!(is_unavailable(&Error::msg(
+            "org.freedesktop.DBus.Error.ServiceUnknown: The name org.freedesktop.Notifications \
+             was not provided by any .service files"
+        )));
+        assert!(!is_unavailable(&Error::msg("invalid image data")));
+    }
+}
@@ -0,0 +1,240 @@
+//! Shared HTTP delivery for backends that POST notifications to a server, so they all retry
+//! flaky requests the same way: a few attempts with exponential backoff and jitter, each with
+//! its own timeout, and never longer in total than `http.max_total_secs`.
+
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    time::{Duration, Instant},
+};
+
+use anyhow::{Error, anyhow};
+use tracing::{debug, info, warn};
+
+use crate::configuration::Config;
+
+/// The wait before the first retry. Each retry waits twice as long as the one before.
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+
+/// Whether a failed attempt is worth repeating.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Retry {
+    /// Timeouts, connection problems, 5xx and 429: the next attempt may succeed.
+    Retryable,
+    /// Any other 4xx, or a request that can't be sent at all: retrying won't help.
+    Fatal,
+}
+
+/// How a response status is handled: `None` for success, otherwise whether to retry.
+pub fn classify_status(status: u16) -> Option<Retry> {
+    match status {
+        200..=299 => None,
+        429 | 500..=599 => Some(Retry::Retryable),
+        _ => Some(Retry::Fatal),
+    }
+}
+
+fn classify_error(error: &ureq::Error) -> Retry {
+    match error {
+        ureq::Error::Timeout(_)
+        | ureq::Error::Io(_)
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::HostNotFound
+        | ureq::Error::BodyStalled => Retry::Retryable,
+        _ => Retry::Fatal,
+    }
+}
+
+/// The wait before retry number `retry` (from 1): exponential, with jitter between half and
+/// the full delay so clients that failed together don't retry together.
+fn backoff(retry: u32) -> Duration {
+    let full = BACKOFF_BASE.saturating_mul(2u32.saturating_pow(retry.saturating_sub(1)));
+    let jitter = RandomState::new().build_hasher().finish() % 1000;
+    full / 2 + full / 2 * jitter as u32 / 1000
+}
+
+/// POSTs `body` to `url` with `headers`, retrying failures that may be transient. Gives up
+/// after `http.retries` retries or once `http.max_total_secs` have passed.
+pub fn post(
+    config: &Config,
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+) -> Result<(), Error> {
+    let settings = &config.http;
+    let deadline = Instant::now() + Duration::from_secs(settings.max_total_secs);
+    let attempts = settings.retries + 1;
+    let mut attempt = 0;
+
+    loop {
+        attempt += 1;
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let timeout = Duration::from_secs(settings.timeout_secs).min(remaining);
+        debug!(
+            url,
+            attempt,
+            timeout_ms = timeout.as_millis() as u64,
+            "sending HTTP request"
+        );
+
+        let (error, retry) = match send(url, headers, body, timeout) {
+            Ok(()) => {
+                if attempt > 1 {
+                    info!(url, attempt, "HTTP delivery succeeded after retrying");
+                }
+                return Ok(());
+            }
+            Err(failure) => failure,
+        };
+
+        let wait = backoff(attempt);
+        let out_of_time = Instant::now() + wait >= deadline;
+        if retry == Retry::Fatal || attempt >= attempts || out_of_time {
+            warn!(url, attempt, retry = ?retry, error = %error, "HTTP delivery failed");
+            return Err(error.context(format!("POST {url} failed after {attempt} attempt(s)")));
+        }
+
+        warn!(
+            url,
+            attempt,
+            wait_ms = wait.as_millis() as u64,
+            error = %error,
+            "HTTP delivery failed; retrying"
+        );
+        std::thread::sleep(wait);
+    }
+}
+
+fn send(
+    url: &str,
+    headers: &[(&str, &str)],
+    body: &[u8],
+    timeout: Duration,
+) -> Result<(), (Error, Retry)> {
+    let agent = ureq::Agent::config_builder()
+        .http_status_as_error(false)
+        .timeout_global(Some(timeout))
+        .build()
+        .new_agent();
+    let mut request = agent.post(url);
+    for (name, value) in headers {
+        request = request.header(*name, *value);
+    }
+
+    let response = request.send(body).map_err(|e| {
+        let retry = classify_error(&e);
+        (Error::new(e), retry)
+    })?;
+    let status = response.status().as_u16();
+    match classify_status(status) {
+        None => Ok(()),
+        Some(retry) => Err((anyhow!("the server answered {status}"), retry)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::JoinHandle,
+    };
+
+    use super::*;
+
+    /// A server answering each request with the next of `statuses`. Joining it returns how
+    /// many requests it received.
+    fn mock_server(statuses: &'static [u16]) -> (String, JoinHandle<usize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/notify", listener.local_addr().unwrap());
+
+        let handle = std::thread::spawn(move || {
+            for (served, status) in statuses.iter().enumerate() {
+                let Ok((stream, _)) = listener.accept() else {
+                    return served;
+                };
+                let mut reader = BufReader::new(stream);
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if let Some(value) = line.to_lowercase().strip_prefix("content-length:") {
+                        content_length = value.trim().parse().unwrap();
+                    }
+                    if line == "\r\n" || line.is_empty() {
+                        break;
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+
+                let mut stream = reader.into_inner();
+                write!(
+                    stream,
+                    "HTTP/1.1 {status} Status\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                )
+                .unwrap();
+            }
+            statuses.len()
+        });
+        (url, handle)
+    }
+
+    #[test]
+    fn classifies_statuses() {
+        assert_eq!(classify_status(204), None);
+        assert_eq!(classify_status(429), Some(Retry::Retryable));
+        assert_eq!(classify_status(503), Some(Retry::Retryable));
+        assert_eq!(classify_status(400), Some(Retry::Fatal));
+        assert_eq!(classify_status(404), Some(Retry::Fatal));
+    }
+
+    #[test]
+    fn backoff_grows_with_jitter() {
+        for retry in 1..=3 {
+            let full = BACKOFF_BASE * 2u32.pow(retry - 1);
+            let wait = backoff(retry);
+            assert!(wait >= full / 2 && wait <= full, "retry {retry}: {wait:?}");
+        }
+    }
+
+    #[test]
+    fn server_errors_are_retried() {
+        let (url, server) = mock_server(&[500, 200]);
+
+        post(&Config::default(), &url, &[], b"{}").unwrap();
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn client_errors_are_not_retried() {
+        let (url, server) = mock_server(&[400]);
+
+        let error = post(&Config::default(), &url, &[], b"{}").unwrap_err();
+        assert!(format!("{error:#}").contains("after 1 attempt(s): the server answered 400"));
+        assert_eq!(server.join().unwrap(), 1);
+    }
+
+    #[test]
+    fn retries_stop_at_the_limit() {
+        let (url, server) = mock_server(&[503, 503]);
+        let mut config = Config::default();
+        config.http.retries = 1;
+
+        let error = post(&config, &url, &[], b"{}").unwrap_err();
+        assert!(format!("{error:#}").contains("after 2 attempt(s)"));
+        assert_eq!(server.join().unwrap(), 2);
+    }
+
+    #[test]
+    fn total_time_is_bounded() {
+        let mut config = Config::default();
+        config.http.retries = 100;
+        config.http.max_total_secs = 1;
+        // Nothing listens on port 1, so every attempt fails right away.
+        let started = Instant::now();
+
+        assert!(post(&config, "http://127.0.0.1:1/", &[], b"{}").is_err());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,45 @@
+use std::path::PathBuf;
+
+use anyhow::Error;
+
+use crate::{backends::one_line, configuration::Config, storage::write_atomic};
+
+pub fn statusfile_path(config: &Config) -> PathBuf {
+    config
+        .statusfile
+        .path
+        .clone()
+        .unwrap_or_else(|| config.data_dir().join("status"))
+}
+
+/// Replaces the status file with the latest notification line.
+pub fn send(title: &str, body: &str, config: &Config) -> Result<(), Error> {
+    let line = format!("{}\n", one_line(title, body));
+    write_atomic(&statusfile_path(config), line.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_path;
+
+    #[test]
+    fn keeps_only_the_latest_line() {
+        let path = temp_path("statusfile", "latest").join("status");
+        let mut config = Config::default();
+        config.statusfile.path = Some(path.clone());
+
+        send("Claude Code: Stop", "first", &config).unwrap();
+        send(
+            "Codex: agent-turn-complete",
+            "Turn Completed:\n  all done",
+            &config,
+        )
+        .unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "Codex: agent-turn-complete: Turn Completed: all done\n"
+        );
+    }
+}
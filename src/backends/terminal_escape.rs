@@ -0,0 +1,103 @@
+use std::{fs::OpenOptions, io::Write};
+
+use anyhow::Error;
+
+use crate::configuration::{Config, EscapeFlavor};
+
+const ESC: &str = "\x1b";
+const BEL: &str = "\x07";
+
+/// Writes the notification escape sequence to the controlling terminal.
+///
+/// This goes to `/dev/tty` rather than stdout, since Claude Code reads hook output from stdout.
+pub fn send(title: &str, body: &str, config: &Config) -> Result<(), Error> {
+    let sequence = escape_sequence(config.terminal_escape.flavor, title, body);
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    tty.write_all(sequence.as_bytes())?;
+    tty.flush()?;
+
+    Ok(())
+}
+
+/// Rings the terminal bell, the simplest signal that works almost everywhere.
+pub fn bell() -> Result<(), Error> {
+    let mut tty = OpenOptions::new().write(true).open("/dev/tty")?;
+    tty.write_all(BEL.as_bytes())?;
+    tty.flush()?;
+
+    Ok(())
+}
+
+/// Whether the session looks like SSH without a local display, where desktop notifications
+/// can't reach the user.
+pub fn is_remote_session() -> bool {
+    std::env::var_os("SSH_CONNECTION").is_some()
+        && std::env::var_os("DISPLAY").is_none()
+        && std::env::var_os("WAYLAND_DISPLAY").is_none()
+}
+
+fn escape_sequence(flavor: EscapeFlavor, title: &str, body: &str) -> String {
+    let title = sanitize(title);
+    let body = sanitize(body);
+
+    match flavor {
+        EscapeFlavor::Osc9 => format!("{ESC}]9;{title}: {body}{BEL}"),
+        EscapeFlavor::Osc777 => {
+            // `;` separates the title from the body.
+            let title = title.replace(';', ",");
+            format!("{ESC}]777;notify;{title};{body}{BEL}")
+        }
+        EscapeFlavor::Iterm => {
+            format!("{ESC}]9;{title}: {body}{BEL}{ESC}]1337;RequestAttention=once{BEL}")
+        }
+    }
+}
+
+/// Strips control characters, which could end the escape sequence early or inject new ones.
+/// Line breaks and tabs become spaces.
+fn sanitize(text: &str) -> String {
+    text.chars()
+        .filter_map(|c| match c {
+            '\n' | '\r' | '\t' => Some(' '),
+            c if c.is_control() => None,
+            c => Some(c),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_sequence_for_each_flavor() {
+        assert_eq!(
+            escape_sequence(EscapeFlavor::Osc9, "Claude Code: Stop", "Done"),
+            "\x1b]9;Claude Code: Stop: Done\x07"
+        );
+        assert_eq!(
+            escape_sequence(
+                EscapeFlavor::Osc777,
+                "Codex; repo",
+                "Turn Completed: ok; next"
+            ),
+            "\x1b]777;notify;Codex, repo;Turn Completed: ok; next\x07"
+        );
+        assert_eq!(
+            escape_sequence(EscapeFlavor::Iterm, "OpenCode", "Idle"),
+            "\x1b]9;OpenCode: Idle\x07\x1b]1337;RequestAttention=once\x07"
+        );
+    }
+
+    #[test]
+    fn strips_control_characters() {
+        assert_eq!(
+            escape_sequence(
+                EscapeFlavor::Osc9,
+                "Claude\x07 Code",
+                "line one\nline\ttwo\x1b]0;pwned\x07\u{9b}"
+            ),
+            "\x1b]9;Claude Code: line one line two]0;pwned\x07"
+        );
+    }
+}
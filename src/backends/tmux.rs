@@ -0,0 +1,79 @@
+use std::process::Command;
+
+use anyhow::{Error, bail};
+use tracing::debug;
+
+use crate::{backends::one_line, configuration::Config};
+
+/// Whether anot runs inside a tmux session.
+pub fn in_session() -> bool {
+    std::env::var_os("TMUX").is_some()
+}
+
+/// Shows the notification in the current tmux session. Does nothing outside tmux.
+pub fn send(title: &str, body: &str, config: &Config) -> Result<(), Error> {
+    if !in_session() {
+        debug!("not inside tmux; skipping tmux backend");
+        return Ok(());
+    }
+
+    for args in tmux_commands(&one_line(title, body), config.tmux.set_status_option) {
+        let output = Command::new("tmux").args(&args).output()?;
+        if !output.status.success() {
+            bail!(
+                "tmux {} failed: {}",
+                args[0],
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// The tmux invocations (without the `tmux` program name) for one notification line.
+fn tmux_commands(line: &str, set_status_option: bool) -> Vec<Vec<String>> {
+    // display-message expands `#` formats; `##` is a literal `#`.
+    let mut commands = vec![vec!["display-message".to_string(), line.replace('#', "##")]];
+
+    if set_status_option {
+        commands.push(vec![
+            "set-option".to_string(),
+            "-g".to_string(),
+            "@anot_last".to_string(),
+            line.to_string(),
+        ]);
+    }
+
+    commands
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_display_and_status_commands() {
+        let commands = tmux_commands("Claude Code: Stop: Fixed #42", true);
+
+        assert_eq!(
+            commands,
+            [
+                vec!["display-message", "Claude Code: Stop: Fixed ##42"],
+                vec![
+                    "set-option",
+                    "-g",
+                    "@anot_last",
+                    "Claude Code: Stop: Fixed #42"
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn status_option_is_optional() {
+        let commands = tmux_commands("Codex: done", false);
+
+        assert_eq!(commands, [vec!["display-message", "Codex: done"]]);
+    }
+}
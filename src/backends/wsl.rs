@@ -0,0 +1,122 @@
+use std::process::Command;
+
+use anyhow::{Error, bail};
+use base64::{Engine, engine::general_purpose::STANDARD};
+
+/// App id the toast is shown under; Windows only shows toasts for registered app ids, and
+/// PowerShell's is always present.
+const POWERSHELL_APP_ID: &str =
+    r"{1AC14E77-02E7-4E5D-B744-2EB1AE5198B7}\WindowsPowerShell\v1.0\powershell.exe";
+
+/// Whether `anot` runs inside the Windows Subsystem for Linux.
+pub fn is_wsl() -> bool {
+    detect_wsl(
+        std::env::var("WSL_DISTRO_NAME").ok().as_deref(),
+        std::fs::read_to_string("/proc/version").ok().as_deref(),
+    )
+}
+
+fn detect_wsl(distro_name: Option<&str>, proc_version: Option<&str>) -> bool {
+    distro_name.is_some_and(|name| !name.is_empty())
+        || proc_version.is_some_and(|version| version.to_lowercase().contains("microsoft"))
+}
+
+/// Shows a Windows toast on the host through `powershell.exe`.
+pub fn send(title: &str, body: &str) -> Result<(), Error> {
+    let output = Command::new("powershell.exe")
+        .args(powershell_args(title, body))
+        .output()?;
+    if !output.status.success() {
+        bail!(
+            "powershell.exe exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Builds the `powershell.exe` arguments for a toast.
+///
+/// Agent messages can contain any quotes or newlines, and WSL interop re-quotes arguments for
+/// the Windows command line. To stay safe the title and body are embedded as base64 and the
+/// whole script is passed with `-EncodedCommand` (base64 of UTF-16LE), so no user text ever
+/// reaches a parser.
+fn powershell_args(title: &str, body: &str) -> Vec<String> {
+    let script = format!(
+        "$ErrorActionPreference = 'Stop'\n\
+         function Decode($s) {{ [Text.Encoding]::UTF8.GetString([Convert]::FromBase64String($s)) }}\n\
+         [Windows.UI.Notifications.ToastNotificationManager, Windows.UI.Notifications, ContentType = WindowsRuntime] | Out-Null\n\
+         $xml = [Windows.UI.Notifications.ToastNotificationManager]::GetTemplateContent([Windows.UI.Notifications.ToastTemplateType]::ToastText02)\n\
+         $text = $xml.GetElementsByTagName('text')\n\
+         $text.Item(0).AppendChild($xml.CreateTextNode((Decode '{}'))) | Out-Null\n\
+         $text.Item(1).AppendChild($xml.CreateTextNode((Decode '{}'))) | Out-Null\n\
+         $toast = [Windows.UI.Notifications.ToastNotification]::new($xml)\n\
+         [Windows.UI.Notifications.ToastNotificationManager]::CreateToastNotifier('{POWERSHELL_APP_ID}').Show($toast)\n",
+        STANDARD.encode(title),
+        STANDARD.encode(body),
+    );
+    let utf16: Vec<u8> = script
+        .encode_utf16()
+        .flat_map(|unit| unit.to_le_bytes())
+        .collect();
+
+    vec![
+        "-NoProfile".to_string(),
+        "-NonInteractive".to_string(),
+        "-EncodedCommand".to_string(),
+        STANDARD.encode(utf16),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_script(encoded: &str) -> String {
+        let bytes = STANDARD.decode(encoded).unwrap();
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16(&units).unwrap()
+    }
+
+    #[test]
+    fn detects_wsl() {
+        assert!(detect_wsl(Some("Ubuntu"), None));
+        assert!(detect_wsl(
+            None,
+            Some("Linux version 5.15.167.4-microsoft-standard-WSL2 (gcc ...)")
+        ));
+        assert!(!detect_wsl(
+            Some(""),
+            Some("Linux version 6.8.0-45-generic")
+        ));
+        assert!(!detect_wsl(None, None));
+    }
+
+    #[test]
+    fn passes_quotes_and_newlines_only_as_base64() {
+        let title = r#"Claude Code: "Stop""#;
+        let body = "it's done;\nrun `rm -rf $HOME` & exit\r\n\"quoted\"";
+        let args = powershell_args(title, body);
+
+        assert_eq!(
+            args[..3],
+            ["-NoProfile", "-NonInteractive", "-EncodedCommand"]
+        );
+        assert_eq!(args.len(), 4);
+        assert!(
+            args[3]
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "+/=".contains(c))
+        );
+
+        let script = decode_script(&args[3]);
+        assert!(script.contains(&format!("(Decode '{}')", STANDARD.encode(title))));
+        assert!(script.contains(&format!("(Decode '{}')", STANDARD.encode(body))));
+        assert!(!script.contains("rm -rf"));
+    }
+}
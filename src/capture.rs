@@ -0,0 +1,180 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Error;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{Config, DebugOptions},
+    privacy,
+};
+
+pub fn captures_dir(config: &Config) -> PathBuf {
+    config.data_dir().join("captures")
+}
+
+/// Saves the raw payload `agent` sent, redacted as `privacy` asks, when
+/// `debug.capture_payloads` is on. Failures are only logged.
+pub fn capture_payload(config: &Config, agent: &str, input: &str) {
+    if !config.debug.capture_payloads {
+        return;
+    }
+
+    let dir = captures_dir(config);
+    let input = privacy::redact_payload(config, input);
+    match write_capture(&dir, agent, &input, &config.debug) {
+        Ok(path) => debug!(path = %path.display(), "captured raw payload"),
+        Err(e) => warn!(dir = %dir.display(), error = %e, "failed to capture raw payload"),
+    }
+}
+
+fn write_capture(
+    dir: &Path,
+    agent: &str,
+    input: &str,
+    options: &DebugOptions,
+) -> Result<PathBuf, Error> {
+    fs::create_dir_all(dir)?;
+
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let path = dir.join(format!("{agent}-{nanos}.json"));
+    fs::write(&path, redact(input, &options.capture_redact))?;
+
+    evict_oldest(dir, options.capture_max_files)?;
+    Ok(path)
+}
+
+/// Blanks `fields` anywhere in `input` when it is JSON. Field names match with `-` and `_`
+/// treated alike, so `last_assistant_message` also covers Codex's `last-assistant-message`.
+/// Input that doesn't parse is kept verbatim: that's usually what's being debugged.
+fn redact(input: &str, fields: &[String]) -> String {
+    if fields.is_empty() {
+        return input.to_string();
+    }
+
+    match serde_json::from_str::<Value>(input) {
+        Ok(mut value) => {
+            let fields: Vec<String> = fields.iter().map(|f| normalize(f)).collect();
+            blank_fields(&mut value, &fields);
+            value.to_string()
+        }
+        Err(_) => input.to_string(),
+    }
+}
+
+fn normalize(field: &str) -> String {
+    field.replace('-', "_")
+}
+
+fn blank_fields(value: &mut Value, fields: &[String]) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if fields.contains(&normalize(key)) {
+                    *value = Value::String(String::new());
+                } else {
+                    blank_fields(value, fields);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| blank_fields(v, fields)),
+        _ => {}
+    }
+}
+
+/// Deletes the oldest captures so at most `max_files` remain.
+fn evict_oldest(dir: &Path, max_files: usize) -> Result<(), Error> {
+    let mut captures: Vec<(SystemTime, PathBuf)> = fs::read_dir(dir)?
+        .filter_map(Result::ok)
+        .filter(|e| e.path().extension().is_some_and(|ext| ext == "json"))
+        .filter_map(|e| Some((e.metadata().ok()?.modified().ok()?, e.path())))
+        .collect();
+    if captures.len() <= max_files {
+        return Ok(());
+    }
+
+    captures.sort();
+    let excess = captures.len() - max_files;
+    for (_, path) in captures.into_iter().take(excess) {
+        fs::remove_file(&path)?;
+        debug!(path = %path.display(), "evicted old capture");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_path;
+
+    fn options(redact: &[&str], max_files: usize) -> DebugOptions {
+        DebugOptions {
+            capture_payloads: true,
+            capture_redact: redact.iter().map(|f| f.to_string()).collect(),
+            capture_max_files: max_files,
+        }
+    }
+
+    #[test]
+    fn redacts_listed_fields() {
+        let fields = ["prompt".to_string(), "last_assistant_message".to_string()];
+
+        assert_eq!(
+            redact(
+                r#"{"hook_event_name":"UserPromptSubmit","prompt":"my secret"}"#,
+                &fields
+            ),
+            r#"{"hook_event_name":"UserPromptSubmit","prompt":""}"#
+        );
+        assert_eq!(
+            redact(
+                r#"{"type":"agent-turn-complete","last-assistant-message":"done"}"#,
+                &fields
+            ),
+            r#"{"last-assistant-message":"","type":"agent-turn-complete"}"#
+        );
+        assert_eq!(
+            redact(r#"{"properties":{"prompt":"nested"}}"#, &fields),
+            r#"{"properties":{"prompt":""}}"#
+        );
+    }
+
+    #[test]
+    fn captures_unparseable_input_verbatim() {
+        let dir = temp_path("captures", "unparseable");
+        let path = write_capture(&dir, "claude", "{not json", &options(&["prompt"], 10)).unwrap();
+
+        assert!(
+            path.file_name()
+                .unwrap()
+                .to_string_lossy()
+                .starts_with("claude-")
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{not json");
+    }
+
+    #[test]
+    fn evicts_oldest_captures() {
+        let dir = temp_path("captures", "eviction");
+        let options = options(&[], 3);
+
+        let mut paths = Vec::new();
+        for i in 0..5 {
+            paths.push(write_capture(&dir, "codex", &format!("{{\"n\":{i}}}"), &options).unwrap());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+
+        let remaining = fs::read_dir(&dir).unwrap().count();
+        assert_eq!(remaining, 3);
+        assert!(!paths[0].exists());
+        assert!(!paths[1].exists());
+        assert!(paths[4].exists());
+    }
+}
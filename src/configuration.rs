@@ -1,136 +1,2566 @@
+pub mod format;
+pub mod jsonc;
+pub mod local;
+pub mod onboarding;
+pub mod profiles;
+pub mod project;
+pub mod reset;
+pub mod schema;
+pub mod wizard;
+
 use std::{
+    collections::BTreeMap,
     env, fs,
+    io::ErrorKind,
     path::{Path, PathBuf},
 };
 
-use anyhow::Error;
-use serde::{Deserialize, Serialize};
+use anyhow::{Context, Error, bail};
+use schemars::JsonSchema;
+use serde::{Deserialize, Deserializer, Serialize, de};
+use serde_json::Value;
+use strum::IntoEnumIterator;
+use tracing::{info, warn};
+
+use crate::{
+    configuration::format::ConfigFormat,
+    error::AnotError,
+    output::ValidatedFile,
+    processors::{claude::structs::HookEventName, codex::structs::NotificationType},
+    storage::{create_file_exclusively, with_lock, write_atomic},
+    utils::expand_tilde,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Claude {
-    pub pretend: bool,
+    /// Whether notifications use the Claude app's identity on macOS.
+    #[serde(default = "default_claude_pretend")]
+    pub pretend: Pretend,
+
+    /// macOS bundle id notifications are sent as, instead of looking up the Claude app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+
+    /// macOS bundle id used when not pretending. Defaults to Terminal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_bundle_id: Option<String>,
+
+    /// Play a sound with Claude notifications.
+    #[serde(default = "default_sound")]
     pub sound: bool,
+
+    /// A wav or mp3 clip (absolute or `~/` path) played instead of the system sound, with the
+    /// `audio` feature.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_sound_file"
+    )]
+    pub sound_file: Option<PathBuf>,
+
+    /// Volume `sound_file` plays at, from 0.0 to 1.0.
+    #[serde(
+        default = "default_sound_volume",
+        deserialize_with = "deserialize_sound_volume"
+    )]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub sound_volume: f32,
+
+    /// Image shown instead of the built-in Claude icon (absolute or `~/` path).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<PathBuf>,
+
+    /// On Linux, an icon of the icon theme (e.g. `utilities-terminal`) shown instead of the
+    /// built-in Claude icon. A configured `icon` file still wins.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_icon_name"
+    )]
+    pub icon_name: Option<String>,
+
+    /// Notify again for a Stop/SubagentStop that arrives with `stop_hook_active` set.
+    #[serde(default)]
+    pub notify_on_repeated_stop: bool,
+
+    /// Which PostToolUse events produce a notification.
+    #[serde(default)]
+    pub post_tool_use: PostToolUseMode,
+
+    /// Which SessionStart events produce a notification, by how the session started.
+    #[serde(default)]
+    pub session_start: SessionStartMode,
+
+    /// Per-category handling of the Notification event (permission requests vs idle prompts).
+    #[serde(default)]
+    pub notification_categories: NotificationCategories,
+
+    /// Use the last assistant message from the transcript as the Stop notification body.
+    #[serde(default)]
+    pub stop_summary: bool,
+
+    /// Maximum number of characters of the Stop summary.
+    #[serde(default = "default_stop_summary_length")]
+    pub stop_summary_length: usize,
+
+    /// Show a generic notification for hook events this version doesn't recognize.
+    #[serde(default)]
+    pub notify_unknown_events: bool,
+
+    /// Sum the session up in the SessionEnd notification: its tool calls, permission prompts
+    /// and how long it ran.
+    #[serde(default)]
+    pub session_digest: bool,
+
+    /// Say in a Notification how many other Claude sessions are waiting on the user too.
+    #[serde(default)]
+    pub count_waiting: bool,
+
+    /// Coalesce bursts of tool events into one summary notification.
+    #[serde(default)]
+    pub batching: Batching,
+
+    /// Show only every Nth notification of an event, keyed by hook event name (e.g.
+    /// `{"PostToolUse": 5}`). Counted per session across invocations; `1` shows them all.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_claude_sample"
+    )]
+    #[schemars(schema_with = "schema::claude_sample")]
+    pub sample: BTreeMap<String, u64>,
+
+    /// Only these hook events (e.g. `Stop`, `Notification`) notify. Empty (the default) notifies
+    /// for every event the hooks are installed for.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_claude_events"
+    )]
+    #[schemars(schema_with = "schema::claude_events")]
+    pub events: Vec<String>,
+
+    /// Remind again when a Notification (idle or permission prompt) goes unanswered for this
+    /// many seconds. `0` disables reminders.
+    #[serde(default)]
+    pub reminder_after_secs: u64,
+
+    /// How many reminders are sent for one prompt. Each waits twice as long as the previous.
+    #[serde(default = "default_reminder_max_count")]
+    pub reminder_max_count: u32,
+
+    /// `anot claude` gives up when stdin stays empty this long, so a hook invoked without a
+    /// payload doesn't hang until Claude Code's hook timeout. `0` waits indefinitely.
+    #[serde(default = "default_stdin_timeout_secs")]
+    pub stdin_timeout_secs: u64,
+
+    /// Pair each PreToolUse with its PostToolUse: PreToolUse stays silent and PostToolUse
+    /// says how long the tool ran, for slow calls only.
+    #[serde(default)]
+    pub tool_pairing: bool,
+
+    /// With `tool_pairing`, tool calls that finish faster than this don't notify.
+    #[serde(default = "default_min_tool_duration_secs")]
+    pub min_tool_duration_secs: u64,
+
+    /// On Linux, a PostToolUse arriving within this many seconds of its PreToolUse replaces the
+    /// PreToolUse notification, or closes it when the PostToolUse doesn't notify. `0` disables it.
+    #[serde(default = "default_collapse_tool_calls_secs")]
+    pub collapse_tool_calls_secs: u64,
+
+    /// Per-event `pretend`, `sound` and `icon`, keyed by hook event name (e.g. `Stop`).
+    #[serde(default, deserialize_with = "deserialize_claude_overrides")]
+    #[schemars(schema_with = "schema::claude_event_map::<EventOverride>")]
+    pub overrides: BTreeMap<String, EventOverride>,
+
+    /// Notification titles replacing the built-in "Claude Code · Finished" and the like, keyed
+    /// by hook event name. `{event}`, `{summary}`, `{tool}`, `{project}`, `{cwd}` and
+    /// `{session_id}` are filled in.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_claude_titles"
+    )]
+    #[schemars(schema_with = "schema::claude_event_map::<String>")]
+    pub titles: BTreeMap<String, String>,
+
+    /// Bodies longer than this many characters are shortened. `0` disables truncation.
+    #[serde(default = "default_max_body_length")]
+    pub max_body_length: usize,
+
+    /// Remove markdown syntax (backticks, `**`, heading hashes, link targets) from bodies.
+    #[serde(default = "default_strip_markdown")]
+    pub strip_markdown: bool,
+
+    /// Images shown instead of the built-in icon for an event, keyed by hook event name.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_claude_event_icons"
+    )]
+    #[schemars(schema_with = "schema::claude_event_map::<PathBuf>")]
+    pub event_icons: BTreeMap<String, PathBuf>,
+
+    /// Commands run when a notification is clicked or one of its buttons is pressed (macOS,
+    /// detached delivery only).
+    #[serde(default)]
+    pub actions: ClaudeActions,
+
+    /// Fallback chain for Claude notifications, replacing the top-level `fallbacks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallbacks: Option<Vec<Backend>>,
+
+    /// Relay each hook payload to `anot serve` on another machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward: Option<Forward>,
+
+    /// Program and arguments run after each Claude notification is sent, besides the top-level
+    /// `on_sent`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_sent: Vec<String>,
+
+    /// Rules answering matching PreToolUse calls with an `ask` or `deny` permission decision
+    /// instead of passing them through. Empty (the default) leaves every call to Claude Code.
+    #[serde(
+        default,
+        skip_serializing_if = "Vec::is_empty",
+        deserialize_with = "deserialize_guard_rules"
+    )]
+    pub guard: Vec<GuardRule>,
+
+    /// When the hook output tells Claude, through `additionalContext`, how notifying the user
+    /// went.
+    #[serde(default)]
+    pub report_delivery: ReportDelivery,
+
+    /// How much of the hook output `anot claude` writes to stdout. `guard` rules need `full`.
+    #[serde(default)]
+    pub emit_output: EmitOutput,
+}
+
+impl Claude {
+    fn check(&self) -> Result<(), Error> {
+        let mode = match self.emit_output {
+            EmitOutput::Full => return Ok(()),
+            EmitOutput::Minimal => "minimal",
+            EmitOutput::None => "none",
+        };
+        if !self.guard.is_empty() {
+            bail!(
+                "claude.emit_output `{mode}` would drop the decisions of claude.guard; \
+                 use `full` or remove the guard rules"
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether `event` notifies with `events`.
+    pub fn notifies_for(&self, event: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|e| e == event)
+    }
+
+    /// Settings for `event`: its override where set, otherwise the section's. An `event_icons`
+    /// entry takes precedence over `icon`.
+    pub fn settings_for(&self, event: &str) -> NotificationSettings {
+        NotificationSettings::resolve(
+            self.pretend,
+            self.sound,
+            self.sound_file.as_deref(),
+            self.event_icons
+                .get(event)
+                .map(PathBuf::as_path)
+                .or(self.icon.as_deref()),
+            self.overrides.get(event),
+        )
+    }
+}
+
+fn default_sound() -> bool {
+    true
+}
+
+fn default_sound_volume() -> f32 {
+    1.0
+}
+
+fn default_max_body_length() -> usize {
+    240
+}
+
+fn default_strip_markdown() -> bool {
+    true
+}
+
+fn default_stop_summary_length() -> usize {
+    200
+}
+
+fn default_reminder_max_count() -> u32 {
+    3
+}
+
+fn default_stdin_timeout_secs() -> u64 {
+    5
+}
+
+fn default_min_tool_duration_secs() -> u64 {
+    10
+}
+
+fn default_collapse_tool_calls_secs() -> u64 {
+    5
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionStartMode {
+    /// Notify for new, resumed and cleared sessions
+    All,
+    /// Notify only for new sessions; a payload without a source counts as one
+    #[default]
+    StartupOnly,
+    /// Never notify for SessionStart
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportDelivery {
+    /// Never; the hook output stays as it was
+    None,
+    /// Only when a backend failed to deliver the notification
+    #[default]
+    Failures,
+    /// After every notification, naming the backends that delivered it
+    Always,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmitOutput {
+    /// The whole hook output, with failures in `systemMessage`
+    #[default]
+    Full,
+    /// Only `{"continue":true,"suppressOutput":true}`, whatever happened; failures are only
+    /// logged
+    Minimal,
+    /// Nothing; Claude Code carries on as it does after a hook with empty output
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PostToolUseMode {
+    /// Notify for every PostToolUse event
+    #[default]
+    All,
+    /// Notify only when the tool response looks like a failure
+    Failures,
+    /// Never notify for PostToolUse
+    Off,
+}
+
+fn default_claude_pretend() -> Pretend {
+    Pretend::Auto
 }
 
 impl Default for Claude {
     fn default() -> Self {
         Claude {
-            pretend: true,
+            pretend: default_claude_pretend(),
+            bundle_id: None,
+            fallback_bundle_id: None,
             sound: true,
+            sound_file: None,
+            sound_volume: default_sound_volume(),
+            icon: None,
+            icon_name: None,
+            notify_on_repeated_stop: false,
+            post_tool_use: PostToolUseMode::default(),
+            session_start: SessionStartMode::default(),
+            notification_categories: NotificationCategories::default(),
+            stop_summary: false,
+            stop_summary_length: default_stop_summary_length(),
+            notify_unknown_events: false,
+            session_digest: false,
+            count_waiting: false,
+            batching: Batching::default(),
+            sample: BTreeMap::new(),
+            events: Vec::new(),
+            reminder_after_secs: 0,
+            reminder_max_count: default_reminder_max_count(),
+            stdin_timeout_secs: default_stdin_timeout_secs(),
+            tool_pairing: false,
+            min_tool_duration_secs: default_min_tool_duration_secs(),
+            collapse_tool_calls_secs: default_collapse_tool_calls_secs(),
+            overrides: BTreeMap::new(),
+            titles: BTreeMap::new(),
+            max_body_length: default_max_body_length(),
+            strip_markdown: default_strip_markdown(),
+            event_icons: BTreeMap::new(),
+            actions: ClaudeActions::default(),
+            fallbacks: None,
+            forward: None,
+            on_sent: Vec::new(),
+            guard: Vec::new(),
+            report_delivery: ReportDelivery::default(),
+            emit_output: EmitOutput::default(),
+        }
+    }
+}
+
+/// A `claude.guard` rule. It matches a tool call when every pattern it sets matches.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct GuardRule {
+    /// Regex matched against the Bash `command`. Without `tool`, the rule only applies to Bash.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Tool name glob, e.g. `mcp__github__*`. `*` matches any run of characters, `?` one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+    #[serde(default)]
+    pub decision: GuardDecision,
+    /// Shown with the decision; a sentence naming the pattern when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GuardDecision {
+    /// Claude Code asks the user before running the tool
+    #[default]
+    Ask,
+    /// The tool call is refused and Claude is told why
+    Deny,
+}
+
+/// Commands attached to Claude notifications. `{transcript_path}`, `{session_id}` and `{cwd}`
+/// in a command are replaced with the event's values.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct ClaudeActions {
+    /// Run when the notification itself is clicked, e.g. `open -a iTerm`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_click: Option<ActionCommand>,
+    /// Extra buttons on the notification.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buttons: Vec<ActionButton>,
+    /// Add a "Dismiss all" button that closes every notification anot has on screen (Linux).
+    #[serde(default = "default_dismiss_all")]
+    pub dismiss_all: bool,
+    /// How long the background process waits for a button press on Linux.
+    #[serde(default = "default_action_wait_secs")]
+    pub wait_secs: u64,
+}
+
+impl ClaudeActions {
+    /// Whether any command is configured.
+    pub fn is_empty(&self) -> bool {
+        self.on_click.is_none() && self.buttons.is_empty()
+    }
+}
+
+impl Default for ClaudeActions {
+    fn default() -> Self {
+        ClaudeActions {
+            on_click: None,
+            buttons: Vec::new(),
+            dismiss_all: default_dismiss_all(),
+            wait_secs: default_action_wait_secs(),
+        }
+    }
+}
+
+fn default_dismiss_all() -> bool {
+    true
+}
+
+fn default_action_wait_secs() -> u64 {
+    60
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct ActionButton {
+    pub label: String,
+    pub command: ActionCommand,
+}
+
+/// A command run by a notification action.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ActionCommand {
+    /// Program and arguments, run without a shell, e.g. `["code", "{cwd}"]`
+    Argv(Vec<String>),
+    /// A command line run with `sh -c`, e.g. `"$EDITOR" {transcript_path}`
+    Shell(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Batching {
+    /// How long tool events are collected before they're summarized. `0` disables batching.
+    #[serde(default)]
+    pub window_secs: u64,
+
+    /// Batches smaller than this are sent as the original individual notifications.
+    #[serde(default = "default_min_batch_size")]
+    pub min_batch_size: usize,
+}
+
+fn default_min_batch_size() -> usize {
+    3
+}
+
+impl Default for Batching {
+    fn default() -> Self {
+        Batching {
+            window_secs: 0,
+            min_batch_size: default_min_batch_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Urgency {
+    Low,
+    #[default]
+    Normal,
+    Critical,
+}
+
+/// Category of a Claude `Notification` event message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    /// "Claude needs your permission to use Bash"
+    Permission,
+    /// "Claude is waiting for your input"
+    Idle,
+    Other,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct NotificationCategories {
+    #[serde(default)]
+    pub permission: CategorySettings,
+    #[serde(default)]
+    pub idle: CategorySettings,
+    #[serde(default)]
+    pub other: CategorySettings,
+}
+
+/// User overrides for a notification category. Unset fields fall back to the built-in
+/// defaults of the category (see [`NotificationCategories::resolve`]).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct CategorySettings {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    /// Overrides `claude.sound` for this category
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub urgency: Option<Urgency>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_suffix: Option<String>,
+    /// Regex patterns matched against the message; replaces the built-in patterns when set.
+    /// Ignored for the `other` category.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patterns: Option<Vec<String>>,
+}
+
+/// Category settings with the built-in defaults applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResolvedCategory {
+    pub enabled: bool,
+    pub sound: Option<bool>,
+    pub urgency: Urgency,
+    pub title_suffix: Option<String>,
+    pub patterns: Vec<String>,
+}
+
+impl NotificationCategories {
+    pub fn resolve(&self, category: NotificationCategory) -> ResolvedCategory {
+        let (settings, defaults) = match category {
+            NotificationCategory::Permission => (
+                &self.permission,
+                ResolvedCategory {
+                    enabled: true,
+                    sound: Some(true),
+                    urgency: Urgency::Critical,
+                    title_suffix: Some("Permission needed".to_string()),
+                    patterns: vec![
+                        "(?i)needs your permission".to_string(),
+                        "(?i)permission to use".to_string(),
+                    ],
+                },
+            ),
+            NotificationCategory::Idle => (
+                &self.idle,
+                ResolvedCategory {
+                    enabled: true,
+                    sound: Some(false),
+                    urgency: Urgency::Low,
+                    title_suffix: Some("Waiting for input".to_string()),
+                    patterns: vec!["(?i)waiting for your input".to_string()],
+                },
+            ),
+            NotificationCategory::Other => (
+                &self.other,
+                ResolvedCategory {
+                    enabled: true,
+                    sound: None,
+                    urgency: Urgency::Normal,
+                    title_suffix: None,
+                    patterns: Vec::new(),
+                },
+            ),
+        };
+
+        ResolvedCategory {
+            enabled: settings.enabled.unwrap_or(defaults.enabled),
+            sound: settings.sound.or(defaults.sound),
+            urgency: settings.urgency.unwrap_or(defaults.urgency),
+            title_suffix: settings.title_suffix.clone().or(defaults.title_suffix),
+            patterns: settings.patterns.clone().unwrap_or(defaults.patterns),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct Codex {
-    pub pretend: bool,
+    /// Whether notifications use the ChatGPT app's identity on macOS.
+    pub pretend: Pretend,
     pub sound: bool,
+
+    /// A wav or mp3 clip played instead of the system sound, with the `audio` feature.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_sound_file"
+    )]
+    pub sound_file: Option<PathBuf>,
+
+    /// Volume `sound_file` plays at, from 0.0 to 1.0.
+    #[serde(
+        default = "default_sound_volume",
+        deserialize_with = "deserialize_sound_volume"
+    )]
+    #[schemars(range(min = 0.0, max = 1.0))]
+    pub sound_volume: f32,
+
+    /// macOS bundle id notifications are sent as, instead of looking up the ChatGPT app.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle_id: Option<String>,
+
+    /// macOS bundle id used when not pretending. Defaults to Terminal.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_bundle_id: Option<String>,
+
+    /// Image shown instead of the built-in Codex icon (absolute or `~/` path).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<PathBuf>,
+
+    /// On Linux, an icon of the icon theme shown instead of the built-in Codex icon. A
+    /// configured `icon` file still wins.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_icon_name"
+    )]
+    pub icon_name: Option<String>,
+
+    /// Per-event `pretend`, `sound` and `icon`, keyed by notification type (e.g.
+    /// `AgentTurnComplete`).
+    #[serde(default, deserialize_with = "deserialize_codex_overrides")]
+    #[schemars(schema_with = "schema::codex_event_map::<EventOverride>")]
+    pub overrides: BTreeMap<String, EventOverride>,
+
+    /// Notification titles replacing the built-in "Codex · Turn complete", keyed by
+    /// notification type. `{type}`, `{summary}`, `{project}`, `{cwd}`, `{turn_id}` and
+    /// `{prompt}` are filled in.
+    #[serde(
+        default,
+        skip_serializing_if = "BTreeMap::is_empty",
+        deserialize_with = "deserialize_codex_titles"
+    )]
+    #[schemars(schema_with = "schema::codex_event_map::<String>")]
+    pub titles: BTreeMap<String, String>,
+
+    /// Bodies longer than this many characters are shortened. `0` disables truncation.
+    #[serde(default = "default_max_body_length")]
+    pub max_body_length: usize,
+
+    /// Remove markdown syntax (backticks, `**`, heading hashes, link targets) from bodies.
+    #[serde(default = "default_strip_markdown")]
+    pub strip_markdown: bool,
+
+    /// Add the start of the user's prompt to the title, so it's clear which request finished.
+    #[serde(default)]
+    pub title_includes_prompt: bool,
+
+    /// How the user's prompts (`input_messages`) appear in turn notifications. Unset, they're
+    /// only the body when Codex sent no message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_messages: Option<InputMessages>,
+
+    /// End turn notifications with the local time they completed at, e.g. "completed at 14:32".
+    #[serde(default)]
+    pub show_timestamps: bool,
+
+    /// A second turn-complete notification for the same `turn-id` within this many seconds,
+    /// as when Codex retries a failed turn, is dropped. `0` disables it.
+    #[serde(default = "default_turn_dedupe_window_secs")]
+    pub turn_dedupe_window_secs: u64,
+
+    /// Fallback chain for Codex notifications, replacing the top-level `fallbacks`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallbacks: Option<Vec<Backend>>,
+
+    /// Relay each notification payload to `anot serve` on another machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forward: Option<Forward>,
+
+    /// Program and arguments run after each Codex notification is sent, besides the top-level
+    /// `on_sent`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_sent: Vec<String>,
+}
+
+impl Codex {
+    /// Settings for `event`: its override where set, otherwise the section's.
+    pub fn settings_for(&self, event: &str) -> NotificationSettings {
+        NotificationSettings::resolve(
+            self.pretend,
+            self.sound,
+            self.sound_file.as_deref(),
+            self.icon.as_deref(),
+            self.overrides.get(event),
+        )
+    }
 }
 
 impl Default for Codex {
     fn default() -> Self {
         Codex {
-            pretend: false,
+            pretend: Pretend::Never,
             sound: true,
+            sound_file: None,
+            sound_volume: default_sound_volume(),
+            bundle_id: None,
+            fallback_bundle_id: None,
+            icon: None,
+            icon_name: None,
+            overrides: BTreeMap::new(),
+            titles: BTreeMap::new(),
+            max_body_length: default_max_body_length(),
+            strip_markdown: default_strip_markdown(),
+            title_includes_prompt: false,
+            input_messages: None,
+            show_timestamps: false,
+            turn_dedupe_window_secs: default_turn_dedupe_window_secs(),
+            fallbacks: None,
+            forward: None,
+            on_sent: Vec::new(),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Opencode {
-    pub pretend: bool,
-    pub sound: bool,
+/// `codex.input_messages`: which of the user's prompts are shown, and where.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(default)]
+pub struct InputMessages {
+    /// The first this many non-empty prompts are shown. `0` shows them all.
+    pub max: usize,
+    /// Put between the prompts.
+    pub separator: String,
+    pub position: InputMessagesPosition,
 }
 
-impl Default for Opencode {
+impl Default for InputMessages {
     fn default() -> Self {
-        Opencode {
-            pretend: false,
-            sound: true,
+        InputMessages {
+            max: 2,
+            separator: " · ".to_string(),
+            position: InputMessagesPosition::Body,
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct Config {
-    pub version: u32,
-    pub claude: Claude,
-    pub codex: Codex,
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InputMessagesPosition {
+    /// Before the agent's message, as in "Re: fix the login bug — Done, …"
+    #[default]
+    Body,
+    /// In place of "Turn complete" in the title, as in "Codex · fix the login bug"
+    Title,
+    /// Nowhere, not even when Codex sent no message
+    Ignore,
+}
 
-    #[serde(default)]
-    pub opencode: Opencode,
+/// Whether a macOS notification pretends to come from the agent's desktop app.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Pretend {
+    /// Use the app when it is installed (checked once a day), Terminal otherwise
+    Auto,
+    /// Always try the app; a missing app is logged and Terminal is used
+    Always,
+    /// Always use Terminal
+    Never,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Config {
-            version: 1,
-            claude: Claude::default(),
-            codex: Codex::default(),
-            opencode: Opencode::default(),
+/// Also accepts the `true`/`false` of older configs, as `always` and `never`.
+impl<'de> Deserialize<'de> for Pretend {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Bool(bool),
+            Mode(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Bool(true) => Ok(Pretend::Always),
+            Raw::Bool(false) => Ok(Pretend::Never),
+            Raw::Mode(mode) => match mode.as_str() {
+                "auto" => Ok(Pretend::Auto),
+                "always" => Ok(Pretend::Always),
+                "never" => Ok(Pretend::Never),
+                other => Err(de::Error::unknown_variant(
+                    other,
+                    &["auto", "always", "never"],
+                )),
+            },
         }
     }
 }
 
-pub fn get_config_path() -> Option<PathBuf> {
-    let system_config_path = dirs::config_dir();
+/// Settings for a single event that take precedence over its section.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct EventOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pretend: Option<Pretend>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sound: Option<bool>,
+    /// Clip played instead of the system sound
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_sound_file"
+    )]
+    pub sound_file: Option<PathBuf>,
+    /// Image shown instead of the built-in agent icon
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<PathBuf>,
+}
 
-    if let Some(mut path) = system_config_path {
-        path.push("agent_notifications/a-notifications.json");
-        return Some(path);
+/// Settings used to show one notification, after applying its event override.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NotificationSettings {
+    pub pretend: Pretend,
+    pub sound: bool,
+    pub sound_file: Option<PathBuf>,
+    pub icon: Option<PathBuf>,
+}
+
+impl NotificationSettings {
+    fn resolve(
+        pretend: Pretend,
+        sound: bool,
+        sound_file: Option<&Path>,
+        icon: Option<&Path>,
+        event: Option<&EventOverride>,
+    ) -> Self {
+        NotificationSettings {
+            pretend: event.and_then(|e| e.pretend).unwrap_or(pretend),
+            sound: event.and_then(|e| e.sound).unwrap_or(sound),
+            sound_file: event
+                .and_then(|e| e.sound_file.clone())
+                .or_else(|| sound_file.map(Path::to_path_buf)),
+            icon: event
+                .and_then(|e| e.icon.clone())
+                .or_else(|| icon.map(Path::to_path_buf)),
+        }
     }
+}
 
-    let mut current_dir = env::current_dir().ok()?;
+fn claude_event_names() -> Vec<String> {
+    HookEventName::iter()
+        .map(|e| e.as_str().to_string())
+        .collect()
+}
 
-    current_dir.push("a-notifications.json");
+fn deserialize_claude_overrides<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, EventOverride>, D::Error> {
+    deserialize_event_map(deserializer, "overrides", &claude_event_names())
+}
 
-    Some(current_dir)
+fn deserialize_claude_event_icons<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, PathBuf>, D::Error> {
+    deserialize_event_map(deserializer, "event_icons", &claude_event_names())
 }
 
-pub fn get_logs_dir() -> PathBuf {
-    if let Some(config_file) = get_config_path()
-        && let Some(parent) = config_file.parent()
+fn deserialize_claude_titles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    deserialize_event_map(deserializer, "titles", &claude_event_names())
+}
+
+fn deserialize_claude_sample<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, u64>, D::Error> {
+    let sample: BTreeMap<String, u64> =
+        deserialize_event_map(deserializer, "sample", &claude_event_names())?;
+
+    if let Some(event) = sample
+        .iter()
+        .find_map(|(event, rate)| (*rate == 0).then_some(event))
     {
-        return parent.join("logs");
+        return Err(de::Error::custom(format!(
+            "invalid sample rate 0 for `{event}`, expected 1 or more (1 shows every notification)"
+        )));
     }
 
-    let base = dirs::config_dir()
-        .unwrap_or_else(std::env::temp_dir)
-        .join("agent_notifications");
-    base.join("logs")
+    Ok(sample)
 }
 
-pub fn create_default_config(path: &Path) -> Result<(), Error> {
-    let default_config = Config::default();
-    let config_data = serde_json::to_string(&default_config)?;
+fn deserialize_claude_events<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<String>, D::Error> {
+    let events = Vec::<String>::deserialize(deserializer)?;
+    let known = claude_event_names();
+    if let Some(unknown) = events.iter().find(|event| !known.contains(event)) {
+        return Err(de::Error::custom(format!(
+            "unknown event `{unknown}` in `events`, expected one of: {}",
+            known.join(", ")
+        )));
+    }
+    Ok(events)
+}
 
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+/// Rejects rules that match nothing and `command` patterns that aren't valid regexes, so a
+/// typo doesn't silently let a command through.
+fn deserialize_guard_rules<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Vec<GuardRule>, D::Error> {
+    let rules = Vec::<GuardRule>::deserialize(deserializer)?;
+    for (index, rule) in rules.iter().enumerate() {
+        if rule.command.is_none() && rule.tool.is_none() {
+            return Err(de::Error::custom(format!(
+                "guard rule {index} sets neither `command` nor `tool`"
+            )));
+        }
+        if let Some(pattern) = &rule.command
+            && let Err(e) = regex::Regex::new(pattern)
+        {
+            return Err(de::Error::custom(format!(
+                "invalid `command` pattern in guard rule {index}: {e}"
+            )));
+        }
+    }
+    Ok(rules)
+}
+
+/// Accepts only names the icon theme can look up, pointing paths to `icon` instead.
+fn deserialize_icon_name<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    let name = String::deserialize(deserializer)?;
+    if name.is_empty() || name.starts_with('~') || name.contains(['/', '\\']) {
+        return Err(de::Error::custom(format!(
+            "`icon_name` is the name of a theme icon such as \"utilities-terminal\", not \
+             `{name}`; set `icon` to use an image file"
+        )));
     }
+    Ok(Some(name))
+}
 
-    std::fs::write(path, config_data)?;
+/// Clip formats `sound_file` can be, by extension.
+pub const SOUND_FILE_EXTENSIONS: &[&str] = &["wav", "mp3"];
 
-    Ok(())
+/// Accepts a wav or mp3 file that exists, so a typo shows up when the config is loaded rather
+/// than as a silent notification.
+fn deserialize_sound_file<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<PathBuf>, D::Error> {
+    let path = PathBuf::deserialize(deserializer)?;
+    let supported = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| {
+            SOUND_FILE_EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str())
+        });
+    if !supported {
+        return Err(de::Error::custom(format!(
+            "unsupported `sound_file` {}, expected a .wav or .mp3 file",
+            path.display()
+        )));
+    }
+    if !expand_tilde(&path).is_file() {
+        return Err(de::Error::custom(format!(
+            "`sound_file` {} doesn't exist",
+            path.display()
+        )));
+    }
+    Ok(Some(path))
 }
 
-pub fn initialize_configuration(config_path: &Path) -> Result<Config, Error> {
-    if !config_path.exists() {
-        create_default_config(config_path)?;
+fn deserialize_sound_volume<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f32, D::Error> {
+    let volume = f32::deserialize(deserializer)?;
+    if !(0.0..=1.0).contains(&volume) {
+        return Err(de::Error::custom(format!(
+            "invalid `sound_volume` {volume}, expected 0.0 to 1.0"
+        )));
     }
+    Ok(volume)
+}
 
-    let contents = fs::read_to_string(config_path)?;
+fn deserialize_icon_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let size = u32::deserialize(deserializer)?;
+    if size == 0 {
+        return Err(de::Error::custom(
+            "invalid `icon_size` 0, expected a size in pixels such as 48 or 128",
+        ));
+    }
+    Ok(size)
+}
 
-    let config: Config = serde_json::from_str(&contents)?;
+fn codex_event_names() -> Vec<String> {
+    vec![NotificationType::AgentTurnComplete.as_str().to_string()]
+}
 
-    Ok(config)
+fn deserialize_codex_overrides<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, EventOverride>, D::Error> {
+    deserialize_event_map(deserializer, "overrides", &codex_event_names())
 }
 
-pub fn reset_configuration(config_path: &Path) -> Result<(), Error> {
-    if config_path.exists() {
-        fs::remove_file(config_path)?;
+fn deserialize_codex_titles<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<BTreeMap<String, String>, D::Error> {
+    deserialize_event_map(deserializer, "titles", &codex_event_names())
+}
+
+/// Rejects keys that aren't event names, so typos don't silently do nothing.
+fn deserialize_event_map<'de, D: Deserializer<'de>, T: Deserialize<'de>>(
+    deserializer: D,
+    field: &str,
+    known: &[String],
+) -> Result<BTreeMap<String, T>, D::Error> {
+    let map = BTreeMap::<String, T>::deserialize(deserializer)?;
+
+    if let Some(unknown) = map.keys().find(|key| !known.contains(key)) {
+        return Err(de::Error::custom(format!(
+            "unknown event `{unknown}` in {field}, expected one of: {}",
+            known.join(", ")
+        )));
     }
 
-    create_default_config(config_path)?;
+    Ok(map)
+}
 
-    Ok(())
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Opencode {
+    pub pretend: bool,
+    pub sound: bool,
+}
+
+impl Default for Opencode {
+    fn default() -> Self {
+        Opencode {
+            pretend: false,
+            sound: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct History {
+    /// Record every notification to `history.jsonl`
+    pub enabled: bool,
+    /// Size after which `history.jsonl` is rotated to `history.jsonl.1`
+    pub max_bytes: u64,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        History {
+            enabled: true,
+            max_bytes: 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct DebugOptions {
+    /// Save every raw payload to `captures/` before it is parsed
+    pub capture_payloads: bool,
+    /// Fields blanked in captured payloads that parse as JSON (e.g. `prompt`)
+    pub capture_redact: Vec<String>,
+    /// Oldest captures are deleted beyond this many files
+    pub capture_max_files: usize,
+}
+
+impl Default for DebugOptions {
+    fn default() -> Self {
+        DebugOptions {
+            capture_payloads: false,
+            capture_redact: Vec::new(),
+            capture_max_files: 100,
+        }
+    }
+}
+
+/// Keeps secrets in prompts and agent messages out of notifications, logs, the history and
+/// the payloads kept on disk or relayed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Privacy {
+    /// Show "User submitted a prompt (N chars)" instead of the prompt
+    pub redact_prompts: bool,
+    /// Leave the agent's last message out of Codex notifications and Claude stop summaries
+    pub redact_messages: bool,
+    /// Regex patterns whose matches are replaced with `•••` in every notification body
+    pub redact_patterns: Vec<String>,
+}
+
+/// Where an agent's payloads are relayed to, e.g. from a headless box to `anot serve` on a
+/// laptop.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+pub struct Forward {
+    /// The `anot serve` endpoint, e.g. `https://laptop:4990/claude`.
+    pub url: String,
+    /// Sent as `Authorization: Bearer <token>`. Needs an `https` URL unless `url` is on this
+    /// machine.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token: Option<String>,
+    /// Whether this machine still notifies too.
+    #[serde(default)]
+    pub mode: ForwardMode,
+    /// How much of what the user and the agent wrote is relayed.
+    #[serde(default)]
+    pub content: ContentLevel,
+    /// How long one attempt may take. A payload that couldn't be relayed is retried with the
+    /// next event.
+    #[serde(default = "default_forward_timeout_secs")]
+    pub timeout_secs: u64,
+}
+
+impl Forward {
+    /// Fails when `token` would cross the network in the clear: over `http` to anything but
+    /// this machine.
+    fn check(&self) -> Result<(), Error> {
+        let Some(rest) = self.url.strip_prefix("http://") else {
+            return Ok(());
+        };
+        let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+        let authority = authority
+            .rsplit_once('@')
+            .map_or(authority, |(_, host)| host);
+        let host = match authority.strip_prefix('[') {
+            Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+            None => authority.split(':').next().unwrap_or_default(),
+        };
+        let loopback = host.eq_ignore_ascii_case("localhost")
+            || host
+                .parse::<std::net::IpAddr>()
+                .is_ok_and(|ip| ip.is_loopback());
+        if self.token.is_some() && !loopback {
+            bail!(
+                "forward.url {} would send forward.token unencrypted; use an https URL",
+                self.url
+            );
+        }
+        Ok(())
+    }
+}
+
+fn default_forward_timeout_secs() -> u64 {
+    3
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ForwardMode {
+    /// Only relay the payload; nothing is shown on this machine
+    #[default]
+    Replace,
+    /// Relay the payload and notify on this machine as well
+    Also,
+}
+
+/// How much of a payload's content leaves this machine. `privacy` applies at every level.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContentLevel {
+    /// The whole payload
+    Full,
+    /// Prompts masked down to their length and agent messages left out, as with
+    /// `privacy.redact_prompts` and `privacy.redact_messages`
+    Redacted,
+    /// Only what titles need: prompts, agent messages and tool input and output are left out
+    #[default]
+    TitleOnly,
+}
+
+/// Prometheus metrics for the node_exporter textfile collector.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Metrics {
+    /// Where to write the metrics after each notification, e.g.
+    /// `/var/lib/node_exporter/textfile/anot.prom`. Unset disables them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub textfile_path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Config {
+    /// JSON Schema editors check the file against, e.g. the one `anot config schema` prints.
+    #[serde(rename = "$schema", default, skip_serializing_if = "Option::is_none")]
+    pub schema: Option<String>,
+
+    pub version: u32,
+    pub claude: Claude,
+    pub codex: Codex,
+
+    #[serde(default)]
+    pub opencode: Opencode,
+
+    #[serde(default)]
+    pub history: History,
+
+    /// Append every processed event, sent or not, to `events.jsonl` in a documented format
+    /// other programs can read.
+    #[serde(default)]
+    pub events_log: bool,
+
+    /// Identical notifications (same agent, event and body) within this many seconds are
+    /// sent only once. `0` disables deduplication.
+    #[serde(default = "default_dedupe_window_secs")]
+    pub dedupe_window_secs: u64,
+
+    /// Program and arguments run with each rendered notification as JSON on stdin before it's
+    /// sent. Exit code 0 sends it, 10 drops it, and anything else is logged and sends it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub filter_command: Vec<String>,
+
+    /// How long `filter_command` may run before the notification is sent without its answer.
+    #[serde(default = "default_filter_timeout_secs")]
+    pub filter_timeout_secs: u64,
+
+    /// Program and arguments run with each notification and how its delivery went as JSON on
+    /// stdin, once anot has answered the agent. Failures are only logged.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub on_sent: Vec<String>,
+
+    /// How long an `on_sent` command may run before it's stopped.
+    #[serde(default = "default_on_sent_timeout_secs")]
+    pub on_sent_timeout_secs: u64,
+
+    /// Skip desktop notifications while one of `terminal_apps` is the focused application.
+    #[serde(default)]
+    pub suppress_when_focused: bool,
+
+    /// macOS bundle ids and X11 WM_CLASS names treated as terminals by `suppress_when_focused`.
+    #[serde(default = "default_terminal_apps")]
+    pub terminal_apps: Vec<String>,
+
+    /// On Linux, a new notification from the same Claude session or Codex turn replaces the
+    /// previous one instead of stacking.
+    #[serde(default = "default_group_by_session")]
+    pub group_by_session: bool,
+
+    /// On Linux, the size in pixels the built-in icons are picked for: the smallest embedded
+    /// size (48, 128 or the full-size image) at least this large.
+    #[serde(
+        default = "default_icon_size",
+        deserialize_with = "deserialize_icon_size"
+    )]
+    #[schemars(range(min = 1))]
+    pub icon_size: u32,
+
+    /// Let `.anot.json`/`.anot.toml` project files set `backends`, backend settings,
+    /// `claude.actions`, `claude.emit_output` and the sound and icon file paths. Off by default
+    /// so a repository can't redirect notifications.
+    #[serde(default)]
+    pub allow_project_backends: bool,
+
+    /// Where notifications are sent, in order.
+    #[serde(default = "default_backends")]
+    pub backends: Vec<Backend>,
+
+    #[serde(default)]
+    pub tmux: Tmux,
+
+    #[serde(default)]
+    pub statusfile: Statusfile,
+
+    #[serde(default)]
+    pub terminal_escape: TerminalEscape,
+
+    #[serde(default)]
+    pub wsl_bridge: WslBridge,
+
+    /// Retries and timeouts of the backends that deliver over HTTP.
+    #[serde(default)]
+    pub http: Http,
+
+    /// What to do instead when desktop notifications can't be shown (no notification daemon).
+    #[serde(default)]
+    pub fallback: Fallback,
+
+    /// Backends tried in order until one delivers, instead of sending to all of `backends`.
+    /// Empty (the default) uses `backends`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fallbacks: Vec<Backend>,
+
+    /// Whether Claude and Codex wait for notifications to be sent before returning.
+    #[serde(default)]
+    pub delivery: DeliveryMode,
+
+    /// What happens to notifications while `anot mute` or `respect_dnd` holds them back.
+    #[serde(default)]
+    pub mute_mode: MuteMode,
+
+    /// Hold notifications back while macOS Focus / Do Not Disturb is on, or while the Linux
+    /// notification server is inhibited.
+    #[serde(default)]
+    pub respect_dnd: bool,
+
+    /// A daily window, in local time, during which notifications are held back as with
+    /// `anot mute`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<QuietHours>,
+
+    #[serde(default)]
+    pub debug: DebugOptions,
+
+    #[serde(default)]
+    pub privacy: Privacy,
+
+    #[serde(default)]
+    pub metrics: Metrics,
+
+    /// Language of the built-in notification text: `en`, `zh-TW` or `ja`. The `ANOT_LANG`
+    /// environment variable overrides it. Unsupported languages fall back to English.
+    #[serde(default = "default_language")]
+    pub language: String,
+
+    /// Named partial configs, merged over this one when selected with `--profile`,
+    /// `ANOT_PROFILE` or `active_profile`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Value>,
+
+    /// Profile used when neither `--profile` nor `ANOT_PROFILE` selects one. Set with
+    /// `anot profile use`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+
+    /// Path the config was loaded from. History and state files live next to it.
+    #[serde(skip)]
+    pub location: Option<PathBuf>,
+
+    /// The profile merged into this config, passed on to the background delivery process.
+    #[serde(skip)]
+    pub profile: Option<String>,
+
+    /// Set by `anot replay`: notifications are marked as replayed.
+    #[serde(skip)]
+    pub replay: bool,
+
+    /// Set by `--dry-run`: notifications are described on stderr instead of sent, and no state
+    /// is recorded.
+    #[serde(skip)]
+    pub dry_run: bool,
+
+    /// Set in the background `anot --deliver` process, where waiting for the user to act on a
+    /// notification doesn't hold up the agent.
+    #[serde(skip)]
+    pub background: bool,
+
+    /// Set while the spool is being sent (`anot flush`, `anot unmute`): those notifications
+    /// aren't held back again.
+    #[serde(skip)]
+    pub flushing: bool,
+
+    /// Set in `anot serve`, whose payloads come from agents on other machines: its own working
+    /// directory says nothing about theirs.
+    #[serde(skip)]
+    pub remote: bool,
+}
+
+/// `quiet_hours`: from `start` until `end`, wrapping past midnight when `end` comes first.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(deny_unknown_fields)]
+pub struct QuietHours {
+    pub start: ClockTime,
+    pub end: ClockTime,
+}
+
+impl QuietHours {
+    /// Whether `minute` (minutes since local midnight) falls in the window.
+    pub fn contains(&self, minute: u16) -> bool {
+        let (start, end) = (self.start.0, self.end.0);
+        if start <= end {
+            (start..end).contains(&minute)
+        } else {
+            minute >= start || minute < end
+        }
+    }
+}
+
+/// A time of day written as `HH:MM`, kept as minutes since midnight.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(try_from = "String", into = "String")]
+pub struct ClockTime(pub u16);
+
+impl TryFrom<String> for ClockTime {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl std::str::FromStr for ClockTime {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("invalid time `{value}`, expected HH:MM such as 22:30");
+        let (hours, minutes) = value.trim().split_once(':').ok_or_else(invalid)?;
+        let hours: u16 = hours.parse().map_err(|_| invalid())?;
+        let minutes: u16 = minutes.parse().map_err(|_| invalid())?;
+        if hours > 23 || minutes > 59 {
+            return Err(invalid());
+        }
+        Ok(ClockTime(hours * 60 + minutes))
+    }
+}
+
+impl From<ClockTime> for String {
+    fn from(time: ClockTime) -> Self {
+        time.to_string()
+    }
+}
+
+impl std::fmt::Display for ClockTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:02}:{:02}", self.0 / 60, self.0 % 60)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Backend {
+    /// Native desktop notifications
+    Desktop,
+    /// `tmux display-message` in the current tmux session
+    Tmux,
+    /// The latest notification written to a file, for shell prompts and status lines
+    Statusfile,
+    /// OSC escape sequences written to the terminal, which forwards them over SSH
+    TerminalEscape,
+    /// Windows toasts on the host, through `powershell.exe` from inside WSL
+    WslBridge,
+}
+
+impl Backend {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Backend::Desktop => "desktop",
+            Backend::Tmux => "tmux",
+            Backend::Statusfile => "statusfile",
+            Backend::TerminalEscape => "terminal_escape",
+            Backend::WslBridge => "wsl_bridge",
+        }
+    }
+}
+
+fn default_backends() -> Vec<Backend> {
+    vec![Backend::Desktop]
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Tmux {
+    /// Also store the latest notification in the `@anot_last` global option for status lines
+    pub set_status_option: bool,
+}
+
+impl Default for Tmux {
+    fn default() -> Self {
+        Tmux {
+            set_status_option: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct Statusfile {
+    /// Defaults to `status` next to the config file
+    #[serde(default)]
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct TerminalEscape {
+    pub flavor: EscapeFlavor,
+    /// Use the terminal escape backend when connected over SSH without a display, even if it
+    /// isn't listed in `backends`
+    pub auto_over_ssh: bool,
+}
+
+impl Default for TerminalEscape {
+    fn default() -> Self {
+        TerminalEscape {
+            flavor: EscapeFlavor::default(),
+            auto_over_ssh: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EscapeFlavor {
+    /// `OSC 9` (iTerm2, kitty, WezTerm, Windows Terminal)
+    #[default]
+    Osc9,
+    /// `OSC 777;notify` with a separate title (foot, WezTerm, urxvt)
+    Osc777,
+    /// `OSC 9` plus iTerm2's dock-bouncing attention request
+    Iterm,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct WslBridge {
+    /// Under WSL, show a Windows toast when desktop notifications can't be shown, even if
+    /// `wsl_bridge` isn't listed in `backends`
+    pub auto: bool,
+}
+
+impl Default for WslBridge {
+    fn default() -> Self {
+        WslBridge { auto: true }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct Http {
+    /// Extra attempts after a failure that may be transient (a timeout, 5xx or 429)
+    pub retries: u32,
+    /// How long one attempt may take
+    pub timeout_secs: u64,
+    /// Upper bound on all attempts and the waits between them, so a hook never hangs longer
+    pub max_total_secs: u64,
+}
+
+impl Default for Http {
+    fn default() -> Self {
+        Http {
+            retries: 2,
+            timeout_secs: 5,
+            max_total_secs: 10,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Fallback {
+    /// Ring the terminal bell
+    TerminalBell,
+    /// Write the notification to the status file
+    Statusfile,
+    /// Only log a warning
+    #[default]
+    None,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryMode {
+    /// Send notifications before answering the hook
+    #[default]
+    Blocking,
+    /// Answer the hook right away and send notifications from a background `anot` process
+    Detached,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, JsonSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MuteMode {
+    /// Discard notifications while muted
+    #[default]
+    Drop,
+    /// Keep them and send them once notifications are let through again
+    Defer,
+}
+
+fn default_filter_timeout_secs() -> u64 {
+    2
+}
+
+fn default_on_sent_timeout_secs() -> u64 {
+    5
+}
+
+fn default_dedupe_window_secs() -> u64 {
+    10
+}
+
+fn default_turn_dedupe_window_secs() -> u64 {
+    60
+}
+
+fn default_group_by_session() -> bool {
+    true
+}
+
+fn default_icon_size() -> u32 {
+    128
+}
+
+fn default_language() -> String {
+    "en".to_string()
+}
+
+fn default_terminal_apps() -> Vec<String> {
+    [
+        // macOS bundle ids
+        "com.apple.Terminal",
+        "com.googlecode.iterm2",
+        "net.kovidgoyal.kitty",
+        "com.github.wez.wezterm",
+        "com.mitchellh.ghostty",
+        // X11 WM_CLASS
+        "kitty",
+        "org.wezfurlong.wezterm",
+        "gnome-terminal-server",
+        "konsole",
+        "Alacritty",
+        "xterm",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            schema: None,
+            version: 1,
+            claude: Claude::default(),
+            codex: Codex::default(),
+            opencode: Opencode::default(),
+            history: History::default(),
+            events_log: false,
+            dedupe_window_secs: default_dedupe_window_secs(),
+            filter_command: Vec::new(),
+            filter_timeout_secs: default_filter_timeout_secs(),
+            on_sent: Vec::new(),
+            on_sent_timeout_secs: default_on_sent_timeout_secs(),
+            suppress_when_focused: false,
+            terminal_apps: default_terminal_apps(),
+            group_by_session: default_group_by_session(),
+            icon_size: default_icon_size(),
+            allow_project_backends: false,
+            backends: default_backends(),
+            tmux: Tmux::default(),
+            statusfile: Statusfile::default(),
+            terminal_escape: TerminalEscape::default(),
+            wsl_bridge: WslBridge::default(),
+            fallback: Fallback::default(),
+            fallbacks: Vec::new(),
+            http: Http::default(),
+            delivery: DeliveryMode::default(),
+            mute_mode: MuteMode::default(),
+            respect_dnd: false,
+            quiet_hours: None,
+            debug: DebugOptions::default(),
+            privacy: Privacy::default(),
+            metrics: Metrics::default(),
+            language: default_language(),
+            profiles: BTreeMap::new(),
+            active_profile: None,
+            location: None,
+            profile: None,
+            replay: false,
+            dry_run: false,
+            background: false,
+            flushing: false,
+            remote: false,
+        }
+    }
+}
+
+impl Config {
+    /// Fails on settings that load but can't be used as they are.
+    fn check(&self) -> Result<(), Error> {
+        self.claude.check()?;
+        for (agent, forward) in [
+            ("claude", &self.claude.forward),
+            ("codex", &self.codex.forward),
+        ] {
+            if let Some(forward) = forward {
+                forward
+                    .check()
+                    .with_context(|| format!("Invalid {agent}.forward"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// This config with `overlay` (a partial config) deep-merged over it. Runtime-only fields
+    /// are kept.
+    pub fn with_overlay(&self, overlay: Value) -> Result<Config, Error> {
+        let mut merged = serde_json::to_value(self)?;
+        merge(&mut merged, overlay);
+
+        let config = Config {
+            location: self.location.clone(),
+            profile: self.profile.clone(),
+            replay: self.replay,
+            dry_run: self.dry_run,
+            background: self.background,
+            flushing: self.flushing,
+            remote: self.remote,
+            ..serde_json::from_value(merged)?
+        };
+        config.check()?;
+        Ok(config)
+    }
+
+    /// The fallback chain for `agent`'s notifications: its own `fallbacks` when set, otherwise
+    /// the top-level one. Empty means every backend in `backends` is used.
+    pub fn fallback_chain(&self, agent: &str) -> &[Backend] {
+        let own = match agent {
+            "claude" => self.claude.fallbacks.as_deref(),
+            "codex" => self.codex.fallbacks.as_deref(),
+            _ => None,
+        };
+        own.unwrap_or(&self.fallbacks)
+    }
+
+    /// Directory for the files anot maintains (history, state): the state directory for the
+    /// default config, or the directory of a config given with `--config`, so separate configs
+    /// keep separate state.
+    pub fn data_dir(&self) -> PathBuf {
+        match self.location.as_deref().and_then(Path::parent) {
+            Some(dir) if self.location != get_config_path() => dir.to_path_buf(),
+            _ => get_state_dir(),
+        }
+    }
+}
+
+/// Merges `overlay` into `base`: tables are merged key by key, anything else is replaced.
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Object(base), Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+pub fn get_config_path() -> Option<PathBuf> {
+    let dir = match dirs::config_dir() {
+        Some(config_dir) => config_dir.join("agent_notifications"),
+        None => env::current_dir().ok()?,
+    };
+    Some(config_file_in(&dir, ConfigFormat::preferred()))
+}
+
+/// The config file in `dir`: `a-notifications.json` or `a-notifications.toml`, whichever
+/// exists (JSON when both do). When neither does, the one in `preferred` format, JSON by
+/// default.
+fn config_file_in(dir: &Path, preferred: Option<ConfigFormat>) -> PathBuf {
+    let json = dir.join("a-notifications.json");
+    let toml = dir.join("a-notifications.toml");
+    match (json.exists(), toml.exists()) {
+        (true, _) => json,
+        (false, true) => toml,
+        (false, false) if preferred == Some(ConfigFormat::Toml) => toml,
+        (false, false) => json,
+    }
+}
+
+/// Directory for logs, history and runtime state, kept out of the config directory:
+/// `$XDG_STATE_HOME/agent_notifications` (`~/.local/state`), the local data directory on
+/// systems without one, and the config directory as a last resort.
+pub fn get_state_dir() -> PathBuf {
+    state_dir_from(
+        dirs::state_dir(),
+        dirs::data_local_dir(),
+        get_config_path().as_deref(),
+    )
+}
+
+fn state_dir_from(
+    state_dir: Option<PathBuf>,
+    data_local_dir: Option<PathBuf>,
+    config_path: Option<&Path>,
+) -> PathBuf {
+    state_dir
+        .or(data_local_dir)
+        .map(|dir| dir.join("agent_notifications"))
+        .or_else(|| config_path.and_then(Path::parent).map(Path::to_path_buf))
+        .unwrap_or_else(|| env::temp_dir().join("agent_notifications"))
+}
+
+pub fn get_logs_dir() -> PathBuf {
+    get_state_dir().join("logs")
+}
+
+/// Where logs were written before they moved to the state directory.
+fn legacy_logs_dir() -> Option<PathBuf> {
+    Some(get_config_path()?.parent()?.join("logs"))
+}
+
+/// Moves the logs an older anot wrote into the config directory over to [`get_logs_dir`].
+/// Returns what was moved; once the old directory is gone this does nothing. Call it before
+/// logging starts, and log the result afterwards.
+pub fn migrate_legacy_logs() -> Result<Vec<PathBuf>, Error> {
+    match legacy_logs_dir() {
+        Some(legacy) => move_dir_contents(&legacy, &get_logs_dir()),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Logs the outcome of [`migrate_legacy_logs`].
+pub fn report_logs_migration(result: &Result<Vec<PathBuf>, Error>) {
+    match result {
+        Ok(moved) if moved.is_empty() => {}
+        Ok(moved) => info!(
+            count = moved.len(),
+            to = %get_logs_dir().display(),
+            files = ?moved,
+            "moved logs from the config directory to the state directory"
+        ),
+        Err(e) => warn!(error = %e, "failed to move logs to the state directory"),
+    }
+}
+
+/// Moves the entries of `from` into `to` and removes `from`. Entries `to` already has are left
+/// where they are, and so is `from` while it still holds them.
+fn move_dir_contents(from: &Path, to: &Path) -> Result<Vec<PathBuf>, Error> {
+    if from == to || !from.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    fs::create_dir_all(to)?;
+    let mut moved = Vec::new();
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        if target.exists() {
+            continue;
+        }
+        match fs::rename(entry.path(), &target) {
+            Ok(()) => moved.push(target),
+            // The state directory is on another file system.
+            Err(e) if e.kind() == ErrorKind::CrossesDevices => {
+                fs::copy(entry.path(), &target)?;
+                fs::remove_file(entry.path())?;
+                moved.push(target);
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    match fs::remove_dir(from) {
+        Ok(()) => {}
+        Err(e) if e.kind() == ErrorKind::DirectoryNotEmpty => {}
+        Err(e) => return Err(e.into()),
+    }
+    Ok(moved)
+}
+
+/// Writes the default config to `path`, unless a file is already there. `schema` is the
+/// `$schema` it points editors at, if any.
+pub fn create_default_config(path: &Path, schema: Option<&str>) -> Result<(), Error> {
+    let default_config = Config {
+        schema: schema.map(String::from),
+        ..Config::default()
+    };
+    let config_data = ConfigFormat::of(path).to_string(&default_config)?;
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    create_file_exclusively(path, config_data.as_bytes())?;
+
+    Ok(())
+}
+
+/// Loads the config at `config_path`, creating the default one when there's none, with the
+/// local overrides merged over it.
+pub fn initialize_configuration(config_path: &Path) -> Result<Config, Error> {
+    if !config_path.exists() {
+        create_default_config(config_path, None).with_context(|| {
+            format!("Can't create the default config {}", config_path.display())
+        })?;
+    }
+
+    let contents = read_configuration(config_path)?;
+    let parse_error = |source: Error| AnotError::ConfigParse {
+        path: config_path.to_path_buf(),
+        source,
+    };
+
+    let mut config: Config = match ConfigFormat::of(config_path) {
+        ConfigFormat::Json => match jsonc::from_str(&contents) {
+            Ok(config) => config,
+            // Anything else may be a hand edit in progress, such as a missing closing brace.
+            Err(e) if contents.trim().is_empty() => recover_empty_configuration(config_path, &e)?,
+            Err(e) => return Err(parse_error(e.into()).into()),
+        },
+        ConfigFormat::Toml => toml::from_str(&contents).map_err(|e| parse_error(e.into()))?,
+    };
+    config.check().map_err(parse_error)?;
+    config.location = Some(config_path.to_path_buf());
+
+    local::apply_local(config, config_path)
+}
+
+/// Checks that the config at `config_path` and its local overrides load, without creating or
+/// recovering anything, and returns the files checked.
+pub fn validate_configuration(config_path: &Path) -> Result<Vec<ValidatedFile>, Error> {
+    let contents = read_configuration(config_path)?;
+    let format = ConfigFormat::of(config_path);
+    let config: Config = format
+        .parse(&contents)
+        .map_err(|source| AnotError::ConfigParse {
+            path: config_path.to_path_buf(),
+            source,
+        })?;
+    config.check().map_err(|source| AnotError::ConfigParse {
+        path: config_path.to_path_buf(),
+        source,
+    })?;
+    let mut files = vec![ValidatedFile {
+        path: config_path.to_path_buf(),
+        lenient: format == ConfigFormat::Json && jsonc::is_lenient(&contents),
+    }];
+
+    let local_path = local::local_path(config_path);
+    if local_path.exists() {
+        local::apply_local(config, config_path)?;
+        files.push(ValidatedFile {
+            lenient: jsonc::is_lenient(&fs::read_to_string(&local_path)?),
+            path: local_path,
+        });
+    }
+    Ok(files)
+}
+
+fn read_configuration(config_path: &Path) -> Result<String, AnotError> {
+    fs::read_to_string(config_path).map_err(|source| AnotError::ConfigRead {
+        path: config_path.to_path_buf(),
+        source,
+    })
+}
+
+/// Moves an empty config (e.g. left behind by an interrupted write) aside and recreates the
+/// default one. Files with any content are never touched, and the lock keeps `anot config set`
+/// from writing one in between.
+fn recover_empty_configuration(
+    config_path: &Path,
+    error: &serde_json::Error,
+) -> Result<Config, Error> {
+    with_lock(config_path, || {
+        let backup_path = config_path.with_extension("json.corrupt");
+        match fs::read_to_string(config_path) {
+            Ok(contents) if contents.trim().is_empty() => {
+                warn!(
+                    path = %config_path.display(),
+                    backup = %backup_path.display(),
+                    error = %error,
+                    "config file is empty; recreating defaults"
+                );
+                fs::rename(config_path, &backup_path)?;
+            }
+            Ok(_) => {}
+            // Another process already moved it aside.
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+
+        create_default_config(config_path, None)?;
+        let contents = fs::read_to_string(config_path)?;
+        Ok(jsonc::from_str(&contents)?)
+    })
+}
+
+/// Replaces the config with the defaults in one step, so hooks running meanwhile never find
+/// it missing and race to create it.
+pub fn reset_configuration(config_path: &Path) -> Result<(), Error> {
+    let config_data = ConfigFormat::of(config_path).to_string(&Config::default())?;
+    write_atomic(config_path, config_data.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn create_default_config_never_truncates_existing_file() {
+        let path = temp_dir("config-tests", "no-truncate").join("a-notifications.json");
+        fs::write(&path, "{\"custom\":true}").unwrap();
+
+        create_default_config(&path, None).unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"custom\":true}");
+    }
+
+    #[test]
+    fn config_file_prefers_the_one_that_exists() {
+        let dir = temp_dir("config-tests", "config-file-in");
+        let json = dir.join("a-notifications.json");
+        let toml = dir.join("a-notifications.toml");
+
+        assert_eq!(config_file_in(&dir, None), json);
+        assert_eq!(config_file_in(&dir, Some(ConfigFormat::Toml)), toml);
+
+        fs::write(&toml, "").unwrap();
+        assert_eq!(config_file_in(&dir, None), toml);
+        fs::write(&json, "{}").unwrap();
+        assert_eq!(config_file_in(&dir, Some(ConfigFormat::Toml)), json);
+    }
+
+    #[test]
+    fn toml_configs_are_created_and_loaded_as_toml() {
+        let path = temp_dir("config-tests", "toml").join("a-notifications.toml");
+
+        let config = initialize_configuration(&path).unwrap();
+        assert_eq!(config.location.as_deref(), Some(path.as_path()));
+        let contents = fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("[claude]"), "{contents}");
+
+        fs::write(
+            &path,
+            contents.replace("dedupe_window_secs = 10", "dedupe_window_secs = 3"),
+        )
+        .unwrap();
+        assert_eq!(
+            initialize_configuration(&path).unwrap().dedupe_window_secs,
+            3
+        );
+    }
+
+    #[test]
+    fn create_default_config_leaves_no_temp_files() {
+        let dir = temp_dir("config-tests", "no-temp-files");
+        let path = dir.join("a-notifications.json");
+
+        create_default_config(&path, None).unwrap();
+        create_default_config(&path, None).unwrap();
+
+        let entries: Vec<_> = fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(entries.len(), 1);
+    }
+
+    #[test]
+    fn state_dir_falls_back_in_order() {
+        let config_path = Path::new("/home/me/.config/agent_notifications/a-notifications.json");
+
+        assert_eq!(
+            state_dir_from(
+                Some("/home/me/.local/state".into()),
+                Some("/home/me/.local/share".into()),
+                Some(config_path)
+            ),
+            Path::new("/home/me/.local/state/agent_notifications")
+        );
+        assert_eq!(
+            state_dir_from(
+                None,
+                Some("/Users/me/Library/Application Support".into()),
+                None
+            ),
+            Path::new("/Users/me/Library/Application Support/agent_notifications")
+        );
+        assert_eq!(
+            state_dir_from(None, None, Some(config_path)),
+            Path::new("/home/me/.config/agent_notifications")
+        );
+        assert_eq!(
+            state_dir_from(None, None, None),
+            env::temp_dir().join("agent_notifications")
+        );
+    }
+
+    #[test]
+    fn data_dir_follows_an_explicit_config() {
+        let config = Config {
+            location: Some(PathBuf::from("/work/anot/a-notifications.json")),
+            ..Config::default()
+        };
+        assert_eq!(config.data_dir(), Path::new("/work/anot"));
+        assert_eq!(Config::default().data_dir(), get_state_dir());
+    }
+
+    #[test]
+    fn logs_are_moved_once() {
+        let dir = temp_dir("config-tests", "move-logs");
+        let (from, to) = (dir.join("config/logs"), dir.join("state/logs"));
+        fs::create_dir_all(&from).unwrap();
+        fs::write(from.join("anot.log.2026-01-01"), "old").unwrap();
+        fs::write(from.join("anot.log.2026-01-02"), "older copy").unwrap();
+        fs::create_dir_all(&to).unwrap();
+        fs::write(to.join("anot.log.2026-01-02"), "new").unwrap();
+
+        let moved = move_dir_contents(&from, &to).unwrap();
+        assert_eq!(moved, [to.join("anot.log.2026-01-01")]);
+        assert_eq!(
+            fs::read_to_string(to.join("anot.log.2026-01-01")).unwrap(),
+            "old"
+        );
+        assert_eq!(
+            fs::read_to_string(to.join("anot.log.2026-01-02")).unwrap(),
+            "new"
+        );
+        assert!(
+            from.exists(),
+            "kept while it holds a file the state dir already has"
+        );
+
+        fs::remove_file(from.join("anot.log.2026-01-02")).unwrap();
+        assert!(move_dir_contents(&from, &to).unwrap().is_empty());
+        assert!(!from.exists());
+        assert!(
+            move_dir_contents(&from, &to).unwrap().is_empty(),
+            "a no-op once done"
+        );
+    }
+
+    #[test]
+    fn empty_config_is_recreated() {
+        let dir = temp_dir("config-tests", "empty");
+        let path = dir.join("a-notifications.json");
+        fs::write(&path, " \n").unwrap();
+
+        let config = initialize_configuration(&path).unwrap();
+
+        assert_eq!(config.version, 1);
+        assert!(dir.join("a-notifications.json.corrupt").exists());
+    }
+
+    #[test]
+    fn unfinished_edits_are_not_recovered() {
+        let dir = temp_dir("config-tests", "unfinished");
+        let path = dir.join("a-notifications.json");
+        let contents = "{\"version\": 1, \"claude\": {\"sound\": false}";
+        fs::write(&path, contents).unwrap();
+
+        assert!(initialize_configuration(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), contents);
+        assert!(!dir.join("a-notifications.json.corrupt").exists());
+    }
+
+    #[test]
+    fn syntax_errors_are_not_recovered() {
+        let path = temp_dir("config-tests", "syntax-error").join("a-notifications.json");
+        fs::write(&path, "{\"version\": nope}").unwrap();
+
+        assert!(initialize_configuration(&path).is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "{\"version\": nope}");
+    }
+
+    #[test]
+    fn event_override_takes_precedence_over_section_and_defaults() {
+        let claude: Claude = serde_json::from_str(
+            r#"{
+                "pretend": false,
+                "sound": false,
+                "overrides": {
+                    "Stop": {"pretend": true},
+                    "Notification": {"sound": true, "icon": "/tmp/permission.png"}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            claude.settings_for("Stop"),
+            NotificationSettings {
+                pretend: Pretend::Always,
+                sound: false,
+                sound_file: None,
+                icon: None,
+            }
+        );
+        assert_eq!(
+            claude.settings_for("Notification"),
+            NotificationSettings {
+                pretend: Pretend::Never,
+                sound: true,
+                sound_file: None,
+                icon: Some(PathBuf::from("/tmp/permission.png")),
+            }
+        );
+        assert_eq!(
+            claude.settings_for("PreToolUse"),
+            NotificationSettings {
+                pretend: Pretend::Never,
+                sound: false,
+                sound_file: None,
+                icon: None,
+            }
+        );
+
+        let codex = Codex::default();
+        assert_eq!(
+            codex.settings_for("AgentTurnComplete"),
+            NotificationSettings {
+                pretend: Pretend::Never,
+                sound: true,
+                sound_file: None,
+                icon: None,
+            }
+        );
+    }
+
+    #[test]
+    fn pretend_accepts_booleans_and_modes() {
+        let cases = [
+            ("true", Pretend::Always),
+            ("false", Pretend::Never),
+            ("\"auto\"", Pretend::Auto),
+            ("\"always\"", Pretend::Always),
+            ("\"never\"", Pretend::Never),
+        ];
+        for (value, expected) in cases {
+            let claude: Claude =
+                serde_json::from_str(&format!(r#"{{"pretend": {value}}}"#)).unwrap();
+            assert_eq!(claude.pretend, expected, "pretend: {value}");
+        }
+
+        let error = serde_json::from_str::<Claude>(r#"{"pretend": "sometimes"}"#).unwrap_err();
+        assert!(
+            error.to_string().contains("unknown variant `sometimes`"),
+            "{error}"
+        );
+
+        let claude: Claude = serde_json::from_str("{}").unwrap();
+        assert_eq!(claude.pretend, Pretend::Auto);
+        assert_eq!(serde_json::to_string(&Pretend::Auto).unwrap(), "\"auto\"");
+    }
+
+    #[test]
+    fn event_icon_sits_between_override_and_section_icon() {
+        let claude: Claude = serde_json::from_str(
+            r#"{
+                "pretend": false,
+                "icon": "/tmp/claude.png",
+                "event_icons": {"Stop": "/tmp/done.png", "Notification": "/tmp/attention.png"},
+                "overrides": {"Notification": {"icon": "/tmp/permission.png"}}
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            claude.settings_for("Stop").icon,
+            Some(PathBuf::from("/tmp/done.png"))
+        );
+        assert_eq!(
+            claude.settings_for("Notification").icon,
+            Some(PathBuf::from("/tmp/permission.png"))
+        );
+        assert_eq!(
+            claude.settings_for("PreToolUse").icon,
+            Some(PathBuf::from("/tmp/claude.png"))
+        );
+
+        let err = serde_json::from_str::<Claude>(
+            r#"{"pretend": true, "event_icons": {"Done": "/tmp/done.png"}}"#,
+        )
+        .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("unknown event `Done` in event_icons")
+        );
+    }
+
+    #[test]
+    fn unknown_override_events_are_rejected() {
+        let err = serde_json::from_str::<Claude>(
+            r#"{"pretend": true, "sound": true, "overrides": {"Stopp": {"sound": false}}}"#,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown event `Stopp`"));
+
+        assert!(
+            serde_json::from_str::<Codex>(
+                r#"{"pretend": false, "sound": true, "overrides": {"Stop": {}}}"#,
+            )
+            .is_err()
+        );
+        assert!(
+            serde_json::from_str::<Claude>(
+                r#"{"pretend": true, "sound": true, "overrides": {"Stop": {"volume": 3}}}"#,
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn icon_names_are_theme_names_not_paths() {
+        let claude =
+            serde_json::from_str::<Claude>(r#"{"icon_name": "utilities-terminal"}"#).unwrap();
+        assert_eq!(claude.icon_name.as_deref(), Some("utilities-terminal"));
+        assert_eq!(Claude::default().icon_name, None);
+
+        for path in ["~/icons/claude.png", "/usr/share/icons/claude.png", ""] {
+            let err = serde_json::from_str::<Codex>(&format!(r#"{{"icon_name": "{path}"}}"#))
+                .unwrap_err();
+            assert!(err.to_string().contains("set `icon`"), "{path}: {err}");
+        }
+    }
+
+    #[test]
+    fn icon_size_defaults_to_128_and_must_be_positive() {
+        assert_eq!(Config::default().icon_size, 128);
+
+        let with_size = |size: u32| {
+            let mut config = serde_json::to_value(Config::default()).unwrap();
+            config["icon_size"] = size.into();
+            serde_json::from_value::<Config>(config)
+        };
+        assert_eq!(with_size(48).unwrap().icon_size, 48);
+        assert!(with_size(0).is_err());
+    }
+
+    #[test]
+    fn sound_file_must_exist_and_be_wav_or_mp3() {
+        let dir = temp_dir("config-tests", "sound-file");
+        let ding = dir.join("ding.WAV");
+        fs::write(&ding, b"RIFF").unwrap();
+        let notes = dir.join("notes.txt");
+        fs::write(&notes, b"").unwrap();
+
+        let claude = |sound_file: &Path| {
+            serde_json::from_value::<Claude>(
+                serde_json::json!({"pretend": true, "sound_file": sound_file}),
+            )
+        };
+        assert_eq!(claude(&ding).unwrap().sound_file, Some(ding.clone()));
+
+        let err = claude(&notes).unwrap_err().to_string();
+        assert!(err.contains("expected a .wav or .mp3 file"), "{err}");
+        let err = claude(&dir.join("missing.mp3")).unwrap_err().to_string();
+        assert!(err.contains("doesn't exist"), "{err}");
+
+        let err = serde_json::from_value::<EventOverride>(
+            serde_json::json!({"sound_file": dir.join("missing.wav")}),
+        )
+        .unwrap_err()
+        .to_string();
+        assert!(err.contains("doesn't exist"), "{err}");
+    }
+
+    #[test]
+    fn sound_volume_defaults_to_full_and_must_be_a_fraction() {
+        assert_eq!(Config::default().codex.sound_volume, 1.0);
+
+        let codex = |volume: f32| {
+            let mut codex = serde_json::to_value(Codex::default()).unwrap();
+            codex["sound_volume"] = volume.into();
+            serde_json::from_value::<Codex>(codex)
+        };
+        assert_eq!(codex(0.25).unwrap().sound_volume, 0.25);
+        assert!(codex(1.5).is_err());
+        assert!(codex(-0.1).is_err());
+    }
+
+    #[test]
+    fn sample_rates_must_be_positive() {
+        let claude = serde_json::from_str::<Claude>(
+            r#"{"pretend": true, "sample": {"PostToolUse": 5, "PreToolUse": 1}}"#,
+        )
+        .unwrap();
+        assert_eq!(claude.sample["PostToolUse"], 5);
+
+        let err =
+            serde_json::from_str::<Claude>(r#"{"pretend": true, "sample": {"PostToolUse": 0}}"#)
+                .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("invalid sample rate 0 for `PostToolUse`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn claude_events_limit_what_notifies() {
+        let claude = serde_json::from_str::<Claude>(
+            r#"{"pretend": true, "events": ["Stop", "Notification"]}"#,
+        )
+        .unwrap();
+        assert!(claude.notifies_for("Stop"));
+        assert!(!claude.notifies_for("PostToolUse"));
+        assert!(Claude::default().notifies_for("PostToolUse"));
+
+        let err =
+            serde_json::from_str::<Claude>(r#"{"pretend": true, "events": ["Done"]}"#).unwrap_err();
+        assert!(
+            err.to_string().contains("unknown event `Done` in `events`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn guard_rules_are_validated() {
+        let claude = serde_json::from_str::<Claude>(
+            r#"{"pretend": true, "guard": [{"command": "rm\\s+-rf"}, {"tool": "mcp__*", "decision": "deny"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(claude.guard[0].decision, GuardDecision::Ask);
+        assert_eq!(claude.guard[1].decision, GuardDecision::Deny);
+        assert!(Claude::default().guard.is_empty());
+
+        let err =
+            serde_json::from_str::<Claude>(r#"{"pretend": true, "guard": [{"command": "rm ("}]}"#)
+                .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("invalid `command` pattern in guard rule 0"),
+            "{err}"
+        );
+        let err =
+            serde_json::from_str::<Claude>(r#"{"pretend": true, "guard": [{"decision": "deny"}]}"#)
+                .unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("guard rule 0 sets neither `command` nor `tool`"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn guard_rules_need_the_full_hook_output() {
+        let path = temp_dir("config-tests", "guard-emit-output").join("a-notifications.json");
+        let mut config = serde_json::to_value(Config::default()).unwrap();
+        config["claude"]["emit_output"] = "minimal".into();
+        config["claude"]["guard"] = serde_json::json!([{"tool": "Bash"}]);
+        fs::write(&path, config.to_string()).unwrap();
+        let err = initialize_configuration(&path).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("claude.emit_output `minimal` would drop"),
+            "{err:#}"
+        );
+        assert!(validate_configuration(&path).is_err());
+
+        let quiet = Config::default()
+            .with_overlay(serde_json::json!({"claude": {"emit_output": "none"}}))
+            .unwrap();
+        assert!(
+            quiet
+                .with_overlay(serde_json::json!({"claude": {"guard": [{"tool": "Bash"}]}}))
+                .is_err()
+        );
+        assert!(
+            quiet
+                .with_overlay(serde_json::json!({"claude": {"emit_output": "full"}}))
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn forward_tokens_need_https_off_this_machine() {
+        let forward = |url: &str, token: Option<&str>| {
+            Config::default().with_overlay(serde_json::json!({
+                "codex": {"forward": {"url": url, "token": token}}
+            }))
+        };
+
+        for url in [
+            "https://laptop:4990/codex",
+            "http://127.0.0.1:4990/codex",
+            "http://localhost/codex",
+            "http://[::1]:4990/codex",
+        ] {
+            assert!(forward(url, Some("s3cret")).is_ok(), "{url}");
+        }
+        assert!(forward("http://laptop:4990/codex", None).is_ok());
+
+        let err = forward("http://user@laptop:4990/codex", Some("s3cret")).unwrap_err();
+        assert!(
+            format!("{err:#}").contains("would send forward.token unencrypted"),
+            "{err:#}"
+        );
+        assert!(forward("http://127.0.0.1.example.com/codex", Some("s3cret")).is_err());
+    }
+
+    #[test]
+    fn history_fields_default_one_by_one() {
+        let history: History = serde_json::from_str(r#"{"enabled": false}"#).unwrap();
+        assert!(!history.enabled);
+        assert_eq!(history.max_bytes, History::default().max_bytes);
+
+        let history: History = serde_json::from_str(r#"{"max_bytes": 4096}"#).unwrap();
+        assert!(history.enabled);
+        assert_eq!(history.max_bytes, 4096);
+    }
+
+    #[test]
+    fn quiet_hours_wrap_past_midnight() {
+        let quiet: QuietHours =
+            serde_json::from_str(r#"{"start": "22:00", "end": "07:30"}"#).unwrap();
+        assert!(quiet.contains(23 * 60));
+        assert!(quiet.contains(0));
+        assert!(quiet.contains(7 * 60 + 29));
+        assert!(!quiet.contains(7 * 60 + 30));
+        assert!(!quiet.contains(12 * 60));
+
+        let lunch: QuietHours =
+            serde_json::from_str(r#"{"start": "12:00", "end": "13:00"}"#).unwrap();
+        assert!(lunch.contains(12 * 60 + 30));
+        assert!(!lunch.contains(13 * 60));
+        assert_eq!(
+            serde_json::to_value(lunch).unwrap(),
+            serde_json::json!({"start": "12:00", "end": "13:00"})
+        );
+
+        for invalid in ["24:00", "7pm", "12:60"] {
+            assert!(
+                serde_json::from_value::<QuietHours>(
+                    serde_json::json!({"start": invalid, "end": "08:00"})
+                )
+                .is_err(),
+                "{invalid}"
+            );
+        }
+    }
+
+    #[test]
+    fn claude_sound_defaults_to_true_and_round_trips() {
+        let claude: Claude = serde_json::from_str(r#"{"pretend": true}"#).unwrap();
+        assert!(claude.sound);
+
+        let quiet = Claude {
+            sound: false,
+            ..Claude::default()
+        };
+        let round_tripped: Claude =
+            serde_json::from_str(&serde_json::to_string(&quiet).unwrap()).unwrap();
+        assert!(!round_tripped.sound);
+    }
 }
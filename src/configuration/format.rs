@@ -0,0 +1,309 @@
+//! The formats a config file can be written in: JSON (with comments, see [`jsonc`]) or TOML.
+//! The format follows the file's extension; the settings are the same either way.
+
+use std::{
+    env, fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Error, bail};
+use serde::{Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use tracing::{info, warn};
+
+use crate::{
+    configuration::{Config, jsonc, local},
+    output::ConvertedFile,
+    storage::write_atomic,
+};
+
+/// Selects the format of a config file anot creates when there's none yet: `json` or `toml`.
+pub const FORMAT_ENV: &str = "ANOT_CONFIG_FORMAT";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConfigFormat {
+    #[default]
+    Json,
+    Toml,
+}
+
+impl ConfigFormat {
+    /// The format of the file at `path`: TOML for a `.toml` extension, JSON otherwise.
+    pub fn of(path: &Path) -> ConfigFormat {
+        if path.extension().is_some_and(|ext| ext == "toml") {
+            ConfigFormat::Toml
+        } else {
+            ConfigFormat::Json
+        }
+    }
+
+    /// The format `ANOT_CONFIG_FORMAT` asks for, if it's set to one.
+    pub fn preferred() -> Option<ConfigFormat> {
+        let value = env::var(FORMAT_ENV).ok()?;
+        match value.trim().parse() {
+            Ok(format) => Some(format),
+            Err(e) => {
+                warn!(error = %e, "ignoring {FORMAT_ENV}");
+                None
+            }
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            ConfigFormat::Json => "json",
+            ConfigFormat::Toml => "toml",
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(self, contents: &str) -> Result<T, Error> {
+        Ok(match self {
+            ConfigFormat::Json => jsonc::from_str(contents)?,
+            ConfigFormat::Toml => toml::from_str(contents)?,
+        })
+    }
+
+    /// `value` as the whole contents of a file, compact where the format allows it.
+    pub fn to_string<T: Serialize>(self, value: &T) -> Result<String, Error> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string(value)?,
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(&without_nulls(serde_json::to_value(value)?))?
+            }
+        })
+    }
+
+    pub fn to_string_pretty<T: Serialize>(self, value: &T) -> Result<String, Error> {
+        Ok(match self {
+            ConfigFormat::Json => serde_json::to_string_pretty(value)?,
+            ConfigFormat::Toml => {
+                toml::to_string_pretty(&without_nulls(serde_json::to_value(value)?))?
+            }
+        })
+    }
+}
+
+/// Converts the config at `config_path`, and its local overrides if there are any, to `to`.
+/// Each new file is written next to the old one, which is moved to `<name>.bak` so that the
+/// new one is the one found. Nothing is written unless every setting survives the conversion.
+pub fn convert(config_path: &Path, to: ConfigFormat) -> Result<Vec<ConvertedFile>, Error> {
+    let from = ConfigFormat::of(config_path);
+    if from == to {
+        bail!("{} is already {to}", config_path.display());
+    }
+    let contents = fs::read_to_string(config_path)
+        .with_context(|| format!("Can't read config {}", config_path.display()))?;
+    from.parse::<Config>(&contents)
+        .with_context(|| format!("Invalid config {}", config_path.display()))?;
+
+    let new_path = config_path.with_extension(to.extension());
+    let mut conversions = vec![convert_file(config_path, &new_path, to)?];
+    let local_path = local::local_path(config_path);
+    if local_path.exists() {
+        conversions.push(convert_file(
+            &local_path,
+            &local::local_path(&new_path),
+            to,
+        )?);
+    }
+
+    let mut converted = Vec::new();
+    for (old_path, path, contents) in conversions {
+        write_atomic(&path, contents.as_bytes())?;
+        let backup = old_path.with_extension(format!("{}.bak", from.extension()));
+        fs::rename(&old_path, &backup)?;
+        info!(from = %old_path.display(), to = %path.display(), "converted config file");
+        converted.push(ConvertedFile { path, backup });
+    }
+    Ok(converted)
+}
+
+/// The contents of `old_path` in the `to` format, checked to hold exactly the same settings,
+/// with the paths to move them between.
+fn convert_file(
+    old_path: &Path,
+    new_path: &Path,
+    to: ConfigFormat,
+) -> Result<(PathBuf, PathBuf, String), Error> {
+    if new_path.exists() {
+        bail!("{} already exists", new_path.display());
+    }
+    let from = ConfigFormat::of(old_path);
+    let value: Value = from
+        .parse(&fs::read_to_string(old_path)?)
+        .with_context(|| format!("Invalid config {}", old_path.display()))?;
+    let contents = to
+        .to_string_pretty(&value)
+        .with_context(|| format!("Can't write {} as {to}", old_path.display()))?;
+    if to.parse::<Value>(&contents)? != without_nulls(value) {
+        bail!(
+            "Can't convert {} to {to} without changing its settings",
+            old_path.display()
+        );
+    }
+    Ok((old_path.to_path_buf(), new_path.to_path_buf(), contents))
+}
+
+/// `value` without the `null` members of its tables. TOML has no null, and a missing setting
+/// reads the same as a `null` one.
+fn without_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, value)| !value.is_null())
+                .map(|(key, value)| (key, without_nulls(value)))
+                .collect(),
+        ),
+        Value::Array(values) => Value::Array(values.into_iter().map(without_nulls).collect()),
+        value => value,
+    }
+}
+
+impl FromStr for ConfigFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "json" => Ok(ConfigFormat::Json),
+            "toml" => Ok(ConfigFormat::Toml),
+            _ => bail!("unknown config format `{value}`: use `json` or `toml`"),
+        }
+    }
+}
+
+impl fmt::Display for ConfigFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::test_support::temp_path;
+
+    /// A config with every optional setting and table filled in.
+    fn populated() -> Value {
+        let overrides = json!({"pretend": "never", "sound": false, "icon": "/icons/stop.png"});
+        json!({
+            "version": 1,
+            "claude": {
+                "pretend": "auto",
+                "bundle_id": "com.example.claude",
+                "fallback_bundle_id": "com.apple.Terminal",
+                "sound": false,
+                "icon": "/icons/claude.png",
+                "post_tool_use": "failures",
+                "notification_categories": {
+                    "permission": {
+                        "enabled": true,
+                        "sound": true,
+                        "urgency": "critical",
+                        "title_suffix": "needs you",
+                        "patterns": ["permission"],
+                    },
+                },
+                "stop_summary": true,
+                "session_digest": true,
+                "batching": {"window_secs": 5, "min_batch_size": 3},
+                "sample": {"PostToolUse": 5},
+                "overrides": {"Stop": overrides},
+                "titles": {"Stop": "Done"},
+                "event_icons": {"Notification": "/icons/bell.png"},
+                "actions": {
+                    "on_click": ["code", "{cwd}"],
+                    "buttons": [{"label": "Open", "command": "open {transcript_path}"}],
+                    "dismiss_all": true,
+                    "wait_secs": 30,
+                },
+                "fallbacks": ["tmux"],
+            },
+            "codex": {
+                "pretend": "always",
+                "sound": false,
+                "bundle_id": "com.example.codex",
+                "icon": "/icons/codex.png",
+                "overrides": {"AgentTurnComplete": overrides},
+                "titles": {"AgentTurnComplete": "Turn done"},
+                "title_includes_prompt": true,
+                "fallbacks": ["statusfile"],
+            },
+            "backends": ["desktop", "statusfile"],
+            "statusfile": {"path": "/tmp/anot-status"},
+            "dedupe_window_secs": 30,
+            "profiles": {"work": {"claude": {"sound": false}, "backends": ["tmux"]}},
+            "active_profile": "work",
+        })
+    }
+
+    #[test]
+    fn a_populated_config_round_trips_between_formats() {
+        let config: Config = serde_json::from_value(populated()).unwrap();
+        let json = serde_json::to_value(&config).unwrap();
+
+        for format in [ConfigFormat::Json, ConfigFormat::Toml] {
+            let contents = format.to_string_pretty(&config).unwrap();
+            let parsed: Config = format.parse(&contents).unwrap();
+            assert_eq!(
+                without_nulls(serde_json::to_value(&parsed).unwrap()),
+                without_nulls(json.clone()),
+                "{format}"
+            );
+        }
+
+        let toml = ConfigFormat::Toml.to_string_pretty(&json).unwrap();
+        let back = ConfigFormat::Json
+            .to_string(&ConfigFormat::Toml.parse::<Value>(&toml).unwrap())
+            .unwrap();
+        let config_back: Config = ConfigFormat::Json.parse(&back).unwrap();
+        assert_eq!(serde_json::to_value(&config_back).unwrap(), json);
+    }
+
+    #[test]
+    fn convert_moves_the_old_files_aside() {
+        let dir = temp_path("format", "convert");
+        fs::create_dir_all(&dir).unwrap();
+        let json_path = dir.join("a-notifications.json");
+        fs::write(&json_path, populated().to_string()).unwrap();
+        fs::write(
+            dir.join("a-notifications.local.json"),
+            r#"{"codex": {"sound": true}}"#,
+        )
+        .unwrap();
+
+        let converted = convert(&json_path, ConfigFormat::Toml).unwrap();
+        assert_eq!(
+            converted,
+            [
+                ConvertedFile {
+                    path: dir.join("a-notifications.toml"),
+                    backup: dir.join("a-notifications.json.bak"),
+                },
+                ConvertedFile {
+                    path: dir.join("a-notifications.local.toml"),
+                    backup: dir.join("a-notifications.local.json.bak"),
+                },
+            ]
+        );
+        assert!(!json_path.exists());
+        let toml: Value =
+            toml::from_str(&fs::read_to_string(dir.join("a-notifications.toml")).unwrap()).unwrap();
+        assert_eq!(toml, populated());
+
+        let error = convert(&dir.join("a-notifications.toml"), ConfigFormat::Toml).unwrap_err();
+        assert!(error.to_string().contains("is already toml"), "{error}");
+    }
+
+    #[test]
+    fn settings_toml_cant_hold_are_never_written() {
+        let value = json!({"claude": {"titles": {"Stop": "Done"}}, "terminal_apps": [null]});
+        assert!(ConfigFormat::Toml.to_string_pretty(&value).is_err());
+        assert_eq!(
+            without_nulls(json!({"statusfile": {"path": null}, "version": 1})),
+            json!({"statusfile": {}, "version": 1})
+        );
+    }
+}
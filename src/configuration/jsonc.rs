@@ -0,0 +1,153 @@
+//! Lenient reading of the hand-edited config files: `//` and `/* */` comments and trailing
+//! commas are accepted. They're blanked out rather than removed before parsing, so the line and
+//! column in a parse error still point into the file as written. Files anot writes are always
+//! strict JSON.
+
+use std::borrow::Cow;
+
+use serde::de::DeserializeOwned;
+
+/// Parses `contents` as JSON with comments and trailing commas.
+pub fn from_str<T: DeserializeOwned>(contents: &str) -> Result<T, serde_json::Error> {
+    serde_json::from_str(&to_strict(contents))
+}
+
+/// Whether `contents` uses comments or trailing commas, which strict JSON doesn't allow.
+pub fn is_lenient(contents: &str) -> bool {
+    matches!(to_strict(contents), Cow::Owned(_))
+}
+
+/// `contents` with its comments and trailing commas replaced by spaces, byte for byte, keeping
+/// line breaks. Borrowed when there's nothing to replace.
+pub fn to_strict(contents: &str) -> Cow<'_, str> {
+    let mut bytes = contents.as_bytes().to_vec();
+    let blanked_comments = blank_comments(&mut bytes);
+    let blanked_commas = blank_trailing_commas(&mut bytes);
+    if !blanked_comments && !blanked_commas {
+        return Cow::Borrowed(contents);
+    }
+    // Only ASCII outside of strings and whole comments were replaced, so it's still UTF-8.
+    Cow::Owned(String::from_utf8(bytes).expect("blanking keeps the text UTF-8"))
+}
+
+/// Replaces comments outside of strings with spaces. An unterminated block comment is left as
+/// it is, so it's reported as the syntax error it is rather than as a truncated file.
+fn blank_comments(bytes: &mut [u8]) -> bool {
+    let mut changed = false;
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match (bytes[i], bytes.get(i + 1)) {
+            (b'\\', _) if in_string => i += 1,
+            (b'"', _) => in_string = !in_string,
+            (b'/', Some(b'/')) if !in_string => {
+                let end = bytes[i..]
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map_or(bytes.len(), |offset| i + offset);
+                blank(&mut bytes[i..end]);
+                changed = true;
+                i = end;
+                continue;
+            }
+            (b'/', Some(b'*')) if !in_string => {
+                let Some(offset) = bytes[i + 2..].windows(2).position(|w| w == b"*/") else {
+                    return changed;
+                };
+                let end = i + 2 + offset + 2;
+                blank(&mut bytes[i..end]);
+                changed = true;
+                i = end;
+                continue;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    changed
+}
+
+/// Replaces commas followed only by whitespace and a closing `}` or `]` with spaces. Expects
+/// the comments to be blanked already.
+fn blank_trailing_commas(bytes: &mut [u8]) -> bool {
+    let mut changed = false;
+    let mut in_string = false;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' if in_string => i += 1,
+            b'"' => in_string = !in_string,
+            b',' if !in_string => {
+                let next = bytes[i + 1..].iter().find(|b| !b.is_ascii_whitespace());
+                if matches!(next, Some(b'}' | b']')) {
+                    bytes[i] = b' ';
+                    changed = true;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    changed
+}
+
+fn blank(bytes: &mut [u8]) {
+    for byte in bytes.iter_mut().filter(|b| **b != b'\n' && **b != b'\r') {
+        *byte = b' ';
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    #[test]
+    fn accepts_comments_and_trailing_commas() {
+        let contents = r#"{
+            // Pretending breaks click-to-focus on this machine.
+            "claude": {"pretend": false, "sound": true,},
+            /* Both of them,
+               in order. */
+            "backends": ["desktop", "tmux",],
+            "url": "http://example.com/a//b /* not a comment */",
+        }"#;
+
+        let value: Value = from_str(contents).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "claude": {"pretend": false, "sound": true},
+                "backends": ["desktop", "tmux"],
+                "url": "http://example.com/a//b /* not a comment */",
+            })
+        );
+        assert!(is_lenient(contents));
+        assert_eq!(to_strict(contents).len(), contents.len());
+    }
+
+    #[test]
+    fn strict_json_is_left_alone() {
+        let contents = r#"{"escaped": "a \"//\" b,]", "list": [1, 2]}"#;
+        assert!(matches!(to_strict(contents), Cow::Borrowed(_)));
+        assert!(!is_lenient(contents));
+    }
+
+    #[test]
+    fn errors_point_into_the_file_as_written() {
+        let contents =
+            "{\n  // A comment with ünïcode.\n  \"a\": 1, /* and another */\n  \"b\": [1 2],\n}";
+        let error = from_str::<Value>(contents).unwrap_err();
+        assert_eq!((error.line(), error.column()), (4, 11));
+    }
+
+    #[test]
+    fn unterminated_block_comments_are_syntax_errors() {
+        let error = from_str::<Value>("{\"a\": 1 /* never closed }").unwrap_err();
+        assert!(!error.is_eof(), "{error}");
+        assert_eq!((error.line(), error.column()), (1, 9));
+    }
+}
@@ -0,0 +1,303 @@
+//! Machine-local overrides: an optional `a-notifications.local.json` next to the config,
+//! deep-merged over it at load time. Settings that shouldn't be synced with dotfiles, such as
+//! webhook URLs or bundle ids, go there.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error, anyhow, bail};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use tracing::debug;
+
+use crate::{
+    configuration::{Config, format::ConfigFormat},
+    storage::{with_lock, write_atomic},
+};
+
+/// The local overrides file for the config at `config_path`, in the same format:
+/// `a-notifications.local.json` for `a-notifications.json`, `a-notifications.local.toml` for
+/// `a-notifications.toml`.
+pub fn local_path(config_path: &Path) -> PathBuf {
+    let format = ConfigFormat::of(config_path);
+    config_path.with_extension(format!("local.{}", format.extension()))
+}
+
+/// The local overrides for `config_path`, if the file exists.
+pub fn read_local(config_path: &Path) -> Result<Option<Value>, Error> {
+    let path = local_path(config_path);
+    match fs::read_to_string(&path) {
+        Ok(contents) => ConfigFormat::of(&path)
+            .parse(&contents)
+            .map(Some)
+            .with_context(|| format!("Invalid local config {}", path.display())),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// `config` with the local overrides for `config_path` merged over it: objects are merged key
+/// by key and everything else, arrays included, is replaced.
+pub fn apply_local(config: Config, config_path: &Path) -> Result<Config, Error> {
+    let Some(local) = read_local(config_path)? else {
+        return Ok(config);
+    };
+    if !local.is_object() {
+        bail!(
+            "Invalid local config {}: expected a JSON object",
+            local_path(config_path).display()
+        );
+    }
+
+    debug!(path = %local_path(config_path).display(), "applying local config overrides");
+    config.with_overlay(local).map_err(|e| {
+        anyhow!(
+            "Invalid local config {}: {e}",
+            local_path(config_path).display()
+        )
+    })
+}
+
+/// Where a setting shown by `anot config show --origins` comes from.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    Default,
+    Config,
+    Local,
+}
+
+/// One setting of the loaded config and the file that set it.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct Origin {
+    /// Dotted path, e.g. `claude.sound`.
+    pub key: String,
+    pub value: Value,
+    pub source: Source,
+}
+
+/// Every setting of `merged`, keyed by its dotted path, with the file it came from: the local
+/// overrides win over the config file, which wins over the defaults.
+pub fn origins(merged: &Value, config: &Value, local: Option<&Value>) -> Vec<Origin> {
+    let mut leaves = Vec::new();
+    flatten(merged, &mut Vec::new(), &mut leaves);
+
+    leaves
+        .into_iter()
+        .map(|(path, value)| {
+            let source = if local.is_some_and(|local| lookup(local, &path).is_some()) {
+                Source::Local
+            } else if lookup(config, &path).is_some() {
+                Source::Config
+            } else {
+                Source::Default
+            };
+            Origin {
+                key: path.join("."),
+                value: value.clone(),
+                source,
+            }
+        })
+        .collect()
+}
+
+/// One line of `anot config show --origins`, e.g. `claude.sound = false  (local)`.
+pub fn format_origin(origin: &Origin) -> String {
+    let source = match origin.source {
+        Source::Default => "default",
+        Source::Config => "config",
+        Source::Local => "local",
+    };
+    format!("{} = {}  ({source})", origin.key, origin.value)
+}
+
+/// Collects the non-object values under `value` with their paths. Empty objects are kept as
+/// values so they still show up.
+fn flatten<'a>(
+    value: &'a Value,
+    path: &mut Vec<String>,
+    leaves: &mut Vec<(Vec<String>, &'a Value)>,
+) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                path.push(key.clone());
+                flatten(child, path, leaves);
+                path.pop();
+            }
+        }
+        _ => leaves.push((path.clone(), value)),
+    }
+}
+
+fn lookup<'a>(value: &'a Value, path: &[String]) -> Option<&'a Value> {
+    path.iter().try_fold(value, |value, key| value.get(key))
+}
+
+/// Sets the dotted `key` to `value` in the config file at `path`, creating the file and any
+/// missing tables. The value is parsed as JSON, or taken as a string when it isn't JSON. The
+/// change is only written when the result is still a valid config.
+pub fn set_value(path: &Path, key: &str, value: &str) -> Result<(), Error> {
+    let keys: Vec<&str> = key.split('.').collect();
+    if keys.iter().any(|key| key.is_empty()) {
+        bail!("Invalid key `{key}`: use a dotted path such as `claude.sound`");
+    }
+    let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+    let format = ConfigFormat::of(path);
+
+    with_lock(path, || {
+        let mut contents = match fs::read_to_string(path) {
+            Ok(contents) => format.parse(&contents)?,
+            Err(e) if e.kind() == ErrorKind::NotFound => Value::Object(Map::new()),
+            Err(e) => return Err(e.into()),
+        };
+        insert(&mut contents, &keys, value)
+            .map_err(|e| anyhow!("Can't set `{key}` in {}: {e}", path.display()))?;
+        Config::default()
+            .with_overlay(contents.clone())
+            .map_err(|e| anyhow!("Invalid value for `{key}`: {e}"))?;
+
+        write_atomic(path, format.to_string_pretty(&contents)?.as_bytes())
+    })
+}
+
+/// Sets `value` at the path `keys` in `target`, creating missing tables.
+pub(crate) fn insert(target: &mut Value, keys: &[&str], value: Value) -> Result<(), Error> {
+    let Value::Object(map) = target else {
+        bail!("not a table");
+    };
+    match keys {
+        [key] => {
+            map.insert(key.to_string(), value);
+            Ok(())
+        }
+        [key, rest @ ..] => {
+            let child = map
+                .entry(key.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            insert(child, rest, value).map_err(|e| anyhow!("`{key}` is {e}"))
+        }
+        [] => unreachable!("keys are never empty"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::{configuration::Backend, test_support};
+
+    fn temp_config_path(test_name: &str) -> PathBuf {
+        test_support::temp_dir("local", test_name).join("a-notifications.json")
+    }
+
+    #[test]
+    fn local_file_sits_next_to_the_config() {
+        assert_eq!(
+            local_path(Path::new(
+                "/home/me/.config/agent_notifications/a-notifications.json"
+            )),
+            Path::new("/home/me/.config/agent_notifications/a-notifications.local.json")
+        );
+    }
+
+    #[test]
+    fn local_values_are_merged_over_the_config() {
+        let path = temp_config_path("merge");
+        let config: Config = serde_json::from_value(json!({
+            "version": 1,
+            "backends": ["desktop", "tmux"],
+            "claude": {"pretend": "never", "sound": false, "stop_summary": true},
+            "codex": {"pretend": "never", "sound": false},
+            "dedupe_window_secs": 30
+        }))
+        .unwrap();
+        fs::write(
+            local_path(&path),
+            r#"{"backends": ["statusfile"], "claude": {"sound": true}}"#,
+        )
+        .unwrap();
+
+        let merged = apply_local(config, &path).unwrap();
+        assert_eq!(
+            merged.backends,
+            [Backend::Statusfile],
+            "arrays are replaced"
+        );
+        assert!(merged.claude.sound, "local values win");
+        assert!(merged.claude.stop_summary, "tables are merged");
+        assert_eq!(merged.dedupe_window_secs, 30);
+    }
+
+    #[test]
+    fn missing_local_file_changes_nothing() {
+        let path = temp_config_path("missing");
+        let merged = apply_local(Config::default(), &path).unwrap();
+        assert_eq!(
+            serde_json::to_value(merged).unwrap(),
+            serde_json::to_value(Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_local_file_is_an_error() {
+        let path = temp_config_path("invalid");
+        fs::write(local_path(&path), r#"{"backends": "desktop"}"#).unwrap();
+
+        let error = apply_local(Config::default(), &path).unwrap_err();
+        assert!(error.to_string().contains("a-notifications.local.json"));
+    }
+
+    #[test]
+    fn origins_name_the_file_that_set_each_key() {
+        let config = json!({"claude": {"sound": false}, "dedupe_window_secs": 30});
+        let local = json!({"claude": {"sound": true}});
+        let merged = json!({
+            "claude": {"sound": true, "stop_summary": false},
+            "dedupe_window_secs": 30
+        });
+
+        let origins = origins(&merged, &config, Some(&local));
+        let lines: Vec<String> = origins.iter().map(format_origin).collect();
+        assert_eq!(
+            lines,
+            [
+                "claude.sound = true  (local)",
+                "claude.stop_summary = false  (default)",
+                "dedupe_window_secs = 30  (config)",
+            ]
+        );
+    }
+
+    #[test]
+    fn sets_dotted_keys() {
+        let path = local_path(&temp_config_path("set"));
+        set_value(&path, "claude.sound", "false").unwrap();
+        set_value(&path, "claude.icon", "/icons/work.png").unwrap();
+        set_value(&path, "backends", r#"["tmux"]"#).unwrap();
+
+        let contents: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(
+            contents,
+            json!({
+                "claude": {"sound": false, "icon": "/icons/work.png"},
+                "backends": ["tmux"]
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_not_written() {
+        let path = local_path(&temp_config_path("set-invalid"));
+        set_value(&path, "claude.sound", "false").unwrap();
+
+        assert!(set_value(&path, "claude.sound", "loud").is_err());
+        assert!(set_value(&path, "claude.sound.level", "1").is_err());
+        let contents: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(contents, json!({"claude": {"sound": false}}));
+    }
+}
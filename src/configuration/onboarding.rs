@@ -0,0 +1,265 @@
+//! The hint shown once after the default config is created on first run: where the config
+//! lives, which agents look installed, and the `anot init` commands that hook them up.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use tracing::{info, warn};
+
+use crate::{
+    configuration::{Config, wizard::Agent},
+    console::{self, Mark},
+    processors::{
+        claude::paths::{self, ClaudeEnv},
+        codex,
+    },
+    storage::{read_json_or_default, write_atomic},
+    utils::{expand_tilde_in, unix_now},
+};
+
+const ONBOARDING_FILE_NAME: &str = "onboarding.json";
+
+/// Whether the config anot created has been explained yet. Configs from before this was
+/// tracked have neither, and never get the hint.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OnboardingState {
+    pub created_at: Option<u64>,
+    pub shown_at: Option<u64>,
+}
+
+/// What the command being run allows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// An agent hook: stdout is the agent's, so the hint is only logged.
+    Hook,
+    /// `anot init`, which is already the next step.
+    Setup,
+    /// Anything else the user typed.
+    Interactive,
+}
+
+/// What to do with the hint.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Print,
+    Log,
+    /// Marks it shown without printing.
+    Dismiss,
+    Nothing,
+}
+
+/// The action for `state`, where `created` says whether this run created the config. A hook
+/// logs it when it created the config, and leaves the printing to the next command typed.
+pub fn decide(state: &OnboardingState, created: bool, mode: Mode) -> Action {
+    if state.created_at.is_none() || state.shown_at.is_some() {
+        return Action::Nothing;
+    }
+    match mode {
+        Mode::Hook if created => Action::Log,
+        Mode::Hook => Action::Nothing,
+        Mode::Setup => Action::Dismiss,
+        Mode::Interactive => Action::Print,
+    }
+}
+
+fn onboarding_path(config: &Config) -> PathBuf {
+    config.data_dir().join(ONBOARDING_FILE_NAME)
+}
+
+/// Shows the hint when it's due, after loading `config`. `created` is whether this run created
+/// the config file. Failures are logged, so they never stop the command.
+pub fn run(config: &Config, created: bool, mode: Mode) {
+    if let Err(e) = run_with(config, created, mode, installed_agents) {
+        warn!(error = %format_args!("{e:#}"), "failed to update the onboarding state");
+    }
+}
+
+/// `agents` finds the installed agents, only when the hint is shown.
+fn run_with(
+    config: &Config,
+    created: bool,
+    mode: Mode,
+    agents: impl FnOnce() -> Vec<Agent>,
+) -> Result<(), Error> {
+    // Hooks only ever log the hint when they created the config, so they skip the read.
+    if mode == Mode::Hook && !created {
+        return Ok(());
+    }
+    let path = onboarding_path(config);
+    let mut state: OnboardingState = if created {
+        OnboardingState {
+            created_at: Some(unix_now()),
+            shown_at: None,
+        }
+    } else {
+        read_json_or_default(&path)?
+    };
+
+    let action = decide(&state, created, mode);
+    let config_path = config.location.as_deref().unwrap_or(Path::new(""));
+    match action {
+        Action::Print => print(config_path, &agents()),
+        Action::Log => {
+            let agents = agents();
+            info!(
+                config = %config_path.display(),
+                agents = ?agents.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                next = ?next_commands(&agents),
+                "created the default config; run the init commands to notify from the agents"
+            );
+        }
+        Action::Dismiss | Action::Nothing => {}
+    }
+    let shown = matches!(action, Action::Print | Action::Dismiss);
+    if shown {
+        state.shown_at = Some(unix_now());
+    }
+    if created || shown {
+        write_atomic(&path, serde_json::to_string(&state)?.as_bytes())?;
+    }
+    Ok(())
+}
+
+/// The `anot init` commands for `agents`, or for every agent when none was found.
+fn next_commands(agents: &[Agent]) -> Vec<&'static str> {
+    if agents.is_empty() {
+        Agent::iter().map(Agent::init_command).collect()
+    } else {
+        agents.iter().map(|agent| agent.init_command()).collect()
+    }
+}
+
+/// Printed to stderr so it never gets mixed into `--output json`.
+fn print(config_path: &Path, agents: &[Agent]) {
+    let style = console::style();
+    let mut lines = vec![console::line(
+        style,
+        Mark::Info,
+        format!("Created the anot config at {}", config_path.display()),
+    )];
+    lines.push(if agents.is_empty() {
+        console::line(
+            style,
+            Mark::Info,
+            "No agent found on this machine. Once one is installed, run its init command:",
+        )
+    } else {
+        let names: Vec<String> = agents.iter().map(ToString::to_string).collect();
+        console::line(
+            style,
+            Mark::Info,
+            format!("Found {}. Hook them up with:", names.join(", ")),
+        )
+    });
+    for command in next_commands(agents) {
+        lines.push(console::line(style, Mark::Item, command));
+    }
+    eprintln!("{}", lines.join("\n"));
+}
+
+/// The agents whose config directory exists on this machine.
+pub fn installed_agents() -> Vec<Agent> {
+    installed_agents_in(
+        &ClaudeEnv::current(),
+        std::env::var("CODEX_HOME").ok().as_deref(),
+        Path::exists,
+    )
+}
+
+fn installed_agents_in(
+    claude: &ClaudeEnv,
+    codex_home: Option<&str>,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<Agent> {
+    let home = Some(claude.home.as_path());
+    Agent::iter()
+        .filter(|agent| match agent {
+            Agent::Claude => paths::candidates(claude, &exists)
+                .iter()
+                .any(|candidate| candidate.exists),
+            Agent::Codex => codex::init::default_config_paths(codex_home, home)
+                .iter()
+                .filter_map(|path| path.parent())
+                .any(&exists),
+            Agent::Opencode => exists(&expand_tilde_in(Path::new("~/.config/opencode"), home)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_path;
+
+    const CREATED: OnboardingState = OnboardingState {
+        created_at: Some(100),
+        shown_at: None,
+    };
+
+    #[test]
+    fn shows_once_after_the_first_run() {
+        assert_eq!(decide(&CREATED, true, Mode::Interactive), Action::Print);
+        assert_eq!(decide(&CREATED, false, Mode::Interactive), Action::Print);
+        assert_eq!(decide(&CREATED, false, Mode::Setup), Action::Dismiss);
+
+        let shown = OnboardingState {
+            shown_at: Some(200),
+            ..CREATED
+        };
+        assert_eq!(decide(&shown, false, Mode::Interactive), Action::Nothing);
+        // A config that was there before anot tracked this.
+        assert_eq!(
+            decide(&OnboardingState::default(), false, Mode::Interactive),
+            Action::Nothing
+        );
+    }
+
+    #[test]
+    fn hooks_log_instead_of_printing() {
+        assert_eq!(decide(&CREATED, true, Mode::Hook), Action::Log);
+        assert_eq!(decide(&CREATED, false, Mode::Hook), Action::Nothing);
+    }
+
+    #[test]
+    fn the_hint_waits_for_a_command_after_a_hook_created_the_config() {
+        let dir = temp_path("onboarding", "hint");
+        let config = Config {
+            location: Some(dir.join("a-notifications.json")),
+            ..Config::default()
+        };
+        let state = || read_json_or_default::<OnboardingState>(&onboarding_path(&config)).unwrap();
+
+        run_with(&config, true, Mode::Hook, Vec::new).unwrap();
+        assert!(state().created_at.is_some() && state().shown_at.is_none());
+        run_with(&config, false, Mode::Hook, Vec::new).unwrap();
+        assert!(state().shown_at.is_none());
+
+        run_with(&config, false, Mode::Interactive, || vec![Agent::Codex]).unwrap();
+        let shown = state().shown_at;
+        assert!(shown.is_some());
+        run_with(&config, false, Mode::Interactive, || vec![Agent::Codex]).unwrap();
+        assert_eq!(state().shown_at, shown);
+    }
+
+    #[test]
+    fn finds_agents_by_their_config_directories() {
+        let claude = ClaudeEnv {
+            home: PathBuf::from("/home/me"),
+            ..ClaudeEnv::default()
+        };
+        let found = |existing: &[&str]| {
+            installed_agents_in(&claude, None, |dir| {
+                existing.iter().any(|existing| dir == Path::new(existing))
+            })
+        };
+
+        assert_eq!(found(&[]), []);
+        assert_eq!(found(&["/home/me/.claude"]), [Agent::Claude]);
+        assert_eq!(
+            found(&["/home/me/.codex", "/home/me/.config/opencode"]),
+            [Agent::Codex, Agent::Opencode]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+//! Named configuration profiles: partial configs under `profiles` merged over the base config
+//! when selected with `--profile`, `ANOT_PROFILE` or the persisted `active_profile`.
+
+use std::{env, fs, path::Path};
+
+use anyhow::{Error, anyhow, bail};
+use serde_json::Value;
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{Config, format::ConfigFormat},
+    storage::{with_lock, write_atomic},
+};
+
+/// Environment variable selecting a profile when `--profile` isn't given.
+pub const PROFILE_ENV: &str = "ANOT_PROFILE";
+
+/// Keys that only make sense in the base config; a profile setting them is ignored.
+const BASE_ONLY_KEYS: [&str; 2] = ["profiles", "active_profile"];
+
+/// The profile to use: `--profile` wins over `ANOT_PROFILE`, which wins over `active_profile`.
+pub fn selected_profile(
+    flag: Option<&str>,
+    env_value: Option<&str>,
+    persisted: Option<&str>,
+) -> Option<String> {
+    [flag, env_value, persisted]
+        .into_iter()
+        .flatten()
+        .find(|name| !name.is_empty())
+        .map(String::from)
+}
+
+/// `config` with the selected profile merged over it, or unchanged when none is selected.
+pub fn apply_selected_profile(config: &Config, flag: Option<&str>) -> Result<Config, Error> {
+    let env_value = env::var(PROFILE_ENV).ok();
+    match selected_profile(flag, env_value.as_deref(), config.active_profile.as_deref()) {
+        Some(name) => apply_profile(config, &name),
+        None => Ok(config.clone()),
+    }
+}
+
+/// `config` with the profile `name` deep-merged over it, so a profile only needs the settings
+/// it changes.
+pub fn apply_profile(config: &Config, name: &str) -> Result<Config, Error> {
+    let mut overlay = profile(config, name)?.clone();
+    let Value::Object(map) = &mut overlay else {
+        bail!("Profile `{name}` must be a table of settings");
+    };
+    for key in BASE_ONLY_KEYS {
+        if map.remove(key).is_some() {
+            warn!(profile = name, key, "ignoring key that profiles can't set");
+        }
+    }
+
+    let mut merged = config
+        .with_overlay(overlay)
+        .map_err(|e| anyhow!("Invalid profile `{name}`: {e}"))?;
+    merged.profile = Some(name.to_string());
+    debug!(profile = name, "applied configuration profile");
+    Ok(merged)
+}
+
+/// The settings of the profile `name`. Unknown names are an error listing the defined ones.
+pub fn profile<'a>(config: &'a Config, name: &str) -> Result<&'a Value, Error> {
+    config.profiles.get(name).ok_or_else(|| {
+        if config.profiles.is_empty() {
+            anyhow!("Unknown profile `{name}`: no profiles are defined in the config")
+        } else {
+            anyhow!(
+                "Unknown profile `{name}`. Available profiles: {}",
+                config
+                    .profiles
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )
+        }
+    })
+}
+
+/// Persists `name` as `active_profile` in the config file at `path`. Other settings are kept
+/// as they are.
+pub fn use_profile(config: &Config, path: &Path, name: &str) -> Result<(), Error> {
+    profile(config, name)?;
+
+    with_lock(path, || {
+        let format = ConfigFormat::of(path);
+        let mut contents: Value = format.parse(&fs::read_to_string(path)?)?;
+        let Value::Object(map) = &mut contents else {
+            bail!("{} is not a JSON object", path.display());
+        };
+        map.insert(
+            "active_profile".to_string(),
+            Value::String(name.to_string()),
+        );
+
+        write_atomic(path, format.to_string_pretty(&contents)?.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::configuration::PostToolUseMode;
+
+    fn config_with_profiles() -> Config {
+        serde_json::from_value(json!({
+            "version": 1,
+            "claude": {"pretend": "never", "stop_summary": true},
+            "codex": {"pretend": "never", "sound": false},
+            "dedupe_window_secs": 30,
+            "profiles": {
+                "focus": {"claude": {"post_tool_use": "off", "sound": false}},
+                "verbose": {"dedupe_window_secs": 0}
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn flag_wins_over_env_which_wins_over_persisted() {
+        assert_eq!(
+            selected_profile(Some("focus"), Some("verbose"), Some("quiet")).as_deref(),
+            Some("focus")
+        );
+        assert_eq!(
+            selected_profile(None, Some("verbose"), Some("quiet")).as_deref(),
+            Some("verbose")
+        );
+        assert_eq!(
+            selected_profile(None, Some(""), Some("quiet")).as_deref(),
+            Some("quiet")
+        );
+        assert_eq!(selected_profile(None, None, None), None);
+    }
+
+    #[test]
+    fn profiles_are_deep_merged() {
+        let base = Config {
+            location: Some(PathBuf::from("/tmp/a-notifications.json")),
+            ..config_with_profiles()
+        };
+
+        let focus = apply_profile(&base, "focus").unwrap();
+        assert_eq!(focus.claude.post_tool_use, PostToolUseMode::Off);
+        assert!(!focus.claude.sound);
+        assert!(focus.claude.stop_summary, "unset settings keep base values");
+        assert_eq!(focus.dedupe_window_secs, 30);
+        assert_eq!(focus.profile.as_deref(), Some("focus"));
+        assert_eq!(focus.location, base.location);
+
+        let verbose = apply_profile(&base, "verbose").unwrap();
+        assert_eq!(verbose.dedupe_window_secs, 0);
+        assert_eq!(verbose.claude.post_tool_use, PostToolUseMode::All);
+    }
+
+    #[test]
+    fn unknown_profiles_list_the_available_ones() {
+        let error = apply_profile(&config_with_profiles(), "quiet").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Unknown profile `quiet`. Available profiles: focus, verbose"
+        );
+
+        let error = apply_profile(&Config::default(), "quiet").unwrap_err();
+        assert!(error.to_string().contains("no profiles are defined"));
+    }
+}
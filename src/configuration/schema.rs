@@ -0,0 +1,207 @@
+//! `anot config schema`: the JSON Schema of the config, derived from the same structs it's
+//! loaded into, so editors can check a hand-edited file as it's typed.
+
+use std::{borrow::Cow, fs, path::Path};
+
+use anyhow::Error;
+use schemars::{JsonSchema, Schema, SchemaGenerator, json_schema};
+use serde_json::Value;
+
+use crate::configuration::{
+    Config, claude_event_names, codex_event_names, create_default_config, local,
+};
+
+/// Where the schema of the latest release is published. `$schema` points here unless
+/// `anot config schema --out` wrote a copy.
+pub const PUBLISHED_URL: &str = "https://raw.githubusercontent.com/Nat1anWasTaken/agent-notifications/main/a-notifications.schema.json";
+
+/// The JSON Schema of the config file.
+pub fn config_schema() -> Value {
+    schemars::schema_for!(Config).to_value()
+}
+
+/// `path` as a `file://` URL, which editors accept for `$schema`.
+pub fn file_url(path: &Path) -> Result<String, Error> {
+    let path = std::path::absolute(path)?
+        .to_string_lossy()
+        .replace('\\', "/")
+        .replace(' ', "%20");
+    // Windows paths start with the drive letter.
+    let separator = if path.starts_with('/') { "" } else { "/" };
+    Ok(format!("file://{separator}{path}"))
+}
+
+/// Points the `$schema` of the config at `config_path` to `url`, creating the default config
+/// with it when there's none yet.
+pub fn link(config_path: &Path, url: &str) -> Result<(), Error> {
+    if fs::exists(config_path)? {
+        local::set_value(config_path, "$schema", &serde_json::to_string(url)?)
+    } else {
+        create_default_config(config_path, Some(url))
+    }
+}
+
+/// A map keyed by Claude hook event names.
+pub(super) fn claude_event_map<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    event_map(generator.subschema_for::<T>(), &claude_event_names())
+}
+
+/// A map keyed by Codex notification types.
+pub(super) fn codex_event_map<T: JsonSchema>(generator: &mut SchemaGenerator) -> Schema {
+    event_map(generator.subschema_for::<T>(), &codex_event_names())
+}
+
+/// `claude.sample`: rates of 1 or more, keyed by Claude hook event names.
+pub(super) fn claude_sample(_: &mut SchemaGenerator) -> Schema {
+    event_map(
+        json_schema!({"type": "integer", "minimum": 1}),
+        &claude_event_names(),
+    )
+}
+
+/// `claude.events`: Claude hook event names.
+pub(super) fn claude_events(_: &mut SchemaGenerator) -> Schema {
+    json_schema!({
+        "type": "array",
+        "items": {"enum": claude_event_names()},
+    })
+}
+
+fn event_map(value: Schema, events: &[String]) -> Schema {
+    json_schema!({
+        "type": "object",
+        "propertyNames": {"enum": events},
+        "additionalProperties": value,
+    })
+}
+
+impl JsonSchema for super::Pretend {
+    fn schema_name() -> Cow<'static, str> {
+        "Pretend".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "Whether notifications use the agent's app identity on macOS: \
+                `auto`, `always` or `never`. `true` and `false` mean `always` and `never`.",
+            "anyOf": [
+                {"type": "string", "enum": ["auto", "always", "never"]},
+                {"type": "boolean"},
+            ],
+        })
+    }
+}
+
+impl JsonSchema for super::ClockTime {
+    fn schema_name() -> Cow<'static, str> {
+        "ClockTime".into()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        json_schema!({
+            "description": "A time of day, `HH:MM`",
+            "type": "string",
+            "pattern": "^\\s*([01]?[0-9]|2[0-3]):[0-5]?[0-9]\\s*$",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use jsonschema::Validator;
+    use serde_json::json;
+
+    use super::*;
+    use crate::{configuration::format::ConfigFormat, test_support::temp_path};
+
+    fn validator() -> Validator {
+        jsonschema::validator_for(&config_schema()).unwrap()
+    }
+
+    #[test]
+    fn the_default_config_is_valid() {
+        let validator = validator();
+        let config = serde_json::to_value(Config::default()).unwrap();
+        let errors: Vec<_> = validator
+            .iter_errors(&config)
+            .map(|e| e.to_string())
+            .collect();
+        assert!(errors.is_empty(), "{errors:?}");
+
+        let written = ConfigFormat::Json
+            .to_string(&Config {
+                schema: Some(PUBLISHED_URL.to_string()),
+                ..Config::default()
+            })
+            .unwrap();
+        assert!(validator.is_valid(&serde_json::from_str(&written).unwrap()));
+    }
+
+    #[test]
+    fn enumerated_settings_list_their_values() {
+        let validator = validator();
+        let with = |patch: Value| {
+            let mut config = serde_json::to_value(Config::default()).unwrap();
+            crate::configuration::merge(&mut config, patch);
+            validator.is_valid(&config)
+        };
+
+        assert!(with(json!({"claude": {"post_tool_use": "failures"}})));
+        assert!(!with(json!({"claude": {"post_tool_use": "sometimes"}})));
+        assert!(with(json!({"claude": {"pretend": false}})));
+        assert!(!with(json!({"claude": {"pretend": "maybe"}})));
+        assert!(with(
+            json!({"claude": {"overrides": {"Stop": {"sound": false}}}})
+        ));
+        assert!(!with(
+            json!({"claude": {"overrides": {"Stopp": {"sound": false}}}})
+        ));
+        assert!(!with(json!({"claude": {"events": ["Stop", "Finish"]}})));
+        assert!(!with(json!({"codex": {"titles": {"Stop": "done"}}})));
+        assert!(!with(json!({"backends": ["desktop", "pager"]})));
+        assert!(with(
+            json!({"quiet_hours": {"start": "22:30", "end": "7:00"}})
+        ));
+        assert!(!with(
+            json!({"quiet_hours": {"start": "25:00", "end": "07:00"}})
+        ));
+        assert!(!with(json!({"claude": {"sound_volume": 2.0}})));
+    }
+
+    #[test]
+    fn the_published_schema_is_up_to_date() {
+        let published: Value =
+            serde_json::from_str(include_str!("../../a-notifications.schema.json")).unwrap();
+        assert!(
+            published == config_schema(),
+            "regenerate it with `anot config schema --out a-notifications.schema.json`"
+        );
+    }
+
+    #[test]
+    fn link_creates_or_updates_the_config() {
+        let dir = temp_path("schema", "link");
+        let path = dir.join("a-notifications.json");
+        let schema_of = |path: &Path| {
+            let contents: Value = serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap();
+            contents["$schema"].clone()
+        };
+
+        link(&path, PUBLISHED_URL).unwrap();
+        assert_eq!(schema_of(&path), PUBLISHED_URL);
+
+        let url = file_url(&dir.join("my schema.json")).unwrap();
+        assert!(
+            url.starts_with("file:///") && url.ends_with("/my%20schema.json"),
+            "{url}"
+        );
+        link(&path, &url).unwrap();
+        assert_eq!(schema_of(&path), url.as_str());
+        assert_eq!(
+            crate::configuration::initialize_configuration(&path)
+                .unwrap()
+                .schema,
+            Some(url)
+        );
+    }
+}
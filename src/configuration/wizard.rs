@@ -0,0 +1,447 @@
+//! `anot init config`: walks through the main settings of the config with prompts, starting
+//! from what the config already says, and writes the result.
+
+use std::{fmt, fs, io::ErrorKind, path::Path};
+
+use anyhow::Error;
+use inquire::{Confirm, MultiSelect, Select, Text, validator::Validation};
+use strum::{EnumIter, IntoEnumIterator};
+use tracing::info;
+
+use crate::{
+    configuration::{ClockTime, Config, Pretend, QuietHours, format::ConfigFormat},
+    console::{self, Mark, Style},
+    processors::{
+        claude::{init::prompt_event_selection, structs::HookEventName},
+        common::init::handle_inquire_error,
+    },
+    storage::write_atomic,
+};
+
+/// Quiet hours offered when the config has none.
+const DEFAULT_QUIET_START: ClockTime = ClockTime(22 * 60);
+const DEFAULT_QUIET_END: ClockTime = ClockTime(8 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, EnumIter)]
+pub enum Agent {
+    Claude,
+    Codex,
+    Opencode,
+}
+
+impl Agent {
+    /// The `anot init` subcommand that installs the agent's hook.
+    pub fn init_command(self) -> &'static str {
+        match self {
+            Agent::Claude => "anot init claude",
+            Agent::Codex => "anot init codex",
+            Agent::Opencode => "anot init opencode",
+        }
+    }
+}
+
+impl fmt::Display for Agent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Agent::Claude => write!(f, "Claude Code"),
+            Agent::Codex => write!(f, "Codex"),
+            Agent::Opencode => write!(f, "OpenCode"),
+        }
+    }
+}
+
+/// What the user chose at each prompt.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WizardAnswers {
+    pub agents: Vec<Agent>,
+    /// Only asked on macOS; `None` keeps each agent's setting.
+    pub pretend: Option<Pretend>,
+    pub sound: bool,
+    /// The Claude hook events that notify. Empty or all of them notifies for every event.
+    pub claude_events: Vec<String>,
+    pub quiet_hours: Option<QuietHours>,
+}
+
+impl WizardAnswers {
+    /// The answers `config` already implies, offered as the defaults when re-running.
+    pub fn from_config(config: &Config) -> Self {
+        WizardAnswers {
+            agents: Agent::iter().collect(),
+            pretend: Some(config.claude.pretend),
+            sound: config.claude.sound,
+            claude_events: config.claude.events.clone(),
+            quiet_hours: config.quiet_hours,
+        }
+    }
+}
+
+/// `config` with `answers` applied to the sections of the chosen agents. Everything the
+/// wizard doesn't ask about is kept.
+pub fn apply_answers(mut config: Config, answers: &WizardAnswers) -> Config {
+    for agent in &answers.agents {
+        match agent {
+            Agent::Claude => {
+                config.claude.sound = answers.sound;
+                if let Some(pretend) = answers.pretend {
+                    config.claude.pretend = pretend;
+                }
+                let every_event = HookEventName::iter()
+                    .all(|event| answers.claude_events.iter().any(|e| e == event.as_str()));
+                config.claude.events = if every_event {
+                    Vec::new()
+                } else {
+                    answers.claude_events.clone()
+                };
+            }
+            Agent::Codex => {
+                config.codex.sound = answers.sound;
+                if let Some(pretend) = answers.pretend {
+                    config.codex.pretend = pretend;
+                }
+            }
+            Agent::Opencode => {
+                config.opencode.sound = answers.sound;
+                if let Some(pretend) = answers.pretend {
+                    config.opencode.pretend = pretend != Pretend::Never;
+                }
+            }
+        }
+    }
+    config.quiet_hours = answers.quiet_hours;
+    config
+}
+
+/// One line per answer, printed once the config is written.
+pub fn summary(answers: &WizardAnswers) -> Vec<String> {
+    let agents = answers
+        .agents
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut lines = vec![format!(
+        "Agents: {}",
+        if agents.is_empty() { "none" } else { &agents }
+    )];
+    if let Some(pretend) = answers.pretend {
+        lines.push(format!("Pretend: {}", PretendChoice(pretend).name()));
+    }
+    lines.push(format!(
+        "Sound: {}",
+        if answers.sound { "on" } else { "off" }
+    ));
+    if answers.agents.contains(&Agent::Claude) {
+        let events = if answers.claude_events.is_empty()
+            || answers.claude_events.len() == HookEventName::iter().count()
+        {
+            "all".to_string()
+        } else {
+            answers.claude_events.join(", ")
+        };
+        lines.push(format!("Claude events: {events}"));
+    }
+    lines.push(match answers.quiet_hours {
+        Some(quiet) => format!("Quiet hours: {}–{}", quiet.start, quiet.end),
+        None => "Quiet hours: off".to_string(),
+    });
+    lines
+}
+
+#[derive(Clone, Copy)]
+struct PretendChoice(Pretend);
+
+impl PretendChoice {
+    fn name(self) -> &'static str {
+        match self.0 {
+            Pretend::Auto => "auto",
+            Pretend::Always => "always",
+            Pretend::Never => "never",
+        }
+    }
+}
+
+impl fmt::Display for PretendChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let description = match self.0 {
+            Pretend::Auto => "as the agent's app when it's installed, Terminal otherwise",
+            Pretend::Always => "always as the agent's app",
+            Pretend::Never => "always as Terminal",
+        };
+        write!(f, "{} — {description}", self.name())
+    }
+}
+
+/// Asks the wizard's questions, writes the answers into the config at `path` (created when
+/// missing) and prints a summary.
+pub fn run(path: &Path) -> Result<(), Error> {
+    let format = ConfigFormat::of(path);
+    let config: Config = match fs::read_to_string(path) {
+        Ok(contents) if !contents.trim().is_empty() => format.parse(&contents)?,
+        Ok(_) => Config::default(),
+        Err(e) if e.kind() == ErrorKind::NotFound => Config::default(),
+        Err(e) => return Err(e.into()),
+    };
+
+    let answers = ask(&WizardAnswers::from_config(&config))?;
+    let config = apply_answers(config, &answers);
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, format.to_string_pretty(&config)?.as_bytes())?;
+    info!(path = %path.display(), ?answers, "wrote configuration from the wizard");
+
+    for line in report(console::style(), path, &answers) {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+/// What `run` prints once the config is written.
+fn report(style: Style, path: &Path, answers: &WizardAnswers) -> Vec<String> {
+    let mut lines = vec![console::line(
+        style,
+        Mark::Ok,
+        format!("Configuration written to: {}", path.display()),
+    )];
+    lines.extend(
+        summary(answers)
+            .into_iter()
+            .map(|line| console::line(style, Mark::Item, line)),
+    );
+    if !answers.agents.is_empty() {
+        lines.push(String::new());
+        lines.push("Next, install the hooks:".to_string());
+        lines.extend(
+            answers
+                .agents
+                .iter()
+                .map(|agent| format!("  {}", agent.init_command())),
+        );
+    }
+    lines
+}
+
+fn ask(current: &WizardAnswers) -> Result<WizardAnswers, Error> {
+    let all_agents: Vec<Agent> = Agent::iter().collect();
+    let agent_defaults: Vec<usize> = all_agents
+        .iter()
+        .enumerate()
+        .filter(|(_, agent)| current.agents.contains(agent))
+        .map(|(index, _)| index)
+        .collect();
+    let agents = MultiSelect::new("Which agents do you use?", all_agents)
+        .with_help_message("Use space to select/deselect, enter to confirm")
+        .with_default(&agent_defaults)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for agents"))?;
+
+    let pretend = if cfg!(target_os = "macos") {
+        let options: Vec<PretendChoice> = [Pretend::Auto, Pretend::Always, Pretend::Never]
+            .into_iter()
+            .map(PretendChoice)
+            .collect();
+        let starting = current
+            .pretend
+            .and_then(|pretend| options.iter().position(|option| option.0 == pretend))
+            .unwrap_or(0);
+        let choice = Select::new("Show notifications as coming from…", options)
+            .with_starting_cursor(starting)
+            .prompt()
+            .map_err(|err| handle_inquire_error(err, "Failed to prompt for pretend"))?;
+        Some(choice.0)
+    } else {
+        None
+    };
+
+    let sound = Confirm::new("Play a sound with notifications?")
+        .with_default(current.sound)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for sound"))?;
+
+    let claude_events = if agents.contains(&Agent::Claude) {
+        let marked: Vec<HookEventName> = HookEventName::iter()
+            .filter(|event| {
+                current.claude_events.is_empty()
+                    || current.claude_events.iter().any(|e| e == event.as_str())
+            })
+            .collect();
+        prompt_event_selection(
+            "Which Claude Code events should notify?",
+            "Use space to select/deselect, enter to confirm. [✓] = notifies now; none selected notifies for every event",
+            &marked,
+        )?
+        .iter()
+        .map(|event| event.as_str().to_string())
+        .collect()
+    } else {
+        current.claude_events.clone()
+    };
+
+    let quiet_hours = ask_quiet_hours(current.quiet_hours)?;
+
+    Ok(WizardAnswers {
+        agents,
+        pretend,
+        sound,
+        claude_events,
+        quiet_hours,
+    })
+}
+
+fn ask_quiet_hours(current: Option<QuietHours>) -> Result<Option<QuietHours>, Error> {
+    let enabled = Confirm::new("Hold notifications back during quiet hours?")
+        .with_default(current.is_some())
+        .with_help_message("Held-back notifications follow mute_mode, as with `anot mute`")
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for quiet hours"))?;
+    if !enabled {
+        return Ok(None);
+    }
+
+    let start = ask_time(
+        "Quiet hours start at (HH:MM):",
+        current.map_or(DEFAULT_QUIET_START, |quiet| quiet.start),
+    )?;
+    let end = ask_time(
+        "Quiet hours end at (HH:MM):",
+        current.map_or(DEFAULT_QUIET_END, |quiet| quiet.end),
+    )?;
+    Ok(Some(QuietHours { start, end }))
+}
+
+fn ask_time(message: &str, default: ClockTime) -> Result<ClockTime, Error> {
+    let answer = Text::new(message)
+        .with_default(&default.to_string())
+        .with_validator(|input: &str| {
+            Ok(match input.parse::<ClockTime>() {
+                Ok(_) => Validation::Valid,
+                Err(message) => Validation::Invalid(message.into()),
+            })
+        })
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for quiet hours"))?;
+    answer.parse().map_err(Error::msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn answers() -> WizardAnswers {
+        WizardAnswers {
+            agents: vec![Agent::Claude, Agent::Opencode],
+            pretend: Some(Pretend::Never),
+            sound: false,
+            claude_events: vec!["Stop".to_string(), "Notification".to_string()],
+            quiet_hours: Some(QuietHours {
+                start: ClockTime(22 * 60),
+                end: ClockTime(7 * 60 + 30),
+            }),
+        }
+    }
+
+    fn answers_quiet() -> QuietHours {
+        answers().quiet_hours.unwrap()
+    }
+
+    #[test]
+    fn answers_apply_to_the_chosen_agents_only() {
+        let config = apply_answers(Config::default(), &answers());
+
+        assert_eq!(config.claude.pretend, Pretend::Never);
+        assert!(!config.claude.sound);
+        assert_eq!(config.claude.events, ["Stop", "Notification"]);
+        assert!(!config.opencode.pretend);
+        assert!(!config.opencode.sound);
+        assert_eq!(config.codex.pretend, Config::default().codex.pretend);
+        assert!(config.codex.sound, "Codex wasn't chosen");
+        assert_eq!(
+            config.quiet_hours.map(|quiet| quiet.end.to_string()),
+            Some("07:30".to_string())
+        );
+    }
+
+    #[test]
+    fn unasked_settings_are_kept() {
+        let mut config = Config::default();
+        config.claude.pretend = Pretend::Always;
+        config.dedupe_window_secs = 99;
+        let answers = WizardAnswers {
+            pretend: None,
+            quiet_hours: None,
+            claude_events: HookEventName::iter()
+                .map(|event| event.as_str().to_string())
+                .collect(),
+            ..answers()
+        };
+        config.quiet_hours = Some(answers_quiet());
+
+        let config = apply_answers(config, &answers);
+        assert_eq!(config.claude.pretend, Pretend::Always);
+        assert_eq!(config.dedupe_window_secs, 99);
+        assert!(
+            config.claude.events.is_empty(),
+            "every event is stored as all"
+        );
+        assert_eq!(config.quiet_hours, None);
+    }
+
+    #[test]
+    fn rerunning_starts_from_the_config() {
+        let config = apply_answers(Config::default(), &answers());
+        let current = WizardAnswers::from_config(&config);
+        assert_eq!(current.pretend, Some(Pretend::Never));
+        assert!(!current.sound);
+        assert_eq!(current.claude_events, ["Stop", "Notification"]);
+        assert_eq!(current.quiet_hours, Some(answers_quiet()));
+    }
+
+    #[test]
+    fn summarizes_the_answers() {
+        assert_eq!(
+            summary(&answers()),
+            [
+                "Agents: Claude Code, OpenCode",
+                "Pretend: never",
+                "Sound: off",
+                "Claude events: Stop, Notification",
+                "Quiet hours: 22:00–07:30",
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_plain_or_fancy() {
+        let path = Path::new("/home/me/.config/a-notifications.json");
+
+        assert_eq!(
+            report(Style::Fancy, path, &answers()).join("\n"),
+            "\
+✅ Configuration written to: /home/me/.config/a-notifications.json
+  • Agents: Claude Code, OpenCode
+  • Pretend: never
+  • Sound: off
+  • Claude events: Stop, Notification
+  • Quiet hours: 22:00–07:30
+
+Next, install the hooks:
+  anot init claude
+  anot init opencode"
+        );
+        assert_eq!(
+            report(Style::Plain, path, &answers()).join("\n"),
+            "\
+[ok] Configuration written to: /home/me/.config/a-notifications.json
+  - Agents: Claude Code, OpenCode
+  - Pretend: never
+  - Sound: off
+  - Claude events: Stop, Notification
+  - Quiet hours: 22:00–07:30
+
+Next, install the hooks:
+  anot init claude
+  anot init opencode"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+//! anot's own messages on the terminal, such as the summaries `anot init` prints. They're
+//! marked with emoji and `•` bullets, or with `[ok]`-style prefixes where emoji render badly:
+//! with `--plain`, when `NO_COLOR` is set, or when stdout isn't a terminal (CI logs, pipes).
+//! Prompts are inquire's and look the same either way.
+
+use std::{env, ffi::OsStr, fmt::Display, io::IsTerminal, sync::OnceLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Style {
+    Fancy,
+    Plain,
+}
+
+impl Style {
+    /// `--plain` wins, then `NO_COLOR` with any non-empty value (see no-color.org), then
+    /// whether stdout is a terminal.
+    pub fn decide(plain: bool, no_color: Option<&OsStr>, stdout_is_terminal: bool) -> Self {
+        let no_color = no_color.is_some_and(|value| !value.is_empty());
+        if plain || no_color || !stdout_is_terminal {
+            Style::Plain
+        } else {
+            Style::Fancy
+        }
+    }
+
+    fn detect(plain: bool) -> Self {
+        Self::decide(
+            plain,
+            env::var_os("NO_COLOR").as_deref(),
+            std::io::stdout().is_terminal(),
+        )
+    }
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Settles the style for the rest of the process, with `--plain` as `plain`. Without a call the
+/// style is detected as if `--plain` wasn't passed.
+pub fn init(plain: bool) {
+    let _ = STYLE.set(Style::detect(plain));
+}
+
+pub fn style() -> Style {
+    *STYLE.get_or_init(|| Style::detect(false))
+}
+
+/// What a message is, which picks the emoji or prefix it starts with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    /// Something was done
+    Ok,
+    Info,
+    Warn,
+    /// The heading of the settings found before changing them
+    Current,
+    /// Where a file was written
+    Written,
+    /// Something was removed
+    Removed,
+    /// The heading of a diff
+    Changes,
+    DryRun,
+    /// An indented list item
+    Item,
+}
+
+impl Mark {
+    fn prefix(self, style: Style) -> &'static str {
+        match style {
+            Style::Fancy => match self {
+                Mark::Ok => "✅ ",
+                Mark::Info => "ℹ️  ",
+                Mark::Warn => "⚠️  ",
+                Mark::Current => "📋 ",
+                Mark::Written => "📁 ",
+                Mark::Removed => "🧹 ",
+                Mark::Changes => "📝 ",
+                Mark::DryRun => "🔍 ",
+                Mark::Item => "  • ",
+            },
+            Style::Plain => match self {
+                Mark::Ok | Mark::Removed => "[ok] ",
+                Mark::Info | Mark::Current => "[info] ",
+                Mark::Warn => "[warn] ",
+                Mark::Written => "[file] ",
+                Mark::Changes => "[diff] ",
+                Mark::DryRun => "[dry run] ",
+                Mark::Item => "  - ",
+            },
+        }
+    }
+}
+
+/// `message` marked with `mark` in `style`.
+pub fn line(style: Style, mark: Mark, message: impl Display) -> String {
+    format!("{}{message}", mark.prefix(style))
+}
+
+/// Prints `message` marked with `mark` in the process's style.
+pub fn say(mark: Mark, message: impl Display) {
+    println!("{}", line(style(), mark, message));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn downgrades_for_plain_no_color_and_pipes() {
+        assert_eq!(Style::decide(false, None, true), Style::Fancy);
+        assert_eq!(Style::decide(true, None, true), Style::Plain);
+        assert_eq!(
+            Style::decide(false, Some(OsStr::new("1")), true),
+            Style::Plain
+        );
+        assert_eq!(Style::decide(false, None, false), Style::Plain);
+    }
+
+    #[test]
+    fn an_empty_no_color_is_ignored() {
+        assert_eq!(
+            Style::decide(false, Some(OsStr::new("")), true),
+            Style::Fancy
+        );
+    }
+
+    #[test]
+    fn plain_lines_are_ascii() {
+        for mark in [
+            Mark::Ok,
+            Mark::Info,
+            Mark::Warn,
+            Mark::Current,
+            Mark::Written,
+            Mark::Removed,
+            Mark::Changes,
+            Mark::DryRun,
+            Mark::Item,
+        ] {
+            assert!(line(Style::Plain, mark, "done").is_ascii(), "{mark:?}");
+        }
+        assert_eq!(line(Style::Plain, Mark::Ok, "done"), "[ok] done");
+        assert_eq!(line(Style::Fancy, Mark::Ok, "done"), "✅ done");
+    }
+}
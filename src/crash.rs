@@ -0,0 +1,80 @@
+//! Panics while handling a hook payload. They're logged with a backtrace, and `anot claude`
+//! still answers Claude Code with valid hook output instead of dying without one.
+
+use std::{
+    any::Any,
+    backtrace::Backtrace,
+    panic::{self, AssertUnwindSafe},
+};
+
+use tracing::error;
+
+use crate::{error::AnotError, processors::claude::structs::HookOutput};
+
+/// Set to make debug builds panic while processing a payload, for testing this module.
+#[cfg(debug_assertions)]
+pub const TEST_PANIC_ENV: &str = "ANOT_TEST_PANIC";
+
+/// Logs every panic and its backtrace to the log file, then reports it as before.
+pub fn log_panics() {
+    let report = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        error!(
+            panic = %info,
+            backtrace = %Backtrace::force_capture(),
+            "anot panicked"
+        );
+        report(info);
+    }));
+}
+
+/// Runs `f`, turning a panic into an [`AnotError::Crashed`] with its message.
+pub fn catch<T>(f: impl FnOnce() -> T) -> Result<T, AnotError> {
+    panic::catch_unwind(AssertUnwindSafe(|| {
+        #[cfg(debug_assertions)]
+        if std::env::var_os(TEST_PANIC_ENV).is_some() {
+            panic!("{TEST_PANIC_ENV} is set");
+        }
+        f()
+    }))
+    .map_err(|payload| AnotError::Crashed {
+        message: panic_message(payload.as_ref()),
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    payload
+        .downcast_ref::<&str>()
+        .map(|message| message.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// The hook output Claude Code gets when processing its payload crashed: it goes on, and the
+/// crash is shown in the transcript.
+pub fn hook_output(crash: &AnotError) -> HookOutput {
+    HookOutput {
+        r#continue: Some(true),
+        suppress_output: Some(true),
+        system_message: Some(crash.to_string()),
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panics_become_errors_with_their_message() {
+        let crash = catch(|| -> () { panic!("index {} out of range", 3) }).unwrap_err();
+        assert_eq!(crash.to_string(), "anot crashed: index 3 out of range");
+        assert_eq!(crash.exit_code(), crate::error::EXIT_CRASH);
+        assert_eq!(catch(|| 7).unwrap(), 7);
+
+        assert_eq!(
+            serde_json::to_string(&hook_output(&crash)).unwrap(),
+            r#"{"continue":true,"suppressOutput":true,"systemMessage":"anot crashed: index 3 out of range"}"#
+        );
+    }
+}
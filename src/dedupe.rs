@@ -0,0 +1,209 @@
+use std::{
+    collections::{BTreeMap, hash_map::DefaultHasher},
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::Config,
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::unix_now,
+};
+
+const DEDUPE_FILE_NAME: &str = "dedupe.json";
+
+/// Recently sent notifications, keyed by a hash of (agent, event, correlation id or body).
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct DedupeState {
+    #[serde(default)]
+    sent: BTreeMap<String, u64>,
+}
+
+pub fn dedupe_path(config: &Config) -> PathBuf {
+    config.data_dir().join(DEDUPE_FILE_NAME)
+}
+
+/// Returns `false` when an identical notification was sent within `dedupe_window_secs`.
+/// Notifications with a `correlation_id` (such as Claude's `tool_use_id`) are identical when
+/// their ids match; others when their bodies do.
+///
+/// Errors reading or writing the state file are logged and the notification is let through,
+/// so a broken state file never swallows notifications. A dry run only checks.
+pub fn should_send(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    correlation_id: Option<&str>,
+    body: &str,
+) -> bool {
+    let window = config.dedupe_window_secs;
+    if window == 0 {
+        return true;
+    }
+
+    let path = dedupe_path(config);
+    let key = match correlation_id {
+        Some(id) => dedupe_key(agent, event, &format!("id:{id}")),
+        None => dedupe_key(agent, event, body),
+    };
+    let checked = if config.dry_run {
+        was_sent(&path, &key, unix_now(), window).map(|sent| !sent)
+    } else {
+        check_and_record(&path, &key, unix_now(), window)
+    };
+    match checked {
+        Ok(true) => true,
+        Ok(false) => {
+            debug!(agent, event, window, "skipping duplicate notification");
+            false
+        }
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to update dedupe state");
+            true
+        }
+    }
+}
+
+fn dedupe_key(agent: &str, event: &str, body: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    (agent, event, body).hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+/// Whether `key` was sent within `window` seconds before `now`. Nothing is recorded.
+fn was_sent(path: &Path, key: &str, now: u64, window: u64) -> Result<bool, Error> {
+    let state: DedupeState = read_json_or_default(path)?;
+    Ok(state
+        .sent
+        .get(key)
+        .is_some_and(|sent_at| now.saturating_sub(*sent_at) < window))
+}
+
+/// Records `key` as sent at `now` unless it was already sent within `window` seconds.
+/// Entries older than the window are pruned on every call. The check runs under the state
+/// file's lock, so of two identical notifications sent at once only one gets through.
+fn check_and_record(path: &Path, key: &str, now: u64, window: u64) -> Result<bool, Error> {
+    with_lock(path, || {
+        let mut state: DedupeState = read_json_or_default(path)?;
+        state
+            .sent
+            .retain(|_, sent_at| now.saturating_sub(*sent_at) < window);
+
+        if state.sent.contains_key(key) {
+            return Ok(false);
+        }
+
+        state.sent.insert(key.to_string(), now);
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+
+        Ok(true)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support;
+
+    fn temp_dedupe_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("dedupe", test_name).join(DEDUPE_FILE_NAME)
+    }
+
+    #[test]
+    fn suppresses_identical_notification_within_window() {
+        let path = temp_dedupe_path("within");
+        let key = dedupe_key("claude", "Stop", "Claude finished");
+
+        assert!(check_and_record(&path, &key, 1000, 10).unwrap());
+        assert!(!check_and_record(&path, &key, 1005, 10).unwrap());
+
+        let other = dedupe_key("claude", "SessionEnd", "Claude finished");
+        assert!(check_and_record(&path, &other, 1005, 10).unwrap());
+    }
+
+    #[test]
+    fn delivers_again_after_window_and_prunes_stale_entries() {
+        let path = temp_dedupe_path("after");
+        let key = dedupe_key("codex", "AgentTurnComplete", "done");
+
+        assert!(check_and_record(&path, &key, 1000, 10).unwrap());
+        assert!(check_and_record(&path, &key, 1010, 10).unwrap());
+
+        let stale = dedupe_key("codex", "AgentTurnComplete", "older");
+        assert!(check_and_record(&path, &stale, 1011, 10).unwrap());
+        assert!(check_and_record(&path, "fresh", 1030, 10).unwrap());
+
+        let state: DedupeState = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(state.sent.keys().collect::<Vec<_>>(), ["fresh"]);
+    }
+
+    #[test]
+    fn zero_window_disables_dedupe() {
+        let dir = temp_dedupe_path("disabled");
+        let config = Config {
+            dedupe_window_secs: 0,
+            location: Some(dir.with_file_name("a-notifications.json")),
+            ..Config::default()
+        };
+
+        assert!(should_send(&config, "claude", "Stop", None, "same"));
+        assert!(should_send(&config, "claude", "Stop", None, "same"));
+        assert!(!dedupe_path(&config).exists());
+    }
+
+    #[test]
+    fn correlated_notifications_dedupe_on_their_id() {
+        let dir = temp_dedupe_path("correlated");
+        let config = Config {
+            dedupe_window_secs: 10,
+            location: Some(dir.with_file_name("a-notifications.json")),
+            ..Config::default()
+        };
+        let body = "The agent is trying to use Bash";
+
+        assert!(should_send(
+            &config,
+            "claude",
+            "PreToolUse",
+            Some("toolu_1"),
+            body
+        ));
+        assert!(should_send(
+            &config,
+            "claude",
+            "PreToolUse",
+            Some("toolu_2"),
+            body
+        ));
+        assert!(!should_send(
+            &config,
+            "claude",
+            "PreToolUse",
+            Some("toolu_1"),
+            "other body"
+        ));
+        assert!(should_send(
+            &config,
+            "claude",
+            "PostToolUse",
+            Some("toolu_1"),
+            body
+        ));
+    }
+
+    #[test]
+    fn recovers_from_corrupt_state() {
+        let path = temp_dedupe_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{not json").unwrap();
+
+        assert!(check_and_record(&path, "key", 1000, 10).unwrap());
+        assert!(!check_and_record(&path, "key", 1001, 10).unwrap());
+    }
+}
@@ -0,0 +1,775 @@
+mod command;
+pub mod detached;
+pub mod dry_run;
+pub mod filter;
+pub mod on_sent;
+pub mod receipts;
+pub mod sound;
+
+use std::fmt;
+
+use anyhow::{Error, bail};
+use serde::Serialize;
+use tracing::{debug, error, info, warn};
+
+use crate::{
+    backends::{desktop, statusfile, terminal_escape, tmux, wsl},
+    configuration::{Backend, Config, Fallback},
+    dedupe,
+    error::AnotError,
+    events::{self, Decision, EventRecord},
+    focus,
+    history::{self, HistoryEntry},
+    metrics::{self, Outcome},
+    timing,
+};
+
+/// A backend that failed to deliver a notification.
+#[derive(Debug)]
+pub struct DeliveryError {
+    pub backend: Backend,
+    pub error: Error,
+}
+
+impl fmt::Display for DeliveryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:#}", self.backend.as_str(), self.error)
+    }
+}
+
+impl From<DeliveryError> for AnotError {
+    fn from(error: DeliveryError) -> Self {
+        AnotError::Delivery {
+            backend: error.backend,
+            source: error.error,
+        }
+    }
+}
+
+/// Which backends delivered a notification, and why the others failed.
+#[derive(Debug)]
+struct Dispatched {
+    delivered_by: Vec<Backend>,
+    result: Result<(), Error>,
+}
+
+/// How a fallback chain went: the backends that failed, in order, and the one that delivered.
+#[derive(Debug)]
+struct ChainOutcome {
+    errors: Vec<DeliveryError>,
+    delivered_by: Option<Backend>,
+}
+
+/// What one backend did with the notification of `anot test`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct StageReport {
+    pub backend: Backend,
+    pub outcome: StageOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StageOutcome {
+    Delivered,
+    Failed,
+    /// An earlier backend of the fallback chain delivered it.
+    NotTried,
+}
+
+/// Sends a rendered notification to every configured backend, or through the agent's fallback
+/// chain when it has one, and records the outcome in the history. `show_desktop` shows it as a
+/// native desktop notification.
+///
+/// `filter_command` may drop it first, and `on_sent` runs after it. Recent duplicates are
+/// dropped entirely; a focused terminal only skips the desktop backend. A dry run prints what
+/// would be sent instead.
+pub fn deliver(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    deliver_correlated(config, agent, event, None, title, body, show_desktop)
+}
+
+/// Like [`deliver`], for events the agent gave an id to: duplicates are recognized by
+/// `correlation_id` rather than by their body.
+pub fn deliver_correlated(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    correlation_id: Option<&str>,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    let filter_input = filter::FilterInput {
+        agent,
+        event,
+        title,
+        body,
+        correlation_id,
+    };
+    if !filter::allows(config, &filter_input) {
+        dry_run::report_suppressed(
+            config,
+            agent,
+            event,
+            Decision::Filtered,
+            "dropped by filter_command",
+        );
+        return Ok(());
+    }
+    if !dedupe::should_send(config, agent, event, correlation_id, body) {
+        dry_run::report_suppressed(
+            config,
+            agent,
+            event,
+            Decision::Duplicate,
+            "duplicate of a recent notification",
+        );
+        return Ok(());
+    }
+    timing::mark("filters");
+
+    let chain = config.fallback_chain(agent);
+    if config.dry_run {
+        let backends = if chain.is_empty() {
+            active_backends(config, terminal_escape::is_remote_session())?
+        } else {
+            chain.to_vec()
+        };
+        dry_run::DryRunReport::sent(config, agent, event, title, body, backends).print();
+        return Ok(());
+    }
+    let Dispatched {
+        delivered_by,
+        result,
+    } = if chain.is_empty() {
+        match active_backends(config, terminal_escape::is_remote_session()) {
+            Ok(backends) => {
+                send_to_backends(config, &backends, agent, event, title, body, show_desktop)
+            }
+            Err(error) => Dispatched {
+                delivered_by: Vec::new(),
+                result: Err(error),
+            },
+        }
+    } else {
+        send_through_chain(config, chain, agent, event, title, body, show_desktop)
+    };
+    timing::mark("backends");
+    receipts::record(config, agent, event, &delivered_by, &result);
+    events::record(
+        config,
+        EventRecord::delivered(agent, event, title, body, &delivered_by, &result),
+    );
+    history::record(
+        config,
+        &HistoryEntry::new(agent, event, title, body, &result),
+    );
+    on_sent::queue(
+        config,
+        on_sent::SentReport::new(agent, event, correlation_id, title, body, &result),
+    );
+    if result.is_ok() {
+        metrics::record_notification(config, agent, event, Outcome::Sent);
+    }
+    result
+}
+
+/// `title` with a "(replay)" suffix when the notification is being replayed, so it isn't mistaken
+/// for a live event.
+pub fn replay_title(config: &Config, title: &str) -> String {
+    if config.replay {
+        format!("{title} (replay)")
+    } else {
+        title.to_string()
+    }
+}
+
+/// The backends to use: the configured ones, plus the terminal escape backend in remote
+/// sessions, minus the desktop backend when this build doesn't include it.
+fn active_backends(config: &Config, remote_session: bool) -> Result<Vec<Backend>, Error> {
+    let mut backends = config.backends.clone();
+
+    if remote_session
+        && config.terminal_escape.auto_over_ssh
+        && !backends.contains(&Backend::TerminalEscape)
+    {
+        debug!("SSH session without a display; adding the terminal escape backend");
+        backends.push(Backend::TerminalEscape);
+    }
+
+    if !cfg!(feature = "desktop") {
+        backends.retain(|backend| *backend != Backend::Desktop);
+        if backends.is_empty() && config.wsl_bridge.auto && wsl::is_wsl() {
+            backends.push(Backend::WslBridge);
+        }
+        if backends.is_empty() {
+            bail!(
+                "desktop notifications aren't available in this build of anot; \
+                 add another backend such as \"tmux\" or \"statusfile\" to `backends`"
+            );
+        }
+    }
+
+    Ok(backends)
+}
+
+fn send_to_backends(
+    config: &Config,
+    backends: &[Backend],
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> Dispatched {
+    let mut errors = fan_out(config, backends, agent, event, title, body, show_desktop);
+    record_failures(config, &errors);
+
+    let delivered_by = backends
+        .iter()
+        .copied()
+        .filter(|backend| !errors.iter().any(|e| e.backend == *backend))
+        .collect();
+    let result = match errors.len() {
+        0 => Ok(()),
+        1 => Err(AnotError::from(errors.remove(0)).into()),
+        _ => Err(Error::msg(join_errors(&errors))),
+    };
+    Dispatched {
+        delivered_by,
+        result,
+    }
+}
+
+/// Sends the notification to each of `backends` and returns the failures.
+fn fan_out(
+    config: &Config,
+    backends: &[Backend],
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> Vec<DeliveryError> {
+    let mut show_desktop = Some(show_desktop);
+    let mut errors = Vec::new();
+
+    for &backend in backends {
+        let result = send_one(
+            config,
+            backends,
+            backend,
+            agent,
+            event,
+            title,
+            body,
+            &mut show_desktop,
+        );
+        let result = match backend {
+            Backend::Desktop => fallback_after(result, config.fallback)
+                .map(|fallback| run_fallback(fallback, title, body, config)),
+            _ => result,
+        };
+
+        if let Err(error) = result {
+            warn!(backend = backend.as_str(), error = %error, "notification backend failed");
+            errors.push(DeliveryError { backend, error });
+        }
+    }
+
+    errors
+}
+
+/// Tries the backends of `chain` in order until one delivers. When all fail, the error lists
+/// why each one did.
+fn send_through_chain(
+    config: &Config,
+    chain: &[Backend],
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> Dispatched {
+    let outcome = try_chain(config, chain, agent, event, title, body, show_desktop);
+    record_failures(config, &outcome.errors);
+    if let Some(backend) = outcome.delivered_by {
+        return Dispatched {
+            delivered_by: vec![backend],
+            result: Ok(()),
+        };
+    }
+
+    let errors = join_errors(&outcome.errors);
+    error!(agent, event, errors = %errors, "every backend in the fallback chain failed");
+    Dispatched {
+        delivered_by: Vec::new(),
+        result: Err(Error::msg(format!(
+            "every backend in the fallback chain failed: {errors}"
+        ))),
+    }
+}
+
+fn try_chain(
+    config: &Config,
+    chain: &[Backend],
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: impl FnOnce() -> Result<(), Error>,
+) -> ChainOutcome {
+    let mut show_desktop = Some(show_desktop);
+    run_chain(chain, |backend| match backend {
+        // Both would report success without showing anything.
+        Backend::Tmux if !tmux::in_session() => bail!("not inside tmux"),
+        Backend::Desktop if show_desktop.is_none() => bail!("already tried"),
+        _ => send_one(
+            config,
+            chain,
+            backend,
+            agent,
+            event,
+            title,
+            body,
+            &mut show_desktop,
+        ),
+    })
+}
+
+/// Calls `send` for each backend of `chain` until one succeeds.
+fn run_chain(
+    chain: &[Backend],
+    mut send: impl FnMut(Backend) -> Result<(), Error>,
+) -> ChainOutcome {
+    let mut errors = Vec::new();
+
+    for &backend in chain {
+        match send(backend) {
+            Ok(()) => {
+                if !errors.is_empty() {
+                    info!(
+                        backend = backend.as_str(),
+                        failed = errors.len(),
+                        "delivered by a fallback backend"
+                    );
+                }
+                return ChainOutcome {
+                    errors,
+                    delivered_by: Some(backend),
+                };
+            }
+            Err(error) => {
+                warn!(backend = backend.as_str(), error = %error, "backend failed; trying the next one");
+                errors.push(DeliveryError { backend, error });
+            }
+        }
+    }
+
+    ChainOutcome {
+        errors,
+        delivered_by: None,
+    }
+}
+
+fn record_failures(config: &Config, errors: &[DeliveryError]) {
+    for error in errors {
+        metrics::record_failure(config, error.backend);
+    }
+}
+
+fn join_errors(errors: &[DeliveryError]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Sends the notification through `backend`, one of `backends`. The desktop notification is
+/// shown once, by the first desktop send; a focused terminal skips it.
+#[allow(clippy::too_many_arguments)]
+fn send_one(
+    config: &Config,
+    backends: &[Backend],
+    backend: Backend,
+    agent: &str,
+    event: &str,
+    title: &str,
+    body: &str,
+    show_desktop: &mut Option<impl FnOnce() -> Result<(), Error>>,
+) -> Result<(), Error> {
+    match backend {
+        Backend::Desktop => {
+            if !cfg!(feature = "desktop") {
+                bail!("desktop notifications aren't available in this build of anot");
+            }
+            if config.suppress_when_focused && focus::terminal_is_focused(&config.terminal_apps) {
+                info!(agent, event, "terminal focused; skipping desktop");
+                return Ok(());
+            }
+            let Some(show) = show_desktop.take() else {
+                return Ok(());
+            };
+            match desktop::check_available()
+                .map_err(Error::from)
+                .and_then(|_| show())
+            {
+                Err(e)
+                    if desktop::is_unavailable(&e)
+                        && config.wsl_bridge.auto
+                        && !backends.contains(&Backend::WslBridge)
+                        && wsl::is_wsl() =>
+                {
+                    debug!(error = %e, "desktop unavailable; using the WSL bridge");
+                    wsl::send(title, body)
+                }
+                shown => shown,
+            }
+        }
+        Backend::Tmux => tmux::send(title, body, config),
+        Backend::Statusfile => statusfile::send(title, body, config),
+        Backend::TerminalEscape => terminal_escape::send(title, body, config),
+        Backend::WslBridge => wsl::send(title, body),
+    }
+}
+
+/// Sends a test notification for `agent` the way its notifications go, without deduplication,
+/// history or focus suppression, and reports what each backend did.
+pub fn test_backends(
+    config: &Config,
+    agent: &str,
+    title: &str,
+    body: &str,
+) -> Result<Vec<StageReport>, Error> {
+    let config = &Config {
+        suppress_when_focused: false,
+        ..config.clone()
+    };
+    let show_desktop = || desktop::show(title, body);
+    let event = "Test";
+
+    let chain = config.fallback_chain(agent);
+    if !chain.is_empty() {
+        let outcome = try_chain(config, chain, agent, event, title, body, show_desktop);
+        return Ok(chain_report(chain, outcome));
+    }
+
+    let backends = active_backends(config, terminal_escape::is_remote_session())?;
+    let errors = fan_out(config, &backends, agent, event, title, body, show_desktop);
+    Ok(backends
+        .iter()
+        .map(
+            |&backend| match errors.iter().find(|e| e.backend == backend) {
+                Some(failed) => StageReport {
+                    backend,
+                    outcome: StageOutcome::Failed,
+                    error: Some(format!("{:#}", failed.error)),
+                },
+                None => StageReport {
+                    backend,
+                    outcome: StageOutcome::Delivered,
+                    error: None,
+                },
+            },
+        )
+        .collect())
+}
+
+/// One line per stage of a chain: the failures, the backend that delivered, and the rest.
+fn chain_report(chain: &[Backend], outcome: ChainOutcome) -> Vec<StageReport> {
+    let failed = outcome.errors.len();
+    let mut errors = outcome.errors.into_iter();
+
+    chain
+        .iter()
+        .enumerate()
+        .map(|(index, &backend)| {
+            if index < failed {
+                let failure = errors.next().expect("one error per failed stage");
+                StageReport {
+                    backend,
+                    outcome: StageOutcome::Failed,
+                    error: Some(format!("{:#}", failure.error)),
+                }
+            } else if index == failed && outcome.delivered_by.is_some() {
+                StageReport {
+                    backend,
+                    outcome: StageOutcome::Delivered,
+                    error: None,
+                }
+            } else {
+                StageReport {
+                    backend,
+                    outcome: StageOutcome::NotTried,
+                    error: None,
+                }
+            }
+        })
+        .collect()
+}
+
+/// A stage of `anot test`, e.g. "desktop          failed: no D-Bus session bus".
+pub fn format_stage(stage: &StageReport) -> String {
+    let outcome = match (stage.outcome, &stage.error) {
+        (StageOutcome::Failed, Some(error)) => format!("failed: {error}"),
+        (StageOutcome::Failed, None) => "failed".to_string(),
+        (StageOutcome::Delivered, _) => "delivered".to_string(),
+        (StageOutcome::NotTried, _) => "not tried".to_string(),
+    };
+    format!("{:<16} {outcome}", stage.backend.as_str())
+}
+
+/// Turns a desktop failure caused by a missing notification service (a headless machine) into
+/// the fallback to run instead. Other errors are passed through.
+fn fallback_after(shown: Result<(), Error>, fallback: Fallback) -> Result<Fallback, Error> {
+    match shown {
+        Ok(()) => Ok(Fallback::None),
+        Err(e) if desktop::is_unavailable(&e) => {
+            warn!(error = %e, fallback = ?fallback, "desktop notifications unavailable");
+            Ok(fallback)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Runs the fallback. Its failures are only logged: the hook should still succeed.
+fn run_fallback(fallback: Fallback, title: &str, body: &str, config: &Config) {
+    let result = match fallback {
+        Fallback::TerminalBell => terminal_escape::bell(),
+        Fallback::Statusfile => statusfile::send(title, body, config),
+        Fallback::None => return,
+    };
+
+    if let Err(e) = result {
+        warn!(fallback = ?fallback, error = %e, "fallback notification failed");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_config;
+
+    #[test]
+    fn adds_terminal_escape_in_remote_sessions() {
+        let config = Config {
+            backends: vec![Backend::Statusfile],
+            ..Config::default()
+        };
+
+        assert_eq!(
+            active_backends(&config, true).unwrap(),
+            [Backend::Statusfile, Backend::TerminalEscape]
+        );
+        assert_eq!(
+            active_backends(&config, false).unwrap(),
+            [Backend::Statusfile]
+        );
+    }
+
+    #[test]
+    fn falls_back_only_when_desktop_is_unavailable() {
+        assert_eq!(
+            fallback_after(Ok(()), Fallback::TerminalBell).unwrap(),
+            Fallback::None
+        );
+        assert_eq!(
+            fallback_after(
+                Err(Error::msg(
+                    "I/O error: No such file or directory (os error 2)"
+                )),
+                Fallback::TerminalBell
+            )
+            .unwrap(),
+            Fallback::TerminalBell
+        );
+        assert_eq!(
+            fallback_after(
+                Err(Error::new(desktop::DesktopUnavailable(
+                    "no D-Bus session bus".to_string()
+                ))),
+                Fallback::Statusfile
+            )
+            .unwrap(),
+            Fallback::Statusfile
+        );
+        assert!(
+            fallback_after(Err(Error::msg("invalid image data")), Fallback::Statusfile).is_err()
+        );
+    }
+
+    /// Runs `chain` with the backends in `failing` failing, recording which were tried.
+    fn run_with_failures(chain: &[Backend], failing: &[Backend]) -> (ChainOutcome, Vec<Backend>) {
+        let mut tried = Vec::new();
+        let outcome = run_chain(chain, |backend| {
+            tried.push(backend);
+            if failing.contains(&backend) {
+                bail!("{} is down", backend.as_str())
+            }
+            Ok(())
+        });
+        (outcome, tried)
+    }
+
+    const CHAIN: [Backend; 3] = [
+        Backend::Desktop,
+        Backend::TerminalEscape,
+        Backend::Statusfile,
+    ];
+
+    #[test]
+    fn chain_stops_at_the_first_success() {
+        let (outcome, tried) = run_with_failures(&CHAIN, &[]);
+        assert_eq!(outcome.delivered_by, Some(Backend::Desktop));
+        assert_eq!(tried, [Backend::Desktop]);
+
+        let (outcome, tried) = run_with_failures(&CHAIN, &[Backend::Desktop]);
+        assert_eq!(outcome.delivered_by, Some(Backend::TerminalEscape));
+        assert_eq!(tried, [Backend::Desktop, Backend::TerminalEscape]);
+        assert_eq!(outcome.errors[0].to_string(), "desktop: desktop is down");
+
+        let (outcome, tried) =
+            run_with_failures(&CHAIN, &[Backend::Desktop, Backend::TerminalEscape]);
+        assert_eq!(outcome.delivered_by, Some(Backend::Statusfile));
+        assert_eq!(tried, CHAIN);
+    }
+
+    #[test]
+    fn chain_failure_keeps_every_error() {
+        let (outcome, tried) = run_with_failures(&CHAIN, &CHAIN);
+
+        assert_eq!(outcome.delivered_by, None);
+        assert_eq!(tried, CHAIN);
+        assert_eq!(
+            join_errors(&outcome.errors),
+            "desktop: desktop is down; terminal_escape: terminal_escape is down; \
+             statusfile: statusfile is down"
+        );
+    }
+
+    #[test]
+    fn chain_report_marks_each_stage() {
+        let (outcome, _) = run_with_failures(&CHAIN, &[Backend::Desktop]);
+        let report: Vec<String> = chain_report(&CHAIN, outcome)
+            .iter()
+            .map(format_stage)
+            .collect();
+
+        assert_eq!(
+            report,
+            [
+                "desktop          failed: desktop is down",
+                "terminal_escape  delivered",
+                "statusfile       not tried",
+            ]
+        );
+
+        let (outcome, _) = run_with_failures(&CHAIN, &CHAIN);
+        assert!(
+            chain_report(&CHAIN, outcome)
+                .iter()
+                .all(|stage| stage.outcome == StageOutcome::Failed)
+        );
+    }
+
+    #[test]
+    fn agent_chains_replace_the_top_level_one() {
+        let mut config = Config {
+            fallbacks: vec![Backend::Desktop, Backend::Statusfile],
+            ..Config::default()
+        };
+        config.codex.fallbacks = Some(vec![Backend::Tmux]);
+
+        assert_eq!(
+            config.fallback_chain("claude"),
+            [Backend::Desktop, Backend::Statusfile]
+        );
+        assert_eq!(config.fallback_chain("codex"), [Backend::Tmux]);
+        assert_eq!(
+            Config::default().fallback_chain("opencode"),
+            [] as [Backend; 0]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dropped_notifications_never_build_the_desktop_one() {
+        let mut config = Config {
+            backends: vec![Backend::Desktop],
+            filter_command: ["sh", "-c", "exit 10"].map(String::from).to_vec(),
+            ..temp_config("delivery", "drop")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        // Building it writes the icon to disk, which a dropped notification doesn't need.
+        let show_desktop = || -> Result<(), Error> { panic!("the desktop notification was built") };
+
+        deliver(
+            &config,
+            "claude",
+            "Stop",
+            "Claude Code · Finished",
+            "Done",
+            show_desktop,
+        )
+        .unwrap();
+
+        config.filter_command.clear();
+        config.backends = vec![Backend::Statusfile];
+        deliver(
+            &config,
+            "claude",
+            "Stop",
+            "Claude Code · Finished",
+            "Done",
+            || Ok(()),
+        )
+        .unwrap();
+        config.backends = vec![Backend::Desktop];
+        deliver(
+            &config,
+            "claude",
+            "Stop",
+            "Claude Code · Finished",
+            "Done",
+            show_desktop,
+        )
+        .unwrap();
+    }
+
+    #[cfg(feature = "desktop")]
+    #[test]
+    fn keeps_desktop_backend_when_compiled_in() {
+        assert_eq!(
+            active_backends(&Config::default(), false).unwrap(),
+            [Backend::Desktop]
+        );
+    }
+
+    #[cfg(not(feature = "desktop"))]
+    #[test]
+    fn errors_when_only_desktop_is_requested_but_compiled_out() {
+        let err = active_backends(&Config::default(), false).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("desktop notifications aren't available")
+        );
+
+        let config = Config {
+            backends: vec![Backend::Desktop, Backend::Statusfile],
+            ..Config::default()
+        };
+        assert_eq!(
+            active_backends(&config, false).unwrap(),
+            [Backend::Statusfile]
+        );
+    }
+}
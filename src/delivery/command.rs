@@ -0,0 +1,56 @@
+//! Running the user's own commands, like `filter_command`: directly rather than through a
+//! shell, with JSON on stdin and a time limit.
+
+use std::{
+    io::Write,
+    process::{Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Error, anyhow, bail};
+
+/// How often a running command is checked on.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// Runs `argv` with `env` added to its environment and `stdin` as its input, and returns how it
+/// exited. One still running after `timeout` is killed and reported as an error.
+pub fn run(
+    argv: &[String],
+    env: &[(&str, &str)],
+    stdin: Vec<u8>,
+    timeout: Duration,
+) -> Result<ExitStatus, Error> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow!("the command is empty"))?;
+    let mut child = Command::new(program)
+        .args(args)
+        .envs(env.iter().copied())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow!("can't run {program}: {e}"))?;
+
+    // Written from another thread, so a command that doesn't read stdin can't block anot. One
+    // that exits without reading it closes the pipe, which isn't an error.
+    if let Some(mut pipe) = child.stdin.take() {
+        thread::spawn(move || {
+            let _ = pipe.write_all(&stdin);
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(status);
+        }
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            bail!("{program} didn't finish within {}s", timeout.as_secs());
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,245 @@
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Error, bail};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{Config, DeliveryMode},
+    forward, privacy,
+    processors::{claude, codex, opencode},
+    storage::write_atomic,
+};
+
+/// Input handed from a hook invocation to the background process that sends its
+/// notifications.
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeliveryJob {
+    pub agent: String,
+    pub input: String,
+    /// Relay `input` to the agent's `forward` endpoint instead of notifying.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub forward: bool,
+    /// The input came from another machine, see [`Config::remote`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remote: bool,
+}
+
+/// Hands `input` to a background `anot --deliver` process. When that can't be started, the
+/// notification is sent in this process with `deliver_inline` instead.
+pub fn spawn_or(
+    config: &Config,
+    agent: &str,
+    input: &str,
+    deliver_inline: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    let job = DeliveryJob {
+        agent: agent.to_string(),
+        input: input.to_string(),
+        forward: false,
+        remote: config.remote,
+    };
+    spawn_job_or(config, job, deliver_inline)
+}
+
+/// Hands `job` to a background `anot --deliver` process, or runs `run_inline` when that can't
+/// be started.
+pub fn spawn_job_or(
+    config: &Config,
+    job: DeliveryJob,
+    run_inline: impl FnOnce() -> Result<(), Error>,
+) -> Result<(), Error> {
+    // A dry run prints its report from this process.
+    if config.dry_run {
+        return run_inline();
+    }
+    match spawn(config, &job) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            warn!(agent = %job.agent, error = %e, "failed to start background delivery; sending in-process");
+            run_inline()
+        }
+    }
+}
+
+fn spawn(config: &Config, job: &DeliveryJob) -> Result<(), Error> {
+    let path = job_path(config, &job.agent);
+    let job = DeliveryJob {
+        agent: job.agent.clone(),
+        input: privacy::redact_payload(config, &job.input),
+        forward: job.forward,
+        remote: job.remote,
+    };
+    write_atomic(&path, serde_json::to_string(&job)?.as_bytes())?;
+
+    match spawn_background(deliver_args(config, &path)) {
+        Ok(pid) => {
+            debug!(pid, job = %path.display(), "started background delivery");
+            Ok(())
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&path);
+            Err(e)
+        }
+    }
+}
+
+/// Starts `anot` with `args` as a background process that outlives this one. Returns its pid.
+pub fn spawn_background(args: Vec<OsString>) -> Result<u32, Error> {
+    let mut command = Command::new(std::env::current_exe()?);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    detach(&mut command);
+    Ok(command.spawn()?.id())
+}
+
+/// Arguments that make `anot` send the notifications for the job at `job`.
+fn deliver_args(config: &Config, job: &Path) -> Vec<OsString> {
+    let mut args = config_args(config);
+    args.push(OsString::from("--deliver"));
+    args.push(job.as_os_str().to_owned());
+    args
+}
+
+/// Arguments that make a background `anot` load the same config and profile as this one.
+pub fn config_args(config: &Config) -> Vec<OsString> {
+    let mut args = Vec::new();
+    if let Some(location) = &config.location {
+        args.push(OsString::from("--config"));
+        args.push(location.as_os_str().to_owned());
+    }
+    if let Some(profile) = &config.profile {
+        args.push(OsString::from("--profile"));
+        args.push(OsString::from(profile));
+    }
+    args
+}
+
+fn job_path(config: &Config, agent: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    config
+        .data_dir()
+        .join("jobs")
+        .join(format!("{agent}-{}-{nanos}.json", std::process::id()))
+}
+
+/// Keeps the background process alive when the agent kills the hook's process group.
+#[cfg(unix)]
+fn detach(command: &mut Command) {
+    use std::os::unix::process::CommandExt;
+
+    command.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(command: &mut Command) {
+    use std::os::windows::process::CommandExt;
+
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    command.creation_flags(DETACHED_PROCESS);
+}
+
+#[cfg(not(any(unix, windows)))]
+fn detach(_command: &mut Command) {}
+
+/// Sends the notifications for a job written by a detached hook invocation, then removes it.
+pub fn run_job(path: &Path, config: &Config) -> Result<(), Error> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read delivery job {}", path.display()))?;
+    let _ = fs::remove_file(path);
+    let job: DeliveryJob = serde_json::from_str(&contents)?;
+    debug!(agent = %job.agent, "running background delivery");
+
+    let config = Config {
+        delivery: DeliveryMode::Blocking,
+        background: true,
+        ..config.clone()
+    };
+    deliver_job(job, &config)
+}
+
+/// Sends the notifications for `job` through its agent's processor, or relays it.
+pub fn deliver_job(job: DeliveryJob, config: &Config) -> Result<(), Error> {
+    let config = &Config {
+        remote: job.remote,
+        ..config.clone()
+    };
+    if job.forward {
+        return forward::relay(config, &job.agent, &job.input);
+    }
+    match job.agent.as_str() {
+        "claude" => claude::input_and_output::deliver_claude_input(&job.input, config),
+        "codex" => codex::input_and_output::process_codex_input(job.input, config),
+        "opencode" => opencode::input_and_output::process_opencode_input(job.input, config),
+        other => bail!("unknown agent `{other}` in delivery job"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_deliver_arguments() {
+        let config = Config {
+            location: Some(PathBuf::from("/home/me/anot.json")),
+            ..Config::default()
+        };
+        assert_eq!(
+            deliver_args(&config, Path::new("/tmp/jobs/claude-1-2.json")),
+            [
+                "--config",
+                "/home/me/anot.json",
+                "--deliver",
+                "/tmp/jobs/claude-1-2.json"
+            ]
+        );
+        assert_eq!(
+            deliver_args(&Config::default(), Path::new("/tmp/jobs/codex-1-2.json")),
+            ["--deliver", "/tmp/jobs/codex-1-2.json"]
+        );
+
+        let config = Config {
+            profile: Some("focus".to_string()),
+            ..Config::default()
+        };
+        assert_eq!(
+            deliver_args(&config, Path::new("/tmp/jobs/codex-1-2.json")),
+            [
+                "--profile",
+                "focus",
+                "--deliver",
+                "/tmp/jobs/codex-1-2.json"
+            ]
+        );
+    }
+
+    #[test]
+    fn run_job_removes_the_job_and_rejects_unknown_agents() {
+        let dir = std::env::temp_dir().join(format!("anot-jobs-{}", std::process::id()));
+        let path = dir.join("job.json");
+        let job = DeliveryJob {
+            agent: "gemini".to_string(),
+            input: "{}".to_string(),
+            forward: false,
+            remote: false,
+        };
+        write_atomic(&path, serde_json::to_string(&job).unwrap().as_bytes()).unwrap();
+
+        let err = run_job(&path, &Config::default()).unwrap_err();
+        assert!(err.to_string().contains("unknown agent `gemini`"));
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,189 @@
+//! `--dry-run`: the whole pipeline runs, but instead of sending a notification anot prints what
+//! it would have sent, or why nothing would be sent, as one JSON line on stderr.
+
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::{
+    configuration::{Backend, Config},
+    events::{self, Decision, EventRecord},
+    metrics::{self, Outcome},
+};
+
+/// Set to anything but `0` to make `anot claude` and `anot codex` dry runs.
+pub const DRY_RUN_ENV: &str = "ANOT_DRY_RUN";
+
+/// What a dry run prints for one notification.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+pub struct DryRunReport {
+    pub agent: String,
+    pub event: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    /// The configured icon; `None` for the agent's built-in one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub icon: Option<PathBuf>,
+    /// The backends it would go to, or the fallback chain it would go through.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub backends: Vec<Backend>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub fallback_chain: bool,
+    /// Why nothing would be sent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suppressed: Option<String>,
+    /// Where the payload would be relayed to, with `forward`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub forwarded_to: Option<String>,
+}
+
+impl DryRunReport {
+    /// A notification that would go to `backends`.
+    pub fn sent(
+        config: &Config,
+        agent: &str,
+        event: &str,
+        title: &str,
+        body: &str,
+        backends: Vec<Backend>,
+    ) -> Self {
+        DryRunReport {
+            agent: agent.to_string(),
+            event: event.to_string(),
+            title: Some(title.to_string()),
+            body: Some(body.to_string()),
+            icon: configured_icon(config, agent, event),
+            fallback_chain: !config.fallback_chain(agent).is_empty(),
+            backends,
+            suppressed: None,
+            forwarded_to: None,
+        }
+    }
+
+    /// An event that wouldn't notify, because of `reason`.
+    pub fn suppressed(agent: &str, event: &str, reason: &str) -> Self {
+        DryRunReport {
+            agent: agent.to_string(),
+            event: event.to_string(),
+            title: None,
+            body: None,
+            icon: None,
+            backends: Vec::new(),
+            fallback_chain: false,
+            suppressed: Some(reason.to_string()),
+            forwarded_to: None,
+        }
+    }
+
+    /// A payload that would be relayed to `url`.
+    pub fn forwarded(agent: &str, event: &str, url: &str) -> Self {
+        DryRunReport {
+            agent: agent.to_string(),
+            event: event.to_string(),
+            title: None,
+            body: None,
+            icon: None,
+            backends: Vec::new(),
+            fallback_chain: false,
+            suppressed: None,
+            forwarded_to: Some(url.to_string()),
+        }
+    }
+
+    pub fn print(&self) {
+        match serde_json::to_string(self) {
+            Ok(line) => eprintln!("{line}"),
+            Err(e) => eprintln!("failed to describe the dry run: {e}"),
+        }
+    }
+}
+
+/// Whether this is a dry run: `--dry-run` was passed or [`DRY_RUN_ENV`] is set.
+pub fn requested(flag: bool) -> bool {
+    flag || env_enables(std::env::var(DRY_RUN_ENV).ok().as_deref())
+}
+
+fn env_enables(value: Option<&str>) -> bool {
+    value.is_some_and(|value| !matches!(value.trim(), "" | "0"))
+}
+
+/// Counts `event` as suppressed in the metrics and the events log, or prints why it wouldn't
+/// notify when this is a dry run.
+pub fn report_suppressed(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    decision: Decision,
+    reason: &str,
+) {
+    if config.dry_run {
+        DryRunReport::suppressed(agent, event, reason).print();
+    } else {
+        metrics::record_notification(config, agent, event, Outcome::Suppressed);
+        events::record(
+            config,
+            EventRecord::suppressed(agent, event, decision, reason),
+        );
+    }
+}
+
+fn configured_icon(config: &Config, agent: &str, event: &str) -> Option<PathBuf> {
+    match agent {
+        "claude" => config.claude.settings_for(event).icon,
+        "codex" => config.codex.settings_for(event).icon,
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn describes_a_sent_notification() {
+        let config = Config {
+            fallbacks: vec![Backend::Tmux, Backend::Statusfile],
+            ..Config::default()
+        };
+        let report = DryRunReport::sent(
+            &config,
+            "codex",
+            "AgentTurnComplete",
+            "Codex · Turn complete",
+            "Tests pass",
+            config.fallbacks.clone(),
+        );
+
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "agent": "codex",
+                "event": "AgentTurnComplete",
+                "title": "Codex · Turn complete",
+                "body": "Tests pass",
+                "backends": ["tmux", "statusfile"],
+                "fallback_chain": true,
+            })
+        );
+    }
+
+    #[test]
+    fn env_var_enables_unless_zero() {
+        assert!(env_enables(Some("1")));
+        assert!(env_enables(Some("true")));
+        assert!(!env_enables(Some("0")));
+        assert!(!env_enables(Some("")));
+        assert!(!env_enables(None));
+    }
+
+    #[test]
+    fn describes_a_suppressed_event() {
+        let report = DryRunReport::suppressed("claude", "PostToolUse", "muted");
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({"agent": "claude", "event": "PostToolUse", "suppressed": "muted"})
+        );
+    }
+}
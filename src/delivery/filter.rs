@@ -0,0 +1,139 @@
+//! `filter_command`: a program that decides whether a notification is shown. It gets the
+//! rendered notification as JSON on stdin and `ANOT_AGENT` and `ANOT_EVENT` in its
+//! environment, and answers with its exit code. It's run directly, never through a shell, so
+//! nothing in the notification can turn into a command.
+//!
+//! Anything but a clear answer (a crash, another exit code, running out of time) is logged and
+//! the notification is sent: a broken filter never hides notifications.
+
+use std::{process::ExitStatus, time::Duration};
+
+use anyhow::Error;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{configuration::Config, delivery::command};
+
+/// The exit code that drops the notification.
+pub const DROP_EXIT_CODE: i32 = 10;
+
+/// What the filter reads on stdin.
+#[derive(Debug, Serialize)]
+pub struct FilterInput<'a> {
+    pub agent: &'a str,
+    pub event: &'a str,
+    pub title: &'a str,
+    pub body: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<&'a str>,
+}
+
+/// Whether the notification should be sent. Always `true` without a `filter_command`.
+pub fn allows(config: &Config, input: &FilterInput) -> bool {
+    if config.filter_command.is_empty() {
+        return true;
+    }
+    match run(config, input) {
+        Ok(status) if status.code() == Some(0) => true,
+        Ok(status) if status.code() == Some(DROP_EXIT_CODE) => {
+            debug!(
+                agent = input.agent,
+                event = input.event,
+                "filter_command dropped the notification"
+            );
+            false
+        }
+        Ok(status) => {
+            warn!(%status, "filter_command failed; sending the notification");
+            true
+        }
+        Err(e) => {
+            warn!(error = %e, "filter_command failed; sending the notification");
+            true
+        }
+    }
+}
+
+fn run(config: &Config, input: &FilterInput) -> Result<ExitStatus, Error> {
+    command::run(
+        &config.filter_command,
+        &[("ANOT_AGENT", input.agent), ("ANOT_EVENT", input.event)],
+        serde_json::to_vec(input)?,
+        Duration::from_secs(config.filter_timeout_secs),
+    )
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, time::Instant};
+
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    /// A config running `script` with `sh`, with `args` as `$1`, `$2` and so on.
+    fn config(script: &str, args: &[&str], timeout_secs: u64) -> Config {
+        let mut filter_command = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        filter_command.push("filter".to_string());
+        filter_command.extend(args.iter().map(|arg| arg.to_string()));
+        Config {
+            filter_command,
+            filter_timeout_secs: timeout_secs,
+            ..Config::default()
+        }
+    }
+
+    fn input<'a>(title: &'a str) -> FilterInput<'a> {
+        FilterInput {
+            agent: "claude",
+            event: "Stop",
+            title,
+            body: "Done",
+            correlation_id: None,
+        }
+    }
+
+    #[test]
+    fn sends_without_a_filter() {
+        assert!(allows(&Config::default(), &input("Finished")));
+    }
+
+    #[test]
+    fn exit_codes_allow_or_drop() {
+        let dir = temp_dir("filter", "allow");
+        let (stdin, env) = (dir.join("stdin.json"), dir.join("env"));
+        let script = r#"cat > "$1"; printf '%s %s' "$ANOT_AGENT" "$ANOT_EVENT" > "$2""#;
+        let title = "Finished $(touch pwned) `touch pwned`";
+
+        let allow = config(script, &[stdin.to_str().unwrap(), env.to_str().unwrap()], 5);
+        assert!(allows(&allow, &input(title)));
+        let received: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&stdin).unwrap()).unwrap();
+        assert_eq!(
+            received,
+            serde_json::json!({"agent": "claude", "event": "Stop", "title": title, "body": "Done"})
+        );
+        assert_eq!(fs::read_to_string(&env).unwrap(), "claude Stop");
+
+        assert!(!allows(
+            &config("cat > /dev/null; exit 10", &[], 5),
+            &input("Finished")
+        ));
+        assert!(!allows(&config("exit 10", &[], 5), &input("Finished")));
+    }
+
+    #[test]
+    fn fails_open() {
+        let started = Instant::now();
+        assert!(allows(&config("exec sleep 30", &[], 1), &input("Finished")));
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        assert!(allows(&config("kill -9 $$", &[], 5), &input("Finished")));
+        assert!(allows(&config("exit 3", &[], 5), &input("Finished")));
+
+        let missing = Config {
+            filter_command: vec!["/nonexistent/anot-filter".to_string()],
+            ..Config::default()
+        };
+        assert!(allows(&missing, &input("Finished")));
+    }
+}
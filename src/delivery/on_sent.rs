@@ -0,0 +1,207 @@
+//! `on_sent`: commands run after a notification was sent, or failed to be, e.g. to log it
+//! elsewhere or switch on a light. Each gets the notification and how its delivery went as
+//! JSON on stdin, with `ANOT_AGENT` and `ANOT_EVENT` set.
+//!
+//! They're queued while anot delivers and run by [`run_pending`] once it has answered the
+//! agent, so a slow command never holds up Claude's hook output. Their failures are only
+//! logged.
+
+use std::{
+    cell::RefCell,
+    io::{self, Write},
+    time::Duration,
+};
+
+use anyhow::Error;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::{configuration::Config, delivery::command, utils::unix_now};
+
+/// What an `on_sent` command reads on stdin.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct SentReport {
+    pub agent: String,
+    pub event: String,
+    pub title: String,
+    pub body: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+    /// Whether a backend delivered it.
+    pub delivered: bool,
+    /// Why it wasn't delivered, or which backends failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub sent_at: u64,
+}
+
+impl SentReport {
+    pub fn new(
+        agent: &str,
+        event: &str,
+        correlation_id: Option<&str>,
+        title: &str,
+        body: &str,
+        result: &Result<(), Error>,
+    ) -> Self {
+        SentReport {
+            agent: agent.to_string(),
+            event: event.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            correlation_id: correlation_id.map(String::from),
+            delivered: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+            sent_at: unix_now(),
+        }
+    }
+}
+
+struct PendingRun {
+    commands: Vec<Vec<String>>,
+    timeout: Duration,
+    report: SentReport,
+}
+
+thread_local! {
+    static PENDING: RefCell<Vec<PendingRun>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Queues the `on_sent` commands for `report`: the top-level one, then the agent's.
+pub fn queue(config: &Config, report: SentReport) {
+    let agent_command = match report.agent.as_str() {
+        "claude" => &config.claude.on_sent,
+        "codex" => &config.codex.on_sent,
+        _ => &Vec::new(),
+    };
+    let commands: Vec<_> = [&config.on_sent, agent_command]
+        .into_iter()
+        .filter(|command| !command.is_empty())
+        .cloned()
+        .collect();
+    if commands.is_empty() {
+        return;
+    }
+    PENDING.with_borrow_mut(|pending| {
+        pending.push(PendingRun {
+            commands,
+            timeout: Duration::from_secs(config.on_sent_timeout_secs),
+            report,
+        })
+    });
+}
+
+/// Runs the commands queued on this thread, after flushing stdout so whatever anot answered
+/// is out first. `anot` calls it before exiting; a program embedding the library calls it
+/// once it has used the output of `process_claude_input` and the like.
+pub fn run_pending() {
+    let pending = PENDING.take();
+    if pending.is_empty() {
+        return;
+    }
+    let _ = io::stdout().flush();
+
+    for run in pending {
+        let report = &run.report;
+        let stdin = match serde_json::to_vec(report) {
+            Ok(stdin) => stdin,
+            Err(e) => {
+                warn!(error = %e, "failed to describe the notification for on_sent");
+                continue;
+            }
+        };
+        for argv in &run.commands {
+            let env = [
+                ("ANOT_AGENT", report.agent.as_str()),
+                ("ANOT_EVENT", report.event.as_str()),
+            ];
+            match command::run(argv, &env, stdin.clone(), run.timeout) {
+                Ok(status) if status.success() => {
+                    debug!(command = ?argv, "ran on_sent");
+                }
+                Ok(status) => warn!(command = ?argv, %status, "on_sent failed"),
+                Err(e) => warn!(command = ?argv, error = %e, "on_sent failed"),
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use anyhow::anyhow;
+
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    /// `sh -c script` with `args` as `$1`, `$2` and so on.
+    fn sh(script: &str, args: &[&PathBuf]) -> Vec<String> {
+        let mut argv = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        argv.push("on_sent".to_string());
+        argv.extend(args.iter().map(|arg| arg.to_string_lossy().to_string()));
+        argv
+    }
+
+    #[test]
+    fn runs_the_global_and_agent_commands_with_the_report() {
+        let dir = temp_dir("on-sent", "both");
+        let (global, agent) = (dir.join("global.json"), dir.join("agent.json"));
+        let mut config = Config {
+            on_sent: sh(r#"cat > "$1""#, &[&global]),
+            ..Config::default()
+        };
+        config.codex.on_sent = sh(r#"cat > "$1"; echo "$ANOT_EVENT" >> "$1""#, &[&agent]);
+
+        let failed = Err(anyhow!("desktop: no notification daemon"));
+        queue(
+            &config,
+            SentReport::new("codex", "AgentTurnComplete", None, "Codex", "Done", &failed),
+        );
+        assert!(!global.exists());
+        run_pending();
+
+        let report: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&global).unwrap()).unwrap();
+        assert!(report["sent_at"].as_u64().unwrap() > 0);
+        assert_eq!(
+            report,
+            serde_json::json!({
+                "agent": "codex",
+                "event": "AgentTurnComplete",
+                "title": "Codex",
+                "body": "Done",
+                "delivered": false,
+                "error": "desktop: no notification daemon",
+                "sent_at": report["sent_at"],
+            })
+        );
+        assert!(
+            fs::read_to_string(&agent)
+                .unwrap()
+                .ends_with("}AgentTurnComplete\n")
+        );
+    }
+
+    #[test]
+    fn failures_and_slow_commands_are_only_logged() {
+        let config = Config {
+            on_sent: sh("exec sleep 30", &[]),
+            on_sent_timeout_secs: 1,
+            ..Config::default()
+        };
+        queue(
+            &config,
+            SentReport::new("claude", "Stop", None, "Claude", "Done", &Ok(())),
+        );
+        let started = std::time::Instant::now();
+        run_pending();
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        queue(
+            &Config::default(),
+            SentReport::new("claude", "Stop", None, "Claude", "Done", &Ok(())),
+        );
+        assert!(PENDING.with_borrow(Vec::is_empty));
+    }
+}
@@ -0,0 +1,80 @@
+//! How each notification's delivery went, kept for the hook output of the invocation that
+//! sent it. Only Claude reads them, and only with `claude.report_delivery` on.
+
+use std::cell::RefCell;
+
+use anyhow::Error;
+
+use crate::{
+    configuration::{Backend, Config, ReportDelivery},
+    utils::unix_now,
+};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Receipt {
+    pub event: String,
+    /// The backends that delivered it, in the order they were tried.
+    pub delivered_by: Vec<Backend>,
+    /// Why it wasn't delivered, or which backends failed.
+    pub error: Option<String>,
+    pub sent_at: u64,
+}
+
+thread_local! {
+    static RECEIPTS: RefCell<Vec<Receipt>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Keeps the outcome of a notification `agent` sent for `event`, when its hook output reports
+/// deliveries.
+pub fn record(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    delivered_by: &[Backend],
+    result: &Result<(), Error>,
+) {
+    if agent != "claude" || config.claude.report_delivery == ReportDelivery::None {
+        return;
+    }
+    RECEIPTS.with_borrow_mut(|receipts| {
+        receipts.push(Receipt {
+            event: event.to_string(),
+            delivered_by: delivered_by.to_vec(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+            sent_at: unix_now(),
+        })
+    });
+}
+
+/// The receipts recorded on this thread since the last call.
+pub fn take() -> Vec<Receipt> {
+    RECEIPTS.take()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_records_what_a_hook_output_reports() {
+        let mut config = Config::default();
+        take();
+
+        record(&config, "claude", "Stop", &[Backend::Tmux], &Ok(()));
+        record(
+            &config,
+            "codex",
+            "AgentTurnComplete",
+            &[Backend::Tmux],
+            &Ok(()),
+        );
+        config.claude.report_delivery = ReportDelivery::None;
+        record(&config, "claude", "Stop", &[Backend::Tmux], &Ok(()));
+
+        let receipts = take();
+        assert_eq!(receipts.len(), 1);
+        assert_eq!(receipts[0].delivered_by, [Backend::Tmux]);
+        assert_eq!(receipts[0].error, None);
+        assert!(take().is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+//! `sound_file`: a short clip of the user's own, played for a notification instead of the
+//! system sound. Clips are queued while anot delivers and played by [`play_pending`] once it
+//! has answered the agent. Playing them needs the `audio` feature; without it notifications
+//! keep the system sound. Playback failures are only logged.
+
+use std::{
+    cell::RefCell,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::Error;
+use tracing::{debug, warn};
+
+use crate::{configuration::NotificationSettings, utils::expand_tilde};
+
+/// Whether this build can play `sound_file` clips.
+pub const CAN_PLAY: bool = cfg!(feature = "audio");
+
+/// Clips are cut off after this, so a long file can't hold anot up.
+pub const MAX_CLIP: Duration = Duration::from_secs(5);
+
+/// What a notification sounds like.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Sound {
+    /// `sound` is off
+    Silent,
+    /// The notification's own sound
+    System,
+    /// The clip, without the system sound
+    File(PathBuf),
+}
+
+/// What a notification with `settings` sounds like in a build that can, or can't, play clips.
+pub fn choose(settings: &NotificationSettings, can_play: bool) -> Sound {
+    match &settings.sound_file {
+        _ if !settings.sound => Sound::Silent,
+        Some(path) if can_play => Sound::File(expand_tilde(path)),
+        _ => Sound::System,
+    }
+}
+
+struct PendingClip {
+    path: PathBuf,
+    volume: f32,
+}
+
+thread_local! {
+    static PENDING: RefCell<Vec<PendingClip>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Queues `sound` to be played at `volume`, when it's a clip.
+pub fn queue(sound: &Sound, volume: f32) {
+    if let Sound::File(path) = sound {
+        PENDING.with_borrow_mut(|pending| {
+            pending.push(PendingClip {
+                path: path.clone(),
+                volume,
+            })
+        });
+    }
+}
+
+/// Plays the clips queued on this thread, one after the other. `anot` calls it before exiting,
+/// after the `on_sent` commands.
+pub fn play_pending() {
+    for clip in PENDING.take() {
+        match play(&clip.path, clip.volume) {
+            Ok(()) => debug!(path = %clip.path.display(), "played sound_file"),
+            Err(e) => warn!(
+                path = %clip.path.display(),
+                error = %format_args!("{e:#}"),
+                "failed to play sound_file"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "audio")]
+fn play(path: &Path, volume: f32) -> Result<(), Error> {
+    use std::{fs::File, io::BufReader};
+
+    use rodio::{Decoder, OutputStream, Sink, Source};
+
+    let clip = Decoder::new(BufReader::new(File::open(path)?))?;
+    // Dropping the stream stops playback, so it's kept until the clip is done.
+    let (_stream, handle) = OutputStream::try_default()?;
+    let sink = Sink::try_new(&handle)?;
+    sink.set_volume(volume);
+    sink.append(clip.take_duration(MAX_CLIP));
+    sink.sleep_until_end();
+    Ok(())
+}
+
+#[cfg(not(feature = "audio"))]
+fn play(_path: &Path, _volume: f32) -> Result<(), Error> {
+    anyhow::bail!("anot was built without the `audio` feature")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::{EventOverride, Pretend};
+
+    fn settings(sound: bool, sound_file: Option<&str>) -> NotificationSettings {
+        NotificationSettings {
+            pretend: Pretend::Never,
+            sound,
+            sound_file: sound_file.map(PathBuf::from),
+            icon: None,
+        }
+    }
+
+    #[test]
+    fn a_clip_replaces_the_system_sound_when_it_can_play() {
+        assert_eq!(
+            choose(&settings(true, Some("/sounds/ding.wav")), true),
+            Sound::File(PathBuf::from("/sounds/ding.wav"))
+        );
+        assert_eq!(
+            choose(&settings(true, Some("/sounds/ding.wav")), false),
+            Sound::System,
+            "builds without `audio` keep the system sound"
+        );
+        assert_eq!(choose(&settings(true, None), true), Sound::System);
+    }
+
+    #[test]
+    fn sound_off_silences_the_clip_too() {
+        assert_eq!(
+            choose(&settings(false, Some("/sounds/ding.wav")), true),
+            Sound::Silent
+        );
+        assert_eq!(choose(&settings(false, None), true), Sound::Silent);
+    }
+
+    #[test]
+    fn the_clip_is_picked_per_event() {
+        let mut config = crate::Config::default();
+        config.claude.overrides.insert(
+            "Stop".to_string(),
+            EventOverride {
+                sound_file: Some(PathBuf::from("/sounds/ding.mp3")),
+                ..EventOverride::default()
+            },
+        );
+        config.claude.overrides.insert(
+            "PostToolUse".to_string(),
+            EventOverride {
+                sound: Some(false),
+                ..EventOverride::default()
+            },
+        );
+
+        let sound =
+            |config: &crate::Config, event| choose(&config.claude.settings_for(event), true);
+        assert_eq!(
+            sound(&config, "Stop"),
+            Sound::File(PathBuf::from("/sounds/ding.mp3"))
+        );
+        assert_eq!(sound(&config, "Notification"), Sound::System);
+        assert_eq!(sound(&config, "PostToolUse"), Sound::Silent);
+
+        config.claude.sound_file = Some(PathBuf::from("/sounds/all.wav"));
+        assert_eq!(
+            sound(&config, "Notification"),
+            Sound::File(PathBuf::from("/sounds/all.wav"))
+        );
+        assert_eq!(
+            sound(&config, "Stop"),
+            Sound::File(PathBuf::from("/sounds/ding.mp3")),
+            "the event's clip wins"
+        );
+    }
+
+    #[test]
+    fn only_clips_are_queued() {
+        queue(&Sound::System, 1.0);
+        queue(&Sound::Silent, 1.0);
+        assert!(PENDING.with_borrow(|pending| pending.is_empty()));
+
+        queue(&Sound::File(PathBuf::from("/sounds/ding.wav")), 0.5);
+        let pending = PENDING.take();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].volume, 0.5);
+    }
+}
@@ -0,0 +1,154 @@
+//! Do Not Disturb detection for `respect_dnd`: macOS Focus, and the `Inhibited` property of the
+//! freedesktop notification server on Linux.
+
+use anyhow::Error;
+use serde_json::Value;
+use tracing::{debug, warn};
+
+/// Whether the system is in Do Not Disturb (or a Focus) right now.
+///
+/// Fails open: when the state can't be determined, this returns `false` so the notification is
+/// still shown.
+pub fn is_active() -> bool {
+    match detect() {
+        Ok(active) => {
+            debug!(active, "Do Not Disturb state");
+            active
+        }
+        Err(e) => {
+            warn!(error = %e, "failed to detect Do Not Disturb");
+            false
+        }
+    }
+}
+
+/// Reads the Focus assertions macOS Monterey and later keep for manually enabled Focus modes,
+/// falling back to the `doNotDisturb` preference of older releases. Focus modes turned on by a
+/// schedule aren't recorded there.
+#[cfg(target_os = "macos")]
+fn detect() -> Result<bool, Error> {
+    use std::{fs, io::ErrorKind};
+
+    let assertions = dirs::home_dir()
+        .unwrap_or_default()
+        .join("Library/DoNotDisturb/DB/Assertions.json");
+    match fs::read_to_string(&assertions) {
+        Ok(contents) => parse_assertions(&contents),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(parse_defaults(&crate::focus::run(
+            "defaults",
+            &[
+                "-currentHost",
+                "read",
+                "com.apple.notificationcenterui",
+                "doNotDisturb",
+            ],
+        )?)),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(all(unix, not(target_os = "macos")))]
+fn detect() -> Result<bool, Error> {
+    const DEST: &str = "org.freedesktop.Notifications";
+    const PATH: &str = "/org/freedesktop/Notifications";
+
+    match crate::focus::run(
+        "busctl",
+        &["--user", "get-property", DEST, PATH, DEST, "Inhibited"],
+    ) {
+        Ok(output) => parse_busctl(&output),
+        Err(e) => {
+            debug!(error = %e, "busctl failed; trying gdbus");
+            parse_gdbus(&crate::focus::run(
+                "gdbus",
+                &[
+                    "call",
+                    "--session",
+                    "--dest",
+                    DEST,
+                    "--object-path",
+                    PATH,
+                    "--method",
+                    "org.freedesktop.DBus.Properties.Get",
+                    DEST,
+                    "Inhibited",
+                ],
+            )?)
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn detect() -> Result<bool, Error> {
+    Ok(false)
+}
+
+/// An active Focus shows up as a non-empty `storeAssertionRecords` list in
+/// `{"data": [{"storeAssertionRecords": [...]}]}`.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_assertions(contents: &str) -> Result<bool, Error> {
+    let assertions: Value = serde_json::from_str(contents)?;
+
+    Ok(assertions["data"].as_array().is_some_and(|data| {
+        data.iter().any(|entry| {
+            entry["storeAssertionRecords"]
+                .as_array()
+                .is_some_and(|records| !records.is_empty())
+        })
+    }))
+}
+
+/// `defaults read` prints `1` while Do Not Disturb is on.
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn parse_defaults(output: &str) -> bool {
+    output.trim() == "1"
+}
+
+/// Parses `b true`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_busctl(output: &str) -> Result<bool, Error> {
+    match output.trim() {
+        "b true" => Ok(true),
+        "b false" => Ok(false),
+        other => Err(Error::msg(format!("unexpected busctl output: {other:?}"))),
+    }
+}
+
+/// Parses `(<true>,)`.
+#[cfg_attr(not(all(unix, not(target_os = "macos"))), allow(dead_code))]
+fn parse_gdbus(output: &str) -> Result<bool, Error> {
+    match output.trim() {
+        "(<true>,)" => Ok(true),
+        "(<false>,)" => Ok(false),
+        other => Err(Error::msg(format!("unexpected gdbus output: {other:?}"))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_macos_focus_assertions() {
+        let active = r#"{"data":[{"storeAssertionRecords":[{"assertionDetails":{
+            "assertionDetailsModeIdentifier":"com.apple.donotdisturb.mode.default"}}]}]}"#;
+        assert!(parse_assertions(active).unwrap());
+        assert!(!parse_assertions(r#"{"data":[{"storeAssertionRecords":[]}]}"#).unwrap());
+        assert!(!parse_assertions(r#"{"data":[{}]}"#).unwrap());
+        assert!(parse_assertions("not json").is_err());
+
+        assert!(parse_defaults("1\n"));
+        assert!(!parse_defaults("0"));
+    }
+
+    #[test]
+    fn parses_inhibited_property() {
+        assert!(parse_busctl("b true\n").unwrap());
+        assert!(!parse_busctl("b false").unwrap());
+        assert!(parse_busctl("").is_err());
+
+        assert!(parse_gdbus("(<true>,)").unwrap());
+        assert!(!parse_gdbus("(<false>,)\n").unwrap());
+        assert!(parse_gdbus("Error: No such property").is_err());
+    }
+}
@@ -0,0 +1,216 @@
+//! The failures anot reports to the user. Each keeps what caused it as its source, so `{:#}`
+//! prints the whole chain down to the OS or parser error, and maps to one of the exit codes
+//! the README documents.
+
+use std::{io, path::PathBuf};
+
+use inquire::InquireError;
+
+use crate::configuration::Backend;
+
+/// Exit code for errors that aren't an [`AnotError`].
+pub const EXIT_FAILURE: i32 = 1;
+
+/// Exit code after a panic, as Rust reports one.
+pub const EXIT_CRASH: i32 = 101;
+
+#[derive(Debug, thiserror::Error)]
+pub enum AnotError {
+    /// A config file, anot's or an agent's, couldn't be read.
+    #[error("Can't read config {}", path.display())]
+    ConfigRead {
+        path: PathBuf,
+        #[source]
+        source: io::Error,
+    },
+    /// A config file, anot's or an agent's, isn't valid JSON or TOML, or has invalid settings.
+    #[error("Invalid config {}", path.display())]
+    ConfigParse {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// An agent's config (Claude Code settings, Codex `config.toml`, the OpenCode plugin)
+    /// couldn't be written.
+    #[error("Failed to write {}", path.display())]
+    AgentConfigWrite {
+        path: PathBuf,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// A backend failed to deliver a notification.
+    #[error("{}", backend.as_str())]
+    Delivery {
+        backend: Backend,
+        #[source]
+        source: anyhow::Error,
+    },
+    /// A prompt of `anot init` failed, e.g. because there's no terminal to show it on.
+    #[error("{context}")]
+    Prompt {
+        context: String,
+        #[source]
+        source: InquireError,
+    },
+    /// The user cancelled or interrupted a prompt, or declined to go ahead.
+    #[error("Operation {} by user", if *.interrupted { "interrupted" } else { "cancelled" })]
+    Cancelled { interrupted: bool },
+    /// Processing a hook payload panicked.
+    #[error("anot crashed: {message}")]
+    Crashed { message: String },
+    #[error("{context}")]
+    Io {
+        context: String,
+        #[source]
+        source: io::Error,
+    },
+}
+
+impl AnotError {
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        AnotError::Io {
+            context: context.into(),
+            source,
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AnotError::ConfigRead { .. } | AnotError::ConfigParse { .. } => 3,
+            AnotError::AgentConfigWrite { .. } => 4,
+            AnotError::Delivery { .. } => 5,
+            AnotError::Prompt { .. } => 6,
+            AnotError::Io { .. } => 7,
+            AnotError::Crashed { .. } => EXIT_CRASH,
+            // As a shell reports a command stopped with Ctrl-C.
+            AnotError::Cancelled { .. } => 130,
+        }
+    }
+
+    /// What the user can do about it, when there's something to suggest.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            AnotError::ConfigRead { .. } => {
+                Some("check that the file is readable, or pass another one with --config")
+            }
+            AnotError::ConfigParse { .. } => {
+                Some("fix the setting named above, or run `anot reset` to go back to the defaults")
+            }
+            AnotError::AgentConfigWrite { source, .. } if is_permission_denied(source) => Some(
+                "this file needs elevated permissions: re-run the command with sudo (or as an \
+                 administrator), or pick a user or project settings file instead",
+            ),
+            AnotError::Delivery { .. } => Some("run `anot test` to see what each backend does"),
+            AnotError::Prompt { .. } => Some(
+                "`anot init` asks in the terminal; pass the path and options as arguments to \
+                 skip the prompts",
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// `error` from a prompt, with `context` saying which one unless the user cancelled it.
+pub fn prompt_error(error: InquireError, context: &str) -> AnotError {
+    match error {
+        InquireError::OperationCanceled => AnotError::Cancelled { interrupted: false },
+        InquireError::OperationInterrupted => AnotError::Cancelled { interrupted: true },
+        source => AnotError::Prompt {
+            context: context.to_string(),
+            source,
+        },
+    }
+}
+
+/// The first [`AnotError`] in `error`'s chain.
+pub fn find(error: &anyhow::Error) -> Option<&AnotError> {
+    error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<AnotError>())
+}
+
+/// The exit code `anot` stops with after `error`.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    find(error).map_or(EXIT_FAILURE, AnotError::exit_code)
+}
+
+fn is_permission_denied(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<io::Error>())
+        .any(|io| io.kind() == io::ErrorKind::PermissionDenied)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    #[test]
+    fn the_chain_keeps_the_cause() {
+        let error = anyhow::Error::new(AnotError::ConfigRead {
+            path: PathBuf::from("/etc/anot.json"),
+            source: io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            ),
+        });
+        assert_eq!(
+            format!("{error:#}"),
+            "Can't read config /etc/anot.json: stream did not contain valid UTF-8"
+        );
+        assert_eq!(exit_code(&error), 3);
+    }
+
+    #[test]
+    fn exit_codes_follow_the_first_anot_error() {
+        let plain = anyhow::anyhow!("something else");
+        assert_eq!(exit_code(&plain), EXIT_FAILURE);
+
+        let wrapped = anyhow::Error::new(AnotError::Delivery {
+            backend: Backend::Tmux,
+            source: anyhow::anyhow!("not inside tmux"),
+        })
+        .context("anot test");
+        assert_eq!(exit_code(&wrapped), 5);
+        assert_eq!(format!("{wrapped:#}"), "anot test: tmux: not inside tmux");
+    }
+
+    #[test]
+    fn cancelled_prompts_are_not_failures_of_the_prompt() {
+        let cancelled = prompt_error(
+            InquireError::OperationCanceled,
+            "Failed to get confirmation",
+        );
+        assert_eq!(cancelled.to_string(), "Operation cancelled by user");
+        assert_eq!(cancelled.exit_code(), 130);
+
+        let failed = prompt_error(InquireError::NotTTY, "Failed to get confirmation");
+        assert_eq!(
+            format!("{:#}", anyhow::Error::new(failed)),
+            "Failed to get confirmation: The input device is not a TTY"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permission_denied_writes_show_the_os_error() {
+        let path = Path::new("/etc/claude-code/managed-settings.json");
+        let denied = AnotError::AgentConfigWrite {
+            path: path.to_path_buf(),
+            source: io::Error::from_raw_os_error(libc::EACCES).into(),
+        };
+        assert!(denied.hint().unwrap().contains("sudo"));
+        assert_eq!(
+            format!("{:#}", anyhow::Error::new(denied)),
+            "Failed to write /etc/claude-code/managed-settings.json: Permission denied (os error 13)"
+        );
+
+        let full = AnotError::AgentConfigWrite {
+            path: path.to_path_buf(),
+            source: io::Error::from(io::ErrorKind::StorageFull).into(),
+        };
+        assert_eq!(full.hint(), None);
+    }
+}
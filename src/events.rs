@@ -0,0 +1,351 @@
+//! `events.jsonl`, with `events_log`: one JSON object per event anot processed, whether it
+//! notified or not. Unlike the logs and the history, its format is kept stable for programs
+//! built on top of anot, such as status bar widgets.
+
+use std::{
+    cell::RefCell,
+    fs::File,
+    io::{ErrorKind, Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    configuration::{Backend, Config},
+    history,
+    utils::{local_clock, unix_now},
+};
+
+const EVENTS_FILE_NAME: &str = "events.jsonl";
+
+/// The `schema_version` of every record. It changes when a field is removed or changes
+/// meaning; new fields may be added without it changing.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// What anot did with an event.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Decision {
+    /// A backend delivered the notification
+    Sent,
+    /// No backend delivered it
+    Failed,
+    /// The config, `filter_command` or sampling left it out
+    Filtered,
+    /// It repeated a recent notification
+    Duplicate,
+    /// A mute, quiet hours or Do Not Disturb held it back
+    Held,
+}
+
+impl Decision {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Decision::Sent => "sent",
+            Decision::Failed => "failed",
+            Decision::Filtered => "filtered",
+            Decision::Duplicate => "duplicate",
+            Decision::Held => "held",
+        }
+    }
+}
+
+/// One line of `events.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventRecord {
+    pub schema_version: u32,
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub agent: String,
+    pub event: String,
+    pub decision: Decision,
+    /// Why it wasn't sent, e.g. "quiet hours"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_id: Option<String>,
+    /// The backends that delivered it, in the order they were tried
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub delivered_by: Vec<Backend>,
+    /// Which backends failed, and why
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl EventRecord {
+    fn new(agent: &str, event: &str, decision: Decision) -> Self {
+        EventRecord {
+            schema_version: SCHEMA_VERSION,
+            timestamp: unix_now(),
+            agent: agent.to_string(),
+            event: event.to_string(),
+            decision,
+            reason: None,
+            title: None,
+            body: None,
+            session_id: None,
+            turn_id: None,
+            delivered_by: Vec::new(),
+            error: None,
+        }
+    }
+
+    /// A notification handed to the backends, which delivered it or failed with `result`.
+    pub fn delivered(
+        agent: &str,
+        event: &str,
+        title: &str,
+        body: &str,
+        delivered_by: &[Backend],
+        result: &Result<(), Error>,
+    ) -> Self {
+        let decision = if result.is_ok() {
+            Decision::Sent
+        } else {
+            Decision::Failed
+        };
+        EventRecord {
+            title: Some(title.to_string()),
+            body: Some(body.to_string()),
+            delivered_by: delivered_by.to_vec(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+            ..EventRecord::new(agent, event, decision)
+        }
+    }
+
+    /// An event that didn't notify, because of `reason`.
+    pub fn suppressed(agent: &str, event: &str, decision: Decision, reason: &str) -> Self {
+        EventRecord {
+            reason: Some(reason.to_string()),
+            ..EventRecord::new(agent, event, decision)
+        }
+    }
+}
+
+/// The session and turn the payload being handled on this thread came from.
+#[derive(Debug, Clone, Default)]
+struct Ids {
+    session_id: Option<String>,
+    turn_id: Option<String>,
+}
+
+thread_local! {
+    static IDS: RefCell<Ids> = RefCell::new(Ids::default());
+}
+
+/// Sets the ids of the payload being handled, for the records appended while handling it.
+pub fn identify(session_id: Option<&str>, turn_id: Option<&str>) {
+    IDS.set(Ids {
+        session_id: session_id.map(str::to_string),
+        turn_id: turn_id.map(str::to_string),
+    });
+}
+
+pub fn events_path(config: &Config) -> PathBuf {
+    config.data_dir().join(EVENTS_FILE_NAME)
+}
+
+/// Appends `record` with the ids set by [`identify`], when `events_log` is on. Like the
+/// history, it's rotated at `history.max_bytes`, and failures are only logged.
+pub fn record(config: &Config, mut record: EventRecord) {
+    if !config.events_log || config.dry_run {
+        return;
+    }
+    IDS.with_borrow(|ids| {
+        record.session_id = record.session_id.take().or_else(|| ids.session_id.clone());
+        record.turn_id = record.turn_id.take().or_else(|| ids.turn_id.clone());
+    });
+
+    let path = events_path(config);
+    if let Err(e) = history::append_entry(&path, &record, config.history.max_bytes) {
+        warn!(path = %path.display(), error = %e, "failed to append to the events log");
+    }
+}
+
+/// Reads the lines appended to a file since it was last read, as `tail -f` does.
+#[derive(Debug)]
+pub struct Follower {
+    path: PathBuf,
+    offset: u64,
+    /// The start of a line still being written.
+    partial: Vec<u8>,
+}
+
+impl Follower {
+    /// Follows `path` from its start.
+    pub fn new(path: &Path) -> Self {
+        Follower {
+            path: path.to_path_buf(),
+            offset: 0,
+            partial: Vec::new(),
+        }
+    }
+
+    /// The complete lines appended since the last call. A file that got shorter was rotated,
+    /// and is read again from its start.
+    pub fn poll(&mut self) -> Result<Vec<String>, Error> {
+        let mut file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        if file.metadata()?.len() < self.offset {
+            self.offset = 0;
+            self.partial.clear();
+        }
+        file.seek(SeekFrom::Start(self.offset))?;
+        let read = file.read_to_end(&mut self.partial)?;
+        self.offset += read as u64;
+
+        let Some(end) = self.partial.iter().rposition(|&byte| byte == b'\n') else {
+            return Ok(Vec::new());
+        };
+        let rest = self.partial.split_off(end + 1);
+        let complete = std::mem::replace(&mut self.partial, rest);
+        Ok(complete
+            .split(|&byte| byte == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| String::from_utf8_lossy(line).into_owned())
+            .collect())
+    }
+}
+
+/// One line of the events log, or why it isn't a record this version of anot understands.
+pub fn parse_line(line: &str) -> Result<EventRecord, serde_json::Error> {
+    serde_json::from_str(line)
+}
+
+/// `record` as `anot events follow` prints it: the time, agent, event and decision, then the
+/// title and body or why it wasn't sent.
+pub fn format_record(record: &EventRecord) -> String {
+    let mut header = format!(
+        "{}  {:<8}  {:<16}  {:<9}",
+        local_clock(record.timestamp),
+        record.agent,
+        record.event,
+        record.decision.as_str()
+    );
+    if let Some(title) = &record.title {
+        header.push_str("  ");
+        header.push_str(title);
+    }
+    let detail = match (&record.reason, &record.error, &record.body) {
+        (Some(reason), _, _) => reason.clone(),
+        (_, Some(error), _) => error.clone(),
+        (_, _, Some(body)) => body.replace('\n', " "),
+        _ => return header.trim_end().to_string(),
+    };
+    format!("{}\n{:>7}{detail}", header.trim_end(), "")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io::Write};
+
+    use super::*;
+    use crate::test_support;
+
+    fn temp_config(test_name: &str) -> Config {
+        Config {
+            events_log: true,
+            ..test_support::temp_config("events", test_name)
+        }
+    }
+
+    #[test]
+    fn every_record_names_its_schema_version() {
+        let mut config = temp_config("schema");
+        identify(Some("3f2a9c1b"), None);
+        record(
+            &config,
+            EventRecord::delivered(
+                "claude",
+                "Stop",
+                "Claude Code · Finished",
+                "Done",
+                &[Backend::Tmux],
+                &Ok(()),
+            ),
+        );
+        record(
+            &config,
+            EventRecord::suppressed("claude", "PostToolUse", Decision::Held, "quiet hours"),
+        );
+        config.events_log = false;
+        record(
+            &config,
+            EventRecord::suppressed("claude", "Stop", Decision::Filtered, "not in claude.events"),
+        );
+        identify(None, None);
+
+        let contents = fs::read_to_string(events_path(&config)).unwrap();
+        let lines: Vec<serde_json::Value> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line["schema_version"] == 1));
+        assert_eq!(lines[0]["decision"], "sent");
+        assert_eq!(lines[0]["delivered_by"], serde_json::json!(["tmux"]));
+        assert_eq!(lines[0]["session_id"], "3f2a9c1b");
+        assert_eq!(lines[1]["decision"], "held");
+        assert_eq!(lines[1]["reason"], "quiet hours");
+        assert!(lines[1].get("title").is_none());
+    }
+
+    #[test]
+    fn follow_reads_only_complete_lines() {
+        let config = temp_config("follow");
+        let path = events_path(&config);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let mut follower = Follower::new(&path);
+        assert!(follower.poll().unwrap().is_empty());
+
+        let line = serde_json::to_string(&EventRecord::suppressed(
+            "codex",
+            "AgentTurnComplete",
+            Decision::Duplicate,
+            "repeat of a recently notified turn",
+        ))
+        .unwrap();
+        let (start, end) = line.split_at(20);
+        let mut file = File::create(&path).unwrap();
+        write!(file, "{line}\n{start}").unwrap();
+        let lines = follower.poll().unwrap();
+        assert_eq!(lines, [line.as_str()]);
+        assert_eq!(parse_line(&lines[0]).unwrap().decision, Decision::Duplicate);
+
+        writeln!(file, "{end}").unwrap();
+        assert_eq!(follower.poll().unwrap(), [line.as_str()]);
+        assert!(follower.poll().unwrap().is_empty());
+
+        // Rotated away: the new file is read from its start.
+        fs::write(&path, "not json\n").unwrap();
+        let lines = follower.poll().unwrap();
+        assert_eq!(lines, ["not json"]);
+        assert!(parse_line(&lines[0]).is_err());
+    }
+
+    #[test]
+    fn parses_records_with_fields_added_later() {
+        let record = parse_line(
+            r#"{"schema_version":1,"timestamp":0,"agent":"claude","event":"Stop",
+                "decision":"filtered","reason":"sampled away","priority":"high"}"#,
+        )
+        .unwrap();
+        assert_eq!(record.decision, Decision::Filtered);
+        assert_eq!(
+            format_record(&record).lines().nth(1).map(str::trim),
+            Some("sampled away")
+        );
+    }
+}
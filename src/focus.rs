@@ -0,0 +1,148 @@
+use std::process::Command;
+
+use anyhow::{Error, bail};
+use tracing::{debug, warn};
+
+/// Whether one of `terminal_apps` is the focused application.
+///
+/// Fails open: when the focused application can't be determined, this returns `false` so the
+/// notification is still shown.
+pub fn terminal_is_focused(terminal_apps: &[String]) -> bool {
+    is_focused(frontmost_app(), terminal_apps)
+}
+
+/// `frontmost` holds the identifiers of the focused application (a bundle id on macOS, the
+/// WM_CLASS instance and class on X11). An empty list means detection isn't supported here.
+fn is_focused(frontmost: Result<Vec<String>, Error>, terminal_apps: &[String]) -> bool {
+    let identifiers = match frontmost {
+        Ok(identifiers) => identifiers,
+        Err(e) => {
+            warn!(error = %e, "failed to detect the focused application");
+            return false;
+        }
+    };
+    debug!(frontmost = ?identifiers, "focused application");
+
+    identifiers
+        .iter()
+        .any(|id| terminal_apps.iter().any(|app| app.eq_ignore_ascii_case(id)))
+}
+
+pub(crate) fn run(program: &str, args: &[&str]) -> Result<String, Error> {
+    let output = Command::new(program).args(args).output()?;
+    if !output.status.success() {
+        bail!(
+            "{program} exited with {}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(target_os = "macos")]
+fn frontmost_app() -> Result<Vec<String>, Error> {
+    let bundle_id = run(
+        "osascript",
+        &[
+            "-e",
+            "id of application (path to frontmost application as text)",
+        ],
+    )?;
+
+    Ok(vec![bundle_id])
+}
+
+#[cfg(not(target_os = "macos"))]
+fn frontmost_app() -> Result<Vec<String>, Error> {
+    // There is no portable way to ask a Wayland compositor for the focused window.
+    if std::env::var_os("WAYLAND_DISPLAY").is_some() || std::env::var_os("DISPLAY").is_none() {
+        return Ok(Vec::new());
+    }
+
+    let active = run("xprop", &["-root", "_NET_ACTIVE_WINDOW"])?;
+    let Some(window_id) = parse_active_window(&active) else {
+        return Ok(Vec::new());
+    };
+
+    Ok(parse_wm_class(&run(
+        "xprop",
+        &["-id", &window_id, "WM_CLASS"],
+    )?))
+}
+
+/// Parses `_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007`.
+#[cfg(not(target_os = "macos"))]
+fn parse_active_window(output: &str) -> Option<String> {
+    let id = output.rsplit(' ').next()?.trim();
+    (id.starts_with("0x") && id != "0x0").then(|| id.to_string())
+}
+
+/// Parses `WM_CLASS(STRING) = "kitty", "kitty"` into its quoted values.
+#[cfg(not(target_os = "macos"))]
+fn parse_wm_class(output: &str) -> Vec<String> {
+    output
+        .split_once('=')
+        .map(|(_, values)| {
+            values
+                .split(',')
+                .map(|v| v.trim().trim_matches('"').to_string())
+                .filter(|v| !v.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apps() -> Vec<String> {
+        vec!["com.apple.Terminal".to_string(), "kitty".to_string()]
+    }
+
+    #[test]
+    fn focused_when_frontmost_is_a_terminal() {
+        assert!(is_focused(
+            Ok(vec!["com.apple.terminal".to_string()]),
+            &apps()
+        ));
+        assert!(is_focused(
+            Ok(vec!["kitty".to_string(), "kitty".to_string()]),
+            &apps()
+        ));
+    }
+
+    #[test]
+    fn not_focused_for_other_apps_or_unknown() {
+        assert!(!is_focused(
+            Ok(vec!["com.apple.Safari".to_string()]),
+            &apps()
+        ));
+        assert!(!is_focused(Ok(Vec::new()), &apps()));
+    }
+
+    #[test]
+    fn fails_open_on_detection_error() {
+        assert!(!is_focused(Err(Error::msg("osascript not found")), &apps()));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn parses_xprop_output() {
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x3a00007").as_deref(),
+            Some("0x3a00007")
+        );
+        assert_eq!(
+            parse_active_window("_NET_ACTIVE_WINDOW(WINDOW): window id # 0x0"),
+            None
+        );
+        assert_eq!(
+            parse_wm_class(r#"WM_CLASS(STRING) = "gnome-terminal-server", "Gnome-terminal""#),
+            ["gnome-terminal-server", "Gnome-terminal"]
+        );
+        assert!(parse_wm_class("WM_CLASS:  not found.").is_empty());
+    }
+}
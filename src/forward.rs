@@ -0,0 +1,300 @@
+//! `forward`: relays an agent's raw payloads to `anot serve` on another machine, e.g. from a
+//! headless box to the laptop that should show the notification. Relaying runs as a detached
+//! delivery, so the hook answers right away.
+//!
+//! Payloads that couldn't be relayed wait in `forward.jsonl` in the state directory, in the
+//! format of the [spool](crate::spool), and are relayed again, oldest first, ahead of the next
+//! one.
+
+use std::path::PathBuf;
+
+use anyhow::Error;
+use tracing::{info, warn};
+
+use crate::{
+    configuration::{Config, Forward, ForwardMode},
+    delivery::{
+        detached::{self, DeliveryJob},
+        dry_run::DryRunReport,
+    },
+    privacy,
+    processors::{claude::structs::HookInput, codex::payload},
+    spool::{self, SpooledNotification},
+    storage::with_lock,
+    utils::unix_now,
+};
+
+const SPOOL_FILE_NAME: &str = "forward.jsonl";
+
+/// The `forward` settings of `agent`, if its payloads are relayed.
+pub fn settings<'a>(config: &'a Config, agent: &str) -> Option<&'a Forward> {
+    match agent {
+        "claude" => config.claude.forward.as_ref(),
+        "codex" => config.codex.forward.as_ref(),
+        _ => None,
+    }
+}
+
+pub fn spool_path(config: &Config) -> PathBuf {
+    config.data_dir().join(SPOOL_FILE_NAME)
+}
+
+/// Starts relaying `input`, redacted as `privacy` and `forward.content` ask, when `agent` has
+/// `forward` set.
+/// Returns whether that's all there is to do, i.e. with `mode: replace`; with `mode: also` (or
+/// no `forward`) this machine notifies as well.
+pub fn hand_off(config: &Config, agent: &str, input: &str) -> bool {
+    let Some(forward) = settings(config, agent) else {
+        return false;
+    };
+    let input = privacy::redact_payload_to(config, input, forward.content);
+    let job = DeliveryJob {
+        agent: agent.to_string(),
+        input: input.clone(),
+        forward: true,
+        remote: config.remote,
+    };
+    if let Err(e) = detached::spawn_job_or(config, job, || relay(config, agent, &input)) {
+        warn!(agent, url = %forward.url, error = %e, "failed to forward the payload");
+    }
+    forward.mode == ForwardMode::Replace
+}
+
+/// Relays whatever waits in the forward spool, then `input`. Stops at the first failure and
+/// keeps that payload and the ones after it for the next attempt.
+pub fn relay(config: &Config, agent: &str, input: &str) -> Result<(), Error> {
+    let event = event_of(agent, input);
+    if config.dry_run {
+        if let Some(forward) = settings(config, agent) {
+            DryRunReport::forwarded(agent, &event, &forward.url).print();
+        }
+        return Ok(());
+    }
+
+    let path = spool_path(config);
+    let new = SpooledNotification {
+        spooled_at: unix_now(),
+        agent: agent.to_string(),
+        event,
+        reason: Some("not forwarded".to_string()),
+        input: input.to_string(),
+        remote: config.remote,
+    };
+    // The lock is held until the spool is rewritten, so a relay from a concurrent hook waits
+    // instead of sending a payload ahead of older ones or one this relay is still sending.
+    with_lock(&path, || {
+        let mut pending = spool::read(&path)?;
+        pending.push(new);
+
+        let count = pending.len();
+        for (sent, entry) in pending.iter().enumerate() {
+            if let Err(e) = send(config, entry) {
+                let kept = &pending[sent..];
+                spool::replace(&path, kept)?;
+                warn!(kept = kept.len(), error = %e, "forwarding failed; will retry with the next event");
+                return Err(e);
+            }
+            info!(agent = %entry.agent, event = %entry.event, "forwarded payload");
+        }
+        spool::replace(&path, &[])?;
+        if count > 1 {
+            info!(count, "forwarded the payloads that were waiting");
+        }
+        Ok(())
+    })
+}
+
+#[cfg(feature = "http")]
+fn send(config: &Config, entry: &SpooledNotification) -> Result<(), Error> {
+    use crate::{backends::http, configuration::Http};
+
+    let Some(forward) = settings(config, &entry.agent) else {
+        warn!(agent = %entry.agent, "dropping a payload that no longer has a `forward` to go to");
+        return Ok(());
+    };
+    // Failures are retried with the next event rather than here, so the relay stays short.
+    let config = Config {
+        http: Http {
+            retries: 0,
+            timeout_secs: forward.timeout_secs,
+            max_total_secs: forward.timeout_secs,
+        },
+        ..config.clone()
+    };
+    let authorization = forward
+        .token
+        .as_ref()
+        .map(|token| format!("Bearer {token}"));
+    let mut headers = vec![("Content-Type", "application/json")];
+    if let Some(authorization) = &authorization {
+        headers.push(("Authorization", authorization));
+    }
+    http::post(&config, &forward.url, &headers, entry.input.as_bytes())
+}
+
+#[cfg(not(feature = "http"))]
+fn send(_config: &Config, _entry: &SpooledNotification) -> Result<(), Error> {
+    anyhow::bail!("`forward` needs anot built with the `http` feature")
+}
+
+/// The event name the payload carries, for logs and the spool. Empty when it doesn't parse;
+/// the receiving end reports that.
+fn event_of(agent: &str, input: &str) -> String {
+    match agent {
+        "claude" => serde_json::from_str::<HookInput>(input)
+            .map(|hook_input| hook_input.hook_event_name.as_str().to_string())
+            .unwrap_or_default(),
+        "codex" => payload::parse(input)
+            .map(|(payload, _)| payload.r#type.as_str().to_string())
+            .unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+#[cfg(all(test, feature = "http"))]
+mod tests {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread,
+        time::Duration,
+    };
+
+    use super::*;
+    use crate::{configuration::ContentLevel, test_support::temp_path};
+
+    const STOP: &str =
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+    const NOTIFICATION: &str = r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Notification","message":"Needs you"}"#;
+
+    /// Accepts `count` requests on `listener`, answering 200 after `delay`, and returns their
+    /// bodies and `Authorization` headers.
+    fn serve(
+        listener: &TcpListener,
+        count: usize,
+        delay: Duration,
+    ) -> Vec<(String, Option<String>)> {
+        (0..count)
+            .map(|_| {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                let (mut length, mut authorization) = (0, None);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    let (name, value) = line.split_once(':').unwrap_or((line, ""));
+                    match name.to_lowercase().as_str() {
+                        "content-length" => length = value.trim().parse().unwrap(),
+                        "authorization" => authorization = Some(value.trim().to_string()),
+                        _ => {}
+                    }
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                thread::sleep(delay);
+                write!(
+                    reader.into_inner(),
+                    "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                )
+                .unwrap();
+                (String::from_utf8(body).unwrap(), authorization)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn keeps_payloads_while_the_server_is_down_and_relays_them_later() {
+        let dir = temp_path("forward", "down");
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let mut config = Config {
+            location: Some(dir.join("a-notifications.json")),
+            ..Config::default()
+        };
+        config.claude.forward = Some(Forward {
+            url: format!("http://{address}/claude"),
+            token: Some("s3cret".to_string()),
+            mode: ForwardMode::Replace,
+            content: ContentLevel::Full,
+            timeout_secs: 1,
+        });
+
+        drop(listener);
+        assert!(relay(&config, "claude", STOP).is_err());
+        let waiting = spool::read(&spool_path(&config)).unwrap();
+        assert_eq!(waiting.len(), 1);
+        assert_eq!(
+            (waiting[0].event.as_str(), waiting[0].input.as_str()),
+            ("Stop", STOP)
+        );
+
+        let listener = TcpListener::bind(address).unwrap();
+        let server = thread::spawn(move || serve(&listener, 2, Duration::ZERO));
+        relay(&config, "claude", NOTIFICATION).unwrap();
+
+        let bearer = Some("Bearer s3cret".to_string());
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                (STOP.to_string(), bearer.clone()),
+                (NOTIFICATION.to_string(), bearer)
+            ]
+        );
+        assert!(spool::read(&spool_path(&config)).unwrap().is_empty());
+    }
+
+    #[test]
+    fn concurrent_relays_send_each_payload_once_in_order() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut config = Config {
+            location: Some(temp_path("forward", "concurrent").join("a-notifications.json")),
+            ..Config::default()
+        };
+        config.claude.forward = Some(Forward {
+            url: format!("http://{}/claude", listener.local_addr().unwrap()),
+            token: None,
+            mode: ForwardMode::Replace,
+            content: ContentLevel::Full,
+            timeout_secs: 5,
+        });
+        let stop = |session: &str| STOP.replace(r#""s""#, &format!("{session:?}"));
+        for session in ["a", "b"] {
+            let entry = SpooledNotification {
+                spooled_at: 1000,
+                agent: "claude".to_string(),
+                event: "Stop".to_string(),
+                reason: Some("not forwarded".to_string()),
+                input: stop(session),
+                remote: false,
+            };
+            spool::append(&spool_path(&config), &entry).unwrap();
+        }
+
+        let server = thread::spawn(move || serve(&listener, 4, Duration::from_millis(100)));
+        let relays: Vec<_> = ["c", "d"]
+            .map(|session| {
+                let (config, input) = (config.clone(), stop(session));
+                let relay = thread::spawn(move || relay(&config, "claude", &input).unwrap());
+                thread::sleep(Duration::from_millis(50));
+                relay
+            })
+            .into_iter()
+            .collect();
+        for relay in relays {
+            relay.join().unwrap();
+        }
+
+        let bodies: Vec<_> = server
+            .join()
+            .unwrap()
+            .into_iter()
+            .map(|(body, _)| body)
+            .collect();
+        assert_eq!(bodies, ["a", "b", "c", "d"].map(stop));
+        assert!(spool::read(&spool_path(&config)).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,371 @@
+//! Per-session notification grouping. On Linux each session's latest notification id is kept
+//! in a state file so the next notification replaces it instead of stacking. macOS has no
+//! equivalent here: mac-notification-sys doesn't expose a thread identifier, so notifications
+//! there keep the system's per-app stacking.
+//!
+//! The same file keeps the id of each tool call's PreToolUse notification for a short while, so
+//! a PostToolUse arriving soon after replaces or closes it rather than adding another popup.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use notify_rust::{Hint, Notification, NotificationHandle};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::Config,
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::unix_now,
+};
+
+const GROUPS_FILE_NAME: &str = "groups.json";
+
+/// Sessions idle for this long are forgotten, along with their notification id.
+const GROUP_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+struct GroupEntry {
+    id: u32,
+    updated: u64,
+}
+
+/// The id of the last notification shown for each session, keyed by `agent:session`, and of
+/// each outstanding tool call's PreToolUse notification, keyed by `agent:session:tool_use_id`.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct GroupState {
+    #[serde(default)]
+    groups: BTreeMap<String, GroupEntry>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    tool_calls: BTreeMap<String, GroupEntry>,
+}
+
+/// Where a notification stands in a tool call, by the call's `tool_use_id`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolCall<'a> {
+    /// The PreToolUse notification, kept so the PostToolUse one can take its place
+    Started(&'a str),
+    /// The PostToolUse notification
+    Finished(&'a str),
+}
+
+pub fn groups_path(config: &Config) -> PathBuf {
+    config.data_dir().join(GROUPS_FILE_NAME)
+}
+
+fn group_key(agent: &str, session: &str) -> String {
+    format!("{agent}:{session}")
+}
+
+fn tool_call_key(agent: &str, session: &str, tool_use_id: &str) -> String {
+    format!("{agent}:{session}:{tool_use_id}")
+}
+
+/// Shows `notification`, replacing the previous one from the same `agent` session when
+/// `group_by_session` is on. A finished `tool_call` replaces its PreToolUse notification
+/// instead, when it comes within `claude.collapse_tool_calls_secs`. Without a session, or when
+/// the state file can't be used, the notification is shown on its own.
+pub fn show_in_group(
+    config: &Config,
+    notification: &mut Notification,
+    agent: &str,
+    session: Option<&str>,
+    tool_call: Option<ToolCall>,
+) -> Result<NotificationHandle, Error> {
+    let Some(session) = session.filter(|s| !s.is_empty()) else {
+        return Ok(notification.show()?);
+    };
+    let path = groups_path(config);
+    let window_secs = config.claude.collapse_tool_calls_secs;
+
+    let started = match tool_call {
+        Some(ToolCall::Finished(call)) if window_secs > 0 => {
+            take_tool_call(&path, &tool_call_key(agent, session, call))
+                .map(|entry| outstanding(entry, unix_now(), window_secs))
+                .unwrap_or_else(|e| {
+                    warn!(path = %path.display(), error = %e, "failed to read notification groups");
+                    None
+                })
+        }
+        _ => None,
+    };
+    let group = config.group_by_session.then(|| group_key(agent, session));
+
+    if let Some(id) = started {
+        notification.id(id);
+        debug!(
+            id,
+            session, "replacing the tool call's PreToolUse notification"
+        );
+    } else if let Some(key) = &group {
+        match previous_id(&path, key, unix_now()) {
+            Ok(Some(id)) => {
+                notification.id(id);
+                debug!(id, session, "replacing previous notification from session");
+            }
+            Ok(None) => {}
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "failed to read notification groups")
+            }
+        }
+    }
+
+    let handle = notification.show()?;
+    if let Some(key) = &group
+        && let Err(e) = store_id(&path, key, handle.id(), unix_now())
+    {
+        warn!(path = %path.display(), error = %e, "failed to record notification id");
+    }
+    if let Some(ToolCall::Started(call)) = tool_call
+        && window_secs > 0
+    {
+        let key = tool_call_key(agent, session, call);
+        if let Err(e) = store_tool_call(&path, &key, handle.id(), unix_now()) {
+            warn!(path = %path.display(), error = %e, "failed to record notification id");
+        }
+    }
+    Ok(handle)
+}
+
+/// Closes the PreToolUse notification of a tool call that finished without a notification of
+/// its own, when it finished within `claude.collapse_tool_calls_secs`. notify-rust only closes
+/// notifications through their handle, so it's replaced by a transient one saying `title` and
+/// `body`, closed right away; a server that doesn't close notifications shows that instead.
+pub fn close_tool_call(
+    config: &Config,
+    agent: &str,
+    session: &str,
+    tool_use_id: &str,
+    title: &str,
+    body: &str,
+) {
+    let window_secs = config.claude.collapse_tool_calls_secs;
+    if window_secs == 0 {
+        return;
+    }
+    let path = groups_path(config);
+    let started = match take_tool_call(&path, &tool_call_key(agent, session, tool_use_id)) {
+        Ok(entry) => outstanding(entry, unix_now(), window_secs),
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to read notification groups");
+            return;
+        }
+    };
+    let Some(id) = started else {
+        return;
+    };
+
+    match Notification::new()
+        .id(id)
+        .summary(title)
+        .body(body)
+        .hint(Hint::Transient(true))
+        .show()
+    {
+        Ok(handle) => {
+            debug!(
+                id,
+                session, "closing the tool call's PreToolUse notification"
+            );
+            handle.close();
+        }
+        Err(e) => debug!(id, error = %e, "failed to close notification"),
+    }
+}
+
+/// Closes the latest notification of every session and forgets the sessions. notify-rust
+/// only closes notifications through their handle, so each one is replaced by a transient
+/// notification that is closed right away.
+pub fn dismiss_all(config: &Config) {
+    let path = groups_path(config);
+    let state = match with_lock(&path, || {
+        let state = read_state(&path)?;
+        match fs::remove_file(&path) {
+            Ok(()) => Ok(state),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(state),
+            Err(e) => Err(e.into()),
+        }
+    }) {
+        Ok(state) => state,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to take notification groups");
+            return;
+        }
+    };
+
+    for (key, entry) in &state.groups {
+        match Notification::new()
+            .id(entry.id)
+            .hint(Hint::Transient(true))
+            .show()
+        {
+            Ok(handle) => handle.close(),
+            Err(e) => debug!(group = %key, error = %e, "failed to close notification"),
+        }
+    }
+    debug!(count = state.groups.len(), "dismissed all notifications");
+}
+
+fn read_state(path: &Path) -> Result<GroupState, Error> {
+    read_json_or_default(path)
+}
+
+/// The id of the last notification shown for `key`, unless that session has gone idle.
+fn previous_id(path: &Path, key: &str, now: u64) -> Result<Option<u32>, Error> {
+    Ok(read_state(path)?
+        .groups
+        .get(key)
+        .filter(|entry| now.saturating_sub(entry.updated) < GROUP_TTL_SECS)
+        .map(|entry| entry.id))
+}
+
+/// The id of a PreToolUse notification recorded in `entry`, when it's recent enough to
+/// collapse into the PostToolUse one.
+fn outstanding(entry: Option<GroupEntry>, now: u64, window_secs: u64) -> Option<u32> {
+    entry
+        .filter(|entry| now.saturating_sub(entry.updated) < window_secs)
+        .map(|entry| entry.id)
+}
+
+/// Records `id` as the PreToolUse notification of the tool call `key`. Calls older than a day
+/// are pruned on every call, since a PostToolUse may never come.
+fn store_tool_call(path: &Path, key: &str, id: u32, now: u64) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut state = read_state(path)?;
+        state
+            .tool_calls
+            .retain(|_, entry| now.saturating_sub(entry.updated) < GROUP_TTL_SECS);
+        state
+            .tool_calls
+            .insert(key.to_string(), GroupEntry { id, updated: now });
+
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())
+    })
+}
+
+/// Forgets the PreToolUse notification of the tool call `key` and returns it.
+fn take_tool_call(path: &Path, key: &str) -> Result<Option<GroupEntry>, Error> {
+    with_lock(path, || {
+        let mut state = read_state(path)?;
+        let entry = state.tool_calls.remove(key);
+        if entry.is_some() {
+            write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+        }
+        Ok(entry)
+    })
+}
+
+/// Records `id` as the latest notification for `key`. Idle sessions are pruned on every call.
+fn store_id(path: &Path, key: &str, id: u32, now: u64) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut state = read_state(path)?;
+        state
+            .groups
+            .retain(|_, entry| now.saturating_sub(entry.updated) < GROUP_TTL_SECS);
+        state
+            .groups
+            .insert(key.to_string(), GroupEntry { id, updated: now });
+
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_groups_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("groups", test_name).join(GROUPS_FILE_NAME)
+    }
+
+    #[test]
+    fn reuses_the_id_per_session() {
+        let path = temp_groups_path("reuse");
+        let first = group_key("claude", "session-a");
+        let second = group_key("claude", "session-b");
+
+        assert_eq!(previous_id(&path, &first, 1000).unwrap(), None);
+        store_id(&path, &first, 7, 1000).unwrap();
+        store_id(&path, &second, 9, 1001).unwrap();
+
+        assert_eq!(previous_id(&path, &first, 1002).unwrap(), Some(7));
+        assert_eq!(previous_id(&path, &second, 1002).unwrap(), Some(9));
+        assert_eq!(
+            previous_id(&path, &group_key("codex", "session-a"), 1002).unwrap(),
+            None
+        );
+
+        store_id(&path, &first, 12, 1003).unwrap();
+        assert_eq!(previous_id(&path, &first, 1004).unwrap(), Some(12));
+    }
+
+    #[test]
+    fn forgets_idle_sessions() {
+        let path = temp_groups_path("idle");
+        let idle = group_key("claude", "idle");
+        let active = group_key("claude", "active");
+
+        store_id(&path, &idle, 3, 1000).unwrap();
+        assert_eq!(
+            previous_id(&path, &idle, 1000 + GROUP_TTL_SECS).unwrap(),
+            None
+        );
+
+        store_id(&path, &active, 4, 1000 + GROUP_TTL_SECS).unwrap();
+        let state = read_state(&path).unwrap();
+        assert_eq!(state.groups.keys().collect::<Vec<_>>(), [&active]);
+    }
+
+    #[test]
+    fn finished_tool_calls_take_their_pre_tool_use_notification() {
+        let path = temp_groups_path("tool-calls");
+        let call = tool_call_key("claude", "s", "toolu_1");
+        store_id(&path, &group_key("claude", "s"), 3, 1000).unwrap();
+        store_tool_call(&path, &call, 7, 1000).unwrap();
+
+        let entry = take_tool_call(&path, &call).unwrap();
+        assert_eq!(outstanding(entry, 1002, 5), Some(7));
+        assert_eq!(
+            take_tool_call(&path, &call).unwrap(),
+            None,
+            "each notification is taken once"
+        );
+        assert_eq!(
+            previous_id(&path, &group_key("claude", "s"), 1003).unwrap(),
+            Some(3),
+            "the session's group is kept"
+        );
+        assert_eq!(
+            take_tool_call(&path, &tool_call_key("claude", "s", "toolu_2")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn slow_tool_calls_show_their_own_notification() {
+        let entry = Some(GroupEntry {
+            id: 7,
+            updated: 1000,
+        });
+        assert_eq!(outstanding(entry, 1004, 5), Some(7));
+        assert_eq!(outstanding(entry, 1005, 5), None);
+        assert_eq!(outstanding(None, 1000, 5), None);
+    }
+
+    #[test]
+    fn recovers_from_corrupt_state() {
+        let path = temp_groups_path("corrupt");
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, "{not json").unwrap();
+
+        assert_eq!(previous_id(&path, "claude:s", 1000).unwrap(), None);
+        store_id(&path, "claude:s", 5, 1000).unwrap();
+        assert_eq!(previous_id(&path, "claude:s", 1001).unwrap(), Some(5));
+    }
+}
@@ -0,0 +1,293 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::Config,
+    storage::with_lock,
+    utils::{format_duration, unix_now},
+};
+
+const HISTORY_FILE_NAME: &str = "history.jsonl";
+
+/// One line of `history.jsonl`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// Seconds since the Unix epoch
+    pub timestamp: u64,
+    pub agent: String,
+    pub event: String,
+    pub title: String,
+    pub body: String,
+    pub delivered: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    pub fn new<T>(
+        agent: &str,
+        event: &str,
+        title: &str,
+        body: &str,
+        result: &Result<T, Error>,
+    ) -> Self {
+        HistoryEntry {
+            timestamp: unix_now(),
+            agent: agent.to_string(),
+            event: event.to_string(),
+            title: title.to_string(),
+            body: body.to_string(),
+            delivered: result.is_ok(),
+            error: result.as_ref().err().map(|e| format!("{e:#}")),
+        }
+    }
+}
+
+pub fn history_path(config: &Config) -> PathBuf {
+    config.data_dir().join(HISTORY_FILE_NAME)
+}
+
+/// Appends an entry to the history file. Failures are logged and never surface to the caller,
+/// so history can't break notification delivery.
+pub fn record(config: &Config, entry: &HistoryEntry) {
+    if !config.history.enabled {
+        return;
+    }
+
+    let path = history_path(config);
+    if let Err(e) = append_entry(&path, entry, config.history.max_bytes) {
+        warn!(path = %path.display(), error = %e, "failed to record notification history");
+    }
+}
+
+/// Appends `entry` as a single line, rotating the file first when it exceeds `max_bytes`.
+///
+/// The line is written with one `write` on a file opened in append mode, and rotation happens
+/// under the history's lock, so concurrent hook processes never interleave partial lines or
+/// rotate away each other's entries.
+pub fn append_entry(path: &Path, entry: &impl Serialize, max_bytes: u64) -> Result<(), Error> {
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+
+    with_lock(path, || {
+        rotate_if_needed(path, max_bytes)?;
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+
+        Ok(())
+    })
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+fn rotate_if_needed(path: &Path, max_bytes: u64) -> Result<(), Error> {
+    let size = match fs::metadata(path) {
+        Ok(metadata) => metadata.len(),
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e.into()),
+    };
+
+    if size < max_bytes {
+        return Ok(());
+    }
+
+    debug!(path = %path.display(), size, "rotating history file");
+    match fs::rename(path, rotated_path(path)) {
+        Ok(()) => Ok(()),
+        // Another process rotated it first.
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Reads all entries (rotated file first), oldest first. Malformed lines are skipped.
+pub fn read_entries(path: &Path) -> Result<Vec<HistoryEntry>, Error> {
+    let mut entries = Vec::new();
+
+    for file in [rotated_path(path), path.to_path_buf()] {
+        let contents = match fs::read_to_string(&file) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+
+        entries.extend(
+            contents
+                .lines()
+                .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok()),
+        );
+    }
+
+    Ok(entries)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub limit: usize,
+    pub agent: Option<String>,
+    /// Only entries newer than this many seconds
+    pub since_secs: Option<u64>,
+}
+
+/// Applies `filter` and returns the matching entries newest first.
+pub fn filter_entries(
+    entries: Vec<HistoryEntry>,
+    filter: &HistoryFilter,
+    now: u64,
+) -> Vec<HistoryEntry> {
+    entries
+        .into_iter()
+        .rev()
+        .filter(|e| {
+            filter
+                .agent
+                .as_deref()
+                .is_none_or(|agent| e.agent.eq_ignore_ascii_case(agent))
+        })
+        .filter(|e| {
+            filter
+                .since_secs
+                .is_none_or(|since| now.saturating_sub(e.timestamp) <= since)
+        })
+        .take(filter.limit)
+        .collect()
+}
+
+pub fn format_entry(entry: &HistoryEntry, now: u64) -> String {
+    let age = format!(
+        "{} ago",
+        format_duration(now.saturating_sub(entry.timestamp))
+    );
+    let status = if entry.delivered { "" } else { " [failed]" };
+    let body = entry.body.replace('\n', " ");
+
+    format!(
+        "{age:>12}  {:<8}  {:<16}  {}{status}\n{:>14}{body}",
+        entry.agent, entry.event, entry.title, ""
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_history_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("history", test_name).join(HISTORY_FILE_NAME)
+    }
+
+    fn entry(agent: &str, timestamp: u64, body: &str) -> HistoryEntry {
+        HistoryEntry {
+            timestamp,
+            agent: agent.to_string(),
+            event: "Stop".to_string(),
+            title: "Claude Code: Stop".to_string(),
+            body: body.to_string(),
+            delivered: true,
+            error: None,
+        }
+    }
+
+    #[test]
+    fn rotates_when_over_max_bytes() {
+        let path = temp_history_path("rotation");
+
+        for i in 0..10 {
+            append_entry(&path, &entry("claude", i, &"x".repeat(50)), 300).unwrap();
+        }
+
+        assert!(rotated_path(&path).exists());
+        assert!(fs::metadata(&path).unwrap().len() < 300 + 200);
+
+        let entries = read_entries(&path).unwrap();
+        assert!(!entries.is_empty());
+        // Oldest entries beyond the rotated file are dropped, order is preserved.
+        assert!(entries.windows(2).all(|w| w[0].timestamp < w[1].timestamp));
+        assert_eq!(entries.last().unwrap().timestamp, 9);
+    }
+
+    #[test]
+    fn filters_by_agent_since_and_limit_newest_first() {
+        let entries = vec![
+            entry("claude", 100, "old"),
+            entry("codex", 900, "codex"),
+            entry("claude", 950, "recent"),
+            entry("claude", 990, "newest"),
+        ];
+
+        let filtered = filter_entries(
+            entries.clone(),
+            &HistoryFilter {
+                limit: 20,
+                agent: Some("Claude".to_string()),
+                since_secs: Some(120),
+            },
+            1000,
+        );
+        let bodies: Vec<_> = filtered.iter().map(|e| e.body.as_str()).collect();
+        assert_eq!(bodies, ["newest", "recent"]);
+
+        let limited = filter_entries(
+            entries,
+            &HistoryFilter {
+                limit: 1,
+                ..Default::default()
+            },
+            1000,
+        );
+        assert_eq!(limited[0].body, "newest");
+        assert_eq!(limited.len(), 1);
+    }
+
+    #[test]
+    fn concurrent_appends_do_not_interleave() {
+        let path = temp_history_path("concurrent");
+        let body = "y".repeat(2000);
+
+        let handles: Vec<_> = (0..8)
+            .map(|t| {
+                let path = path.clone();
+                let body = body.clone();
+                std::thread::spawn(move || {
+                    for i in 0..50 {
+                        append_entry(&path, &entry("claude", t * 100 + i, &body), u64::MAX)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        let contents = fs::read_to_string(&path).unwrap();
+        let lines: Vec<_> = contents.lines().collect();
+        assert_eq!(lines.len(), 400);
+        assert!(
+            lines
+                .iter()
+                .all(|l| serde_json::from_str::<HistoryEntry>(l).is_ok())
+        );
+    }
+
+    #[test]
+    fn records_delivery_failures() {
+        let result: Result<(), Error> = Err(Error::msg("dbus unavailable"));
+        let entry = HistoryEntry::new("codex", "AgentTurnComplete", "Codex", "done", &result);
+
+        assert!(!entry.delivered);
+        assert_eq!(entry.error.as_deref(), Some("dbus unavailable"));
+    }
+}
@@ -0,0 +1,480 @@
+use std::fmt::Display;
+
+use crate::configuration::Config;
+
+/// Message templates keyed by message id. `{name}` placeholders are filled from the arguments.
+type Bundle = &'static [(&'static str, &'static str)];
+
+const EN: Bundle = &[
+    ("claude.pre_tool_use", "The agent is trying to use {tool}"),
+    (
+        "claude.pre_tool_use.accept_edits",
+        "The agent is using {tool} (auto-approved, accept edits mode)",
+    ),
+    (
+        "claude.pre_tool_use.plan",
+        "The agent is using {tool} (auto-approved, plan mode)",
+    ),
+    ("claude.post_tool_use", "The agent has used {tool}"),
+    ("claude.tool_failed", "{tool} failed: {error}"),
+    ("claude.tool_finished", "{tool} finished in {duration}"),
+    ("claude.tool_edited", "Edited {path} (+{added}/-{removed} lines)"),
+    ("claude.tool_wrote", "Wrote {path} ({size}, {lines} lines)"),
+    ("claude.tool_wrote.size", "Wrote {path} ({size})"),
+    ("claude.tool_exited", "{tool} exited with {code}: {stderr}"),
+    ("claude.tool_exited.no_output", "{tool} exited with {code}"),
+    ("claude.unknown_tool", "a unknown tool"),
+    ("claude.no_message", "The agent didn't provide any message."),
+    ("claude.prompt_submitted", "User prompt submitted: {prompt}"),
+    (
+        "claude.prompt_redacted",
+        "User submitted a prompt ({count} chars)",
+    ),
+    ("claude.stopped", "The agent has stopped responding."),
+    (
+        "claude.subagent_stopped",
+        "A subagent has stopped responding.",
+    ),
+    (
+        "claude.pre_compact",
+        "The agent is about to compact the conversation. Trigger: {trigger}",
+    ),
+    (
+        "claude.session_start",
+        "The agent has started a new session.",
+    ),
+    (
+        "claude.session_start.resume",
+        "The agent has started a session (resumed).",
+    ),
+    (
+        "claude.session_start.clear",
+        "The agent has started a new session (after /clear).",
+    ),
+    (
+        "claude.session_end",
+        "The agent has ended the session because {reason}",
+    ),
+    ("claude.session_end.clear", "the user ran /clear."),
+    (
+        "claude.session_end.prompt_input_exit",
+        "the user exited while prompt input was visible.",
+    ),
+    ("claude.session_end.logout", "the user logged out."),
+    (
+        "claude.session_end.other",
+        "the session ended for unspecified reason.",
+    ),
+    (
+        "claude.session_digest",
+        "Session in {project} ended ({reason}) — {activity}, ran {duration}",
+    ),
+    (
+        "claude.session_digest.unnamed",
+        "Session ended ({reason}) — {activity}, ran {duration}",
+    ),
+    ("claude.session_digest.tool_call", "1 tool call ({tools})"),
+    ("claude.session_digest.tool_calls", "{count} tool calls ({tools})"),
+    ("claude.session_digest.no_tool_calls", "no tool calls"),
+    ("claude.session_digest.permission_prompt", "1 permission prompt"),
+    (
+        "claude.session_digest.permission_prompts",
+        "{count} permission prompts",
+    ),
+    ("claude.unknown", "unknown"),
+    ("claude.unknown_event", "Claude Code event: {event}"),
+    ("claude.stop_summary", "Claude finished: {message}"),
+    ("claude.tool_batch.summary", "Used {count} tools"),
+    (
+        "claude.tool_batch",
+        "Claude used {count} tools ({tools}) over {duration}",
+    ),
+    ("claude.title.intercepted", "Command intercepted"),
+    ("claude.guard.ask", "{tool} needs your approval: {detail}"),
+    ("claude.guard.deny", "{tool} was blocked: {detail}"),
+    ("claude.reminder.summary", "Reminder"),
+    ("claude.reminder", "Claude is still waiting (idle {idle})"),
+    ("claude.others_waiting.one", "(1 other session also waiting)"),
+    (
+        "claude.others_waiting",
+        "({count} other sessions also waiting)",
+    ),
+    ("claude.title.tool", "Tool"),
+    ("claude.title.notification", "Notification"),
+    ("claude.title.prompt_submitted", "Prompt submitted"),
+    ("claude.title.finished", "Finished"),
+    ("claude.title.subagent_finished", "Subagent finished"),
+    ("claude.title.compacting", "Compacting"),
+    ("claude.title.session_started", "Session started"),
+    ("claude.title.session_ended", "Session ended"),
+    ("codex.title.turn_complete", "Turn complete"),
+    ("codex.title.unknown", "Codex notification"),
+    ("codex.turn_complete", "Turn Complete!"),
+    ("codex.turn_completed", "Turn Completed: {message}"),
+    ("codex.reply", "Re: {prompt} — {message}"),
+    ("codex.since_last_turn", "(since last turn: {duration})"),
+    ("codex.completed_at", "completed at {time}"),
+];
+
+const ZH_TW: Bundle = &[
+    ("claude.pre_tool_use", "代理程式正嘗試使用 {tool}"),
+    (
+        "claude.pre_tool_use.accept_edits",
+        "代理程式正在使用 {tool}（已自動核准，接受編輯模式）",
+    ),
+    (
+        "claude.pre_tool_use.plan",
+        "代理程式正在使用 {tool}（已自動核准，規劃模式）",
+    ),
+    ("claude.post_tool_use", "代理程式已使用 {tool}"),
+    ("claude.tool_failed", "{tool} 執行失敗：{error}"),
+    ("claude.tool_finished", "{tool} 已完成，耗時 {duration}"),
+    ("claude.tool_edited", "已編輯 {path}（+{added}/-{removed} 行）"),
+    ("claude.tool_wrote", "已寫入 {path}（{size}，{lines} 行）"),
+    ("claude.tool_wrote.size", "已寫入 {path}（{size}）"),
+    ("claude.tool_exited", "{tool} 以代碼 {code} 結束：{stderr}"),
+    ("claude.tool_exited.no_output", "{tool} 以代碼 {code} 結束"),
+    ("claude.unknown_tool", "未知的工具"),
+    ("claude.no_message", "代理程式沒有提供任何訊息。"),
+    ("claude.prompt_submitted", "已送出使用者提示：{prompt}"),
+    (
+        "claude.prompt_redacted",
+        "使用者送出了一則提示（{count} 個字元）",
+    ),
+    ("claude.stopped", "代理程式已停止回應。"),
+    ("claude.subagent_stopped", "子代理程式已停止回應。"),
+    (
+        "claude.pre_compact",
+        "代理程式即將壓縮對話。觸發方式：{trigger}",
+    ),
+    ("claude.session_start", "代理程式已開始新的工作階段。"),
+    ("claude.session_start.resume", "代理程式已開始工作階段（已恢復）。"),
+    (
+        "claude.session_start.clear",
+        "代理程式已開始新的工作階段（執行 /clear 後）。",
+    ),
+    (
+        "claude.session_end",
+        "代理程式已結束工作階段，原因：{reason}",
+    ),
+    ("claude.session_end.clear", "使用者執行了 /clear。"),
+    (
+        "claude.session_end.prompt_input_exit",
+        "使用者在提示輸入顯示時離開。",
+    ),
+    ("claude.session_end.logout", "使用者已登出。"),
+    ("claude.session_end.other", "工作階段因未指明的原因結束。"),
+    (
+        "claude.session_digest",
+        "{project} 的工作階段已結束（{reason}）— {activity}，執行了 {duration}",
+    ),
+    (
+        "claude.session_digest.unnamed",
+        "工作階段已結束（{reason}）— {activity}，執行了 {duration}",
+    ),
+    ("claude.session_digest.tool_call", "1 次工具呼叫（{tools}）"),
+    ("claude.session_digest.tool_calls", "{count} 次工具呼叫（{tools}）"),
+    ("claude.session_digest.no_tool_calls", "沒有工具呼叫"),
+    ("claude.session_digest.permission_prompt", "1 次權限請求"),
+    (
+        "claude.session_digest.permission_prompts",
+        "{count} 次權限請求",
+    ),
+    ("claude.unknown", "未知"),
+    ("claude.unknown_event", "Claude Code 事件：{event}"),
+    ("claude.stop_summary", "Claude 已完成：{message}"),
+    ("claude.tool_batch.summary", "使用了 {count} 個工具"),
+    (
+        "claude.tool_batch",
+        "Claude 在 {duration} 內使用了 {count} 個工具（{tools}）",
+    ),
+    ("claude.title.intercepted", "已攔截指令"),
+    ("claude.guard.ask", "{tool} 需要你的核准：{detail}"),
+    ("claude.guard.deny", "{tool} 已被封鎖：{detail}"),
+    ("claude.reminder.summary", "提醒"),
+    ("claude.reminder", "Claude 仍在等待中（已閒置 {idle}）"),
+    ("claude.others_waiting.one", "（另有 1 個工作階段也在等待）"),
+    ("claude.others_waiting", "（另有 {count} 個工作階段也在等待）"),
+    ("claude.title.tool", "工具"),
+    ("claude.title.notification", "通知"),
+    ("claude.title.prompt_submitted", "已送出提示"),
+    ("claude.title.finished", "已完成"),
+    ("claude.title.subagent_finished", "子代理已完成"),
+    ("claude.title.compacting", "正在壓縮"),
+    ("claude.title.session_started", "工作階段已開始"),
+    ("claude.title.session_ended", "工作階段已結束"),
+    ("codex.title.turn_complete", "回合完成"),
+    ("codex.title.unknown", "Codex 通知"),
+    ("codex.turn_complete", "回合完成！"),
+    ("codex.turn_completed", "回合已完成：{message}"),
+    ("codex.reply", "回覆：{prompt} — {message}"),
+    ("codex.since_last_turn", "（距上一回合：{duration}）"),
+    ("codex.completed_at", "完成於 {time}"),
+];
+
+const JA: Bundle = &[
+    (
+        "claude.pre_tool_use",
+        "エージェントが {tool} を使おうとしています",
+    ),
+    (
+        "claude.pre_tool_use.accept_edits",
+        "エージェントが {tool} を使用しています（自動承認・編集承認モード）",
+    ),
+    (
+        "claude.pre_tool_use.plan",
+        "エージェントが {tool} を使用しています（自動承認・プランモード）",
+    ),
+    (
+        "claude.post_tool_use",
+        "エージェントが {tool} を使用しました",
+    ),
+    ("claude.tool_failed", "{tool} が失敗しました: {error}"),
+    (
+        "claude.tool_finished",
+        "{tool} が完了しました（{duration}）",
+    ),
+    (
+        "claude.tool_edited",
+        "{path} を編集しました（+{added}/-{removed} 行）",
+    ),
+    (
+        "claude.tool_wrote",
+        "{path} を書き込みました（{size}、{lines} 行）",
+    ),
+    ("claude.tool_wrote.size", "{path} を書き込みました（{size}）"),
+    (
+        "claude.tool_exited",
+        "{tool} が終了コード {code} で終了しました: {stderr}",
+    ),
+    (
+        "claude.tool_exited.no_output",
+        "{tool} が終了コード {code} で終了しました",
+    ),
+    ("claude.unknown_tool", "不明なツール"),
+    (
+        "claude.no_message",
+        "エージェントからのメッセージはありません。",
+    ),
+    (
+        "claude.prompt_submitted",
+        "ユーザーがプロンプトを送信しました: {prompt}",
+    ),
+    (
+        "claude.prompt_redacted",
+        "ユーザーがプロンプトを送信しました（{count} 文字）",
+    ),
+    ("claude.stopped", "エージェントの応答が停止しました。"),
+    (
+        "claude.subagent_stopped",
+        "サブエージェントの応答が停止しました。",
+    ),
+    (
+        "claude.pre_compact",
+        "エージェントが会話を圧縮しようとしています。トリガー: {trigger}",
+    ),
+    (
+        "claude.session_start",
+        "エージェントが新しいセッションを開始しました。",
+    ),
+    (
+        "claude.session_start.resume",
+        "エージェントがセッションを開始しました（再開）。",
+    ),
+    (
+        "claude.session_start.clear",
+        "エージェントが新しいセッションを開始しました（/clear 後）。",
+    ),
+    (
+        "claude.session_end",
+        "エージェントがセッションを終了しました。理由: {reason}",
+    ),
+    (
+        "claude.session_end.clear",
+        "ユーザーが /clear を実行しました。",
+    ),
+    (
+        "claude.session_end.prompt_input_exit",
+        "プロンプト入力の表示中にユーザーが終了しました。",
+    ),
+    (
+        "claude.session_end.logout",
+        "ユーザーがログアウトしました。",
+    ),
+    (
+        "claude.session_end.other",
+        "不明な理由でセッションが終了しました。",
+    ),
+    (
+        "claude.session_digest",
+        "{project} のセッションが終了しました（{reason}）— {activity}、実行時間 {duration}",
+    ),
+    (
+        "claude.session_digest.unnamed",
+        "セッションが終了しました（{reason}）— {activity}、実行時間 {duration}",
+    ),
+    ("claude.session_digest.tool_call", "ツール呼び出し 1 回（{tools}）"),
+    (
+        "claude.session_digest.tool_calls",
+        "ツール呼び出し {count} 回（{tools}）",
+    ),
+    ("claude.session_digest.no_tool_calls", "ツール呼び出しなし"),
+    ("claude.session_digest.permission_prompt", "権限の確認 1 回"),
+    (
+        "claude.session_digest.permission_prompts",
+        "権限の確認 {count} 回",
+    ),
+    ("claude.unknown", "不明"),
+    ("claude.unknown_event", "Claude Code イベント: {event}"),
+    ("claude.stop_summary", "Claude が完了しました: {message}"),
+    ("claude.tool_batch.summary", "{count} 個のツールを使用"),
+    (
+        "claude.tool_batch",
+        "Claude が {duration} で {count} 個のツールを使用しました（{tools}）",
+    ),
+    ("claude.title.intercepted", "コマンドを差し止めました"),
+    (
+        "claude.guard.ask",
+        "{tool} の実行には承認が必要です：{detail}",
+    ),
+    ("claude.guard.deny", "{tool} をブロックしました：{detail}"),
+    ("claude.reminder.summary", "リマインダー"),
+    (
+        "claude.reminder",
+        "Claude が入力を待っています（{idle} 操作なし）",
+    ),
+    ("claude.others_waiting.one", "（他に 1 件のセッションも待機中）"),
+    (
+        "claude.others_waiting",
+        "（他に {count} 件のセッションも待機中）",
+    ),
+    ("claude.title.tool", "ツール"),
+    ("claude.title.notification", "通知"),
+    ("claude.title.prompt_submitted", "プロンプト送信"),
+    ("claude.title.finished", "完了"),
+    ("claude.title.subagent_finished", "サブエージェント完了"),
+    ("claude.title.compacting", "圧縮中"),
+    ("claude.title.session_started", "セッション開始"),
+    ("claude.title.session_ended", "セッション終了"),
+    ("codex.title.turn_complete", "ターン完了"),
+    ("codex.title.unknown", "Codex の通知"),
+    ("codex.turn_complete", "ターン完了！"),
+    ("codex.turn_completed", "ターン完了: {message}"),
+    ("codex.reply", "Re: {prompt} — {message}"),
+    ("codex.since_last_turn", "（前のターンから {duration}）"),
+    ("codex.completed_at", "{time} に完了"),
+];
+
+/// The built-in message `key` in the configured language, with `{name}` placeholders replaced
+/// by `args`. `ANOT_LANG` takes precedence over the `language` option.
+pub fn t(config: &Config, key: &str, args: &[(&str, &dyn Display)]) -> String {
+    let language = std::env::var("ANOT_LANG")
+        .ok()
+        .filter(|lang| !lang.trim().is_empty())
+        .unwrap_or_else(|| config.language.clone());
+
+    interpolate(lookup(bundle(&language), key), args)
+}
+
+/// The bundle for a language tag such as `ja`, `zh-TW` or `ja_JP.UTF-8`. Unsupported
+/// languages get `None`, which means English.
+fn bundle(language: &str) -> Option<Bundle> {
+    let language = language
+        .split('.')
+        .next()
+        .unwrap_or_default()
+        .replace('_', "-")
+        .to_ascii_lowercase();
+
+    match language.split('-').next() {
+        Some("en") => Some(EN),
+        Some("zh") => Some(ZH_TW),
+        Some("ja") => Some(JA),
+        _ => None,
+    }
+}
+
+/// Looks `key` up in `bundle`, falling back to English and finally to the key itself.
+fn lookup(bundle: Option<Bundle>, key: &str) -> &str {
+    let find = |bundle: Bundle| {
+        bundle
+            .iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, template)| *template)
+    };
+
+    bundle.and_then(find).or_else(|| find(EN)).unwrap_or(key)
+}
+
+/// `template` with its `{name}` placeholders replaced by `args`. Unknown placeholders are left
+/// as they are.
+pub fn interpolate(template: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn looks_up_language_bundles() {
+        assert_eq!(
+            lookup(bundle("ja"), "claude.stopped"),
+            "エージェントの応答が停止しました。"
+        );
+        assert_eq!(
+            lookup(bundle("zh-TW"), "claude.stopped"),
+            "代理程式已停止回應。"
+        );
+        assert_eq!(
+            lookup(bundle("ja_JP.UTF-8"), "claude.stopped"),
+            lookup(bundle("ja"), "claude.stopped")
+        );
+        assert_eq!(
+            lookup(bundle("fr"), "claude.stopped"),
+            "The agent has stopped responding."
+        );
+    }
+
+    #[test]
+    fn interpolates_arguments() {
+        assert_eq!(
+            interpolate(
+                lookup(Some(EN), "claude.tool_batch"),
+                &[("count", &15), ("tools", &"Bash ×6"), ("duration", &"20s")]
+            ),
+            "Claude used 15 tools (Bash ×6) over 20s"
+        );
+        assert_eq!(
+            interpolate(
+                lookup(Some(JA), "claude.pre_tool_use"),
+                &[("tool", &"Bash")]
+            ),
+            "エージェントが Bash を使おうとしています"
+        );
+    }
+
+    #[test]
+    fn missing_keys_fall_back_to_english() {
+        const PARTIAL: Bundle = &[("claude.stopped", "停止しました")];
+
+        assert_eq!(lookup(Some(PARTIAL), "claude.stopped"), "停止しました");
+        assert_eq!(
+            lookup(Some(PARTIAL), "codex.turn_complete"),
+            "Turn Complete!"
+        );
+        assert_eq!(lookup(Some(JA), "no.such.key"), "no.such.key");
+    }
+
+    #[test]
+    fn bundles_only_use_english_keys() {
+        for bundle in [ZH_TW, JA] {
+            for (key, _) in bundle {
+                assert!(EN.iter().any(|(k, _)| k == key), "unknown key {key}");
+            }
+        }
+    }
+}
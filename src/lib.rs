@@ -0,0 +1,65 @@
+//! Desktop notifications for coding agents. The `anot` binary is a thin wrapper around this
+//! library, which parses Claude Code hook payloads and Codex notify payloads and sends the
+//! resulting notifications through the configured backends.
+//!
+//! ```
+//! use agent_notifications::{HookEventName, HookInput};
+//!
+//! let input: HookInput = serde_json::from_str(
+//!     r#"{"session_id": "abc", "transcript_path": "/tmp/t.jsonl", "hook_event_name": "Stop"}"#,
+//! )
+//! .unwrap();
+//! assert_eq!(input.hook_event_name, HookEventName::Stop);
+//! ```
+
+// Notification actions are attached by the desktop backend; macOS only uses the click and
+// button handling.
+#[cfg_attr(
+    not(all(feature = "desktop", unix, not(target_os = "macos"))),
+    allow(dead_code)
+)]
+mod actions;
+mod backends;
+pub mod capture;
+pub mod configuration;
+pub mod console;
+pub mod crash;
+mod dedupe;
+pub mod delivery;
+mod dnd;
+pub mod error;
+pub mod events;
+mod focus;
+pub mod forward;
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+mod grouping;
+pub mod history;
+pub mod i18n;
+pub mod logging;
+pub mod metrics;
+pub mod mute;
+pub mod output;
+// The app identity is only chosen on macOS.
+#[cfg_attr(not(all(feature = "desktop", target_os = "macos")), allow(dead_code))]
+mod pretend;
+mod preview;
+pub mod privacy;
+pub mod processors;
+pub mod replay;
+pub mod sessions;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod spool;
+pub mod storage;
+#[cfg(test)]
+pub(crate) mod test_support;
+pub mod text;
+pub mod timing;
+pub mod uninstall;
+pub mod utils;
+
+pub use configuration::Config;
+pub use processors::{
+    claude::structs::{HookEventName, HookInput, HookOutput},
+    codex::structs::CodexNotificationInput,
+};
@@ -0,0 +1,266 @@
+use std::{
+    io::{self, Write},
+    path::PathBuf,
+    sync::OnceLock,
+};
+
+use tracing::{Span, field::Empty};
+use tracing_appender::{non_blocking::WorkerGuard, rolling::RollingFileAppender};
+use tracing_subscriber::{
+    EnvFilter,
+    filter::LevelFilter,
+    fmt::{self, MakeWriter},
+    prelude::*,
+};
+
+/// How the log file is written for a given filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LogFile {
+    /// Nothing can pass the filter, so there is no file layer.
+    Off,
+    /// Only warnings and errors pass: the file is opened by the first one, written
+    /// synchronously, and never touched otherwise.
+    OnFirstWrite,
+    /// Verbose logging through a background writer thread.
+    Background,
+}
+
+fn log_file_for(filter: &EnvFilter) -> LogFile {
+    match filter.max_level_hint() {
+        Some(LevelFilter::OFF) => LogFile::Off,
+        Some(level) if level < LevelFilter::INFO => LogFile::OnFirstWrite,
+        _ => LogFile::Background,
+    }
+}
+
+fn filter_for(verbosity: u8) -> EnvFilter {
+    EnvFilter::try_from_default_env().unwrap_or_else(|_| match verbosity {
+        0 => EnvFilter::new("warn"),
+        1 => EnvFilter::new("info"),
+        2 => EnvFilter::new("debug"),
+        _ => EnvFilter::new("trace"),
+    })
+}
+
+/// Installs the global subscriber writing to the daily log file. Hold the returned guard until
+/// the process exits so buffered lines are flushed.
+pub fn init_tracing(verbosity: u8) -> Option<WorkerGuard> {
+    let filter = filter_for(verbosity);
+    let log_dir = crate::configuration::get_logs_dir();
+    let registry = tracing_subscriber::registry().with(filter.clone());
+
+    match log_file_for(&filter) {
+        LogFile::Off => {
+            registry.init();
+            None
+        }
+        LogFile::OnFirstWrite => {
+            registry.with(file_layer(LazyLogFile::new(log_dir))).init();
+            None
+        }
+        LogFile::Background => {
+            let _ = std::fs::create_dir_all(&log_dir);
+            let file_appender = tracing_appender::rolling::daily(&log_dir, "anot.log");
+            let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
+            registry.with(file_layer(non_blocking)).init();
+            Some(guard)
+        }
+    }
+}
+
+fn file_layer<S, W>(writer: W) -> fmt::Layer<S, fmt::format::DefaultFields, fmt::format::Format, W>
+where
+    W: for<'a> MakeWriter<'a> + 'static,
+{
+    fmt::layer()
+        .with_ansi(false)
+        .with_writer(writer)
+        .with_target(false)
+}
+
+/// A daily log file that isn't created until something is written to it.
+struct LazyLogFile {
+    dir: PathBuf,
+    appender: OnceLock<Option<RollingFileAppender>>,
+}
+
+impl LazyLogFile {
+    fn new(dir: PathBuf) -> Self {
+        LazyLogFile {
+            dir,
+            appender: OnceLock::new(),
+        }
+    }
+
+    fn appender(&self) -> Option<&RollingFileAppender> {
+        self.appender
+            .get_or_init(|| {
+                std::fs::create_dir_all(&self.dir).ok()?;
+                RollingFileAppender::builder()
+                    .rotation(tracing_appender::rolling::Rotation::DAILY)
+                    .filename_prefix("anot.log")
+                    .build(&self.dir)
+                    .ok()
+            })
+            .as_ref()
+    }
+}
+
+impl<'a> MakeWriter<'a> for LazyLogFile {
+    type Writer = LazyLogWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LazyLogWriter(self)
+    }
+}
+
+struct LazyLogWriter<'a>(&'a LazyLogFile);
+
+impl Write for LazyLogWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self.0.appender() {
+            Some(appender) => appender.make_writer().write(buf),
+            // Logging must never fail a hook.
+            None => Ok(buf.len()),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.0.appender.get() {
+            Some(Some(appender)) => appender.make_writer().flush(),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Characters of a session or turn id shown in log lines.
+const SHORT_ID_CHARS: usize = 8;
+
+/// The span one hook payload is handled in, so every line logged for it says which agent,
+/// session and turn it came from. It's at error level so it's kept whenever anything is logged.
+/// `session` and `turn` are the first characters of the ids, for reading interleaved logs.
+pub fn hook_span(agent: &'static str, session_id: Option<&str>, turn_id: Option<&str>) -> Span {
+    let span = tracing::error_span!(
+        "hook",
+        agent,
+        session = Empty,
+        turn = Empty,
+        session_id = Empty,
+        turn_id = Empty
+    );
+    if let Some(id) = session_id {
+        span.record("session", short_id(id));
+        span.record("session_id", id);
+    }
+    if let Some(id) = turn_id {
+        span.record("turn", short_id(id));
+        span.record("turn_id", id);
+    }
+    span
+}
+
+/// The first characters of `id`.
+fn short_id(id: &str) -> &str {
+    id.char_indices()
+        .nth(SHORT_ID_CHARS)
+        .map_or(id, |(end, _)| &id[..end])
+}
+
+/// Everything logged at debug level while `f` runs, as the log file would have it.
+#[cfg(test)]
+pub(crate) fn capture_logs(f: impl FnOnce()) -> String {
+    use std::sync::{Arc, Mutex};
+
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let buffer = Buffer::default();
+    let writer = buffer.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_ansi(false)
+        .with_target(false)
+        .with_writer(move || writer.clone())
+        .finish();
+    tracing::subscriber::with_default(subscriber, f);
+
+    String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hook_spans_name_the_agent_and_ids() {
+        let logs = capture_logs(|| {
+            let _claude = hook_span("claude", Some("1f0e9d8c-7b6a-4958"), None).entered();
+            tracing::warn!("from claude");
+        });
+        assert!(
+            logs.contains(r#"hook{agent="claude" session="1f0e9d8c" session_id="1f0e9d8c-7b6a-4958"}: from claude"#),
+            "{logs}"
+        );
+
+        let logs = capture_logs(|| {
+            let _codex = hook_span("codex", None, None).entered();
+            tracing::info!("from codex");
+        });
+        assert!(
+            logs.contains(r#"hook{agent="codex"}: from codex"#),
+            "{logs}"
+        );
+    }
+
+    #[test]
+    fn short_ids_keep_whole_characters() {
+        assert_eq!(short_id("abc"), "abc");
+        assert_eq!(short_id("0123456789"), "01234567");
+        assert_eq!(short_id("ééééééééé"), "éééééééé");
+    }
+
+    #[test]
+    fn picks_the_log_file_per_verbosity() {
+        assert_eq!(log_file_for(&EnvFilter::new("off")), LogFile::Off);
+        assert_eq!(
+            log_file_for(&EnvFilter::new("error")),
+            LogFile::OnFirstWrite
+        );
+        assert_eq!(log_file_for(&EnvFilter::new("warn")), LogFile::OnFirstWrite);
+        assert_eq!(log_file_for(&EnvFilter::new("info")), LogFile::Background);
+        assert_eq!(log_file_for(&EnvFilter::new("trace")), LogFile::Background);
+        assert_eq!(
+            log_file_for(&EnvFilter::new("warn,anot=debug")),
+            LogFile::Background
+        );
+    }
+
+    #[test]
+    fn lazy_log_file_is_created_on_first_write() {
+        let dir = std::env::temp_dir().join(format!("anot-lazy-logs-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let file = LazyLogFile::new(dir.clone());
+
+        file.make_writer().flush().unwrap();
+        assert!(!dir.exists());
+
+        file.make_writer().write_all(b"warning\n").unwrap();
+        let logs: Vec<_> = std::fs::read_dir(&dir).unwrap().collect();
+        assert_eq!(logs.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(logs[0].as_ref().unwrap().path()).unwrap(),
+            "warning\n"
+        );
+    }
+}
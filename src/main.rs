@@ -1,45 +1,152 @@
-use std::path::PathBuf;
+use std::{
+    io::{self, IsTerminal, Write},
+    path::{Path, PathBuf},
+    time::Instant,
+};
 
-use anyhow::Error;
-use clap::{CommandFactory, Parser, Subcommand};
-use std::sync::OnceLock;
+use anyhow::{Error, bail};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
 use tracing::{debug, error};
-use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
-use crate::{
-    configuration::{get_config_path, initialize_configuration, reset_configuration},
+#[cfg(feature = "serve")]
+use agent_notifications::serve;
+use agent_notifications::{
+    Config, HookInput, capture,
+    configuration::{
+        self,
+        format::{self, ConfigFormat},
+        get_config_path, initialize_configuration, local, onboarding,
+        profiles::{self, PROFILE_ENV},
+        reset, schema, wizard,
+    },
+    console::{self, Mark},
+    crash,
+    delivery::{self, StageOutcome},
+    error::{self, AnotError},
+    events,
+    history::{HistoryFilter, filter_entries, format_entry, history_path, read_entries},
+    logging, mute,
+    output::{
+        self, ConfigConversion, ConfigSchema, ConfigSet, ConfigValidation, ErrorReport,
+        ProfileList, ProfileUse, ReplayReport, UnmuteReport,
+    },
     processors::{
-        claude::input_and_output::process_claude_input,
-        codex::input_and_output::process_codex_input,
+        self,
+        claude::{
+            input_and_output::{emitted_output, no_input_output, read_hook_input},
+            process_claude_input, reminders,
+        },
+        codex::{input_and_output::read_codex_payload, process_codex_input},
+        common::init::handle_inquire_error,
         opencode::input_and_output::process_opencode_input,
     },
+    replay, sessions, spool,
+    storage::write_atomic,
+    timing,
+    uninstall::{self, StepOutcome, UninstallOptions},
+    utils,
 };
 
-mod configuration;
-mod processors;
-mod utils;
+/// How often `anot sessions --watch` refreshes.
+const SESSIONS_WATCH_INTERVAL_SECS: u64 = 2;
+
+/// How often `anot events follow` checks for new events.
+const EVENTS_FOLLOW_INTERVAL_MILLIS: u64 = 250;
+
+/// Shown after `--help` and as a section of the manpage.
+const AFTER_HELP: &str = "\
+Config file:
+  a-notifications.json (or a-notifications.toml) in the agent_notifications directory of the
+  system config directory, e.g. ~/.config/agent_notifications/ on Linux and
+  ~/Library/Application Support/agent_notifications/ on macOS. --config picks another file.
+
+Environment:
+  ANOT_PROFILE        Profile merged over the config, unless --profile picks one
+  ANOT_LANG           Language of the built-in notification text (en, zh-TW, ja)
+  ANOT_DRY_RUN        Set to 1 for the same as --dry-run on `anot claude` and `anot codex`
+  ANOT_CONFIG_FORMAT  Format of the config created on first run (json or toml)
+  ANOT_SERVE_TOKEN    Token `anot serve` requires, unless --token sets one
+  NO_COLOR            Print messages without emoji, like --plain
+  RUST_LOG            Log filter, e.g. anot=debug, used instead of the level -d picks";
 
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, after_help = AFTER_HELP)]
 struct Cli {
+    /// Use this config file instead of the default one
     #[arg(short, long, value_name = "FILE")]
     config: Option<PathBuf>,
 
+    /// Log more to the log file: -d for info, -dd for debug, -ddd for trace
     #[arg(short, long, action = clap::ArgAction::Count)]
     debug: u8,
 
+    /// Merge this profile from `profiles` over the config (overrides ANOT_PROFILE and active_profile)
+    #[arg(long, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Save each raw agent payload under `captures/` next to the config (debug.capture_payloads)
+    #[arg(long)]
+    capture: bool,
+
+    /// Print the result of utility commands as text or JSON (errors go to stderr in the same format)
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// Print messages without emoji (also when NO_COLOR is set or stdout isn't a terminal)
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Send the notifications for a job written by a detached hook invocation
+    #[arg(long, value_name = "FILE", hide = true)]
+    deliver: Option<PathBuf>,
+
+    /// Send Claude reminders as they fall due until none are pending
+    #[arg(long, hide = true)]
+    deliver_reminders: bool,
+
+    /// Print how long each stage of the invocation took to stderr
+    #[arg(long, hide = true, global = true)]
+    time_report: bool,
+
     #[command(subcommand)]
     command: Option<Commands>,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Process Claude Code hook events and send desktop notifications (You aren't meant to use this directly. It's called by Claude Code)
-    Claude,
+    Claude {
+        /// Hook payload JSON passed as a single CLI arg, or `@<path>` of a JSON file to read it
+        /// from. If absent, read stdin.
+        #[arg(conflicts_with = "input")]
+        payload: Option<String>,
+        /// Read the hook payload from this file instead of stdin (for testing saved payloads)
+        #[arg(long, value_name = "FILE")]
+        input: Option<PathBuf>,
+        /// Give up when stdin stays empty this many seconds (0 waits indefinitely; default from claude.stdin_timeout_secs)
+        #[arg(long, value_name = "SECS")]
+        stdin_timeout: Option<u64>,
+        /// Print what would be sent to stderr instead of sending it (also ANOT_DRY_RUN=1)
+        #[arg(long)]
+        dry_run: bool,
+    },
     /// Process Codex notifications and send desktop notifications (You aren't meant to use this directly. It's called by Codex)
     Codex {
-        /// Notification JSON passed by Codex as a single CLI arg. If absent, read stdin.
+        /// Notification JSON passed by Codex as a single CLI arg, `@<path>` or the path of a JSON
+        /// file to read it from. If absent, read stdin.
         notification: Option<String>,
+        /// Read the notification JSON from this file
+        #[arg(long, value_name = "PATH", conflicts_with = "notification")]
+        file: Option<PathBuf>,
+        /// Print what would be sent to stderr instead of sending it (also ANOT_DRY_RUN=1)
+        #[arg(long)]
+        dry_run: bool,
     },
     #[command(
         about = "Process OpenCode events and send desktop notifications (pipe an OpenCode event JSON payload to stdin)"
@@ -53,7 +160,170 @@ enum Commands {
         #[command(subcommand)]
         command: Option<InitCommands>,
     },
-    Reset,
+    /// Reset the config, or one section or setting of it, to the defaults
+    Reset {
+        /// What to reset: `claude`, `codex`, a dotted key such as `claude.sound`, or `all` (default)
+        scope: Option<String>,
+        /// Don't ask; back up the old config and reset it
+        #[arg(long, short = 'y')]
+        yes: bool,
+    },
+    /// Remove anot's hooks from Claude Code and Codex, and optionally its config and state
+    Uninstall {
+        /// Don't ask; remove the hooks, and delete anot's own files only with --purge
+        #[arg(long, short = 'y')]
+        yes: bool,
+        /// With --yes, also delete anot's config, state, history and logs
+        #[arg(long)]
+        purge: bool,
+    },
+    /// Show recently sent notifications, newest first
+    History {
+        /// Number of entries to show
+        #[arg(short = 'n', long = "limit", default_value_t = 20)]
+        limit: usize,
+        /// Only show notifications from this agent (claude, codex, opencode)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Only show notifications newer than this (e.g. 90s, 15m, 2h, 1d)
+        #[arg(long)]
+        since: Option<String>,
+        /// Print entries as JSON (same as --output json)
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the agent sessions anot has seen in the last day, most recently active first
+    Sessions {
+        /// Print sessions as JSON (same as --output json)
+        #[arg(long)]
+        json: bool,
+        /// Refresh the list every few seconds until interrupted
+        #[arg(long)]
+        watch: bool,
+    },
+    /// Read the events log that `events_log` turns on
+    Events {
+        #[command(subcommand)]
+        command: EventsCommands,
+    },
+    /// Send the most recent notification again, marked as a replay
+    Replay {
+        /// Replay this agent's last payload (claude, codex, opencode)
+        #[arg(long)]
+        agent: Option<String>,
+        /// Replay a recorded payload from this file instead
+        #[arg(long, value_name = "FILE", requires = "agent")]
+        file: Option<PathBuf>,
+    },
+    /// Hold back all agent notifications, e.g. while screen-sharing
+    Mute {
+        /// How long to mute for (e.g. 90s, 15m, 2h, 1d). Without it, until `anot unmute`
+        duration: Option<String>,
+    },
+    /// End a mute and send the notifications it deferred
+    Unmute,
+    /// Show whether notifications are muted and until when, and how many are pending
+    Status,
+    /// Accept Claude and Codex payloads over HTTP (POST /claude, POST /codex) from agents on other machines
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:4990")]
+        bind: String,
+        /// Require `Authorization: Bearer <token>` (also ANOT_SERVE_TOKEN)
+        #[arg(long)]
+        token: Option<String>,
+        /// Listen on an address beyond this machine without a token
+        #[arg(long)]
+        allow_unauthenticated: bool,
+    },
+    /// Send the notifications held back in the spool now
+    Flush {
+        /// Send one notification summing them up instead of each one
+        #[arg(long)]
+        digest: bool,
+    },
+    /// Send a test notification and report what each backend, or each stage of the fallback chain, did
+    Test {
+        /// Send it the way this agent's notifications go (claude, codex, opencode)
+        #[arg(long, default_value = "claude")]
+        agent: String,
+    },
+    /// List, show or switch the named profiles in the config
+    Profile {
+        #[command(subcommand)]
+        command: ProfileCommands,
+    },
+    /// Show the loaded config or change a setting
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommands,
+    },
+    /// Print a shell completion script, e.g. `anot completions zsh > ~/.zfunc/_anot`
+    Completions { shell: clap_complete::Shell },
+    /// Print the manpage, or write a page for each command to a directory
+    Man {
+        /// Write `anot.1` and a page per subcommand (e.g. `anot-init-claude.1`) to this directory
+        #[arg(long, value_name = "DIR")]
+        out_dir: Option<PathBuf>,
+        /// Only check that every page renders
+        #[arg(long, hide = true, conflicts_with = "out_dir")]
+        check: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommands {
+    /// Print the config with the local overrides merged in
+    Show {
+        /// List each setting with the file it comes from (config, local or default)
+        #[arg(long)]
+        origins: bool,
+    },
+    /// Set a setting by its dotted key, e.g. `claude.sound false` (values are JSON, or strings)
+    Set {
+        key: String,
+        value: String,
+        /// Write it to the machine-local a-notifications.local.json instead
+        #[arg(long)]
+        local: bool,
+    },
+    /// Check that the config and its local overrides load, without changing them
+    Validate,
+    /// Rewrite the config and its local overrides in another format (`json` or `toml`)
+    Convert {
+        #[arg(long, value_name = "FORMAT")]
+        to: ConfigFormat,
+    },
+    /// Print the JSON Schema of the config, for editors that check the file as you type
+    Schema {
+        /// Write it to this file instead
+        #[arg(long, value_name = "FILE")]
+        out: Option<PathBuf>,
+        /// Point the config's `$schema` at the file written with --out, or at the published schema (a missing config is created with it)
+        #[arg(long)]
+        with_schema: bool,
+    },
+}
+
+#[derive(Subcommand)]
+enum EventsCommands {
+    /// Print the last few events, then each new one as it's appended, until interrupted
+    Follow {
+        /// Number of earlier events to show first
+        #[arg(short = 'n', long = "lines", default_value_t = 10)]
+        lines: usize,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommands {
+    /// List the defined profiles, marking the one in use
+    List,
+    /// Print the settings of a profile
+    Show { name: String },
+    /// Use a profile whenever neither --profile nor ANOT_PROFILE selects one
+    Use { name: String },
 }
 
 #[derive(Subcommand)]
@@ -61,82 +331,563 @@ enum InitCommands {
     Claude {
         #[arg(help = "Path to Claude Code settings.json file (optional)")]
         claude_config_path: Option<PathBuf>,
+        /// Only notify for tools matching this pattern in PreToolUse/PostToolUse (repeatable; skips the tool prompt)
+        #[arg(long = "matcher", value_name = "PATTERN")]
+        matchers: Vec<String>,
+        /// Seconds Claude Code waits for the hook (1-600; skips the timeout prompt)
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+        /// Show the changes to the settings file without writing them
+        #[arg(long)]
+        dry_run: bool,
     },
     Codex {
         #[arg(help = "Path to Codex config.toml file (optional)")]
         codex_config_path: Option<PathBuf>,
+        /// Configure notify in this Codex profile ([profiles.<NAME>]) instead of the top level
+        #[arg(long = "profile", value_name = "NAME")]
+        codex_profile: Option<String>,
+        /// Show the changes to config.toml without writing them
+        #[arg(long)]
+        dry_run: bool,
     },
     #[command(about = "Install an OpenCode plugin that forwards OpenCode events to this tool")]
     Opencode {
         #[arg(help = "Path to OpenCode plugin file (optional)")]
         opencode_plugin_path: Option<PathBuf>,
     },
+    /// Walk through the main settings of the anot config (agents, sound, events, quiet hours)
+    Config,
 }
 
 fn main() -> Result<(), Error> {
+    let started = Instant::now();
     let cli = Cli::parse();
+    if cli.time_report {
+        timing::start(started);
+        timing::mark("arguments");
+    }
+    let format = cli.output;
+    console::init(cli.plain);
+
+    let result = run(cli);
+    // Once the hook output is out, so a slow command never holds it up.
+    delivery::on_sent::run_pending();
+    delivery::sound::play_pending();
+    timing::mark("after the output");
+    if let Some(report) = timing::finish() {
+        eprint!("{report}");
+    }
+
+    let Err(e) = result else {
+        return Ok(());
+    };
+    if format == OutputFormat::Json {
+        eprintln!("{}", serde_json::to_string(&ErrorReport::new(&e))?);
+    } else {
+        eprintln!("Error: {e:#}");
+        if let Some(hint) = error::find(&e).and_then(AnotError::hint) {
+            eprintln!("hint: {hint}");
+        }
+    }
+    std::process::exit(error::exit_code(&e));
+}
+
+fn run(cli: Cli) -> Result<(), Error> {
+    // Generated from the CLI definition alone, without a config or logging.
+    if let Some(Commands::Completions { shell }) = &cli.command {
+        let mut script = Vec::new();
+        clap_complete::generate(*shell, &mut Cli::command(), "anot", &mut script);
+        return io::stdout()
+            .write_all(&script)
+            .map_err(|e| AnotError::io("Failed to print the completion script", e).into());
+    }
+    if let Some(Commands::Man { out_dir, check }) = &cli.command {
+        return man(out_dir.as_deref(), *check);
+    }
 
-    init_tracing(cli.debug);
+    let json_output = cli.output == OutputFormat::Json;
+    // Moved before the log file is opened, and reported once logging is up.
+    let logs_migration = configuration::migrate_legacy_logs();
+    let _log_guard = logging::init_tracing(cli.debug);
+    configuration::report_logs_migration(&logs_migration);
+    timing::mark("logging");
 
     let config_path = get_config_path().expect("Failed to determine config path");
 
-    if let Some(Commands::Reset) = cli.command {
-        match reset_configuration(config_path.as_path()) {
-            Ok(_) => println!(
+    let config_path = cli.config.clone().unwrap_or(config_path);
+
+    if let Some(Commands::Reset { scope, yes }) = &cli.command {
+        let scope = scope.as_deref().filter(|scope| *scope != "all");
+        if json_output && !yes {
+            bail!("`anot reset` asks for confirmation; pass --yes with --output json");
+        }
+        let Some(report) = reset::run(&config_path, scope, *yes)? else {
+            return Ok(());
+        };
+        if json_output {
+            return output::print_json(&report);
+        }
+        if let Some(backup) = &report.backup {
+            println!("Backed up the old config to {}", backup.display());
+        }
+        match scope {
+            Some(scope) => println!(
+                "Reset `{scope}` to the defaults in {}",
+                config_path.display()
+            ),
+            None => println!(
                 "Configuration reset to default at {}",
                 config_path.display()
             ),
-            Err(e) => eprintln!("Failed to reset configuration: {}", e),
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Uninstall { yes, purge }) = &cli.command {
+        if json_output && !yes {
+            bail!("`anot uninstall` asks for confirmation; pass --yes with --output json");
+        }
+        let steps = uninstall::run(&UninstallOptions {
+            yes: *yes,
+            purge: *purge,
+            config_path: cli.config.clone(),
+        });
+        if json_output {
+            output::print_json(&steps)?;
+        } else {
+            for step in &steps {
+                println!("{}", uninstall::format_step(step));
+            }
+        }
+        let failed = steps
+            .iter()
+            .filter(|step| step.outcome == StepOutcome::Failed)
+            .count();
+        if failed > 0 {
+            bail!("{failed} uninstall step(s) failed");
+        }
+        return Ok(());
+    }
+
+    // Works even when the file it would fix doesn't load.
+    if let Some(Commands::Config {
+        command: ConfigCommands::Set { key, value, local },
+    }) = &cli.command
+    {
+        let path = if *local {
+            local::local_path(&config_path)
+        } else {
+            config_path
         };
+        local::set_value(&path, key, value)?;
+        if json_output {
+            return output::print_json(&ConfigSet {
+                path,
+                key: key.clone(),
+            });
+        }
+        println!("Set {key} in {}.", path.display());
         return Ok(());
     }
 
-    let config =
-        initialize_configuration(cli.config.clone().unwrap_or(config_path.clone()).as_path())?;
+    if let Some(Commands::Config {
+        command: ConfigCommands::Validate,
+    }) = &cli.command
+    {
+        let files = configuration::validate_configuration(&config_path)?;
+        if json_output {
+            return output::print_json(&ConfigValidation { files });
+        }
+        for file in &files {
+            println!("{} is valid.", file.path.display());
+            if file.lenient {
+                console::say(
+                    Mark::Info,
+                    "It has comments or trailing commas: anot accepts them, but drops them when it rewrites the file.",
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if let Some(Commands::Config {
+        command: ConfigCommands::Convert { to },
+    }) = &cli.command
+    {
+        let files = format::convert(&config_path, *to)?;
+        if json_output {
+            return output::print_json(&ConfigConversion { files });
+        }
+        for file in &files {
+            println!(
+                "Wrote {} (the old file is now {}).",
+                file.path.display(),
+                file.backup.display()
+            );
+        }
+        if cli.config.is_some() {
+            console::say(
+                Mark::Info,
+                "Pass the new path to --config from now on, and re-run `anot init` if your hooks pass --config.",
+            );
+        }
+        return Ok(());
+    }
+
+    // Derived from the structs alone, so it works whatever state the config is in.
+    if let Some(Commands::Config {
+        command: ConfigCommands::Schema { out, with_schema },
+    }) = &cli.command
+    {
+        return config_schema(&config_path, out.as_deref(), *with_schema, json_output);
+    }
+
+    let created = !config_path.exists();
+    let config = initialize_configuration(config_path.as_path())?;
+    onboarding::run(&config, created, onboarding_mode(&cli));
+    timing::mark("config");
+
+    if let Some(Commands::Config {
+        command: ConfigCommands::Show { origins },
+    }) = &cli.command
+    {
+        return show_config(&config, &config_path, *origins, json_output);
+    }
+
+    if let Some(Commands::Profile { command }) = &cli.command {
+        return run_profile_command(
+            &config,
+            &config_path,
+            cli.profile.as_deref(),
+            command,
+            json_output,
+        );
+    }
+
+    let mut config = profiles::apply_selected_profile(&config, cli.profile.as_deref())?;
+
+    config.debug.capture_payloads |= cli.capture;
+    timing::mark("profile");
+
+    if let Some(job) = &cli.deliver {
+        if let Err(e) = delivery::detached::run_job(job, &config) {
+            error!(error = %format_args!("{e:#}"), job = %job.display(), "background delivery failed");
+        }
+        return Ok(());
+    }
+
+    if cli.deliver_reminders {
+        if let Err(e) = reminders::run_scheduler(&config) {
+            error!(error = %e, "reminder scheduler failed");
+        }
+        return Ok(());
+    }
 
     match &cli.command {
-        Some(Commands::Claude) => {
-            debug!("processing Claude input from stdin");
-            let input = utils::catch_stdin();
-            if let Err(e) = process_claude_input(input, &config) {
-                error!(error = %e, "failed to process Claude input");
+        Some(Commands::Claude {
+            payload,
+            input,
+            stdin_timeout,
+            dry_run,
+        }) => {
+            config.dry_run = delivery::dry_run::requested(*dry_run);
+            let timeout = stdin_timeout.unwrap_or(config.claude.stdin_timeout_secs);
+            debug!(file = ?input, timeout, "processing Claude input");
+            let Some(input) = read_hook_input(payload.as_deref(), input.as_deref(), timeout)?
+            else {
+                if let Some(output) = emitted_output(&config, &no_input_output(timeout))? {
+                    print!("{output}");
+                }
+                return Ok(());
+            };
+            record_payload(&config, "claude", &input);
+            timing::mark("input");
+            let parses = serde_json::from_str::<HookInput>(&input).is_ok();
+            crash::log_panics();
+            let output = match crash::catch(|| process_claude_input(input, &config)) {
+                Ok(output) => output,
+                Err(crash) => {
+                    if let Some(output) = emitted_output(&config, &crash::hook_output(&crash))? {
+                        print!("{output}");
+                    }
+                    return Err(crash.into());
+                }
+            };
+            timing::mark("processing");
+            if let Some(emitted) = emitted_output(&config, &output)? {
+                print!("{emitted}");
             }
+            timing::mark("output");
+            if config.dry_run && !parses {
+                bail!("the Claude hook payload isn't valid");
+            }
+            debug!(
+                suppress_output = output.suppress_output.unwrap_or(false),
+                cont = output.r#continue.unwrap_or(false),
+                has_system_message = output
+                    .system_message
+                    .as_ref()
+                    .map(|s| !s.is_empty())
+                    .unwrap_or(false),
+                "emitted Claude hook output JSON"
+            );
         }
-        Some(Commands::Codex { notification }) => {
-            let input = match notification {
-                Some(s) => s.clone(),
-                None => utils::catch_stdin(),
-            };
-            if let Err(e) = process_codex_input(input, &config) {
-                error!(error = %e, "failed to process Codex input");
+        Some(Commands::Codex {
+            notification,
+            file,
+            dry_run,
+        }) => {
+            config.dry_run = delivery::dry_run::requested(*dry_run);
+            let (input, source) =
+                read_codex_payload(notification.as_deref(), file.as_deref(), utils::catch_stdin)
+                    .inspect_err(|e| error!(error = %e, "failed to read Codex input"))?;
+            record_payload(&config, "codex", &input);
+            timing::mark("input");
+            crash::log_panics();
+            if let Err(e) = crash::catch(|| process_codex_input(input, &config))? {
+                error!(error = %format_args!("{e:#}"), %source, "failed to process Codex input");
+                // Codex ignores the exit status, but someone trying a payload wants to see it.
+                if config.dry_run {
+                    return Err(e);
+                }
             }
+            timing::mark("processing");
+        }
+        #[cfg(feature = "serve")]
+        Some(Commands::Serve {
+            bind,
+            token,
+            allow_unauthenticated,
+        }) => {
+            let token = token
+                .clone()
+                .or_else(|| std::env::var(serve::TOKEN_ENV).ok())
+                .filter(|token| !token.is_empty());
+            serve::run(&config, bind, token, *allow_unauthenticated, |address| {
+                println!("Listening on http://{address}");
+                let _ = std::io::Write::flush(&mut std::io::stdout());
+            })?;
         }
         Some(Commands::Opencode { event }) => {
             let input = match event {
                 Some(s) => s.clone(),
                 None => utils::catch_stdin(),
             };
+            record_payload(&config, "opencode", &input);
             if let Err(e) = process_opencode_input(input, &config) {
-                error!(error = %e, "failed to process OpenCode input");
+                error!(error = %format_args!("{e:#}"), "failed to process OpenCode input");
                 return Err(e);
             }
         }
+        Some(Commands::History {
+            limit,
+            agent,
+            since,
+            json,
+        }) => {
+            let filter = HistoryFilter {
+                limit: *limit,
+                agent: agent.clone(),
+                since_secs: since.as_deref().map(utils::parse_duration).transpose()?,
+            };
+            let now = utils::unix_now();
+            let entries = filter_entries(read_entries(&history_path(&config))?, &filter, now);
+
+            if *json || json_output {
+                output::print_json(&entries)?;
+            } else if entries.is_empty() {
+                println!("No notifications recorded yet.");
+            } else {
+                for entry in &entries {
+                    println!("{}", format_entry(entry, now));
+                }
+            }
+        }
+        Some(Commands::Sessions { json, watch }) => {
+            let path = sessions::sessions_path(&config);
+            loop {
+                let now = utils::unix_now();
+                let listed = sessions::list(&path, now)?;
+                if *json || json_output {
+                    output::print_json(&listed)?;
+                } else {
+                    if *watch {
+                        // Clear the screen and move the cursor home before redrawing.
+                        print!("\x1b[2J\x1b[H");
+                    }
+                    if listed.is_empty() {
+                        println!("No sessions seen in the last day.");
+                    }
+                    for session in &listed {
+                        println!("{}", sessions::format_session(session, now));
+                    }
+                }
+                if !*watch {
+                    break;
+                }
+                std::io::Write::flush(&mut std::io::stdout())?;
+                std::thread::sleep(std::time::Duration::from_secs(SESSIONS_WATCH_INTERVAL_SECS));
+            }
+        }
+        Some(Commands::Events {
+            command: EventsCommands::Follow { lines },
+        }) => {
+            if !config.events_log {
+                eprintln!(
+                    "{}",
+                    console::line(
+                        console::style(),
+                        Mark::Info,
+                        "events_log is off, so no events are being recorded. Turn it on with `anot config set events_log true`.",
+                    )
+                );
+            }
+            follow_events(&events::events_path(&config), *lines, json_output)?;
+        }
+        Some(Commands::Replay { agent, file }) => {
+            let (agent, input) = match (agent, file) {
+                (Some(agent), Some(file)) => (agent.clone(), replay::read_payload(file)?),
+                (agent, None) => match replay::last_payload(&config, agent.as_deref())? {
+                    Some(payload) => payload,
+                    None => bail!(
+                        "Nothing to replay yet: no {} payload has been recorded. \
+                         Notifications are recorded as agents send them.",
+                        agent.as_deref().unwrap_or("agent")
+                    ),
+                },
+                (None, Some(_)) => unreachable!("clap requires --agent with --file"),
+            };
+            replay::replay(&config, &agent, input)?;
+            if json_output {
+                output::print_json(&ReplayReport { agent })?;
+            } else {
+                println!("Replayed the {agent} payload.");
+            }
+        }
+        Some(Commands::Mute { duration }) => {
+            let duration = duration.as_deref().map(utils::parse_duration).transpose()?;
+            let state = mute::mute(&config, duration)?;
+            if json_output {
+                output::print_json(&mute::mute_status(&config, state.since)?)?;
+            } else {
+                println!("{}", mute::status(&config, state.since)?);
+            }
+        }
+        Some(Commands::Unmute) => match mute::unmute(&config)? {
+            sent if json_output => output::print_json(&UnmuteReport { sent })?,
+            0 => println!("Notifications unmuted."),
+            sent => println!("Notifications unmuted; sent {sent} deferred."),
+        },
+        Some(Commands::Flush { digest }) => {
+            let report = spool::flush(&config, *digest)?;
+            if json_output {
+                output::print_json(&report)?;
+            } else if report.flushed == 0 {
+                println!("No notifications are pending.");
+            } else if report.digest {
+                println!(
+                    "Sent a digest of {} pending notification(s).",
+                    report.flushed
+                );
+            } else {
+                println!("Sent {} pending notification(s).", report.flushed);
+            }
+            if report.failed > 0 {
+                bail!("{} pending notification(s) couldn't be sent", report.failed);
+            }
+        }
+        Some(Commands::Status) => {
+            if json_output {
+                output::print_json(&mute::mute_status(&config, utils::unix_now())?)?;
+            } else {
+                println!("{}", mute::status(&config, utils::unix_now())?);
+            }
+        }
+        Some(Commands::Test { agent }) => {
+            let stages = delivery::test_backends(
+                &config,
+                agent,
+                "anot · Test notification",
+                "If you can see this, anot can reach you.",
+            )?;
+            if json_output {
+                output::print_json(&stages)?;
+            } else {
+                for stage in &stages {
+                    println!("{}", delivery::format_stage(stage));
+                }
+            }
+            if !stages
+                .iter()
+                .any(|stage| stage.outcome == StageOutcome::Delivered)
+            {
+                bail!("no backend delivered the test notification");
+            }
+        }
         Some(Commands::Init { command }) => match command {
-            Some(InitCommands::Claude { claude_config_path }) => {
-                crate::processors::claude::init::initialize_claude_configuration(
+            Some(InitCommands::Claude {
+                claude_config_path,
+                matchers,
+                timeout,
+                dry_run,
+            }) => {
+                processors::claude::init::initialize_claude_configuration(
                     claude_config_path,
+                    &processors::claude::init::InitOptions {
+                        matchers: matchers.clone(),
+                        timeout: *timeout,
+                        config_path: cli.config.clone(),
+                        dry_run: *dry_run,
+                    },
                 )?;
             }
-            Some(InitCommands::Codex { codex_config_path }) => {
-                crate::processors::codex::init::initialize_codex_configuration(codex_config_path)?;
+            Some(InitCommands::Codex {
+                codex_config_path,
+                codex_profile,
+                dry_run,
+            }) => {
+                processors::codex::init::initialize_codex_configuration(
+                    codex_config_path,
+                    codex_profile.as_deref(),
+                    *dry_run,
+                )?;
             }
             Some(InitCommands::Opencode {
                 opencode_plugin_path,
             }) => {
-                crate::processors::opencode::init::initialize_opencode_configuration(
+                processors::opencode::init::initialize_opencode_configuration(
                     opencode_plugin_path,
                 )?;
             }
+            Some(InitCommands::Config) => wizard::run(&config_path)?,
+            None if std::io::stdin().is_terminal() => {
+                let options = vec![
+                    "anot config",
+                    "Claude Code hooks",
+                    "Codex notify",
+                    "OpenCode plugin",
+                ];
+                let choice = inquire::Select::new("What do you want to set up?", options)
+                    .prompt()
+                    .map_err(|err| handle_inquire_error(err, "Failed to prompt for init target"))?;
+                match choice {
+                    "anot config" => wizard::run(&config_path)?,
+                    "Claude Code hooks" => {
+                        processors::claude::init::initialize_claude_configuration(
+                            &None,
+                            &processors::claude::init::InitOptions {
+                                config_path: cli.config.clone(),
+                                ..Default::default()
+                            },
+                        )?
+                    }
+                    "Codex notify" => {
+                        processors::codex::init::initialize_codex_configuration(&None, None, false)?
+                    }
+                    _ => processors::opencode::init::initialize_opencode_configuration(&None)?,
+                }
+            }
             None => {
                 let mut cmd = Cli::command();
                 if let Some(init_cmd) = cmd.find_subcommand_mut("init") {
@@ -157,31 +908,213 @@ fn main() -> Result<(), Error> {
     Ok(())
 }
 
-static LOG_GUARD: OnceLock<tracing_appender::non_blocking::WorkerGuard> = OnceLock::new();
+/// `anot config schema`: prints the schema or writes it to `out`, and with `with_schema` points
+/// the config's `$schema` at it.
+fn config_schema(
+    config_path: &Path,
+    out: Option<&Path>,
+    with_schema: bool,
+    json_output: bool,
+) -> Result<(), Error> {
+    let contents = serde_json::to_string_pretty(&schema::config_schema())?;
+    let Some(out) = out else {
+        println!("{contents}");
+        if with_schema {
+            schema::link(config_path, schema::PUBLISHED_URL)?;
+            eprintln!(
+                "Pointed $schema in {} at {}.",
+                config_path.display(),
+                schema::PUBLISHED_URL
+            );
+        }
+        return Ok(());
+    };
+
+    write_atomic(out, format!("{contents}\n").as_bytes())?;
+    let linked_config = if with_schema {
+        schema::link(config_path, &schema::file_url(out)?)?;
+        Some(config_path.to_path_buf())
+    } else {
+        None
+    };
+    if json_output {
+        return output::print_json(&ConfigSchema {
+            path: out.to_path_buf(),
+            linked_config,
+        });
+    }
+    println!("Wrote the config schema to {}.", out.display());
+    if let Some(config_path) = linked_config {
+        println!("Pointed $schema in {} at it.", config_path.display());
+    }
+    Ok(())
+}
 
-fn init_tracing(verbosity: u8) {
-    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| match verbosity {
-        0 => EnvFilter::new("warn"),
-        1 => EnvFilter::new("info"),
-        2 => EnvFilter::new("debug"),
-        _ => EnvFilter::new("trace"),
-    });
+/// `anot man`: the page for `anot` on stdout, or every command's page written to `out_dir`.
+/// With `check`, the pages are only rendered, to see that they can be.
+fn man(out_dir: Option<&Path>, check: bool) -> Result<(), Error> {
+    let cmd = Cli::command().name("anot");
+    if check {
+        let pages =
+            render_man_pages(cmd).map_err(|e| AnotError::io("Failed to render the manpages", e))?;
+        println!("{pages} manpages render.");
+        return Ok(());
+    }
 
-    let log_dir = crate::configuration::get_logs_dir();
+    let Some(dir) = out_dir else {
+        return clap_mangen::Man::new(cmd)
+            .render(&mut io::stdout())
+            .map_err(|e| AnotError::io("Failed to print the manpage", e).into());
+    };
+    std::fs::create_dir_all(dir)
+        .and_then(|()| clap_mangen::generate_to(cmd, dir))
+        .map_err(|e| {
+            AnotError::io(
+                format!("Failed to write the manpages to {}", dir.display()),
+                e,
+            )
+        })?;
+    console::say(
+        Mark::Written,
+        format!("Wrote the manpages to {}", dir.display()),
+    );
+    Ok(())
+}
 
-    let _ = std::fs::create_dir_all(&log_dir);
+/// Prints the last `lines` events of the log at `path`, then each one appended, as they'd be
+/// listed or as the raw JSON lines with `--output json`. Lines that aren't records this anot
+/// understands are printed as they are.
+fn follow_events(path: &Path, lines: usize, json_output: bool) -> Result<(), Error> {
+    let mut follower = events::Follower::new(path);
+    let mut appended = follower.poll()?;
+    appended.drain(..appended.len().saturating_sub(lines));
+    loop {
+        for line in &appended {
+            match events::parse_line(line) {
+                Ok(record) if !json_output => println!("{}", events::format_record(&record)),
+                _ => println!("{line}"),
+            }
+        }
+        io::stdout().flush()?;
+        std::thread::sleep(std::time::Duration::from_millis(
+            EVENTS_FOLLOW_INTERVAL_MILLIS,
+        ));
+        appended = follower.poll()?;
+    }
+}
 
-    let file_appender = tracing_appender::rolling::daily(&log_dir, "anot.log");
-    let (non_blocking, guard) = tracing_appender::non_blocking(file_appender);
-    let _ = LOG_GUARD.set(guard);
+/// Renders the pages `clap_mangen::generate_to` writes, without writing them, and counts them.
+fn render_man_pages(cmd: clap::Command) -> io::Result<usize> {
+    fn render(cmd: clap::Command) -> io::Result<usize> {
+        let mut pages = 1;
+        for subcommand in cmd.get_subcommands().filter(|s| !s.is_hide_set()) {
+            pages += render(subcommand.clone())?;
+        }
+        clap_mangen::Man::new(cmd).render(&mut io::sink())?;
+        Ok(pages)
+    }
 
-    let fmt_layer = fmt::layer()
-        .with_ansi(false)
-        .with_writer(non_blocking)
-        .with_target(false);
+    let mut cmd = cmd.disable_help_subcommand(true);
+    cmd.build();
+    render(cmd)
+}
 
-    tracing_subscriber::registry()
-        .with(filter)
-        .with(fmt_layer)
-        .init();
+/// How the first-run hint may be shown for the command `cli` runs.
+fn onboarding_mode(cli: &Cli) -> onboarding::Mode {
+    if cli.deliver.is_some() || cli.deliver_reminders {
+        return onboarding::Mode::Hook;
+    }
+    match cli.command {
+        Some(Commands::Claude { .. } | Commands::Codex { .. } | Commands::Opencode { .. }) => {
+            onboarding::Mode::Hook
+        }
+        Some(Commands::Init { .. }) => onboarding::Mode::Setup,
+        _ => onboarding::Mode::Interactive,
+    }
+}
+
+fn run_profile_command(
+    config: &Config,
+    config_path: &Path,
+    flag: Option<&str>,
+    command: &ProfileCommands,
+    json_output: bool,
+) -> Result<(), Error> {
+    match command {
+        ProfileCommands::List => {
+            let env_value = std::env::var(PROFILE_ENV).ok();
+            let selected = profiles::selected_profile(
+                flag,
+                env_value.as_deref(),
+                config.active_profile.as_deref(),
+            );
+            if json_output {
+                return output::print_json(&ProfileList {
+                    profiles: config.profiles.keys().cloned().collect(),
+                    selected,
+                });
+            }
+            if config.profiles.is_empty() {
+                println!("No profiles defined.");
+                return Ok(());
+            }
+            for name in config.profiles.keys() {
+                let marker = if selected.as_deref() == Some(name) {
+                    "*"
+                } else {
+                    " "
+                };
+                println!("{marker} {name}");
+            }
+        }
+        ProfileCommands::Show { name } => {
+            let profile = profiles::profile(config, name)?;
+            println!("{}", serde_json::to_string_pretty(profile)?);
+        }
+        ProfileCommands::Use { name } => {
+            profiles::use_profile(config, config_path, name)?;
+            if json_output {
+                output::print_json(&ProfileUse {
+                    active_profile: name.clone(),
+                })?;
+            } else {
+                println!("Using profile {name}.");
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Prints the loaded config, or with `origins` each setting and the file it comes from.
+fn show_config(
+    config: &Config,
+    config_path: &Path,
+    origins: bool,
+    json_output: bool,
+) -> Result<(), Error> {
+    let merged = serde_json::to_value(config)?;
+    if !origins {
+        return output::print_json(&merged);
+    }
+
+    let contents = ConfigFormat::of(config_path).parse(&std::fs::read_to_string(config_path)?)?;
+    let local = local::read_local(config_path)?;
+    let origins = local::origins(&merged, &contents, local.as_ref());
+    if json_output {
+        return output::print_json(&origins);
+    }
+    for origin in &origins {
+        println!("{}", local::format_origin(origin));
+    }
+    Ok(())
+}
+
+/// Keeps the raw payload an agent sent for `anot replay` and, when enabled, as a capture. A
+/// dry run keeps nothing.
+fn record_payload(config: &Config, agent: &str, input: &str) {
+    if config.dry_run {
+        return;
+    }
+    capture::capture_payload(config, agent, input);
+    replay::record_payload(config, agent, input);
 }
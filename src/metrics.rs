@@ -0,0 +1,264 @@
+//! Prometheus metrics for the node_exporter textfile collector, written to
+//! `metrics.textfile_path` whenever they change. Each hook is a fresh process, so the counters
+//! are kept in `metrics.json` in the state directory and the whole file is rewritten from them.
+//!
+//! The metric names are stable:
+//!
+//! - `anot_notifications_sent_total{agent, event}`: counter of notifications handed to the
+//!   backends.
+//! - `anot_notifications_suppressed_total{agent, event}`: counter of events that didn't notify:
+//!   filtered out by the config, sampled away, muted, duplicates and the like.
+//! - `anot_delivery_failures_total{backend}`: counter of failed sends, per backend.
+//! - `anot_last_event_timestamp_seconds{agent}`: gauge of when the agent's last event was
+//!   handled, in seconds since the Unix epoch.
+
+use std::{collections::BTreeMap, fmt::Write, path::Path};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    configuration::{Backend, Config},
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::unix_now,
+};
+
+const STATE_FILE_NAME: &str = "metrics.json";
+
+/// The counters behind the metrics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct Stats {
+    /// Notifications sent, by agent and event.
+    pub sent: BTreeMap<String, BTreeMap<String, u64>>,
+    /// Events that didn't notify, by agent and event.
+    pub suppressed: BTreeMap<String, BTreeMap<String, u64>>,
+    /// Failed sends, by backend.
+    pub failures: BTreeMap<String, u64>,
+    /// When each agent's last event was handled.
+    pub last_event: BTreeMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    Sent,
+    Suppressed,
+}
+
+/// Counts a notification of `agent` that was sent or suppressed.
+pub fn record_notification(config: &Config, agent: &str, event: &str, outcome: Outcome) {
+    update(config, |stats| {
+        let counts = match outcome {
+            Outcome::Sent => &mut stats.sent,
+            Outcome::Suppressed => &mut stats.suppressed,
+        };
+        *counts
+            .entry(agent.to_string())
+            .or_default()
+            .entry(event.to_string())
+            .or_default() += 1;
+        stats.last_event.insert(agent.to_string(), unix_now());
+    });
+}
+
+/// Counts a send that failed in `backend`.
+pub fn record_failure(config: &Config, backend: Backend) {
+    update(config, |stats| {
+        *stats
+            .failures
+            .entry(backend.as_str().to_string())
+            .or_default() += 1;
+    });
+}
+
+/// Applies `change` to the stored counters and rewrites the textfile, when metrics are on.
+/// Failures are logged: metrics never keep a notification from being sent.
+fn update(config: &Config, change: impl FnOnce(&mut Stats)) {
+    let Some(textfile_path) = &config.metrics.textfile_path else {
+        return;
+    };
+    if config.dry_run {
+        return;
+    }
+
+    let state_path = config.data_dir().join(STATE_FILE_NAME);
+    let updated = with_lock(&state_path, || {
+        let mut stats: Stats = read_json_or_default(&state_path)?;
+        change(&mut stats);
+        write_atomic(&state_path, serde_json::to_string(&stats)?.as_bytes())?;
+        write_textfile(textfile_path, &stats)
+    });
+    if let Err(e) = updated {
+        warn!(path = %textfile_path.display(), error = %e, "failed to update metrics");
+    }
+}
+
+/// Replaces the textfile at `path` in one step, so the collector never reads half of it.
+pub fn write_textfile(path: &Path, stats: &Stats) -> Result<(), Error> {
+    write_atomic(path, render(stats).as_bytes())
+}
+
+/// `stats` in the Prometheus text exposition format.
+pub fn render(stats: &Stats) -> String {
+    let mut out = String::new();
+    let by_agent_and_event = |counts: &BTreeMap<String, BTreeMap<String, u64>>| {
+        counts
+            .iter()
+            .flat_map(|(agent, events)| {
+                events.iter().map(move |(event, count)| {
+                    (
+                        format!("agent=\"{}\",event=\"{}\"", escape(agent), escape(event)),
+                        *count,
+                    )
+                })
+            })
+            .collect::<Vec<_>>()
+    };
+
+    family(
+        &mut out,
+        "anot_notifications_sent_total",
+        "counter",
+        "Notifications handed to the backends.",
+        by_agent_and_event(&stats.sent),
+    );
+    family(
+        &mut out,
+        "anot_notifications_suppressed_total",
+        "counter",
+        "Events that didn't notify, e.g. filtered out, muted or duplicates.",
+        by_agent_and_event(&stats.suppressed),
+    );
+    family(
+        &mut out,
+        "anot_delivery_failures_total",
+        "counter",
+        "Sends that failed, per backend.",
+        stats
+            .failures
+            .iter()
+            .map(|(backend, count)| (format!("backend=\"{}\"", escape(backend)), *count))
+            .collect(),
+    );
+    family(
+        &mut out,
+        "anot_last_event_timestamp_seconds",
+        "gauge",
+        "When the agent's last event was handled, in seconds since the Unix epoch.",
+        stats
+            .last_event
+            .iter()
+            .map(|(agent, at)| (format!("agent=\"{}\"", escape(agent)), *at))
+            .collect(),
+    );
+    out
+}
+
+fn family(out: &mut String, name: &str, kind: &str, help: &str, samples: Vec<(String, u64)>) {
+    let _ = writeln!(out, "# HELP {name} {help}");
+    let _ = writeln!(out, "# TYPE {name} {kind}");
+    for (labels, value) in samples {
+        let _ = writeln!(out, "{name}{{{labels}}} {value}");
+    }
+}
+
+/// `value` escaped for a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', r"\\")
+        .replace('"', "\\\"")
+        .replace('\n', r"\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::test_support::temp_path;
+
+    fn counts(entries: &[(&str, &str, u64)]) -> BTreeMap<String, BTreeMap<String, u64>> {
+        let mut counts: BTreeMap<String, BTreeMap<String, u64>> = BTreeMap::new();
+        for (agent, event, count) in entries {
+            counts
+                .entry(agent.to_string())
+                .or_default()
+                .insert(event.to_string(), *count);
+        }
+        counts
+    }
+
+    #[test]
+    fn renders_the_exposition_format() {
+        let stats = Stats {
+            sent: counts(&[("claude", "Stop", 3), ("codex", "AgentTurnComplete", 1)]),
+            suppressed: counts(&[("claude", "PostToolUse", 12)]),
+            failures: BTreeMap::from([("tmux".to_string(), 2)]),
+            last_event: BTreeMap::from([
+                ("claude".to_string(), 1767225600),
+                ("codex".to_string(), 1767225000),
+            ]),
+        };
+
+        assert_eq!(
+            render(&stats),
+            r#"# HELP anot_notifications_sent_total Notifications handed to the backends.
+# TYPE anot_notifications_sent_total counter
+anot_notifications_sent_total{agent="claude",event="Stop"} 3
+anot_notifications_sent_total{agent="codex",event="AgentTurnComplete"} 1
+# HELP anot_notifications_suppressed_total Events that didn't notify, e.g. filtered out, muted or duplicates.
+# TYPE anot_notifications_suppressed_total counter
+anot_notifications_suppressed_total{agent="claude",event="PostToolUse"} 12
+# HELP anot_delivery_failures_total Sends that failed, per backend.
+# TYPE anot_delivery_failures_total counter
+anot_delivery_failures_total{backend="tmux"} 2
+# HELP anot_last_event_timestamp_seconds When the agent's last event was handled, in seconds since the Unix epoch.
+# TYPE anot_last_event_timestamp_seconds gauge
+anot_last_event_timestamp_seconds{agent="claude"} 1767225600
+anot_last_event_timestamp_seconds{agent="codex"} 1767225000
+"#
+        );
+    }
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("a\"b\\c\nd"), r#"a\"b\\c\nd"#);
+    }
+
+    #[test]
+    fn counters_persist_across_updates() {
+        let dir = temp_path("metrics", "persist");
+        let textfile = dir.join("textfile").join("anot.prom");
+        let config = Config {
+            location: Some(dir.join("a-notifications.json")),
+            metrics: crate::configuration::Metrics {
+                textfile_path: Some(textfile.clone()),
+            },
+            ..Config::default()
+        };
+
+        record_notification(&config, "claude", "Stop", Outcome::Sent);
+        record_notification(&config, "claude", "Stop", Outcome::Sent);
+        record_notification(&config, "claude", "PostToolUse", Outcome::Suppressed);
+        record_failure(&config, Backend::Tmux);
+
+        let text = fs::read_to_string(&textfile).unwrap();
+        assert!(
+            text.contains("anot_notifications_sent_total{agent=\"claude\",event=\"Stop\"} 2\n")
+        );
+        assert!(text.contains(
+            "anot_notifications_suppressed_total{agent=\"claude\",event=\"PostToolUse\"} 1\n"
+        ));
+        assert!(text.contains("anot_delivery_failures_total{backend=\"tmux\"} 1\n"));
+        let stats: Stats = read_json_or_default(&dir.join(STATE_FILE_NAME)).unwrap();
+        assert_eq!(stats.sent["claude"]["Stop"], 2);
+
+        let dry_run = Config {
+            dry_run: true,
+            ..config
+        };
+        record_notification(&dry_run, "claude", "Stop", Outcome::Sent);
+        assert_eq!(fs::read_to_string(&textfile).unwrap(), text);
+    }
+}
@@ -0,0 +1,458 @@
+//! `anot mute`: a global switch that holds back every agent notification until it expires or
+//! `anot unmute` is run. With `respect_dnd`, the system's Do Not Disturb does the same, and so
+//! do `quiet_hours` every day. Hook output is unaffected, so the agents don't notice.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{
+    configuration::{Config, MuteMode},
+    delivery::dry_run,
+    dnd,
+    events::{self, Decision, EventRecord},
+    privacy,
+    spool::{self, SpooledNotification},
+    storage::{read_json_or_default, write_atomic},
+    utils::{format_duration, local_minute_of_day, unix_now},
+};
+
+const MUTE_FILE_NAME: &str = "mute.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+pub struct MuteState {
+    pub since: u64,
+    /// `None` mutes until `anot unmute`
+    pub until: Option<u64>,
+}
+
+impl MuteState {
+    fn is_active(&self, now: u64) -> bool {
+        self.until.is_none_or(|until| now < until)
+    }
+}
+
+fn mute_path(config: &Config) -> PathBuf {
+    config.data_dir().join(MUTE_FILE_NAME)
+}
+
+/// Mutes notifications for `duration_secs`, or until `anot unmute` without one.
+pub fn mute(config: &Config, duration_secs: Option<u64>) -> Result<MuteState, Error> {
+    let now = unix_now();
+    let state = MuteState {
+        since: now,
+        until: duration_secs.map(|secs| now + secs),
+    };
+    write_atomic(
+        &mute_path(config),
+        serde_json::to_string(&state)?.as_bytes(),
+    )?;
+    info!(until = ?state.until, "muted notifications");
+    Ok(state)
+}
+
+/// Lifts the mute and sends the deferred notifications. Returns how many were sent.
+pub fn unmute(config: &Config) -> Result<usize, Error> {
+    match fs::remove_file(mute_path(config)) {
+        Ok(()) => info!("unmuted notifications"),
+        Err(e) if e.kind() == ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+    flush_deferred(config)
+}
+
+/// The mute in effect at `now`, if any.
+pub fn active_mute(config: &Config, now: u64) -> Result<Option<MuteState>, Error> {
+    Ok(read_state(&mute_path(config))?.filter(|state| state.is_active(now)))
+}
+
+/// Number of notifications waiting in the spool.
+pub fn deferred_count(config: &Config) -> Result<usize, Error> {
+    Ok(spool::pending(config)?.len())
+}
+
+/// What `anot status` reports, in the shape `--output json` prints.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+pub struct MuteStatus {
+    pub muted: bool,
+    /// When the mute ends. `null` while muted means until `anot unmute`.
+    pub until: Option<u64>,
+    /// Whether Do Not Disturb holds notifications back (only checked with `respect_dnd`).
+    pub dnd: bool,
+    pub deferred: usize,
+}
+
+/// The mute, Do Not Disturb and deferred notifications at `now`.
+pub fn mute_status(config: &Config, now: u64) -> Result<MuteStatus, Error> {
+    let state = active_mute(config, now)?;
+    Ok(MuteStatus {
+        muted: state.is_some(),
+        until: state.and_then(|state| state.until),
+        dnd: config.respect_dnd && dnd::is_active(),
+        deferred: deferred_count(config)?,
+    })
+}
+
+/// The `anot status` report, including Do Not Disturb when `respect_dnd` is on.
+pub fn status(config: &Config, now: u64) -> Result<String, Error> {
+    let status = mute_status(config, now)?;
+    let state = status.muted.then_some(MuteState {
+        since: now,
+        until: status.until,
+    });
+    Ok(status_line(state, status.dnd, status.deferred, now))
+}
+
+fn status_line(state: Option<MuteState>, dnd: bool, deferred: usize, now: u64) -> String {
+    let mut muted = match state {
+        None => "Notifications are not muted.".to_string(),
+        Some(MuteState { until: None, .. }) => {
+            "Notifications are muted until `anot unmute`.".to_string()
+        }
+        Some(MuteState {
+            until: Some(until), ..
+        }) => format!(
+            "Notifications are muted for another {}.",
+            format_duration(until.saturating_sub(now))
+        ),
+    };
+    if dnd {
+        muted.push_str("\nDo Not Disturb is on, so notifications are held back.");
+    }
+
+    match deferred {
+        0 => muted,
+        1 => format!("{muted}\n1 notification is pending; `anot flush` sends it now."),
+        n => format!("{muted}\n{n} notifications are pending; `anot flush` sends them now."),
+    }
+}
+
+/// Checked by the processors before sending anything: returns `true` when `input` was held
+/// back by an active mute, `quiet_hours` or Do Not Disturb (dropped, or spooled with
+/// `mute_mode: defer`). Otherwise notifications deferred earlier are sent first, and an
+/// expired mute is cleared. A dry run only reports why `event` would be held back.
+pub fn intercept(config: &Config, agent: &str, event: &str, input: &str) -> bool {
+    intercept_with(config, agent, event, input, unix_now(), dnd::is_active)
+}
+
+fn intercept_with(
+    config: &Config,
+    agent: &str,
+    event: &str,
+    input: &str,
+    now: u64,
+    dnd_active: impl FnOnce() -> bool,
+) -> bool {
+    if config.replay || config.flushing {
+        return false;
+    }
+
+    let reason = if is_muted(config, now) {
+        Some("muted")
+    } else if config
+        .quiet_hours
+        .is_some_and(|quiet| quiet.contains(local_minute_of_day(now)))
+    {
+        Some("quiet hours")
+    } else if config.respect_dnd && dnd_active() {
+        Some("Do Not Disturb is on")
+    } else {
+        None
+    };
+
+    if config.dry_run {
+        if let Some(reason) = reason {
+            dry_run::report_suppressed(config, agent, event, Decision::Held, reason);
+        }
+        return reason.is_some();
+    }
+
+    match reason {
+        Some(reason) => {
+            hold_back(config, agent, event, input, reason, now);
+            true
+        }
+        None => {
+            if let Err(e) = flush_deferred(config) {
+                warn!(error = %format_args!("{e:#}"), "failed to send deferred notifications");
+            }
+            false
+        }
+    }
+}
+
+/// Whether a mute is in effect. An expired mute is removed; read errors fail open.
+fn is_muted(config: &Config, now: u64) -> bool {
+    let path = mute_path(config);
+    match read_state(&path) {
+        Ok(Some(state)) if state.is_active(now) => true,
+        Ok(Some(_)) => {
+            debug!("mute expired");
+            if let Err(e) = fs::remove_file(&path)
+                && e.kind() != ErrorKind::NotFound
+            {
+                warn!(path = %path.display(), error = %e, "failed to clear expired mute");
+            }
+            false
+        }
+        Ok(None) => false,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to read mute state");
+            false
+        }
+    }
+}
+
+fn hold_back(config: &Config, agent: &str, event: &str, input: &str, reason: &str, now: u64) {
+    events::record(
+        config,
+        EventRecord::suppressed(agent, event, Decision::Held, reason),
+    );
+    match config.mute_mode {
+        MuteMode::Drop => info!(agent, reason, "dropping notification"),
+        MuteMode::Defer => {
+            let entry = SpooledNotification {
+                spooled_at: now,
+                agent: agent.to_string(),
+                event: event.to_string(),
+                reason: Some(reason.to_string()),
+                input: privacy::redact_payload(config, input),
+                remote: config.remote,
+            };
+            match spool::append(&spool::spool_path(config), &entry) {
+                Ok(()) => info!(agent, reason, "deferring notification"),
+                Err(e) => warn!(agent, error = %e, "failed to defer notification"),
+            }
+        }
+    }
+}
+
+fn flush_deferred(config: &Config) -> Result<usize, Error> {
+    Ok(spool::flush(config, false)?.flushed)
+}
+
+fn read_state(path: &Path) -> Result<Option<MuteState>, Error> {
+    read_json_or_default(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{configuration::Backend, test_support};
+
+    fn temp_config(test_name: &str, mute_mode: MuteMode) -> Config {
+        Config {
+            backends: vec![Backend::Statusfile],
+            mute_mode,
+            ..test_support::temp_config("mute", test_name)
+        }
+    }
+
+    const STOP: &str =
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+    #[test]
+    fn mutes_expire() {
+        let config = temp_config("expiry", MuteMode::Drop);
+        let now = unix_now();
+        assert_eq!(active_mute(&config, now).unwrap(), None);
+
+        let state = mute(&config, Some(60)).unwrap();
+        assert_eq!(active_mute(&config, now + 30).unwrap(), Some(state));
+        assert_eq!(active_mute(&config, state.since + 60).unwrap(), None);
+
+        let state = mute(&config, None).unwrap();
+        assert_eq!(active_mute(&config, now + 1_000_000).unwrap(), Some(state));
+    }
+
+    #[test]
+    fn muted_notifications_are_dropped_by_default() {
+        let config = temp_config("drop", MuteMode::Drop);
+        mute(&config, None).unwrap();
+
+        assert!(intercept(&config, "claude", "Stop", STOP));
+        assert_eq!(deferred_count(&config).unwrap(), 0);
+        assert!(
+            !intercept(
+                &Config {
+                    replay: true,
+                    ..config.clone()
+                },
+                "claude",
+                "Stop",
+                STOP
+            ),
+            "replays aren't muted"
+        );
+    }
+
+    #[test]
+    fn deferred_notifications_are_sent_on_unmute() {
+        let config = temp_config("defer", MuteMode::Defer);
+        let status = config.data_dir().join("status");
+        mute(&config, Some(3600)).unwrap();
+
+        assert!(intercept(&config, "claude", "Stop", STOP));
+        assert!(intercept(&config, "claude", "Stop", STOP));
+        assert_eq!(deferred_count(&config).unwrap(), 2);
+        assert!(!status.exists());
+
+        assert_eq!(unmute(&config).unwrap(), 2);
+        assert_eq!(deferred_count(&config).unwrap(), 0);
+        assert!(
+            fs::read_to_string(&status)
+                .unwrap()
+                .contains("The agent has stopped responding.")
+        );
+        assert!(!intercept(&config, "claude", "Stop", STOP));
+    }
+
+    #[test]
+    fn expired_mutes_flush_on_the_next_notification() {
+        let config = temp_config("expired", MuteMode::Defer);
+        spool::append(
+            &spool::spool_path(&config),
+            &SpooledNotification {
+                spooled_at: 1,
+                agent: "claude".to_string(),
+                event: "Stop".to_string(),
+                reason: Some("muted".to_string()),
+                input: STOP.to_string(),
+                remote: false,
+            },
+        )
+        .unwrap();
+        let expired = MuteState {
+            since: 1,
+            until: Some(2),
+        };
+        write_atomic(
+            &mute_path(&config),
+            serde_json::to_string(&expired).unwrap().as_bytes(),
+        )
+        .unwrap();
+
+        assert!(!intercept(&config, "claude", "Stop", STOP));
+        assert!(!mute_path(&config).exists());
+        assert_eq!(deferred_count(&config).unwrap(), 0);
+    }
+
+    #[test]
+    fn do_not_disturb_holds_back_when_respected() {
+        let config = temp_config("dnd", MuteMode::Defer);
+        let now = unix_now();
+
+        assert!(
+            !intercept_with(&config, "claude", "Stop", STOP, now, || panic!(
+                "DND isn't checked"
+            )),
+            "respect_dnd is off by default"
+        );
+
+        let config = Config {
+            respect_dnd: true,
+            ..config
+        };
+        assert!(intercept_with(&config, "claude", "Stop", STOP, now, || {
+            true
+        }));
+        assert!(intercept_with(
+            &config,
+            "codex",
+            "agent-turn-complete",
+            "{}",
+            now,
+            || true
+        ));
+        assert_eq!(deferred_count(&config).unwrap(), 2);
+
+        let dropping = Config {
+            mute_mode: MuteMode::Drop,
+            ..config.clone()
+        };
+        assert!(intercept_with(
+            &dropping,
+            "claude",
+            "Stop",
+            STOP,
+            now,
+            || true
+        ));
+        assert_eq!(deferred_count(&config).unwrap(), 2);
+    }
+
+    #[test]
+    fn deferred_notifications_flush_once_do_not_disturb_lifts() {
+        let config = Config {
+            respect_dnd: true,
+            ..temp_config("dnd-flush", MuteMode::Defer)
+        };
+        let status = config.data_dir().join("status");
+        let now = unix_now();
+
+        assert!(intercept_with(&config, "claude", "Stop", STOP, now, || {
+            true
+        }));
+        assert!(!status.exists());
+
+        assert!(!intercept_with(
+            &config,
+            "claude",
+            "Stop",
+            STOP,
+            now,
+            || false
+        ));
+        assert_eq!(deferred_count(&config).unwrap(), 0);
+        assert!(
+            fs::read_to_string(&status)
+                .unwrap()
+                .contains("The agent has stopped responding.")
+        );
+    }
+
+    #[test]
+    fn status_lines() {
+        assert_eq!(
+            status_line(None, false, 0, 100),
+            "Notifications are not muted."
+        );
+        assert_eq!(
+            status_line(None, true, 0, 100),
+            "Notifications are not muted.\n\
+             Do Not Disturb is on, so notifications are held back."
+        );
+        assert_eq!(
+            status_line(
+                Some(MuteState {
+                    since: 0,
+                    until: None
+                }),
+                false,
+                1,
+                100
+            ),
+            "Notifications are muted until `anot unmute`.\n\
+             1 notification is pending; `anot flush` sends it now."
+        );
+        assert_eq!(
+            status_line(
+                Some(MuteState {
+                    since: 0,
+                    until: Some(100 + 45 * 60)
+                }),
+                false,
+                3,
+                100
+            ),
+            "Notifications are muted for another 45m 0s.\n\
+             3 notifications are pending; `anot flush` sends them now."
+        );
+    }
+}
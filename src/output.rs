@@ -0,0 +1,165 @@
+//! Results and errors of the CLI utility commands in the shape `--output json` prints, so
+//! scripts don't have to parse the human-readable text. Field names are stable.
+
+use std::{io, path::PathBuf};
+
+use anyhow::Error;
+use serde::Serialize;
+
+/// `anot reset`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ResetReport {
+    pub path: PathBuf,
+    /// The section or setting that was reset; `None` for the whole config.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scope: Option<String>,
+    /// Where the old config was copied to.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub backup: Option<PathBuf>,
+}
+
+/// `anot replay`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ReplayReport {
+    pub agent: String,
+}
+
+/// `anot unmute`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UnmuteReport {
+    /// Deferred notifications sent now that the mute is over.
+    pub sent: usize,
+}
+
+/// `anot profile list`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProfileList {
+    pub profiles: Vec<String>,
+    /// The profile `--profile`, `ANOT_PROFILE` or `active_profile` selects.
+    pub selected: Option<String>,
+}
+
+/// `anot profile use`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ProfileUse {
+    pub active_profile: String,
+}
+
+/// `anot config set`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConfigSet {
+    pub path: PathBuf,
+    pub key: String,
+}
+
+/// `anot config validate`: the files that were checked.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConfigValidation {
+    pub files: Vec<ValidatedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ValidatedFile {
+    pub path: PathBuf,
+    /// Whether it has comments or trailing commas, which strict JSON doesn't allow.
+    pub lenient: bool,
+}
+
+/// `anot config schema --out`
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConfigSchema {
+    pub path: PathBuf,
+    /// The config whose `$schema` now points at it, with `--with-schema`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub linked_config: Option<PathBuf>,
+}
+
+/// `anot config convert`: each file written in the new format, and where the old one was
+/// moved.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConfigConversion {
+    pub files: Vec<ConvertedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ConvertedFile {
+    pub path: PathBuf,
+    pub backup: PathBuf,
+}
+
+/// A failed command, printed to stderr as `{"error": {"code": ..., "message": ...}}`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ErrorReport {
+    pub error: ErrorDetail,
+}
+
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct ErrorDetail {
+    /// `not_found`, `permission_denied`, `io`, `invalid_json`, `invalid_toml` or `error`.
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl ErrorReport {
+    pub fn new(error: &Error) -> Self {
+        ErrorReport {
+            error: ErrorDetail {
+                code: error_code(error),
+                message: format!("{error:#}"),
+            },
+        }
+    }
+}
+
+/// The code of the first cause in `error`'s chain that has one.
+fn error_code(error: &Error) -> &'static str {
+    error
+        .chain()
+        .find_map(|cause| {
+            if let Some(e) = cause.downcast_ref::<io::Error>() {
+                Some(match e.kind() {
+                    io::ErrorKind::NotFound => "not_found",
+                    io::ErrorKind::PermissionDenied => "permission_denied",
+                    _ => "io",
+                })
+            } else if cause.is::<serde_json::Error>() {
+                Some("invalid_json")
+            } else if cause.is::<toml::de::Error>() {
+                Some("invalid_toml")
+            } else {
+                None
+            }
+        })
+        .unwrap_or("error")
+}
+
+/// Prints `value` as pretty JSON on stdout.
+pub fn print_json<T: Serialize>(value: &T) -> Result<(), Error> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::anyhow;
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn error_codes_come_from_the_cause() {
+        let missing = Error::from(io::Error::from(io::ErrorKind::NotFound)).context("reading");
+        assert_eq!(ErrorReport::new(&missing).error.code, "not_found");
+
+        let json_error = serde_json::from_str::<u8>("{").unwrap_err();
+        assert_eq!(
+            ErrorReport::new(&Error::from(json_error)).error.code,
+            "invalid_json"
+        );
+
+        assert_eq!(
+            serde_json::to_value(ErrorReport::new(&anyhow!("Unknown profile `x`"))).unwrap(),
+            json!({"error": {"code": "error", "message": "Unknown profile `x`"}})
+        );
+    }
+}
@@ -0,0 +1,337 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{Config, Pretend},
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::unix_now,
+};
+
+const BUNDLES_FILE_NAME: &str = "bundles.json";
+
+/// Bundle id used when notifications don't pretend to be an agent app.
+pub const TERMINAL_BUNDLE_ID: &str = "com.apple.Terminal";
+
+/// How long an `auto` probe result is trusted before the app is looked up again.
+const PROBE_TTL_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct Probe {
+    bundle_id: Option<String>,
+    checked: u64,
+}
+
+/// The last bundle lookup for each app name; `None` means the app wasn't installed.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ProbeCache {
+    #[serde(default)]
+    apps: BTreeMap<String, Probe>,
+}
+
+pub fn bundles_path(config: &Config) -> PathBuf {
+    config.data_dir().join(BUNDLES_FILE_NAME)
+}
+
+/// The bundle id to send `app`'s notifications as, or `None` for the Terminal identity.
+/// `probe` looks the app up; in `auto` mode its result is cached in `bundles.json`.
+pub fn bundle_id(
+    config: &Config,
+    app: &str,
+    pretend: Pretend,
+    probe: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    resolve(&bundles_path(config), app, pretend, unix_now(), probe)
+}
+
+/// The application a macOS notification is sent as.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Identity {
+    /// The agent's app (or the configured `bundle_id`); the app icon stands for the agent
+    App(String),
+    /// The terminal (or the configured `fallback_bundle_id`); the agent icon is attached
+    Fallback(String),
+}
+
+/// Where a section's notifications may come from, in order of preference.
+pub struct IdentityOptions<'a> {
+    /// App name looked up by the pretend probe, e.g. `Claude`
+    pub app: &'a str,
+    pub pretend: Pretend,
+    pub bundle_id: Option<&'a str>,
+    pub fallback_bundle_id: Option<&'a str>,
+}
+
+/// Sets the application notifications are sent as. macOS only allows that once per process,
+/// so the first choice is kept for every later notification.
+///
+/// `set_application` applies a bundle id and reports whether it was accepted; ids it rejects
+/// are logged and the next candidate is tried.
+pub fn set_identity(
+    config: &Config,
+    options: &IdentityOptions,
+    probe: impl FnOnce() -> Option<String>,
+    set_application: impl FnMut(&str) -> bool,
+) -> Identity {
+    static IDENTITY: OnceLock<Identity> = OnceLock::new();
+
+    IDENTITY
+        .get_or_init(|| {
+            choose_identity(options, set_application, || {
+                bundle_id(config, options.app, options.pretend, probe)
+            })
+        })
+        .clone()
+}
+
+/// Tries the explicit `bundle_id`, then the pretend lookup, then the fallback.
+fn choose_identity(
+    options: &IdentityOptions,
+    mut set_application: impl FnMut(&str) -> bool,
+    pretend_bundle_id: impl FnOnce() -> Option<String>,
+) -> Identity {
+    if let Some(bundle_id) = options.bundle_id {
+        if set_application(bundle_id) {
+            return Identity::App(bundle_id.to_string());
+        }
+        warn!(
+            bundle_id,
+            "couldn't use the configured bundle_id; falling back"
+        );
+    }
+
+    if let Some(bundle_id) = pretend_bundle_id() {
+        if set_application(&bundle_id) {
+            return Identity::App(bundle_id);
+        }
+        warn!(bundle_id = %bundle_id, app = options.app, "couldn't use the app's bundle id");
+    }
+
+    if let Some(fallback) = options.fallback_bundle_id
+        && fallback != TERMINAL_BUNDLE_ID
+    {
+        if set_application(fallback) {
+            return Identity::Fallback(fallback.to_string());
+        }
+        warn!(
+            bundle_id = fallback,
+            "couldn't use the configured fallback_bundle_id; using Terminal"
+        );
+    }
+
+    set_application(TERMINAL_BUNDLE_ID);
+    Identity::Fallback(TERMINAL_BUNDLE_ID.to_string())
+}
+
+fn resolve(
+    path: &Path,
+    app: &str,
+    pretend: Pretend,
+    now: u64,
+    probe: impl FnOnce() -> Option<String>,
+) -> Option<String> {
+    match pretend {
+        Pretend::Never => None,
+        Pretend::Always => {
+            let bundle_id = probe();
+            if bundle_id.is_none() {
+                warn!(
+                    app,
+                    "pretend is `always` but the app isn't installed; using Terminal"
+                );
+            }
+            bundle_id
+        }
+        Pretend::Auto => match cached_probe(path, app, now) {
+            Ok(Some(bundle_id)) => bundle_id,
+            result => {
+                if let Err(e) = result {
+                    warn!(path = %path.display(), error = %e, "failed to read bundle probe cache");
+                }
+                let bundle_id = probe();
+                debug!(app, found = bundle_id.is_some(), "probed app bundle");
+                if let Err(e) = store_probe(path, app, bundle_id.clone(), now) {
+                    warn!(path = %path.display(), error = %e, "failed to cache bundle probe");
+                }
+                bundle_id
+            }
+        },
+    }
+}
+
+fn read_cache(path: &Path) -> Result<ProbeCache, Error> {
+    read_json_or_default(path)
+}
+
+/// The cached probe for `app`, unless it is missing or older than [`PROBE_TTL_SECS`].
+fn cached_probe(path: &Path, app: &str, now: u64) -> Result<Option<Option<String>>, Error> {
+    Ok(read_cache(path)?
+        .apps
+        .remove(app)
+        .filter(|probe| now.saturating_sub(probe.checked) < PROBE_TTL_SECS)
+        .map(|probe| probe.bundle_id))
+}
+
+fn store_probe(path: &Path, app: &str, bundle_id: Option<String>, now: u64) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut cache = read_cache(path)?;
+        cache.apps.insert(
+            app.to_string(),
+            Probe {
+                bundle_id,
+                checked: now,
+            },
+        );
+        write_atomic(path, serde_json::to_string(&cache)?.as_bytes())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::test_support;
+
+    fn temp_bundles_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("bundles", test_name).join(BUNDLES_FILE_NAME)
+    }
+
+    const CLAUDE: &str = "com.anthropic.claudefordesktop";
+
+    #[test]
+    fn decision_matrix() {
+        let path = temp_bundles_path("matrix");
+        let installed = || Some(CLAUDE.to_string());
+        let missing = || None;
+
+        assert_eq!(
+            resolve(&path, "Claude", Pretend::Never, 1000, installed),
+            None
+        );
+        assert!(!path.exists(), "never mode doesn't probe");
+
+        assert_eq!(
+            resolve(&path, "Claude", Pretend::Always, 1000, installed).as_deref(),
+            Some(CLAUDE)
+        );
+        assert_eq!(
+            resolve(&path, "Claude", Pretend::Always, 1000, missing),
+            None
+        );
+
+        assert_eq!(
+            resolve(&path, "Claude", Pretend::Auto, 1000, installed).as_deref(),
+            Some(CLAUDE)
+        );
+        assert_eq!(
+            resolve(&path, "ChatGPT", Pretend::Auto, 1000, missing),
+            None
+        );
+    }
+
+    fn options<'a>(bundle_id: Option<&'a str>, fallback: Option<&'a str>) -> IdentityOptions<'a> {
+        IdentityOptions {
+            app: "Claude",
+            pretend: Pretend::Auto,
+            bundle_id,
+            fallback_bundle_id: fallback,
+        }
+    }
+
+    /// Accepts every bundle id except `unknown.*` ones, recording what was tried.
+    fn choose(options: &IdentityOptions, pretend: Option<&str>) -> (Identity, Vec<String>) {
+        let mut tried = Vec::new();
+        let identity = choose_identity(
+            options,
+            |id| {
+                tried.push(id.to_string());
+                !id.starts_with("unknown.")
+            },
+            || pretend.map(String::from),
+        );
+        (identity, tried)
+    }
+
+    #[test]
+    fn identity_resolution_order() {
+        let iterm = "com.googlecode.iterm2";
+
+        assert_eq!(
+            choose(&options(Some("com.example.wrapper"), None), Some(CLAUDE)),
+            (
+                Identity::App("com.example.wrapper".to_string()),
+                vec!["com.example.wrapper".to_string()]
+            )
+        );
+        assert_eq!(
+            choose(&options(None, Some(iterm)), Some(CLAUDE)).0,
+            Identity::App(CLAUDE.to_string())
+        );
+        assert_eq!(
+            choose(&options(None, Some(iterm)), None).0,
+            Identity::Fallback(iterm.to_string())
+        );
+        assert_eq!(
+            choose(&options(None, None), None).0,
+            Identity::Fallback(TERMINAL_BUNDLE_ID.to_string())
+        );
+    }
+
+    #[test]
+    fn unknown_bundle_ids_fall_through_the_chain() {
+        let (identity, tried) = choose(
+            &options(Some("unknown.wrapper"), Some("unknown.terminal")),
+            Some("unknown.claude"),
+        );
+
+        assert_eq!(identity, Identity::Fallback(TERMINAL_BUNDLE_ID.to_string()));
+        assert_eq!(
+            tried,
+            [
+                "unknown.wrapper",
+                "unknown.claude",
+                "unknown.terminal",
+                TERMINAL_BUNDLE_ID
+            ]
+        );
+    }
+
+    #[test]
+    fn auto_probes_once_per_ttl() {
+        let path = temp_bundles_path("ttl");
+        let probes = Cell::new(0);
+        let probe = || {
+            probes.set(probes.get() + 1);
+            None
+        };
+
+        assert_eq!(resolve(&path, "Claude", Pretend::Auto, 1000, probe), None);
+        assert_eq!(resolve(&path, "Claude", Pretend::Auto, 1500, probe), None);
+        assert_eq!(probes.get(), 1, "a missing app is cached too");
+
+        let installed = || Some(CLAUDE.to_string());
+        assert_eq!(
+            resolve(
+                &path,
+                "Claude",
+                Pretend::Auto,
+                1000 + PROBE_TTL_SECS,
+                installed
+            )
+            .as_deref(),
+            Some(CLAUDE)
+        );
+        assert_eq!(
+            resolve(&path, "Claude", Pretend::Auto, 1001 + PROBE_TTL_SECS, probe).as_deref(),
+            Some(CLAUDE)
+        );
+        assert_eq!(probes.get(), 1);
+    }
+}
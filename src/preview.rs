@@ -0,0 +1,223 @@
+//! Shows what `anot init` is about to change in an agent's config file, as a unified diff, and
+//! writes it only once the user agrees (never with `--dry-run`).
+
+use std::path::Path;
+
+use anyhow::Error;
+use inquire::Confirm;
+
+use crate::{
+    console::{self, Mark},
+    error::{AnotError, prompt_error},
+    storage::write_atomic,
+};
+
+/// Lines of unchanged context around each change.
+const CONTEXT_LINES: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The line-by-line edit turning `old` into `new`, from their longest common subsequence.
+fn line_edits<'a>(old: &'a str, new: &'a str) -> Vec<Line<'a>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // common[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut edits = Vec::with_capacity(old.len().max(new.len()));
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            edits.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            edits.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            edits.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    edits
+}
+
+/// A unified diff from `old` to `new` with `label` in the headers, or an empty string when they
+/// have the same lines. A file that doesn't exist yet is diffed as `old = ""`.
+pub fn unified_diff(old: &str, new: &str, label: &str) -> String {
+    let edits = line_edits(old, new);
+    let changed: Vec<usize> = edits
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Same(_)))
+        .map(|(index, _)| index)
+        .collect();
+    if changed.is_empty() {
+        return String::new();
+    }
+
+    // Group changes whose context overlaps into one hunk.
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(edits.len());
+        match hunks.last_mut() {
+            Some((_, last_end)) if start <= *last_end => *last_end = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+
+    let mut diff = format!("--- a/{label}\n+++ b/{label}\n");
+    let (mut old_line, mut new_line) = (1, 1);
+    let mut position = 0;
+    for (start, end) in hunks {
+        for line in &edits[position..start] {
+            match line {
+                Line::Same(_) => (old_line, new_line) = (old_line + 1, new_line + 1),
+                Line::Removed(_) => old_line += 1,
+                Line::Added(_) => new_line += 1,
+            }
+        }
+
+        let hunk = &edits[start..end];
+        let old_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Added(_)))
+            .count();
+        let new_count = hunk
+            .iter()
+            .filter(|line| !matches!(line, Line::Removed(_)))
+            .count();
+        diff.push_str(&format!(
+            "@@ -{} +{} @@\n",
+            hunk_range(old_line, old_count),
+            hunk_range(new_line, new_count)
+        ));
+        for line in hunk {
+            let (prefix, text) = match line {
+                Line::Same(text) => (' ', text),
+                Line::Removed(text) => ('-', text),
+                Line::Added(text) => ('+', text),
+            };
+            diff.push(prefix);
+            diff.push_str(text);
+            diff.push('\n');
+        }
+
+        old_line += old_count;
+        new_line += new_count;
+        position = end;
+    }
+    diff
+}
+
+/// `start,count` as in `diff -u`, where an empty range starts at the line before it.
+fn hunk_range(start: usize, count: usize) -> String {
+    match count {
+        0 => format!("{},0", start - 1),
+        1 => start.to_string(),
+        _ => format!("{start},{count}"),
+    }
+}
+
+/// Shows the diff from `old` to `new` for `path` and writes `new` once the user confirms.
+/// With `dry_run` only the diff is shown. Returns whether the file was written.
+pub fn write_with_preview(path: &Path, old: &str, new: &str, dry_run: bool) -> Result<bool, Error> {
+    let diff = unified_diff(old, new, &path.to_string_lossy());
+    if diff.is_empty() {
+        console::say(
+            Mark::Info,
+            format!("{} is already up to date. No changes made.", path.display()),
+        );
+        return Ok(false);
+    }
+
+    console::say(Mark::Changes, format!("Changes to {}:", path.display()));
+    print!("{diff}");
+    println!();
+
+    if dry_run {
+        console::say(Mark::DryRun, "Dry run: nothing was written.");
+        return Ok(false);
+    }
+
+    let apply = Confirm::new("Apply these changes?")
+        .with_default(true)
+        .prompt()
+        .map_err(|err| prompt_error(err, "Failed to get confirmation"))?;
+    if !apply {
+        console::say(Mark::Info, "No changes made.");
+        return Ok(false);
+    }
+
+    write_atomic(path, new.as_bytes()).map_err(|source| AnotError::AgentConfigWrite {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_path;
+
+    #[test]
+    fn identical_content_has_no_diff() {
+        assert_eq!(unified_diff("a\nb\n", "a\nb\n", "x"), "");
+        assert_eq!(unified_diff("", "", "x"), "");
+    }
+
+    #[test]
+    fn new_files_are_diffed_against_empty() {
+        assert_eq!(
+            unified_diff("", "{\n  \"hooks\": {}\n}\n", "settings.json"),
+            "--- a/settings.json\n+++ b/settings.json\n@@ -0,0 +1,3 @@\n+{\n+  \"hooks\": {}\n+}\n"
+        );
+    }
+
+    #[test]
+    fn distant_changes_get_separate_hunks() {
+        let old: String = (1..=20).map(|i| format!("line {i}\n")).collect();
+        let new = old
+            .replace("line 2\n", "line two\n")
+            .replace("line 18\n", "");
+
+        assert_eq!(
+            unified_diff(&old, &new, "f"),
+            "--- a/f\n+++ b/f\n\
+             @@ -1,5 +1,5 @@\n line 1\n-line 2\n+line two\n line 3\n line 4\n line 5\n\
+             @@ -15,6 +15,5 @@\n line 15\n line 16\n line 17\n-line 18\n line 19\n line 20\n"
+        );
+    }
+
+    #[test]
+    fn dry_run_leaves_the_file_untouched() {
+        let dir = temp_path("preview", "dry-run");
+        let existing = dir.join("settings.json");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(&existing, "{}\n").unwrap();
+
+        assert!(!write_with_preview(&existing, "{}\n", "{\"hooks\": {}}\n", true).unwrap());
+        assert_eq!(std::fs::read_to_string(&existing).unwrap(), "{}\n");
+
+        let missing = dir.join("config.toml");
+        assert!(!write_with_preview(&missing, "", "notify = []\n", true).unwrap());
+        assert!(!missing.exists());
+    }
+}
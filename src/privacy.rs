@@ -0,0 +1,223 @@
+use regex::Regex;
+use serde_json::Value;
+use tracing::warn;
+
+use crate::{
+    configuration::{Config, ContentLevel},
+    i18n::t,
+};
+
+/// Replaces text matched by `privacy.redact_patterns`.
+pub const MASK: &str = "•••";
+
+/// `text` with every match of `privacy.redact_patterns` replaced by [`MASK`]. Apply it before
+/// text is logged, shown or recorded in the history.
+pub fn redact(config: &Config, text: &str) -> String {
+    let mut redacted = text.to_string();
+
+    for pattern in &config.privacy.redact_patterns {
+        match Regex::new(pattern) {
+            Ok(re) => redacted = re.replace_all(&redacted, MASK).into_owned(),
+            Err(e) => warn!(pattern = %pattern, error = %e, "invalid redaction pattern"),
+        }
+    }
+
+    redacted
+}
+
+/// `input`, an agent's raw payload, as it may be kept on disk or relayed: prompts hidden by
+/// `redact_prompts` become one `•` per character, so [`prompt_placeholder`] still counts them
+/// right, agent messages hidden by `redact_messages` are dropped, and every string is masked
+/// with [`redact`]. Input that isn't JSON is only masked.
+pub fn redact_payload(config: &Config, input: &str) -> String {
+    redact_payload_to(config, input, ContentLevel::Full)
+}
+
+/// `input` redacted as [`redact_payload`] does, keeping no more than `level` allows.
+pub fn redact_payload_to(config: &Config, input: &str, level: ContentLevel) -> String {
+    let privacy = &config.privacy;
+    let hidden = Hidden {
+        prompts: privacy.redact_prompts || level != ContentLevel::Full,
+        messages: privacy.redact_messages || level != ContentLevel::Full,
+        content: level == ContentLevel::TitleOnly,
+    };
+    if !hidden.prompts && !hidden.messages && privacy.redact_patterns.is_empty() {
+        return input.to_string();
+    }
+    let Ok(mut payload) = serde_json::from_str::<Value>(input) else {
+        return redact(config, input);
+    };
+    redact_value(config, hidden, &mut payload);
+    payload.to_string()
+}
+
+/// What [`redact_payload_to`] takes out of a payload besides `redact_patterns` matches.
+#[derive(Clone, Copy)]
+struct Hidden {
+    prompts: bool,
+    messages: bool,
+    /// Prompts, messages and tool input and output, which are removed altogether
+    content: bool,
+}
+
+/// Keys of the Claude and Codex payloads holding what the user or the agent wrote.
+const CONTENT_KEYS: [&str; 6] = [
+    "prompt",
+    "input-messages",
+    "last-assistant-message",
+    "tool_input",
+    "tool_response",
+    "custom_instructions",
+];
+
+fn redact_value(config: &Config, hidden: Hidden, value: &mut Value) {
+    match value {
+        Value::String(text) => *text = redact(config, text),
+        Value::Array(items) => items
+            .iter_mut()
+            .for_each(|item| redact_value(config, hidden, item)),
+        Value::Object(map) => {
+            if hidden.content {
+                map.retain(|key, _| !CONTENT_KEYS.contains(&key.as_str()));
+            }
+            if hidden.messages {
+                map.remove("last-assistant-message");
+            }
+            for (key, value) in map.iter_mut() {
+                match (key.as_str(), value) {
+                    ("prompt" | "input-messages", value) if hidden.prompts => hide_prompts(value),
+                    (_, value) => redact_value(config, hidden, value),
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+fn hide_prompts(value: &mut Value) {
+    match value {
+        Value::String(prompt) => *prompt = "•".repeat(prompt.chars().count()),
+        Value::Array(prompts) => prompts.iter_mut().for_each(hide_prompts),
+        _ => {}
+    }
+}
+
+/// The body shown for a user prompt when `privacy.redact_prompts` hides its content.
+pub fn prompt_placeholder(config: &Config, prompt: &str) -> String {
+    t(
+        config,
+        "claude.prompt_redacted",
+        &[("count", &prompt.chars().count())],
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(patterns: &[&str]) -> Config {
+        let mut config = Config::default();
+        config.privacy.redact_patterns = patterns.iter().map(|p| p.to_string()).collect();
+        config
+    }
+
+    #[test]
+    fn masks_every_match() {
+        let config = config(&[r"sk-[A-Za-z0-9]{8,}", r"(?i)password=\S+"]);
+
+        assert_eq!(
+            redact(
+                &config,
+                "use sk-abcdef123456 and PASSWORD=hunter2 or sk-zyxwvu987654"
+            ),
+            "use ••• and ••• or •••"
+        );
+        assert_eq!(redact(&config, "nothing secret"), "nothing secret");
+    }
+
+    #[test]
+    fn skips_invalid_patterns() {
+        let config = config(&["(unclosed", "secret"]);
+
+        assert_eq!(redact(&config, "a secret"), "a •••");
+    }
+
+    #[test]
+    fn payloads_keep_their_shape() {
+        let mut config = config(&[r"sk-[A-Za-z0-9]{8,}"]);
+        let claude = r#"{"hook_event_name":"UserPromptSubmit","prompt":"use sk-abcdef123456","tool_input":{"command":"echo sk-abcdef123456"}}"#;
+        let codex = r#"{"type":"agent-turn-complete","input-messages":["hi"],"last-assistant-message":"sk-abcdef123456"}"#;
+
+        let redacted: Value = serde_json::from_str(&redact_payload(&config, claude)).unwrap();
+        assert_eq!(redacted["prompt"], "use •••");
+        assert_eq!(redacted["tool_input"]["command"], "echo •••");
+        assert_eq!(
+            redact_payload(&config, "not json sk-abcdef123456"),
+            "not json •••"
+        );
+
+        config.privacy.redact_prompts = true;
+        config.privacy.redact_messages = true;
+        let redacted: Value = serde_json::from_str(&redact_payload(&config, claude)).unwrap();
+        assert_eq!(
+            prompt_placeholder(&config, redacted["prompt"].as_str().unwrap()),
+            "User submitted a prompt (19 chars)"
+        );
+        let redacted: Value = serde_json::from_str(&redact_payload(&config, codex)).unwrap();
+        assert_eq!(
+            redacted,
+            serde_json::json!({
+                "type": "agent-turn-complete",
+                "input-messages": ["••"]
+            })
+        );
+
+        let untouched = Config::default();
+        assert_eq!(redact_payload(&untouched, codex), codex);
+    }
+
+    #[test]
+    fn content_levels_keep_less_and_less() {
+        let config = config(&[]);
+        let claude = r#"{"hook_event_name":"PostToolUse","tool_name":"Bash","tool_input":{"command":"ls"},"prompt":"hi"}"#;
+        let codex = r#"{"type":"agent-turn-complete","cwd":"/src/api","input-messages":["hi"],"last-assistant-message":"Done"}"#;
+
+        for (level, claude_expected, codex_expected) in [
+            (
+                ContentLevel::Full,
+                serde_json::from_str(claude).unwrap(),
+                serde_json::from_str(codex).unwrap(),
+            ),
+            (
+                ContentLevel::Redacted,
+                serde_json::json!({
+                    "hook_event_name": "PostToolUse", "tool_name": "Bash",
+                    "tool_input": {"command": "ls"}, "prompt": "••"
+                }),
+                serde_json::json!({
+                    "type": "agent-turn-complete", "cwd": "/src/api",
+                    "input-messages": ["••"]
+                }),
+            ),
+            (
+                ContentLevel::TitleOnly,
+                serde_json::json!({"hook_event_name": "PostToolUse", "tool_name": "Bash"}),
+                serde_json::json!({"type": "agent-turn-complete", "cwd": "/src/api"}),
+            ),
+        ] {
+            let relayed = |input| {
+                serde_json::from_str::<Value>(&redact_payload_to(&config, input, level)).unwrap()
+            };
+            assert_eq!(relayed(claude), claude_expected, "{level:?}");
+            assert_eq!(relayed(codex), codex_expected, "{level:?}");
+        }
+    }
+
+    #[test]
+    fn prompt_placeholder_counts_chars() {
+        assert_eq!(
+            prompt_placeholder(&Config::default(), "héllo"),
+            "User submitted a prompt (5 chars)"
+        );
+    }
+}
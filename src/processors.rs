@@ -1,3 +1,6 @@
 pub mod claude;
 pub mod codex;
+pub mod common;
+#[cfg(feature = "desktop")]
+pub mod icon;
 pub mod opencode;
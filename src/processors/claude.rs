@@ -1,4 +1,16 @@
+pub mod batching;
+pub mod formatters;
+pub mod guard;
+#[cfg(feature = "desktop")]
 pub mod icon;
 pub mod init;
 pub mod input_and_output;
+pub mod pairing;
+pub mod paths;
+pub mod reminders;
+pub mod report_delivery;
+pub mod sampling;
 pub mod structs;
+pub mod transcript;
+
+pub use input_and_output::{process_claude_input, send_notification};
@@ -0,0 +1,274 @@
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+use crate::{
+    configuration::Config,
+    i18n::t,
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::format_duration,
+};
+
+/// A tool notification held back while a batch is collecting.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpooledEvent {
+    pub timestamp: u64,
+    pub event: String,
+    pub tool: String,
+    pub summary: String,
+    pub body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Spool {
+    #[serde(default)]
+    events: Vec<SpooledEvent>,
+}
+
+/// A notification produced by flushing the spool.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlushedMessage {
+    pub event: String,
+    pub summary: String,
+    pub body: String,
+}
+
+/// Spool file for one Claude session, so parallel sessions batch independently.
+pub fn spool_path(config: &Config, session_id: &str) -> PathBuf {
+    let session: String = session_id
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    config
+        .data_dir()
+        .join("spool")
+        .join(format!("claude-{session}.json"))
+}
+
+fn read_spool(path: &Path) -> Result<Spool, Error> {
+    read_json_or_default(path)
+}
+
+pub fn append(path: &Path, event: SpooledEvent) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut spool = read_spool(path)?;
+        spool.events.push(event);
+        write_atomic(path, serde_json::to_string(&spool)?.as_bytes())
+    })
+}
+
+/// Empties the spool and returns the notifications to show for it, when `force` is set or the
+/// batch window has elapsed since the first spooled event. Otherwise returns nothing.
+pub fn take_pending(
+    path: &Path,
+    now: u64,
+    config: &Config,
+    force: bool,
+) -> Result<Vec<FlushedMessage>, Error> {
+    let events = with_lock(path, || {
+        let spool = read_spool(path)?;
+        let Some(first) = spool.events.first() else {
+            return Ok(Vec::new());
+        };
+
+        let settings = &config.claude.batching;
+        if !force && now.saturating_sub(first.timestamp) < settings.window_secs {
+            return Ok(Vec::new());
+        }
+
+        fs::remove_file(path)?;
+        Ok(spool.events)
+    })?;
+    if events.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    debug!(count = events.len(), force, "flushing batch spool");
+    Ok(aggregate(events, config))
+}
+
+fn aggregate(events: Vec<SpooledEvent>, config: &Config) -> Vec<FlushedMessage> {
+    if events.len() < config.claude.batching.min_batch_size.max(2) {
+        return events
+            .into_iter()
+            .map(|e| FlushedMessage {
+                event: e.event,
+                summary: e.summary,
+                body: e.body,
+            })
+            .collect();
+    }
+
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for event in &events {
+        *counts.entry(event.tool.as_str()).or_default() += 1;
+    }
+    let mut counts: Vec<_> = counts.into_iter().collect();
+    counts.sort_by_key(|(_, count)| Reverse(*count));
+
+    let tools = counts
+        .iter()
+        .map(|(tool, count)| format!("{tool} ×{count}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // Parallel hooks can spool out of order, so the span is taken over every event.
+    let timestamps = events.iter().map(|e| e.timestamp);
+    let span = timestamps.clone().max().unwrap_or_default() - timestamps.min().unwrap_or_default();
+
+    vec![FlushedMessage {
+        event: "ToolBatch".to_string(),
+        summary: t(
+            config,
+            "claude.tool_batch.summary",
+            &[("count", &events.len())],
+        ),
+        body: t(
+            config,
+            "claude.tool_batch",
+            &[
+                ("count", &events.len()),
+                ("tools", &tools),
+                ("duration", &format_duration(span)),
+            ],
+        ),
+    }]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{configuration::Batching, test_support};
+
+    fn temp_spool_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("spool", test_name).join("claude-session.json")
+    }
+
+    fn tool_event(timestamp: u64, tool: &str) -> SpooledEvent {
+        SpooledEvent {
+            timestamp,
+            event: "PostToolUse".to_string(),
+            tool: tool.to_string(),
+            summary: "PostToolUse".to_string(),
+            body: format!("The agent has used {tool}"),
+        }
+    }
+
+    fn settings(window_secs: u64, min_batch_size: usize) -> Config {
+        let mut config = Config::default();
+        config.claude.batching = Batching {
+            window_secs,
+            min_batch_size,
+        };
+        config
+    }
+
+    #[test]
+    fn aggregates_by_tool_after_window_elapses() {
+        let path = temp_spool_path("window");
+        let settings = settings(30, 3);
+        let tools = [
+            "Bash", "Edit", "Bash", "Read", "Edit", "Bash", "Read", "Bash", "Edit", "Read", "Bash",
+            "Edit", "Bash", "Read", "Edit",
+        ];
+
+        for (i, tool) in tools.iter().enumerate() {
+            let now = 1000 + i as u64;
+            assert!(
+                take_pending(&path, now, &settings, false)
+                    .unwrap()
+                    .is_empty()
+            );
+            append(&path, tool_event(now, tool)).unwrap();
+        }
+        // 20s after the first event the 30s window is still open.
+        assert!(
+            take_pending(&path, 1020, &settings, false)
+                .unwrap()
+                .is_empty()
+        );
+
+        let flushed = take_pending(&path, 1030, &settings, false).unwrap();
+        assert_eq!(
+            flushed,
+            [FlushedMessage {
+                event: "ToolBatch".to_string(),
+                summary: "Used 15 tools".to_string(),
+                body: "Claude used 15 tools (Bash ×6, Edit ×5, Read ×4) over 14s".to_string(),
+            }]
+        );
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn non_tool_event_forces_flush() {
+        let path = temp_spool_path("force");
+        let settings = settings(300, 3);
+
+        for (i, tool) in ["Read", "Grep", "Read"].iter().enumerate() {
+            append(&path, tool_event(1000 + i as u64, tool)).unwrap();
+        }
+
+        let flushed = take_pending(&path, 1005, &settings, true).unwrap();
+        assert_eq!(flushed.len(), 1);
+        assert_eq!(
+            flushed[0].body,
+            "Claude used 3 tools (Read ×2, Grep ×1) over 2s"
+        );
+        assert!(
+            take_pending(&path, 1006, &settings, true)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn out_of_order_events_span_from_earliest_to_latest() {
+        let path = temp_spool_path("out-of-order");
+        let settings = settings(300, 3);
+
+        for (timestamp, tool) in [
+            (1004, "Read"),
+            (1000, "Bash"),
+            (1009, "Read"),
+            (1002, "Edit"),
+        ] {
+            append(&path, tool_event(timestamp, tool)).unwrap();
+        }
+
+        let flushed = take_pending(&path, 1010, &settings, true).unwrap();
+        assert_eq!(
+            flushed[0].body,
+            "Claude used 4 tools (Read ×2, Bash ×1, Edit ×1) over 9s"
+        );
+    }
+
+    #[test]
+    fn small_batches_are_sent_individually() {
+        let path = temp_spool_path("small");
+        let settings = settings(10, 3);
+
+        append(&path, tool_event(1000, "Bash")).unwrap();
+        append(&path, tool_event(1001, "Edit")).unwrap();
+
+        let flushed = take_pending(&path, 1011, &settings, false).unwrap();
+        let bodies: Vec<_> = flushed.iter().map(|m| m.body.as_str()).collect();
+        assert_eq!(
+            bodies,
+            ["The agent has used Bash", "The agent has used Edit"]
+        );
+        assert!(flushed.iter().all(|m| m.event == "PostToolUse"));
+    }
+}
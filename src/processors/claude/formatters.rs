@@ -0,0 +1,440 @@
+use std::path::Path;
+
+use serde_json::Value;
+
+/// Bytes of a string looked at when counting its lines. Tools can write or replace megabytes,
+/// and a notification only needs a rough count.
+const MAX_SCANNED_BYTES: usize = 256 * 1024;
+
+/// Lines of a `structuredPatch` looked at, across its hunks.
+const MAX_PATCH_LINES: usize = 20_000;
+
+/// What a file-editing or shell tool call did, from its PostToolUse payload.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ToolSummary {
+    /// Edit or MultiEdit: the file and how many lines were added and removed.
+    Edited {
+        path: String,
+        added: usize,
+        removed: usize,
+    },
+    /// Write: the file, its size and, when it was small enough to count, its lines.
+    Wrote {
+        path: String,
+        bytes: usize,
+        lines: Option<usize>,
+    },
+    /// Bash exiting with a non-zero status, and the last line it wrote to stderr.
+    Exited { code: i64, stderr: Option<String> },
+}
+
+/// Summarizes a `tool_name` call from its `tool_input` and `tool_response`, with paths shown
+/// relative to `cwd`. `None` for other tools, successful Bash commands and payloads of a shape
+/// this doesn't know.
+pub fn summarize_tool_call(
+    tool_name: &str,
+    tool_input: Option<&Value>,
+    tool_response: Option<&Value>,
+    cwd: Option<&str>,
+) -> Option<ToolSummary> {
+    let input = tool_input.and_then(Value::as_object);
+    let response = tool_response.and_then(Value::as_object);
+    let path = || {
+        input
+            .and_then(|input| input.get("file_path"))
+            .or_else(|| response.and_then(|response| response.get("filePath")))
+            .and_then(Value::as_str)
+            .map(|path| display_path(path, cwd))
+    };
+
+    match tool_name {
+        "Edit" | "MultiEdit" => {
+            let (added, removed) = response
+                .and_then(|response| response.get("structuredPatch"))
+                .and_then(patch_delta)
+                .or_else(|| input.and_then(edits_delta))?;
+            Some(ToolSummary::Edited {
+                path: path()?,
+                added,
+                removed,
+            })
+        }
+        "Write" => {
+            let content = input?.get("content")?.as_str()?;
+            Some(ToolSummary::Wrote {
+                path: path()?,
+                bytes: content.len(),
+                lines: (content.len() <= MAX_SCANNED_BYTES).then(|| count_lines(content)),
+            })
+        }
+        "Bash" => {
+            let response = response?;
+            let code = ["exit_code", "exitCode", "returnCode"]
+                .iter()
+                .find_map(|key| response.get(*key).and_then(Value::as_i64))
+                .filter(|code| *code != 0)?;
+            let stderr = response
+                .get("stderr")
+                .and_then(Value::as_str)
+                .and_then(last_line);
+            Some(ToolSummary::Exited { code, stderr })
+        }
+        _ => None,
+    }
+}
+
+/// `path` relative to `cwd` when it's inside it.
+fn display_path(path: &str, cwd: Option<&str>) -> String {
+    cwd.and_then(|cwd| Path::new(path).strip_prefix(cwd).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .map_or_else(
+            || path.to_string(),
+            |relative| relative.to_string_lossy().to_string(),
+        )
+}
+
+/// Added and removed lines of an Edit `structuredPatch`: hunks whose `lines` start with `+`
+/// or `-`.
+fn patch_delta(patch: &Value) -> Option<(usize, usize)> {
+    let hunks = patch.as_array()?;
+    if hunks.is_empty() {
+        return None;
+    }
+    let lines = hunks
+        .iter()
+        .filter_map(|hunk| hunk.get("lines")?.as_array())
+        .flatten()
+        .take(MAX_PATCH_LINES)
+        .filter_map(Value::as_str);
+    let (mut added, mut removed) = (0, 0);
+    for line in lines {
+        if line.starts_with('+') {
+            added += 1;
+        } else if line.starts_with('-') {
+            removed += 1;
+        }
+    }
+    Some((added, removed))
+}
+
+/// A rough delta from the strings an Edit or MultiEdit replaced: every line of the old string
+/// removed and every line of the new one added.
+fn edits_delta(input: &serde_json::Map<String, Value>) -> Option<(usize, usize)> {
+    let delta = |edit: &serde_json::Map<String, Value>| {
+        let old = edit.get("old_string")?.as_str()?;
+        let new = edit.get("new_string")?.as_str()?;
+        Some((count_lines(new), count_lines(old)))
+    };
+    match input.get("edits") {
+        Some(Value::Array(edits)) => edits
+            .iter()
+            .filter_map(|edit| delta(edit.as_object()?))
+            .reduce(|(added, removed), (more, fewer)| (added + more, removed + fewer)),
+        _ => delta(input),
+    }
+}
+
+/// The lines of `text`, counted in its first [`MAX_SCANNED_BYTES`].
+fn count_lines(text: &str) -> usize {
+    let scanned = &text.as_bytes()[..text.len().min(MAX_SCANNED_BYTES)];
+    if scanned.is_empty() {
+        return 0;
+    }
+    let newlines = scanned.iter().filter(|&&byte| byte == b'\n').count();
+    newlines + usize::from(!scanned.ends_with(b"\n"))
+}
+
+/// The last non-blank line of `text`, looked for in its last [`MAX_SCANNED_BYTES`].
+fn last_line(text: &str) -> Option<String> {
+    let mut start = text.len().saturating_sub(MAX_SCANNED_BYTES);
+    while !text.is_char_boundary(start) {
+        start += 1;
+    }
+    text[start..]
+        .lines()
+        .map(str::trim)
+        .rfind(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// Inspects a PostToolUse `tool_response` for the common failure shapes and returns the first
+/// line of the error text when the call failed.
+///
+/// Recognized shapes: an `error` key, `is_error: true` (MCP), `success: false`, and
+/// `interrupted: true` (Bash). Anything else is treated as a success.
+pub fn detect_tool_failure(tool_response: &Value) -> Option<String> {
+    let obj = tool_response.as_object()?;
+
+    let failed = obj.get("error").is_some_and(|e| !e.is_null() && e != false)
+        || obj.get("is_error").and_then(Value::as_bool) == Some(true)
+        || obj.get("isError").and_then(Value::as_bool) == Some(true)
+        || obj.get("success").and_then(Value::as_bool) == Some(false)
+        || obj.get("interrupted").and_then(Value::as_bool) == Some(true);
+
+    if !failed {
+        return None;
+    }
+
+    let text = obj
+        .get("error")
+        .and_then(error_text)
+        .or_else(|| {
+            obj.get("message")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .or_else(|| obj.get("content").and_then(content_text))
+        .or_else(|| {
+            obj.get("stderr")
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        })
+        .and_then(|t| first_line(&t));
+
+    Some(text.unwrap_or_else(|| {
+        if obj.get("interrupted").and_then(Value::as_bool) == Some(true) {
+            "interrupted".to_string()
+        } else {
+            "unknown error".to_string()
+        }
+    }))
+}
+
+fn error_text(error: &Value) -> Option<String> {
+    match error {
+        Value::String(s) => Some(s.clone()),
+        Value::Object(o) => o.get("message").and_then(Value::as_str).map(str::to_string),
+        _ => None,
+    }
+}
+
+/// Extracts text from MCP-style `content: [{ "type": "text", "text": "..." }]` arrays.
+fn content_text(content: &Value) -> Option<String> {
+    match content {
+        Value::String(s) => Some(s.clone()),
+        Value::Array(items) => items
+            .iter()
+            .find_map(|item| item.get("text").and_then(Value::as_str))
+            .map(str::to_string),
+        _ => None,
+    }
+}
+
+fn first_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn summarizes_edits_writes_and_failed_commands() {
+        let big = "x\n".repeat(MAX_SCANNED_BYTES);
+        let cases = [
+            (
+                "edit with a structured patch",
+                "Edit",
+                json!({"file_path": "/repo/src/main.rs", "old_string": "a", "new_string": "b\nc"}),
+                json!({
+                    "filePath": "/repo/src/main.rs",
+                    "oldString": "a",
+                    "newString": "b\nc",
+                    "structuredPatch": [{
+                        "oldStart": 3, "oldLines": 3, "newStart": 3, "newLines": 4,
+                        "lines": [" fn main() {", "-    a", "+    b", "+    c", " }"]
+                    }],
+                    "userModified": false
+                }),
+                Some(ToolSummary::Edited {
+                    path: "src/main.rs".into(),
+                    added: 2,
+                    removed: 1,
+                }),
+            ),
+            (
+                "edit without a patch",
+                "Edit",
+                json!({"file_path": "/elsewhere/notes.md", "old_string": "one\ntwo", "new_string": "three"}),
+                json!({"filePath": "/elsewhere/notes.md", "structuredPatch": []}),
+                Some(ToolSummary::Edited {
+                    path: "/elsewhere/notes.md".into(),
+                    added: 1,
+                    removed: 2,
+                }),
+            ),
+            (
+                "multi edit",
+                "MultiEdit",
+                json!({"file_path": "/repo/lib.rs", "edits": [
+                    {"old_string": "a", "new_string": "b\nc\n"},
+                    {"old_string": "d\ne\nf", "new_string": ""}
+                ]}),
+                json!({"filePath": "/repo/lib.rs"}),
+                Some(ToolSummary::Edited {
+                    path: "lib.rs".into(),
+                    added: 2,
+                    removed: 4,
+                }),
+            ),
+            (
+                "write",
+                "Write",
+                json!({"file_path": "/repo/README.md", "content": "# Title\n\nBody\n"}),
+                json!({"type": "create", "filePath": "/repo/README.md", "content": "# Title\n\nBody\n", "structuredPatch": []}),
+                Some(ToolSummary::Wrote {
+                    path: "README.md".into(),
+                    bytes: 14,
+                    lines: Some(3),
+                }),
+            ),
+            (
+                "write too large to count",
+                "Write",
+                json!({"file_path": "/repo/data.txt", "content": big}),
+                json!({"type": "create", "filePath": "/repo/data.txt"}),
+                Some(ToolSummary::Wrote {
+                    path: "data.txt".into(),
+                    bytes: 2 * MAX_SCANNED_BYTES,
+                    lines: None,
+                }),
+            ),
+            (
+                "bash failure",
+                "Bash",
+                json!({"command": "cargo test"}),
+                json!({"stdout": "running 3 tests", "stderr": "warning: unused\nerror: test failed\n\n", "exit_code": 101, "interrupted": false}),
+                Some(ToolSummary::Exited {
+                    code: 101,
+                    stderr: Some("error: test failed".into()),
+                }),
+            ),
+            (
+                "bash failure without stderr",
+                "Bash",
+                json!({"command": "false"}),
+                json!({"stdout": "", "stderr": "", "exitCode": 1}),
+                Some(ToolSummary::Exited {
+                    code: 1,
+                    stderr: None,
+                }),
+            ),
+            (
+                "bash success",
+                "Bash",
+                json!({"command": "ls"}),
+                json!({"stdout": "a\nb", "stderr": "", "exit_code": 0}),
+                None,
+            ),
+            (
+                "bash without an exit code",
+                "Bash",
+                json!({"command": "ls"}),
+                json!({"stdout": "a", "stderr": "", "interrupted": false, "isImage": false}),
+                None,
+            ),
+            (
+                "edit of an unknown shape",
+                "Edit",
+                json!(["not", "an", "object"]),
+                json!("Updated"),
+                None,
+            ),
+            (
+                "other tool",
+                "Read",
+                json!({"file_path": "/repo/src/main.rs"}),
+                json!({"type": "text", "file": {"content": "fn main() {}"}}),
+                None,
+            ),
+        ];
+
+        for (name, tool, input, response, expected) in cases {
+            assert_eq!(
+                summarize_tool_call(tool, Some(&input), Some(&response), Some("/repo")),
+                expected,
+                "case: {name}"
+            );
+        }
+    }
+
+    #[test]
+    fn caps_the_work_on_huge_strings() {
+        let huge = "line\n".repeat(1_000_000);
+        assert_eq!(count_lines(&huge), MAX_SCANNED_BYTES / "line\n".len() + 1);
+        assert_eq!(
+            last_line(&format!("{huge}fatal: out of memory\n")).as_deref(),
+            Some("fatal: out of memory")
+        );
+        assert_eq!(
+            last_line(&"é".repeat(MAX_SCANNED_BYTES)).map(|l| l.len()),
+            Some(MAX_SCANNED_BYTES)
+        );
+    }
+
+    #[test]
+    fn detects_failures_across_tool_shapes() {
+        let cases = [
+            (
+                "bash success",
+                json!({"stdout": "ok\n", "stderr": "", "interrupted": false, "isImage": false}),
+                None,
+            ),
+            (
+                "bash interrupted with stderr",
+                json!({"stdout": "", "stderr": "\nerror: build failed\nmore", "interrupted": true, "isImage": false}),
+                Some("error: build failed"),
+            ),
+            (
+                "bash interrupted without output",
+                json!({"stdout": "", "stderr": "", "interrupted": true}),
+                Some("interrupted"),
+            ),
+            (
+                "edit success",
+                json!({"filePath": "/repo/src/main.rs", "oldString": "a", "newString": "b", "structuredPatch": []}),
+                None,
+            ),
+            (
+                "edit error string",
+                json!({"error": "String to replace not found in file.\nString: foo"}),
+                Some("String to replace not found in file."),
+            ),
+            (
+                "write success false",
+                json!({"success": false, "message": "Permission denied"}),
+                Some("Permission denied"),
+            ),
+            (
+                "mcp is_error",
+                json!({"is_error": true, "content": [{"type": "text", "text": "Rate limited\nretry later"}]}),
+                Some("Rate limited"),
+            ),
+            (
+                "mcp success",
+                json!({"content": [{"type": "text", "text": "42 results"}]}),
+                None,
+            ),
+            (
+                "error object",
+                json!({"error": {"code": 500, "message": "upstream unavailable"}}),
+                Some("upstream unavailable"),
+            ),
+            ("null error", json!({"error": null, "result": "ok"}), None),
+            ("unknown shape", json!(["a", "b"]), None),
+            ("plain string", json!("Error: something"), None),
+        ];
+
+        for (name, response, expected) in cases {
+            assert_eq!(
+                detect_tool_failure(&response).as_deref(),
+                expected,
+                "case: {name}"
+            );
+        }
+    }
+}
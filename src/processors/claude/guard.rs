@@ -0,0 +1,216 @@
+//! `claude.guard`: PreToolUse calls matching a rule are answered with an `ask` or `deny`
+//! permission decision instead of being passed through.
+
+use regex::Regex;
+use tracing::warn;
+
+use crate::{
+    configuration::{Config, GuardDecision, GuardRule},
+    processors::claude::structs::{
+        HookEventName, HookInput, HookOutput, HookSpecificOutput, PermissionDecision,
+    },
+};
+
+/// A tool call a guard rule matched.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Intercepted {
+    pub decision: GuardDecision,
+    pub reason: String,
+    pub tool: String,
+    /// The Bash command, when the tool is Bash
+    pub command: Option<String>,
+}
+
+/// The first `claude.guard` rule matching `hook_input`, when it's a PreToolUse.
+pub fn check(config: &Config, hook_input: &HookInput) -> Option<Intercepted> {
+    if hook_input.hook_event_name != HookEventName::PreToolUse {
+        return None;
+    }
+    let tool = hook_input.tool_name.as_deref()?;
+    let command = hook_input
+        .tool_input
+        .as_ref()
+        .and_then(|input| input.get("command"))
+        .and_then(|command| command.as_str());
+
+    let rule = config
+        .claude
+        .guard
+        .iter()
+        .find(|rule| matches(rule, tool, command))?;
+    Some(Intercepted {
+        decision: rule.decision,
+        reason: rule
+            .reason
+            .clone()
+            .unwrap_or_else(|| default_reason(rule, tool)),
+        tool: tool.to_string(),
+        command: (tool == "Bash").then(|| command.unwrap_or_default().to_string()),
+    })
+}
+
+/// The hook output answering an intercepted call.
+pub fn hook_output(intercepted: &Intercepted) -> HookOutput {
+    HookOutput {
+        r#continue: Some(true),
+        suppress_output: Some(true),
+        hook_specific_output: Some(HookSpecificOutput {
+            hook_event_name: Some(HookEventName::PreToolUse.as_str().to_string()),
+            permission_decision: Some(match intercepted.decision {
+                GuardDecision::Ask => PermissionDecision::Ask,
+                GuardDecision::Deny => PermissionDecision::Deny,
+            }),
+            permission_decision_reason: Some(intercepted.reason.clone()),
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+fn matches(rule: &GuardRule, tool: &str, command: Option<&str>) -> bool {
+    let tool_matches = match &rule.tool {
+        Some(glob) => glob_matches(glob, tool),
+        None => tool == "Bash",
+    };
+    if !tool_matches {
+        return false;
+    }
+    let Some(pattern) = &rule.command else {
+        return true;
+    };
+    // Patterns are checked when the config is loaded; this only fails for one built in code.
+    match Regex::new(pattern) {
+        Ok(re) => command.is_some_and(|command| re.is_match(command)),
+        Err(e) => {
+            warn!(pattern = %pattern, error = %e, "invalid guard pattern");
+            false
+        }
+    }
+}
+
+fn default_reason(rule: &GuardRule, tool: &str) -> String {
+    match (&rule.command, &rule.tool) {
+        (Some(pattern), _) => format!("The command matches `{pattern}` in anot's claude.guard"),
+        (None, Some(glob)) => format!("{tool} matches `{glob}` in anot's claude.guard"),
+        (None, None) => format!("{tool} is guarded by anot's claude.guard"),
+    }
+}
+
+/// Whether `name` matches `glob`, where `*` matches any run of characters and `?` one.
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Where the last `*` was, and the name position it's currently standing in for.
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                backtrack = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    g = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pre_tool_use(tool: &str, tool_input: serde_json::Value) -> HookInput {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "s",
+            "transcript_path": "/tmp/t.jsonl",
+            "hook_event_name": "PreToolUse",
+            "tool_name": tool,
+            "tool_input": tool_input,
+        }))
+        .unwrap()
+    }
+
+    fn config(guard: serde_json::Value) -> Config {
+        let mut config = Config::default();
+        config.claude.guard = serde_json::from_value(guard).unwrap();
+        config
+    }
+
+    #[test]
+    fn globs_match_tool_names() {
+        assert!(glob_matches("mcp__github__*", "mcp__github__create_pr"));
+        assert!(glob_matches("*", "Bash"));
+        assert!(glob_matches("Web?????", "WebFetch"));
+        assert!(glob_matches("*__delete_*", "mcp__db__delete_rows"));
+        assert!(!glob_matches("Web?????", "WebSearch"));
+        assert!(!glob_matches("mcp__*", "Bash"));
+        assert!(!glob_matches("Edit", "MultiEdit"));
+    }
+
+    #[test]
+    fn command_rules_only_apply_to_bash() {
+        let config = config(serde_json::json!([{"command": r"rm\s+-rf"}]));
+
+        let intercepted = check(
+            &config,
+            &pre_tool_use("Bash", serde_json::json!({"command": "rm -rf build"})),
+        )
+        .unwrap();
+        assert_eq!(intercepted.decision, GuardDecision::Ask);
+        assert_eq!(
+            intercepted.reason,
+            r"The command matches `rm\s+-rf` in anot's claude.guard"
+        );
+        assert_eq!(intercepted.command.as_deref(), Some("rm -rf build"));
+
+        let benign = pre_tool_use("Bash", serde_json::json!({"command": "ls -la"}));
+        assert_eq!(check(&config, &benign), None);
+        let other_tool = pre_tool_use("Task", serde_json::json!({"command": "rm -rf build"}));
+        assert_eq!(check(&config, &other_tool), None);
+        let mut post = pre_tool_use("Bash", serde_json::json!({"command": "rm -rf build"}));
+        post.hook_event_name = HookEventName::PostToolUse;
+        assert_eq!(check(&config, &post), None);
+    }
+
+    #[test]
+    fn tool_rules_match_by_glob_and_the_first_rule_wins() {
+        let config = config(serde_json::json!([
+            {"tool": "mcp__*", "decision": "deny", "reason": "No MCP tools"},
+            {"tool": "*"},
+        ]));
+
+        let intercepted = check(
+            &config,
+            &pre_tool_use("mcp__db__query", serde_json::json!({})),
+        )
+        .unwrap();
+        assert_eq!(intercepted.decision, GuardDecision::Deny);
+        assert_eq!(intercepted.reason, "No MCP tools");
+        assert_eq!(intercepted.command, None);
+
+        let intercepted = check(&config, &pre_tool_use("Write", serde_json::json!({}))).unwrap();
+        assert_eq!(intercepted.decision, GuardDecision::Ask);
+        assert_eq!(
+            intercepted.reason,
+            "Write matches `*` in anot's claude.guard"
+        );
+    }
+
+    #[test]
+    fn no_rules_intercept_nothing() {
+        let input = pre_tool_use("Bash", serde_json::json!({"command": "rm -rf /"}));
+        assert_eq!(check(&Config::default(), &input), None);
+    }
+}
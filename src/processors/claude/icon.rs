@@ -1,18 +1,137 @@
 use anyhow::Error;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-const CLAUDE_ICON_BYTES: &[u8] = include_bytes!("../../../assets/claude-icon.png");
+use crate::processors::{
+    claude::structs::HookEventName,
+    icon::{EmbeddedIcon, custom_icon},
+};
 
-pub fn get_claude_icon_temp_path() -> Result<PathBuf, Error> {
-    let temp_dir = std::env::temp_dir();
-    let icon_path = temp_dir.join("claude-code-icon.png");
+const CLAUDE_ICON: EmbeddedIcon = EmbeddedIcon {
+    name: "claude-icon",
+    variants: &[
+        (48, include_bytes!("../../../assets/claude-icon-48.png")),
+        (128, include_bytes!("../../../assets/claude-icon-128.png")),
+        (1200, include_bytes!("../../../assets/claude-icon.png")),
+    ],
+};
+const CLAUDE_ATTENTION_ICON: EmbeddedIcon = EmbeddedIcon {
+    name: "claude-icon-attention",
+    variants: &[
+        (
+            48,
+            include_bytes!("../../../assets/claude-icon-attention-48.png"),
+        ),
+        (
+            128,
+            include_bytes!("../../../assets/claude-icon-attention-128.png"),
+        ),
+        (
+            240,
+            include_bytes!("../../../assets/claude-icon-attention.png"),
+        ),
+    ],
+};
+const CLAUDE_DONE_ICON: EmbeddedIcon = EmbeddedIcon {
+    name: "claude-icon-done",
+    variants: &[
+        (
+            48,
+            include_bytes!("../../../assets/claude-icon-done-48.png"),
+        ),
+        (
+            128,
+            include_bytes!("../../../assets/claude-icon-done-128.png"),
+        ),
+        (240, include_bytes!("../../../assets/claude-icon-done.png")),
+    ],
+};
 
-    if !icon_path.exists() {
-        let mut file = File::create(&icon_path)?;
-        file.write_all(CLAUDE_ICON_BYTES)?;
+/// Returns the configured icon when it exists, otherwise the built-in icon for `event`, in the
+/// size for `size_hint`, written to the temp directory.
+pub fn get_icon(
+    event: &HookEventName,
+    configured: Option<&Path>,
+    size_hint: u32,
+) -> Result<PathBuf, Error> {
+    if let Some(path) = configured.and_then(custom_icon) {
+        return Ok(path);
     }
 
-    Ok(icon_path)
+    builtin_icon(event).get_icon(size_hint)
+}
+
+/// Events that wait on the user get a warning badge, finished work gets a checkmark.
+pub fn builtin_icon(event: &HookEventName) -> &'static EmbeddedIcon {
+    match event {
+        HookEventName::Notification => &CLAUDE_ATTENTION_ICON,
+        HookEventName::Stop | HookEventName::SubagentStop | HookEventName::SessionEnd => {
+            &CLAUDE_DONE_ICON
+        }
+        _ => &CLAUDE_ICON,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::icon::LARGEST;
+
+    fn largest(icon: &EmbeddedIcon) -> &'static [u8] {
+        icon.variant(LARGEST).1
+    }
+
+    #[test]
+    fn falls_back_to_builtin_icon() {
+        let path = get_icon(
+            &HookEventName::PreToolUse,
+            Some(Path::new("~/nonexistent-anot-icon.png")),
+            LARGEST,
+        )
+        .unwrap();
+
+        assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+        assert_eq!(std::fs::read(&path).unwrap(), largest(&CLAUDE_ICON));
+    }
+
+    #[test]
+    fn selects_builtin_icon_by_event() {
+        let icon = |event| std::fs::read(get_icon(&event, None, LARGEST).unwrap()).unwrap();
+
+        assert_eq!(
+            icon(HookEventName::Notification),
+            largest(&CLAUDE_ATTENTION_ICON)
+        );
+        assert_eq!(icon(HookEventName::Stop), largest(&CLAUDE_DONE_ICON));
+        assert_eq!(
+            icon(HookEventName::SubagentStop),
+            largest(&CLAUDE_DONE_ICON)
+        );
+        assert_eq!(
+            icon(HookEventName::Unknown("ToolBatch".to_string())),
+            largest(&CLAUDE_ICON)
+        );
+    }
+
+    #[test]
+    fn every_variant_is_a_png_of_its_size() {
+        for icon in [CLAUDE_ICON, CLAUDE_ATTENTION_ICON, CLAUDE_DONE_ICON] {
+            for (size, bytes) in icon.variants {
+                assert_eq!(&bytes[1..4], b"PNG", "{}", icon.name);
+                let width = u32::from_be_bytes(bytes[16..20].try_into().unwrap());
+                assert_eq!(width, *size, "{}", icon.name);
+            }
+        }
+    }
+
+    #[test]
+    fn configured_icon_wins_over_event_icon() {
+        let configured =
+            std::env::temp_dir().join(format!("anot-custom-event-icon-{}.png", std::process::id()));
+        std::fs::write(&configured, b"custom").unwrap();
+
+        assert_eq!(
+            get_icon(&HookEventName::Stop, Some(&configured), 128).unwrap(),
+            configured
+        );
+    }
 }
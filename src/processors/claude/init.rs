@@ -1,108 +1,294 @@
 use std::{
-    collections::HashMap,
-    fmt,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
 };
 
-use anyhow::Error;
-use inquire::{Confirm, InquireError, MultiSelect, Select};
+use anyhow::{Context, Error};
+use inquire::{CustomType, MultiSelect, validator::Validation};
 use serde::{Deserialize, Serialize};
 
-use crate::processors::claude::structs::HookEventName;
+use crate::console::{self, Mark};
+use crate::error::AnotError;
+use crate::preview::write_with_preview;
+use crate::processors::{
+    claude::{
+        paths::{self, CONFIG_DIR_ENV, ClaudeEnv, HomeSource},
+        structs::HookEventName,
+    },
+    common::init::{ConfigTarget, PathCandidate, confirm_creation, handle_inquire_error},
+};
 use strum::IntoEnumIterator;
 use tracing::{debug, info, instrument, warn};
 
-fn handle_inquire_error(err: InquireError, context: &str) -> Error {
-    match err {
-        InquireError::OperationCanceled => Error::msg("Operation cancelled by user"),
-        InquireError::OperationInterrupted => Error::msg("Operation interrupted by user"),
-        _ => Error::msg(format!("{}: {}", context, err)),
-    }
-}
-
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[serde(rename_all = "snake_case")]
 enum HookType {
     Command,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 struct ActionConfiguration {
     r#type: HookType,
     command: String,
+    #[serde(default)]
     timeout: Option<u64>,
+    /// Keys we don't know about, kept so they survive a rewrite.
+    #[serde(flatten)]
+    other: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 struct EventHookConfiguration {
+    #[serde(default)]
     matcher: String,
+    #[serde(deserialize_with = "one_or_many")]
     hooks: Vec<ActionConfiguration>,
 }
 
+/// Accepts a single value where a list is expected, as hand-written settings sometimes have.
+fn one_or_many<'de, D, T>(deserializer: D) -> Result<Vec<T>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum OneOrMany<T> {
+        Many(Vec<T>),
+        One(T),
+    }
+
+    Ok(match OneOrMany::deserialize(deserializer)? {
+        OneOrMany::Many(values) => values,
+        OneOrMany::One(value) => vec![value],
+    })
+}
+
+/// Ordered maps keep the rewritten file, and so the previewed diff, the same from run to run.
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
 struct ClaudeConfiguration {
     #[serde(default)]
-    hooks: HashMap<HookEventName, Vec<EventHookConfiguration>>,
+    hooks: BTreeMap<HookEventName, Vec<EventHookConfiguration>>,
     #[serde(flatten)]
-    other: HashMap<String, serde_json::Value>,
+    other: serde_json::Map<String, serde_json::Value>,
+    /// Hook entries we couldn't read, written back untouched after the ones we could.
+    #[serde(skip)]
+    unparsed: BTreeMap<HookEventName, Vec<serde_json::Value>>,
 }
 
-enum ClaudeCodePathSelection {
-    UserSettings(bool),
-    ProjectSettings(bool),
-    LocalProjectSettings(bool),
-    CustomPath,
-}
+impl ClaudeConfiguration {
+    /// Reads settings leniently: an entry that doesn't fit is kept as it is and reported in
+    /// the returned warnings instead of failing the whole file. Only a file that isn't a JSON
+    /// object, or whose `hooks` isn't one, is an error.
+    fn parse(data: &str) -> Result<(Self, Vec<String>), Error> {
+        let mut other: serde_json::Map<String, serde_json::Value> = serde_json::from_str(data)?;
 
-impl fmt::Display for ClaudeCodePathSelection {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ClaudeCodePathSelection::UserSettings(exists) => {
-                let status = if *exists { "✓" } else { "✗" };
-                write!(f, "{} User Settings (~/.claude/settings.json)", status)
+        let mut config = ClaudeConfiguration::default();
+        let mut warnings = Vec::new();
+        let hooks = match other.remove("hooks") {
+            None => serde_json::Map::new(),
+            Some(serde_json::Value::Object(hooks)) => hooks,
+            Some(_) => {
+                return Err(Error::msg(
+                    "`hooks` must be an object mapping event names to lists of hook entries",
+                ));
             }
-            ClaudeCodePathSelection::ProjectSettings(exists) => {
-                let status = if *exists { "✓" } else { "✗" };
-                write!(f, "{} Project Settings (.claude/settings.json)", status)
+        };
+        config.other = other;
+
+        for (name, entries) in hooks {
+            let event = HookEventName::from_name(&name);
+            let entries = match entries {
+                serde_json::Value::Array(entries) => entries,
+                entry => vec![entry],
+            };
+            for (index, entry) in entries.into_iter().enumerate() {
+                match serde_json::from_value::<EventHookConfiguration>(entry.clone()) {
+                    Ok(entry) => config.hooks.entry(event.clone()).or_default().push(entry),
+                    Err(e) => {
+                        warnings.push(format!("hooks.{name}[{index}]: {e}"));
+                        config
+                            .unparsed
+                            .entry(event.clone())
+                            .or_default()
+                            .push(entry);
+                    }
+                }
             }
-            ClaudeCodePathSelection::LocalProjectSettings(exists) => {
-                let status = if *exists { "✓" } else { "✗" };
-                write!(
-                    f,
-                    "{} Local Project Settings (.claude/settings.local.json)",
-                    status
-                )
+        }
+        Ok((config, warnings))
+    }
+
+    /// The settings as written back, unreadable entries included.
+    fn to_value(&self) -> Result<serde_json::Value, Error> {
+        let mut value = serde_json::to_value(self)?;
+        if self.unparsed.is_empty() {
+            return Ok(value);
+        }
+        let hooks = value
+            .as_object_mut()
+            .and_then(|settings| settings.get_mut("hooks"))
+            .and_then(|hooks| hooks.as_object_mut())
+            .ok_or_else(|| Error::msg("Failed to serialize the configuration"))?;
+        for (event, entries) in &self.unparsed {
+            let list = hooks
+                .entry(event.as_str())
+                .or_insert_with(|| serde_json::Value::Array(Vec::new()));
+            if let Some(list) = list.as_array_mut() {
+                list.extend(entries.iter().cloned());
             }
-            ClaudeCodePathSelection::CustomPath => write!(f, "📂 Custom Path"),
         }
+        Ok(value)
+    }
+}
+
+/// A settings file Claude Code reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SettingsLocation {
+    User,
+    Project,
+    LocalProject,
+    /// Enterprise policy settings, which take precedence over all others. Usually writable
+    /// only by an administrator.
+    Managed,
+}
+
+/// A settings file offered by `anot init claude`, with what it's called and whether it exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SettingsCandidate {
+    location: SettingsLocation,
+    label: String,
+    path: PathBuf,
+    exists: bool,
+}
+
+/// The managed settings file Claude Code reads on `os` (as in `std::env::consts::OS`).
+fn managed_settings_path(os: &str) -> Option<PathBuf> {
+    match os {
+        "macos" => Some(PathBuf::from(
+            "/Library/Application Support/ClaudeCode/managed-settings.json",
+        )),
+        "windows" => Some(PathBuf::from(
+            r"C:\ProgramData\ClaudeCode\managed-settings.json",
+        )),
+        "linux" | "freebsd" | "openbsd" | "netbsd" => {
+            Some(PathBuf::from("/etc/claude-code/managed-settings.json"))
+        }
+        _ => None,
     }
 }
 
+/// The settings files Claude Code reads, in the order they're offered. The user settings of
+/// the directory Claude Code uses come first (see [`paths`]); those of the other directories
+/// are offered too when they exist. `exists` is `Path::exists` outside tests.
+fn settings_candidates(
+    os: &str,
+    env: &ClaudeEnv,
+    exists: impl Fn(&Path) -> bool,
+) -> Vec<SettingsCandidate> {
+    let homes = paths::candidates(env, &exists);
+    let active = paths::active(&homes);
+    let mut locations = Vec::new();
+    for home in &homes {
+        let label = if active == Some(home) {
+            match home.source {
+                HomeSource::EnvVar => format!("User Settings ({CONFIG_DIR_ENV})"),
+                _ => "User Settings".to_string(),
+            }
+        } else if home.exists {
+            format!("User Settings in {}", home.label())
+        } else {
+            continue;
+        };
+        locations.push((SettingsLocation::User, label, home.settings_path()));
+    }
+    locations.extend([
+        (
+            SettingsLocation::Project,
+            "Project Settings".to_string(),
+            Path::new(".claude").join("settings.json"),
+        ),
+        (
+            SettingsLocation::LocalProject,
+            "Local Project Settings".to_string(),
+            Path::new(".claude").join("settings.local.json"),
+        ),
+    ]);
+    locations.extend(managed_settings_path(os).map(|path| {
+        (
+            SettingsLocation::Managed,
+            "Managed Settings".to_string(),
+            path,
+        )
+    }));
+
+    locations
+        .into_iter()
+        .map(|(location, label, path)| SettingsCandidate {
+            exists: exists(&path),
+            location,
+            label,
+            path,
+        })
+        .collect()
+}
+
+/// Tools offered for the `matcher` of PreToolUse/PostToolUse hooks. Claude Code matches the
+/// tool name against the pattern as a regex.
+const COMMON_TOOL_MATCHERS: [&str; 5] = ["Bash", "Write", "Edit", "WebFetch", "mcp__.*"];
+const ALL_TOOLS_OPTION: &str = "All tools";
+const CUSTOM_PATTERN_OPTION: &str = "Custom pattern…";
+
+/// Tool-name patterns per hook event. Events without an entry (or with an empty list) match
+/// every tool.
+type ToolMatchers = HashMap<HookEventName, Vec<String>>;
+
+/// Hook timeout used when neither `--timeout` nor an existing hook sets one.
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 10;
+const MAX_HOOK_TIMEOUT_SECS: u64 = 600;
+
+/// Choices for `anot init claude` given on the command line instead of at a prompt.
+#[derive(Debug, Clone, Default)]
+pub struct InitOptions {
+    /// Limit the PreToolUse and PostToolUse hooks to these tool patterns.
+    pub matchers: Vec<String>,
+    /// Hook timeout in seconds (1–600).
+    pub timeout: Option<u64>,
+    /// Config file the hooks pass to `anot` with `--config`.
+    pub config_path: Option<PathBuf>,
+    /// Show the changes to the settings file without writing it.
+    pub dry_run: bool,
+}
+
+/// Sets up the anot hooks in a Claude Code settings file.
 #[instrument(skip(claude_config_path))]
 pub fn initialize_claude_configuration(
     claude_config_path: &Option<PathBuf>,
+    options: &InitOptions,
 ) -> Result<(), anyhow::Error> {
-    let chosen_path = choose_config_path(claude_config_path)?;
-    let expanded_path = expand_tilde(&chosen_path);
-    let config_exists = expanded_path.exists();
+    if let Some(timeout) = options.timeout {
+        validate_timeout(timeout).map_err(Error::msg)?;
+    }
 
-    debug!(chosen = %chosen_path.display(), expanded = %expanded_path.display(), exists = config_exists, "resolved Claude settings path");
-    ensure_path_exists(&expanded_path)?;
+    let expanded_path = choose_config_path(claude_config_path)?;
+    let config_exists = expanded_path.exists();
+    if !options.dry_run {
+        confirm_creation(&expanded_path)?;
+    }
 
-    let mut config = read_config(&expanded_path)?;
-    let command = agent_command()?;
+    let (original, mut config) = read_config(&expanded_path)?;
+    let command = agent_command(options.config_path.as_deref())?;
 
     if config_exists && !config.hooks.is_empty() {
         info!(
             hooks_entries = config.hooks.len(),
             "existing Claude hooks detected"
         );
-        println!("📋 Current hook configuration:");
+        console::say(Mark::Current, "Current hook configuration:");
         for (hook, configurations) in &config.hooks {
-            println!(
-                "  • {:?}: {} hook(s) configured",
-                hook,
-                configurations.len()
+            console::say(
+                Mark::Item,
+                format!("{:?}: {} hook(s) configured", hook, configurations.len()),
             );
         }
         println!();
@@ -110,138 +296,156 @@ pub fn initialize_claude_configuration(
 
     let selected_hooks = choose_hooks(&config)?;
     debug!(selected = ?selected_hooks, "user selected hooks");
-    config = with_selected_notification_hooks(config, command, selected_hooks);
-    write_config(&expanded_path, &config)?;
+    let tool_matchers = choose_tool_matchers(&config, &selected_hooks, &options.matchers)?;
+    let timeout = match options.timeout {
+        Some(timeout) => timeout,
+        None if selected_hooks.is_empty() => DEFAULT_HOOK_TIMEOUT_SECS,
+        None => prompt_timeout(current_timeout(&config).unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS))?,
+    };
+    config = with_selected_notification_hooks(
+        config,
+        HookCommand { command, timeout },
+        selected_hooks,
+        &tool_matchers,
+    );
 
-    println!("✅ Successfully configured Claude Code notifications");
-    println!("📁 Configuration written to: {}", expanded_path.display());
+    if write_config(&expanded_path, &original, &config, options.dry_run)? {
+        console::say(
+            Mark::Ok,
+            "Successfully configured Claude Code notifications",
+        );
+        console::say(
+            Mark::Written,
+            format!("Configuration written to: {}", expanded_path.display()),
+        );
+    }
 
     Ok(())
 }
 
-#[instrument(skip(claude_config_path))]
 fn choose_config_path(claude_config_path: &Option<PathBuf>) -> Result<PathBuf, Error> {
-    if let Some(p) = claude_config_path {
-        info!(path = %p.display(), "using provided path");
-        return Ok(p.clone());
-    }
-
-    let user_settings_path = expand_tilde(&PathBuf::from("~/.claude/settings.json"));
-    let project_settings_path = PathBuf::from(".claude/settings.json");
-    let local_project_settings_path = PathBuf::from(".claude/settings.local.json");
-
-    let user_exists = user_settings_path.exists();
-    let project_exists = project_settings_path.exists();
-    let local_project_exists = local_project_settings_path.exists();
-
-    let selection = Select::new(
-        "Where do you want to initialize the notifications for?",
-        vec![
-            ClaudeCodePathSelection::UserSettings(user_exists),
-            ClaudeCodePathSelection::ProjectSettings(project_exists),
-            ClaudeCodePathSelection::LocalProjectSettings(local_project_exists),
-            ClaudeCodePathSelection::CustomPath,
-        ],
-    )
-    .with_help_message(
-        "Select the configuration path for Claude Code. ✓ = file exists, ✗ = file missing",
-    )
-    .prompt()
-    .map_err(|err| handle_inquire_error(err, "Failed to prompt for Claude configuration path"))?;
+    let candidates = settings_candidates(std::env::consts::OS, &ClaudeEnv::current(), Path::exists)
+        .into_iter()
+        .map(|candidate| {
+            let path = PathCandidate::new(candidate.label, candidate.path, candidate.exists);
+            match candidate.location {
+                SettingsLocation::Managed => {
+                    path.with_warning("requires admin rights, applies to every user")
+                }
+                _ => path,
+            }
+        })
+        .collect();
 
-    let path = match selection {
-        ClaudeCodePathSelection::UserSettings(exists) => {
-            info!(exists, path = %PathBuf::from("~/.claude/settings.json").display(), "selected user settings");
-            PathBuf::from("~/.claude/settings.json")
-        }
-        ClaudeCodePathSelection::ProjectSettings(exists) => {
-            info!(exists, path = %PathBuf::from(".claude/settings.json").display(), "selected project settings");
-            PathBuf::from(".claude/settings.json")
-        }
-        ClaudeCodePathSelection::LocalProjectSettings(exists) => {
-            info!(exists, path = %PathBuf::from(".claude/settings.local.json").display(), "selected local project settings");
-            PathBuf::from(".claude/settings.local.json")
-        }
-        ClaudeCodePathSelection::CustomPath => {
-            let custom_path: String = inquire::Text::new("Enter the custom path:")
-                .with_help_message("Provide the full path to the Claude Code settings.json file.")
-                .prompt()
-                .map_err(|err| handle_inquire_error(err, "Failed to prompt for custom path"))?;
+    ConfigTarget {
+        agent: "Claude Code",
+        question: "Where do you want to initialize the notifications for?",
+        file: "settings.json file",
+        candidates,
+    }
+    .choose_path(claude_config_path.as_deref())
+}
 
-            info!(path = %custom_path, "selected custom path");
-            PathBuf::from(custom_path)
+/// The settings file's current content and its parsed form. A missing file reads as empty.
+#[instrument]
+fn read_config(path: &PathBuf) -> Result<(String, ClaudeConfiguration), Error> {
+    let config_data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            debug!(path = %path.display(), "settings file doesn't exist yet");
+            return Ok((String::new(), ClaudeConfiguration::default()));
+        }
+        Err(source) => {
+            return Err(AnotError::ConfigRead {
+                path: path.clone(),
+                source,
+            }
+            .into());
         }
     };
 
-    Ok(path)
-}
-
-fn expand_tilde(path: &Path) -> PathBuf {
-    if let Ok(s) = path.to_path_buf().into_os_string().into_string() {
-        if let Some(rest) = s.strip_prefix("~/")
-            && let Ok(home) = std::env::var("HOME")
-        {
-            debug!(original = %s, expanded = %PathBuf::from(home.clone()).join(rest).display(), "expanding ~ to HOME");
-            return PathBuf::from(home).join(rest);
+    let (config, warnings) =
+        ClaudeConfiguration::parse(&config_data).map_err(|source| AnotError::ConfigParse {
+            path: path.clone(),
+            source,
+        })?;
+    debug!(hooks_entries = config.hooks.len(), "parsed Claude settings");
+    if !warnings.is_empty() {
+        warn!(
+            count = warnings.len(),
+            "kept unreadable hook entries as they are"
+        );
+        console::say(
+            Mark::Warn,
+            format!(
+                "Some hook entries in {} couldn't be read and will be left as they are:",
+                path.display()
+            ),
+        );
+        for warning in &warnings {
+            console::say(Mark::Item, warning);
         }
-        return PathBuf::from(s);
+        println!();
     }
-    path.to_path_buf()
+    Ok((config_data, config))
 }
 
-#[instrument]
-fn ensure_path_exists(path: &PathBuf) -> Result<(), Error> {
-    if !path.exists() {
-        let should_create = Confirm::new(&format!(
-            "The configuration file '{}' does not exist. Would you like to create it?",
-            path.display()
-        ))
-        .with_default(true)
-        .prompt()
-        .map_err(|err| handle_inquire_error(err, "Failed to get user confirmation"))?;
-
-        if !should_create {
-            info!(path = %path.display(), "user declined to create Claude settings file");
-            return Err(Error::msg("Operation cancelled by user"));
-        }
-
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)
-                .or(Err(Error::msg("Failed to create parent directories")))?;
-        }
+/// Whether `action` runs `anot claude`, whatever options (`--config`, `-d`) were added to it.
+fn is_our_notification_action(action: &ActionConfiguration) -> bool {
+    let words = command_words(&action.command);
+    let Some((program, args)) = words.split_first() else {
+        return false;
+    };
+    let program = Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
 
-        let empty_config = ClaudeConfiguration {
-            hooks: HashMap::new(),
-            other: HashMap::new(),
-        };
+    program.contains("anot") && args.iter().any(|arg| arg == "claude")
+}
 
-        let config_json = serde_json::to_string_pretty(&empty_config)
-            .or(Err(Error::msg("Failed to serialize empty configuration")))?;
+/// Splits a hook command into words the way `sh` would for the commands we generate: on
+/// whitespace, with double or single quotes grouping and backslashes escaping in double quotes.
+fn command_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
 
-        std::fs::write(path, config_json)
-            .or(Err(Error::msg("Failed to create configuration file")))?;
-        info!(path = %path.display(), "created initial Claude settings file");
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => word.extend(chars.next()),
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|&c| c != '\''));
+            }
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
     }
-    Ok(())
+    words.extend(word);
+    words
 }
 
-#[instrument]
-fn read_config(path: &PathBuf) -> Result<ClaudeConfiguration, Error> {
-    let config_data = std::fs::read_to_string(path)
-        .map_err(|e| Error::msg(format!("Failed to read the configuration file: {}", e)))?;
-
-    let config: ClaudeConfiguration = serde_json::from_str(&config_data).map_err(|e| {
-        Error::msg(format!(
-            "Failed to parse the configuration file: {}. Content: {}",
-            e, config_data
-        ))
-    })?;
-    debug!(hooks_entries = config.hooks.len(), "parsed Claude settings");
-    Ok(config)
-}
-
-fn is_our_notification_action(action: &ActionConfiguration) -> bool {
-    action.command.contains("anot") && action.command.contains("claude")
+/// `value` in double quotes, safe to pass to `sh` even with spaces or `$` in it.
+fn shell_quote(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '`') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
 }
 
 fn has_our_notification_hook(event_hooks: &[EventHookConfiguration]) -> bool {
@@ -280,17 +484,16 @@ fn find_default_indices(
 }
 
 fn prompt_user_selection(
+    message: &str,
+    help: &str,
     options: &[String],
     default_indices: &[usize],
 ) -> Result<Vec<String>, Error> {
-    MultiSelect::new(
-        "Select which hooks you want to configure for notifications:",
-        options.to_vec(),
-    )
-    .with_help_message("Use space to select/deselect, arrow keys to navigate, enter to confirm. [✓] = currently configured")
-    .with_default(default_indices)
-    .prompt()
-    .map_err(|err| handle_inquire_error(err, "Failed to get hook selection"))
+    MultiSelect::new(message, options.to_vec())
+        .with_help_message(help)
+        .with_default(default_indices)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to get hook selection"))
 }
 
 fn convert_selections_to_hooks(
@@ -309,17 +512,21 @@ fn convert_selections_to_hooks(
         .collect()
 }
 
-fn choose_hooks(config: &ClaudeConfiguration) -> Result<Vec<HookEventName>, Error> {
+/// Asks which hook events to pick, listing every event with `[✓]` before the `marked` ones,
+/// which start out selected. `help` explains what the mark means.
+pub(crate) fn prompt_event_selection(
+    message: &str,
+    help: &str,
+    marked: &[HookEventName],
+) -> Result<Vec<HookEventName>, Error> {
     let all_hooks: Vec<HookEventName> = HookEventName::iter().collect();
-    let currently_configured = get_currently_configured_hooks(config);
-
     let options: Vec<String> = all_hooks
         .iter()
-        .map(|hook| create_hook_option(hook, &currently_configured))
+        .map(|hook| create_hook_option(hook, marked))
         .collect();
 
-    let default_indices = find_default_indices(&currently_configured, &all_hooks);
-    let selected_strings = prompt_user_selection(&options, &default_indices)?;
+    let default_indices = find_default_indices(marked, &all_hooks);
+    let selected_strings = prompt_user_selection(message, help, &options, &default_indices)?;
 
     Ok(convert_selections_to_hooks(
         selected_strings,
@@ -328,21 +535,176 @@ fn choose_hooks(config: &ClaudeConfiguration) -> Result<Vec<HookEventName>, Erro
     ))
 }
 
-fn agent_command() -> Result<String, Error> {
-    let current_exe =
-        std::env::current_exe().or(Err(Error::msg("Failed to get current executable path")))?;
-    let exe_str = current_exe.to_string_lossy().to_string();
-    let cmd = format!("\"{}\" claude", exe_str);
-    Ok(cmd)
+fn choose_hooks(config: &ClaudeConfiguration) -> Result<Vec<HookEventName>, Error> {
+    prompt_event_selection(
+        "Select which hooks you want to configure for notifications:",
+        "Use space to select/deselect, arrow keys to navigate, enter to confirm. [✓] = currently configured",
+        &get_currently_configured_hooks(config),
+    )
+}
+
+fn takes_tool_matcher(event: &HookEventName) -> bool {
+    matches!(
+        event,
+        HookEventName::PreToolUse | HookEventName::PostToolUse
+    )
+}
+
+/// The non-empty matchers of the anot hooks already configured for `event`.
+fn current_matchers(config: &ClaudeConfiguration, event: &HookEventName) -> Vec<String> {
+    config
+        .hooks
+        .get(event)
+        .into_iter()
+        .flatten()
+        .filter(|hook_config| hook_config.hooks.iter().any(is_our_notification_action))
+        .filter(|hook_config| !hook_config.matcher.is_empty())
+        .map(|hook_config| hook_config.matcher.clone())
+        .collect()
+}
+
+fn choose_tool_matchers(
+    config: &ClaudeConfiguration,
+    selected_hooks: &[HookEventName],
+    matchers: &[String],
+) -> Result<ToolMatchers, Error> {
+    let mut tool_matchers = ToolMatchers::new();
+    for event in selected_hooks
+        .iter()
+        .filter(|event| takes_tool_matcher(event))
+    {
+        let patterns = if matchers.is_empty() {
+            prompt_tool_matchers(event, &current_matchers(config, event))?
+        } else {
+            matchers.to_vec()
+        };
+        debug!(event = %event, patterns = ?patterns, "chose tool matchers");
+        tool_matchers.insert(event.clone(), patterns);
+    }
+    Ok(tool_matchers)
+}
+
+/// Asks which tools `event` notifies for, pre-selecting the `current` patterns. Returns no
+/// patterns for all tools.
+fn prompt_tool_matchers(event: &HookEventName, current: &[String]) -> Result<Vec<String>, Error> {
+    let mut options = vec![ALL_TOOLS_OPTION.to_string()];
+    options.extend(COMMON_TOOL_MATCHERS.iter().map(|tool| tool.to_string()));
+    options.extend(
+        current
+            .iter()
+            .filter(|pattern| !options.contains(pattern))
+            .cloned()
+            .collect::<Vec<_>>(),
+    );
+    options.push(CUSTOM_PATTERN_OPTION.to_string());
+
+    let default_indices: Vec<usize> = if current.is_empty() {
+        vec![0]
+    } else {
+        current
+            .iter()
+            .filter_map(|pattern| options.iter().position(|option| option == pattern))
+            .collect()
+    };
+
+    let selected = MultiSelect::new(&format!("Which tools should {event} notify for?"), options)
+        .with_help_message(
+            "Each pattern becomes its own hook entry. mcp__.* matches every MCP tool",
+        )
+        .with_default(&default_indices)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to get tool selection"))?;
+
+    if selected.iter().any(|option| option == ALL_TOOLS_OPTION) {
+        return Ok(Vec::new());
+    }
+
+    let mut patterns = Vec::new();
+    for option in selected {
+        if option == CUSTOM_PATTERN_OPTION {
+            let pattern = inquire::Text::new("Enter the tool name pattern:")
+                .with_help_message("A tool name or regex, e.g. mcp__github__.* or Read|Grep")
+                .prompt()
+                .map_err(|err| handle_inquire_error(err, "Failed to prompt for pattern"))?;
+            if !pattern.trim().is_empty() {
+                patterns.push(pattern.trim().to_string());
+            }
+        } else {
+            patterns.push(option);
+        }
+    }
+    Ok(patterns)
+}
+
+/// The timeout of the anot hooks already configured, if any.
+fn current_timeout(config: &ClaudeConfiguration) -> Option<u64> {
+    config
+        .hooks
+        .values()
+        .flatten()
+        .flat_map(|hook_config| &hook_config.hooks)
+        .filter(|action| is_our_notification_action(action))
+        .find_map(|action| action.timeout)
 }
 
-fn create_our_hook_config(command: String) -> EventHookConfiguration {
+fn validate_timeout(timeout: u64) -> Result<(), String> {
+    if (1..=MAX_HOOK_TIMEOUT_SECS).contains(&timeout) {
+        Ok(())
+    } else {
+        Err(format!(
+            "The hook timeout must be between 1 and {MAX_HOOK_TIMEOUT_SECS} seconds, got {timeout}"
+        ))
+    }
+}
+
+fn prompt_timeout(default: u64) -> Result<u64, Error> {
+    CustomType::<u64>::new("Hook timeout in seconds:")
+        .with_default(default)
+        .with_help_message("How long Claude Code waits for anot before giving up (1–600)")
+        .with_validator(|timeout: &u64| {
+            Ok(match validate_timeout(*timeout) {
+                Ok(()) => Validation::Valid,
+                Err(message) => Validation::Invalid(message.into()),
+            })
+        })
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for hook timeout"))
+}
+
+/// The command and timeout written into every hook entry we create.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct HookCommand {
+    command: String,
+    timeout: u64,
+}
+
+fn agent_command(config_path: Option<&Path>) -> Result<String, Error> {
+    let current_exe = std::env::current_exe()
+        .map_err(|e| AnotError::io("Failed to get current executable path", e))?;
+    Ok(build_agent_command(&current_exe, config_path))
+}
+
+/// `"<exe>" [--config "<path>"] claude`. The config path is made absolute, since Claude Code
+/// runs hooks from the project directory.
+fn build_agent_command(exe: &Path, config_path: Option<&Path>) -> String {
+    let mut command = shell_quote(&exe.to_string_lossy());
+    if let Some(path) = config_path {
+        let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+        command.push_str(" --config ");
+        command.push_str(&shell_quote(&path.to_string_lossy()));
+    }
+    command.push_str(" claude");
+    command
+}
+
+fn create_our_hook_config(hook_command: &HookCommand, matcher: String) -> EventHookConfiguration {
     EventHookConfiguration {
-        matcher: "".to_string(),
+        matcher,
         hooks: vec![ActionConfiguration {
             r#type: HookType::Command,
-            command,
-            timeout: Some(10),
+            command: hook_command.command.clone(),
+            timeout: Some(hook_command.timeout),
+            other: serde_json::Map::new(),
         }],
     }
 }
@@ -353,17 +715,23 @@ fn remove_our_notification_hooks(config: &mut ClaudeConfiguration) {
     }
 }
 
+/// Adds our hook to each selected event, once per tool matcher; events without matchers get a
+/// single entry with an empty matcher.
 fn add_hooks_to_selected_events(
     config: &mut ClaudeConfiguration,
     selected_hooks: Vec<HookEventName>,
-    our_hook_config: EventHookConfiguration,
+    hook_command: &HookCommand,
+    tool_matchers: &ToolMatchers,
 ) {
     for event in selected_hooks {
-        config
-            .hooks
-            .entry(event)
-            .or_default()
-            .push(our_hook_config.clone());
+        let matchers = match tool_matchers.get(&event) {
+            Some(matchers) if !matchers.is_empty() => matchers.clone(),
+            _ => vec![String::new()],
+        };
+        let event_hooks = config.hooks.entry(event).or_default();
+        for matcher in matchers {
+            event_hooks.push(create_our_hook_config(hook_command, matcher));
+        }
     }
 }
 
@@ -375,24 +743,528 @@ fn cleanup_empty_hook_entries(config: &mut ClaudeConfiguration) {
 
 fn with_selected_notification_hooks(
     mut config: ClaudeConfiguration,
-    command: String,
+    hook_command: HookCommand,
     selected_hooks: Vec<HookEventName>,
+    tool_matchers: &ToolMatchers,
 ) -> ClaudeConfiguration {
-    let our_hook_config = create_our_hook_config(command);
+    remove_our_notification_hooks(&mut config);
+    add_hooks_to_selected_events(&mut config, selected_hooks, &hook_command, tool_matchers);
+    cleanup_empty_hook_entries(&mut config);
+
+    config
+}
+
+/// The settings files `anot uninstall` cleans up: the user's, in each directory Claude Code
+/// may use, and this project's shared and local ones. Managed settings need admin rights and
+/// are left alone.
+pub fn uninstall_candidates() -> Vec<PathBuf> {
+    settings_candidates(std::env::consts::OS, &ClaudeEnv::current(), Path::exists)
+        .into_iter()
+        .filter(|candidate| candidate.location != SettingsLocation::Managed)
+        .map(|candidate| candidate.path)
+        .collect()
+}
+
+/// The settings file at `path` before and after removing every anot hook, as deselecting all
+/// events in `anot init claude` would. Everything else in it is kept. `None` when it has no
+/// anot hooks.
+pub fn without_our_hooks(path: &PathBuf) -> Result<Option<(String, String)>, Error> {
+    let (original, mut config) = read_config(path)?;
+    if get_currently_configured_hooks(&config).is_empty() {
+        return Ok(None);
+    }
 
     remove_our_notification_hooks(&mut config);
-    add_hooks_to_selected_events(&mut config, selected_hooks, our_hook_config);
     cleanup_empty_hook_entries(&mut config);
+    let updated = serialize_config(&config)?;
+    Ok(Some((original, updated)))
+}
 
+fn serialize_config(config: &ClaudeConfiguration) -> Result<String, Error> {
     config
+        .to_value()
+        .and_then(|value| Ok(serde_json::to_string_pretty(&value)?))
+        .context("Failed to serialize the configuration")
 }
 
-#[instrument]
-fn write_config(path: &PathBuf, config: &ClaudeConfiguration) -> Result<(), Error> {
-    let new_config = serde_json::to_string_pretty(config)
-        .or(Err(Error::msg("Failed to serialize the configuration")))?;
-    std::fs::write(path, new_config)
-        .or(Err(Error::msg("Failed to write the configuration file")))?;
-    info!(path = %path.display(), "wrote Claude settings");
-    Ok(())
+/// Shows the changes from `original` and writes `config` once they're confirmed. Returns
+/// whether the file was written.
+#[instrument(skip(original, config))]
+fn write_config(
+    path: &PathBuf,
+    original: &str,
+    config: &ClaudeConfiguration,
+    dry_run: bool,
+) -> Result<bool, Error> {
+    let new_config = serialize_config(config)?;
+    let written = write_with_preview(path, original, &new_config, dry_run)?;
+    if written {
+        info!(path = %path.display(), "wrote Claude settings");
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    const COMMAND: &str = "\"/usr/local/bin/anot\" claude";
+
+    fn configure(
+        config: ClaudeConfiguration,
+        selected_hooks: Vec<HookEventName>,
+        tool_matchers: &[(HookEventName, &[&str])],
+    ) -> Value {
+        let tool_matchers = tool_matchers
+            .iter()
+            .map(|(event, patterns)| {
+                (
+                    event.clone(),
+                    patterns.iter().map(|pattern| pattern.to_string()).collect(),
+                )
+            })
+            .collect();
+        let hook_command = HookCommand {
+            command: COMMAND.to_string(),
+            timeout: 10,
+        };
+        let config =
+            with_selected_notification_hooks(config, hook_command, selected_hooks, &tool_matchers);
+        config.to_value().unwrap()
+    }
+
+    fn our_entry(matcher: &str) -> Value {
+        json!({
+            "matcher": matcher,
+            "hooks": [{"type": "command", "command": COMMAND, "timeout": 10}]
+        })
+    }
+
+    #[test]
+    fn single_matcher_is_written() {
+        let settings = configure(
+            ClaudeConfiguration::default(),
+            vec![HookEventName::PreToolUse, HookEventName::Stop],
+            &[(HookEventName::PreToolUse, &["Bash"])],
+        );
+
+        assert_eq!(settings["hooks"]["PreToolUse"], json!([our_entry("Bash")]));
+        assert_eq!(settings["hooks"]["Stop"], json!([our_entry("")]));
+    }
+
+    #[test]
+    fn each_matcher_gets_its_own_entry() {
+        let settings = configure(
+            ClaudeConfiguration::default(),
+            vec![HookEventName::PostToolUse],
+            &[(HookEventName::PostToolUse, &["Write", "Edit", "mcp__.*"])],
+        );
+
+        assert_eq!(
+            settings["hooks"]["PostToolUse"],
+            json!([our_entry("Write"), our_entry("Edit"), our_entry("mcp__.*")])
+        );
+    }
+
+    #[test]
+    fn rerun_recognizes_and_replaces_matched_entries() {
+        let existing: ClaudeConfiguration = serde_json::from_value(json!({
+            "hooks": {
+                "PreToolUse": [
+                    our_entry("Bash"),
+                    our_entry("mcp__github__.*"),
+                    {"matcher": "Bash", "hooks": [{"type": "command", "command": "lint.sh", "timeout": null}]}
+                ]
+            },
+            "model": "opus"
+        }))
+        .unwrap();
+
+        assert_eq!(
+            current_matchers(&existing, &HookEventName::PreToolUse),
+            ["Bash", "mcp__github__.*"]
+        );
+        assert_eq!(
+            get_currently_configured_hooks(&existing),
+            [HookEventName::PreToolUse]
+        );
+
+        let settings = configure(
+            existing,
+            vec![HookEventName::PreToolUse],
+            &[(HookEventName::PreToolUse, &["Edit"])],
+        );
+        assert_eq!(
+            settings["hooks"]["PreToolUse"],
+            json!([
+                {"matcher": "Bash", "hooks": [{"type": "command", "command": "lint.sh", "timeout": null}]},
+                our_entry("Edit")
+            ])
+        );
+        assert_eq!(settings["model"], "opus");
+    }
+
+    #[test]
+    fn config_paths_with_spaces_are_quoted() {
+        let command = build_agent_command(
+            Path::new("/usr/local/bin/anot"),
+            Some(Path::new("/Users/me/My Configs/anot $work.json")),
+        );
+        assert_eq!(
+            command,
+            r#""/usr/local/bin/anot" --config "/Users/me/My Configs/anot \$work.json" claude"#
+        );
+        assert_eq!(
+            command_words(&command),
+            [
+                "/usr/local/bin/anot",
+                "--config",
+                "/Users/me/My Configs/anot $work.json",
+                "claude"
+            ]
+        );
+
+        assert_eq!(
+            build_agent_command(Path::new("/opt/my tools/anot"), None),
+            r#""/opt/my tools/anot" claude"#
+        );
+
+        let command = build_agent_command(Path::new(r"C:\Program Files\anot\anot.exe"), None);
+        assert_eq!(command, r#""C:\\Program Files\\anot\\anot.exe" claude"#);
+        assert_eq!(
+            command_words(&command),
+            [r"C:\Program Files\anot\anot.exe", "claude"]
+        );
+    }
+
+    #[test]
+    fn commands_with_extra_options_are_ours() {
+        let action = |command: &str| ActionConfiguration {
+            r#type: HookType::Command,
+            command: command.to_string(),
+            timeout: Some(10),
+            other: serde_json::Map::new(),
+        };
+
+        assert!(is_our_notification_action(&action(COMMAND)));
+        assert!(is_our_notification_action(&action(
+            r#""/usr/local/bin/anot" --config "/Users/me/My Configs/a.json" claude"#
+        )));
+        assert!(is_our_notification_action(&action("anot -d claude")));
+        assert!(is_our_notification_action(&action(
+            r#"'/opt/my tools/anot' claude"#
+        )));
+        assert!(!is_our_notification_action(&action("anot codex")));
+        assert!(!is_our_notification_action(&action(
+            "/home/claude/bin/lint.sh --config anot.json"
+        )));
+
+        let existing: ClaudeConfiguration = serde_json::from_value(json!({
+            "hooks": {"Stop": [{"matcher": "", "hooks": [{
+                "type": "command",
+                "command": "\"/old/anot\" --config \"/tmp/a b.json\" claude",
+                "timeout": 30
+            }]}]}
+        }))
+        .unwrap();
+        assert_eq!(current_timeout(&existing), Some(30));
+        let settings = configure(existing, vec![HookEventName::Stop], &[]);
+        assert_eq!(settings["hooks"]["Stop"], json!([our_entry("")]));
+    }
+
+    #[test]
+    fn timeouts_are_validated() {
+        assert!(validate_timeout(1).is_ok());
+        assert!(validate_timeout(600).is_ok());
+        assert!(validate_timeout(0).is_err());
+        assert!(validate_timeout(601).is_err());
+    }
+
+    #[test]
+    fn previews_adding_hooks_as_a_diff() {
+        let original = "{\n  \"model\": \"opus\"\n}";
+        let config: ClaudeConfiguration = serde_json::from_str(original).unwrap();
+        let config = with_selected_notification_hooks(
+            config,
+            HookCommand {
+                command: "anot claude".to_string(),
+                timeout: 10,
+            },
+            vec![HookEventName::Stop],
+            &ToolMatchers::new(),
+        );
+        let updated = serde_json::to_string_pretty(&config).unwrap();
+
+        assert_eq!(
+            crate::preview::unified_diff(original, &updated, "settings.json"),
+            r#"--- a/settings.json
++++ b/settings.json
+@@ -1,3 +1,17 @@
+ {
++  "hooks": {
++    "Stop": [
++      {
++        "matcher": "",
++        "hooks": [
++          {
++            "type": "command",
++            "command": "anot claude",
++            "timeout": 10
++          }
++        ]
++      }
++    ]
++  },
+   "model": "opus"
+ }
+"#
+        );
+    }
+
+    fn home_env(config_dir: Option<&str>) -> ClaudeEnv {
+        ClaudeEnv {
+            config_dir: config_dir.map(String::from),
+            xdg_config_home: None,
+            home: PathBuf::from("/home/me"),
+        }
+    }
+
+    /// The user settings offered when only the `existing` paths exist, with their labels.
+    fn user_settings(config_dir: Option<&str>, existing: &[&str]) -> Vec<(String, PathBuf, bool)> {
+        settings_candidates("linux", &home_env(config_dir), |path| {
+            existing.iter().any(|e| Path::new(e) == path)
+        })
+        .into_iter()
+        .filter(|candidate| candidate.location == SettingsLocation::User)
+        .map(|candidate| (candidate.label, candidate.path, candidate.exists))
+        .collect()
+    }
+
+    #[test]
+    fn claude_config_dir_moves_the_user_settings() {
+        assert_eq!(
+            user_settings(None, &[]),
+            [(
+                "User Settings".to_string(),
+                PathBuf::from("/home/me/.claude/settings.json"),
+                false
+            )]
+        );
+
+        // Offered even though it doesn't exist yet, so it can be created.
+        assert_eq!(
+            user_settings(Some("/srv/claude-work"), &[]),
+            [(
+                "User Settings (CLAUDE_CONFIG_DIR)".to_string(),
+                PathBuf::from("/srv/claude-work/settings.json"),
+                false
+            )]
+        );
+        assert_eq!(
+            user_settings(Some("~/claude-alt"), &[])[0].1,
+            PathBuf::from("/home/me/claude-alt/settings.json")
+        );
+        assert_eq!(user_settings(Some(""), &[]), user_settings(None, &[]));
+    }
+
+    #[test]
+    fn other_existing_claude_dirs_are_offered_after_the_active_one() {
+        let existing = [
+            "/home/me/.config/claude",
+            "/home/me/.claude",
+            "/home/me/.claude/settings.json",
+        ];
+        assert_eq!(
+            user_settings(None, &existing),
+            [
+                (
+                    "User Settings".to_string(),
+                    PathBuf::from("/home/me/.config/claude/settings.json"),
+                    false
+                ),
+                (
+                    "User Settings in ~/.claude".to_string(),
+                    PathBuf::from("/home/me/.claude/settings.json"),
+                    true
+                ),
+            ]
+        );
+        assert_eq!(user_settings(Some("/srv/claude-work"), &existing).len(), 3);
+    }
+
+    #[test]
+    fn windows_settings_paths_are_native() {
+        let home = PathBuf::from(r"C:\Users\me");
+        let env = ClaudeEnv {
+            home: home.clone(),
+            ..ClaudeEnv::default()
+        };
+        let paths: Vec<_> = settings_candidates("windows", &env, |_| false)
+            .into_iter()
+            .map(|candidate| candidate.path)
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                home.join(".claude").join("settings.json"),
+                Path::new(".claude").join("settings.json"),
+                Path::new(".claude").join("settings.local.json"),
+                PathBuf::from(r"C:\ProgramData\ClaudeCode\managed-settings.json"),
+            ]
+        );
+    }
+
+    #[test]
+    fn managed_settings_follow_the_os() {
+        let env = home_env(None);
+        let candidates = |os: &str| settings_candidates(os, &env, |_| false);
+        let managed = |os: &str| {
+            candidates(os)
+                .into_iter()
+                .find(|candidate| candidate.location == SettingsLocation::Managed)
+                .map(|candidate| candidate.path)
+        };
+
+        assert_eq!(
+            managed("macos"),
+            Some(PathBuf::from(
+                "/Library/Application Support/ClaudeCode/managed-settings.json"
+            ))
+        );
+        assert_eq!(
+            managed("linux"),
+            Some(PathBuf::from("/etc/claude-code/managed-settings.json"))
+        );
+        assert_eq!(
+            managed("windows"),
+            Some(PathBuf::from(
+                r"C:\ProgramData\ClaudeCode\managed-settings.json"
+            ))
+        );
+        assert_eq!(managed("plan9"), None);
+
+        let locations: Vec<_> = candidates("linux")
+            .into_iter()
+            .map(|candidate| candidate.location)
+            .collect();
+        assert_eq!(
+            locations,
+            [
+                SettingsLocation::User,
+                SettingsLocation::Project,
+                SettingsLocation::LocalProject,
+                SettingsLocation::Managed
+            ]
+        );
+    }
+
+    #[test]
+    fn recoverable_hook_shapes_are_read() {
+        let (config, warnings) = ClaudeConfiguration::parse(
+            r#"{
+                "hooks": {
+                    "Stop": [{"hooks": [{"type": "command", "command": "say done"}]}],
+                    "PreToolUse": [
+                        {"matcher": "Bash", "hooks": {"type": "command", "command": "lint"}}
+                    ],
+                    "Notification": {"matcher": "", "hooks": []}
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert!(warnings.is_empty(), "{warnings:?}");
+        assert_eq!(config.hooks[&HookEventName::Stop][0].matcher, "");
+        assert_eq!(config.hooks[&HookEventName::Stop][0].hooks[0].timeout, None);
+        assert_eq!(
+            config.hooks[&HookEventName::PreToolUse][0].hooks[0].command,
+            "lint"
+        );
+        assert_eq!(config.hooks[&HookEventName::Notification].len(), 1);
+    }
+
+    #[test]
+    fn unknown_action_keys_round_trip() {
+        let (config, _) = ClaudeConfiguration::parse(
+            r#"{
+                "hooks": {
+                    "Stop": [{"matcher": "", "hooks": [
+                        {"type": "command", "command": "say done", "async": true, "env": {"A": "1"}}
+                    ]}]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.to_value().unwrap()["hooks"]["Stop"][0]["hooks"][0],
+            json!({
+                "type": "command",
+                "command": "say done",
+                "timeout": null,
+                "async": true,
+                "env": {"A": "1"}
+            })
+        );
+    }
+
+    #[test]
+    fn unreadable_entries_are_kept_with_a_warning() {
+        let settings = r#"{
+            "model": "opus",
+            "hooks": {
+                "Stop": [
+                    {"matcher": "", "hooks": [{"type": "command", "command": "say done"}]},
+                    {"matcher": 42, "hooks": []},
+                    "not an entry"
+                ]
+            }
+        }"#;
+        let (config, warnings) = ClaudeConfiguration::parse(settings).unwrap();
+
+        assert_eq!(warnings.len(), 2);
+        assert!(
+            warnings[0].starts_with("hooks.Stop[1]: "),
+            "{}",
+            warnings[0]
+        );
+        assert!(
+            warnings[1].starts_with("hooks.Stop[2]: "),
+            "{}",
+            warnings[1]
+        );
+        assert_eq!(config.hooks[&HookEventName::Stop].len(), 1);
+
+        let written = configure(config, vec![HookEventName::Stop], &[]);
+        assert_eq!(written["model"], "opus");
+        assert_eq!(
+            written["hooks"]["Stop"],
+            json!([
+                {"matcher": "", "hooks": [{"type": "command", "command": "say done", "timeout": null}]},
+                our_entry(""),
+                {"matcher": 42, "hooks": []},
+                "not an entry"
+            ])
+        );
+    }
+
+    #[test]
+    fn invalid_settings_still_fail() {
+        let path = std::env::temp_dir().join(format!(
+            "anot-claude-init-{}-invalid-settings.json",
+            std::process::id()
+        ));
+        for settings in [r#"{"hooks": []}"#, r#"["hooks"]"#, "{\"hooks\": {"] {
+            std::fs::write(&path, settings).unwrap();
+            let error = format!("{:#}", read_config(&path).unwrap_err());
+            assert!(
+                error.starts_with(&format!("Invalid config {}: ", path.display())),
+                "{error}"
+            );
+            assert!(!error.contains(settings), "the file isn't dumped: {error}");
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -1,265 +1,1964 @@
-use anyhow::Error;
-#[cfg(not(target_os = "macos"))]
-use notify_rust::Notification;
+use std::{
+    io::{self, Write},
+    path::Path,
+    time::Duration,
+};
+
+use anyhow::{Context, Error};
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+use notify_rust::{Hint, Notification};
+use regex::Regex;
 use tracing::{debug, error, info, instrument, warn};
 
+#[cfg(all(feature = "desktop", unix))]
+use crate::actions;
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+use crate::grouping;
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+use crate::pretend;
+#[cfg(feature = "desktop")]
+use crate::processors::claude::icon;
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+use crate::processors::icon::LARGEST;
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+use crate::processors::icon::icon_or_theme_name;
 use crate::{
-    configuration::Config,
+    configuration::{
+        Config, DeliveryMode, EmitOutput, GuardDecision, NotificationCategory,
+        NotificationSettings, PostToolUseMode, SessionStartMode, Urgency,
+        project::with_project_overlay,
+    },
+    delivery::{self, detached, dry_run, receipts, sound},
+    events::{self, Decision},
+    forward,
+    i18n::{interpolate, t},
+    logging, mute, privacy,
     processors::claude::{
-        icon::get_claude_icon_temp_path,
-        structs::{HookEventName, HookInput, HookOutput, SessionEndReason},
+        batching,
+        formatters::{ToolSummary, detect_tool_failure, summarize_tool_call},
+        guard::{self, Intercepted},
+        pairing, reminders, report_delivery, sampling,
+        structs::{
+            HookEventName, HookInput, HookOutput, PermissionMode, SessionEndReason,
+            SessionStartSource,
+        },
+        transcript::last_assistant_message,
+    },
+    sessions::{self, Activity},
+    text,
+    utils::{
+        catch_stdin, format_duration, format_size, project_name, read_with_idle_timeout,
+        truncate_chars, unix_now,
     },
 };
 
+/// A rendered Claude notification, ready to be shown.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct ClaudeNotification {
+    /// What the title says after "Claude Code · ", e.g. "Finished"
+    summary: String,
+    body: String,
+    /// Overrides `claude.sound` when set
+    sound: Option<bool>,
+    urgency: Urgency,
+}
+
+impl ClaudeNotification {
+    fn new(summary: impl Into<String>, body: impl Into<String>) -> Self {
+        ClaudeNotification {
+            summary: summary.into(),
+            body: body.into(),
+            sound: None,
+            urgency: Urgency::Normal,
+        }
+    }
+}
+
+#[cfg_attr(
+    any(not(feature = "desktop"), target_os = "macos"),
+    allow(unused_variables)
+)]
 fn create_claude_notification(
-    summary: &str,
-    body: &str,
-    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] config: &Config,
+    config: &Config,
+    hook_input: &HookInput,
+    event: &HookEventName,
+    title: &str,
+    message: &ClaudeNotification,
+    settings: &NotificationSettings,
 ) -> Result<(), Error> {
     debug!(
-        body_len = body.len(),
-        pretend = config.claude.pretend,
+        event = event.as_str(),
+        body_len = message.body.len(),
+        pretend = ?settings.pretend,
+        sound = settings.sound,
+        urgency = ?message.urgency,
         "preparing Claude notification"
     );
-    #[cfg(target_os = "macos")]
+    let played = sound::choose(settings, sound::CAN_PLAY);
+    #[cfg(all(feature = "desktop", target_os = "macos"))]
     {
+        use mac_notification_sys::MainButton;
         use mac_notification_sys::Notification;
         use mac_notification_sys::Sound;
         use mac_notification_sys::get_bundle_identifier;
         use mac_notification_sys::set_application;
 
+        let register_actions = actions::should_register(config);
+        let button_labels: Vec<&str> = config
+            .claude
+            .actions
+            .buttons
+            .iter()
+            .map(|b| b.label.as_str())
+            .collect();
         let mut notification = Notification::new();
 
-        let title = format!("Claude Code: {}", &summary);
+        notification.title(title).message(&message.body);
 
-        notification.title(&title).message(body).sound(true);
+        let icon_path = icon_path(event, settings).unwrap_or_default();
 
-        let icon_path = get_claude_icon_temp_path().unwrap_or_default();
+        let options = pretend::IdentityOptions {
+            app: "Claude",
+            pretend: settings.pretend,
+            bundle_id: config.claude.bundle_id.as_deref(),
+            fallback_bundle_id: config.claude.fallback_bundle_id.as_deref(),
+        };
+        match pretend::set_identity(
+            config,
+            &options,
+            || get_bundle_identifier("Claude"),
+            |bundle_id| set_application(bundle_id).is_ok(),
+        ) {
+            pretend::Identity::App(bundle_id) => {
+                debug!(bundle_id = %bundle_id, "using pretend app bundle for notification");
+            }
+            pretend::Identity::Fallback(bundle_id) => {
+                debug!(bundle_id = %bundle_id, "using fallback bundle for notification");
 
-        if let Some(bundle_id) = get_bundle_identifier("Claude")
-            && config.claude.pretend
-        {
-            set_application(&bundle_id).ok();
-            debug!(bundle_id = %bundle_id, "using pretend app bundle for notification");
-        } else {
-            set_application("com.apple.Terminal").ok();
-            debug!("using Terminal bundle for notification");
-
-            if let Some(s) = icon_path.to_str() {
-                notification.content_image(s);
-                debug!(icon = s, "attached icon to notification");
+                if let Some(s) = icon_path.to_str() {
+                    notification.content_image(s);
+                    debug!(icon = s, "attached icon to notification");
+                }
             }
         }
 
-        if config.claude.sound {
+        if played == sound::Sound::System {
             notification.sound(Sound::Default);
         }
 
-        notification.send()?;
+        if register_actions {
+            match button_labels.as_slice() {
+                [] => {}
+                [label] => {
+                    notification.main_button(MainButton::SingleAction(label));
+                }
+                labels => {
+                    notification.main_button(MainButton::DropdownActions("Actions", labels));
+                }
+            }
+            notification.wait_for_click(true);
+        }
+
+        let response = notification.send()?;
         debug!("sent macOS notification (Claude)");
+
+        if register_actions {
+            run_action(config, hook_input, response);
+        }
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(all(feature = "desktop", not(target_os = "macos")))]
     {
-        let mut notification = Notification::new();
-
-        let title = format!("Claude Code: {}", &summary);
-
-        notification.summary(&title).body(body);
-
-        if let Ok(p) = get_claude_icon_temp_path()
-            && let Some(s) = p.to_str()
+        let mut notification = linux_notification(config, event, title, message, settings);
+        #[cfg(unix)]
         {
-            notification.icon(s);
-            debug!(icon = s, "attached icon to notification");
-        }
+            let actions = actions::freedesktop_actions(config, || {
+                notify_rust::get_capabilities().unwrap_or_else(|e| {
+                    debug!(error = %e, "failed to query notification server capabilities");
+                    Vec::new()
+                })
+            });
+            for (key, label) in &actions {
+                notification.action(key, label);
+            }
 
+            let handle = grouping::show_in_group(
+                config,
+                &mut notification,
+                "claude",
+                Some(&hook_input.session_id),
+                tool_call(hook_input, event),
+            )?;
+            if !actions.is_empty() {
+                wait_for_action(config, hook_input, handle);
+            }
+        }
+        #[cfg(not(unix))]
         notification.show()?;
         debug!("sent Linux notification (Claude)");
     }
+    sound::queue(&played, config.claude.sound_volume);
     Ok(())
 }
 
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+fn linux_notification(
+    config: &Config,
+    event: &HookEventName,
+    title: &str,
+    message: &ClaudeNotification,
+    settings: &NotificationSettings,
+) -> Notification {
+    let mut notification = Notification::new();
+
+    notification
+        .summary(title)
+        .body(&message.body)
+        .urgency(linux_urgency(message.urgency));
+
+    // A `sound_file` clip plays instead of the system sound.
+    if sound::choose(settings, sound::CAN_PLAY) != sound::Sound::System {
+        notification.hint(Hint::SuppressSound(true));
+    }
+
+    if let Ok(icon) = linux_icon(config, event, settings) {
+        notification.icon(&icon);
+        debug!(icon = %icon, "attached icon to notification");
+    }
+
+    notification
+}
+
+/// Where the notification for `event` stands in the payload's tool call. Notifications flushed
+/// from a batch or reminders aren't about the payload's call.
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+fn tool_call<'a>(
+    hook_input: &'a HookInput,
+    event: &HookEventName,
+) -> Option<grouping::ToolCall<'a>> {
+    if *event != hook_input.hook_event_name {
+        return None;
+    }
+    let id = hook_input.tool_use_id.as_deref()?;
+    match event {
+        HookEventName::PreToolUse => Some(grouping::ToolCall::Started(id)),
+        HookEventName::PostToolUse => Some(grouping::ToolCall::Finished(id)),
+        _ => None,
+    }
+}
+
+/// Closes the PreToolUse notification of a tool call whose PostToolUse doesn't notify.
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+fn close_finished_tool_call(config: &Config, hook_input: &HookInput) {
+    let Some(id) = hook_input.tool_use_id.as_deref() else {
+        return;
+    };
+    if hook_input.hook_event_name != HookEventName::PostToolUse || config.dry_run {
+        return;
+    }
+    let title = format!(
+        "Claude Code · {}",
+        event_title(config, &hook_input.hook_event_name)
+    );
+    let body = t(
+        config,
+        "claude.post_tool_use",
+        &[("tool", &tool_name(hook_input, config))],
+    );
+    grouping::close_tool_call(config, "claude", &hook_input.session_id, id, &title, &body);
+}
+
+/// Runs the `claude.actions` command for a click or button press on a macOS notification.
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+fn run_action(
+    config: &Config,
+    hook_input: &HookInput,
+    response: mac_notification_sys::NotificationResponse,
+) {
+    use mac_notification_sys::NotificationResponse;
+
+    let key = match &response {
+        NotificationResponse::Click => Some(actions::CLICK.to_string()),
+        NotificationResponse::ActionButton(label) => config
+            .claude
+            .actions
+            .buttons
+            .iter()
+            .position(|b| &b.label == label)
+            .map(actions::button_key),
+        _ => None,
+    };
+
+    match key {
+        Some(key) => actions::run_action(config, hook_input, &key),
+        None => debug!("notification dismissed without an action"),
+    }
+}
+
+/// Waits up to `claude.actions.wait_secs` for a button press on a Linux notification and runs
+/// its command. The wait happens on another thread, since notify-rust's wait has no timeout.
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+fn wait_for_action(
+    config: &Config,
+    hook_input: &HookInput,
+    handle: notify_rust::NotificationHandle,
+) {
+    use std::{sync::mpsc, time::Duration};
+
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            let _ = sender.send(action.to_string());
+        })
+    });
+
+    let wait = Duration::from_secs(config.claude.actions.wait_secs);
+    match receiver.recv_timeout(wait) {
+        Ok(action) if action == actions::DISMISS_ALL => grouping::dismiss_all(config),
+        Ok(action) => actions::run_action(config, hook_input, &action),
+        Err(_) => debug!(
+            wait_secs = wait.as_secs(),
+            "no notification action before the timeout"
+        ),
+    }
+}
+
+/// The configured icon, or the largest built-in Claude icon for `event`, which macOS scales
+/// down itself.
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+fn icon_path(
+    event: &HookEventName,
+    settings: &NotificationSettings,
+) -> Result<std::path::PathBuf, Error> {
+    icon::get_icon(event, settings.icon.as_deref(), LARGEST)
+}
+
+/// The configured icon, the `claude.icon_name` theme icon, or the built-in Claude icon for
+/// `event` in `icon_size`.
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+fn linux_icon(
+    config: &Config,
+    event: &HookEventName,
+    settings: &NotificationSettings,
+) -> Result<String, Error> {
+    icon_or_theme_name(
+        settings.icon.as_deref(),
+        config.claude.icon_name.as_deref(),
+        icon::builtin_icon(event),
+        config.icon_size,
+    )
+}
+
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+fn linux_urgency(urgency: Urgency) -> notify_rust::Urgency {
+    match urgency {
+        Urgency::Low => notify_rust::Urgency::Low,
+        Urgency::Normal => notify_rust::Urgency::Normal,
+        Urgency::Critical => notify_rust::Urgency::Critical,
+    }
+}
+
+/// Reads the hook payload from `argument`, from the file an `@<path>` argument names, from
+/// `file`, or from stdin as Claude Code pipes it. Returns `None` when stdin stays empty for
+/// `timeout_secs` (`0` waits indefinitely).
+pub fn read_hook_input(
+    argument: Option<&str>,
+    file: Option<&Path>,
+    timeout_secs: u64,
+) -> Result<Option<String>, Error> {
+    if let Some(argument) = argument {
+        let Some(path) = argument.strip_prefix('@') else {
+            return Ok(Some(argument.to_string()));
+        };
+        let input = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read the hook input from @file argument {path}"))?;
+        return Ok(Some(input));
+    }
+    if let Some(file) = file {
+        let input = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read the hook input from {}", file.display()))?;
+        return Ok(Some(input));
+    }
+    if timeout_secs == 0 {
+        return Ok(Some(catch_stdin()));
+    }
+
+    io::stdout().flush()?;
+    let input = read_with_idle_timeout(io::stdin(), Duration::from_secs(timeout_secs))?;
+    if input.is_none() {
+        warn!(timeout_secs, "no Claude hook input on stdin");
+    }
+    Ok(input)
+}
+
+/// How much of an unparsable payload the `systemMessage` quotes.
+const PARSE_ERROR_INPUT_CHARS: usize = 200;
+
+/// `output` as `claude.emit_output` has `anot claude` write it to stdout, or `None` when
+/// nothing is written.
+pub fn emitted_output(config: &Config, output: &HookOutput) -> Result<Option<String>, Error> {
+    // Guard rules need `full`, which the config enforces when it loads.
+    let emitted = match config.claude.emit_output {
+        EmitOutput::Full => output,
+        EmitOutput::Minimal => &HookOutput {
+            r#continue: Some(true),
+            suppress_output: Some(true),
+            ..Default::default()
+        },
+        EmitOutput::None => return Ok(None),
+    };
+    Ok(Some(serde_json::to_string(emitted)?))
+}
+
+/// The hook output when no payload arrived on stdin.
+pub fn no_input_output(timeout_secs: u64) -> HookOutput {
+    HookOutput {
+        r#continue: Some(true),
+        suppress_output: Some(false),
+        system_message: Some(format!(
+            "anot received no hook input on stdin within {timeout_secs}s, so no notification \
+             was sent. Claude Code pipes the payload to hooks; to test by hand, use \
+             `anot claude --input <payload.json>`."
+        )),
+        ..Default::default()
+    }
+}
+
+/// Processes one hook payload and returns the output for Claude Code. Failures are logged and
+/// reported in the output's `systemMessage`, so the output is always safe to hand back.
+///
+/// ```no_run
+/// use agent_notifications::{configuration::Config, processors::claude::process_claude_input};
+///
+/// let input = r#"{"session_id": "abc", "transcript_path": "/tmp/t.jsonl", "hook_event_name": "Stop"}"#;
+/// let output = process_claude_input(input.to_string(), &Config::default());
+/// println!("{}", serde_json::to_string(&output).unwrap());
+/// ```
 #[instrument(skip(input, config), level = "debug")]
-pub fn process_claude_input(input: String, config: &Config) -> Result<(), Error> {
+pub fn process_claude_input(input: String, config: &Config) -> HookOutput {
     let hook_input = match serde_json::from_str::<HookInput>(&input) {
         Ok(hook_input) => hook_input,
         Err(error) => {
-            let output = HookOutput {
+            error!(error = ?error, "failed to parse Claude input JSON");
+            // The start is enough to recognize the payload; all of it could flood the transcript.
+            let input = truncate_chars(&input, PARSE_ERROR_INPUT_CHARS);
+            return HookOutput {
                 system_message: Some(format!(
                     "Failed to parse input JSON: {input:?}, error: {error:?}"
                 )),
                 suppress_output: Some(false),
                 ..Default::default()
             };
+        }
+    };
+    let _hook = logging::hook_span("claude", Some(&hook_input.session_id), None).entered();
+    events::identify(Some(&hook_input.session_id), None);
 
-            print!("{}", serde_json::to_string(&output)?);
+    // With `forward` in `replace` mode, the other machine does the rest, except for the guard.
+    let handed_off = forward::hand_off(config, "claude", &input);
+    if !handed_off {
+        reminders::observe(config, &hook_input, &input);
+        sessions::observe(
+            config,
+            "claude",
+            &hook_input.session_id,
+            hook_input.cwd.as_deref(),
+            hook_input.hook_event_name.as_str(),
+            session_activity(&hook_input, config),
+        );
+    }
 
-            error!(error = ?error, "failed to parse Claude input JSON");
-            return Err(Error::msg("Failed to parse input JSON"));
+    // Only what this payload sends is reported back.
+    receipts::take();
+
+    // A guarded call is answered even when the payload was handed off or notifications are
+    // held back, since the decision is what Claude Code waits on.
+    if hook_input.hook_event_name == HookEventName::PreToolUse {
+        let config = &with_project_overlay(config, hook_input.cwd.as_deref().map(Path::new));
+        if let Some(intercepted) = guard::check(config, &hook_input) {
+            info!(
+                tool = %intercepted.tool,
+                decision = ?intercepted.decision,
+                "guard intercepted a tool call"
+            );
+            if !handed_off && let Err(error) = send_interception(config, &hook_input, &intercepted)
+            {
+                error!(error = ?error, "failed to send the interception notification");
+            }
+            return report_delivery::attach(
+                config,
+                &hook_input.hook_event_name,
+                guard::hook_output(&intercepted),
+                &receipts::take(),
+            );
         }
+    }
+
+    if handed_off {
+        return HookOutput {
+            r#continue: Some(true),
+            suppress_output: Some(true),
+            ..Default::default()
+        };
+    }
+
+    let sent = if mute::intercept(
+        config,
+        "claude",
+        hook_input.hook_event_name.as_str(),
+        &input,
+    ) {
+        Ok(())
+    } else if config.delivery == DeliveryMode::Detached {
+        detached::spawn_or(config, "claude", &input, || {
+            send_notification(&hook_input, config)
+        })
+    } else {
+        send_notification(&hook_input, config)
     };
 
-    let output = match send_notification(&hook_input, config) {
-        Ok(_) => HookOutput {
+    let output = match sent {
+        Ok(()) => HookOutput {
             r#continue: Some(true),
             suppress_output: Some(true),
             ..Default::default()
         },
         Err(error) => {
-            let output = HookOutput {
+            error!(error = ?error, "failed to send Claude notification");
+            HookOutput {
                 r#continue: Some(true),
                 suppress_output: Some(true),
                 system_message: Some(format!("Failed to send notification: {error:?}")),
                 ..Default::default()
-            };
+            }
+        }
+    };
+    let receipts = receipts::take();
+    if receipts.is_empty() {
+        return output;
+    }
+    let config = &with_project_overlay(config, hook_input.cwd.as_deref().map(Path::new));
+    report_delivery::attach(config, &hook_input.hook_event_name, output, &receipts)
+}
 
-            print!(
-                "{}",
-                serde_json::to_string(&output).expect("Failed to serialize output")
-            );
+/// What the event counts towards the session digest: a finished tool call or a permission
+/// prompt.
+fn session_activity<'a>(hook_input: &'a HookInput, config: &Config) -> Activity<'a> {
+    match hook_input.hook_event_name {
+        HookEventName::PostToolUse => Activity {
+            tool: Some(hook_input.tool_name.as_deref().unwrap_or("unknown")),
+            ..Activity::default()
+        },
+        HookEventName::Notification => Activity {
+            permission_prompt: hook_input.message.as_deref().is_some_and(|message| {
+                classify_notification(message, config) == NotificationCategory::Permission
+            }),
+            ..Activity::default()
+        },
+        _ => Activity::default(),
+    }
+}
 
-            error!(error = ?error, "failed to send Claude notification");
-            return Err(error);
+/// Sends the notifications for a hook payload without writing hook output, for background
+/// delivery.
+pub fn deliver_claude_input(input: &str, config: &Config) -> Result<(), Error> {
+    let hook_input = serde_json::from_str::<HookInput>(input)?;
+    send_notification(&hook_input, config)
+}
+
+#[instrument(skip(hook_input, config), fields(event = ?hook_input.hook_event_name), level = "debug")]
+pub fn send_notification(hook_input: &HookInput, config: &Config) -> Result<(), Error> {
+    let config = &with_project_overlay(config, hook_input.cwd.as_deref().map(Path::new));
+    let event = hook_input.hook_event_name.as_str();
+    let pairing = pair_tool_call(hook_input, config);
+    // Rendering the message is most of the work left, so events that can't notify skip it.
+    if !config.claude.notifies_for(event) {
+        debug!(event, "not in claude.events");
+        dry_run::report_suppressed(
+            config,
+            "claude",
+            event,
+            Decision::Filtered,
+            "not in claude.events",
+        );
+        return Ok(());
+    }
+    let message = match pairing {
+        ToolPairing::Unpaired => map_event_to_message(hook_input, config),
+        ToolPairing::Started => None,
+        ToolPairing::Finished(duration) => paired_tool_message(hook_input, config, duration),
+    };
+
+    if message.is_some() && !sampled_in(hook_input, config, event) {
+        return Ok(());
+    }
+
+    // A dry run reports each event on its own rather than touching the batch spool.
+    if config.claude.batching.window_secs > 0 && !config.dry_run {
+        let is_tool_event = matches!(
+            hook_input.hook_event_name,
+            HookEventName::PreToolUse | HookEventName::PostToolUse
+        );
+        let path = batching::spool_path(config, &hook_input.session_id);
+
+        // Flush whatever is pending first so notifications keep their order.
+        match batching::take_pending(&path, unix_now(), config, !is_tool_event) {
+            Ok(pending) => {
+                for flushed in pending {
+                    deliver(
+                        config,
+                        hook_input,
+                        &flushed.event,
+                        None,
+                        &ClaudeNotification::new(flushed.summary, flushed.body),
+                    )?;
+                }
+            }
+            Err(e) => warn!(error = %e, "failed to read batch spool"),
         }
+
+        if is_tool_event && let Some(message) = &message {
+            let spooled = batching::SpooledEvent {
+                timestamp: unix_now(),
+                event: event.to_string(),
+                tool: hook_input
+                    .tool_name
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                summary: message.summary.clone(),
+                body: message.body.clone(),
+            };
+            match batching::append(&path, spooled) {
+                Ok(()) => {
+                    debug!("spooled tool notification for batching");
+                    return Ok(());
+                }
+                Err(e) => warn!(error = %e, "failed to spool tool notification; sending it now"),
+            }
+        }
+    }
+
+    match message {
+        Some(message) => deliver(
+            config,
+            hook_input,
+            event,
+            hook_input.tool_use_id.as_deref(),
+            &message,
+        )?,
+        None => {
+            #[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+            close_finished_tool_call(config, hook_input);
+            dry_run::report_suppressed(
+                config,
+                "claude",
+                event,
+                Decision::Filtered,
+                "filtered out by the config",
+            )
+        }
+    }
+
+    Ok(())
+}
+
+/// Sends the "still waiting" reminder for a `Notification` nobody answered after `idle_secs`.
+pub fn send_reminder(config: &Config, hook_input: &HookInput, idle_secs: u64) -> Result<(), Error> {
+    let config = &with_project_overlay(config, hook_input.cwd.as_deref().map(Path::new));
+    let body = t(
+        config,
+        "claude.reminder",
+        &[("idle", &format_duration(idle_secs))],
+    );
+
+    deliver(
+        config,
+        hook_input,
+        HookEventName::Notification.as_str(),
+        None,
+        &ClaudeNotification::new(t(config, "claude.reminder.summary", &[]), body),
+    )
+}
+
+/// Sends the urgent notification for a tool call `claude.guard` intercepted. It isn't subject
+/// to `claude.events`, sampling or batching.
+fn send_interception(
+    config: &Config,
+    hook_input: &HookInput,
+    intercepted: &Intercepted,
+) -> Result<(), Error> {
+    let key = match intercepted.decision {
+        GuardDecision::Ask => "claude.guard.ask",
+        GuardDecision::Deny => "claude.guard.deny",
     };
+    let detail = intercepted
+        .command
+        .clone()
+        .unwrap_or_else(|| intercepted.reason.clone());
+    let body = t(
+        config,
+        key,
+        &[("tool", &intercepted.tool), ("detail", &detail)],
+    );
 
-    print!(
-        "{}",
-        serde_json::to_string(&output).expect("Failed to serialize output")
+    let mut message = ClaudeNotification::new(
+        t(config, "claude.title.intercepted", &[]),
+        format_body(config, &body),
     );
-    debug!(
-        suppress_output = output.suppress_output.unwrap_or(false),
-        cont = output.r#continue.unwrap_or(false),
-        has_system_message = output
-            .system_message
-            .as_ref()
-            .map(|s| !s.is_empty())
-            .unwrap_or(false),
-        "emitted Claude hook output JSON"
+    message.sound = Some(true);
+    message.urgency = Urgency::Critical;
+    deliver(
+        config,
+        hook_input,
+        HookEventName::PreToolUse.as_str(),
+        hook_input.tool_use_id.as_deref(),
+        &message,
+    )
+}
+
+/// Sends `message` for `event`. `correlation_id` is the tool call it's about, when it's about
+/// exactly one.
+fn deliver(
+    config: &Config,
+    hook_input: &HookInput,
+    event: &str,
+    correlation_id: Option<&str>,
+    message: &ClaudeNotification,
+) -> Result<(), Error> {
+    // Notification categories are more specific than the event, so their sound wins.
+    let mut settings = config.claude.settings_for(event);
+    settings.sound = message.sound.unwrap_or(settings.sound);
+    let hook_event = HookEventName::from_name(event);
+    let title = delivery::replay_title(
+        config,
+        &notification_title(config, hook_input, event, message),
     );
 
-    Ok(())
+    delivery::deliver_correlated(
+        config,
+        "claude",
+        event,
+        correlation_id,
+        &title,
+        &message.body,
+        || create_claude_notification(config, hook_input, &hook_event, &title, message, &settings),
+    )
 }
 
-#[instrument(skip(hook_input, config), fields(event = ?hook_input.hook_event_name), level = "debug")]
-pub fn send_notification(hook_input: &HookInput, config: &Config) -> Result<(), Error> {
+/// The title for `message`: the `claude.titles` template for `event` with its placeholders
+/// filled in, or "Claude Code · " and the message's summary.
+fn notification_title(
+    config: &Config,
+    hook_input: &HookInput,
+    event: &str,
+    message: &ClaudeNotification,
+) -> String {
+    let Some(template) = config.claude.titles.get(event) else {
+        return format!("Claude Code · {}", message.summary);
+    };
+    let project = hook_input
+        .cwd
+        .as_deref()
+        .and_then(project_name)
+        .unwrap_or_default();
+
+    interpolate(
+        template,
+        &[
+            ("event", &event),
+            ("summary", &message.summary),
+            ("tool", &hook_input.tool_name.as_deref().unwrap_or_default()),
+            ("project", &project),
+            ("cwd", &hook_input.cwd.as_deref().unwrap_or_default()),
+            ("session_id", &hook_input.session_id),
+        ],
+    )
+}
+
+/// The built-in title summary for `event`, e.g. "Finished" for Stop.
+fn event_title(config: &Config, event: &HookEventName) -> String {
+    let key = match event {
+        HookEventName::PreToolUse | HookEventName::PostToolUse => "claude.title.tool",
+        HookEventName::Notification => "claude.title.notification",
+        HookEventName::UserPromptSubmit => "claude.title.prompt_submitted",
+        HookEventName::Stop => "claude.title.finished",
+        HookEventName::SubagentStop => "claude.title.subagent_finished",
+        HookEventName::PreCompact => "claude.title.compacting",
+        HookEventName::SessionStart => "claude.title.session_started",
+        HookEventName::SessionEnd => "claude.title.session_ended",
+        HookEventName::Unknown(name) => return name.clone(),
+    };
+    t(config, key, &[])
+}
+
+/// Whether `event` is shown with its `claude.sample` rate: only every Nth one per session is.
+/// Counting fails open, so a broken state file never hides notifications.
+fn sampled_in(hook_input: &HookInput, config: &Config, event: &str) -> bool {
+    let Some(rate) = config
+        .claude
+        .sample
+        .get(event)
+        .copied()
+        .filter(|rate| *rate > 1)
+    else {
+        return true;
+    };
+    if config.replay {
+        return true;
+    }
+
+    let path = sampling::sampling_path(config);
+    let key = sampling::counter_key(&hook_input.session_id, event);
+    // A dry run tells whether the next event would be shown, without counting it.
+    let count = if config.dry_run {
+        sampling::peek(&path, &key)
+    } else {
+        sampling::count(&path, &key, unix_now())
+    };
+    match count {
+        Ok(count) if sampling::fires(count, rate) => true,
+        Ok(count) => {
+            debug!(event, count, rate, "sampled away");
+            dry_run::report_suppressed(
+                config,
+                "claude",
+                event,
+                Decision::Filtered,
+                &format!("sampled away (#{count}, 1 in {rate} shown)"),
+            );
+            false
+        }
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to count the event for sampling");
+            true
+        }
+    }
+}
+
+/// Where a tool event stands with `claude.tool_pairing`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolPairing {
+    /// Not paired: pairing is off, this isn't a tool event, or the start wasn't recorded.
+    Unpaired,
+    /// A PreToolUse whose start was recorded.
+    Started,
+    /// A PostToolUse whose call ran for this many seconds.
+    Finished(u64),
+}
+
+fn pair_tool_call(hook_input: &HookInput, config: &Config) -> ToolPairing {
+    if !config.claude.tool_pairing || config.replay || config.dry_run {
+        return ToolPairing::Unpaired;
+    }
+
+    let path = pairing::pairing_path(config);
+    let key = pairing::call_key(hook_input);
     match hook_input.hook_event_name {
+        HookEventName::PreToolUse => match pairing::record_start(&path, &key, unix_now()) {
+            Ok(()) => {
+                debug!(key, "recorded tool call start");
+                ToolPairing::Started
+            }
+            Err(e) => {
+                warn!(error = %e, "failed to record tool call start");
+                ToolPairing::Unpaired
+            }
+        },
+        HookEventName::PostToolUse => match pairing::take_duration(&path, &key, unix_now()) {
+            Ok(Some(duration)) => ToolPairing::Finished(duration),
+            Ok(None) => {
+                debug!(key, "no recorded start for this tool call");
+                ToolPairing::Unpaired
+            }
+            Err(e) => {
+                warn!(error = %e, "failed to look up tool call start");
+                ToolPairing::Unpaired
+            }
+        },
+        _ => ToolPairing::Unpaired,
+    }
+}
+
+/// The PostToolUse notification for a paired call that ran `duration` seconds: "Bash finished
+/// in 42s" once it's past `min_tool_duration_secs`. Failures notify as usual.
+fn paired_tool_message(
+    hook_input: &HookInput,
+    config: &Config,
+    duration: u64,
+) -> Option<ClaudeNotification> {
+    let failed = hook_input
+        .tool_response
+        .as_ref()
+        .and_then(detect_tool_failure)
+        .is_some()
+        || matches!(tool_summary(hook_input), Some(ToolSummary::Exited { .. }));
+    if failed || config.claude.post_tool_use == PostToolUseMode::Off {
+        return map_event_to_message(hook_input, config);
+    }
+    if duration < config.claude.min_tool_duration_secs {
+        debug!(duration, "tool call was quick; not notifying");
+        return None;
+    }
+
+    let tool_name = tool_name(hook_input, config);
+    info!(tool = %tool_name, duration, "Claude: tool call finished");
+    let body = t(
+        config,
+        "claude.tool_finished",
+        &[
+            ("tool", &tool_name),
+            ("duration", &format_duration(duration)),
+        ],
+    );
+    Some(ClaudeNotification::new(
+        event_title(config, &hook_input.hook_event_name),
+        format_body(config, &privacy::redact(config, &body)),
+    ))
+}
+
+/// Builds the notification for a hook event, or `None` when the event should not produce a
+/// notification.
+fn map_event_to_message(hook_input: &HookInput, config: &Config) -> Option<ClaudeNotification> {
+    let summary = event_title(config, &hook_input.hook_event_name);
+
+    let body = match &hook_input.hook_event_name {
         HookEventName::PreToolUse => {
-            let tool_name = hook_input.tool_name.as_deref().unwrap_or("a unknown tool");
-            info!(tool = tool_name, "Claude: pre tool use");
+            let tool_name = tool_name(hook_input, config);
+            info!(tool = %tool_name, permission_mode = ?hook_input.permission_mode, "Claude: pre tool use");
 
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                &format!("The agent is trying to use {}", tool_name),
-                config,
-            )?
+            let key = match hook_input.permission_mode {
+                Some(PermissionMode::AcceptEdits) => "claude.pre_tool_use.accept_edits",
+                Some(PermissionMode::Plan) => "claude.pre_tool_use.plan",
+                _ => "claude.pre_tool_use",
+            };
+            t(config, key, &[("tool", &tool_name)])
         }
         HookEventName::PostToolUse => {
-            let tool_name = hook_input.tool_name.as_deref().unwrap_or("a unknown tool");
-            info!(tool = tool_name, "Claude: post tool use");
+            let tool_name = tool_name(hook_input, config);
+            let failure = hook_input
+                .tool_response
+                .as_ref()
+                .and_then(detect_tool_failure);
+            let summary = tool_summary(hook_input);
+            let exited = matches!(summary, Some(ToolSummary::Exited { .. }));
+            info!(
+                tool = %tool_name,
+                failed = failure.is_some() || exited,
+                "Claude: post tool use"
+            );
 
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                &format!("The agent has used {}", tool_name),
-                config,
-            )?
+            match (config.claude.post_tool_use, failure) {
+                (PostToolUseMode::Off, _) => {
+                    debug!(mode = ?config.claude.post_tool_use, "skipping PostToolUse notification");
+                    return None;
+                }
+                (PostToolUseMode::Failures, None) if !exited => {
+                    debug!(mode = ?config.claude.post_tool_use, "skipping PostToolUse notification");
+                    return None;
+                }
+                (_, Some(error)) if !exited => t(
+                    config,
+                    "claude.tool_failed",
+                    &[("tool", &tool_name), ("error", &error)],
+                ),
+                _ => match summary {
+                    Some(summary) => describe_tool_call(config, &tool_name, &summary),
+                    None => t(config, "claude.post_tool_use", &[("tool", &tool_name)]),
+                },
+            }
         }
         HookEventName::Notification => {
             let message = hook_input
                 .message
-                .as_deref()
-                .unwrap_or("The agent didn't provide any message.");
+                .clone()
+                .unwrap_or_else(|| t(config, "claude.no_message", &[]));
+            let category = classify_notification(&message, config);
+            let message = privacy::redact(config, &message);
             let preview: String = message.chars().take(120).collect();
-            info!("Claude: generic notification");
+            let settings = config.claude.notification_categories.resolve(category);
+            info!(category = ?category, "Claude: generic notification");
             debug!(
                 message_len = message.len(),
                 preview = preview,
                 "constructed notification message"
             );
 
-            create_claude_notification(hook_input.hook_event_name.as_str(), message, config)?
+            if !settings.enabled {
+                debug!(category = ?category, "notification category disabled");
+                return None;
+            }
+
+            let mut body = format_body(config, &message);
+            if let Some(others) = others_waiting(hook_input, config) {
+                body = format!("{body} {others}");
+            }
+            return Some(ClaudeNotification {
+                summary: settings.title_suffix.unwrap_or(summary),
+                body,
+                sound: settings.sound,
+                urgency: settings.urgency,
+            });
         }
         HookEventName::UserPromptSubmit => {
-            let prompt = hook_input.prompt.as_deref().unwrap_or("unknown");
-            let preview: String = prompt.chars().take(120).collect();
+            let prompt = hook_input
+                .prompt
+                .clone()
+                .unwrap_or_else(|| t(config, "claude.unknown", &[]));
             info!("Claude: user prompt submitted");
+            if config.privacy.redact_prompts {
+                debug!(prompt_len = prompt.len(), "user prompt redacted");
+                return Some(ClaudeNotification::new(
+                    summary,
+                    privacy::prompt_placeholder(config, &prompt),
+                ));
+            }
+
+            let prompt = privacy::redact(config, &prompt);
+            let preview: String = prompt.chars().take(120).collect();
             debug!(
                 prompt_len = prompt.len(),
                 preview = preview,
                 "user prompt preview"
             );
 
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                &format!("User prompt submitted: {}", prompt),
-                config,
-            )?
+            t(config, "claude.prompt_submitted", &[("prompt", &prompt)])
         }
         HookEventName::Stop => {
+            if is_repeated_stop(hook_input, config) {
+                return None;
+            }
             info!("Claude: session stop");
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                "The agent has stopped responding.",
-                config,
-            )?
+
+            stop_summary(hook_input, config).unwrap_or_else(|| t(config, "claude.stopped", &[]))
         }
         HookEventName::SubagentStop => {
+            if is_repeated_stop(hook_input, config) {
+                return None;
+            }
             info!("Claude: subagent stop");
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                "A subagent has stopped responding.",
-                config,
-            )?
+
+            t(config, "claude.subagent_stopped", &[])
         }
         HookEventName::PreCompact => {
             let trigger = hook_input
                 .trigger
                 .as_ref()
                 .map(|t| format!("{:?}", t))
-                .unwrap_or_else(|| "unknown".to_string());
+                .unwrap_or_else(|| t(config, "claude.unknown", &[]));
             info!("Claude: pre compact");
             debug!(trigger = trigger, "compaction trigger");
 
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                &format!(
-                    "The agent is about to compact the conversation. Trigger: {}",
-                    trigger
-                ),
-                config,
-            )?
+            t(config, "claude.pre_compact", &[("trigger", &trigger)])
         }
         HookEventName::SessionStart => {
-            info!("Claude: session start");
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                "The agent has started a new session.",
-                config,
-            )?
+            let source = hook_input
+                .source
+                .clone()
+                .unwrap_or(SessionStartSource::Startup);
+            info!(source = ?source, "Claude: session start");
+
+            match (config.claude.session_start, &source) {
+                (SessionStartMode::All, _)
+                | (SessionStartMode::StartupOnly, SessionStartSource::Startup) => {}
+                _ => {
+                    debug!(mode = ?config.claude.session_start, "skipping SessionStart notification");
+                    return None;
+                }
+            }
+            let key = match source {
+                SessionStartSource::Startup => "claude.session_start",
+                SessionStartSource::Resume => "claude.session_start.resume",
+                SessionStartSource::Clear => "claude.session_start.clear",
+            };
+            t(config, key, &[])
         }
         HookEventName::SessionEnd => {
             let reason = hook_input
                 .reason
                 .as_ref()
                 .map(|r| match r {
-                    SessionEndReason::Clear => "the user ran /clear.",
-                    SessionEndReason::PromptInputExit => {
-                        "the user exited while prompt input was visible."
-                    }
-                    SessionEndReason::Logout => "the user logged out.",
-                    SessionEndReason::Other => "the session ended for unspecified reason.",
+                    SessionEndReason::Clear => "claude.session_end.clear",
+                    SessionEndReason::PromptInputExit => "claude.session_end.prompt_input_exit",
+                    SessionEndReason::Logout => "claude.session_end.logout",
+                    SessionEndReason::Other => "claude.session_end.other",
                 })
-                .unwrap_or("unknown");
+                .unwrap_or("claude.unknown");
+            let reason = t(config, reason, &[]);
             info!("Claude: session end");
-            debug!(reason = reason, "session end reason");
+            debug!(reason = %reason, "session end reason");
 
-            create_claude_notification(
-                hook_input.hook_event_name.as_str(),
-                &format!("The agent has ended the session because {}", reason),
-                config,
-            )?
+            match session_digest(hook_input, config) {
+                Some(digest) => digest,
+                None => t(config, "claude.session_end", &[("reason", &reason)]),
+            }
+        }
+        HookEventName::Unknown(name) => {
+            if !config.claude.notify_unknown_events {
+                info!(event = %name, "Claude: unknown hook event; not notifying");
+                return None;
+            }
+            info!(event = %name, "Claude: unknown hook event");
+
+            t(config, "claude.unknown_event", &[("event", name)])
+        }
+    };
+
+    Some(ClaudeNotification::new(
+        summary,
+        format_body(config, &privacy::redact(config, &body)),
+    ))
+}
+
+/// The SessionEnd body with `claude.session_digest`, from the counters the session's events
+/// left in the session registry, which are reset afterwards. `None` without the option or
+/// when anot has no record of the session.
+fn session_digest(hook_input: &HookInput, config: &Config) -> Option<String> {
+    if !config.claude.session_digest {
+        return None;
+    }
+
+    let path = sessions::sessions_path(config);
+    // Replays and dry runs leave the counters for the real SessionEnd.
+    let keep = config.replay || config.dry_run;
+    let record = match sessions::take_counters(&path, "claude", &hook_input.session_id, keep) {
+        Ok(Some(record)) => record,
+        Ok(None) => {
+            debug!("no session record; sending the plain SessionEnd notification");
+            return None;
+        }
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to read the session counters");
+            return None;
         }
+    };
+    let reason = hook_input
+        .reason
+        .as_ref()
+        .and_then(|reason| serde_json::to_value(reason).ok())
+        .and_then(|reason| reason.as_str().map(String::from))
+        .unwrap_or_else(|| t(config, "claude.unknown", &[]));
+
+    Some(sessions::digest(config, &record, &reason))
+}
+
+/// "(2 other sessions also waiting)" with `claude.count_waiting`, when other sessions are
+/// waiting on the user as well. After the truncated message, so it's never cut off.
+fn others_waiting(hook_input: &HookInput, config: &Config) -> Option<String> {
+    if !config.claude.count_waiting {
+        return None;
     }
 
-    Ok(())
+    let path = sessions::sessions_path(config);
+    let count = match sessions::others_waiting(&path, &hook_input.session_id, unix_now()) {
+        Ok(count) => count,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to count the waiting sessions");
+            return None;
+        }
+    };
+    match count {
+        0 => None,
+        1 => Some(t(config, "claude.others_waiting.one", &[])),
+        count => Some(t(config, "claude.others_waiting", &[("count", &count)])),
+    }
+}
+
+/// What an Edit, MultiEdit, Write or Bash call did, when its payload says.
+fn tool_summary(hook_input: &HookInput) -> Option<ToolSummary> {
+    summarize_tool_call(
+        hook_input.tool_name.as_deref()?,
+        hook_input.tool_input.as_ref(),
+        hook_input.tool_response.as_ref(),
+        hook_input.cwd.as_deref(),
+    )
+}
+
+/// "Edited src/main.rs (+12/-3 lines)" and the like.
+fn describe_tool_call(config: &Config, tool_name: &str, summary: &ToolSummary) -> String {
+    match summary {
+        ToolSummary::Edited {
+            path,
+            added,
+            removed,
+        } => t(
+            config,
+            "claude.tool_edited",
+            &[("path", path), ("added", added), ("removed", removed)],
+        ),
+        ToolSummary::Wrote {
+            path,
+            bytes,
+            lines: Some(lines),
+        } => t(
+            config,
+            "claude.tool_wrote",
+            &[
+                ("path", path),
+                ("size", &format_size(*bytes)),
+                ("lines", lines),
+            ],
+        ),
+        ToolSummary::Wrote {
+            path,
+            bytes,
+            lines: None,
+        } => t(
+            config,
+            "claude.tool_wrote.size",
+            &[("path", path), ("size", &format_size(*bytes))],
+        ),
+        ToolSummary::Exited {
+            code,
+            stderr: Some(stderr),
+        } => t(
+            config,
+            "claude.tool_exited",
+            &[("tool", &tool_name), ("code", code), ("stderr", stderr)],
+        ),
+        ToolSummary::Exited { code, stderr: None } => t(
+            config,
+            "claude.tool_exited.no_output",
+            &[("tool", &tool_name), ("code", code)],
+        ),
+    }
+}
+
+fn tool_name(hook_input: &HookInput, config: &Config) -> String {
+    hook_input
+        .tool_name
+        .clone()
+        .unwrap_or_else(|| t(config, "claude.unknown_tool", &[]))
+}
+
+fn format_body(config: &Config, body: &str) -> String {
+    text::format_body(
+        body,
+        config.claude.max_body_length,
+        config.claude.strip_markdown,
+    )
+}
+
+/// Maps a Notification event message to its category using the configured patterns.
+/// Permission patterns are checked before idle ones.
+fn classify_notification(message: &str, config: &Config) -> NotificationCategory {
+    let categories = &config.claude.notification_categories;
+
+    for category in [NotificationCategory::Permission, NotificationCategory::Idle] {
+        let matched = categories
+            .resolve(category)
+            .patterns
+            .iter()
+            .any(|pattern| match Regex::new(pattern) {
+                Ok(re) => re.is_match(message),
+                Err(e) => {
+                    warn!(pattern = %pattern, error = %e, "invalid notification category pattern");
+                    false
+                }
+            });
+
+        if matched {
+            return category;
+        }
+    }
+
+    NotificationCategory::Other
+}
+
+/// Builds the "Claude finished: …" body from the transcript when `claude.stop_summary` is on.
+fn stop_summary(hook_input: &HookInput, config: &Config) -> Option<String> {
+    if !config.claude.stop_summary || config.privacy.redact_messages {
+        return None;
+    }
+
+    let message = last_assistant_message(Path::new(&hook_input.transcript_path));
+    debug!(
+        transcript = %hook_input.transcript_path,
+        found = message.is_some(),
+        "looked up last assistant message"
+    );
+
+    message.map(|m| {
+        let message = truncate_chars(&m, config.claude.stop_summary_length);
+        t(config, "claude.stop_summary", &[("message", &message)])
+    })
+}
+
+/// A Stop/SubagentStop that arrives while a Stop hook is already keeping Claude going is a
+/// continuation of the previous stop, not a new one.
+fn is_repeated_stop(hook_input: &HookInput, config: &Config) -> bool {
+    if hook_input.stop_hook_active != Some(true) || config.claude.notify_on_repeated_stop {
+        return false;
+    }
+
+    debug!(
+        session_id = %hook_input.session_id,
+        "suppressing repeated stop notification (stop_hook_active)"
+    );
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_config;
+
+    fn stop_input(stop_hook_active: Option<bool>) -> HookInput {
+        let mut value = serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "hook_event_name": "Stop",
+        });
+        if let Some(active) = stop_hook_active {
+            value["stop_hook_active"] = serde_json::Value::Bool(active);
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn repeated_stop_is_suppressed_by_default() {
+        let config = Config::default();
+        assert_eq!(map_event_to_message(&stop_input(Some(true)), &config), None);
+    }
+
+    #[test]
+    fn repeated_stop_notifies_when_enabled() {
+        let mut config = Config::default();
+        config.claude.notify_on_repeated_stop = true;
+        let message = map_event_to_message(&stop_input(Some(true)), &config).unwrap();
+        assert_eq!(message.summary, "Finished");
+        assert!(message.body.contains("stopped responding"));
+    }
+
+    #[test]
+    fn built_in_messages_follow_the_configured_language() {
+        let config = Config {
+            language: "zh-TW".to_string(),
+            ..Config::default()
+        };
+        let message = map_event_to_message(&stop_input(None), &config).unwrap();
+        assert_eq!(message.summary, "已完成");
+        assert_eq!(message.body, "代理程式已停止回應。");
+    }
+
+    #[test]
+    fn stop_without_stop_hook_active_notifies() {
+        let config = Config::default();
+        assert!(map_event_to_message(&stop_input(None), &config).is_some());
+        assert!(map_event_to_message(&stop_input(Some(false)), &config).is_some());
+    }
+
+    fn post_tool_use_input(tool_response: serde_json::Value) -> HookInput {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "hook_event_name": "PostToolUse",
+            "tool_name": "Bash",
+            "tool_response": tool_response,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn post_tool_use_failures_mode_only_notifies_on_failure() {
+        let mut config = Config::default();
+        config.claude.post_tool_use = PostToolUseMode::Failures;
+
+        let ok = post_tool_use_input(serde_json::json!({"stdout": "ok", "interrupted": false}));
+        assert_eq!(map_event_to_message(&ok, &config), None);
+
+        let failed = post_tool_use_input(serde_json::json!({"error": "exit code 1\nstack"}));
+        let message = map_event_to_message(&failed, &config).unwrap();
+        assert_eq!(message.body, "Bash failed: exit code 1");
+    }
+
+    #[test]
+    fn post_tool_use_summarizes_edits_and_failed_commands() {
+        let mut config = Config::default();
+        let mut edit = post_tool_use_input(serde_json::json!({
+            "filePath": "/repo/src/main.rs",
+            "structuredPatch": [{"lines": [" a", "-b", "+c", "+d"]}],
+        }));
+        edit.tool_name = Some("Edit".to_string());
+        edit.cwd = Some("/repo".to_string());
+        assert_eq!(
+            map_event_to_message(&edit, &config).unwrap().body,
+            "Edited src/main.rs (+2/-1 lines)"
+        );
+
+        config.claude.post_tool_use = PostToolUseMode::Failures;
+        assert_eq!(map_event_to_message(&edit, &config), None);
+        let failed = post_tool_use_input(serde_json::json!({
+            "stdout": "",
+            "stderr": "Compiling\nerror: could not compile\n",
+            "exit_code": 101,
+        }));
+        assert_eq!(
+            map_event_to_message(&failed, &config).unwrap().body,
+            "Bash exited with 101: error: could not compile"
+        );
+    }
+
+    #[test]
+    fn post_tool_use_off_mode_never_notifies() {
+        let mut config = Config::default();
+        config.claude.post_tool_use = PostToolUseMode::Off;
+
+        let failed = post_tool_use_input(serde_json::json!({"error": "boom"}));
+        assert_eq!(map_event_to_message(&failed, &config), None);
+    }
+
+    fn session_start_input(source: Option<&str>) -> HookInput {
+        let mut value = serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/t.jsonl",
+            "hook_event_name": "SessionStart",
+        });
+        if let Some(source) = source {
+            value["source"] = source.into();
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn session_start_notifies_by_source() {
+        let body = |mode: SessionStartMode, source: Option<&str>| {
+            let mut config = Config::default();
+            config.claude.session_start = mode;
+            map_event_to_message(&session_start_input(source), &config).map(|m| m.body)
+        };
+        let started = Some("The agent has started a new session.".to_string());
+        let resumed = Some("The agent has started a session (resumed).".to_string());
+        let cleared = Some("The agent has started a new session (after /clear).".to_string());
+
+        for (mode, expected) in [
+            (
+                SessionStartMode::All,
+                [started.clone(), started.clone(), resumed, cleared],
+            ),
+            (
+                SessionStartMode::StartupOnly,
+                [started.clone(), started, None, None],
+            ),
+            (SessionStartMode::Off, [None, None, None, None]),
+        ] {
+            let bodies = [None, Some("startup"), Some("resume"), Some("clear")]
+                .map(|source| body(mode, source));
+            assert_eq!(bodies, expected, "{mode:?}");
+        }
+        assert_eq!(
+            Config::default().claude.session_start,
+            SessionStartMode::StartupOnly
+        );
+    }
+
+    fn pre_tool_use_input(permission_mode: Option<&str>) -> HookInput {
+        let mut value = serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "cwd": "/tmp",
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Edit",
+            "tool_input": {"file_path": "/tmp/a.rs", "old_string": "a", "new_string": "b"},
+            "tool_use_id": "toolu_01ABC",
+        });
+        if let Some(mode) = permission_mode {
+            value["permission_mode"] = serde_json::Value::from(mode);
+        }
+        serde_json::from_value(value).unwrap()
+    }
+
+    #[test]
+    fn pre_tool_use_mentions_auto_approving_modes() {
+        let config = Config::default();
+        let body = |mode| {
+            map_event_to_message(&pre_tool_use_input(mode), &config)
+                .unwrap()
+                .body
+        };
+
+        assert_eq!(body(None), "The agent is trying to use Edit");
+        assert_eq!(body(Some("default")), "The agent is trying to use Edit");
+        assert_eq!(
+            body(Some("acceptEdits")),
+            "The agent is using Edit (auto-approved, accept edits mode)"
+        );
+        assert_eq!(
+            body(Some("plan")),
+            "The agent is using Edit (auto-approved, plan mode)"
+        );
+    }
+
+    #[test]
+    fn paired_tool_calls_notify_when_slow() {
+        let mut config = Config::default();
+        config.claude.tool_pairing = true;
+        let input = post_tool_use_input(serde_json::json!({"stdout": "ok", "interrupted": false}));
+
+        assert_eq!(paired_tool_message(&input, &config, 9), None);
+        let message = paired_tool_message(&input, &config, 42).unwrap();
+        assert_eq!(message.summary, "Tool");
+        assert_eq!(message.body, "Bash finished in 42s");
+
+        config.claude.min_tool_duration_secs = 0;
+        assert!(paired_tool_message(&input, &config, 0).is_some());
+    }
+
+    #[test]
+    fn paired_tool_failures_notify_regardless_of_duration() {
+        let mut config = Config::default();
+        config.claude.tool_pairing = true;
+        let failed = post_tool_use_input(serde_json::json!({"error": "exit code 1"}));
+
+        let message = paired_tool_message(&failed, &config, 1).unwrap();
+        assert_eq!(message.body, "Bash failed: exit code 1");
+
+        config.claude.post_tool_use = PostToolUseMode::Off;
+        assert_eq!(paired_tool_message(&failed, &config, 60), None);
+    }
+
+    #[test]
+    fn tool_pairing_tracks_starts_and_falls_back_when_unmatched() {
+        let mut config = temp_config("pairing", "send");
+        let pre = pre_tool_use_input(None);
+        let mut post = post_tool_use_input(serde_json::json!({"stdout": "ok"}));
+        post.tool_use_id = pre.tool_use_id.clone();
+
+        assert_eq!(pair_tool_call(&pre, &config), ToolPairing::Unpaired, "off");
+
+        config.claude.tool_pairing = true;
+        assert_eq!(
+            pair_tool_call(&post, &config),
+            ToolPairing::Unpaired,
+            "no recorded start"
+        );
+        assert_eq!(pair_tool_call(&pre, &config), ToolPairing::Started);
+        assert!(matches!(
+            pair_tool_call(&post, &config),
+            ToolPairing::Finished(duration) if duration < 5
+        ));
+        assert_eq!(pair_tool_call(&post, &config), ToolPairing::Unpaired);
+    }
+
+    #[test]
+    fn session_digest_replaces_the_session_end_body() {
+        let mut config = temp_config("digest", "session-end");
+        config.claude.session_digest = true;
+        let end: HookInput = serde_json::from_value(serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "hook_event_name": "SessionEnd",
+            "cwd": "/work/api",
+            "reason": "logout",
+        }))
+        .unwrap();
+
+        let plain = map_event_to_message(&end, &config).unwrap().body;
+        assert_eq!(
+            plain, "The agent has ended the session because the user logged out.",
+            "no record of the session"
+        );
+
+        let mut post = post_tool_use_input(serde_json::json!({"stdout": "ok"}));
+        post.session_id = end.session_id.clone();
+        sessions::upsert(
+            &sessions::sessions_path(&config),
+            "claude",
+            &post.session_id,
+            Some("/work/api"),
+            "PostToolUse",
+            session_activity(&post, &config),
+            1000,
+        )
+        .unwrap();
+        let digest = map_event_to_message(&end, &config).unwrap().body;
+        assert!(
+            digest.starts_with("Session in api ended (logout) — 1 tool call (Bash ×1)"),
+            "{digest}"
+        );
+    }
+
+    #[test]
+    fn notifications_count_the_other_sessions_waiting() {
+        let mut config = temp_config("waiting", "count");
+        config.claude.count_waiting = true;
+        let path = sessions::sessions_path(&config);
+        let now = unix_now();
+        let event = |session: &str, event: &str, at: u64| {
+            sessions::upsert(
+                &path,
+                "claude",
+                session,
+                None,
+                event,
+                Activity::default(),
+                at,
+            )
+            .unwrap();
+        };
+        // As `process_claude_input` does: the registry is updated, then the body is built.
+        let notify = |session: &str| {
+            event(session, "Notification", now);
+            let mut input = notification_input("Claude needs your permission to use Bash");
+            input.session_id = session.to_string();
+            map_event_to_message(&input, &config).unwrap().body
+        };
+
+        assert_eq!(notify("a"), "Claude needs your permission to use Bash");
+        assert_eq!(
+            notify("b"),
+            "Claude needs your permission to use Bash (1 other session also waiting)"
+        );
+        event("c", "PreToolUse", now);
+        assert_eq!(
+            notify("c"),
+            "Claude needs your permission to use Bash (2 other sessions also waiting)"
+        );
+
+        event("a", "UserPromptSubmit", now);
+        assert_eq!(
+            notify("b"),
+            "Claude needs your permission to use Bash (1 other session also waiting)",
+            "a was answered"
+        );
+
+        event(
+            "d",
+            "Notification",
+            now - sessions::WAITING_STALE_AFTER_SECS,
+        );
+        event("b", "Stop", now);
+        assert_eq!(
+            notify("c"),
+            "Claude needs your permission to use Bash",
+            "d is stale and b finished its turn instead"
+        );
+
+        config.claude.count_waiting = false;
+        assert_eq!(
+            map_event_to_message(
+                &notification_input("Claude is waiting for your input"),
+                &config
+            )
+            .unwrap()
+            .body,
+            "Claude is waiting for your input"
+        );
+    }
+
+    #[test]
+    fn repeated_subagent_stop_is_suppressed_by_default() {
+        let mut input = stop_input(Some(true));
+        input.hook_event_name = HookEventName::SubagentStop;
+        assert_eq!(map_event_to_message(&input, &Config::default()), None);
+    }
+
+    fn notification_input(message: &str) -> HookInput {
+        serde_json::from_value(serde_json::json!({
+            "session_id": "abc123",
+            "transcript_path": "/tmp/transcript.jsonl",
+            "hook_event_name": "Notification",
+            "message": message,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn classifies_default_notification_phrasings() {
+        let config = Config::default();
+        assert_eq!(
+            classify_notification("Claude needs your permission to use Bash", &config),
+            NotificationCategory::Permission
+        );
+        assert_eq!(
+            classify_notification("Claude is waiting for your input", &config),
+            NotificationCategory::Idle
+        );
+        assert_eq!(
+            classify_notification("Something else happened", &config),
+            NotificationCategory::Other
+        );
+    }
+
+    #[test]
+    fn permission_notification_is_urgent_with_sound() {
+        let message = map_event_to_message(
+            &notification_input("Claude needs your permission to use Bash"),
+            &Config::default(),
+        )
+        .unwrap();
+
+        assert_eq!(message.summary, "Permission needed");
+        assert_eq!(message.sound, Some(true));
+        assert_eq!(message.urgency, Urgency::Critical);
+    }
+
+    #[test]
+    fn user_patterns_override_defaults() {
+        let mut config = Config::default();
+        config.claude.notification_categories.idle.patterns = Some(vec!["(?i)^ping".to_string()]);
+        config.claude.notification_categories.idle.enabled = Some(false);
+
+        assert_eq!(
+            classify_notification("Ping from Claude", &config),
+            NotificationCategory::Idle
+        );
+        assert_eq!(
+            classify_notification("Claude is waiting for your input", &config),
+            NotificationCategory::Other
+        );
+        assert_eq!(
+            map_event_to_message(&notification_input("Ping from Claude"), &config),
+            None
+        );
+    }
+
+    #[test]
+    fn unmatched_notification_uses_other_category() {
+        let message = map_event_to_message(
+            &notification_input("Compaction finished"),
+            &Config::default(),
+        )
+        .unwrap();
+
+        assert_eq!(message.summary, "Notification");
+        assert_eq!(message.sound, None);
+        assert_eq!(message.urgency, Urgency::Normal);
+    }
+
+    #[test]
+    fn stop_summary_uses_truncated_transcript_message() {
+        let transcript =
+            std::env::temp_dir().join(format!("anot-stop-summary-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &transcript,
+            r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Refactored the parser and fixed tests."}]}}"#,
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.claude.stop_summary = true;
+        config.claude.stop_summary_length = 10;
+
+        let mut input = stop_input(None);
+        input.transcript_path = transcript.to_string_lossy().to_string();
+
+        let message = map_event_to_message(&input, &config).unwrap();
+        assert_eq!(message.body, "Claude finished: Refactored…");
+    }
+
+    #[test]
+    fn stop_summary_falls_back_when_transcript_is_missing() {
+        let mut config = Config::default();
+        config.claude.stop_summary = true;
+
+        let message = map_event_to_message(&stop_input(None), &config).unwrap();
+        assert_eq!(message.body, "The agent has stopped responding.");
+    }
+
+    fn unknown_event_input() -> HookInput {
+        serde_json::from_str(
+            r#"{"session_id":"abc123","transcript_path":"/tmp/t.jsonl","hook_event_name":"SomeFutureEvent"}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn redact_prompts_replaces_prompt_with_its_length() {
+        let input: HookInput = serde_json::from_str(
+            r#"{"session_id":"abc123","transcript_path":"/tmp/t.jsonl","hook_event_name":"UserPromptSubmit","prompt":"my token is sk-abc123"}"#,
+        )
+        .unwrap();
+        let mut config = Config::default();
+
+        config.privacy.redact_patterns = vec![r"sk-[a-z0-9]+".to_string()];
+        assert_eq!(
+            map_event_to_message(&input, &config).unwrap().body,
+            "User prompt submitted: my token is •••"
+        );
+
+        config.privacy.redact_prompts = true;
+        assert_eq!(
+            map_event_to_message(&input, &config).unwrap().body,
+            "User submitted a prompt (21 chars)"
+        );
+    }
+
+    #[test]
+    fn unknown_event_parses_and_does_not_notify_by_default() {
+        let input = unknown_event_input();
+        assert_eq!(
+            input.hook_event_name,
+            HookEventName::Unknown("SomeFutureEvent".to_string())
+        );
+        assert_eq!(map_event_to_message(&input, &Config::default()), None);
+    }
+
+    #[test]
+    fn unknown_event_notifies_when_enabled() {
+        let mut config = Config::default();
+        config.claude.notify_unknown_events = true;
+
+        let message = map_event_to_message(&unknown_event_input(), &config).unwrap();
+        assert_eq!(message.summary, "SomeFutureEvent");
+        assert_eq!(message.body, "Claude Code event: SomeFutureEvent");
+    }
+
+    fn title_of(input: &HookInput, config: &Config) -> String {
+        let message = map_event_to_message(input, config).unwrap();
+        notification_title(config, input, input.hook_event_name.as_str(), &message)
+    }
+
+    #[test]
+    fn titles_name_the_event() {
+        let config = Config::default();
+
+        assert_eq!(
+            title_of(&stop_input(None), &config),
+            "Claude Code · Finished"
+        );
+        assert_eq!(
+            title_of(&pre_tool_use_input(None), &config),
+            "Claude Code · Tool"
+        );
+        assert_eq!(
+            title_of(
+                &notification_input("Claude needs your permission to use Bash"),
+                &config
+            ),
+            "Claude Code · Permission needed"
+        );
+        assert_eq!(
+            title_of(&notification_input("Compaction finished"), &config),
+            "Claude Code · Notification"
+        );
+    }
+
+    #[test]
+    fn configured_titles_fill_placeholders() {
+        let mut config = Config::default();
+        config.claude.titles.insert(
+            "PreToolUse".to_string(),
+            "{tool} · {summary} ({event}, {session_id})".to_string(),
+        );
+
+        assert_eq!(
+            title_of(&pre_tool_use_input(None), &config),
+            "Edit · Tool (PreToolUse, abc123)"
+        );
+        assert_eq!(
+            title_of(&stop_input(None), &config),
+            "Claude Code · Finished",
+            "events without a title keep the built-in one"
+        );
+    }
+
+    fn emitted(mode: EmitOutput, output: &HookOutput) -> Option<String> {
+        let mut config = Config::default();
+        config.claude.emit_output = mode;
+        emitted_output(&config, output).unwrap()
+    }
+
+    #[test]
+    fn emit_output_modes_on_success() {
+        let delivered = HookOutput {
+            r#continue: Some(true),
+            suppress_output: Some(true),
+            ..Default::default()
+        };
+        let expected = r#"{"continue":true,"suppressOutput":true}"#;
+
+        assert_eq!(
+            emitted(EmitOutput::Full, &delivered).as_deref(),
+            Some(expected)
+        );
+        assert_eq!(
+            emitted(EmitOutput::Minimal, &delivered).as_deref(),
+            Some(expected)
+        );
+        assert_eq!(emitted(EmitOutput::None, &delivered), None);
+    }
+
+    #[test]
+    fn emit_output_modes_on_parse_failure() {
+        let failed = process_claude_input("not json".to_string(), &Config::default());
+
+        assert_eq!(
+            emitted(EmitOutput::Full, &failed).as_deref(),
+            Some(
+                r#"{"continue":true,"suppressOutput":false,"systemMessage":"Failed to parse input JSON: \"not json\", error: Error(\"expected ident\", line: 1, column: 2)"}"#
+            )
+        );
+        assert_eq!(
+            emitted(EmitOutput::Minimal, &failed).as_deref(),
+            Some(r#"{"continue":true,"suppressOutput":true}"#)
+        );
+        assert_eq!(emitted(EmitOutput::None, &failed), None);
+    }
+
+    #[test]
+    fn parse_errors_quote_only_the_start_of_the_payload() {
+        let payload = format!("{{\"session_id\": \"{}", "x".repeat(100_000));
+        let message = process_claude_input(payload, &Config::default())
+            .system_message
+            .unwrap();
+
+        assert!(message.len() < 400, "{} bytes", message.len());
+        assert!(message.starts_with(r#"Failed to parse input JSON: "{\"session_id\": \"xxx"#));
+        assert!(message.contains("…\", error: Error("));
+    }
+
+    #[test]
+    fn every_line_logged_for_a_session_names_it() {
+        let mut config = Config {
+            backends: vec![crate::configuration::Backend::Statusfile],
+            ..temp_config("claude", "span")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        let input = r#"{"session_id":"3f2a9c1b-88e0-4d1c","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+        let logs =
+            crate::logging::capture_logs(|| drop(process_claude_input(input.to_string(), &config)));
+
+        assert!(!logs.is_empty());
+        for line in logs.lines() {
+            assert!(
+                line.contains(
+                    r#"hook{agent="claude" session="3f2a9c1b" session_id="3f2a9c1b-88e0-4d1c"}"#
+                ),
+                "{line}"
+            );
+        }
+    }
+
+    #[cfg(all(feature = "desktop", not(target_os = "macos")))]
+    #[test]
+    fn linux_notification_suppresses_sound_when_disabled() {
+        let message = ClaudeNotification::new("Stop", "done");
+        let mut settings = Config::default().claude.settings_for("Stop");
+
+        settings.sound = false;
+        assert!(
+            linux_notification(
+                &Config::default(),
+                &HookEventName::Stop,
+                "Claude Code · Finished",
+                &message,
+                &settings
+            )
+            .hints
+            .contains(&Hint::SuppressSound(true))
+        );
+
+        settings.sound = true;
+        assert!(
+            !linux_notification(
+                &Config::default(),
+                &HookEventName::Stop,
+                "Claude Code · Finished",
+                &message,
+                &settings
+            )
+            .hints
+            .contains(&Hint::SuppressSound(true))
+        );
+    }
 }
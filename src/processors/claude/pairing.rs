@@ -0,0 +1,124 @@
+//! Matches each PostToolUse with the PreToolUse of the same call, so `claude.tool_pairing` can
+//! replace the two notifications with one saying how long the tool ran.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    configuration::Config,
+    processors::claude::structs::HookInput,
+    storage::{read_json_or_default, with_lock, write_atomic},
+};
+
+const PAIRING_FILE_NAME: &str = "tool-calls.json";
+
+/// Calls without a PostToolUse after this long (interrupted, or the hook was removed) are
+/// dropped.
+const STALE_AFTER_SECS: u64 = 24 * 60 * 60;
+
+/// Start times of tool calls still running, keyed by [`call_key`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct PendingCalls {
+    #[serde(default)]
+    started: BTreeMap<String, u64>,
+}
+
+pub fn pairing_path(config: &Config) -> PathBuf {
+    config.data_dir().join(PAIRING_FILE_NAME)
+}
+
+/// Identifies a tool call within its session: its `tool_use_id`, or the tool's name when Claude
+/// Code didn't send one.
+pub fn call_key(hook_input: &HookInput) -> String {
+    match (&hook_input.tool_use_id, &hook_input.tool_name) {
+        (Some(id), _) => format!("{}:{id}", hook_input.session_id),
+        (None, tool) => format!(
+            "{}:tool:{}",
+            hook_input.session_id,
+            tool.as_deref().unwrap_or_default()
+        ),
+    }
+}
+
+/// Records that the call `key` started at `now`, dropping stale calls.
+pub fn record_start(path: &Path, key: &str, now: u64) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut pending: PendingCalls = read_json_or_default(path)?;
+        pending
+            .started
+            .retain(|_, started| now.saturating_sub(*started) < STALE_AFTER_SECS);
+        pending.started.insert(key.to_string(), now);
+        write_atomic(path, serde_json::to_string(&pending)?.as_bytes())
+    })
+}
+
+/// Removes the call `key` and returns how long it ran, or `None` when its start wasn't
+/// recorded.
+pub fn take_duration(path: &Path, key: &str, now: u64) -> Result<Option<u64>, Error> {
+    with_lock(path, || {
+        let mut pending: PendingCalls = read_json_or_default(path)?;
+        let Some(started) = pending.started.remove(key) else {
+            return Ok(None);
+        };
+        write_atomic(path, serde_json::to_string(&pending)?.as_bytes())?;
+        Ok(Some(now.saturating_sub(started)))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("pairing", test_name).join(PAIRING_FILE_NAME)
+    }
+
+    #[test]
+    fn calls_pair_by_key() {
+        let path = temp_path("pair");
+        record_start(&path, "s:toolu_1", 1000).unwrap();
+        record_start(&path, "s:toolu_2", 1010).unwrap();
+
+        assert_eq!(take_duration(&path, "s:toolu_2", 1015).unwrap(), Some(5));
+        assert_eq!(take_duration(&path, "s:toolu_1", 1042).unwrap(), Some(42));
+        assert_eq!(
+            take_duration(&path, "s:toolu_1", 1050).unwrap(),
+            None,
+            "a call pairs once"
+        );
+    }
+
+    #[test]
+    fn stale_calls_are_dropped() {
+        let path = temp_path("stale");
+        record_start(&path, "s:old", 1000).unwrap();
+        record_start(&path, "s:new", 1000 + STALE_AFTER_SECS).unwrap();
+
+        assert_eq!(
+            take_duration(&path, "s:old", 2000 + STALE_AFTER_SECS).unwrap(),
+            None
+        );
+        assert_eq!(
+            take_duration(&path, "s:new", 1060 + STALE_AFTER_SECS).unwrap(),
+            Some(60)
+        );
+    }
+
+    #[test]
+    fn calls_without_an_id_are_keyed_on_the_tool() {
+        let mut input: HookInput = serde_json::from_str(
+            r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PreToolUse","tool_name":"Bash"}"#,
+        )
+        .unwrap();
+        assert_eq!(call_key(&input), "s:tool:Bash");
+
+        input.tool_use_id = Some("toolu_1".to_string());
+        assert_eq!(call_key(&input), "s:toolu_1");
+    }
+}
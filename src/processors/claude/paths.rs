@@ -0,0 +1,194 @@
+//! Where Claude Code keeps its user settings. `CLAUDE_CONFIG_DIR` wins when it's set, even
+//! before the directory exists. Otherwise it's the XDG location (`$XDG_CONFIG_HOME/claude`,
+//! `~/.config/claude` by default) when that exists, and the legacy `~/.claude` when not.
+
+use std::path::{Path, PathBuf};
+
+use crate::utils::expand_tilde_in;
+
+pub const CONFIG_DIR_ENV: &str = "CLAUDE_CONFIG_DIR";
+
+/// The environment the locations are resolved from. Tests build one rather than change the
+/// process environment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ClaudeEnv {
+    /// `CLAUDE_CONFIG_DIR`
+    pub config_dir: Option<String>,
+    /// `XDG_CONFIG_HOME`
+    pub xdg_config_home: Option<String>,
+    pub home: PathBuf,
+}
+
+impl ClaudeEnv {
+    pub fn current() -> Self {
+        ClaudeEnv {
+            config_dir: std::env::var(CONFIG_DIR_ENV).ok(),
+            xdg_config_home: std::env::var("XDG_CONFIG_HOME").ok(),
+            home: dirs::home_dir().unwrap_or_default(),
+        }
+    }
+
+    /// `value` as a directory: empty counts as unset, and a leading `~` is the home directory.
+    fn dir(&self, value: Option<&str>) -> Option<PathBuf> {
+        let value = value.filter(|value| !value.is_empty())?;
+        Some(expand_tilde_in(Path::new(value), Some(&self.home)))
+    }
+}
+
+/// Why a directory is a candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HomeSource {
+    EnvVar,
+    Xdg,
+    Legacy,
+}
+
+/// A directory Claude Code may keep its user settings in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ClaudeHome {
+    pub source: HomeSource,
+    pub dir: PathBuf,
+    pub exists: bool,
+}
+
+impl ClaudeHome {
+    /// Where the directory comes from, for display.
+    pub fn label(&self) -> &'static str {
+        match self.source {
+            HomeSource::EnvVar => CONFIG_DIR_ENV,
+            HomeSource::Xdg => "XDG config dir",
+            HomeSource::Legacy => "~/.claude",
+        }
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.dir.join("settings.json")
+    }
+}
+
+/// The candidate directories in order of precedence, each once. `exists` checks a directory;
+/// it's `Path::exists` outside tests.
+pub fn candidates(env: &ClaudeEnv, exists: impl Fn(&Path) -> bool) -> Vec<ClaudeHome> {
+    let xdg_dir = env
+        .dir(env.xdg_config_home.as_deref())
+        .unwrap_or_else(|| env.home.join(".config"))
+        .join("claude");
+    let dirs = [
+        (HomeSource::EnvVar, env.dir(env.config_dir.as_deref())),
+        (HomeSource::Xdg, Some(xdg_dir)),
+        (HomeSource::Legacy, Some(env.home.join(".claude"))),
+    ];
+
+    let mut homes: Vec<ClaudeHome> = Vec::new();
+    for (source, dir) in dirs {
+        if let Some(dir) = dir
+            && !homes.iter().any(|home| home.dir == dir)
+        {
+            homes.push(ClaudeHome {
+                exists: exists(&dir),
+                source,
+                dir,
+            });
+        }
+    }
+    homes
+}
+
+/// The candidate Claude Code uses: `CLAUDE_CONFIG_DIR` when set, otherwise the first one that
+/// exists, otherwise `~/.claude`.
+pub fn active(homes: &[ClaudeHome]) -> Option<&ClaudeHome> {
+    homes
+        .iter()
+        .find(|home| home.source == HomeSource::EnvVar)
+        .or_else(|| homes.iter().find(|home| home.exists))
+        .or_else(|| homes.iter().find(|home| home.source == HomeSource::Legacy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(config_dir: Option<&str>, xdg_config_home: Option<&str>) -> ClaudeEnv {
+        ClaudeEnv {
+            config_dir: config_dir.map(String::from),
+            xdg_config_home: xdg_config_home.map(String::from),
+            home: PathBuf::from("/home/me"),
+        }
+    }
+
+    /// The candidates and the active one when only `existing` directories exist.
+    fn resolve(env: &ClaudeEnv, existing: &[&str]) -> (Vec<(HomeSource, PathBuf, bool)>, PathBuf) {
+        let homes = candidates(env, |dir| existing.iter().any(|e| Path::new(e) == dir));
+        let active = active(&homes).unwrap().dir.clone();
+        let homes = homes
+            .into_iter()
+            .map(|home| (home.source, home.dir, home.exists))
+            .collect();
+        (homes, active)
+    }
+
+    #[test]
+    fn defaults_to_the_legacy_dir() {
+        let (homes, active) = resolve(&env(None, None), &[]);
+        assert_eq!(
+            homes,
+            [
+                (
+                    HomeSource::Xdg,
+                    PathBuf::from("/home/me/.config/claude"),
+                    false
+                ),
+                (HomeSource::Legacy, PathBuf::from("/home/me/.claude"), false),
+            ]
+        );
+        assert_eq!(active, PathBuf::from("/home/me/.claude"));
+
+        let (_, active) = resolve(&env(None, None), &["/home/me/.claude"]);
+        assert_eq!(active, PathBuf::from("/home/me/.claude"));
+    }
+
+    #[test]
+    fn an_existing_xdg_dir_wins_over_the_legacy_one() {
+        let existing = ["/home/me/.config/claude", "/home/me/.claude"];
+        let (_, active) = resolve(&env(None, None), &existing);
+        assert_eq!(active, PathBuf::from("/home/me/.config/claude"));
+
+        let (homes, active) = resolve(&env(None, Some("/xdg")), &["/xdg/claude"]);
+        assert_eq!(
+            homes[0],
+            (HomeSource::Xdg, PathBuf::from("/xdg/claude"), true)
+        );
+        assert_eq!(active, PathBuf::from("/xdg/claude"));
+    }
+
+    #[test]
+    fn claude_config_dir_wins_even_before_it_exists() {
+        let existing = ["/home/me/.config/claude", "/home/me/.claude"];
+        let (homes, active) = resolve(&env(Some("/srv/claude-work"), None), &existing);
+        assert_eq!(
+            homes[0],
+            (HomeSource::EnvVar, PathBuf::from("/srv/claude-work"), false)
+        );
+        assert_eq!(homes.len(), 3);
+        assert_eq!(active, PathBuf::from("/srv/claude-work"));
+
+        let (_, active) = resolve(&env(Some("~/claude-alt"), None), &[]);
+        assert_eq!(active, PathBuf::from("/home/me/claude-alt"));
+    }
+
+    #[test]
+    fn empty_or_repeated_dirs_are_skipped() {
+        let (homes, _) = resolve(&env(Some(""), Some("")), &[]);
+        assert_eq!(homes, resolve(&env(None, None), &[]).0);
+
+        let (homes, active) = resolve(&env(Some("/home/me/.claude"), None), &[]);
+        assert_eq!(
+            homes
+                .iter()
+                .map(|(source, _, _)| *source)
+                .collect::<Vec<_>>(),
+            [HomeSource::EnvVar, HomeSource::Xdg]
+        );
+        assert_eq!(active, PathBuf::from("/home/me/.claude"));
+    }
+}
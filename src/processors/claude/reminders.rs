@@ -0,0 +1,342 @@
+//! Follow-up notifications for Claude prompts nobody answered: a `Notification` starts a
+//! reminder chain for its session, and any activity in the session cancels it.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::Config,
+    delivery::detached,
+    mute,
+    processors::claude::{
+        input_and_output::send_reminder,
+        structs::{HookEventName, HookInput},
+    },
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::unix_now,
+};
+
+const REMINDERS_FILE_NAME: &str = "reminders.json";
+const SCHEDULER_LOCK_FILE_NAME: &str = ".reminders.scheduler.lock";
+
+/// Longest the scheduler sleeps before looking at the state again, so cancelled chains don't
+/// keep it alive long.
+const MAX_SLEEP_SECS: u64 = 30;
+
+/// A session waiting for the user since its last `Notification`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+struct Waiting {
+    since: u64,
+    /// Reminders already sent.
+    sent: u32,
+    due: u64,
+    /// The raw `Notification` payload, for the reminder's actions and grouping.
+    input: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Reminders {
+    #[serde(default)]
+    sessions: BTreeMap<String, Waiting>,
+}
+
+/// A reminder to send now.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DueReminder {
+    pub input: String,
+    pub idle_secs: u64,
+}
+
+/// What a hook event means for its session's reminder chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transition {
+    Wait,
+    Cancel,
+    Ignore,
+}
+
+fn transition(event: &HookEventName) -> Transition {
+    match event {
+        HookEventName::Notification => Transition::Wait,
+        HookEventName::UserPromptSubmit
+        | HookEventName::PreToolUse
+        | HookEventName::PostToolUse
+        | HookEventName::SessionEnd => Transition::Cancel,
+        _ => Transition::Ignore,
+    }
+}
+
+pub fn reminders_path(config: &Config) -> PathBuf {
+    config.data_dir().join(REMINDERS_FILE_NAME)
+}
+
+/// Seconds from the `sent`th reminder to the next: `after_secs`, doubling each time.
+fn backoff(after_secs: u64, sent: u32) -> u64 {
+    after_secs.saturating_mul(1 << sent.min(16))
+}
+
+/// Starts, restarts or cancels the session's reminder chain for a hook event. Returns whether
+/// a reminder is now pending for it.
+fn record(
+    path: &Path,
+    hook_input: &HookInput,
+    input: &str,
+    now: u64,
+    after_secs: u64,
+) -> Result<bool, Error> {
+    let transition = transition(&hook_input.hook_event_name);
+    if transition == Transition::Ignore {
+        return Ok(false);
+    }
+
+    with_lock(path, || {
+        let mut reminders: Reminders = read_json_or_default(path)?;
+        let session = hook_input.session_id.clone();
+
+        if transition == Transition::Wait {
+            reminders.sessions.insert(
+                session,
+                Waiting {
+                    since: now,
+                    sent: 0,
+                    due: now + after_secs,
+                    input: input.to_string(),
+                },
+            );
+        } else if reminders.sessions.remove(&session).is_some() {
+            debug!(session, "activity cancelled the reminder chain");
+        } else {
+            return Ok(false);
+        }
+
+        write_atomic(path, serde_json::to_string(&reminders)?.as_bytes())?;
+        Ok(transition == Transition::Wait)
+    })
+}
+
+/// Takes the reminders due at `now`, scheduling each chain's next one or ending it after
+/// `max_count` reminders.
+fn take_due(
+    path: &Path,
+    now: u64,
+    after_secs: u64,
+    max_count: u32,
+) -> Result<Vec<DueReminder>, Error> {
+    with_lock(path, || {
+        let mut reminders: Reminders = read_json_or_default(path)?;
+        let mut due = Vec::new();
+
+        reminders.sessions.retain(|_, waiting| {
+            if waiting.due > now {
+                return true;
+            }
+            due.push(DueReminder {
+                input: waiting.input.clone(),
+                idle_secs: now.saturating_sub(waiting.since),
+            });
+            waiting.sent += 1;
+            waiting.due = now + backoff(after_secs, waiting.sent);
+            waiting.sent < max_count
+        });
+
+        if !due.is_empty() {
+            write_atomic(path, serde_json::to_string(&reminders)?.as_bytes())?;
+        }
+        Ok(due)
+    })
+}
+
+/// When the next reminder is due, if any chain is running.
+fn next_due(path: &Path) -> Result<Option<u64>, Error> {
+    let reminders: Reminders = read_json_or_default(path)?;
+    Ok(reminders.sessions.values().map(|waiting| waiting.due).min())
+}
+
+/// Updates the reminder chains for a hook invocation and sends the reminders that are due.
+/// A new chain starts the background scheduler, so reminders arrive without further hooks.
+pub fn observe(config: &Config, hook_input: &HookInput, input: &str) {
+    let after_secs = config.claude.reminder_after_secs;
+    if after_secs == 0 || config.replay || config.dry_run {
+        return;
+    }
+
+    let path = reminders_path(config);
+    match record(&path, hook_input, input, unix_now(), after_secs) {
+        Ok(true) => {
+            if let Err(e) = spawn_scheduler(config) {
+                warn!(error = %e, "failed to start the reminder scheduler");
+            }
+        }
+        Ok(false) => {}
+        Err(e) => warn!(error = %e, "failed to update reminders"),
+    }
+
+    send_due(config);
+}
+
+/// Sends every reminder that's due. Reminders due while muted are skipped.
+pub fn send_due(config: &Config) {
+    let now = unix_now();
+    let due = match take_due(
+        &reminders_path(config),
+        now,
+        config.claude.reminder_after_secs,
+        config.claude.reminder_max_count,
+    ) {
+        Ok(due) => due,
+        Err(e) => {
+            warn!(error = %e, "failed to read reminders");
+            return;
+        }
+    };
+    if due.is_empty() {
+        return;
+    }
+    if let Ok(Some(_)) = mute::active_mute(config, now) {
+        debug!(count = due.len(), "muted; skipping reminders");
+        return;
+    }
+
+    for reminder in due {
+        let sent = serde_json::from_str::<HookInput>(&reminder.input)
+            .map_err(Error::from)
+            .and_then(|hook_input| send_reminder(config, &hook_input, reminder.idle_secs));
+        if let Err(e) = sent {
+            warn!(error = %format_args!("{e:#}"), "failed to send reminder");
+        }
+    }
+}
+
+fn spawn_scheduler(config: &Config) -> Result<(), Error> {
+    let mut args = detached::config_args(config);
+    args.push("--deliver-reminders".into());
+    let pid = detached::spawn_background(args)?;
+    debug!(pid, "started reminder scheduler");
+    Ok(())
+}
+
+/// Runs `anot --deliver-reminders`: sends reminders as they fall due until no chain is left.
+/// Only one scheduler runs at a time; others exit right away.
+pub fn run_scheduler(config: &Config) -> Result<(), Error> {
+    let path = reminders_path(config);
+    fs::create_dir_all(config.data_dir())?;
+    let lock = File::options()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(config.data_dir().join(SCHEDULER_LOCK_FILE_NAME))?;
+    if lock.try_lock().is_err() {
+        debug!("reminder scheduler already running");
+        return Ok(());
+    }
+
+    while let Some(due) = next_due(&path)? {
+        let wait = due.saturating_sub(unix_now()).min(MAX_SLEEP_SECS);
+        thread::sleep(Duration::from_secs(wait));
+        send_due(config);
+    }
+    drop(lock);
+
+    // A chain started just before the lock was released found this scheduler running.
+    if next_due(&path)?.is_some() {
+        spawn_scheduler(config)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    const AFTER: u64 = 600;
+
+    fn temp_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("reminders", test_name).join(REMINDERS_FILE_NAME)
+    }
+
+    fn event(path: &Path, name: &str, session: &str, now: u64) -> bool {
+        let input = format!(
+            r#"{{"session_id":"{session}","transcript_path":"/tmp/t.jsonl","hook_event_name":"{name}"}}"#
+        );
+        let hook_input: HookInput = serde_json::from_str(&input).unwrap();
+        record(path, &hook_input, &input, now, AFTER).unwrap()
+    }
+
+    fn idle_times(due: &[DueReminder]) -> Vec<u64> {
+        due.iter().map(|reminder| reminder.idle_secs).collect()
+    }
+
+    #[test]
+    fn unanswered_notification_reminds_with_backoff() {
+        let path = temp_path("backoff");
+        assert!(event(&path, "Notification", "a", 1000));
+
+        assert!(take_due(&path, 1599, AFTER, 3).unwrap().is_empty());
+        assert_eq!(idle_times(&take_due(&path, 1600, AFTER, 3).unwrap()), [600]);
+        assert!(take_due(&path, 2799, AFTER, 3).unwrap().is_empty());
+        assert_eq!(
+            idle_times(&take_due(&path, 2800, AFTER, 3).unwrap()),
+            [1800]
+        );
+        assert_eq!(next_due(&path).unwrap(), Some(2800 + 4 * AFTER));
+        assert_eq!(
+            idle_times(&take_due(&path, 5200, AFTER, 3).unwrap()),
+            [4200]
+        );
+
+        assert_eq!(next_due(&path).unwrap(), None, "max count ends the chain");
+        assert!(take_due(&path, 100_000, AFTER, 3).unwrap().is_empty());
+    }
+
+    #[test]
+    fn activity_cancels_the_chain() {
+        for activity in [
+            "UserPromptSubmit",
+            "PreToolUse",
+            "PostToolUse",
+            "SessionEnd",
+        ] {
+            let path = temp_path(activity);
+            event(&path, "Notification", "a", 1000);
+            assert_eq!(take_due(&path, 1600, AFTER, 3).unwrap().len(), 1);
+
+            assert!(!event(&path, activity, "a", 1700));
+            assert!(take_due(&path, 100_000, AFTER, 3).unwrap().is_empty());
+            assert_eq!(next_due(&path).unwrap(), None);
+        }
+    }
+
+    #[test]
+    fn sessions_are_independent() {
+        let path = temp_path("sessions");
+        event(&path, "Notification", "a", 1000);
+        event(&path, "Notification", "b", 1300);
+        assert!(!event(&path, "Stop", "a", 1400), "Stop isn't activity");
+        event(&path, "UserPromptSubmit", "b", 1500);
+
+        let due = take_due(&path, 2000, AFTER, 3).unwrap();
+        assert_eq!(idle_times(&due), [1000]);
+        assert!(due[0].input.contains(r#""session_id":"a""#));
+    }
+
+    #[test]
+    fn new_notification_restarts_the_chain() {
+        let path = temp_path("restart");
+        event(&path, "Notification", "a", 1000);
+        take_due(&path, 1600, AFTER, 3).unwrap();
+
+        event(&path, "Notification", "a", 1700);
+        assert!(take_due(&path, 2299, AFTER, 3).unwrap().is_empty());
+        assert_eq!(idle_times(&take_due(&path, 2300, AFTER, 3).unwrap()), [600]);
+    }
+}
@@ -0,0 +1,188 @@
+//! `claude.report_delivery`: the hook output tells Claude, through `additionalContext`,
+//! whether the user was notified, so it knows a human has been pinged.
+
+use crate::{
+    configuration::{Config, ReportDelivery},
+    delivery::receipts::Receipt,
+    processors::claude::structs::{HookEventName, HookOutput, HookSpecificOutput},
+    utils::local_clock,
+};
+
+/// `output` with what `receipts` say about the notifications sent for `event`, as far as
+/// `claude.report_delivery` asks for.
+pub fn attach(
+    config: &Config,
+    event: &HookEventName,
+    mut output: HookOutput,
+    receipts: &[Receipt],
+) -> HookOutput {
+    let Some(context) = context(config.claude.report_delivery, receipts) else {
+        return output;
+    };
+    // Claude Code rejects `hookSpecificOutput` on the other events.
+    if !accepts_additional_context(event) {
+        return output;
+    }
+
+    let specific = output
+        .hook_specific_output
+        .get_or_insert_with(|| HookSpecificOutput {
+            hook_event_name: Some(event.as_str().to_string()),
+            ..Default::default()
+        });
+    specific.additional_context = Some(context);
+    output
+}
+
+fn accepts_additional_context(event: &HookEventName) -> bool {
+    matches!(
+        event,
+        HookEventName::PreToolUse
+            | HookEventName::PostToolUse
+            | HookEventName::UserPromptSubmit
+            | HookEventName::SessionStart
+    )
+}
+
+/// One sentence per receipt the mode reports, e.g. "The user was notified via desktop at
+/// 14:32."
+fn context(mode: ReportDelivery, receipts: &[Receipt]) -> Option<String> {
+    let sentences: Vec<String> = receipts
+        .iter()
+        .filter(|receipt| match mode {
+            ReportDelivery::None => false,
+            ReportDelivery::Failures => receipt.error.is_some(),
+            ReportDelivery::Always => true,
+        })
+        .map(describe)
+        .collect();
+    (!sentences.is_empty()).then(|| sentences.join(" "))
+}
+
+fn describe(receipt: &Receipt) -> String {
+    let time = local_clock(receipt.sent_at);
+    let backends = receipt
+        .delivered_by
+        .iter()
+        .map(|backend| backend.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match (&receipt.error, receipt.delivered_by.as_slice()) {
+        (None, _) => format!("The user was notified via {backends} at {time}."),
+        (Some(error), []) => format!(
+            "anot couldn't notify the user at {time} ({error}), so they may not know about this yet."
+        ),
+        (Some(error), [..]) => format!(
+            "The user was notified via {backends} at {time}, though other backends failed ({error})."
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::configuration::Backend;
+
+    fn receipt(delivered_by: &[Backend], error: Option<&str>) -> Receipt {
+        Receipt {
+            event: "PostToolUse".to_string(),
+            delivered_by: delivered_by.to_vec(),
+            error: error.map(String::from),
+            sent_at: 0,
+        }
+    }
+
+    fn output() -> HookOutput {
+        HookOutput {
+            r#continue: Some(true),
+            suppress_output: Some(true),
+            ..Default::default()
+        }
+    }
+
+    fn serialized(mode: ReportDelivery, event: HookEventName, receipts: &[Receipt]) -> String {
+        let mut config = Config::default();
+        config.claude.report_delivery = mode;
+        serde_json::to_string(&attach(&config, &event, output(), receipts)).unwrap()
+    }
+
+    fn clock() -> String {
+        local_clock(0)
+    }
+
+    #[test]
+    fn none_leaves_the_output_as_it_was() {
+        let today = serde_json::to_string(&output()).unwrap();
+        assert_eq!(today, r#"{"continue":true,"suppressOutput":true}"#);
+
+        for receipts in [
+            vec![receipt(&[Backend::Desktop], None)],
+            vec![receipt(&[], Some("desktop: no notification daemon"))],
+        ] {
+            assert_eq!(
+                serialized(ReportDelivery::None, HookEventName::PostToolUse, &receipts),
+                today
+            );
+        }
+    }
+
+    #[test]
+    fn failures_only_reports_failed_deliveries() {
+        let delivered = [receipt(&[Backend::Desktop], None)];
+        assert_eq!(
+            serialized(
+                ReportDelivery::Failures,
+                HookEventName::PostToolUse,
+                &delivered
+            ),
+            r#"{"continue":true,"suppressOutput":true}"#
+        );
+
+        let failed = [receipt(&[], Some("desktop: no notification daemon"))];
+        assert_eq!(
+            serialized(
+                ReportDelivery::Failures,
+                HookEventName::PostToolUse,
+                &failed
+            ),
+            format!(
+                r#"{{"continue":true,"suppressOutput":true,"hookSpecificOutput":{{"hookEventName":"PostToolUse","additionalContext":"anot couldn't notify the user at {} (desktop: no notification daemon), so they may not know about this yet."}}}}"#,
+                clock()
+            )
+        );
+    }
+
+    #[test]
+    fn always_reports_every_delivery() {
+        let receipts = [
+            receipt(&[Backend::Desktop, Backend::Tmux], None),
+            receipt(&[Backend::Tmux], Some("desktop: no notification daemon")),
+        ];
+        assert_eq!(
+            serialized(
+                ReportDelivery::Always,
+                HookEventName::UserPromptSubmit,
+                &receipts
+            ),
+            format!(
+                r#"{{"continue":true,"suppressOutput":true,"hookSpecificOutput":{{"hookEventName":"UserPromptSubmit","additionalContext":"The user was notified via desktop, tmux at {0}. The user was notified via tmux at {0}, though other backends failed (desktop: no notification daemon)."}}}}"#,
+                clock()
+            )
+        );
+        assert_eq!(
+            serialized(ReportDelivery::Always, HookEventName::UserPromptSubmit, &[]),
+            r#"{"continue":true,"suppressOutput":true}"#,
+            "nothing was sent"
+        );
+    }
+
+    #[test]
+    fn events_without_additional_context_are_left_alone() {
+        let receipts = [receipt(&[Backend::Desktop], None)];
+        assert_eq!(
+            serialized(ReportDelivery::Always, HookEventName::Stop, &receipts),
+            r#"{"continue":true,"suppressOutput":true}"#
+        );
+    }
+}
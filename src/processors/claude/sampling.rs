@@ -0,0 +1,112 @@
+//! `claude.sample`: only every Nth notification of a chatty event is shown. The count is kept
+//! per event and session in a state file, so which ones fire is the same however many hook
+//! processes it takes.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    configuration::Config,
+    storage::{read_json_or_default, with_lock, write_atomic},
+};
+
+const SAMPLING_FILE_NAME: &str = "sampling.json";
+
+/// Counters of sessions without the event for this long are dropped.
+const STALE_AFTER_SECS: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+struct Counter {
+    count: u64,
+    last_seen: u64,
+}
+
+/// Events counted so far, keyed by [`counter_key`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Counters {
+    #[serde(default)]
+    counters: BTreeMap<String, Counter>,
+}
+
+pub fn sampling_path(config: &Config) -> PathBuf {
+    config.data_dir().join(SAMPLING_FILE_NAME)
+}
+
+pub fn counter_key(session_id: &str, event: &str) -> String {
+    format!("{session_id}:{event}")
+}
+
+/// Counts one more event for `key` at `now` and returns its number, starting at 1. Stale
+/// counters are dropped.
+pub fn count(path: &Path, key: &str, now: u64) -> Result<u64, Error> {
+    with_lock(path, || {
+        let mut state: Counters = read_json_or_default(path)?;
+        state
+            .counters
+            .retain(|_, counter| now.saturating_sub(counter.last_seen) < STALE_AFTER_SECS);
+        let counter = state.counters.entry(key.to_string()).or_default();
+        counter.count += 1;
+        counter.last_seen = now;
+        let count = counter.count;
+
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+        Ok(count)
+    })
+}
+
+/// The number the next event for `key` would get, without counting it.
+pub fn peek(path: &Path, key: &str) -> Result<u64, Error> {
+    let state: Counters = read_json_or_default(path)?;
+    Ok(state.counters.get(key).map_or(0, |counter| counter.count) + 1)
+}
+
+/// Whether the event numbered `count` is shown with a 1-in-`rate` sample.
+pub fn fires(count: u64, rate: u64) -> bool {
+    rate <= 1 || count.is_multiple_of(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("sampling", test_name).join(SAMPLING_FILE_NAME)
+    }
+
+    #[test]
+    fn counts_per_session_and_event() {
+        let path = temp_path("count");
+        let a = counter_key("a", "PostToolUse");
+        let b = counter_key("b", "PostToolUse");
+
+        assert_eq!(peek(&path, &a).unwrap(), 1);
+        assert_eq!(count(&path, &a, 1000).unwrap(), 1);
+        assert_eq!(count(&path, &a, 1001).unwrap(), 2);
+        assert_eq!(count(&path, &b, 1002).unwrap(), 1);
+        assert_eq!(peek(&path, &a).unwrap(), 3);
+        assert_eq!(peek(&path, &a).unwrap(), 3, "peeking doesn't count");
+    }
+
+    #[test]
+    fn stale_counters_start_over() {
+        let path = temp_path("stale");
+        let key = counter_key("a", "PreToolUse");
+        count(&path, &key, 1000).unwrap();
+        count(&path, &key, 1001).unwrap();
+
+        assert_eq!(count(&path, &key, 1001 + STALE_AFTER_SECS).unwrap(), 1);
+    }
+
+    #[test]
+    fn every_nth_fires() {
+        let fired: Vec<u64> = (1..=12).filter(|count| fires(*count, 5)).collect();
+        assert_eq!(fired, [5, 10]);
+        assert!((1..=3).all(|count| fires(count, 1)));
+    }
+}
@@ -1,11 +1,10 @@
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use serde_json::Value;
 use std::fmt;
 use strum::EnumIter;
 
 /// Hook event names
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash, EnumIter)]
-#[serde(rename_all = "PascalCase")]
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, EnumIter)]
 pub enum HookEventName {
     PreToolUse,
     PostToolUse,
@@ -16,22 +15,15 @@ pub enum HookEventName {
     PreCompact,
     SessionStart,
     SessionEnd,
+    /// An event this version doesn't know about, carrying the raw name.
+    /// Never yielded by `HookEventName::iter()`.
+    #[strum(disabled)]
+    Unknown(String),
 }
 
 impl fmt::Display for HookEventName {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = match self {
-            HookEventName::Notification => "Notification",
-            HookEventName::PreToolUse => "PreToolUse",
-            HookEventName::PostToolUse => "PostToolUse",
-            HookEventName::UserPromptSubmit => "UserPromptSubmit",
-            HookEventName::Stop => "Stop",
-            HookEventName::SubagentStop => "SubagentStop",
-            HookEventName::PreCompact => "PreCompact",
-            HookEventName::SessionStart => "SessionStart",
-            HookEventName::SessionEnd => "SessionEnd",
-        };
-        write!(f, "{}", name)
+        write!(f, "{}", self.as_str())
     }
 }
 
@@ -47,8 +39,127 @@ impl HookEventName {
             HookEventName::PreCompact => "PreCompact",
             HookEventName::SessionStart => "SessionStart",
             HookEventName::SessionEnd => "SessionEnd",
+            HookEventName::Unknown(name) => name,
         }
     }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Notification" => HookEventName::Notification,
+            "PreToolUse" => HookEventName::PreToolUse,
+            "PostToolUse" => HookEventName::PostToolUse,
+            "UserPromptSubmit" => HookEventName::UserPromptSubmit,
+            "Stop" => HookEventName::Stop,
+            "SubagentStop" => HookEventName::SubagentStop,
+            "PreCompact" => HookEventName::PreCompact,
+            "SessionStart" => HookEventName::SessionStart,
+            "SessionEnd" => HookEventName::SessionEnd,
+            other => HookEventName::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for HookEventName {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for HookEventName {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(HookEventName::from_name(&name))
+    }
+}
+
+/// The permission mode Claude Code was in when the hook fired.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PermissionMode {
+    Default,
+    /// File edits are approved without asking
+    AcceptEdits,
+    /// Planning only; nothing is changed
+    Plan,
+    BypassPermissions,
+    /// A mode this version doesn't know about, carrying the raw name.
+    Unknown(String),
+}
+
+impl PermissionMode {
+    pub fn as_str(&self) -> &str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::Plan => "plan",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+            PermissionMode::Unknown(name) => name,
+        }
+    }
+
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "default" => PermissionMode::Default,
+            "acceptEdits" => PermissionMode::AcceptEdits,
+            "plan" => PermissionMode::Plan,
+            "bypassPermissions" => PermissionMode::BypassPermissions,
+            other => PermissionMode::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl Serialize for PermissionMode {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for PermissionMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        Ok(PermissionMode::from_name(&name))
+    }
+}
+
+/// The model behind the session: a bare id, or an object with a display name.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum ModelInfo {
+    Id(String),
+    Details {
+        id: String,
+        #[serde(default)]
+        display_name: Option<String>,
+    },
+}
+
+impl ModelInfo {
+    pub fn id(&self) -> &str {
+        match self {
+            ModelInfo::Id(id) | ModelInfo::Details { id, .. } => id,
+        }
+    }
+
+    /// The name to show people, falling back to the id.
+    pub fn display_name(&self) -> &str {
+        match self {
+            ModelInfo::Details {
+                display_name: Some(name),
+                ..
+            } => name,
+            _ => self.id(),
+        }
+    }
+}
+
+/// Reads an optional field whose shape may change between Claude Code versions: a value that
+/// doesn't fit reads as absent instead of failing the whole payload.
+fn lenient<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: serde::de::DeserializeOwned,
+{
+    let value = Option::<Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| serde_json::from_value(value).ok()))
 }
 
 /// Trigger source for PreCompact
@@ -96,6 +207,11 @@ pub struct HookInput {
     #[serde(default)]
     pub cwd: Option<String>,
     pub hook_event_name: HookEventName,
+    /// Missing from payloads of older Claude Code versions
+    #[serde(default, deserialize_with = "lenient")]
+    pub permission_mode: Option<PermissionMode>,
+    #[serde(default, deserialize_with = "lenient")]
+    pub model: Option<ModelInfo>,
 
     // ---- PreToolUse / PostToolUse specific ----
     /// Tool name (e.g., Write, Edit, Bash, or mcp__...)
@@ -107,6 +223,9 @@ pub struct HookInput {
     /// Tool response: schema varies depending on the tool, only present in PostToolUse
     #[serde(default)]
     pub tool_response: Option<Value>,
+    /// Identifies one tool call across its PreToolUse and PostToolUse events
+    #[serde(default)]
+    pub tool_use_id: Option<String>,
 
     // ---- Notification specific ----
     #[serde(default)]
@@ -192,19 +311,19 @@ impl Default for HookOutput {
 #[serde(rename_all = "camelCase")]
 pub struct HookSpecificOutput {
     /// The hook event this output applies to
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub hook_event_name: Option<String>,
 
     /// Adds context for Claude to consider
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub additional_context: Option<String>,
 
     /// PreToolUse-specific permission control
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub permission_decision: Option<PermissionDecision>,
 
     /// Reason for permission decision
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub permission_decision_reason: Option<String>,
 }
 
@@ -219,3 +338,90 @@ pub enum PermissionDecision {
     /// Ask the user for confirmation
     Ask,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator;
+
+    #[test]
+    fn unknown_event_names_round_trip() {
+        let event: HookEventName = serde_json::from_str(r#""SomeFutureEvent""#).unwrap();
+        assert_eq!(event, HookEventName::Unknown("SomeFutureEvent".to_string()));
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            r#""SomeFutureEvent""#
+        );
+    }
+
+    #[test]
+    fn known_event_names_round_trip() {
+        for event in HookEventName::iter() {
+            let json = serde_json::to_string(&event).unwrap();
+            assert_eq!(serde_json::from_str::<HookEventName>(&json).unwrap(), event);
+        }
+    }
+
+    #[test]
+    fn newer_payload_fields_are_read() {
+        let input: HookInput = serde_json::from_str(
+            r#"{
+                "session_id": "abc123",
+                "transcript_path": "/Users/me/.claude/projects/app/abc123.jsonl",
+                "cwd": "/Users/me/app",
+                "permission_mode": "acceptEdits",
+                "model": {"id": "claude-sonnet-4-5", "display_name": "Sonnet 4.5"},
+                "hook_event_name": "PreToolUse",
+                "tool_name": "Write",
+                "tool_input": {"file_path": "/Users/me/app/a.txt", "content": "hi"},
+                "tool_use_id": "toolu_01ABCDEF"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(input.permission_mode, Some(PermissionMode::AcceptEdits));
+        assert_eq!(input.tool_use_id.as_deref(), Some("toolu_01ABCDEF"));
+        let model = input.model.unwrap();
+        assert_eq!(model.id(), "claude-sonnet-4-5");
+        assert_eq!(model.display_name(), "Sonnet 4.5");
+    }
+
+    #[test]
+    fn newer_payload_fields_are_optional() {
+        let input: HookInput = serde_json::from_str(
+            r#"{
+                "session_id": "abc123",
+                "transcript_path": "/Users/me/.claude/projects/app/abc123.jsonl",
+                "cwd": "/Users/me/app",
+                "hook_event_name": "Stop",
+                "stop_hook_active": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(input.permission_mode, None);
+        assert_eq!(input.tool_use_id, None);
+        assert_eq!(input.model, None);
+
+        let input: HookInput = serde_json::from_str(
+            r#"{
+                "session_id": "abc123",
+                "transcript_path": "/tmp/t.jsonl",
+                "hook_event_name": "Stop",
+                "permission_mode": "somethingNew",
+                "model": 42
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            input.permission_mode,
+            Some(PermissionMode::Unknown("somethingNew".to_string()))
+        );
+        assert_eq!(input.model, None, "an unexpected shape reads as absent");
+    }
+
+    #[test]
+    fn iter_excludes_unknown() {
+        assert_eq!(HookEventName::iter().count(), 9);
+        assert!(HookEventName::iter().all(|e| !matches!(e, HookEventName::Unknown(_))));
+    }
+}
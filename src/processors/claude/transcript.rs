@@ -0,0 +1,138 @@
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use serde_json::Value;
+use tracing::debug;
+
+/// Size of the first chunk read from the end of the transcript.
+const INITIAL_TAIL_BYTES: u64 = 64 * 1024;
+/// Never read more than this much of a transcript looking for an assistant message.
+const MAX_TAIL_BYTES: u64 = 4 * 1024 * 1024;
+
+/// Returns the text of the last assistant message in a Claude Code JSONL transcript.
+///
+/// Only the tail of the file is read; the window grows until an assistant message with text
+/// content is found or [`MAX_TAIL_BYTES`] is reached. Malformed lines and assistant entries
+/// without text (e.g. tool use only) are skipped.
+pub fn last_assistant_message(path: &Path) -> Option<String> {
+    let mut file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) => {
+            debug!(path = %path.display(), error = %e, "cannot open transcript");
+            return None;
+        }
+    };
+    let len = file.metadata().ok()?.len();
+
+    let mut window = INITIAL_TAIL_BYTES;
+    loop {
+        let start = len.saturating_sub(window);
+        file.seek(SeekFrom::Start(start)).ok()?;
+
+        let mut buf = Vec::new();
+        file.by_ref().take(len - start).read_to_end(&mut buf).ok()?;
+        let tail = String::from_utf8_lossy(&buf);
+
+        // The first line of a partial window is most likely cut in half.
+        let lines = tail.lines().skip(if start > 0 { 1 } else { 0 });
+        let found = lines
+            .collect::<Vec<_>>()
+            .into_iter()
+            .rev()
+            .find_map(assistant_text);
+
+        if found.is_some() || start == 0 || window >= MAX_TAIL_BYTES {
+            return found;
+        }
+        window = (window * 2).min(MAX_TAIL_BYTES);
+    }
+}
+
+fn assistant_text(line: &str) -> Option<String> {
+    let entry: Value = serde_json::from_str(line.trim()).ok()?;
+
+    if entry.get("type").and_then(Value::as_str) != Some("assistant") {
+        return None;
+    }
+
+    let content = entry.pointer("/message/content")?;
+    let text = match content {
+        Value::String(s) => s.clone(),
+        Value::Array(parts) => parts
+            .iter()
+            .filter(|part| part.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|part| part.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_path;
+    use std::path::PathBuf;
+
+    fn write_transcript(test_name: &str, lines: &[&str]) -> PathBuf {
+        let path = temp_path("transcript", &format!("{test_name}.jsonl"));
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        path
+    }
+
+    const USER: &str = r#"{"type":"user","message":{"role":"user","content":"fix the tests"}}"#;
+    const ASSISTANT_TEXT: &str = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"text","text":"All tests pass now."}]}}"#;
+    const ASSISTANT_TOOL_USE: &str = r#"{"type":"assistant","message":{"role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"cargo test"}}]}}"#;
+
+    #[test]
+    fn finds_last_assistant_text() {
+        let path = write_transcript("simple", &[USER, ASSISTANT_TEXT]);
+        assert_eq!(
+            last_assistant_message(&path).as_deref(),
+            Some("All tests pass now.")
+        );
+    }
+
+    #[test]
+    fn skips_tool_use_only_and_malformed_entries() {
+        let path = write_transcript(
+            "tool-use-last",
+            &[USER, ASSISTANT_TEXT, ASSISTANT_TOOL_USE, "{not json", ""],
+        );
+        assert_eq!(
+            last_assistant_message(&path).as_deref(),
+            Some("All tests pass now.")
+        );
+    }
+
+    #[test]
+    fn returns_none_without_assistant_text() {
+        let path = write_transcript("no-assistant", &[USER, ASSISTANT_TOOL_USE]);
+        assert_eq!(last_assistant_message(&path), None);
+    }
+
+    #[test]
+    fn returns_none_for_missing_file() {
+        let path = std::env::temp_dir().join("anot-transcript-does-not-exist.jsonl");
+        assert_eq!(last_assistant_message(&path), None);
+    }
+
+    #[test]
+    fn grows_window_past_large_trailing_entries() {
+        let filler = format!(
+            r#"{{"type":"user","message":{{"role":"user","content":"{}"}}}}"#,
+            "x".repeat(INITIAL_TAIL_BYTES as usize * 2)
+        );
+        let path = write_transcript("large-tail", &[ASSISTANT_TEXT, &filler]);
+        assert_eq!(
+            last_assistant_message(&path).as_deref(),
+            Some("All tests pass now.")
+        );
+    }
+}
@@ -1,4 +1,9 @@
+#[cfg(feature = "desktop")]
 pub mod icon;
 pub mod init;
 pub mod input_and_output;
+pub mod payload;
 pub mod structs;
+pub mod turns;
+
+pub use input_and_output::{process_codex_input, send_notification};
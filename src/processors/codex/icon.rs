@@ -1,18 +1,46 @@
 use anyhow::Error;
-use std::fs::File;
-use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-const CODEX_ICON_BYTES: &[u8] = include_bytes!("../../../assets/codex-icon.png");
+use crate::processors::icon::{EmbeddedIcon, custom_icon};
 
-pub fn get_codex_icon_path() -> Result<PathBuf, Error> {
-    let temp_dir = std::env::temp_dir();
-    let icon_path = temp_dir.join("codex-icon.png");
+pub const CODEX_ICON: EmbeddedIcon = EmbeddedIcon {
+    name: "codex-icon",
+    variants: &[
+        (48, include_bytes!("../../../assets/codex-icon-48.png")),
+        (128, include_bytes!("../../../assets/codex-icon-128.png")),
+        (512, include_bytes!("../../../assets/codex-icon.png")),
+    ],
+};
 
-    if !icon_path.exists() {
-        let mut file = File::create(&icon_path)?;
-        file.write_all(CODEX_ICON_BYTES)?;
+/// Returns the configured icon when it exists, otherwise the built-in icon, in the size for
+/// `size_hint`, written to the temp directory.
+pub fn get_icon(configured: Option<&Path>, size_hint: u32) -> Result<PathBuf, Error> {
+    if let Some(path) = configured.and_then(custom_icon) {
+        return Ok(path);
     }
 
-    Ok(icon_path)
+    CODEX_ICON.get_icon(size_hint)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::processors::icon::LARGEST;
+
+    #[test]
+    fn falls_back_to_builtin_icon() {
+        let path = get_icon(Some(Path::new("~/nonexistent-anot-icon.png")), LARGEST).unwrap();
+
+        assert_eq!(path.parent(), Some(std::env::temp_dir().as_path()));
+        assert_eq!(std::fs::read(&path).unwrap(), CODEX_ICON.variant(LARGEST).1);
+    }
+
+    #[test]
+    fn linux_sizes_pick_the_smaller_variants() {
+        let icon = |size| std::fs::read(get_icon(None, size).unwrap()).unwrap();
+
+        assert_eq!(icon(48), CODEX_ICON.variants[0].1);
+        assert_eq!(icon(128), CODEX_ICON.variants[1].1);
+        assert_eq!(icon(256), CODEX_ICON.variants[2].1);
+    }
 }
@@ -3,10 +3,21 @@ use std::{
     path::{Path, PathBuf},
 };
 
-use anyhow::Error;
-use inquire::{Confirm, InquireError, Select};
+use anyhow::{Context, Error};
+use inquire::{Confirm, Select};
 use serde::{Deserialize, Serialize};
 use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    console::{self, Mark},
+    error::AnotError,
+    preview::write_with_preview,
+    processors::common::init::{
+        ConfigTarget, PathCandidate, confirm_creation, handle_inquire_error,
+    },
+    utils::expand_tilde_in,
+};
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 struct CodexConfiguration {
     #[serde(default)]
@@ -15,28 +26,98 @@ struct CodexConfiguration {
     other: toml::value::Table,
 }
 
-impl CodexConfiguration {
-    fn set_notify(&mut self, cmd: Vec<String>) {
-        self.notify = Some(cmd);
-    }
+/// Where `notify` is written: the root of `config.toml` or one of its `[profiles.<name>]`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum NotifyTarget {
+    Root,
+    Profile(String),
+}
 
-    fn clear_notify(&mut self) {
-        self.notify = None;
+impl fmt::Display for NotifyTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NotifyTarget::Root => write!(f, "Top level (all profiles)"),
+            NotifyTarget::Profile(name) => write!(f, "Profile {name}"),
+        }
     }
 }
 
-fn handle_inquire_error(err: InquireError, context: &str) -> Error {
-    match err {
-        InquireError::OperationCanceled => Error::msg("Operation cancelled by user"),
-        InquireError::OperationInterrupted => Error::msg("Operation interrupted by user"),
-        _ => Error::msg(format!("{}: {}", context, err)),
+impl NotifyTarget {
+    /// The TOML key `notify` is set under, e.g. `profiles.work.notify`.
+    fn key(&self) -> String {
+        match self {
+            NotifyTarget::Root => "notify".to_string(),
+            NotifyTarget::Profile(name) => format!("profiles.{name}.notify"),
+        }
     }
 }
 
-enum CodexConfigPathSelection {
-    CodexHomeConfig(bool),
-    DotCodexConfig(bool),
-    CustomPath,
+impl CodexConfiguration {
+    fn profiles(&self) -> Option<&toml::value::Table> {
+        self.other.get("profiles").and_then(toml::Value::as_table)
+    }
+
+    fn profile_names(&self) -> Vec<String> {
+        self.profiles()
+            .map(|profiles| profiles.keys().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    fn profile_mut(&mut self, name: &str) -> Option<&mut toml::value::Table> {
+        self.other
+            .get_mut("profiles")
+            .and_then(toml::Value::as_table_mut)
+            .and_then(|profiles| profiles.get_mut(name))
+            .and_then(toml::Value::as_table_mut)
+    }
+
+    fn notify(&self, target: &NotifyTarget) -> Option<Vec<String>> {
+        match target {
+            NotifyTarget::Root => self.notify.clone(),
+            NotifyTarget::Profile(name) => self
+                .profiles()?
+                .get(name)?
+                .get("notify")?
+                .clone()
+                .try_into()
+                .ok(),
+        }
+    }
+
+    /// Every `notify` in the file with the key it's under, for reporting what's configured.
+    fn configured_notifies(&self) -> Vec<(String, Vec<String>)> {
+        let mut targets = vec![NotifyTarget::Root];
+        targets.extend(self.profile_names().into_iter().map(NotifyTarget::Profile));
+        targets
+            .into_iter()
+            .filter_map(|target| Some((target.key(), self.notify(&target)?)))
+            .collect()
+    }
+
+    fn set_notify(&mut self, target: &NotifyTarget, cmd: Vec<String>) {
+        match target {
+            NotifyTarget::Root => self.notify = Some(cmd),
+            NotifyTarget::Profile(name) => {
+                if let Some(profile) = self.profile_mut(name) {
+                    profile.insert(
+                        "notify".to_string(),
+                        toml::Value::Array(cmd.into_iter().map(toml::Value::String).collect()),
+                    );
+                }
+            }
+        }
+    }
+
+    fn clear_notify(&mut self, target: &NotifyTarget) {
+        match target {
+            NotifyTarget::Root => self.notify = None,
+            NotifyTarget::Profile(name) => {
+                if let Some(profile) = self.profile_mut(name) {
+                    profile.remove("notify");
+                }
+            }
+        }
+    }
 }
 
 #[derive(Clone, Copy)]
@@ -56,39 +137,37 @@ impl fmt::Display for ExistingNotifyAction {
     }
 }
 
-impl fmt::Display for CodexConfigPathSelection {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            CodexConfigPathSelection::CodexHomeConfig(exists) => {
-                let status = if *exists { "✓" } else { "✗" };
-                write!(f, "{} $CODEX_HOME/config.toml", status)
-            }
-            CodexConfigPathSelection::DotCodexConfig(exists) => {
-                let status = if *exists { "✓" } else { "✗" };
-                write!(f, "{} ~/.codex/config.toml", status)
-            }
-            CodexConfigPathSelection::CustomPath => write!(f, "📂 Custom Path"),
-        }
-    }
-}
-
+/// Sets up `notify` in a Codex `config.toml`, at the top level or, with `profile`, in
+/// `[profiles.<profile>]`. Without `profile`, a file that defines profiles asks which to use.
+/// With `dry_run`, the changes are shown but not written.
 #[instrument(skip(codex_config_path))]
-pub fn initialize_codex_configuration(codex_config_path: &Option<PathBuf>) -> Result<(), Error> {
-    let chosen_path = choose_config_path(codex_config_path)?;
-    let expanded_path = expand_tilde(&chosen_path);
-
-    debug!(chosen = %chosen_path.display(), expanded = %expanded_path.display(), "resolved Codex config path");
-    ensure_path_exists(&expanded_path)?;
+pub fn initialize_codex_configuration(
+    codex_config_path: &Option<PathBuf>,
+    profile: Option<&str>,
+    dry_run: bool,
+) -> Result<(), Error> {
+    let expanded_path = choose_config_path(codex_config_path)?;
+    if !dry_run {
+        confirm_creation(&expanded_path)?;
+    }
 
-    let mut config = read_config(&expanded_path)?;
+    let (original, mut config) = read_config(&expanded_path)?;
     let notify_cmd = notify_command()?;
 
-    if let Some(current) = &config.notify {
-        info!(?current, "existing Codex notify configuration detected");
-        println!("📋 Current notify configuration:");
-        println!("  • notify = {:?}", current);
+    let configured = config.configured_notifies();
+    if !configured.is_empty() {
+        info!(?configured, "existing Codex notify configuration detected");
+        console::say(Mark::Current, "Current notify configuration:");
+        for (key, current) in &configured {
+            console::say(Mark::Item, format!("{key} = {current:?}"));
+        }
         println!();
+    }
 
+    let target = choose_target(&config, profile)?;
+    debug!(target = %target.key(), "chose where to write notify");
+
+    if config.notify(&target).is_some() {
         let choice = Select::new(
             "Notify is already configured. What would you like to do?",
             vec![
@@ -103,153 +182,161 @@ pub fn initialize_codex_configuration(codex_config_path: &Option<PathBuf>) -> Re
 
         match choice {
             ExistingNotifyAction::Override => {
-                config.set_notify(notify_cmd);
-                write_config(&expanded_path, &config)?;
-                info!(path = %expanded_path.display(), "overrode notify configuration");
-                println!("✅ Updated: notify now uses this tool");
-                println!("📁 Configuration written to: {}", expanded_path.display());
+                config.set_notify(&target, notify_cmd);
+                if write_config(&expanded_path, &original, &config, dry_run)? {
+                    info!(path = %expanded_path.display(), "overrode notify configuration");
+                    console::say(Mark::Ok, "Updated: notify now uses this tool");
+                    console::say(
+                        Mark::Written,
+                        format!("Configuration written to: {}", expanded_path.display()),
+                    );
+                }
             }
             ExistingNotifyAction::Keep => {
                 info!("kept existing notify configuration");
-                println!("ℹ️  Keeping existing notify setting. No changes made.");
+                console::say(
+                    Mark::Info,
+                    "Keeping existing notify setting. No changes made.",
+                );
             }
             ExistingNotifyAction::Remove => {
-                config.clear_notify();
-                write_config(&expanded_path, &config)?;
-                info!(path = %expanded_path.display(), "removed notify configuration");
-                println!("🧹 Removed notify configuration");
-                println!("📁 Configuration written to: {}", expanded_path.display());
+                config.clear_notify(&target);
+                if write_config(&expanded_path, &original, &config, dry_run)? {
+                    info!(path = %expanded_path.display(), "removed notify configuration");
+                    console::say(Mark::Removed, "Removed notify configuration");
+                    console::say(
+                        Mark::Written,
+                        format!("Configuration written to: {}", expanded_path.display()),
+                    );
+                }
             }
         }
     } else {
-        let should_set = Confirm::new("Configure Codex notify to use this tool?")
-            .with_default(true)
-            .prompt()
-            .map_err(|err| handle_inquire_error(err, "Failed to get confirmation"))?;
+        let should_set = Confirm::new(&format!(
+            "Configure Codex {} to use this tool?",
+            target.key()
+        ))
+        .with_default(true)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to get confirmation"))?;
 
         if should_set {
-            config.set_notify(notify_cmd);
-            write_config(&expanded_path, &config)?;
-
-            info!(path = %expanded_path.display(), "configured notify with this tool");
-            println!("✅ Successfully configured notify");
-            println!("📁 Configuration written to: {}", expanded_path.display());
+            config.set_notify(&target, notify_cmd);
+            if write_config(&expanded_path, &original, &config, dry_run)? {
+                info!(path = %expanded_path.display(), "configured notify with this tool");
+                console::say(Mark::Ok, "Successfully configured notify");
+                console::say(
+                    Mark::Written,
+                    format!("Configuration written to: {}", expanded_path.display()),
+                );
+            }
         } else {
             info!("user declined to configure notify");
-            println!("ℹ️  No changes made.");
+            console::say(Mark::Info, "No changes made.");
         }
     }
 
     Ok(())
 }
 
-#[instrument(skip(codex_config_path))]
-fn choose_config_path(codex_config_path: &Option<PathBuf>) -> Result<PathBuf, Error> {
-    if let Some(p) = codex_config_path {
-        info!(path = %p.display(), "using provided path");
-        return Ok(p.clone());
-    }
+/// The profile named by `--profile`, or the user's pick when the file defines profiles.
+fn choose_target(
+    config: &CodexConfiguration,
+    profile: Option<&str>,
+) -> Result<NotifyTarget, Error> {
+    let names = config.profile_names();
 
-    let codex_home_dir = std::env::var("CODEX_HOME")
-        .ok()
-        .unwrap_or("~/.codex".to_string());
-    let codex_home_path = expand_tilde(&PathBuf::from(codex_home_dir)).join("config.toml");
-    let dot_codex_path = expand_tilde(&PathBuf::from("~/.codex/config.toml"));
-
-    let codex_home_exists = codex_home_path.exists();
-    let dot_codex_exists = dot_codex_path.exists();
-
-    let selection = Select::new(
-        "Where do you want to initialize the notifications for?",
-        vec![
-            CodexConfigPathSelection::CodexHomeConfig(codex_home_exists),
-            CodexConfigPathSelection::DotCodexConfig(dot_codex_exists),
-            CodexConfigPathSelection::CustomPath,
-        ],
-    )
-    .with_help_message("Select the configuration path for Codex. ✓ = file exists, ✗ = file missing")
-    .prompt()
-    .map_err(|err| handle_inquire_error(err, "Failed to prompt for Codex configuration path"))?;
-
-    let path = match selection {
-        CodexConfigPathSelection::CodexHomeConfig(_) => {
-            info!(path = %codex_home_path.display(), exists = codex_home_exists, "selected $CODEX_HOME/config.toml");
-            codex_home_path
+    if let Some(name) = profile {
+        if names.iter().any(|n| n == name) {
+            return Ok(NotifyTarget::Profile(name.to_string()));
         }
-        CodexConfigPathSelection::DotCodexConfig(_) => {
-            info!(path = %dot_codex_path.display(), exists = dot_codex_exists, "selected ~/.codex/config.toml");
-            dot_codex_path
-        }
-        CodexConfigPathSelection::CustomPath => {
-            let custom_path: String = inquire::Text::new("Enter the custom path:")
-                .with_help_message("Provide the full path to the Codex config.toml file.")
-                .prompt()
-                .map_err(|err| handle_inquire_error(err, "Failed to prompt for custom path"))?;
-
-            info!(path = %custom_path, "selected custom path");
-            PathBuf::from(custom_path)
-        }
-    };
-
-    Ok(path)
-}
+        return Err(Error::msg(if names.is_empty() {
+            format!("Codex profile `{name}` not found: the config defines no [profiles]")
+        } else {
+            format!(
+                "Codex profile `{name}` not found. Available profiles: {}",
+                names.join(", ")
+            )
+        }));
+    }
 
-fn expand_tilde(path: &Path) -> PathBuf {
-    if let Ok(s) = path.to_path_buf().into_os_string().into_string() {
-        if let Some(rest) = s.strip_prefix("~/")
-            && let Ok(home) = std::env::var("HOME")
-        {
-            debug!(original = %s, expanded = %PathBuf::from(home.clone()).join(rest).display(), "expanding ~ to HOME");
-            return PathBuf::from(home).join(rest);
-        }
-        return PathBuf::from(s);
+    if names.is_empty() {
+        return Ok(NotifyTarget::Root);
     }
-    path.to_path_buf()
-}
 
-#[instrument]
-fn ensure_path_exists(path: &PathBuf) -> Result<(), Error> {
-    if !path.exists() {
-        let should_create = Confirm::new(&format!(
-            "The configuration file '{}' does not exist. Would you like to create it?",
-            path.display()
-        ))
-        .with_default(true)
+    let mut options = vec![NotifyTarget::Root];
+    options.extend(names.into_iter().map(NotifyTarget::Profile));
+    Select::new("Where should notify be configured?", options)
+        .with_help_message("The top level applies to every profile that doesn't set its own notify")
         .prompt()
-        .map_err(|err| handle_inquire_error(err, "Failed to get user confirmation"))?;
-
-        if !should_create {
-            info!(path = %path.display(), "user declined to create file");
-            return Err(Error::msg("Operation cancelled by user"));
-        }
+        .map_err(|err| handle_inquire_error(err, "Failed to prompt for Codex profile"))
+}
 
-        if let Some(parent) = path.parent() {
-            std::fs::create_dir_all(parent)
-                .or(Err(Error::msg("Failed to create parent directories")))?;
-        }
+fn choose_config_path(codex_config_path: &Option<PathBuf>) -> Result<PathBuf, Error> {
+    let [codex_home_path, dot_codex_path] = default_config_paths(
+        std::env::var("CODEX_HOME").ok().as_deref(),
+        dirs::home_dir().as_deref(),
+    );
 
-        std::fs::write(path, "").or(Err(Error::msg("Failed to create configuration file")))?;
-        info!(path = %path.display(), "created empty config file");
+    ConfigTarget {
+        agent: "Codex",
+        question: "Where do you want to initialize the notifications for?",
+        file: "config.toml file",
+        candidates: vec![
+            PathCandidate::new(
+                "$CODEX_HOME/config.toml",
+                codex_home_path.clone(),
+                codex_home_path.exists(),
+            ),
+            PathCandidate::new(
+                "~/.codex/config.toml",
+                dot_codex_path.clone(),
+                dot_codex_path.exists(),
+            ),
+        ],
     }
-    Ok(())
+    .choose_path(codex_config_path.as_deref())
+}
+
+/// `$CODEX_HOME/config.toml` and `~/.codex/config.toml` against `home`. An unset or empty
+/// `$CODEX_HOME` is `~/.codex`, as in Codex.
+pub(crate) fn default_config_paths(codex_home: Option<&str>, home: Option<&Path>) -> [PathBuf; 2] {
+    let dot_codex = expand_tilde_in(Path::new("~"), home).join(".codex");
+    let codex_home = match codex_home.filter(|dir| !dir.is_empty()) {
+        Some(dir) => expand_tilde_in(Path::new(dir), home),
+        None => dot_codex.clone(),
+    };
+    [
+        codex_home.join("config.toml"),
+        dot_codex.join("config.toml"),
+    ]
 }
 
+/// The config file's current content and its parsed form. A missing file reads as empty.
 #[instrument]
-fn read_config(path: &PathBuf) -> Result<CodexConfiguration, Error> {
-    let config_data = std::fs::read_to_string(path)
-        .map_err(|e| Error::msg(format!("Failed to read the configuration file: {}", e)))?;
+fn read_config(path: &PathBuf) -> Result<(String, CodexConfiguration), Error> {
+    let config_data = match std::fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(source) => {
+            return Err(AnotError::ConfigRead {
+                path: path.clone(),
+                source,
+            }
+            .into());
+        }
+    };
 
     if config_data.trim().is_empty() {
         debug!(path = %path.display(), "empty file; using default configuration");
-        return Ok(CodexConfiguration::default());
+        return Ok((config_data, CodexConfiguration::default()));
     }
 
-    let config: CodexConfiguration = toml::from_str(&config_data).map_err(|e| {
-        Error::msg(format!(
-            "Failed to parse the configuration file as TOML: {}. Content: {}",
-            e, config_data
-        ))
-    })?;
+    let config: CodexConfiguration =
+        toml::from_str(&config_data).map_err(|e| AnotError::ConfigParse {
+            path: path.clone(),
+            source: e.into(),
+        })?;
     debug!(
         has_notify = config
             .notify
@@ -259,26 +346,229 @@ fn read_config(path: &PathBuf) -> Result<CodexConfiguration, Error> {
         other_keys = config.other.len(),
         "parsed Codex configuration"
     );
-    Ok(config)
+    Ok((config_data, config))
 }
 
 #[instrument]
 fn notify_command() -> Result<Vec<String>, Error> {
-    let current_exe =
-        std::env::current_exe().or(Err(Error::msg("Failed to get current executable path")))?;
+    let current_exe = std::env::current_exe()
+        .map_err(|e| AnotError::io("Failed to get current executable path", e))?;
     let exe_str = current_exe.to_string_lossy().to_string();
     let cmd = vec![exe_str, "codex".to_string()];
     debug!(?cmd, "constructed notify command");
     Ok(cmd)
 }
 
-#[instrument]
-fn write_config(path: &PathBuf, config: &CodexConfiguration) -> Result<(), Error> {
-    let new_config = toml::to_string_pretty(config).or(Err(Error::msg(
-        "Failed to serialize the configuration to TOML",
-    )))?;
-    std::fs::write(path, new_config)
-        .or(Err(Error::msg("Failed to write the configuration file")))?;
-    info!(path = %path.display(), "wrote Codex configuration");
-    Ok(())
+/// Whether a `notify` command runs `anot codex`.
+fn is_our_notify(command: &[String]) -> bool {
+    let Some((program, args)) = command.split_first() else {
+        return false;
+    };
+    let program = Path::new(program)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+
+    program.contains("anot") && args.iter().any(|arg| arg == "codex")
+}
+
+/// The Codex config files `anot uninstall` cleans up: `$CODEX_HOME/config.toml` and
+/// `~/.codex/config.toml`.
+pub fn uninstall_candidates() -> Vec<PathBuf> {
+    let [codex_home_path, dot_codex_path] = default_config_paths(
+        std::env::var("CODEX_HOME").ok().as_deref(),
+        dirs::home_dir().as_deref(),
+    );
+    let mut candidates = vec![dot_codex_path];
+    if !candidates.contains(&codex_home_path) {
+        candidates.insert(0, codex_home_path);
+    }
+    candidates
+}
+
+/// The config file at `path` before and after removing every `notify` that runs anot, at the
+/// top level and in profiles, as "Remove the notify configuration" in `anot init codex` would.
+/// Other commands and settings are kept. `None` when no `notify` runs anot.
+pub fn without_our_notify(path: &PathBuf) -> Result<Option<(String, String)>, Error> {
+    let (original, mut config) = read_config(path)?;
+    let mut targets = vec![NotifyTarget::Root];
+    targets.extend(
+        config
+            .profile_names()
+            .into_iter()
+            .map(NotifyTarget::Profile),
+    );
+    let ours: Vec<NotifyTarget> = targets
+        .into_iter()
+        .filter(|target| config.notify(target).is_some_and(|cmd| is_our_notify(&cmd)))
+        .collect();
+    if ours.is_empty() {
+        return Ok(None);
+    }
+
+    for target in &ours {
+        config.clear_notify(target);
+    }
+    let updated =
+        toml::to_string_pretty(&config).context("Failed to serialize the configuration to TOML")?;
+    Ok(Some((original, updated)))
+}
+
+/// Shows the changes from `original` and writes `config` once they're confirmed. Returns
+/// whether the file was written.
+#[instrument(skip(original, config))]
+fn write_config(
+    path: &PathBuf,
+    original: &str,
+    config: &CodexConfiguration,
+    dry_run: bool,
+) -> Result<bool, Error> {
+    let new_config =
+        toml::to_string_pretty(config).context("Failed to serialize the configuration to TOML")?;
+    let written = write_with_preview(path, original, &new_config, dry_run)?;
+    if written {
+        info!(path = %path.display(), "wrote Codex configuration");
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROFILES_FIXTURE: &str = r#"
+model = "gpt-5"
+
+[profiles.work]
+model = "o3"
+notify = ["/old/anot", "codex"]
+
+[profiles.personal]
+model = "gpt-5"
+approval_policy = "never"
+"#;
+
+    fn command() -> Vec<String> {
+        vec!["/usr/local/bin/anot".to_string(), "codex".to_string()]
+    }
+
+    fn round_trip(config: &CodexConfiguration) -> toml::Value {
+        toml::from_str(&toml::to_string_pretty(config).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn reports_notify_at_the_root_and_in_profiles() {
+        let mut config: CodexConfiguration = toml::from_str(PROFILES_FIXTURE).unwrap();
+        assert_eq!(config.profile_names(), ["personal", "work"]);
+        assert_eq!(
+            config.configured_notifies(),
+            [(
+                "profiles.work.notify".to_string(),
+                vec!["/old/anot".to_string(), "codex".to_string()]
+            )]
+        );
+
+        config.set_notify(&NotifyTarget::Root, command());
+        assert_eq!(
+            config
+                .configured_notifies()
+                .into_iter()
+                .map(|(key, _)| key)
+                .collect::<Vec<_>>(),
+            ["notify", "profiles.work.notify"]
+        );
+    }
+
+    #[test]
+    fn writes_notify_into_a_profile() {
+        let mut config: CodexConfiguration = toml::from_str(PROFILES_FIXTURE).unwrap();
+        let personal = NotifyTarget::Profile("personal".to_string());
+        config.set_notify(&personal, command());
+
+        let written = round_trip(&config);
+        assert_eq!(
+            written["profiles"]["personal"]["notify"],
+            toml::Value::try_from(command()).unwrap()
+        );
+        assert_eq!(
+            written["profiles"]["personal"]["approval_policy"].as_str(),
+            Some("never")
+        );
+        assert_eq!(written["profiles"]["work"]["model"].as_str(), Some("o3"));
+        assert!(written.get("notify").is_none(), "the root stays untouched");
+        assert_eq!(written["model"].as_str(), Some("gpt-5"));
+    }
+
+    #[test]
+    fn removes_notify_from_a_profile() {
+        let mut config: CodexConfiguration = toml::from_str(PROFILES_FIXTURE).unwrap();
+        config.set_notify(&NotifyTarget::Root, command());
+        config.clear_notify(&NotifyTarget::Profile("work".to_string()));
+
+        let written = round_trip(&config);
+        assert!(written["profiles"]["work"].get("notify").is_none());
+        assert_eq!(written["profiles"]["work"]["model"].as_str(), Some("o3"));
+        assert_eq!(written["notify"], toml::Value::try_from(command()).unwrap());
+    }
+
+    #[test]
+    fn recognizes_notify_commands_that_run_anot() {
+        assert!(is_our_notify(&command()));
+        assert!(is_our_notify(&[
+            "anot".to_string(),
+            "--config".to_string(),
+            "/tmp/a.json".to_string(),
+            "codex".to_string(),
+        ]));
+        assert!(!is_our_notify(&[
+            "notify-send".to_string(),
+            "codex".to_string()
+        ]));
+        assert!(!is_our_notify(&["/usr/local/bin/anot".to_string()]));
+        assert!(!is_our_notify(&[]));
+    }
+
+    #[test]
+    fn default_paths_are_built_from_the_home_dir() {
+        let home = PathBuf::from(r"C:\Users\me");
+        let dot_codex = home.join(".codex").join("config.toml");
+        assert_eq!(
+            default_config_paths(None, Some(&home)),
+            [dot_codex.clone(), dot_codex.clone()]
+        );
+        assert_eq!(
+            default_config_paths(Some(""), Some(&home)),
+            [dot_codex.clone(), dot_codex.clone()]
+        );
+        assert_eq!(
+            default_config_paths(Some(r"~\codex-work"), Some(&home)),
+            [
+                home.join("codex-work").join("config.toml"),
+                dot_codex.clone()
+            ]
+        );
+        assert_eq!(
+            default_config_paths(Some("/srv/codex"), Some(&home)),
+            [PathBuf::from("/srv/codex").join("config.toml"), dot_codex]
+        );
+    }
+
+    #[test]
+    fn unknown_profiles_are_rejected() {
+        let config: CodexConfiguration = toml::from_str(PROFILES_FIXTURE).unwrap();
+        assert_eq!(
+            choose_target(&config, Some("work")).unwrap(),
+            NotifyTarget::Profile("work".to_string())
+        );
+        assert_eq!(
+            choose_target(&config, Some("play"))
+                .unwrap_err()
+                .to_string(),
+            "Codex profile `play` not found. Available profiles: personal, work"
+        );
+        assert_eq!(
+            choose_target(&CodexConfiguration::default(), None).unwrap(),
+            NotifyTarget::Root
+        );
+    }
 }
@@ -1,25 +1,60 @@
-use anyhow::Error;
-#[cfg(not(target_os = "macos"))]
-use notify_rust::Notification;
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Error};
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+use notify_rust::{Hint, Notification};
 use tracing::{debug, error, info, instrument, warn};
 
+#[cfg(all(feature = "desktop", unix, not(target_os = "macos")))]
+use crate::grouping;
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+use crate::pretend;
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+use crate::processors::{codex::icon, icon::LARGEST};
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+use crate::processors::{codex::icon::CODEX_ICON, icon::icon_or_theme_name};
 use crate::{
-    configuration::Config,
-    processors::codex::icon::get_codex_icon_path,
-    processors::codex::structs::{CodexNotificationInput, NotificationType},
+    configuration::{
+        Config, DeliveryMode, InputMessages, InputMessagesPosition, NotificationSettings,
+        project::with_project_overlay,
+    },
+    delivery::{self, detached, dry_run, sound},
+    events::{self, Decision},
+    forward,
+    i18n::{interpolate, t},
+    logging, mute, privacy,
+    processors::codex::{
+        payload,
+        structs::{CodexNotificationInput, NotificationType},
+        turns,
+    },
+    sessions::{self, Activity},
+    text,
+    utils::{project_name, unix_now},
 };
 
+#[cfg_attr(
+    any(not(feature = "desktop"), target_os = "macos"),
+    allow(unused_variables)
+)]
 fn create_codex_notification(
-    summary: &str,
+    config: &Config,
+    turn_id: Option<&str>,
+    title: &str,
     body: &str,
-    #[cfg_attr(not(target_os = "macos"), allow(unused_variables))] config: &Config,
+    settings: &NotificationSettings,
 ) -> Result<(), Error> {
     debug!(
         body_len = body.len(),
-        pretend = config.codex.pretend,
+        pretend = ?settings.pretend,
+        sound = settings.sound,
         "preparing Codex notification"
     );
-    #[cfg(target_os = "macos")]
+    let played = sound::choose(settings, sound::CAN_PLAY);
+    #[cfg(all(feature = "desktop", target_os = "macos"))]
     {
         use mac_notification_sys::Notification;
         use mac_notification_sys::Sound;
@@ -28,68 +63,170 @@ fn create_codex_notification(
 
         let mut notification = Notification::new();
 
-        let title = format!("Codex: {}", &summary);
+        notification.title(title).message(body);
 
-        notification.title(&title).message(body).sound(true);
+        let icon_path = icon_path(settings).unwrap_or_default();
 
-        let icon_path = get_codex_icon_path().unwrap_or_default();
-
-        if let Some(bundle_id) = get_bundle_identifier("ChatGPT")
-            && config.codex.pretend
-        {
-            set_application(&bundle_id).ok();
-            debug!(bundle_id = %bundle_id, "using pretend app bundle for notification");
-        } else {
-            set_application("com.apple.Terminal").ok();
-            debug!("using Terminal bundle for notification");
+        let options = pretend::IdentityOptions {
+            app: "ChatGPT",
+            pretend: settings.pretend,
+            bundle_id: config.codex.bundle_id.as_deref(),
+            fallback_bundle_id: config.codex.fallback_bundle_id.as_deref(),
+        };
+        match pretend::set_identity(
+            config,
+            &options,
+            || get_bundle_identifier("ChatGPT"),
+            |bundle_id| set_application(bundle_id).is_ok(),
+        ) {
+            pretend::Identity::App(bundle_id) => {
+                debug!(bundle_id = %bundle_id, "using pretend app bundle for notification");
+            }
+            pretend::Identity::Fallback(bundle_id) => {
+                debug!(bundle_id = %bundle_id, "using fallback bundle for notification");
 
-            if let Some(s) = icon_path.to_str() {
-                notification.content_image(s);
-                debug!(icon = s, "attached icon to notification");
+                if let Some(s) = icon_path.to_str() {
+                    notification.content_image(s);
+                    debug!(icon = s, "attached icon to notification");
+                }
             }
-        };
+        }
 
-        if config.codex.sound {
+        if played == sound::Sound::System {
             notification.sound(Sound::Default);
         }
 
         notification.send()?;
         debug!("sent macOS notification (Codex)");
     }
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(all(feature = "desktop", not(target_os = "macos")))]
     {
         let mut notification = Notification::new();
 
-        let title = format!("Codex: {}", &summary);
+        notification.summary(title).body(body);
 
-        notification.summary(&title).body(body);
+        if played != sound::Sound::System {
+            notification.hint(Hint::SuppressSound(true));
+        }
 
-        if let Ok(p) = get_codex_icon_path()
-            && let Some(s) = p.to_str()
-        {
-            notification.icon(s);
-            debug!(icon = s, "attached icon to notification");
+        if let Ok(icon) = linux_icon(config, settings) {
+            notification.icon(&icon);
+            debug!(icon = %icon, "attached icon to notification");
         }
 
+        #[cfg(unix)]
+        grouping::show_in_group(config, &mut notification, "codex", turn_id, None)?;
+        #[cfg(not(unix))]
         notification.show()?;
         debug!("sent Linux notification (Codex)");
     }
+    sound::queue(&played, config.codex.sound_volume);
     Ok(())
 }
 
+/// The configured icon, or the largest built-in Codex icon, which macOS scales down itself.
+#[cfg(all(feature = "desktop", target_os = "macos"))]
+fn icon_path(settings: &NotificationSettings) -> Result<PathBuf, Error> {
+    icon::get_icon(settings.icon.as_deref(), LARGEST)
+}
+
+/// The configured icon, the `codex.icon_name` theme icon, or the built-in Codex icon in
+/// `icon_size`.
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
+fn linux_icon(config: &Config, settings: &NotificationSettings) -> Result<String, Error> {
+    icon_or_theme_name(
+        settings.icon.as_deref(),
+        config.codex.icon_name.as_deref(),
+        &CODEX_ICON,
+        config.icon_size,
+    )
+}
+
+/// Where `anot codex` read its payload from, for error messages.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PayloadSource {
+    /// The argument itself
+    Inline,
+    /// `--file <path>`
+    FileFlag(PathBuf),
+    /// An `@<path>` argument
+    AtFile(PathBuf),
+    /// An argument naming a JSON file
+    PathArgument(PathBuf),
+    Stdin,
+}
+
+impl fmt::Display for PayloadSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PayloadSource::Inline => write!(f, "the argument as inline JSON"),
+            PayloadSource::FileFlag(path) => write!(f, "--file {}", path.display()),
+            PayloadSource::AtFile(path) => write!(f, "the @file argument {}", path.display()),
+            PayloadSource::PathArgument(path) => {
+                write!(f, "the argument as a file path {}", path.display())
+            }
+            PayloadSource::Stdin => write!(f, "stdin"),
+        }
+    }
+}
+
+/// Reads the Codex payload: from `file`, from the file an `@<path>` argument names, from the
+/// file a bare argument names when it holds JSON, from the argument itself, or, without an
+/// argument, from stdin. Wrappers write long payloads to a file to stay under argv limits.
+pub fn read_codex_payload(
+    argument: Option<&str>,
+    file: Option<&Path>,
+    read_stdin: impl FnOnce() -> String,
+) -> Result<(String, PayloadSource), Error> {
+    let source = match (file, argument) {
+        (Some(path), _) => PayloadSource::FileFlag(path.to_path_buf()),
+        (None, Some(argument)) => match argument.strip_prefix('@') {
+            Some(path) => PayloadSource::AtFile(PathBuf::from(path)),
+            None => {
+                let path = Path::new(argument);
+                match read_payload_file(path) {
+                    Ok(content) if serde_json::from_str::<serde_json::Value>(&content).is_ok() => {
+                        debug!(path = %path.display(), "Codex argument names a payload file");
+                        return Ok((content, PayloadSource::PathArgument(path.to_path_buf())));
+                    }
+                    _ => return Ok((argument.to_string(), PayloadSource::Inline)),
+                }
+            }
+        },
+        (None, None) => return Ok((read_stdin(), PayloadSource::Stdin)),
+    };
+
+    let (PayloadSource::FileFlag(path) | PayloadSource::AtFile(path)) = &source else {
+        unreachable!("only file sources are left");
+    };
+    let content = read_payload_file(path)
+        .with_context(|| format!("Failed to read the Codex payload from {source}"))?;
+    Ok((content, source))
+}
+
+/// A payload file's content, without the byte order mark some editors write.
+fn read_payload_file(path: &Path) -> Result<String, Error> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(content
+        .strip_prefix('\u{feff}')
+        .map(String::from)
+        .unwrap_or(content))
+}
+
 #[instrument(skip(input, config), level = "debug")]
 pub fn process_codex_input(input: String, config: &Config) -> Result<(), Error> {
-    let payload = match serde_json::from_str::<CodexNotificationInput>(&input) {
-        Ok(v) => v,
+    let (mut payload, shape) = match payload::parse(&input) {
+        Ok(parsed) => parsed,
         Err(e) => {
             error!(error = %e, "failed to parse Codex notification JSON");
-            return Err(Error::msg(format!(
-                "Failed to parse Codex notification JSON: {e}"
-            )));
+            return Err(e);
         }
     };
+    let _hook = logging::hook_span("codex", None, payload.turn_id.as_deref()).entered();
+    events::identify(None, payload.turn_id.as_deref());
     info!(
         event_type = ?payload.r#type,
+        shape = shape.as_str(),
         has_last_assistant_message = payload
             .last_assistant_message
             .as_ref()
@@ -102,34 +239,295 @@ pub fn process_codex_input(input: String, config: &Config) -> Result<(), Error>
             .unwrap_or(0),
         "parsed Codex input"
     );
+    if !config.remote {
+        fill_missing_cwd(&mut payload);
+    }
+    // The receiving end runs elsewhere, so the payload carries the cwd filled in here.
+    if forward::hand_off(config, "codex", &serde_json::to_string(&payload)?) {
+        return Ok(());
+    }
+    // Codex doesn't identify its sessions, so turns in one directory count as one session.
+    if let Some(cwd) = payload.cwd.as_deref() {
+        sessions::observe(
+            config,
+            "codex",
+            cwd,
+            Some(cwd),
+            payload.r#type.as_str(),
+            Activity::default(),
+        );
+    }
+
+    // Deferred payloads are sent from another directory, so they keep the filled-in cwd.
+    if mute::intercept(
+        config,
+        "codex",
+        payload.r#type.as_str(),
+        &serde_json::to_string(&payload)?,
+    ) {
+        return Ok(());
+    }
+
+    if config.delivery == DeliveryMode::Detached {
+        // The background process inherits the cwd, so it fills in the same project directory
+        // (or, for a remote payload, none).
+        return detached::spawn_or(config, "codex", &input, || {
+            send_notification(&payload, config)
+        });
+    }
     send_notification(&payload, config)
 }
 
+/// Codex runs the notify command from the session's working directory, so the process cwd
+/// stands in for the project directory the payload doesn't carry. Not for payloads
+/// [`Config::remote`], sent from another machine.
+fn fill_missing_cwd(payload: &mut CodexNotificationInput) {
+    if payload.cwd.is_some() {
+        return;
+    }
+
+    match std::env::current_dir() {
+        Ok(dir) => {
+            debug!(cwd = %dir.display(), "using process cwd as Codex project directory");
+            payload.cwd = Some(dir.to_string_lossy().to_string());
+        }
+        Err(e) => debug!(error = %e, "process cwd unavailable; omitting project directory"),
+    }
+}
+
+/// Characters of the prompt shown in titles with `codex.title_includes_prompt`, and of each
+/// prompt `codex.input_messages` shows.
+const TITLE_PROMPT_CHARS: usize = 60;
+
+/// The `codex.titles` template for the notification's type with its placeholders filled in, or
+/// "Codex · Turn complete" followed by the project name (and the prompt, with
+/// `codex.title_includes_prompt`).
+fn notification_title(config: &Config, notification: &CodexNotificationInput) -> String {
+    let event = notification.r#type.as_str();
+    let summary = notification.r#type.display_name(config);
+    let project = notification.cwd.as_deref().and_then(project_name);
+    let prompt = title_prompt(config, notification);
+
+    if let Some(template) = config.codex.titles.get(event) {
+        return interpolate(
+            template,
+            &[
+                ("type", &event),
+                ("summary", &summary),
+                ("project", &project.unwrap_or_default()),
+                ("cwd", &notification.cwd.as_deref().unwrap_or_default()),
+                (
+                    "turn_id",
+                    &notification.turn_id.as_deref().unwrap_or_default(),
+                ),
+                ("prompt", &prompt.unwrap_or_default()),
+            ],
+        );
+    }
+
+    let prompt_in_title = config
+        .codex
+        .input_messages
+        .as_ref()
+        .is_some_and(|settings| settings.position == InputMessagesPosition::Title);
+    let (summary, prompt) = match (&notification.r#type, prompt) {
+        (NotificationType::AgentTurnComplete, Some(prompt)) if prompt_in_title => (prompt, None),
+        (_, prompt) => (
+            summary,
+            prompt.filter(|_| config.codex.title_includes_prompt),
+        ),
+    };
+    let title = match notification.r#type {
+        NotificationType::AgentTurnComplete => format!("Codex · {summary}"),
+        NotificationType::Unknown(_) => summary,
+    };
+    [Some(title), project, prompt]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+/// The start of the user's prompt on one line, or the prompts as `codex.input_messages` shows
+/// them, unless prompts are redacted.
+fn title_prompt(config: &Config, notification: &CodexNotificationInput) -> Option<String> {
+    if config.privacy.redact_prompts {
+        return None;
+    }
+    if let Some(settings) = &config.codex.input_messages {
+        return joined_prompts(config, notification, settings);
+    }
+    let prompt = notification
+        .input_messages
+        .as_ref()?
+        .join(" ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    if prompt.is_empty() {
+        return None;
+    }
+    Some(text::truncate_at_boundary(
+        &privacy::redact(config, &prompt),
+        TITLE_PROMPT_CHARS,
+    ))
+}
+
+/// The prompts shown in the body of a turn notification: as `codex.input_messages` shows them
+/// there, or when it's unset, all of them on one line.
+fn body_prompts(config: &Config, notification: &CodexNotificationInput) -> Option<String> {
+    if config.privacy.redact_prompts {
+        return None;
+    }
+    match &config.codex.input_messages {
+        Some(settings) if settings.position == InputMessagesPosition::Body => {
+            joined_prompts(config, notification, settings)
+        }
+        Some(_) => None,
+        None => {
+            let joined = notification.input_messages.as_ref()?.join(" ");
+            (!joined.trim().is_empty()).then(|| privacy::redact(config, &joined))
+        }
+    }
+}
+
+/// The first `settings.max` non-empty prompts, each on one line and shortened on its own, joined
+/// by `settings.separator`. `None` when there are none or they're ignored.
+fn joined_prompts(
+    config: &Config,
+    notification: &CodexNotificationInput,
+    settings: &InputMessages,
+) -> Option<String> {
+    if settings.position == InputMessagesPosition::Ignore {
+        return None;
+    }
+    let max = if settings.max == 0 {
+        usize::MAX
+    } else {
+        settings.max
+    };
+    let prompts: Vec<String> = notification
+        .input_messages
+        .as_ref()?
+        .iter()
+        .map(|message| message.split_whitespace().collect::<Vec<_>>().join(" "))
+        .filter(|message| !message.is_empty())
+        .take(max)
+        .map(|message| {
+            text::truncate_at_boundary(&privacy::redact(config, &message), TITLE_PROMPT_CHARS)
+        })
+        .collect();
+    (!prompts.is_empty()).then(|| prompts.join(&settings.separator))
+}
+
+/// Appends how long it's been since the previous turn in the same directory, and records this
+/// turn's end for the next one. Replays aren't recorded.
+fn with_timing(config: &Config, notification: &CodexNotificationInput, body: String) -> String {
+    if config.replay || config.dry_run {
+        return body;
+    }
+
+    let now = unix_now();
+    let since_last = match turns::record_end(
+        &turns::turns_path(config),
+        &turns::turn_key(notification),
+        notification.cwd.as_deref(),
+        now,
+    ) {
+        Ok(previous) => previous.map(|end| now.saturating_sub(end)),
+        Err(e) => {
+            warn!(error = %e, "failed to record Codex turn");
+            None
+        }
+    };
+
+    let suffix = turns::timing_suffix(config, since_last, Some(now));
+    if suffix.is_empty() {
+        body
+    } else {
+        format!("{body} {suffix}")
+    }
+}
+
+/// Whether this is the first turn-complete notification for its turn within
+/// `codex.turn_dedupe_window_secs`. Turns without an id are left to the generic dedupe.
+/// Replays always notify, and a dry run only checks.
+fn is_first_for_turn(config: &Config, notification: &CodexNotificationInput) -> bool {
+    let window = config.codex.turn_dedupe_window_secs;
+    let Some(turn_id) = notification.turn_id.as_deref() else {
+        return true;
+    };
+    if window == 0 || config.replay {
+        return true;
+    }
+
+    let path = turns::turns_path(config);
+    let checked = if config.dry_run {
+        turns::was_notified(&path, turn_id, unix_now(), window).map(|notified| !notified)
+    } else {
+        turns::record_notified(&path, turn_id, unix_now(), window)
+    };
+    match checked {
+        Ok(first) => first,
+        Err(e) => {
+            warn!(path = %path.display(), error = %e, "failed to check Codex turn notifications");
+            true
+        }
+    }
+}
+
 #[instrument(skip(notification, config), level = "debug")]
 pub fn send_notification(
     notification: &CodexNotificationInput,
     config: &Config,
 ) -> Result<(), Error> {
-    match notification.r#type {
+    let config = &with_project_overlay(config, notification.cwd.as_deref().map(Path::new));
+    match &notification.r#type {
         NotificationType::AgentTurnComplete => {
-            let preferred_message = notification
+            if !is_first_for_turn(config, notification) {
+                info!(
+                    turn_id = ?notification.turn_id,
+                    window = config.codex.turn_dedupe_window_secs,
+                    "skipping repeated notification for a Codex turn"
+                );
+                dry_run::report_suppressed(
+                    config,
+                    "codex",
+                    notification.r#type.as_str(),
+                    Decision::Duplicate,
+                    "repeat of a recently notified turn",
+                );
+                return Ok(());
+            }
+            let message = notification
                 .last_assistant_message
                 .as_ref()
-                .filter(|s| !s.trim().is_empty())
-                .cloned()
-                .or_else(|| {
-                    notification.input_messages.as_ref().map(|inputs| {
-                        let joined = inputs.join(" ");
-                        if joined.trim().is_empty() {
-                            "Turn Complete!".to_string()
-                        } else {
-                            joined
-                        }
-                    })
-                })
-                .unwrap_or_else(|| "Turn Complete!".to_string());
-
-            let body = format!("Turn Completed: {}", preferred_message);
+                .filter(|s| !s.trim().is_empty() && !config.privacy.redact_messages)
+                .map(|message| privacy::redact(config, message));
+            let prompts = body_prompts(config, notification);
+            let preferred_message = message
+                .clone()
+                .or_else(|| prompts.clone())
+                .unwrap_or_else(|| t(config, "codex.turn_complete", &[]));
+
+            let body = match (&message, &prompts) {
+                (Some(message), Some(prompt)) if config.codex.input_messages.is_some() => t(
+                    config,
+                    "codex.reply",
+                    &[("prompt", prompt), ("message", message)],
+                ),
+                _ => t(
+                    config,
+                    "codex.turn_completed",
+                    &[("message", &preferred_message)],
+                ),
+            };
+            let body = text::format_body(
+                &body,
+                config.codex.max_body_length,
+                config.codex.strip_markdown,
+            );
+            let body = with_timing(config, notification, body);
             let preview: String = preferred_message.chars().take(120).collect();
             info!("Codex: agent turn complete");
             debug!(
@@ -138,10 +536,22 @@ pub fn send_notification(
                 "chosen message"
             );
 
-            create_codex_notification(notification.r#type.as_str(), &body, config)?;
+            let event = notification.r#type.as_str();
+            let title = delivery::replay_title(config, &notification_title(config, notification));
+            let settings = config.codex.settings_for(event);
+            delivery::deliver(config, "codex", event, &title, &body, || {
+                create_codex_notification(
+                    config,
+                    notification.turn_id.as_deref(),
+                    &title,
+                    &body,
+                    &settings,
+                )
+            })?;
         }
-        NotificationType::Unknown => {
+        NotificationType::Unknown(raw) => {
             warn!(
+                r#type = %raw,
                 turn_id = ?notification.turn_id,
                 last_assistant_message_present = notification
                     .last_assistant_message
@@ -155,8 +565,419 @@ pub fn send_notification(
                     .unwrap_or(0),
                 "unknown Codex notification type"
             );
+            dry_run::report_suppressed(
+                config,
+                "codex",
+                notification.r#type.as_str(),
+                Decision::Filtered,
+                "unknown notification type",
+            );
         }
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logging::capture_logs;
+    use crate::test_support::{temp_config, temp_dir};
+
+    const PAYLOAD: &str = r#"{"type":"agent-turn-complete","turn-id":"abc123"}"#;
+
+    fn temp_payload(test_name: &str, content: &str) -> PathBuf {
+        let path = temp_dir("codex-payload", test_name).join("payload.json");
+        std::fs::write(&path, content).unwrap();
+        path
+    }
+
+    fn no_stdin() -> String {
+        panic!("stdin must not be read")
+    }
+
+    #[test]
+    fn payload_comes_from_the_argument_or_stdin() {
+        assert_eq!(
+            read_codex_payload(Some(PAYLOAD), None, no_stdin).unwrap(),
+            (PAYLOAD.to_string(), PayloadSource::Inline)
+        );
+        assert_eq!(
+            read_codex_payload(None, None, || PAYLOAD.to_string()).unwrap(),
+            (PAYLOAD.to_string(), PayloadSource::Stdin)
+        );
+    }
+
+    #[test]
+    fn payload_comes_from_a_file() {
+        let path = temp_payload("file", &format!("\u{feff}{PAYLOAD}\n"));
+        let argument = path.to_str().unwrap();
+        let expected = format!("{PAYLOAD}\n");
+
+        assert_eq!(
+            read_codex_payload(None, Some(&path), no_stdin).unwrap(),
+            (expected.clone(), PayloadSource::FileFlag(path.clone()))
+        );
+        assert_eq!(
+            read_codex_payload(Some(&format!("@{argument}")), None, no_stdin).unwrap(),
+            (expected.clone(), PayloadSource::AtFile(path.clone()))
+        );
+        assert_eq!(
+            read_codex_payload(Some(argument), None, no_stdin).unwrap(),
+            (expected.clone(), PayloadSource::PathArgument(path.clone()))
+        );
+        serde_json::from_str::<CodexNotificationInput>(&expected).unwrap();
+    }
+
+    #[test]
+    fn payload_errors_name_the_interpretation() {
+        let not_json = temp_payload("not-json", "hello");
+        assert_eq!(
+            read_codex_payload(Some(not_json.to_str().unwrap()), None, no_stdin).unwrap(),
+            (
+                not_json.to_string_lossy().to_string(),
+                PayloadSource::Inline
+            ),
+            "a file that isn't JSON isn't taken for the payload"
+        );
+
+        let error = read_codex_payload(Some("@/nonexistent/payload.json"), None, no_stdin)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Failed to read the Codex payload from the @file argument /nonexistent/payload.json"
+        );
+
+        let error = read_codex_payload(None, Some(Path::new("/nonexistent/p.json")), no_stdin)
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            error,
+            "Failed to read the Codex payload from --file /nonexistent/p.json"
+        );
+    }
+
+    fn turn_complete() -> CodexNotificationInput {
+        serde_json::from_str(r#"{"type":"agent-turn-complete","turn-id":"abc123"}"#).unwrap()
+    }
+
+    #[test]
+    fn title_includes_project_name_from_cwd() {
+        let project_dir = std::env::temp_dir().join("anot-codex-project");
+        let mut notification = turn_complete();
+        notification.cwd = Some(project_dir.to_string_lossy().to_string());
+
+        assert_eq!(
+            notification_title(&Config::default(), &notification),
+            "Codex · Turn complete · anot-codex-project"
+        );
+    }
+
+    #[test]
+    fn title_omits_project_without_cwd() {
+        assert_eq!(
+            notification_title(&Config::default(), &turn_complete()),
+            "Codex · Turn complete"
+        );
+    }
+
+    #[test]
+    fn titles_use_display_names() {
+        let config = Config::default();
+        let unknown: CodexNotificationInput =
+            serde_json::from_str(r#"{"type":"future-event"}"#).unwrap();
+
+        assert_eq!(notification_title(&config, &unknown), "Codex notification");
+        assert_eq!(
+            notification_title(
+                &Config {
+                    language: "ja".to_string(),
+                    ..Config::default()
+                },
+                &turn_complete()
+            ),
+            "Codex · ターン完了"
+        );
+    }
+
+    #[test]
+    fn titles_include_the_prompt_when_enabled() {
+        let mut config = Config::default();
+        let mut notification = turn_complete();
+        notification.input_messages = Some(vec![
+            "Fix the flaky\nlogin test".to_string(),
+            "and then update the changelog with a note about the retry logic".to_string(),
+        ]);
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete"
+        );
+
+        config.codex.title_includes_prompt = true;
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete · Fix the flaky login test and then update the changelog with…"
+        );
+
+        config.privacy.redact_prompts = true;
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete"
+        );
+    }
+
+    fn with_prompts(prompts: &[&str]) -> CodexNotificationInput {
+        let mut notification = turn_complete();
+        notification.input_messages = Some(prompts.iter().map(|p| p.to_string()).collect());
+        notification
+    }
+
+    #[test]
+    fn input_messages_are_shortened_one_by_one() {
+        let mut config = Config::default();
+        let settings = InputMessages::default();
+        let notification = with_prompts(&[
+            "  ",
+            "fix the login bug",
+            "",
+            "and then update the changelog with a note about the retry logic\nand the timeout",
+            "also bump the version",
+        ]);
+
+        assert_eq!(
+            joined_prompts(&config, &notification, &settings).as_deref(),
+            Some(
+                "fix the login bug · and then update the changelog with a note about the retry l…"
+            )
+        );
+        let all = InputMessages {
+            max: 0,
+            separator: " / ".to_string(),
+            ..settings.clone()
+        };
+        assert!(
+            joined_prompts(&config, &notification, &all)
+                .unwrap()
+                .ends_with("retry l… / also bump the version")
+        );
+        assert_eq!(
+            joined_prompts(&config, &with_prompts(&[" ", "\n"]), &settings),
+            None
+        );
+
+        config.privacy.redact_patterns = vec!["login".to_string()];
+        assert_eq!(
+            joined_prompts(&config, &with_prompts(&["fix the login bug"]), &settings).as_deref(),
+            Some("fix the ••• bug")
+        );
+    }
+
+    #[test]
+    fn input_messages_go_where_configured() {
+        let mut config = Config::default();
+        let notification = with_prompts(&["fix the login bug", "and the signup one"]);
+        let one_line = Some("fix the login bug and the signup one".to_string());
+        assert_eq!(body_prompts(&config, &notification), one_line);
+
+        config.codex.input_messages = Some(InputMessages::default());
+        assert_eq!(
+            body_prompts(&config, &notification).as_deref(),
+            Some("fix the login bug · and the signup one")
+        );
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete"
+        );
+
+        let position = |position| InputMessages {
+            max: 1,
+            position,
+            ..InputMessages::default()
+        };
+        config.codex.input_messages = Some(position(InputMessagesPosition::Title));
+        assert_eq!(body_prompts(&config, &notification), None);
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · fix the login bug"
+        );
+        config.codex.title_includes_prompt = true;
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · fix the login bug"
+        );
+        assert_eq!(
+            notification_title(&config, &with_prompts(&[" "])),
+            "Codex · Turn complete"
+        );
+
+        config.codex.input_messages = Some(position(InputMessagesPosition::Ignore));
+        assert_eq!(body_prompts(&config, &notification), None);
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete"
+        );
+
+        config.codex.input_messages = Some(position(InputMessagesPosition::Title));
+        config.privacy.redact_prompts = true;
+        assert_eq!(
+            notification_title(&config, &notification),
+            "Codex · Turn complete"
+        );
+    }
+
+    #[test]
+    fn the_reply_names_the_request() {
+        let mut config = Config {
+            backends: vec![crate::configuration::Backend::Statusfile],
+            ..temp_config("codex", "reply")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        config.codex.input_messages = Some(InputMessages::default());
+        let last_body = |config: &Config| {
+            let history = std::fs::read_to_string(crate::history::history_path(config)).unwrap();
+            let last: serde_json::Value =
+                serde_json::from_str(history.lines().last().unwrap()).unwrap();
+            last["body"].as_str().unwrap().to_string()
+        };
+
+        let mut notification = with_prompts(&["fix the login bug"]);
+        notification.last_assistant_message = Some("Done, I refactored the session check.".into());
+        send_notification(&notification, &config).unwrap();
+        assert_eq!(
+            last_body(&config),
+            "Re: fix the login bug — Done, I refactored the session check."
+        );
+
+        notification.turn_id = Some("def456".to_string());
+        notification.last_assistant_message = None;
+        send_notification(&notification, &config).unwrap();
+        // Followed by the time since the turn above.
+        assert!(last_body(&config).starts_with("Turn Completed: fix the login bug ("));
+    }
+
+    #[test]
+    fn configured_titles_fill_placeholders() {
+        let mut config = Config::default();
+        config.codex.titles.insert(
+            "AgentTurnComplete".to_string(),
+            "{project}: {summary} ({turn_id})".to_string(),
+        );
+        let mut notification = turn_complete();
+        notification.cwd = Some("/work/api".to_string());
+
+        assert_eq!(
+            notification_title(&config, &notification),
+            "api: Turn complete (abc123)"
+        );
+    }
+
+    #[test]
+    fn missing_cwd_is_filled_from_process_cwd() {
+        let mut notification = turn_complete();
+        fill_missing_cwd(&mut notification);
+
+        let expected = std::env::current_dir().unwrap();
+        assert_eq!(
+            notification.cwd,
+            Some(expected.to_string_lossy().to_string())
+        );
+    }
+
+    #[test]
+    fn payload_cwd_takes_precedence_over_process_cwd() {
+        let mut notification = turn_complete();
+        notification.cwd = Some("/work/my-api".to_string());
+        fill_missing_cwd(&mut notification);
+
+        assert_eq!(notification.cwd.as_deref(), Some("/work/my-api"));
+    }
+
+    #[test]
+    fn redaction_reaches_logs_and_history() {
+        let mut config = Config {
+            backends: vec![crate::configuration::Backend::Statusfile],
+            ..temp_config("codex", "privacy")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        config.privacy.redact_patterns = vec![r"sk-[a-z0-9]+".to_string()];
+
+        let mut notification = turn_complete();
+        notification.last_assistant_message = Some("Rotated the key sk-abc123 for you".to_string());
+
+        let logs = capture_logs(|| send_notification(&notification, &config).unwrap());
+        let history = std::fs::read_to_string(crate::history::history_path(&config)).unwrap();
+
+        assert!(logs.contains("Rotated the key ••• for you"), "{logs}");
+        assert!(history.contains("Turn Completed: Rotated the key ••• for you"));
+        for recorded in [&logs, &history] {
+            assert!(!recorded.contains("sk-abc123"));
+        }
+
+        config.privacy.redact_messages = true;
+        config.privacy.redact_prompts = true;
+        notification.input_messages = Some(vec!["my prompt".to_string()]);
+        notification.turn_id = Some("def456".to_string());
+        let logs = capture_logs(|| send_notification(&notification, &config).unwrap());
+        assert!(logs.contains("preview=\"Turn Complete!\""), "{logs}");
+        assert!(!logs.contains("Rotated") && !logs.contains("my prompt"));
+    }
+
+    #[test]
+    fn a_retried_turn_is_notified_once() {
+        let mut config = Config {
+            backends: vec![crate::configuration::Backend::Statusfile],
+            dedupe_window_secs: 0,
+            ..temp_config("codex", "retry")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        let sent = |config: &Config| {
+            std::fs::read_to_string(crate::history::history_path(config))
+                .unwrap_or_default()
+                .lines()
+                .count()
+        };
+
+        let mut notification = turn_complete();
+        send_notification(&notification, &config).unwrap();
+        let logs = capture_logs(|| send_notification(&notification, &config).unwrap());
+        assert!(logs.contains("skipping repeated notification"), "{logs}");
+        assert_eq!(sent(&config), 1);
+
+        notification.turn_id = Some("def456".to_string());
+        send_notification(&notification, &config).unwrap();
+        assert_eq!(sent(&config), 2);
+
+        notification.turn_id = None;
+        send_notification(&notification, &config).unwrap();
+        send_notification(&notification, &config).unwrap();
+        assert_eq!(
+            sent(&config),
+            4,
+            "without an id only the generic dedupe applies"
+        );
+    }
+
+    #[test]
+    fn every_line_logged_for_a_turn_names_it() {
+        let mut config = Config {
+            backends: vec![crate::configuration::Backend::Statusfile],
+            ..temp_config("codex", "span")
+        };
+        config.terminal_escape.auto_over_ssh = false;
+        let input =
+            r#"{"type":"agent-turn-complete","turn-id":"7c9e6679-7425-40de","cwd":"/work/api"}"#;
+
+        let logs = capture_logs(|| process_codex_input(input.to_string(), &config).unwrap());
+
+        assert!(logs.contains("parsed Codex input"), "{logs}");
+        for line in logs.lines() {
+            assert!(
+                line.contains(
+                    r#"hook{agent="codex" turn="7c9e6679" turn_id="7c9e6679-7425-40de"}"#
+                ),
+                "{line}"
+            );
+        }
+    }
+}
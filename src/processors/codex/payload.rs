@@ -0,0 +1,220 @@
+//! The shapes Codex payloads come in. Current builds send the fields flat with kebab-case keys;
+//! some wrap them in `{"notification": {...}}`, and older ones used snake_case or camelCase
+//! keys. The shapes are tried in the order of [`PayloadShape::ALL`].
+
+use std::fmt;
+
+use anyhow::Error;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::processors::codex::structs::{CodexNotificationInput, NotificationType};
+
+/// How much of a payload that matched no shape is quoted in the error.
+const SNIPPET_CHARS: usize = 200;
+
+/// Keys older builds used in place of the kebab-case ones.
+const LEGACY_KEYS: &[&str] = &[
+    "turn_id",
+    "turnId",
+    "input_messages",
+    "inputMessages",
+    "last_assistant_message",
+    "lastAssistantMessage",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadShape {
+    /// `{"type": "agent-turn-complete", "turn-id": ...}`
+    Flat,
+    /// `{"notification": {...}}`, with either of the other shapes inside.
+    Envelope,
+    /// `{"type": "agent_turn_complete", "turn_id": ...}`, or camelCase.
+    Legacy,
+}
+
+impl PayloadShape {
+    pub const ALL: [PayloadShape; 3] = [
+        PayloadShape::Flat,
+        PayloadShape::Envelope,
+        PayloadShape::Legacy,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PayloadShape::Flat => "flat",
+            PayloadShape::Envelope => "envelope",
+            PayloadShape::Legacy => "legacy",
+        }
+    }
+
+    /// `value` parsed as this shape, or `None` when it's clearly another one.
+    fn parse(self, value: &Value) -> Option<Result<CodexNotificationInput, serde_json::Error>> {
+        let object = value.as_object()?;
+        match self {
+            PayloadShape::Flat => {
+                (!is_legacy(object)).then(|| CodexNotificationInput::deserialize(value))
+            }
+            PayloadShape::Envelope => {
+                let inner = object
+                    .get("notification")
+                    .filter(|_| !object.contains_key("type"))?;
+                PayloadShape::Flat
+                    .parse(inner)
+                    .or_else(|| PayloadShape::Legacy.parse(inner))
+            }
+            PayloadShape::Legacy => is_legacy(object)
+                .then(|| LegacyInput::deserialize(value).map(CodexNotificationInput::from)),
+        }
+    }
+}
+
+/// The fields as older builds named them. The type is kebab-cased before it's matched.
+#[derive(Deserialize)]
+struct LegacyInput {
+    r#type: String,
+    #[serde(default, alias = "turnId")]
+    turn_id: Option<String>,
+    #[serde(default, alias = "inputMessages")]
+    input_messages: Option<Vec<String>>,
+    #[serde(default, alias = "lastAssistantMessage")]
+    last_assistant_message: Option<String>,
+    #[serde(default)]
+    cwd: Option<String>,
+}
+
+impl From<LegacyInput> for CodexNotificationInput {
+    fn from(legacy: LegacyInput) -> Self {
+        let r#type = match NotificationType::from_raw(kebab_case(&legacy.r#type)) {
+            NotificationType::Unknown(_) => NotificationType::Unknown(legacy.r#type),
+            known => known,
+        };
+        CodexNotificationInput {
+            r#type,
+            turn_id: legacy.turn_id,
+            input_messages: legacy.input_messages,
+            last_assistant_message: legacy.last_assistant_message,
+            cwd: legacy.cwd,
+        }
+    }
+}
+
+/// Whether `object` uses the keys or type names of older builds.
+fn is_legacy(object: &Map<String, Value>) -> bool {
+    let legacy_type = object
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|r#type| r#type.contains('_') || r#type.contains(char::is_uppercase));
+    legacy_type || LEGACY_KEYS.iter().any(|key| object.contains_key(*key))
+}
+
+/// `agent_turn_complete` and `AgentTurnComplete` as `agent-turn-complete`.
+fn kebab_case(name: &str) -> String {
+    let mut kebab = String::with_capacity(name.len() + 4);
+    for c in name.chars() {
+        if c == '_' {
+            kebab.push('-');
+        } else if c.is_uppercase() {
+            if !kebab.is_empty() && !kebab.ends_with('-') {
+                kebab.push('-');
+            }
+            kebab.extend(c.to_lowercase());
+        } else {
+            kebab.push(c);
+        }
+    }
+    kebab
+}
+
+/// Parses a Codex payload of any known shape and says which one it was.
+pub fn parse(input: &str) -> Result<(CodexNotificationInput, PayloadShape), Error> {
+    let value: Value = serde_json::from_str(input).map_err(|e| parse_error(&e, input))?;
+
+    let mut first_error = None;
+    for shape in PayloadShape::ALL {
+        match shape.parse(&value) {
+            Some(Ok(payload)) => return Ok((payload, shape)),
+            Some(Err(e)) => {
+                first_error.get_or_insert(e.to_string());
+            }
+            None => {}
+        }
+    }
+    let reason = first_error.unwrap_or_else(|| "expected a JSON object".to_string());
+    Err(parse_error(&reason, input))
+}
+
+fn parse_error(reason: &dyn fmt::Display, input: &str) -> Error {
+    let snippet: String = input.chars().take(SNIPPET_CHARS).collect();
+    Error::msg(format!(
+        "Failed to parse Codex notification JSON: {reason} (input: {snippet})"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expected() -> CodexNotificationInput {
+        CodexNotificationInput {
+            r#type: NotificationType::AgentTurnComplete,
+            turn_id: Some("t1".to_string()),
+            input_messages: Some(vec!["Fix the build".to_string()]),
+            last_assistant_message: Some("Fixed it.".to_string()),
+            cwd: None,
+        }
+    }
+
+    #[test]
+    fn accepts_the_flat_shape() {
+        let input = r#"{"type": "agent-turn-complete", "turn-id": "t1",
+            "input-messages": ["Fix the build"], "last-assistant-message": "Fixed it."}"#;
+        assert_eq!(parse(input).unwrap(), (expected(), PayloadShape::Flat));
+    }
+
+    #[test]
+    fn accepts_an_envelope() {
+        let input = r#"{"notification": {"type": "agent-turn-complete", "turn-id": "t1",
+            "input-messages": ["Fix the build"], "last-assistant-message": "Fixed it."}}"#;
+        assert_eq!(parse(input).unwrap(), (expected(), PayloadShape::Envelope));
+
+        let input = r#"{"notification": {"type": "agent_turn_complete", "turn_id": "t1",
+            "input_messages": ["Fix the build"], "last_assistant_message": "Fixed it."}}"#;
+        assert_eq!(parse(input).unwrap(), (expected(), PayloadShape::Envelope));
+    }
+
+    #[test]
+    fn accepts_legacy_keys() {
+        let input = r#"{"type": "agent_turn_complete", "turn_id": "t1",
+            "input_messages": ["Fix the build"], "last_assistant_message": "Fixed it."}"#;
+        assert_eq!(parse(input).unwrap(), (expected(), PayloadShape::Legacy));
+
+        let input = r#"{"type": "AgentTurnComplete", "turnId": "t1",
+            "inputMessages": ["Fix the build"], "lastAssistantMessage": "Fixed it."}"#;
+        assert_eq!(parse(input).unwrap(), (expected(), PayloadShape::Legacy));
+
+        let (payload, _) = parse(r#"{"type": "approval_requested", "turn_id": "t1"}"#).unwrap();
+        assert_eq!(
+            payload.r#type,
+            NotificationType::Unknown("approval_requested".to_string())
+        );
+    }
+
+    #[test]
+    fn reports_what_matched_no_shape() {
+        let error = parse(r#"{"kind": "agent-turn-complete"}"#).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Failed to parse Codex notification JSON: missing field `type` \
+             (input: {\"kind\": \"agent-turn-complete\"})"
+        );
+
+        let long = format!("[{}1]", "1,".repeat(500));
+        let error = parse(&long).unwrap_err().to_string();
+        assert!(error.contains("expected a JSON object"), "{error}");
+        assert!(
+            error.ends_with(&format!("{})", &long[..SNIPPET_CHARS])),
+            "{error}"
+        );
+    }
+}
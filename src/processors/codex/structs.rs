@@ -1,30 +1,73 @@
 use std::fmt;
 
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
-#[serde(rename_all = "kebab-case")]
+use crate::{configuration::Config, i18n::t};
+
+/// The `type` of a Codex notification, serialized as Codex sends it (kebab-case).
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum NotificationType {
     AgentTurnComplete,
-    #[serde(other)]
-    Unknown,
+    /// A type this version doesn't know, with the string Codex sent.
+    Unknown(String),
+}
+
+impl<'de> Deserialize<'de> for NotificationType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(NotificationType::from_raw(String::deserialize(
+            deserializer,
+        )?))
+    }
+}
+
+impl Serialize for NotificationType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.raw())
+    }
 }
 
 impl fmt::Display for NotificationType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let name = match self {
-            NotificationType::AgentTurnComplete => "AgentTurnComplete",
-            NotificationType::Unknown => "Unknown",
-        };
-        write!(f, "{}", name)
+        match self {
+            NotificationType::AgentTurnComplete => write!(f, "AgentTurnComplete"),
+            NotificationType::Unknown(raw) => write!(f, "Unknown({raw:?})"),
+        }
     }
 }
 
 impl NotificationType {
+    /// The type Codex sent as `raw`, e.g. `agent-turn-complete`.
+    pub fn from_raw(raw: String) -> NotificationType {
+        match raw.as_str() {
+            "agent-turn-complete" => NotificationType::AgentTurnComplete,
+            _ => NotificationType::Unknown(raw),
+        }
+    }
+
+    /// The name logs, history and config keys use: `AgentTurnComplete`, or the type Codex sent
+    /// when it's unknown (`Unknown` when that was empty).
     pub fn as_str(&self) -> &str {
         match self {
             NotificationType::AgentTurnComplete => "AgentTurnComplete",
-            NotificationType::Unknown => "Unknown",
+            NotificationType::Unknown(raw) if raw.is_empty() => "Unknown",
+            NotificationType::Unknown(raw) => raw,
+        }
+    }
+
+    /// The type as Codex sends it, e.g. `agent-turn-complete`.
+    pub fn raw(&self) -> &str {
+        match self {
+            NotificationType::AgentTurnComplete => "agent-turn-complete",
+            NotificationType::Unknown(raw) => raw,
+        }
+    }
+
+    /// What titles call the type, e.g. "Turn complete". `as_str` is the name logs and config
+    /// keys use.
+    pub fn display_name(&self, config: &Config) -> String {
+        match self {
+            NotificationType::AgentTurnComplete => t(config, "codex.title.turn_complete", &[]),
+            NotificationType::Unknown(_) => t(config, "codex.title.unknown", &[]),
         }
     }
 }
@@ -39,4 +82,49 @@ pub struct CodexNotificationInput {
     pub input_messages: Option<Vec<String>>,
     #[serde(default)]
     pub last_assistant_message: Option<String>,
+    /// Working directory of the Codex session. Codex doesn't send one, so it is filled in from
+    /// the process cwd (Codex runs `notify` from the session's directory).
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(r#type: &str) -> NotificationType {
+        serde_json::from_value(serde_json::json!(r#type)).unwrap()
+    }
+
+    #[test]
+    fn known_types_are_recognized() {
+        assert_eq!(
+            parse("agent-turn-complete"),
+            NotificationType::AgentTurnComplete
+        );
+        assert_eq!(
+            serde_json::to_value(NotificationType::AgentTurnComplete).unwrap(),
+            "agent-turn-complete"
+        );
+    }
+
+    #[test]
+    fn unknown_types_keep_what_codex_sent() {
+        let r#type = parse("approval-requested");
+        assert_eq!(
+            r#type,
+            NotificationType::Unknown("approval-requested".to_string())
+        );
+        assert_eq!(r#type.as_str(), "approval-requested");
+        assert_eq!(r#type.to_string(), r#"Unknown("approval-requested")"#);
+        assert_eq!(serde_json::to_value(&r#type).unwrap(), "approval-requested");
+    }
+
+    #[test]
+    fn an_empty_type_is_unknown() {
+        let r#type = parse("");
+        assert_eq!(r#type, NotificationType::Unknown(String::new()));
+        assert_eq!(r#type.as_str(), "Unknown");
+        assert_eq!(serde_json::to_value(&r#type).unwrap(), "");
+    }
 }
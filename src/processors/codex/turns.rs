@@ -0,0 +1,292 @@
+//! When Codex turns start and end, kept so turn notifications can say how long the agent
+//! worked. Codex only reports completed turns today, so the time since the previous turn in
+//! the same directory stands in for the turn's duration.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    configuration::Config,
+    i18n::t,
+    processors::codex::structs::CodexNotificationInput,
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::{format_duration, local_clock},
+};
+
+const TURNS_FILE_NAME: &str = "codex-turns.json";
+
+/// Turns kept in the state file; older ones are dropped.
+const MAX_TURNS: usize = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Turn {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// When the turn started. Codex has no turn-start notification yet, so this stays unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub start: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<u64>,
+    /// When a turn-complete notification for the turn last went out.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notified: Option<u64>,
+}
+
+impl Turn {
+    /// When anything last happened to the turn, which decides which turns are dropped.
+    fn last_seen(&self) -> u64 {
+        [self.start, self.end, self.notified]
+            .into_iter()
+            .flatten()
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Turns {
+    #[serde(default)]
+    turns: BTreeMap<String, Turn>,
+}
+
+pub fn turns_path(config: &Config) -> PathBuf {
+    config.data_dir().join(TURNS_FILE_NAME)
+}
+
+/// The state key of a turn: its `turn-id`, or its directory when Codex didn't send one.
+pub fn turn_key(notification: &CodexNotificationInput) -> String {
+    match &notification.turn_id {
+        Some(turn_id) => turn_id.clone(),
+        None => format!("cwd:{}", notification.cwd.as_deref().unwrap_or_default()),
+    }
+}
+
+/// Records that the turn `key` in `cwd` ended at `now`. Returns when the previous turn in the
+/// same directory ended, if one was recorded. Turns keyed on their directory share one entry,
+/// whose last end is the previous turn's.
+pub fn record_end(
+    path: &Path,
+    key: &str,
+    cwd: Option<&str>,
+    now: u64,
+) -> Result<Option<u64>, Error> {
+    with_lock(path, || {
+        let mut state: Turns = read_json_or_default(path)?;
+
+        let previous_end = state
+            .turns
+            .values()
+            .filter(|turn| turn.cwd.as_deref() == cwd)
+            .filter_map(|turn| turn.end)
+            .max();
+
+        let turn = state.turns.entry(key.to_string()).or_default();
+        turn.cwd = cwd.map(String::from);
+        turn.end = Some(now);
+
+        prune(&mut state);
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+        Ok(previous_end)
+    })
+}
+
+/// Whether a notification for `turn_id` went out within `window` seconds before `now`. Nothing
+/// is recorded.
+pub fn was_notified(path: &Path, turn_id: &str, now: u64, window: u64) -> Result<bool, Error> {
+    let state: Turns = read_json_or_default(path)?;
+    Ok(state
+        .turns
+        .get(turn_id)
+        .and_then(|turn| turn.notified)
+        .is_some_and(|notified| now.saturating_sub(notified) < window))
+}
+
+/// Records that a notification for `turn_id` goes out at `now`, unless one already did within
+/// `window` seconds: Codex retries a failed turn under the same id. Returns whether to notify.
+pub fn record_notified(path: &Path, turn_id: &str, now: u64, window: u64) -> Result<bool, Error> {
+    with_lock(path, || {
+        let mut state: Turns = read_json_or_default(path)?;
+
+        let turn = state.turns.entry(turn_id.to_string()).or_default();
+        if turn
+            .notified
+            .is_some_and(|notified| now.saturating_sub(notified) < window)
+        {
+            return Ok(false);
+        }
+        turn.notified = Some(now);
+
+        prune(&mut state);
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+        Ok(true)
+    })
+}
+
+/// Keeps the `MAX_TURNS` most recent turns.
+fn prune(state: &mut Turns) {
+    if state.turns.len() <= MAX_TURNS {
+        return;
+    }
+    let mut seen: Vec<_> = state.turns.values().map(Turn::last_seen).collect();
+    seen.sort_unstable();
+    let cutoff = seen[seen.len() - MAX_TURNS];
+    state.turns.retain(|_, turn| turn.last_seen() >= cutoff);
+}
+
+/// The timing appended to a turn notification body, e.g. "(since last turn: 4m 12s) · completed
+/// at 14:32". Empty when there's nothing to show.
+pub fn timing_suffix(
+    config: &Config,
+    since_last: Option<u64>,
+    completed_at: Option<u64>,
+) -> String {
+    let since_last = since_last.map(|secs| {
+        t(
+            config,
+            "codex.since_last_turn",
+            &[("duration", &format_duration(secs))],
+        )
+    });
+    let completed_at = completed_at
+        .filter(|_| config.codex.show_timestamps)
+        .map(|at| t(config, "codex.completed_at", &[("time", &local_clock(at))]));
+
+    [since_last, completed_at]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_turns_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("turns", test_name).join(TURNS_FILE_NAME)
+    }
+
+    #[test]
+    fn turn_ends_round_trip_per_directory() {
+        let path = temp_turns_path("round-trip");
+
+        assert_eq!(
+            record_end(&path, "t1", Some("/work/api"), 1000).unwrap(),
+            None
+        );
+        assert_eq!(
+            record_end(&path, "t2", Some("/work/web"), 1100).unwrap(),
+            None
+        );
+        assert_eq!(
+            record_end(&path, "t3", Some("/work/api"), 1252).unwrap(),
+            Some(1000)
+        );
+        assert_eq!(
+            record_end(&path, "t4", Some("/work/api"), 1300).unwrap(),
+            Some(1252)
+        );
+
+        let state: Turns = read_json_or_default(&path).unwrap();
+        assert_eq!(
+            state.turns["t3"],
+            Turn {
+                cwd: Some("/work/api".to_string()),
+                start: None,
+                end: Some(1252),
+                notified: None,
+            }
+        );
+    }
+
+    #[test]
+    fn a_retried_turn_notifies_once_per_window() {
+        let path = temp_turns_path("notified");
+
+        assert!(record_notified(&path, "t1", 1000, 60).unwrap());
+        assert!(was_notified(&path, "t1", 1030, 60).unwrap());
+        assert!(
+            !record_notified(&path, "t1", 1030, 60).unwrap(),
+            "same turn id"
+        );
+        assert!(
+            record_notified(&path, "t2", 1030, 60).unwrap(),
+            "different turn id"
+        );
+        assert!(!was_notified(&path, "t1", 1060, 60).unwrap());
+        assert!(
+            record_notified(&path, "t1", 1060, 60).unwrap(),
+            "the window expired"
+        );
+        assert!(!record_notified(&path, "t1", 1100, 60).unwrap());
+    }
+
+    #[test]
+    fn notifying_keeps_the_turn_end() {
+        let path = temp_turns_path("notified-end");
+        record_end(&path, "t1", Some("/work/api"), 1000).unwrap();
+        record_notified(&path, "t1", 1000, 60).unwrap();
+
+        let state: Turns = read_json_or_default(&path).unwrap();
+        assert_eq!(state.turns["t1"].end, Some(1000));
+        assert_eq!(state.turns["t1"].notified, Some(1000));
+    }
+
+    #[test]
+    fn turns_without_an_id_are_keyed_on_the_directory() {
+        let mut notification: CodexNotificationInput =
+            serde_json::from_str(r#"{"type":"agent-turn-complete","turn-id":"abc123"}"#).unwrap();
+        assert_eq!(turn_key(&notification), "abc123");
+
+        notification.turn_id = None;
+        notification.cwd = Some("/work/api".to_string());
+        let key = turn_key(&notification);
+        assert_eq!(key, "cwd:/work/api");
+
+        let path = temp_turns_path("fallback");
+        record_end(&path, &key, Some("/work/api"), 1000).unwrap();
+        assert_eq!(
+            record_end(&path, &key, Some("/work/api"), 1600).unwrap(),
+            Some(1000)
+        );
+    }
+
+    #[test]
+    fn old_turns_are_dropped() {
+        let path = temp_turns_path("prune");
+        for i in 0..(MAX_TURNS as u64 + 20) {
+            record_end(&path, &format!("t{i}"), None, 1000 + i).unwrap();
+        }
+
+        let state: Turns = read_json_or_default(&path).unwrap();
+        assert_eq!(state.turns.len(), MAX_TURNS);
+        assert!(!state.turns.contains_key("t0"));
+    }
+
+    #[test]
+    fn formats_the_timing_suffix() {
+        let mut config = Config::default();
+        assert_eq!(
+            timing_suffix(&config, Some(252), Some(0)),
+            "(since last turn: 4m 12s)"
+        );
+        assert_eq!(timing_suffix(&config, None, Some(0)), "");
+
+        config.codex.show_timestamps = true;
+        let at = local_clock(0);
+        assert_eq!(
+            timing_suffix(&config, Some(45), Some(0)),
+            format!("(since last turn: 45s) · completed at {at}")
+        );
+        assert_eq!(
+            timing_suffix(&config, None, Some(0)),
+            format!("completed at {at}")
+        );
+    }
+}
@@ -0,0 +1,255 @@
+//! The parts of `anot init` every agent shares: picking the file to set up, asking before
+//! creating it, and turning prompt errors into messages.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use inquire::{Confirm, InquireError, Select};
+use tracing::{debug, info, instrument, warn};
+
+use crate::{
+    error::{AnotError, prompt_error},
+    utils::expand_tilde,
+};
+
+/// `err` from a prompt as an error for the user. Cancelling or interrupting keeps its own
+/// message; anything else is described by `context`, with `err` as its cause.
+pub fn handle_inquire_error(err: InquireError, context: &str) -> Error {
+    prompt_error(err, context).into()
+}
+
+/// A file `anot init` offers to set up, with what it's called and whether it exists.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathCandidate {
+    pub label: String,
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Shown next to the choice, and logged when it's picked.
+    pub warning: Option<&'static str>,
+}
+
+impl PathCandidate {
+    pub fn new(label: impl Into<String>, path: PathBuf, exists: bool) -> Self {
+        PathCandidate {
+            label: label.into(),
+            path,
+            exists,
+            warning: None,
+        }
+    }
+
+    pub fn with_warning(mut self, warning: &'static str) -> Self {
+        self.warning = Some(warning);
+        self
+    }
+}
+
+enum PathChoice {
+    Candidate(PathCandidate),
+    CustomPath,
+}
+
+impl fmt::Display for PathChoice {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathChoice::Candidate(candidate) => {
+                let status = if candidate.exists { "✓" } else { "✗" };
+                write!(
+                    f,
+                    "{} {} ({})",
+                    status,
+                    candidate.label,
+                    candidate.path.display()
+                )?;
+                if let Some(warning) = candidate.warning {
+                    write!(f, " ⚠️  {warning}")?;
+                }
+                Ok(())
+            }
+            PathChoice::CustomPath => write!(f, "📂 Custom Path"),
+        }
+    }
+}
+
+/// The config file `anot init` sets up for an agent: the files it offers, and how it asks.
+#[derive(Debug, Clone)]
+pub struct ConfigTarget {
+    /// The agent, as shown to the user, e.g. `Claude Code`.
+    pub agent: &'static str,
+    /// The question the files are offered under.
+    pub question: &'static str,
+    /// What a custom path should point to, e.g. `settings.json file`.
+    pub file: &'static str,
+    pub candidates: Vec<PathCandidate>,
+}
+
+impl ConfigTarget {
+    /// The file to set up, with a leading `~` expanded: `provided` when given, otherwise the
+    /// candidate or custom path the user picks.
+    #[instrument(skip(self), fields(agent = self.agent))]
+    pub fn choose_path(self, provided: Option<&Path>) -> Result<PathBuf, Error> {
+        let chosen = match provided {
+            Some(path) => {
+                info!(path = %path.display(), "using provided path");
+                path.to_path_buf()
+            }
+            None => self.prompt()?,
+        };
+        let expanded = expand_tilde(&chosen);
+        debug!(chosen = %chosen.display(), expanded = %expanded.display(), "resolved config path");
+        Ok(expanded)
+    }
+
+    fn prompt(self) -> Result<PathBuf, Error> {
+        let mut options: Vec<PathChoice> = self
+            .candidates
+            .into_iter()
+            .map(PathChoice::Candidate)
+            .collect();
+        options.push(PathChoice::CustomPath);
+
+        let selection = Select::new(self.question, options)
+            .with_help_message(&format!(
+                "Select the configuration path for {}. ✓ = file exists, ✗ = file missing",
+                self.agent
+            ))
+            .prompt()
+            .map_err(|err| {
+                handle_inquire_error(
+                    err,
+                    &format!("Failed to prompt for {} configuration path", self.agent),
+                )
+            })?;
+
+        match selection {
+            PathChoice::Candidate(candidate) => {
+                info!(label = %candidate.label, exists = candidate.exists, path = %candidate.path.display(), "selected config path");
+                if let Some(warning) = candidate.warning {
+                    warn!(path = %candidate.path.display(), warning, "selected a path that needs care");
+                }
+                Ok(candidate.path)
+            }
+            PathChoice::CustomPath => {
+                let custom_path: String = inquire::Text::new("Enter the custom path:")
+                    .with_help_message(&format!(
+                        "Provide the full path to the {} {}.",
+                        self.agent, self.file
+                    ))
+                    .prompt()
+                    .map_err(|err| handle_inquire_error(err, "Failed to prompt for custom path"))?;
+
+                info!(path = %custom_path, "selected custom path");
+                Ok(PathBuf::from(custom_path))
+            }
+        }
+    }
+}
+
+/// Asks before creating a config file that doesn't exist yet. The file itself is written once
+/// the changes are confirmed.
+#[instrument]
+pub fn confirm_creation(path: &Path) -> Result<(), Error> {
+    if !path.exists() {
+        let should_create = Confirm::new(&format!(
+            "The configuration file '{}' does not exist. Would you like to create it?",
+            path.display()
+        ))
+        .with_default(true)
+        .prompt()
+        .map_err(|err| handle_inquire_error(err, "Failed to get user confirmation"))?;
+
+        if !should_create {
+            info!(path = %path.display(), "user declined to create file");
+            return Err(AnotError::Cancelled { interrupted: false }.into());
+        }
+    }
+    Ok(())
+}
+
+/// Creates the directories `path` goes in.
+pub fn ensure_parent_dir_exists(path: &Path) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AnotError::AgentConfigWrite {
+            path: path.to_path_buf(),
+            source: e.into(),
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompt_errors_keep_cancellation_messages() {
+        assert_eq!(
+            handle_inquire_error(InquireError::OperationCanceled, "ctx").to_string(),
+            "Operation cancelled by user"
+        );
+        assert_eq!(
+            handle_inquire_error(InquireError::OperationInterrupted, "ctx").to_string(),
+            "Operation interrupted by user"
+        );
+        assert_eq!(
+            format!(
+                "{:#}",
+                handle_inquire_error(InquireError::NotTTY, "Failed to prompt")
+            ),
+            format!("Failed to prompt: {}", InquireError::NotTTY)
+        );
+    }
+
+    #[test]
+    fn choices_show_existence_path_and_warning() {
+        let user = PathCandidate::new("User Settings", PathBuf::from("/home/me/a.json"), true);
+        assert_eq!(
+            PathChoice::Candidate(user).to_string(),
+            "✓ User Settings (/home/me/a.json)"
+        );
+
+        let managed = PathCandidate::new("Managed", PathBuf::from("/etc/a.json"), false)
+            .with_warning("requires admin rights");
+        assert_eq!(
+            PathChoice::Candidate(managed).to_string(),
+            "✗ Managed (/etc/a.json) ⚠️  requires admin rights"
+        );
+        assert_eq!(PathChoice::CustomPath.to_string(), "📂 Custom Path");
+    }
+
+    #[test]
+    fn provided_paths_skip_the_prompt() {
+        let target = ConfigTarget {
+            agent: "Test",
+            question: "Where?",
+            file: "config",
+            candidates: Vec::new(),
+        };
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            target
+                .clone()
+                .choose_path(Some(Path::new("~/agent/config.toml")))
+                .unwrap(),
+            home.join("agent/config.toml")
+        );
+        assert_eq!(
+            target
+                .choose_path(Some(Path::new("/etc/agent.toml")))
+                .unwrap(),
+            PathBuf::from("/etc/agent.toml")
+        );
+    }
+
+    #[test]
+    fn parent_dirs_are_created() {
+        let dir = std::env::temp_dir().join(format!("anot-init-parent-{}", std::process::id()));
+        let path = dir.join("nested/plugins/anot.js");
+        ensure_parent_dir_exists(&path).unwrap();
+        assert!(path.parent().unwrap().is_dir());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,241 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use tracing::{debug, warn};
+
+use crate::{storage::write_atomic, utils::expand_tilde};
+
+/// Size hint for the largest variant of a built-in icon, which macOS scales down itself.
+pub const LARGEST: u32 = u32::MAX;
+
+/// A built-in icon embedded in several sizes.
+#[derive(Debug, Clone, Copy)]
+pub struct EmbeddedIcon {
+    pub name: &'static str,
+    /// Width in pixels and PNG bytes of each size, smallest first.
+    pub variants: &'static [(u32, &'static [u8])],
+}
+
+impl EmbeddedIcon {
+    /// The smallest variant at least `size_hint` pixels wide, or the largest when none is.
+    pub fn variant(&self, size_hint: u32) -> (u32, &'static [u8]) {
+        self.variants
+            .iter()
+            .copied()
+            .find(|(size, _)| *size >= size_hint)
+            .or_else(|| self.variants.last().copied())
+            .expect("an embedded icon has at least one size")
+    }
+
+    /// The variant for `size_hint` written to the temp directory. The other sizes aren't
+    /// written.
+    pub fn get_icon(&self, size_hint: u32) -> Result<PathBuf, Error> {
+        self.materialize(&std::env::temp_dir(), size_hint)
+    }
+
+    fn materialize(&self, dir: &Path, size_hint: u32) -> Result<PathBuf, Error> {
+        let (size, bytes) = self.variant(size_hint);
+        materialize_icon(dir, &format!("{}-{size}", self.name), bytes)
+    }
+}
+
+/// What a Linux notification shows as its icon, as notify-rust takes it: the configured file
+/// when it exists, otherwise the theme icon `icon_name`, otherwise `builtin` in the size for
+/// `size_hint`.
+pub fn icon_or_theme_name(
+    configured: Option<&Path>,
+    icon_name: Option<&str>,
+    builtin: &EmbeddedIcon,
+    size_hint: u32,
+) -> Result<String, Error> {
+    if let Some(path) = configured.and_then(custom_icon) {
+        return Ok(path.to_string_lossy().into_owned());
+    }
+    if let Some(name) = icon_name {
+        return Ok(name.to_string());
+    }
+    Ok(builtin.get_icon(size_hint)?.to_string_lossy().into_owned())
+}
+
+/// Resolves a configured icon path. Returns `None` (after a warning) when the file doesn't
+/// exist, so callers fall back to the built-in icon.
+pub fn custom_icon(configured: &Path) -> Option<PathBuf> {
+    let path = expand_tilde(configured);
+    if path.is_file() {
+        Some(path)
+    } else {
+        warn!(icon = %path.display(), "configured icon not found; using the built-in icon");
+        None
+    }
+}
+
+/// Writes an embedded icon to the temp directory and returns its path.
+///
+/// The file name carries a hash of the icon (`<prefix>-<hash>.png`), so an upgraded binary never
+/// reuses a stale icon, and the file is written atomically so concurrent hooks never read a
+/// partial one.
+pub fn embedded_icon_path(prefix: &str, bytes: &[u8]) -> Result<PathBuf, Error> {
+    materialize_icon(&std::env::temp_dir(), prefix, bytes)
+}
+
+fn materialize_icon(dir: &Path, prefix: &str, bytes: &[u8]) -> Result<PathBuf, Error> {
+    let file_name = icon_file_name(prefix, bytes);
+    let path = dir.join(&file_name);
+
+    if fs::metadata(&path).is_ok_and(|m| m.len() == bytes.len() as u64) {
+        return Ok(path);
+    }
+
+    write_atomic(&path, bytes)?;
+    remove_stale_icons(dir, prefix, &file_name);
+
+    Ok(path)
+}
+
+fn icon_file_name(prefix: &str, bytes: &[u8]) -> String {
+    format!("{prefix}-{:08x}.png", fnv1a(bytes) as u32)
+}
+
+/// FNV-1a, used instead of `DefaultHasher` because file names must stay stable across builds.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// Removes icons written by other versions. Best effort: errors are ignored.
+fn remove_stale_icons(dir: &Path, prefix: &str, current: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+
+    for entry in entries.flatten() {
+        let name = entry.file_name();
+        let Some(name) = name.to_str() else {
+            continue;
+        };
+        let is_icon = name
+            .strip_prefix(prefix)
+            .and_then(|rest| rest.strip_prefix('-'))
+            .and_then(|rest| rest.strip_suffix(".png"))
+            .is_some_and(|hash| hash.len() == 8 && hash.chars().all(|c| c.is_ascii_hexdigit()));
+
+        if is_icon && name != current && fs::remove_file(entry.path()).is_ok() {
+            debug!(icon = name, "removed stale icon");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const ICON: EmbeddedIcon = EmbeddedIcon {
+        name: "anot-test-icon",
+        variants: &[(48, b"small"), (128, b"medium"), (512, b"large")],
+    };
+
+    #[test]
+    fn picks_the_smallest_variant_that_is_large_enough() {
+        assert_eq!(ICON.variant(1).0, 48);
+        assert_eq!(ICON.variant(48).0, 48);
+        assert_eq!(ICON.variant(64).0, 128);
+        assert_eq!(ICON.variant(128).0, 128);
+        assert_eq!(ICON.variant(256).0, 512);
+        assert_eq!(ICON.variant(LARGEST).0, 512, "nothing larger");
+    }
+
+    #[test]
+    fn only_the_variant_used_is_written() {
+        let dir = temp_dir("icons", "variants");
+
+        let path = ICON.materialize(&dir, 100).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"medium");
+        assert_eq!(
+            path,
+            dir.join(icon_file_name("anot-test-icon-128", b"medium"))
+        );
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_configured_file_beats_the_theme_name_which_beats_the_builtin_icon() {
+        let file =
+            std::env::temp_dir().join(format!("anot-themed-icon-{}.png", std::process::id()));
+        fs::write(&file, b"png").unwrap();
+
+        assert_eq!(
+            icon_or_theme_name(Some(&file), Some("utilities-terminal"), &ICON, 48).unwrap(),
+            file.to_string_lossy()
+        );
+        assert_eq!(
+            icon_or_theme_name(
+                Some(Path::new("/nonexistent/anot/icon.png")),
+                Some("utilities-terminal"),
+                &ICON,
+                48
+            )
+            .unwrap(),
+            "utilities-terminal"
+        );
+        let builtin = icon_or_theme_name(None, None, &ICON, 48).unwrap();
+        assert_eq!(fs::read(builtin).unwrap(), b"small");
+    }
+
+    #[test]
+    fn missing_custom_icon_falls_back() {
+        assert_eq!(custom_icon(Path::new("/nonexistent/anot/icon.png")), None);
+
+        let path =
+            std::env::temp_dir().join(format!("anot-custom-icon-{}.png", std::process::id()));
+        std::fs::write(&path, b"png").unwrap();
+        assert_eq!(custom_icon(&path), Some(path));
+    }
+
+    #[test]
+    fn names_icon_after_content_hash() {
+        assert_eq!(
+            icon_file_name("claude-icon", b""),
+            "claude-icon-84222325.png"
+        );
+        assert_ne!(
+            icon_file_name("claude-icon", b"v1"),
+            icon_file_name("claude-icon", b"v2")
+        );
+    }
+
+    #[test]
+    fn second_call_reuses_the_file() {
+        let dir = temp_dir("icons", "idempotent");
+
+        let first = materialize_icon(&dir, "codex-icon", b"icon bytes").unwrap();
+        let modified = fs::metadata(&first).unwrap().modified().unwrap();
+        let second = materialize_icon(&dir, "codex-icon", b"icon bytes").unwrap();
+
+        assert_eq!(first, second);
+        assert_eq!(fs::metadata(&second).unwrap().modified().unwrap(), modified);
+        assert_eq!(fs::read(&second).unwrap(), b"icon bytes");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn rewrites_truncated_icon_and_removes_old_versions() {
+        let dir = temp_dir("icons", "cleanup");
+        let old = materialize_icon(&dir, "claude-icon", b"old icon").unwrap();
+        let unrelated = dir.join("claude-icon-notes.png");
+        fs::write(&unrelated, b"keep").unwrap();
+
+        let current_name = icon_file_name("claude-icon", b"new icon");
+        fs::write(dir.join(&current_name), b"new").unwrap();
+        let current = materialize_icon(&dir, "claude-icon", b"new icon").unwrap();
+
+        assert_eq!(current, dir.join(current_name));
+        assert_eq!(fs::read(&current).unwrap(), b"new icon");
+        assert!(!old.exists());
+        assert!(unrelated.exists());
+    }
+}
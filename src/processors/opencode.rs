@@ -1,3 +1,4 @@
+#[cfg(feature = "desktop")]
 pub mod icon;
 pub mod init;
 pub mod input_and_output;
@@ -1,17 +1,21 @@
 use anyhow::Error;
-#[cfg(not(target_os = "macos"))]
+#[cfg(all(feature = "desktop", not(target_os = "macos")))]
 use notify_rust::Notification;
 use tracing::{debug, error, info, instrument};
 
 use serde_json::Value;
 
+#[cfg(feature = "desktop")]
+use crate::processors::opencode::icon::get_opencode_icon_path;
 use crate::{
     configuration::Config,
-    processors::opencode::{icon::get_opencode_icon_path, structs::OpencodeSupportedEvent},
+    delivery, mute,
+    processors::opencode::structs::OpencodeSupportedEvent,
 };
 
 use super::structs::parse_supported_event;
 
+#[cfg_attr(not(feature = "desktop"), allow(unused_variables))]
 fn create_opencode_notification(
     title: &str,
     body: &str,
@@ -19,7 +23,7 @@ fn create_opencode_notification(
 ) -> Result<(), Error> {
     debug!(body_len = body.len(), "preparing OpenCode notification");
 
-    #[cfg(target_os = "macos")]
+    #[cfg(all(feature = "desktop", target_os = "macos"))]
     {
         use mac_notification_sys::Notification;
         use mac_notification_sys::Sound;
@@ -27,7 +31,7 @@ fn create_opencode_notification(
         use mac_notification_sys::set_application;
 
         let mut notification = Notification::new();
-        notification.title(title).message(body).sound(true);
+        notification.title(title).message(body);
 
         let icon_path = get_opencode_icon_path().unwrap_or_default();
 
@@ -54,7 +58,7 @@ fn create_opencode_notification(
         debug!("sent macOS notification (OpenCode)");
     }
 
-    #[cfg(not(target_os = "macos"))]
+    #[cfg(all(feature = "desktop", not(target_os = "macos")))]
     {
         let mut notification = Notification::new();
         notification.summary(title).body(body);
@@ -309,6 +313,10 @@ pub fn process_opencode_input(input: String, config: &Config) -> Result<(), Erro
         }
     };
 
+    if mute::intercept(config, "opencode", evt.event_type(), &input) {
+        return Ok(());
+    }
+
     match &evt {
         OpencodeSupportedEvent::SessionIdle { session_id } => {
             info!(session_id = session_id, "OpenCode: session idle");
@@ -361,7 +369,11 @@ pub fn process_opencode_input(input: String, config: &Config) -> Result<(), Erro
     }
 
     let (title, body) = map_event_to_message(&evt);
-    create_opencode_notification(&title, &body, config)
+    let title = delivery::replay_title(config, &title);
+    let body = crate::privacy::redact(config, &body);
+    delivery::deliver(config, "opencode", evt.event_type(), &title, &body, || {
+        create_opencode_notification(&title, &body, config)
+    })
 }
 
 #[cfg(test)]
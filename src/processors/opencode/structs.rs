@@ -31,6 +31,19 @@ pub enum OpencodeSupportedEvent {
     },
 }
 
+impl OpencodeSupportedEvent {
+    /// The OpenCode event type this variant was parsed from.
+    pub fn event_type(&self) -> &str {
+        match self {
+            OpencodeSupportedEvent::SessionIdle { .. } => "session.idle",
+            OpencodeSupportedEvent::Permission { event_type, .. } => event_type,
+            OpencodeSupportedEvent::PermissionReplied { .. } => "permission.replied",
+            OpencodeSupportedEvent::QuestionAsked { .. } => "question.asked",
+            OpencodeSupportedEvent::SessionError { .. } => "session.error",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 pub struct PermissionTime {
     pub created: u64,
@@ -336,16 +349,16 @@ pub fn parse_supported_event(input: &str) -> Result<Option<OpencodeSupportedEven
                 |e| Error::msg(format!("Invalid question.asked properties: {e}")),
             )?;
 
-            if request.questions.is_empty() {
-                if let Some(text) = resolve_question_text(event, &value) {
-                    request.questions.push(QuestionInfo {
-                        question: text,
-                        header: String::new(),
-                        options: Vec::new(),
-                        multiple: None,
-                        custom: None,
-                    });
-                }
+            if request.questions.is_empty()
+                && let Some(text) = resolve_question_text(event, &value)
+            {
+                request.questions.push(QuestionInfo {
+                    question: text,
+                    header: String::new(),
+                    options: Vec::new(),
+                    multiple: None,
+                    custom: None,
+                });
             }
 
             Ok(Some(OpencodeSupportedEvent::QuestionAsked {
@@ -0,0 +1,151 @@
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{Context, Error, bail};
+use tracing::{debug, warn};
+
+use crate::{
+    configuration::{Config, DeliveryMode},
+    privacy,
+    processors::{claude, codex, opencode},
+};
+
+/// Agents whose payloads are recorded and can be replayed.
+pub const AGENTS: [&str; 3] = ["claude", "codex", "opencode"];
+
+fn payload_path(config: &Config, agent: &str) -> PathBuf {
+    config.data_dir().join(format!("last-payload-{agent}.json"))
+}
+
+/// Overwrites the last payload recorded for `agent`, redacted as `privacy` asks. Failures are
+/// only logged.
+pub fn record_payload(config: &Config, agent: &str, input: &str) {
+    let path = payload_path(config, agent);
+    let written = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| fs::write(&path, privacy::redact_payload(config, input)));
+
+    if let Err(e) = written {
+        warn!(path = %path.display(), error = %e, "failed to record the last payload");
+    }
+}
+
+/// The last recorded payload of `agent`, or of whichever agent sent one most recently. Returns
+/// the agent and the payload.
+pub fn last_payload(
+    config: &Config,
+    agent: Option<&str>,
+) -> Result<Option<(String, String)>, Error> {
+    let agents = match agent {
+        Some(agent) => vec![agent],
+        None => AGENTS.to_vec(),
+    };
+
+    let mut latest: Option<(SystemTime, &str, PathBuf)> = None;
+    for agent in agents {
+        let path = payload_path(config, agent);
+        let modified = match fs::metadata(&path).and_then(|m| m.modified()) {
+            Ok(modified) => modified,
+            Err(e) if e.kind() == ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        if latest
+            .as_ref()
+            .is_none_or(|(newest, ..)| modified > *newest)
+        {
+            latest = Some((modified, agent, path));
+        }
+    }
+
+    latest
+        .map(|(_, agent, path)| Ok((agent.to_string(), read_payload(&path)?)))
+        .transpose()
+}
+
+pub fn read_payload(path: &Path) -> Result<String, Error> {
+    fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))
+}
+
+/// Sends `input` through `agent`'s processor again. The notification is marked as a replay and
+/// bypasses deduplication and batching so it is always shown.
+pub fn replay(config: &Config, agent: &str, input: String) -> Result<(), Error> {
+    let config = replay_config(config);
+    debug!(agent, "replaying payload");
+
+    match agent {
+        "claude" => claude::input_and_output::deliver_claude_input(&input, &config),
+        "codex" => codex::process_codex_input(input, &config),
+        "opencode" => opencode::input_and_output::process_opencode_input(input, &config),
+        other => bail!(
+            "unknown agent `{other}`, expected one of: {}",
+            AGENTS.join(", ")
+        ),
+    }
+}
+
+fn replay_config(config: &Config) -> Config {
+    let mut config = config.clone();
+    config.replay = true;
+    config.delivery = DeliveryMode::Blocking;
+    config.dedupe_window_secs = 0;
+    config.claude.batching.window_secs = 0;
+    config
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_config;
+
+    #[test]
+    fn returns_the_most_recently_recorded_payload() {
+        let config = temp_config("replay", "latest");
+        assert_eq!(last_payload(&config, None).unwrap(), None);
+
+        record_payload(&config, "codex", r#"{"type": "agent-turn-complete"}"#);
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        record_payload(&config, "claude", r#"{"hook_event_name": "Stop"}"#);
+
+        assert_eq!(
+            last_payload(&config, None).unwrap(),
+            Some((
+                "claude".to_string(),
+                r#"{"hook_event_name": "Stop"}"#.to_string()
+            ))
+        );
+        assert_eq!(
+            last_payload(&config, Some("codex")).unwrap().unwrap().0,
+            "codex"
+        );
+        assert_eq!(last_payload(&config, Some("opencode")).unwrap(), None);
+    }
+
+    #[test]
+    fn replay_bypasses_dedupe_and_batching() {
+        let mut config = temp_config("replay", "settings");
+        config.claude.batching.window_secs = 30;
+        config.delivery = DeliveryMode::Detached;
+
+        let replayed = replay_config(&config);
+        assert!(replayed.replay);
+        assert_eq!(replayed.delivery, DeliveryMode::Blocking);
+        assert_eq!(replayed.dedupe_window_secs, 0);
+        assert_eq!(replayed.claude.batching.window_secs, 0);
+    }
+
+    #[test]
+    fn rejects_unknown_agents() {
+        let err = replay(
+            &temp_config("replay", "unknown"),
+            "gemini",
+            "{}".to_string(),
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("unknown agent `gemini`"));
+    }
+}
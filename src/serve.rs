@@ -0,0 +1,466 @@
+//! `anot serve`: a small HTTP listener for agents running elsewhere, e.g. in a container.
+//! `POST /claude` and `POST /codex` take the same payloads as `anot claude` and `anot codex`
+//! and run them through the same processors with this machine's config.
+//!
+//! It speaks just enough HTTP/1.1 for that: one request per connection, bodies sized by
+//! `Content-Length`. With a token, requests need `Authorization: Bearer <token>`.
+
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Error, bail};
+use serde_json::json;
+use tracing::{error, info, warn};
+
+use crate::{
+    capture,
+    configuration::Config,
+    delivery::{on_sent, sound},
+    processors::{
+        claude::{process_claude_input, structs::HookInput},
+        codex::{payload, process_codex_input},
+    },
+    replay,
+};
+
+/// Read from `ANOT_SERVE_TOKEN` when `--token` isn't given, which keeps it out of `ps`.
+pub const TOKEN_ENV: &str = "ANOT_SERVE_TOKEN";
+
+/// Larger request bodies are refused with 413.
+pub const MAX_BODY_BYTES: usize = 1024 * 1024;
+
+/// Request lines and headers together are capped at this, so a client can't make anot buffer
+/// without end.
+const MAX_HEAD_BYTES: usize = 16 * 1024;
+
+/// A client that stops sending for this long is dropped.
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Connections handled at once. Any beyond that get 503 right away, so clients that stall
+/// can't hold a thread each without end.
+const MAX_HANDLERS: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Request {
+    method: String,
+    path: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Response {
+    status: u16,
+    body: String,
+}
+
+impl Response {
+    fn json(status: u16, body: serde_json::Value) -> Self {
+        Response {
+            status,
+            body: body.to_string(),
+        }
+    }
+
+    fn error(status: u16, message: impl Into<String>) -> Self {
+        Response::json(status, json!({ "error": message.into() }))
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            401 => "Unauthorized",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            411 => "Length Required",
+            413 => "Content Too Large",
+            503 => "Service Unavailable",
+            _ => "Internal Server Error",
+        }
+    }
+}
+
+/// Listens on `bind` until the process is stopped, handling each connection on its own
+/// thread. `on_listening` gets the bound address, which tells the port when `bind` asked for
+/// port `0`.
+///
+/// Without a token anyone who can reach the address can show notifications and run the
+/// `on_sent` commands, so addresses beyond this machine need one unless
+/// `allow_unauthenticated`.
+pub fn run(
+    config: &Config,
+    bind: &str,
+    token: Option<String>,
+    allow_unauthenticated: bool,
+    on_listening: impl FnOnce(SocketAddr),
+) -> Result<(), Error> {
+    let listener = TcpListener::bind(bind).with_context(|| format!("Can't listen on {bind}"))?;
+    let address = listener.local_addr()?;
+    if token.is_none() && !address.ip().is_loopback() {
+        if !allow_unauthenticated {
+            bail!(
+                "Refusing to listen on {address} without a token; pass --token (or set \
+                 {TOKEN_ENV}), or --allow-unauthenticated if anyone who can reach it may notify here"
+            );
+        }
+        warn!(%address, "listening beyond this machine without a token");
+    }
+    info!(%address, authenticated = token.is_some(), "serving agent notifications");
+    on_listening(address);
+    // Payloads come from agents elsewhere, so this process's cwd isn't their project.
+    let config = Config {
+        remote: true,
+        ..config.clone()
+    };
+    accept(listener, &config, token, MAX_HANDLERS);
+    Ok(())
+}
+
+/// Hands each connection on `listener` to a thread of its own, with at most `max_handlers`
+/// running at once.
+fn accept(listener: TcpListener, config: &Config, token: Option<String>, max_handlers: usize) {
+    let handlers = Arc::new(AtomicUsize::new(0));
+    for stream in listener.incoming() {
+        let mut stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!(error = %e, "failed to accept a connection");
+                continue;
+            }
+        };
+        let Some(slot) = HandlerSlot::take(&handlers, max_handlers) else {
+            warn!(max_handlers, "too many connections at once; answering 503");
+            let busy = Response::error(503, "too many requests at once; try again shortly");
+            let _ = write_response(&mut stream, &busy);
+            continue;
+        };
+        let config = config.clone();
+        let token = token.clone();
+        thread::spawn(move || {
+            handle_connection(stream, &config, token.as_deref());
+            drop(slot);
+        });
+    }
+}
+
+/// One of the `max_handlers` connections handled at once, given back when dropped, even by a
+/// handler that panicked.
+struct HandlerSlot(Arc<AtomicUsize>);
+
+impl HandlerSlot {
+    fn take(handlers: &Arc<AtomicUsize>, max_handlers: usize) -> Option<Self> {
+        handlers
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |running| {
+                (running < max_handlers).then_some(running + 1)
+            })
+            .ok()
+            .map(|_| HandlerSlot(Arc::clone(handlers)))
+    }
+}
+
+impl Drop for HandlerSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, config: &Config, token: Option<&str>) {
+    let remote = stream
+        .peer_addr()
+        .map_or_else(|_| "unknown".to_string(), |addr| addr.to_string());
+    let _ = stream.set_read_timeout(Some(READ_TIMEOUT));
+
+    let (request, response) = match stream.try_clone() {
+        Ok(reader) => match read_request(BufReader::new(reader)) {
+            Ok(request) => {
+                let response = respond(&request, config, token);
+                (Some(request), response)
+            }
+            Err(response) => (None, response),
+        },
+        Err(e) => (None, Response::error(500, format!("{e:#}"))),
+    };
+    info!(
+        remote = %remote,
+        method = request.as_ref().map_or("-", |r| r.method.as_str()),
+        path = request.as_ref().map_or("-", |r| r.path.as_str()),
+        status = response.status,
+        "handled request"
+    );
+
+    if let Err(e) = write_response(&mut stream, &response) {
+        warn!(remote = %remote, error = %e, "failed to send the response");
+    }
+    drop(stream);
+    on_sent::run_pending();
+    sound::play_pending();
+}
+
+fn write_response(stream: &mut TcpStream, response: &Response) -> io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        response.status,
+        response.reason(),
+        response.body.len(),
+        response.body
+    )?;
+    stream.flush()
+}
+
+/// Reads the request line, the headers and a `Content-Length` body. A request anot can't take
+/// is answered with the response it gets instead.
+fn read_request(mut reader: impl BufRead) -> Result<Request, Response> {
+    let mut head_bytes = 0;
+    let mut next_line = |reader: &mut dyn BufRead| -> Result<String, Response> {
+        let mut line = String::new();
+        let read = Read::take(&mut *reader, (MAX_HEAD_BYTES - head_bytes) as u64)
+            .read_line(&mut line)
+            .map_err(|e| Response::error(400, format!("can't read the request: {e}")))?;
+        head_bytes += read;
+        if !line.ends_with('\n') {
+            return Err(Response::error(400, "incomplete request headers"));
+        }
+        Ok(line.trim_end_matches(['\r', '\n']).to_string())
+    };
+
+    let request_line = next_line(&mut reader)?;
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        return Err(Response::error(400, "malformed request line"));
+    };
+    let (method, path) = (method.to_string(), path.to_string());
+
+    let mut content_length = None;
+    let mut authorization = None;
+    loop {
+        let line = next_line(&mut reader)?;
+        if line.is_empty() {
+            break;
+        }
+        let Some((name, value)) = line.split_once(':') else {
+            return Err(Response::error(400, "malformed header"));
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case("content-length") {
+            content_length = Some(
+                value
+                    .parse::<usize>()
+                    .map_err(|_| Response::error(400, "invalid Content-Length"))?,
+            );
+        } else if name.eq_ignore_ascii_case("authorization") {
+            authorization = Some(value.to_string());
+        }
+    }
+
+    let body = if method == "POST" {
+        let length =
+            content_length.ok_or_else(|| Response::error(411, "Content-Length is required"))?;
+        if length > MAX_BODY_BYTES {
+            return Err(Response::error(
+                413,
+                format!("the body is over {MAX_BODY_BYTES} bytes"),
+            ));
+        }
+        let mut body = vec![0; length];
+        reader
+            .read_exact(&mut body)
+            .map_err(|e| Response::error(400, format!("can't read the body: {e}")))?;
+        String::from_utf8(body).map_err(|_| Response::error(400, "the body isn't UTF-8"))?
+    } else {
+        String::new()
+    };
+
+    Ok(Request {
+        method,
+        path,
+        authorization,
+        body,
+    })
+}
+
+fn respond(request: &Request, config: &Config, token: Option<&str>) -> Response {
+    let agent = match request.path.as_str() {
+        "/claude" => "claude",
+        "/codex" => "codex",
+        _ => return Response::error(404, "use POST /claude or POST /codex"),
+    };
+    if request.method != "POST" {
+        return Response::error(405, "use POST");
+    }
+    if let Some(token) = token
+        && !authorized(request.authorization.as_deref(), token)
+    {
+        return Response::error(401, "missing or wrong bearer token");
+    }
+
+    let input = request.body.clone();
+    match agent {
+        "claude" => {
+            if let Err(e) = serde_json::from_str::<HookInput>(&input) {
+                return Response::error(400, format!("Failed to parse input JSON: {e}"));
+            }
+            record_payload(config, agent, &input);
+            match serde_json::to_value(process_claude_input(input, config)) {
+                Ok(output) => Response::json(200, output),
+                Err(e) => Response::error(500, format!("{e:#}")),
+            }
+        }
+        _ => {
+            if let Err(e) = payload::parse(&input) {
+                return Response::error(400, e.to_string());
+            }
+            record_payload(config, agent, &input);
+            match process_codex_input(input, config) {
+                Ok(()) => Response::json(200, json!({})),
+                Err(e) => {
+                    error!(error = %format_args!("{e:#}"), "failed to process Codex input");
+                    Response::error(500, format!("{e:#}"))
+                }
+            }
+        }
+    }
+}
+
+/// Whether `authorization` is `Bearer <token>`, compared without stopping at the first
+/// difference.
+fn authorized(authorization: Option<&str>, token: &str) -> bool {
+    let Some(given) = authorization.and_then(|value| value.strip_prefix("Bearer ")) else {
+        return false;
+    };
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+/// Keeps the payload for `anot replay` and captures, as `anot claude` and `anot codex` do.
+fn record_payload(config: &Config, agent: &str, input: &str) {
+    if config.dry_run {
+        return;
+    }
+    capture::capture_payload(config, agent, input);
+    replay::record_payload(config, agent, input);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &str) -> Result<Request, Response> {
+        read_request(raw.as_bytes())
+    }
+
+    #[test]
+    fn reads_a_post() {
+        let request = parse(
+            "POST /codex HTTP/1.1\r\nHost: laptop\r\nauthorization: Bearer s3cret\r\n\
+             Content-Length: 7\r\n\r\n{\"a\":1}",
+        )
+        .unwrap();
+        assert_eq!(
+            request,
+            Request {
+                method: "POST".to_string(),
+                path: "/codex".to_string(),
+                authorization: Some("Bearer s3cret".to_string()),
+                body: "{\"a\":1}".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn refuses_what_it_cant_take() {
+        let status = |raw: &str| parse(raw).unwrap_err().status;
+        assert_eq!(status("POST /claude HTTP/1.1\r\n\r\n"), 411);
+        assert_eq!(
+            status(&format!(
+                "POST /claude HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                MAX_BODY_BYTES + 1
+            )),
+            413
+        );
+        assert_eq!(
+            status("POST /claude HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}"),
+            400
+        );
+        assert_eq!(status("garbage"), 400);
+        assert_eq!(
+            status(&format!(
+                "GET / HTTP/1.1\r\nX: {}\r\n\r\n",
+                "a".repeat(MAX_HEAD_BYTES)
+            )),
+            400
+        );
+    }
+
+    #[test]
+    fn checks_the_bearer_token() {
+        assert!(authorized(Some("Bearer s3cret"), "s3cret"));
+        assert!(!authorized(Some("Bearer s3cre"), "s3cret"));
+        assert!(!authorized(Some("Bearer s3cres"), "s3cret"));
+        assert!(!authorized(Some("s3cret"), "s3cret"));
+        assert!(!authorized(None, "s3cret"));
+    }
+
+    #[test]
+    fn needs_a_token_beyond_this_machine() {
+        let listening = |_| panic!("listened without a token");
+        let error = run(&Config::default(), "0.0.0.0:0", None, false, listening).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("Refusing to listen on 0.0.0.0:"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn answers_503_beyond_the_handler_limit() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || accept(listener, &Config::default(), None, 1));
+
+        // Sends nothing, so it holds the only handler until `READ_TIMEOUT`.
+        let _stalled = TcpStream::connect(address).unwrap();
+        thread::sleep(Duration::from_millis(100));
+        let mut response = String::new();
+        TcpStream::connect(address)
+            .unwrap()
+            .read_to_string(&mut response)
+            .unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{response}"
+        );
+    }
+
+    #[test]
+    fn routes_by_path_and_method() {
+        let config = Config::default();
+        let request = |method: &str, path: &str, authorization: Option<&str>| Request {
+            method: method.to_string(),
+            path: path.to_string(),
+            authorization: authorization.map(String::from),
+            body: "not json".to_string(),
+        };
+
+        let status = |request: Request| respond(&request, &config, Some("t")).status;
+        assert_eq!(status(request("POST", "/opencode", None)), 404);
+        assert_eq!(status(request("GET", "/claude", Some("Bearer t"))), 405);
+        assert_eq!(status(request("POST", "/claude", None)), 401);
+        assert_eq!(status(request("POST", "/claude", Some("Bearer t"))), 400);
+        assert_eq!(status(request("POST", "/codex", Some("Bearer t"))), 400);
+    }
+}
@@ -0,0 +1,651 @@
+//! What anot knows about each agent session: where it runs, what it did last and whether it's
+//! waiting for the user. Every processed Claude event and Codex turn updates its session's
+//! record, and `anot sessions` lists them.
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    configuration::Config,
+    i18n::t,
+    storage::{read_json_or_default, with_lock, write_atomic},
+    utils::{format_duration, project_name, unix_now},
+};
+
+const SESSIONS_FILE_NAME: &str = "sessions.json";
+
+/// Sessions without activity for this long are dropped.
+const PRUNE_AFTER_SECS: u64 = 24 * 60 * 60;
+
+/// A running session with no event for this long is shown as idle.
+pub const IDLE_AFTER_SECS: u64 = 5 * 60;
+
+/// A session left waiting on a prompt this long is taken to be abandoned, and no longer
+/// counted by [`others_waiting`].
+pub const WAITING_STALE_AFTER_SECS: u64 = 60 * 60;
+
+/// Events after which the agent waits for the user.
+const WAITING_EVENTS: &[&str] = &["Notification", "Stop", "AgentTurnComplete"];
+
+/// Events that end a session.
+const ENDING_EVENTS: &[&str] = &["SessionEnd"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SessionRecord {
+    pub agent: String,
+    pub session_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<String>,
+    /// When anot first saw the session.
+    pub started: u64,
+    pub last_event: String,
+    pub last_activity: u64,
+    /// Events seen, including the first.
+    #[serde(default)]
+    pub events: u64,
+    /// Since when the agent has been waiting for the user, if it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub waiting_since: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<u64>,
+    /// Finished tool calls by tool name, for the `claude.session_digest`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tool_calls: BTreeMap<String, u64>,
+    /// Permission prompts shown, for the `claude.session_digest`.
+    #[serde(default)]
+    pub permission_prompts: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionState {
+    /// Working: the last event came recently and wasn't one the agent waits after.
+    Active,
+    /// Waiting for the user: a prompt, a finished turn or a permission request.
+    Waiting,
+    /// No event for [`IDLE_AFTER_SECS`] without waiting for anything.
+    Idle,
+    Ended,
+}
+
+impl SessionRecord {
+    /// The session's state at `now`. A session waits after a `Notification` or a finished turn;
+    /// one that's been quiet for long otherwise is idle.
+    pub fn state(&self, now: u64) -> SessionState {
+        if self.ended_at.is_some() {
+            SessionState::Ended
+        } else if self.waiting_since.is_some() {
+            SessionState::Waiting
+        } else if now.saturating_sub(self.last_activity) >= IDLE_AFTER_SECS {
+            SessionState::Idle
+        } else {
+            SessionState::Active
+        }
+    }
+}
+
+/// What an event did besides happening, counted for the session digest.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Activity<'a> {
+    /// The tool of a finished tool call.
+    pub tool: Option<&'a str>,
+    pub permission_prompt: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Sessions {
+    #[serde(default)]
+    sessions: BTreeMap<String, SessionRecord>,
+}
+
+/// A session as listed by `anot sessions --json`.
+#[derive(Debug, Serialize)]
+pub struct SessionSummary {
+    #[serde(flatten)]
+    pub record: SessionRecord,
+    pub state: SessionState,
+}
+
+pub fn sessions_path(config: &Config) -> PathBuf {
+    config.data_dir().join(SESSIONS_FILE_NAME)
+}
+
+fn session_key(agent: &str, session_id: &str) -> String {
+    format!("{agent}:{session_id}")
+}
+
+/// Records `event` and its `activity` for the session at `now` and drops sessions idle for a
+/// day.
+pub fn upsert(
+    path: &Path,
+    agent: &str,
+    session_id: &str,
+    cwd: Option<&str>,
+    event: &str,
+    activity: Activity,
+    now: u64,
+) -> Result<(), Error> {
+    with_lock(path, || {
+        let mut state: Sessions = read_json_or_default(path)?;
+        state
+            .sessions
+            .retain(|_, record| now.saturating_sub(record.last_activity) < PRUNE_AFTER_SECS);
+
+        let record = state
+            .sessions
+            .entry(session_key(agent, session_id))
+            .or_insert_with(|| SessionRecord {
+                agent: agent.to_string(),
+                session_id: session_id.to_string(),
+                cwd: None,
+                started: now,
+                last_event: String::new(),
+                last_activity: now,
+                events: 0,
+                waiting_since: None,
+                ended_at: None,
+                tool_calls: BTreeMap::new(),
+                permission_prompts: 0,
+            });
+        // Hooks of one session can finish out of order; the newest event wins.
+        if now >= record.last_activity {
+            record.last_event = event.to_string();
+            record.last_activity = now;
+            record.waiting_since = if WAITING_EVENTS.contains(&event) {
+                record.waiting_since.or(Some(now))
+            } else {
+                None
+            };
+            record.ended_at = ENDING_EVENTS.contains(&event).then_some(now);
+        }
+        if let Some(cwd) = cwd {
+            record.cwd = Some(cwd.to_string());
+        }
+        record.events += 1;
+        if let Some(tool) = activity.tool {
+            *record.tool_calls.entry(tool.to_string()).or_default() += 1;
+        }
+        if activity.permission_prompt {
+            record.permission_prompts += 1;
+        }
+
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())
+    })
+}
+
+/// Records an agent event for `anot sessions`. Failures are logged; they never stop the
+/// notification.
+pub fn observe(
+    config: &Config,
+    agent: &str,
+    session_id: &str,
+    cwd: Option<&str>,
+    event: &str,
+    activity: Activity,
+) {
+    if config.replay || config.dry_run {
+        return;
+    }
+    let path = sessions_path(config);
+    if let Err(e) = upsert(&path, agent, session_id, cwd, event, activity, unix_now()) {
+        warn!(path = %path.display(), error = %e, "failed to update the session registry");
+    }
+}
+
+/// The session's record with the digest counters as they are, which are then reset unless
+/// `keep` is set. `None` when anot hasn't seen the session.
+pub fn take_counters(
+    path: &Path,
+    agent: &str,
+    session_id: &str,
+    keep: bool,
+) -> Result<Option<SessionRecord>, Error> {
+    let key = session_key(agent, session_id);
+    if keep {
+        let state: Sessions = read_json_or_default(path)?;
+        return Ok(state.sessions.get(&key).cloned());
+    }
+
+    with_lock(path, || {
+        let mut state: Sessions = read_json_or_default(path)?;
+        let Some(record) = state.sessions.get_mut(&key) else {
+            return Ok(None);
+        };
+        let taken = record.clone();
+        record.tool_calls.clear();
+        record.permission_prompts = 0;
+
+        write_atomic(path, serde_json::to_string(&state)?.as_bytes())?;
+        Ok(Some(taken))
+    })
+}
+
+/// How many Claude sessions besides `session_id` are stuck on a permission or idle prompt at
+/// `now`: their last event was a `Notification`, nothing has happened since, and it came less
+/// than [`WAITING_STALE_AFTER_SECS`] ago.
+pub fn others_waiting(path: &Path, session_id: &str, now: u64) -> Result<usize, Error> {
+    let state: Sessions = read_json_or_default(path)?;
+    Ok(state
+        .sessions
+        .values()
+        .filter(|record| {
+            record.agent == "claude"
+                && record.session_id != session_id
+                && record.last_event == "Notification"
+                && record.state(now) == SessionState::Waiting
+                && now.saturating_sub(record.last_activity) < WAITING_STALE_AFTER_SECS
+        })
+        .count())
+}
+
+/// The known sessions, most recently active first.
+pub fn list(path: &Path, now: u64) -> Result<Vec<SessionSummary>, Error> {
+    let state: Sessions = read_json_or_default(path)?;
+    let mut sessions: Vec<SessionSummary> = state
+        .sessions
+        .into_values()
+        .filter(|record| now.saturating_sub(record.last_activity) < PRUNE_AFTER_SECS)
+        .map(|record| SessionSummary {
+            state: record.state(now),
+            record,
+        })
+        .collect();
+    sessions.sort_by_key(|session| Reverse(session.record.last_activity));
+    Ok(sessions)
+}
+
+/// The `claude.session_digest` body for `record`, ended because of `reason`: "Session in api
+/// ended (logout) — 14 tool calls (Bash ×6, Edit ×5, Read ×3), 2 permission prompts, ran 48m
+/// 0s". Only the three most used tools are named.
+pub fn digest(config: &Config, record: &SessionRecord, reason: &str) -> String {
+    let mut tools: Vec<(&String, &u64)> = record.tool_calls.iter().collect();
+    tools.sort_by_key(|(_, count)| Reverse(**count));
+    let named = tools
+        .iter()
+        .take(3)
+        .map(|(tool, count)| format!("{tool} ×{count}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let mut activity = match record.tool_calls.values().sum::<u64>() {
+        0 => t(config, "claude.session_digest.no_tool_calls", &[]),
+        1 => t(
+            config,
+            "claude.session_digest.tool_call",
+            &[("tools", &named)],
+        ),
+        count => t(
+            config,
+            "claude.session_digest.tool_calls",
+            &[("count", &count), ("tools", &named)],
+        ),
+    };
+    match record.permission_prompts {
+        0 => {}
+        1 => activity.push_str(&format!(
+            ", {}",
+            t(config, "claude.session_digest.permission_prompt", &[])
+        )),
+        count => activity.push_str(&format!(
+            ", {}",
+            t(
+                config,
+                "claude.session_digest.permission_prompts",
+                &[("count", &count)]
+            )
+        )),
+    }
+
+    let ran = record
+        .ended_at
+        .unwrap_or(record.last_activity)
+        .saturating_sub(record.started);
+    let project = record.cwd.as_deref().and_then(project_name);
+    let key = match project {
+        Some(_) => "claude.session_digest",
+        None => "claude.session_digest.unnamed",
+    };
+    t(
+        config,
+        key,
+        &[
+            ("project", &project.unwrap_or_default()),
+            ("reason", &reason),
+            ("activity", &activity),
+            ("duration", &format_duration(ran)),
+        ],
+    )
+}
+
+/// One line of `anot sessions`: short id, agent, project, last event, age and state.
+pub fn format_session(session: &SessionSummary, now: u64) -> String {
+    let record = &session.record;
+    // Codex sessions are keyed on their directory, which the project column already shows.
+    let short_id: String = if record.cwd.as_ref() == Some(&record.session_id) {
+        "-".to_string()
+    } else {
+        record.session_id.chars().take(8).collect()
+    };
+    let project = record
+        .cwd
+        .as_deref()
+        .and_then(project_name)
+        .unwrap_or_else(|| "-".to_string());
+    let age = format!(
+        "{} ago",
+        format_duration(now.saturating_sub(record.last_activity))
+    );
+    let state = match session.state {
+        SessionState::Active => "active",
+        SessionState::Waiting => "waiting",
+        SessionState::Idle => "idle",
+        SessionState::Ended => "ended",
+    };
+
+    format!(
+        "{short_id:<8}  {:<8}  {project:<20}  {:<18}  {age:>12}  {state}",
+        record.agent, record.last_event
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support;
+
+    fn temp_sessions_path(test_name: &str) -> PathBuf {
+        test_support::temp_path("sessions", test_name).join(SESSIONS_FILE_NAME)
+    }
+
+    fn ids(sessions: &[SessionSummary]) -> Vec<&str> {
+        sessions
+            .iter()
+            .map(|session| session.record.session_id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn upserts_keep_the_newest_event() {
+        let path = temp_sessions_path("upsert");
+        upsert(
+            &path,
+            "claude",
+            "a",
+            Some("/work/api"),
+            "SessionStart",
+            Activity::default(),
+            1000,
+        )
+        .unwrap();
+        upsert(
+            &path,
+            "claude",
+            "b",
+            Some("/work/web"),
+            "SessionStart",
+            Activity::default(),
+            1010,
+        )
+        .unwrap();
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "PreToolUse",
+            Activity::default(),
+            1020,
+        )
+        .unwrap();
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "UserPromptSubmit",
+            Activity::default(),
+            1015,
+        )
+        .unwrap();
+
+        let sessions = list(&path, 1030).unwrap();
+        assert_eq!(ids(&sessions), ["a", "b"]);
+        let a = &sessions[0].record;
+        assert_eq!(a.last_event, "PreToolUse", "a late hook doesn't win");
+        assert_eq!(a.last_activity, 1020);
+        assert_eq!(a.started, 1000);
+        assert_eq!(a.events, 3);
+        assert_eq!(a.cwd.as_deref(), Some("/work/api"));
+    }
+
+    #[test]
+    fn stale_sessions_are_pruned() {
+        let path = temp_sessions_path("prune");
+        upsert(
+            &path,
+            "claude",
+            "old",
+            None,
+            "Stop",
+            Activity::default(),
+            1000,
+        )
+        .unwrap();
+        upsert(
+            &path,
+            "codex",
+            "new",
+            None,
+            "AgentTurnComplete",
+            Activity::default(),
+            5000,
+        )
+        .unwrap();
+
+        let later = 1000 + PRUNE_AFTER_SECS;
+        assert_eq!(ids(&list(&path, later).unwrap()), ["new"]);
+
+        upsert(
+            &path,
+            "codex",
+            "new",
+            None,
+            "AgentTurnComplete",
+            Activity::default(),
+            later,
+        )
+        .unwrap();
+        let state: Sessions = read_json_or_default(&path).unwrap();
+        assert_eq!(
+            state.sessions.keys().collect::<Vec<_>>(),
+            ["codex:new"],
+            "pruned from the file too"
+        );
+    }
+
+    #[test]
+    fn state_follows_the_last_event() {
+        let path = temp_sessions_path("state");
+        let state_at = |now| list(&path, now).unwrap()[0].state;
+
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "PreToolUse",
+            Activity::default(),
+            1000,
+        )
+        .unwrap();
+        assert_eq!(state_at(1010), SessionState::Active);
+        assert_eq!(state_at(1000 + IDLE_AFTER_SECS), SessionState::Idle);
+
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "Notification",
+            Activity::default(),
+            1100,
+        )
+        .unwrap();
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "Stop",
+            Activity::default(),
+            1150,
+        )
+        .unwrap();
+        assert_eq!(state_at(1200), SessionState::Waiting);
+        assert_eq!(
+            list(&path, 1200).unwrap()[0].record.waiting_since,
+            Some(1100),
+            "waiting since the first prompt"
+        );
+
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "UserPromptSubmit",
+            Activity::default(),
+            1300,
+        )
+        .unwrap();
+        assert_eq!(state_at(1310), SessionState::Active);
+
+        upsert(
+            &path,
+            "claude",
+            "a",
+            None,
+            "SessionEnd",
+            Activity::default(),
+            1400,
+        )
+        .unwrap();
+        assert_eq!(state_at(1410), SessionState::Ended);
+    }
+
+    #[test]
+    fn only_fresh_unanswered_prompts_of_other_claude_sessions_count() {
+        let path = temp_sessions_path("waiting");
+        let event = |agent, session, event, at| {
+            upsert(&path, agent, session, None, event, Activity::default(), at).unwrap();
+        };
+        let now = 10_000;
+
+        event("claude", "a", "Notification", now - 10);
+        event("claude", "b", "Notification", now - 20);
+        event("claude", "b", "UserPromptSubmit", now - 5);
+        event(
+            "claude",
+            "c",
+            "Notification",
+            now - WAITING_STALE_AFTER_SECS,
+        );
+        event("claude", "d", "Stop", now - 10);
+        event("codex", "e", "AgentTurnComplete", now - 10);
+        event("claude", "f", "Notification", now - 30);
+
+        assert_eq!(others_waiting(&path, "a", now).unwrap(), 1, "f");
+        assert_eq!(others_waiting(&path, "b", now).unwrap(), 2, "a and f");
+        assert_eq!(others_waiting(&path, "new", now).unwrap(), 2);
+    }
+
+    #[test]
+    fn digest_sums_the_session_up_and_resets_its_counters() {
+        let path = temp_sessions_path("digest");
+        let tool = |tool| Activity {
+            tool: Some(tool),
+            ..Activity::default()
+        };
+        let prompt = Activity {
+            permission_prompt: true,
+            ..Activity::default()
+        };
+        let events = [
+            ("SessionStart", Activity::default()),
+            ("PostToolUse", tool("Bash")),
+            ("PostToolUse", tool("Edit")),
+            ("Notification", prompt),
+            ("PostToolUse", tool("Bash")),
+            ("PostToolUse", tool("Read")),
+            ("PostToolUse", tool("Bash")),
+            ("Notification", Activity::default()),
+            ("PostToolUse", tool("Grep")),
+            ("Notification", prompt),
+            ("PostToolUse", tool("Edit")),
+            ("SessionEnd", Activity::default()),
+        ];
+        for (n, (event, activity)) in events.into_iter().enumerate() {
+            let now = 1000 + n as u64 * 240;
+            upsert(
+                &path,
+                "claude",
+                "a",
+                Some("/work/api"),
+                event,
+                activity,
+                now,
+            )
+            .unwrap();
+        }
+        let config = Config::default();
+
+        let record = take_counters(&path, "claude", "a", true).unwrap().unwrap();
+        assert_eq!(
+            digest(&config, &record, "logout"),
+            "Session in api ended (logout) — 7 tool calls (Bash ×3, Edit ×2, Grep ×1), \
+             2 permission prompts, ran 44m 0s"
+        );
+
+        let record = take_counters(&path, "claude", "a", false).unwrap().unwrap();
+        assert_eq!(record.tool_calls.values().sum::<u64>(), 7);
+        let record = take_counters(&path, "claude", "a", false).unwrap().unwrap();
+        assert!(record.tool_calls.is_empty(), "the counters are reset");
+        assert_eq!(record.permission_prompts, 0);
+        assert_eq!(
+            digest(&config, &record, "clear"),
+            "Session in api ended (clear) — no tool calls, ran 44m 0s"
+        );
+        assert_eq!(take_counters(&path, "claude", "b", false).unwrap(), None);
+    }
+
+    #[test]
+    fn formats_a_session_line() {
+        let session = SessionSummary {
+            record: SessionRecord {
+                agent: "claude".to_string(),
+                session_id: "0f3c9a2e-1111-2222".to_string(),
+                cwd: Some("/work/api".to_string()),
+                started: 1000,
+                last_event: "Notification".to_string(),
+                last_activity: 1000,
+                events: 4,
+                waiting_since: Some(1000),
+                ended_at: None,
+                tool_calls: BTreeMap::new(),
+                permission_prompts: 0,
+            },
+            state: SessionState::Waiting,
+        };
+        assert_eq!(
+            format_session(&session, 1125),
+            "0f3c9a2e  claude    api                   Notification           2m 5s ago  waiting"
+        );
+    }
+}
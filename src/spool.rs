@@ -0,0 +1,400 @@
+//! The spool of notifications held back to be sent later, e.g. by `anot mute` with
+//! `mute_mode: defer`. `anot flush` sends them, one by one or as a single digest.
+//!
+//! On disk it's `spool.jsonl` in the state directory: one JSON object per line, appended under
+//! the file lock in the order the events arrived.
+//!
+//! ```json
+//! {"spooled_at": 1767225600, "agent": "claude", "event": "Stop", "reason": "muted", "input": "{\"session_id\":\"...\"}"}
+//! ```
+//!
+//! `input` is the agent's raw payload, so a spooled notification is rendered when it's sent,
+//! like a live one. Taking the spool empties it under the same lock: each entry is sent once
+//! even when flushes race, and one appended meanwhile waits for the next flush. Lines that
+//! can't be read are skipped.
+
+use std::{
+    fs::{self, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::{
+    backends::desktop,
+    configuration::{Config, DeliveryMode},
+    delivery::{
+        self,
+        detached::{self, DeliveryJob},
+    },
+    storage::{with_lock, write_atomic},
+};
+
+const SPOOL_FILE_NAME: &str = "spool.jsonl";
+
+/// Where `mute_mode: defer` kept notifications before the spool was shared. Still read, so
+/// nothing deferred by an older version is lost.
+const LEGACY_MUTE_FILE_NAME: &str = "muted.jsonl";
+
+/// A notification waiting in the spool.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SpooledNotification {
+    /// When it was held back. `0` for entries written before this was recorded.
+    #[serde(default)]
+    pub spooled_at: u64,
+    pub agent: String,
+    /// The event or notification type, e.g. `Stop`. Empty for entries written before this was
+    /// recorded.
+    #[serde(default)]
+    pub event: String,
+    /// Why it was held back, e.g. `muted`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// The agent's payload, as it was passed to anot.
+    pub input: String,
+    /// The payload came from another machine, see [`Config::remote`].
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub remote: bool,
+}
+
+/// What `anot flush` sent.
+#[derive(Debug, Clone, Copy, Default, Serialize, PartialEq, Eq)]
+pub struct FlushReport {
+    /// Spooled notifications taken from the spool.
+    pub flushed: usize,
+    /// Those that couldn't be sent. They aren't spooled again.
+    pub failed: usize,
+    /// Whether they were sent as one digest notification.
+    pub digest: bool,
+}
+
+pub fn spool_path(config: &Config) -> PathBuf {
+    config.data_dir().join(SPOOL_FILE_NAME)
+}
+
+fn legacy_mute_path(config: &Config) -> PathBuf {
+    config.data_dir().join(LEGACY_MUTE_FILE_NAME)
+}
+
+/// Adds `entry` to the end of the spool at `path`.
+pub fn append(path: &Path, entry: &SpooledNotification) -> Result<(), Error> {
+    with_lock(path, || {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", serde_json::to_string(entry)?)?;
+        Ok(())
+    })
+}
+
+/// The entries of the spool at `path`, oldest first, leaving them in place.
+pub fn read(path: &Path) -> Result<Vec<SpooledNotification>, Error> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    Ok(contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                debug!(error = %e, "skipping unreadable spooled notification");
+                None
+            }
+        })
+        .collect())
+}
+
+/// Empties the spool at `path` and returns what was in it, oldest first.
+pub fn take(path: &Path) -> Result<Vec<SpooledNotification>, Error> {
+    with_lock(path, || {
+        let entries = read(path)?;
+        match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(entries),
+        }
+    })
+}
+
+/// Replaces the spool at `path` with `entries`, removing it when there are none. Unlike
+/// [`append`] and [`take`] this doesn't lock; callers hold [`with_lock`] on `path`.
+pub fn replace(path: &Path, entries: &[SpooledNotification]) -> Result<(), Error> {
+    if entries.is_empty() {
+        return match fs::remove_file(path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        };
+    }
+    let mut contents = String::new();
+    for entry in entries {
+        contents.push_str(&serde_json::to_string(entry)?);
+        contents.push('\n');
+    }
+    write_atomic(path, contents.as_bytes())
+}
+
+/// The notifications waiting to be sent, oldest first.
+pub fn pending(config: &Config) -> Result<Vec<SpooledNotification>, Error> {
+    let mut entries = read(&legacy_mute_path(config))?;
+    entries.extend(read(&spool_path(config))?);
+    Ok(entries)
+}
+
+/// Sends everything in the spool: each notification as it would have been sent, or with
+/// `digest` one notification summing them up. A flush with nothing spooled sends nothing.
+pub fn flush(config: &Config, digest: bool) -> Result<FlushReport, Error> {
+    let mut entries = take(&legacy_mute_path(config))?;
+    entries.extend(take(&spool_path(config))?);
+    if entries.is_empty() {
+        return Ok(FlushReport {
+            digest,
+            ..FlushReport::default()
+        });
+    }
+
+    let config = Config {
+        delivery: DeliveryMode::Blocking,
+        flushing: true,
+        ..config.clone()
+    };
+    let failed = if digest {
+        let (title, body) = digest_text(&entries);
+        let sent = delivery::deliver(&config, "anot", "Digest", &title, &body, || {
+            desktop::show(&title, &body)
+        });
+        match sent {
+            Ok(()) => 0,
+            Err(e) => {
+                warn!(error = %format_args!("{e:#}"), "failed to send the digest of spooled notifications");
+                entries.len()
+            }
+        }
+    } else {
+        let mut failed = 0;
+        for entry in &entries {
+            let job = DeliveryJob {
+                agent: entry.agent.clone(),
+                input: entry.input.clone(),
+                forward: false,
+                remote: entry.remote,
+            };
+            if let Err(e) = detached::deliver_job(job, &config) {
+                warn!(agent = %entry.agent, error = %format_args!("{e:#}"), "failed to send spooled notification");
+                failed += 1;
+            }
+        }
+        failed
+    };
+
+    info!(count = entries.len(), failed, digest, "flushed the spool");
+    Ok(FlushReport {
+        flushed: entries.len(),
+        failed,
+        digest,
+    })
+}
+
+/// The title and body of a digest of `entries`, e.g. "anot · 5 notifications held back" and
+/// "claude: Stop ×2, Notification · codex: AgentTurnComplete ×2". Agents and events are listed
+/// in the order they first came.
+pub fn digest_text(entries: &[SpooledNotification]) -> (String, String) {
+    let mut agents: Vec<(&str, Vec<(&str, usize)>)> = Vec::new();
+    for entry in entries {
+        let event = if entry.event.is_empty() {
+            "notification"
+        } else {
+            entry.event.as_str()
+        };
+        let events = match agents.iter().position(|(agent, _)| *agent == entry.agent) {
+            Some(index) => &mut agents[index].1,
+            None => {
+                agents.push((&entry.agent, Vec::new()));
+                &mut agents.last_mut().expect("just pushed").1
+            }
+        };
+        match events.iter_mut().find(|(name, _)| *name == event) {
+            Some((_, count)) => *count += 1,
+            None => events.push((event, 1)),
+        }
+    }
+
+    let title = match entries.len() {
+        1 => "anot · 1 notification held back".to_string(),
+        n => format!("anot · {n} notifications held back"),
+    };
+    let body = agents
+        .iter()
+        .map(|(agent, events)| {
+            let events: Vec<String> = events
+                .iter()
+                .map(|(event, count)| match count {
+                    1 => event.to_string(),
+                    n => format!("{event} ×{n}"),
+                })
+                .collect();
+            format!("{agent}: {}", events.join(", "))
+        })
+        .collect::<Vec<_>>()
+        .join(" · ");
+    (title, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::Arc, thread};
+
+    use super::*;
+    use crate::{configuration::Backend, test_support};
+
+    fn temp_config(test_name: &str) -> Config {
+        Config {
+            backends: vec![Backend::Statusfile],
+            dedupe_window_secs: 0,
+            ..test_support::temp_config("spool", test_name)
+        }
+    }
+
+    fn entry(agent: &str, event: &str, input: &str) -> SpooledNotification {
+        SpooledNotification {
+            spooled_at: 1000,
+            agent: agent.to_string(),
+            event: event.to_string(),
+            reason: Some("muted".to_string()),
+            input: input.to_string(),
+            remote: false,
+        }
+    }
+
+    const STOP: &str =
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+    #[test]
+    fn digest_counts_events_per_agent() {
+        let entries = [
+            entry("claude", "Stop", ""),
+            entry("codex", "AgentTurnComplete", ""),
+            entry("claude", "Notification", ""),
+            entry("claude", "Stop", ""),
+            entry("codex", "AgentTurnComplete", ""),
+        ];
+
+        assert_eq!(
+            digest_text(&entries),
+            (
+                "anot · 5 notifications held back".to_string(),
+                "claude: Stop ×2, Notification · codex: AgentTurnComplete ×2".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn flushing_an_empty_spool_sends_nothing() {
+        let config = temp_config("empty");
+        for digest in [false, true] {
+            assert_eq!(
+                flush(&config, digest).unwrap(),
+                FlushReport {
+                    flushed: 0,
+                    failed: 0,
+                    digest
+                }
+            );
+        }
+        assert!(!config.data_dir().join("status").exists());
+    }
+
+    #[test]
+    fn flush_sends_each_entry_once() {
+        let config = temp_config("individual");
+        let status = config.data_dir().join("status");
+        append(&spool_path(&config), &entry("claude", "Stop", STOP)).unwrap();
+        // Written by an older version, before the spool was shared.
+        fs::write(
+            legacy_mute_path(&config),
+            format!(
+                "{}\n",
+                serde_json::json!({"agent": "claude", "input": STOP})
+            ),
+        )
+        .unwrap();
+        assert_eq!(pending(&config).unwrap().len(), 2);
+
+        assert_eq!(flush(&config, false).unwrap().flushed, 2);
+        assert!(
+            fs::read_to_string(&status)
+                .unwrap()
+                .contains("The agent has stopped responding.")
+        );
+        assert!(pending(&config).unwrap().is_empty());
+
+        fs::remove_file(&status).unwrap();
+        assert_eq!(flush(&config, false).unwrap().flushed, 0);
+        assert!(!status.exists(), "a second flush sends nothing");
+    }
+
+    #[test]
+    fn digest_is_one_notification() {
+        let config = temp_config("digest");
+        let path = spool_path(&config);
+        append(&path, &entry("claude", "Stop", STOP)).unwrap();
+        append(&path, &entry("claude", "Stop", STOP)).unwrap();
+
+        let report = flush(&config, true).unwrap();
+        assert_eq!(
+            report,
+            FlushReport {
+                flushed: 2,
+                failed: 0,
+                digest: true
+            }
+        );
+        assert_eq!(
+            fs::read_to_string(config.data_dir().join("status")).unwrap(),
+            "anot · 2 notifications held back: claude: Stop ×2\n"
+        );
+    }
+
+    #[test]
+    fn appends_while_taking_are_never_lost_or_taken_twice() {
+        let config = temp_config("concurrent");
+        let path = Arc::new(spool_path(&config));
+        const WRITERS: usize = 4;
+        const PER_WRITER: usize = 50;
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|writer| {
+                let path = Arc::clone(&path);
+                thread::spawn(move || {
+                    for n in 0..PER_WRITER {
+                        append(&path, &entry("claude", "Stop", &format!("{writer}-{n}"))).unwrap();
+                    }
+                })
+            })
+            .collect();
+        let mut taken = Vec::new();
+        while writers.iter().any(|writer| !writer.is_finished()) {
+            taken.extend(take(&path).unwrap());
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+        taken.extend(take(&path).unwrap());
+
+        assert_eq!(taken.len(), WRITERS * PER_WRITER);
+        let mut inputs: Vec<String> = taken.into_iter().map(|entry| entry.input).collect();
+        inputs.sort();
+        inputs.dedup();
+        assert_eq!(
+            inputs.len(),
+            WRITERS * PER_WRITER,
+            "each entry is taken once"
+        );
+    }
+}
@@ -0,0 +1,237 @@
+//! Crash- and race-safe file writes for the config and the state files anot keeps next to it.
+//! Several hook processes often run at once (e.g. `SessionStart` from parallel subagents), so
+//! files are replaced atomically and read-modify-write cycles run under an advisory lock.
+
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Error;
+use serde::de::DeserializeOwned;
+use tracing::debug;
+
+/// A uniquely named sibling of `path` to write to before moving it into place. The counter
+/// keeps names unique between threads writing in the same nanosecond.
+fn temp_path(path: &Path) -> PathBuf {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    path.with_file_name(format!(
+        ".{file_name}.{}.{nanos}.{count}.tmp",
+        std::process::id()
+    ))
+}
+
+fn write_temp(temp_path: &Path, contents: &[u8]) -> std::io::Result<()> {
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(temp_path)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
+/// Replaces `path` with `contents` atomically: readers see either the old or the new file,
+/// never a partially written one, even when several hook processes write at once.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = temp_path(path);
+    let written = write_temp(&temp_path, contents).and_then(|_| fs::rename(&temp_path, path));
+
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    Ok(())
+}
+
+/// Creates `path` with `contents` without ever truncating an existing file.
+///
+/// The contents are written to a uniquely named temp file first and then hard-linked into
+/// place, so concurrent callers either see no file or a complete one. Returns `false` when
+/// another process created the file first.
+pub fn create_file_exclusively(path: &Path, contents: &[u8]) -> Result<bool, Error> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temp_path = temp_path(path);
+    if let Err(e) = write_temp(&temp_path, contents) {
+        let _ = fs::remove_file(&temp_path);
+        return Err(e.into());
+    }
+
+    let created = match fs::hard_link(&temp_path, path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+        Err(e) => {
+            // Filesystems without hard links: fall back to a rename, which is still atomic
+            // but may replace a file created in the meantime by another process.
+            debug!(error = %e, "hard link failed; falling back to rename");
+            if path.exists() {
+                Ok(false)
+            } else {
+                fs::rename(&temp_path, path).map(|_| true)
+            }
+        }
+    };
+
+    let _ = fs::remove_file(&temp_path);
+
+    if let Ok(false) = created {
+        debug!(path = %path.display(), "file was created concurrently; keeping it");
+    }
+
+    Ok(created?)
+}
+
+/// The lock file guarding `path`. `path` itself can't hold the lock because
+/// [`write_atomic`] replaces it.
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(".lock");
+    path.with_file_name(name)
+}
+
+/// Runs `f` while holding an exclusive advisory lock on `path`, so concurrent read-modify-write
+/// cycles on the same file run one after another. The lock is released when `f` returns.
+pub fn with_lock<T>(path: &Path, f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    let lock_path = lock_path(path);
+    if let Some(parent) = lock_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let lock = File::options()
+        .create(true)
+        .write(true)
+        .truncate(false)
+        .open(&lock_path)?;
+    lock.lock()?;
+    f()
+}
+
+/// Reads the JSON state file at `path`. A missing file gives the default state, and so does a
+/// corrupt one, which the next write then replaces.
+pub fn read_json_or_default<T: DeserializeOwned + Default>(path: &Path) -> Result<T, Error> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Ok(serde_json::from_str(&contents).unwrap_or_else(|e| {
+            debug!(path = %path.display(), error = %e, "discarding unreadable state file");
+            T::default()
+        })),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(T::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::Arc, thread};
+
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    #[test]
+    fn locked_updates_are_not_lost() {
+        let path = Arc::new(temp_dir("storage", "lock").join("counter.json"));
+
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let path = Arc::clone(&path);
+                thread::spawn(move || {
+                    for _ in 0..25 {
+                        with_lock(&path, || {
+                            let mut counts: BTreeMap<String, u64> = read_json_or_default(&path)?;
+                            *counts.entry("count".to_string()).or_default() += 1;
+                            write_atomic(&path, serde_json::to_string(&counts)?.as_bytes())
+                        })
+                        .unwrap();
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let counts: BTreeMap<String, u64> = read_json_or_default(&path).unwrap();
+        assert_eq!(counts["count"], 200);
+    }
+
+    #[test]
+    fn readers_never_see_torn_writes() {
+        let path = Arc::new(temp_dir("storage", "torn").join("state.json"));
+        let contents: Arc<Vec<String>> = Arc::new(
+            ('a'..='h')
+                .map(|c| c.to_string().repeat(64 * 1024))
+                .collect(),
+        );
+        write_atomic(&path, contents[0].as_bytes()).unwrap();
+
+        let writers: Vec<_> = (0..contents.len())
+            .map(|i| {
+                let (path, contents) = (Arc::clone(&path), Arc::clone(&contents));
+                thread::spawn(move || {
+                    for _ in 0..20 {
+                        write_atomic(&path, contents[i].as_bytes()).unwrap();
+                    }
+                })
+            })
+            .collect();
+        for _ in 0..200 {
+            let read = fs::read_to_string(path.as_path()).unwrap();
+            assert!(contents.contains(&read), "read a partially written file");
+        }
+        for writer in writers {
+            writer.join().unwrap();
+        }
+
+        let leftovers: Vec<_> = fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name.to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(
+            leftovers.is_empty(),
+            "temp files left behind: {leftovers:?}"
+        );
+    }
+
+    #[test]
+    fn corrupt_state_reads_as_default() {
+        let path = temp_dir("storage", "corrupt").join("state.json");
+        fs::write(&path, "{\"count\": 4").unwrap();
+
+        let counts: BTreeMap<String, u64> = read_json_or_default(&path).unwrap();
+        assert!(counts.is_empty());
+        let missing: BTreeMap<String, u64> =
+            read_json_or_default(&path.with_file_name("missing.json")).unwrap();
+        assert!(missing.is_empty());
+    }
+
+    #[test]
+    fn exclusive_creation_keeps_the_first_file() {
+        let path = temp_dir("storage", "exclusive").join("a-notifications.json");
+
+        assert!(create_file_exclusively(&path, b"first").unwrap());
+        assert!(!create_file_exclusively(&path, b"second").unwrap());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    }
+}
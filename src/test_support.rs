@@ -0,0 +1,38 @@
+//! Fixtures shared by the unit tests.
+
+use std::{
+    fs,
+    path::PathBuf,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::configuration::Config;
+
+/// A path under the system temp dir that no other test or test run uses, e.g.
+/// `/tmp/anot-spool-4242-1760659200000000000-retry`. Nothing is created there.
+pub(crate) fn temp_path(prefix: &str, test_name: &str) -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_nanos();
+    std::env::temp_dir().join(format!(
+        "anot-{prefix}-{}-{nanos}-{test_name}",
+        std::process::id()
+    ))
+}
+
+/// A [`temp_path`], created as an empty directory.
+pub(crate) fn temp_dir(prefix: &str, test_name: &str) -> PathBuf {
+    let dir = temp_path(prefix, test_name);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// The default config, located in a [`temp_path`] so the state it keeps stays out of the
+/// user's.
+pub(crate) fn temp_config(prefix: &str, test_name: &str) -> Config {
+    Config {
+        location: Some(temp_path(prefix, test_name).join("a-notifications.json")),
+        ..Config::default()
+    }
+}
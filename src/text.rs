@@ -0,0 +1,151 @@
+use std::sync::LazyLock;
+
+use regex::Regex;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// How far back from the length limit truncation looks for the end of a sentence or line.
+const BOUNDARY_LOOKBACK: usize = 40;
+
+/// Prepares an agent message for a notification body: strips markdown when `strip` is set,
+/// then shortens it to `max_len` characters. A `max_len` of `0` keeps the full text.
+pub fn format_body(body: &str, max_len: usize, strip: bool) -> String {
+    let body = if strip {
+        strip_markdown(body)
+    } else {
+        body.to_string()
+    };
+
+    if max_len == 0 {
+        body
+    } else {
+        truncate_at_boundary(&body, max_len)
+    }
+}
+
+static CODE_FENCE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^[ \t]*```.*\n?").unwrap());
+static HEADING: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^[ \t]{0,3}#{1,6}[ \t]+").unwrap());
+static LINK: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"!?\[([^\]]*)\]\([^)]*\)").unwrap());
+
+/// Removes the markdown syntax that reads as noise in a notification: code fences and
+/// backticks, `**`, heading hashes, and link targets (the link text is kept).
+pub fn strip_markdown(text: &str) -> String {
+    // Each pattern is compiled on first use, once per process, so text without the syntax
+    // skips it.
+    let mut text = text.to_string();
+    if text.contains("```") {
+        text = CODE_FENCE.replace_all(&text, "").into_owned();
+    }
+    if text.contains('#') {
+        text = HEADING.replace_all(&text, "").into_owned();
+    }
+    if text.contains("](") {
+        text = LINK.replace_all(&text, "$1").into_owned();
+    }
+
+    text.replace("**", "").replace('`', "").trim().to_string()
+}
+
+/// Shortens `text` to at most `max_len` grapheme clusters including the trailing `…`, so
+/// emoji and combining characters are never split. When a sentence or line ends within the
+/// last few characters before the limit, the cut is made there instead.
+pub fn truncate_at_boundary(text: &str, max_len: usize) -> String {
+    let graphemes: Vec<(usize, &str)> = text.grapheme_indices(true).collect();
+    if graphemes.len() <= max_len {
+        return text.to_string();
+    }
+
+    let keep = max_len.saturating_sub(1);
+    let cut = graphemes.get(keep).map_or(text.len(), |(i, _)| *i);
+    let window_start = keep.saturating_sub(BOUNDARY_LOOKBACK);
+
+    let boundary = (window_start..keep).rev().find_map(|i| {
+        let (offset, grapheme) = graphemes[i];
+        let next = graphemes.get(i + 1).map(|(_, g)| *g);
+        match grapheme {
+            "\n" => Some(offset),
+            "." | "!" | "?" if next.is_none_or(|g| g.trim().is_empty()) => {
+                Some(offset + grapheme.len())
+            }
+            _ => None,
+        }
+    });
+
+    let kept = text[..boundary.filter(|b| *b > 0).unwrap_or(cut)].trim_end();
+    format!("{kept}…")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn strips_markdown() {
+        let cases = [
+            ("Run `cargo test` first", "Run cargo test first"),
+            ("This is **important** now", "This is important now"),
+            ("## Summary\nAll done", "Summary\nAll done"),
+            (
+                "See [the docs](https://example.com/docs) and ![logo](logo.png)",
+                "See the docs and logo",
+            ),
+            (
+                "Fixed it:\n```rust\nlet x = 1;\n```\nDone",
+                "Fixed it:\nlet x = 1;\nDone",
+            ),
+            (
+                "Calls mcp__github__create_issue",
+                "Calls mcp__github__create_issue",
+            ),
+            ("#hashtag stays", "#hashtag stays"),
+        ];
+
+        for (input, expected) in cases {
+            assert_eq!(strip_markdown(input), expected, "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn truncates_at_boundaries() {
+        let cases = [
+            ("short", 10, "short"),
+            ("exactly ten", 11, "exactly ten"),
+            ("one two three four", 10, "one two t…"),
+            (
+                "First sentence. Second sentence is long",
+                30,
+                "First sentence.…",
+            ),
+            ("Line one\nline two goes on", 15, "Line one…"),
+            ("Version 1.2 is out now", 15, "Version 1.2 is…"),
+            ("héllo wörld ünïcode", 8, "héllo w…"),
+            ("👍🏽👍🏽👍🏽👍🏽👍🏽", 3, "👍🏽👍🏽…"),
+            ("e\u{301}e\u{301}e\u{301}e\u{301}", 3, "e\u{301}e\u{301}…"),
+        ];
+
+        for (input, max_len, expected) in cases {
+            assert_eq!(
+                truncate_at_boundary(input, max_len),
+                expected,
+                "input: {input:?}, max_len: {max_len}"
+            );
+        }
+    }
+
+    #[test]
+    fn formats_markdown_heavy_messages() {
+        let message = "## Done\n\nI updated **`src/main.rs`** to use [clap](https://docs.rs/clap). \
+                       Then I ran the tests and they all pass now, which took a while.";
+
+        assert_eq!(
+            format_body(message, 80, true),
+            "Done\n\nI updated src/main.rs to use clap.…"
+        );
+        assert_eq!(
+            format_body(message, 0, true),
+            "Done\n\nI updated src/main.rs to use clap. \
+             Then I ran the tests and they all pass now, which took a while."
+        );
+        assert!(format_body(message, 80, false).starts_with("## Done"));
+    }
+}
@@ -0,0 +1,100 @@
+//! `--time-report`: how long each stage of one invocation took, printed to stderr when it's
+//! done. Hooks run on every tool call, so this is where their startup cost shows.
+
+use std::{
+    cell::RefCell,
+    fmt::Write,
+    time::{Duration, Instant},
+};
+
+struct Report {
+    started: Instant,
+    last: Instant,
+    stages: Vec<(&'static str, Duration)>,
+}
+
+thread_local! {
+    static REPORT: RefCell<Option<Report>> = const { RefCell::new(None) };
+}
+
+/// Starts timing from `started`, the start of the process.
+pub fn start(started: Instant) {
+    REPORT.set(Some(Report {
+        started,
+        last: started,
+        stages: Vec::new(),
+    }));
+}
+
+/// Ends the stage `name` now, when timing. The next stage starts here.
+pub fn mark(name: &'static str) {
+    REPORT.with_borrow_mut(|report| {
+        if let Some(report) = report {
+            let now = Instant::now();
+            report.stages.push((name, now - report.last));
+            report.last = now;
+        }
+    });
+}
+
+/// The stages timed on this thread and the total since [`start`], one per line, or `None`
+/// when not timing.
+pub fn finish() -> Option<String> {
+    let report = REPORT.take()?;
+    Some(format_report(&report.stages, report.started.elapsed()))
+}
+
+fn format_report(stages: &[(&str, Duration)], total: Duration) -> String {
+    let width = stages
+        .iter()
+        .map(|(name, _)| name.len())
+        .chain(["total".len()])
+        .max()
+        .unwrap_or_default();
+    let mut out = String::new();
+    for (name, took) in stages.iter().chain([&("total", total)]) {
+        let _ = writeln!(
+            out,
+            "{name:<width$}  {:>8.3} ms",
+            took.as_secs_f64() * 1000.0
+        );
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_records_while_timing() {
+        mark("ignored");
+        assert_eq!(finish(), None);
+
+        start(Instant::now());
+        mark("config");
+        mark("process");
+        let report = finish().unwrap();
+        let names: Vec<_> = report
+            .lines()
+            .map(|line| line.split_whitespace().next().unwrap())
+            .collect();
+        assert_eq!(names, ["config", "process", "total"]);
+        assert_eq!(finish(), None);
+    }
+
+    #[test]
+    fn lines_up_the_stages() {
+        let report = format_report(
+            &[
+                ("logging", Duration::from_micros(250)),
+                ("config", Duration::from_micros(1500)),
+            ],
+            Duration::from_micros(1900),
+        );
+        assert_eq!(
+            report,
+            "logging     0.250 ms\nconfig      1.500 ms\ntotal       1.900 ms\n"
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! `anot uninstall`: removes the anot hooks from the Claude Code settings files, the Codex
+//! `notify` that runs anot, and optionally anot's own config and state, asking before each
+//! step. A step that fails is reported and the others still run.
+
+use std::{
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use inquire::Confirm;
+use serde::Serialize;
+use tracing::info;
+
+use crate::{
+    configuration::{get_config_path, get_state_dir, local::local_path},
+    preview::unified_diff,
+    processors::{claude, codex},
+    storage::write_atomic,
+};
+
+/// The name of the directories anot keeps its config and state in.
+const ANOT_DIR_NAME: &str = "agent_notifications";
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Changed,
+    Skipped,
+    Failed,
+}
+
+/// What one step of the uninstall did to `path`.
+#[derive(Debug, Clone, Serialize, PartialEq, Eq)]
+pub struct UninstallStep {
+    pub step: &'static str,
+    pub path: PathBuf,
+    pub outcome: StepOutcome,
+    /// Why it was skipped or failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl UninstallStep {
+    fn new(step: &'static str, path: &Path, outcome: StepOutcome, detail: Option<String>) -> Self {
+        UninstallStep {
+            step,
+            path: path.to_path_buf(),
+            outcome,
+            detail,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UninstallOptions {
+    /// Don't ask: agent config files are cleaned up, and anot's own files are deleted only with
+    /// `purge`.
+    pub yes: bool,
+    /// Delete anot's config and state without asking, with `yes`.
+    pub purge: bool,
+    /// The config given with `--config`, if any. Only that file (and its local overrides) is
+    /// deleted then, not the default config directory.
+    pub config_path: Option<PathBuf>,
+}
+
+/// Runs every step and reports what each one did.
+pub fn run(options: &UninstallOptions) -> Vec<UninstallStep> {
+    let mut steps = Vec::new();
+
+    for path in claude::init::uninstall_candidates() {
+        steps.push(edit_agent_config(
+            "claude hooks",
+            &path,
+            claude::init::without_our_hooks,
+            options,
+        ));
+    }
+    for path in codex::init::uninstall_candidates() {
+        steps.push(edit_agent_config(
+            "codex notify",
+            &path,
+            codex::init::without_our_notify,
+            options,
+        ));
+    }
+    for (step, path) in data_paths(options) {
+        steps.push(delete(step, &path, options));
+    }
+
+    steps
+}
+
+/// anot's own files: the config directory (or the `--config` file and its local overrides)
+/// and the state directory with the logs and history.
+fn data_paths(options: &UninstallOptions) -> Vec<(&'static str, PathBuf)> {
+    let mut paths = match &options.config_path {
+        Some(config_path) => config_paths(config_path, false),
+        None => get_config_path()
+            .map(|config_path| config_paths(&config_path, true))
+            .unwrap_or_default(),
+    };
+    let state_dir = get_state_dir();
+    if !paths.iter().any(|(_, path)| *path == state_dir) {
+        paths.push(("anot state and logs", state_dir));
+    }
+    paths
+}
+
+/// The config's whole directory when `whole_dir` and it's anot's own, and otherwise just the
+/// config file and its local overrides. Without a system config directory the config sits in
+/// the current directory, which holds the user's own files too.
+fn config_paths(config_path: &Path, whole_dir: bool) -> Vec<(&'static str, PathBuf)> {
+    match config_path.parent() {
+        Some(dir) if whole_dir && is_anot_dir(dir) => vec![("anot config", dir.to_path_buf())],
+        _ => vec![
+            ("anot config", config_path.to_path_buf()),
+            ("anot config", local_path(config_path)),
+        ],
+    }
+}
+
+/// Whether `dir` is an `agent_notifications` directory, which only ever holds anot's files.
+fn is_anot_dir(dir: &Path) -> bool {
+    dir.file_name().is_some_and(|name| name == ANOT_DIR_NAME)
+}
+
+fn edit_agent_config(
+    step: &'static str,
+    path: &Path,
+    without_anot: impl FnOnce(&PathBuf) -> Result<Option<(String, String)>, Error>,
+    options: &UninstallOptions,
+) -> UninstallStep {
+    if !path.exists() {
+        return UninstallStep::new(step, path, StepOutcome::Skipped, Some("not found".into()));
+    }
+
+    let result = without_anot(&path.to_path_buf()).and_then(|edit| {
+        let Some((original, updated)) = edit else {
+            return Ok(Some("nothing of anot's in it"));
+        };
+        if !options.yes {
+            print!(
+                "{}",
+                unified_diff(&original, &updated, &path.to_string_lossy())
+            );
+            if !confirm(&format!("Remove anot from {}?", path.display()), true)? {
+                return Ok(Some("declined"));
+            }
+        }
+        write_atomic(path, updated.as_bytes())?;
+        info!(step, path = %path.display(), "removed anot from agent config");
+        Ok(None)
+    });
+
+    match result {
+        Ok(None) => UninstallStep::new(step, path, StepOutcome::Changed, None),
+        Ok(Some(reason)) => {
+            UninstallStep::new(step, path, StepOutcome::Skipped, Some(reason.into()))
+        }
+        Err(e) => UninstallStep::new(step, path, StepOutcome::Failed, Some(format!("{e:#}"))),
+    }
+}
+
+fn delete(step: &'static str, path: &Path, options: &UninstallOptions) -> UninstallStep {
+    if !path.exists() {
+        return UninstallStep::new(step, path, StepOutcome::Skipped, Some("not found".into()));
+    }
+    if path.is_dir() && !is_anot_dir(path) {
+        let detail = "not anot's own directory".into();
+        return UninstallStep::new(step, path, StepOutcome::Skipped, Some(detail));
+    }
+
+    let approved = if options.yes {
+        Ok(options.purge)
+    } else {
+        confirm(&format!("Delete {}?", path.display()), false)
+    };
+    let result = approved.and_then(|approved| {
+        if !approved {
+            let reason = if options.yes {
+                "kept (pass --purge to delete it)"
+            } else {
+                "declined"
+            };
+            return Ok(Some(reason));
+        }
+        // Not logged: the log file may be among what was just deleted.
+        remove(path)?;
+        Ok(None)
+    });
+
+    match result {
+        Ok(None) => UninstallStep::new(step, path, StepOutcome::Changed, None),
+        Ok(Some(reason)) => {
+            UninstallStep::new(step, path, StepOutcome::Skipped, Some(reason.into()))
+        }
+        Err(e) => UninstallStep::new(step, path, StepOutcome::Failed, Some(format!("{e:#}"))),
+    }
+}
+
+fn remove(path: &Path) -> Result<(), Error> {
+    let removed = if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match removed {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+fn confirm(question: &str, default: bool) -> Result<bool, Error> {
+    Confirm::new(question)
+        .with_default(default)
+        .prompt()
+        .map_err(|err| Error::msg(format!("Failed to get confirmation: {err}")))
+}
+
+/// One line of `anot uninstall`'s report, e.g. `removed   claude hooks  ~/.claude/settings.json`.
+pub fn format_step(step: &UninstallStep) -> String {
+    let outcome = match step.outcome {
+        StepOutcome::Changed if step.step.starts_with("anot") => "deleted",
+        StepOutcome::Changed => "removed",
+        StepOutcome::Skipped => "skipped",
+        StepOutcome::Failed => "failed",
+    };
+    let mut line = format!("{outcome:<8}  {:<20} {}", step.step, step.path.display());
+    if let Some(detail) = &step.detail {
+        line.push_str(&format!(": {detail}"));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::temp_dir;
+
+    const PURGE: UninstallOptions = UninstallOptions {
+        yes: true,
+        purge: true,
+        config_path: None,
+    };
+
+    fn purge(paths: Vec<(&'static str, PathBuf)>) -> Vec<(StepOutcome, Option<String>)> {
+        paths
+            .into_iter()
+            .map(|(step, path)| delete(step, &path, &PURGE))
+            .map(|step| (step.outcome, step.detail))
+            .collect()
+    }
+
+    #[test]
+    fn only_anot_files_are_deleted_outside_its_own_directory() {
+        let dir = temp_dir("uninstall", "cwd");
+        let config_path = dir.join("a-notifications.json");
+        fs::write(&config_path, "{}").unwrap();
+        fs::write(local_path(&config_path), "{}").unwrap();
+        fs::write(dir.join("main.rs"), "fn main() {}").unwrap();
+
+        let mut paths = config_paths(&config_path, true);
+        paths.push(("anot state and logs", dir.clone()));
+        assert_eq!(
+            purge(paths),
+            [
+                (StepOutcome::Changed, None),
+                (StepOutcome::Changed, None),
+                (
+                    StepOutcome::Skipped,
+                    Some("not anot's own directory".to_string())
+                ),
+            ]
+        );
+        assert!(!config_path.exists() && !local_path(&config_path).exists());
+        assert!(dir.join("main.rs").exists());
+    }
+
+    #[test]
+    fn anot_directories_are_deleted_whole() {
+        let dir = temp_dir("uninstall", "own").join(ANOT_DIR_NAME);
+        fs::create_dir_all(dir.join("logs")).unwrap();
+        let config_path = dir.join("a-notifications.json");
+        fs::write(&config_path, "{}").unwrap();
+
+        let paths = config_paths(&config_path, true);
+        assert_eq!(paths, [("anot config", dir.clone())]);
+        assert_eq!(purge(paths), [(StepOutcome::Changed, None)]);
+        assert!(!dir.exists());
+    }
+}
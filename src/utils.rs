@@ -1,4 +1,12 @@
-use std::io::{self, Read, Write};
+use std::{
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Error;
 
 pub fn catch_stdin() -> String {
     io::stdout().flush().expect("Failed to flush stdout");
@@ -8,3 +16,267 @@ pub fn catch_stdin() -> String {
 
     input
 }
+
+/// Reads `reader` to the end on a background thread, giving up when no data arrives for
+/// `idle_timeout`. The deadline restarts with every chunk, so a large payload that's still
+/// streaming isn't cut off. Returns `None` on timeout.
+pub fn read_with_idle_timeout<R: Read + Send + 'static>(
+    mut reader: R,
+    idle_timeout: Duration,
+) -> Result<Option<String>, Error> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    if sender.send(Ok(buffer[..read].to_vec())).is_err() {
+                        break;
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    let _ = sender.send(Err(e));
+                    break;
+                }
+            }
+        }
+    });
+
+    let mut input = Vec::new();
+    loop {
+        match receiver.recv_timeout(idle_timeout) {
+            Ok(chunk) => input.extend(chunk?),
+            Err(RecvTimeoutError::Disconnected) => break,
+            Err(RecvTimeoutError::Timeout) => return Ok(None),
+        }
+    }
+    Ok(Some(String::from_utf8(input)?))
+}
+
+/// Returns the project name (the last path component) of an agent's working directory.
+pub fn project_name(cwd: &str) -> Option<String> {
+    Path::new(cwd)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+/// Expands a leading `~` to the home directory: `~` alone, `~/…`, and `~\…`. Other paths,
+/// including `~user/…`, are returned as they are.
+pub fn expand_tilde(path: &Path) -> PathBuf {
+    expand_tilde_in(path, dirs::home_dir().as_deref())
+}
+
+/// [`expand_tilde`] against `home` rather than the current user's home directory. Without a
+/// home directory the path is returned as it is.
+pub fn expand_tilde_in(path: &Path, home: Option<&Path>) -> PathBuf {
+    let (Some(home), Some(s)) = (home, path.to_str()) else {
+        return path.to_path_buf();
+    };
+    match s.strip_prefix('~') {
+        Some("") => home.to_path_buf(),
+        Some(rest) if rest.starts_with(['/', '\\']) => {
+            home.join(rest.trim_start_matches(['/', '\\']))
+        }
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Truncates `s` to at most `max_chars` characters, appending `…` when it was cut.
+pub fn truncate_chars(s: &str, max_chars: usize) -> String {
+    let mut out = String::new();
+    for (i, ch) in s.chars().enumerate() {
+        if i >= max_chars {
+            out.push('…');
+            break;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+/// Seconds since the Unix epoch.
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// The local wall-clock time of `timestamp` as `HH:MM`. UTC where the local time zone isn't
+/// available.
+pub fn local_clock(timestamp: u64) -> String {
+    let minute = local_minute_of_day(timestamp);
+    format!("{:02}:{:02}", minute / 60, minute % 60)
+}
+
+/// Minutes since local midnight at `timestamp`. UTC where the local time zone isn't available.
+pub fn local_minute_of_day(timestamp: u64) -> u16 {
+    #[cfg(unix)]
+    {
+        let time = timestamp as libc::time_t;
+        // SAFETY: `localtime_r` only writes to the `tm` it's given, which is zeroed beforehand.
+        let mut tm: libc::tm = unsafe { std::mem::zeroed() };
+        if !unsafe { libc::localtime_r(&time, &mut tm) }.is_null() {
+            return (tm.tm_hour * 60 + tm.tm_min) as u16;
+        }
+    }
+
+    (timestamp % 86400 / 60) as u16
+}
+
+/// Parses durations like `90s`, `15m`, `2h`, or `1d` into seconds. A bare number is seconds.
+pub fn parse_duration(input: &str) -> Result<u64, Error> {
+    let input = input.trim();
+    let split = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(input.len());
+    let (digits, unit) = input.split_at(split);
+
+    let value: u64 = digits
+        .parse()
+        .map_err(|_| Error::msg(format!("Invalid duration: {input:?}")))?;
+    let multiplier = match unit {
+        "" | "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => {
+            return Err(Error::msg(format!(
+                "Invalid duration unit in {input:?} (expected s, m, h, or d)"
+            )));
+        }
+    };
+
+    value
+        .checked_mul(multiplier)
+        .ok_or_else(|| Error::msg(format!("Duration too long: {input:?}")))
+}
+
+/// Formats a number of seconds compactly, e.g. `45s`, `4m 12s`, `2h 5m`, `3d 1h`.
+pub fn format_duration(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// Formats a number of bytes compactly, e.g. `512 B`, `1.2 KB`, `3.4 MB`.
+pub fn format_size(bytes: usize) -> String {
+    const KB: f64 = 1024.0;
+    let size = bytes as f64;
+    if size < KB {
+        format!("{bytes} B")
+    } else if size < KB * KB {
+        format!("{:.1} KB", size / KB)
+    } else {
+        format!("{:.1} MB", size / (KB * KB))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Yields `chunks` one at a time, `delay` apart.
+    struct SlowReader {
+        chunks: Vec<&'static str>,
+        delay: Duration,
+    }
+
+    impl Read for SlowReader {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            thread::sleep(self.delay);
+            if self.chunks.is_empty() {
+                return Ok(0);
+            }
+            let chunk = self.chunks.remove(0).as_bytes();
+            buf[..chunk.len()].copy_from_slice(chunk);
+            Ok(chunk.len())
+        }
+    }
+
+    #[test]
+    fn idle_timeout_waits_for_streaming_input() {
+        let reader = SlowReader {
+            chunks: vec!["{\"a\":", " 1", "}"],
+            delay: Duration::from_millis(60),
+        };
+        assert_eq!(
+            read_with_idle_timeout(reader, Duration::from_millis(150)).unwrap(),
+            Some("{\"a\": 1}".to_string()),
+            "longer than the timeout in total, but never idle that long"
+        );
+    }
+
+    #[test]
+    fn idle_timeout_gives_up_without_input() {
+        let reader = SlowReader {
+            chunks: vec!["late"],
+            delay: Duration::from_secs(2),
+        };
+        assert_eq!(
+            read_with_idle_timeout(reader, Duration::from_millis(50)).unwrap(),
+            None
+        );
+        assert_eq!(
+            read_with_idle_timeout(io::empty(), Duration::from_millis(50)).unwrap(),
+            Some(String::new())
+        );
+    }
+
+    #[test]
+    fn expands_tilde_against_the_given_home() {
+        let home = Path::new("/home/me");
+        let expand = |path: &str| expand_tilde_in(Path::new(path), Some(home));
+
+        assert_eq!(expand("~"), home);
+        assert_eq!(expand("~/icons/a.png"), home.join("icons/a.png"));
+        assert_eq!(
+            expand(r"~\.codex\config.toml"),
+            home.join(r".codex\config.toml")
+        );
+        assert_eq!(
+            expand("/opt/icons/a.png"),
+            PathBuf::from("/opt/icons/a.png")
+        );
+        assert_eq!(expand("~user/a.png"), PathBuf::from("~user/a.png"));
+        assert_eq!(
+            expand_tilde_in(Path::new("~/a.png"), None),
+            PathBuf::from("~/a.png")
+        );
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90s").unwrap(), 90);
+        assert_eq!(parse_duration("15m").unwrap(), 900);
+        assert_eq!(parse_duration("2h").unwrap(), 7200);
+        assert_eq!(parse_duration("1d").unwrap(), 86400);
+        assert_eq!(parse_duration("42").unwrap(), 42);
+        assert!(parse_duration("2w").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+        assert_eq!(parse_duration("18446744073709551615s").unwrap(), u64::MAX);
+    }
+
+    #[test]
+    fn formats_durations() {
+        assert_eq!(format_duration(45), "45s");
+        assert_eq!(format_duration(252), "4m 12s");
+        assert_eq!(format_duration(7500), "2h 5m");
+        assert_eq!(format_duration(90000), "1d 1h");
+    }
+
+    #[test]
+    fn formats_sizes() {
+        assert_eq!(format_size(512), "512 B");
+        assert_eq!(format_size(1229), "1.2 KB");
+        assert_eq!(format_size(3_565_158), "3.4 MB");
+    }
+}
@@ -0,0 +1,644 @@
+use std::io::Read;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+use serde_json::Value;
+
+mod common;
+
+use common::{CONFIG, write_config};
+
+fn anot(config_path: &Path) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_anot"));
+    command.arg("--config").arg(config_path);
+    command
+}
+
+#[test]
+fn reads_the_payload_from_an_input_file() {
+    let config_path = write_config("claude-input-file", CONFIG);
+    let payload = config_path.with_file_name("payload.json");
+    std::fs::write(
+        &payload,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#,
+    )
+    .unwrap();
+
+    let output = anot(&config_path)
+        .args(["claude", "--input"])
+        .arg(&payload)
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        std::fs::read_to_string(config_path.with_file_name("status")).unwrap(),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+}
+
+const STOP_PAYLOAD: &str =
+    r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+/// Runs `anot claude` with `args`, writing `stdin` to it, and returns the status line sent.
+fn status_after(config_path: &Path, args: &[&str], stdin: &str) -> String {
+    let mut child = anot(config_path)
+        .arg("claude")
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("failed to wait on anot");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    std::fs::read_to_string(config_path.with_file_name("status")).unwrap()
+}
+
+#[test]
+fn reads_the_payload_from_an_argument() {
+    let config_path = write_config("claude-argument", CONFIG);
+
+    assert_eq!(
+        status_after(&config_path, &[STOP_PAYLOAD], ""),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+}
+
+#[test]
+fn reads_the_payload_from_an_at_file_argument() {
+    let config_path = write_config("claude-at-file", CONFIG);
+    let payload = config_path.with_file_name("payload.json");
+    std::fs::write(&payload, STOP_PAYLOAD).unwrap();
+
+    assert_eq!(
+        status_after(&config_path, &[&format!("@{}", payload.display())], ""),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+
+    let output = anot(&config_path)
+        .args(["claude", "@/nonexistent/payload.json"])
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains(
+            "Failed to read the hook input from @file argument /nonexistent/payload.json"
+        ),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn an_argument_payload_wins_over_stdin() {
+    let config_path = write_config("claude-argument-over-stdin", CONFIG);
+    let subagent_stop =
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"SubagentStop"}"#;
+
+    assert_eq!(
+        status_after(&config_path, &[STOP_PAYLOAD], subagent_stop),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+}
+
+#[test]
+fn gives_up_when_stdin_stays_empty() {
+    let config_path = write_config("claude-stdin-timeout", CONFIG);
+    let started = Instant::now();
+    let mut child = anot(&config_path)
+        .args(["claude", "--stdin-timeout", "1"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    // Holding stdin open without writing is what a hook invoked without a payload sees.
+    let _stdin = child.stdin.take();
+
+    let status = child.wait().expect("failed to wait on anot");
+    let mut stdout = String::new();
+    child
+        .stdout
+        .take()
+        .unwrap()
+        .read_to_string(&mut stdout)
+        .unwrap();
+
+    assert!(status.success());
+    assert!(started.elapsed() < Duration::from_secs(10));
+    let output: Value = serde_json::from_str(&stdout).expect("stdout is not JSON");
+    assert_eq!(output["continue"], true);
+    assert!(
+        output["systemMessage"]
+            .as_str()
+            .unwrap()
+            .contains("no hook input on stdin within 1s")
+    );
+    assert!(!config_path.with_file_name("status").exists());
+}
+
+/// Runs `anot claude --dry-run` on `payload` and returns the summary it printed to stderr.
+fn dry_run(config_path: &Path, payload: &str) -> Value {
+    let mut child = anot(config_path)
+        .args(["claude", "--dry-run"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    std::io::Write::write_all(&mut child.stdin.take().unwrap(), payload.as_bytes()).unwrap();
+    let output = child.wait_with_output().expect("failed to wait on anot");
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let hook_output: Value = serde_json::from_slice(&output.stdout).expect("stdout is not JSON");
+    assert_eq!(hook_output["continue"], true);
+    serde_json::from_slice(&output.stderr).expect("stderr is not a JSON summary")
+}
+
+#[test]
+fn dry_run_describes_the_notification_without_sending_it() {
+    let config_path = write_config("claude-dry-run", CONFIG);
+
+    let summary = dry_run(
+        &config_path,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#,
+    );
+    assert_eq!(
+        summary,
+        serde_json::json!({
+            "agent": "claude",
+            "event": "Stop",
+            "title": "Claude Code · Finished",
+            "body": "The agent has stopped responding.",
+            "backends": ["statusfile"],
+        })
+    );
+
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""claude": {"pretend": false}"#,
+        r#""claude": {"pretend": false, "post_tool_use": "failures"}"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+    // A tool call that succeeded is filtered out with `post_tool_use: failures`.
+    let summary = dry_run(
+        &config_path,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PostToolUse",
+            "tool_name":"Bash","tool_response":{"stdout":"ok"}}"#,
+    );
+    assert_eq!(
+        summary,
+        serde_json::json!({
+            "agent": "claude",
+            "event": "PostToolUse",
+            "suppressed": "filtered out by the config",
+        })
+    );
+
+    let data_dir = config_path.parent().unwrap();
+    assert!(!data_dir.join("status").exists());
+    assert!(!data_dir.join("sessions.json").exists());
+}
+
+#[test]
+fn dry_run_fails_on_an_invalid_payload() {
+    let config_path = write_config("claude-dry-run-invalid", CONFIG);
+    let output = anot(&config_path)
+        .args(["claude", "--input"])
+        .arg("/dev/null")
+        .env("ANOT_DRY_RUN", "1")
+        .output()
+        .expect("failed to run anot");
+
+    assert!(!output.status.success());
+    let hook_output: Value = serde_json::from_slice(&output.stdout).expect("stdout is not JSON");
+    assert!(
+        hook_output["systemMessage"]
+            .as_str()
+            .unwrap()
+            .contains("Failed to parse input JSON")
+    );
+}
+
+#[test]
+fn emit_output_controls_what_reaches_stdout() {
+    let config_path = write_config("claude-emit-output", CONFIG);
+    let payload = config_path.with_file_name("payload.json");
+    let stdout = |mode: &str, contents: &str| {
+        let config = std::fs::read_to_string(&config_path).unwrap().replace(
+            r#""claude": {"pretend": false}"#,
+            &format!(r#""claude": {{"pretend": false, "emit_output": "{mode}"}}"#),
+        );
+        let mode_config = config_path.with_file_name(format!("{mode}.json"));
+        std::fs::write(&mode_config, config).unwrap();
+        std::fs::write(&payload, contents).unwrap();
+
+        let output = anot(&mode_config)
+            .args(["claude", "--input"])
+            .arg(&payload)
+            .output()
+            .expect("failed to run anot");
+        assert!(
+            output.status.success(),
+            "stderr: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout).unwrap()
+    };
+    let stop = r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+    assert_eq!(
+        stdout("full", stop),
+        r#"{"continue":true,"suppressOutput":true}"#
+    );
+    assert!(stdout("full", "not json").contains(r#""systemMessage":"Failed to parse input JSON"#));
+    for payload in [stop, "not json"] {
+        assert_eq!(
+            stdout("minimal", payload),
+            r#"{"continue":true,"suppressOutput":true}"#
+        );
+        assert_eq!(stdout("none", payload), "");
+    }
+}
+
+/// Runs `anot claude` on `payload` and returns whether it wrote a notification to the status
+/// file.
+fn notifies(config_path: &Path, payload: &str) -> bool {
+    let status = config_path.with_file_name("status");
+    let _ = std::fs::remove_file(&status);
+    let payload_path = config_path.with_file_name("payload.json");
+    std::fs::write(&payload_path, payload).unwrap();
+
+    let output = anot(config_path)
+        .args(["claude", "--input"])
+        .arg(&payload_path)
+        .output()
+        .expect("failed to run anot");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    status.exists()
+}
+
+#[test]
+fn sampling_shows_every_nth_event_across_invocations() {
+    let config_path = write_config("claude-sample", CONFIG);
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""claude": {"pretend": false}"#,
+        r#""claude": {"pretend": false, "sample": {"PostToolUse": 3}}, "dedupe_window_secs": 0"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+
+    let fired: Vec<usize> = (1..=7)
+        .filter(|n| {
+            notifies(
+                &config_path,
+                &format!(
+                    r#"{{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PostToolUse",
+                        "tool_name":"Bash","tool_input":{{"command":"echo {n}"}}}}"#
+                ),
+            )
+        })
+        .collect();
+    assert_eq!(fired, [3, 6]);
+
+    assert!(
+        notifies(
+            &config_path,
+            r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#,
+        ),
+        "other events aren't sampled"
+    );
+    assert!(
+        !notifies(
+            &config_path,
+            r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PostToolUse",
+                "tool_name":"Bash","tool_input":{"command":"echo 8"}}"#,
+        ),
+        "the count carries on after them"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn filter_command_decides_whether_to_notify() {
+    let config_path = write_config("claude-filter-command", CONFIG);
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""backends": ["statusfile"]"#,
+        r#""backends": ["statusfile"],
+            "filter_command": ["sh", "-c", "grep -q Notification && exit 10; exit 0"]"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+    let run = |payload: &str| {
+        let output = anot(&config_path)
+            .args(["claude", "--input"])
+            .arg({
+                let path = config_path.with_file_name("payload.json");
+                std::fs::write(&path, payload).unwrap();
+                path
+            })
+            .stdin(Stdio::null())
+            .output()
+            .expect("failed to run anot");
+        assert!(output.status.success());
+    };
+    let status_path = config_path.with_file_name("status");
+
+    run(
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Notification",
+        "message":"Claude is waiting for your input"}"#,
+    );
+    assert!(!status_path.exists());
+
+    run(r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#);
+    assert_eq!(
+        std::fs::read_to_string(&status_path).unwrap(),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn detached_delivery_answers_before_notifying() {
+    let config_path = write_config("claude-detached", CONFIG);
+    // The filter holds the background delivery back long enough to tell the two apart.
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""backends": ["statusfile"]"#,
+        r#""backends": ["statusfile"],
+            "delivery": "detached",
+            "filter_command": ["sh", "-c", "sleep 2"],
+            "filter_timeout_secs": 10"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+    let status_path = config_path.with_file_name("status");
+
+    let output = anot(&config_path)
+        .args(["claude", STOP_PAYLOAD])
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"{"continue":true,"suppressOutput":true}"#
+    );
+    assert!(!status_path.exists());
+
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !status_path.exists() {
+        assert!(
+            Instant::now() < deadline,
+            "the background delivery never ran"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn on_sent_runs_after_the_hook_output_is_written() {
+    let config_path = write_config("claude-on-sent", CONFIG);
+    let dir = config_path.parent().unwrap();
+    let (go, report) = (dir.join("go"), dir.join("report.json"));
+    // Waits for the test to have read the hook output before writing anything.
+    let script = r#"while [ ! -e "$1" ]; do sleep 0.05; done; cat > "$2""#;
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""backends": ["statusfile"]"#,
+        &format!(
+            r#""backends": ["statusfile"], "on_sent_timeout_secs": 20,
+            "on_sent": {}"#,
+            serde_json::json!(["sh", "-c", script, "on_sent", go, report])
+        ),
+    );
+    std::fs::write(&config_path, config).unwrap();
+    let payload = config_path.with_file_name("payload.json");
+    std::fs::write(
+        &payload,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#,
+    )
+    .unwrap();
+
+    let mut child = anot(&config_path)
+        .args(["claude", "--input"])
+        .arg(&payload)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn anot");
+    let mut stdout = child.stdout.take().unwrap();
+    let mut received = Vec::new();
+    let hook_output: Value = loop {
+        let mut buffer = [0; 256];
+        let read = stdout.read(&mut buffer).unwrap();
+        assert!(
+            read > 0,
+            "stdout closed before the hook output was complete"
+        );
+        received.extend_from_slice(&buffer[..read]);
+        if let Ok(output) = serde_json::from_slice(&received) {
+            break output;
+        }
+    };
+    assert_eq!(hook_output["continue"], true);
+    assert!(!report.exists());
+
+    std::fs::write(&go, "").unwrap();
+    assert!(child.wait().unwrap().success());
+    let report: Value = serde_json::from_str(&std::fs::read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(
+        report,
+        serde_json::json!({
+            "agent": "claude",
+            "event": "Stop",
+            "title": "Claude Code · Finished",
+            "body": "The agent has stopped responding.",
+            "delivered": true,
+            "sent_at": report["sent_at"],
+        })
+    );
+}
+
+/// Runs `anot claude` on `payload` and returns exactly what it wrote to stdout.
+fn hook_stdout(config_path: &Path, payload: &str) -> String {
+    let payload_path = config_path.with_file_name("payload.json");
+    std::fs::write(&payload_path, payload).unwrap();
+
+    let output = anot(config_path)
+        .args(["claude", "--input"])
+        .arg(&payload_path)
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn guard_answers_dangerous_commands_with_a_permission_decision() {
+    let config_path = write_config("claude-guard", CONFIG);
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""claude": {"pretend": false}"#,
+        r#""claude": {"pretend": false, "guard": [
+            {"command": "\\brm\\s+-[a-z]*r[a-z]*f"},
+            {"command": "\\bgit\\s+push\\b.*(--force|-f\\b)", "decision": "deny",
+             "reason": "Force pushes are blocked"}
+        ]}, "dedupe_window_secs": 0"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+    let status_path = config_path.with_file_name("status");
+    let pre_tool_use = |command: &str| {
+        format!(
+            r#"{{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PreToolUse",
+                "tool_name":"Bash","tool_input":{{"command":"{command}"}}}}"#
+        )
+    };
+
+    assert_eq!(
+        hook_stdout(&config_path, &pre_tool_use("rm -rf build")),
+        r#"{"continue":true,"suppressOutput":true,"hookSpecificOutput":{"hookEventName":"PreToolUse","permissionDecision":"ask","permissionDecisionReason":"The command matches `\\brm\\s+-[a-z]*r[a-z]*f` in anot's claude.guard"}}"#
+    );
+    assert_eq!(
+        std::fs::read_to_string(&status_path).unwrap(),
+        "Claude Code · Command intercepted: Bash needs your approval: rm -rf build\n"
+    );
+
+    assert_eq!(
+        hook_stdout(&config_path, &pre_tool_use("git push --force origin main")),
+        r#"{"continue":true,"suppressOutput":true,"hookSpecificOutput":{"hookEventName":"PreToolUse","permissionDecision":"deny","permissionDecisionReason":"Force pushes are blocked"}}"#
+    );
+    assert_eq!(
+        std::fs::read_to_string(&status_path).unwrap(),
+        "Claude Code · Command intercepted: Bash was blocked: git push --force origin main\n"
+    );
+
+    assert_eq!(
+        hook_stdout(&config_path, &pre_tool_use("cargo test")),
+        r#"{"continue":true,"suppressOutput":true}"#
+    );
+    assert_eq!(
+        std::fs::read_to_string(&status_path).unwrap(),
+        "Claude Code · Tool: The agent is trying to use Bash\n"
+    );
+}
+
+#[test]
+fn report_delivery_tells_claude_the_user_was_notified() {
+    let config_path = write_config("claude-report-delivery", CONFIG);
+    let payload = r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PostToolUse",
+        "tool_name":"Bash","tool_input":{"command":"cargo test"},"tool_response":{"stdout":"ok"}}"#;
+    assert_eq!(
+        hook_stdout(&config_path, payload),
+        r#"{"continue":true,"suppressOutput":true}"#
+    );
+
+    let config = std::fs::read_to_string(&config_path).unwrap().replace(
+        r#""claude": {"pretend": false}"#,
+        r#""claude": {"pretend": false, "report_delivery": "always"}, "dedupe_window_secs": 0"#,
+    );
+    std::fs::write(&config_path, config).unwrap();
+    let output: Value = serde_json::from_str(&hook_stdout(&config_path, payload)).unwrap();
+    assert_eq!(output["hookSpecificOutput"]["hookEventName"], "PostToolUse");
+    let context = output["hookSpecificOutput"]["additionalContext"]
+        .as_str()
+        .unwrap();
+    assert!(
+        context.starts_with("The user was notified via statusfile at "),
+        "{context}"
+    );
+}
+
+#[test]
+fn a_crash_still_answers_claude_code() {
+    let config_path = write_config("claude-crash", CONFIG);
+    let output = anot(&config_path)
+        .args(["claude", STOP_PAYLOAD])
+        .env("ANOT_TEST_PANIC", "1")
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+
+    assert_eq!(output.status.code(), Some(101));
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        r#"{"continue":true,"suppressOutput":true,"systemMessage":"anot crashed: ANOT_TEST_PANIC is set"}"#
+    );
+    assert!(!config_path.with_file_name("status").exists());
+}
+
+#[test]
+fn payloads_kept_on_disk_are_redacted() {
+    let config_path = write_config(
+        "claude-redacted-payloads",
+        &CONFIG.replace(
+            r#""backends": ["statusfile"]"#,
+            r#""backends": ["statusfile"],
+            "privacy": {"redact_patterns": ["sk-[a-z0-9]{8,}"]},
+            "debug": {"capture_payloads": true},
+            "mute_mode": "defer""#,
+        ),
+    );
+    let muted = anot(&config_path)
+        .args(["mute", "1h"])
+        .output()
+        .expect("failed to run anot");
+    assert!(muted.status.success());
+
+    // Muted, so the payload is deferred to the spool rather than sent.
+    let output = anot(&config_path)
+        .args([
+            "claude",
+            r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"UserPromptSubmit",
+                "prompt":"deploy with sk-0123456789abcdef"}"#,
+        ])
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+    assert!(output.status.success());
+    assert!(!config_path.with_file_name("status").exists());
+
+    let dir = config_path.parent().unwrap();
+    let captures: Vec<_> = std::fs::read_dir(dir.join("captures"))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    assert_eq!(captures.len(), 1);
+    for path in captures.iter().chain([
+        &dir.join("last-payload-claude.json"),
+        &dir.join("spool.jsonl"),
+    ]) {
+        let contents = std::fs::read_to_string(path).unwrap();
+        assert!(
+            contents.contains("deploy with •••"),
+            "{}: {contents}",
+            path.display()
+        );
+        assert!(
+            !contents.contains("sk-0123456789abcdef"),
+            "{}",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+mod common;
+
+use common::write_config;
+
+const PAYLOAD: &str = r#"{"type":"agent-turn-complete","last-assistant-message":"Tests pass"}"#;
+
+/// A config that only writes the status file, so tests don't need a notification daemon.
+const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false},
+    "codex": {"pretend": false, "sound": false},
+    "backends": ["statusfile"],
+    "dedupe_window_secs": 0,
+    "terminal_escape": {"auto_over_ssh": false}
+}"#;
+
+fn run_anot(args: &[&str], stdin: &str, config_path: &Path) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    child
+        .stdin
+        .take()
+        .expect("failed to open stdin")
+        .write_all(stdin.as_bytes())
+        .expect("failed to write stdin");
+
+    child.wait_with_output().expect("failed to wait on anot")
+}
+
+fn assert_delivered(output: &Output, config_path: &Path) {
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let status =
+        std::fs::read_to_string(config_path.with_file_name("status")).expect("no status file");
+    assert!(status.contains("Tests pass"), "status: {status}");
+}
+
+#[test]
+fn reads_the_payload_inline_and_from_stdin() {
+    let config_path = write_config("codex-inline", CONFIG);
+    assert_delivered(
+        &run_anot(&["codex", PAYLOAD], "", &config_path),
+        &config_path,
+    );
+
+    let config_path = write_config("codex-stdin", CONFIG);
+    assert_delivered(&run_anot(&["codex"], PAYLOAD, &config_path), &config_path);
+}
+
+#[test]
+fn accepts_an_enveloped_payload() {
+    let config_path = write_config("codex-envelope", CONFIG);
+    let envelope = format!(r#"{{"notification": {PAYLOAD}}}"#);
+    assert_delivered(
+        &run_anot(&["codex", &envelope], "", &config_path),
+        &config_path,
+    );
+}
+
+#[test]
+fn reads_the_payload_from_a_file() {
+    let config_path = write_config("codex-file", CONFIG);
+    let payload = config_path.with_file_name("payload.json");
+    std::fs::write(&payload, format!("\u{feff}{PAYLOAD}")).unwrap();
+    let path = payload.to_str().unwrap();
+
+    for args in [
+        vec!["codex", "--file", path],
+        vec!["codex", &format!("@{path}")],
+        vec!["codex", path],
+    ] {
+        std::fs::remove_file(config_path.with_file_name("status")).ok();
+        assert_delivered(&run_anot(&args, "", &config_path), &config_path);
+    }
+}
+
+#[test]
+fn titles_name_the_directory_codex_runs_in() {
+    let config_path = write_config("codex-cwd", CONFIG);
+    let project = config_path.with_file_name("my-api");
+    std::fs::create_dir_all(&project).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["codex", PAYLOAD])
+        .current_dir(&project)
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot");
+
+    assert_delivered(&output, &config_path);
+    let status = std::fs::read_to_string(config_path.with_file_name("status")).unwrap();
+    let title = status.split(": ").next().unwrap();
+    assert_eq!(title, "Codex · Turn complete · my-api");
+}
+
+#[test]
+fn missing_payload_file_exits_nonzero() {
+    let config_path = write_config("codex-missing", CONFIG);
+    let output = run_anot(&["codex", "@/nonexistent/payload.json"], "", &config_path);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("the @file argument /nonexistent/payload.json"),
+        "stderr: {stderr}"
+    );
+}
+
+#[test]
+fn dry_run_prints_the_notification_instead_of_sending_it() {
+    let config_path = write_config("codex-dry-run", CONFIG);
+    let output = run_anot(&["codex", "--dry-run", PAYLOAD], "", &config_path);
+
+    assert!(output.status.success());
+    let summary: serde_json::Value =
+        serde_json::from_slice(&output.stderr).expect("stderr is not a JSON summary");
+    assert_eq!(summary["event"], "AgentTurnComplete");
+    assert_eq!(summary["backends"], serde_json::json!(["statusfile"]));
+    assert!(
+        summary["body"].as_str().unwrap().contains("Tests pass"),
+        "summary: {summary}"
+    );
+    assert!(!config_path.with_file_name("status").exists());
+
+    let output = run_anot(&["codex", "--dry-run", "{not json"], "", &config_path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Failed to parse Codex notification"));
+}
+
+#[test]
+fn a_crash_exits_with_the_crash_code() {
+    let config_path = write_config("codex-crash", CONFIG);
+    let output = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["codex", PAYLOAD])
+        .env("ANOT_TEST_PANIC", "1")
+        .output()
+        .expect("failed to run anot");
+
+    assert_eq!(output.status.code(), Some(101));
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("Error: anot crashed: ANOT_TEST_PANIC is set"),
+        "stderr: {stderr}"
+    );
+    assert!(!config_path.with_file_name("status").exists());
+}
+
+#[test]
+fn events_follow_prints_what_the_events_log_records() {
+    let config_path = write_config("codex-events", CONFIG);
+    let output = run_anot(&["config", "set", "events_log", "true"], "", &config_path);
+    assert!(output.status.success());
+    let payload =
+        r#"{"type":"agent-turn-complete","turn-id":"t-42","last-assistant-message":"Tests pass"}"#;
+    assert_delivered(
+        &run_anot(&["codex", payload], "", &config_path),
+        &config_path,
+    );
+
+    let mut follow = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["--output", "json", "events", "follow", "-n", "1"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to spawn anot");
+    let mut line = String::new();
+    std::io::BufRead::read_line(
+        &mut std::io::BufReader::new(follow.stdout.take().unwrap()),
+        &mut line,
+    )
+    .unwrap();
+    follow.kill().unwrap();
+    follow.wait().unwrap();
+
+    let record: serde_json::Value = serde_json::from_str(&line).expect("not a JSON line");
+    assert_eq!(record["schema_version"], 1);
+    assert_eq!(record["agent"], "codex");
+    assert_eq!(record["event"], "AgentTurnComplete");
+    assert_eq!(record["decision"], "sent");
+    assert_eq!(record["turn_id"], "t-42");
+    assert_eq!(record["delivered_by"], serde_json::json!(["statusfile"]));
+}
@@ -0,0 +1,36 @@
+//! Helpers shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::path::PathBuf;
+
+/// A config that only writes the status file, so tests don't need a notification daemon.
+pub const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false},
+    "codex": {"pretend": false, "sound": false},
+    "backends": ["statusfile"],
+    "terminal_escape": {"auto_over_ssh": false}
+}"#;
+
+/// A config path of its own for `test_name`. Nothing is created.
+pub fn temp_config_path(test_name: &str) -> PathBuf {
+    let pid = std::process::id();
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_nanos();
+
+    std::env::temp_dir()
+        .join(format!("anot-tests-{pid}-{nanos}"))
+        .join(test_name)
+        .join("a-notifications.json")
+}
+
+/// A [`temp_config_path`] holding `contents`.
+pub fn write_config(test_name: &str, contents: &str) -> PathBuf {
+    let path = temp_config_path(test_name);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(&path, contents).unwrap();
+    path
+}
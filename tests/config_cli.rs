@@ -0,0 +1,151 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+mod common;
+
+use common::{CONFIG, write_config};
+
+fn run_anot(args: &[&str], config_path: &Path) -> Output {
+    let output = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .output()
+        .expect("failed to run anot");
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    output
+}
+
+#[test]
+fn local_overrides_are_merged_and_shown_with_their_origin() {
+    let config_path = write_config("config-local", CONFIG);
+    run_anot(
+        &[
+            "config",
+            "set",
+            "--local",
+            "statusfile.path",
+            "/tmp/work-status",
+        ],
+        &config_path,
+    );
+    run_anot(
+        &["config", "set", "--local", "claude.sound", "false"],
+        &config_path,
+    );
+    assert!(
+        !std::fs::read_to_string(&config_path)
+            .unwrap()
+            .contains("work-status"),
+        "the synced file is untouched"
+    );
+
+    let output = run_anot(&["config", "show"], &config_path);
+    let shown: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(shown["statusfile"]["path"], "/tmp/work-status");
+    assert_eq!(shown["backends"], serde_json::json!(["statusfile"]));
+
+    let output = run_anot(&["config", "show", "--origins"], &config_path);
+    let lines = String::from_utf8_lossy(&output.stdout);
+    for expected in [
+        "statusfile.path = \"/tmp/work-status\"  (local)",
+        "claude.sound = false  (local)",
+        "claude.pretend = \"never\"  (config)",
+        "backends = [\"statusfile\"]  (config)",
+        "dedupe_window_secs = 10  (default)",
+    ] {
+        assert!(
+            lines.contains(expected),
+            "missing {expected:?} in:\n{lines}"
+        );
+    }
+}
+
+#[test]
+fn comments_and_trailing_commas_are_accepted() {
+    let config_path = write_config("config-jsonc", CONFIG);
+    std::fs::write(
+        &config_path,
+        r#"{
+            "version": 1,
+            // Quiet at work.
+            "claude": {"pretend": false, "sound": false,},
+            "codex": {"pretend": false, "sound": false},
+            /* Only the status file in tests. */
+            "backends": ["statusfile",],
+            "terminal_escape": {"auto_over_ssh": false},
+        }"#,
+    )
+    .unwrap();
+
+    let output = run_anot(&["config", "validate"], &config_path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("is valid."), "{stdout}");
+    assert!(
+        stdout.contains("[info] It has comments or trailing commas"),
+        "piped output is plain: {stdout}"
+    );
+
+    let output = run_anot(&["--output", "json", "config", "validate"], &config_path);
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["files"][0]["lenient"], true);
+
+    // Rewriting the file keeps the settings, as strict JSON.
+    run_anot(&["config", "set", "codex.sound", "true"], &config_path);
+    let contents: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+    assert_eq!(contents["claude"]["sound"], false);
+    assert_eq!(contents["backends"], serde_json::json!(["statusfile"]));
+    assert_eq!(contents["codex"]["sound"], true);
+}
+
+#[test]
+fn a_broken_config_is_reported_where_it_breaks() {
+    let config_path = write_config("config-broken", CONFIG);
+    std::fs::write(
+        &config_path,
+        "{\n  // Still a comment.\n  \"version\": 1,\n  \"backends\": [\"statusfile\" \"tmux\"]\n}",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(&config_path)
+        .args(["config", "validate"])
+        .output()
+        .expect("failed to run anot");
+    assert_eq!(output.status.code(), Some(3), "invalid config");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("line 4 column 29"), "{stderr}");
+    assert!(stderr.contains("\nhint: "), "{stderr}");
+    assert!(
+        config_path.exists(),
+        "a hand-edited file is never moved aside"
+    );
+}
+
+#[test]
+fn convert_rewrites_the_config_as_toml() {
+    let config_path = write_config("config-convert", CONFIG);
+    run_anot(&["config", "set", "claude.sound", "false"], &config_path);
+    let before = run_anot(&["config", "show"], &config_path).stdout;
+
+    let output = run_anot(&["config", "convert", "--to", "toml"], &config_path);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let toml_path = config_path.with_extension("toml");
+    assert!(
+        stdout.contains(&toml_path.display().to_string()),
+        "{stdout}"
+    );
+    assert!(!config_path.exists());
+
+    let after = run_anot(&["config", "show"], &toml_path).stdout;
+    assert_eq!(
+        serde_json::from_slice::<serde_json::Value>(&after).unwrap(),
+        serde_json::from_slice::<serde_json::Value>(&before).unwrap()
+    );
+}
@@ -0,0 +1,53 @@
+use std::process::{Command, Stdio};
+
+mod common;
+
+use common::temp_config_path;
+
+#[test]
+fn concurrent_first_runs_always_leave_a_valid_config() {
+    let exe = env!("CARGO_BIN_EXE_anot");
+
+    for round in 0..5 {
+        let config_path = temp_config_path(&format!("first-run-race-{round}"));
+
+        // An unknown Codex notification type is parsed and logged but never shown, so this
+        // exercises config creation without needing a notification daemon.
+        let children: Vec<_> = (0..8)
+            .map(|_| {
+                Command::new(exe)
+                    .arg("--config")
+                    .arg(&config_path)
+                    .args(["codex", r#"{"type":"something-else"}"#])
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::piped())
+                    .spawn()
+                    .expect("failed to spawn anot")
+            })
+            .collect();
+
+        for child in children {
+            let output = child.wait_with_output().expect("failed to wait on anot");
+            assert!(
+                output.status.success(),
+                "stderr: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+
+        let contents = std::fs::read_to_string(&config_path).expect("config was not created");
+        let value: serde_json::Value =
+            serde_json::from_str(&contents).expect("config is not valid JSON");
+        assert_eq!(value["version"], 1);
+
+        let leftovers: Vec<_> = std::fs::read_dir(config_path.parent().unwrap())
+            .unwrap()
+            .filter_map(Result::ok)
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+            .collect();
+        assert!(
+            leftovers.is_empty(),
+            "temp files left behind: {leftovers:?}"
+        );
+    }
+}
@@ -0,0 +1,108 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde_json::Value;
+
+mod common;
+
+use common::write_config;
+
+const STOP: &str =
+    r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+
+/// A config whose notifications go through `fallbacks`, so tests don't need a notification
+/// daemon. `statusfile` overrides the status file settings.
+fn temp_config_path(test_name: &str, fallbacks: &str, statusfile: &str) -> PathBuf {
+    write_config(
+        test_name,
+        &format!(
+            r#"{{
+                "version": 1,
+                "claude": {{"pretend": false}},
+                "codex": {{"pretend": false, "sound": false}},
+                "backends": ["statusfile"],
+                "fallbacks": {fallbacks},
+                "statusfile": {statusfile},
+                "terminal_escape": {{"auto_over_ssh": false}}
+            }}"#
+        ),
+    )
+}
+
+fn run_anot(args: &[&str], stdin: &str, config_path: &Path) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .env_remove("TMUX")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    child
+        .stdin
+        .take()
+        .expect("failed to open stdin")
+        .write_all(stdin.as_bytes())
+        .expect("failed to write stdin");
+
+    child.wait_with_output().expect("failed to wait on anot")
+}
+
+#[test]
+fn test_command_reports_each_stage() {
+    let config_path = temp_config_path(
+        "fallbacks-test",
+        r#"["tmux", "statusfile", "terminal_escape"]"#,
+        "{}",
+    );
+    let output = run_anot(&["test"], "", &config_path);
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "tmux             failed: not inside tmux\n\
+         statusfile       delivered\n\
+         terminal_escape  not tried\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(config_path.with_file_name("status")).unwrap(),
+        "anot · Test notification: If you can see this, anot can reach you.\n"
+    );
+}
+
+#[test]
+fn chain_failure_reaches_the_system_message() {
+    // A status file inside a regular file can't be written.
+    let blocker = std::env::temp_dir().join(format!("anot-tests-{}-blocker", std::process::id()));
+    std::fs::write(&blocker, "").unwrap();
+    let config_path = temp_config_path(
+        "fallbacks-failure",
+        r#"["tmux", "statusfile"]"#,
+        &serde_json::json!({"path": blocker.join("status")}).to_string(),
+    );
+
+    let output = run_anot(&["claude"], STOP, &config_path);
+    assert!(output.status.success());
+    let hook_output: Value = serde_json::from_slice(&output.stdout).expect("stdout is not JSON");
+    let message = hook_output["systemMessage"].as_str().unwrap();
+    assert!(
+        message.contains(
+            "every backend in the fallback chain failed: tmux: not inside tmux; statusfile:"
+        ),
+        "systemMessage: {message}"
+    );
+
+    let output = run_anot(&["test"], "", &config_path);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("no backend delivered the test notification")
+    );
+}
@@ -0,0 +1,220 @@
+#![cfg(feature = "http")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+mod common;
+
+use common::write_config;
+
+const STOP: &str =
+    r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+const NOTIFICATION: &str = r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Notification","message":"Needs you"}"#;
+
+/// A config that forwards Claude payloads to `address` and otherwise only writes the status
+/// file.
+fn temp_config_path(test_name: &str, address: SocketAddr) -> PathBuf {
+    write_config(
+        test_name,
+        &format!(
+            r#"{{
+                "version": 1,
+                "claude": {{
+                    "pretend": false,
+                    "forward": {{"url": "http://{address}/claude", "token": "s3cret", "timeout_secs": 1}}
+                }},
+                "codex": {{"pretend": false, "sound": false}},
+                "backends": ["statusfile"],
+                "terminal_escape": {{"auto_over_ssh": false}}
+            }}"#
+        ),
+    )
+}
+
+/// Runs `anot config set key value`.
+fn config_set(config_path: &Path, key: &str, value: &str) {
+    let set = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(["config", "set", key, value])
+        .output()
+        .unwrap();
+    assert!(
+        set.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&set.stderr)
+    );
+}
+
+/// Runs `anot claude` with `payload`, checks it answered the hook and returns the answer.
+fn run_claude(config_path: &Path, payload: &str) -> serde_json::Value {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .arg("claude")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(payload.as_bytes())
+        .unwrap();
+    let output = child.wait_with_output().unwrap();
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let hook_output: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(hook_output["continue"], true);
+    hook_output
+}
+
+/// Waits for the detached relay to leave `count` lines in the forward spool.
+fn wait_for_spooled(path: &Path, count: usize) {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    loop {
+        let lines = std::fs::read_to_string(path)
+            .map(|contents| contents.lines().count())
+            .unwrap_or(0);
+        if lines == count {
+            return;
+        }
+        assert!(
+            Instant::now() < deadline,
+            "{lines} spooled, expected {count}"
+        );
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Accepts `count` requests, answering 200, and returns their paths, bodies and
+/// `Authorization` headers.
+fn serve(listener: TcpListener, count: usize) -> Vec<(String, String, Option<String>)> {
+    (0..count)
+        .map(|_| {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request_line = String::new();
+            reader.read_line(&mut request_line).unwrap();
+            let path = request_line.split_whitespace().nth(1).unwrap().to_string();
+            let (mut length, mut authorization) = (0, None);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                let (name, value) = line.split_once(':').unwrap();
+                match name.to_lowercase().as_str() {
+                    "content-length" => length = value.trim().parse().unwrap(),
+                    "authorization" => authorization = Some(value.trim().to_string()),
+                    _ => {}
+                }
+            }
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            write!(
+                reader.into_inner(),
+                "HTTP/1.1 200 OK\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+            )
+            .unwrap();
+            (path, String::from_utf8(body).unwrap(), authorization)
+        })
+        .collect()
+}
+
+#[test]
+fn spools_while_the_server_is_down_and_retries_with_the_next_event() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let config_path = temp_config_path("forward-retry", address);
+    let spool = config_path.with_file_name("forward.jsonl");
+
+    run_claude(&config_path, STOP);
+    wait_for_spooled(&spool, 1);
+    // `replace` leaves the notifying to the other machine.
+    assert!(!config_path.with_file_name("status").exists());
+
+    let server = std::thread::spawn(move || serve(TcpListener::bind(address).unwrap(), 2));
+    run_claude(&config_path, NOTIFICATION);
+
+    // Relayed payloads are re-serialized, so they're compared as JSON.
+    let json = |body: &str| serde_json::from_str::<serde_json::Value>(body).unwrap();
+    let relayed: Vec<_> = server
+        .join()
+        .unwrap()
+        .into_iter()
+        .map(|(path, body, authorization)| (path, json(&body), authorization))
+        .collect();
+    let bearer = Some("Bearer s3cret".to_string());
+    assert_eq!(
+        relayed,
+        [
+            ("/claude".to_string(), json(STOP), bearer.clone()),
+            ("/claude".to_string(), json(NOTIFICATION), bearer),
+        ]
+    );
+    wait_for_spooled(&spool, 0);
+    assert!(!config_path.with_file_name("status").exists());
+}
+
+#[test]
+fn the_guard_still_answers_forwarded_calls() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let config_path = temp_config_path("forward-guard", address);
+    config_set(
+        &config_path,
+        "claude.guard",
+        r#"[{"command": "rm -rf", "decision": "deny"}]"#,
+    );
+
+    let hook_output = run_claude(
+        &config_path,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"PreToolUse",
+            "tool_name":"Bash","tool_input":{"command":"rm -rf /"}}"#,
+    );
+    assert_eq!(
+        hook_output["hookSpecificOutput"]["permissionDecision"],
+        "deny"
+    );
+    // It's still relayed, and notified about on the other machine.
+    wait_for_spooled(&config_path.with_file_name("forward.jsonl"), 1);
+    assert!(!config_path.with_file_name("status").exists());
+}
+
+#[test]
+fn relays_payloads_redacted() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    drop(listener);
+    let config_path = temp_config_path("forward-redacted", address);
+    config_set(
+        &config_path,
+        "privacy.redact_patterns",
+        r#"["sk-[a-z0-9]{8,}"]"#,
+    );
+    config_set(&config_path, "claude.forward.content", "full");
+
+    run_claude(
+        &config_path,
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"UserPromptSubmit",
+            "prompt":"deploy with sk-0123456789abcdef"}"#,
+    );
+    let spool = config_path.with_file_name("forward.jsonl");
+    wait_for_spooled(&spool, 1);
+    let spooled = std::fs::read_to_string(&spool).unwrap();
+    assert!(!spooled.contains("sk-0123456789abcdef"), "{spooled}");
+    assert!(spooled.contains("deploy with •••"), "{spooled}");
+}
@@ -0,0 +1,116 @@
+use std::path::PathBuf;
+
+use agent_notifications::{
+    CodexNotificationInput, Config, HookEventName, HookInput,
+    configuration::{Backend, TerminalEscape},
+    processors::{claude, codex},
+};
+
+fn temp_config(test_name: &str) -> Config {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("time went backwards")
+        .as_nanos();
+    let location = std::env::temp_dir()
+        .join(format!("anot-library-{}-{nanos}", std::process::id()))
+        .join(test_name)
+        .join("a-notifications.json");
+
+    Config {
+        backends: vec![Backend::Statusfile],
+        terminal_escape: TerminalEscape {
+            auto_over_ssh: false,
+            ..TerminalEscape::default()
+        },
+        location: Some(location),
+        ..Config::default()
+    }
+}
+
+fn status(config: &Config) -> String {
+    let path: PathBuf = config.data_dir().join("status");
+    std::fs::read_to_string(path).expect("status file was not written")
+}
+
+#[test]
+fn parses_claude_hook_input() {
+    let input: HookInput = serde_json::from_str(
+        r#"{
+            "session_id": "abc",
+            "transcript_path": "/tmp/t.jsonl",
+            "hook_event_name": "PreToolUse",
+            "tool_name": "Bash"
+        }"#,
+    )
+    .unwrap();
+
+    assert_eq!(input.hook_event_name, HookEventName::PreToolUse);
+    assert_eq!(input.tool_name.as_deref(), Some("Bash"));
+}
+
+#[test]
+fn claude_input_returns_hook_output_after_sending() {
+    let config = temp_config("claude");
+
+    let output = claude::process_claude_input(
+        r#"{"session_id": "abc", "transcript_path": "/tmp/t.jsonl", "hook_event_name": "Stop"}"#
+            .to_string(),
+        &config,
+    );
+
+    assert_eq!(output.r#continue, Some(true));
+    assert_eq!(output.suppress_output, Some(true));
+    assert_eq!(output.system_message, None);
+    assert!(status(&config).contains("The agent has stopped responding."));
+}
+
+#[test]
+fn claude_titles_name_the_project_the_session_runs_in() {
+    let mut config = temp_config("claude-project");
+    config
+        .claude
+        .titles
+        .insert("Stop".to_string(), "{project} · {summary}".to_string());
+    let project = config.data_dir().join("my-api");
+    std::fs::create_dir_all(&project).unwrap();
+    let input = serde_json::json!({
+        "session_id": "abc",
+        "transcript_path": "/tmp/t.jsonl",
+        "hook_event_name": "Stop",
+        "cwd": project,
+    });
+
+    let output = claude::process_claude_input(input.to_string(), &config);
+
+    assert_eq!(output.system_message, None);
+    assert_eq!(
+        status(&config),
+        "my-api · Finished: The agent has stopped responding.\n"
+    );
+}
+
+#[test]
+fn claude_parse_errors_are_reported_in_the_output() {
+    let output = claude::process_claude_input("not json".to_string(), &temp_config("invalid"));
+
+    assert_eq!(output.r#continue, Some(true));
+    assert!(
+        output
+            .system_message
+            .unwrap()
+            .starts_with("Failed to parse input JSON")
+    );
+}
+
+#[test]
+fn codex_notifications_can_be_sent_directly() {
+    let config = temp_config("codex");
+    let notification: CodexNotificationInput = serde_json::from_str(
+        r#"{"type": "agent-turn-complete", "last-assistant-message": "Tests pass", "cwd": "/src/anot"}"#,
+    )
+    .unwrap();
+
+    codex::send_notification(&notification, &config).unwrap();
+
+    assert!(status(&config).contains("Tests pass"));
+}
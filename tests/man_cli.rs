@@ -0,0 +1,93 @@
+use std::process::{Command, Output};
+
+fn run_anot(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anot"))
+        .args(args)
+        .output()
+        .expect("failed to run anot")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn manpage_lists_every_subcommand() {
+    let output = run_anot(&["man"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let page = stdout(&output);
+
+    assert!(page.starts_with(".ie"), "not roff: {page}");
+    assert!(page.contains(".TH anot 1"));
+    let mut subcommands = vec![
+        "claude",
+        "codex",
+        "opencode",
+        "init",
+        "reset",
+        "uninstall",
+        "history",
+        "sessions",
+        "replay",
+        "mute",
+        "unmute",
+        "status",
+        "flush",
+        "test",
+        "profile",
+        "config",
+        "completions",
+        "man",
+    ];
+    if cfg!(feature = "serve") {
+        subcommands.push("serve");
+    }
+    for subcommand in subcommands {
+        assert!(
+            page.contains(&format!("anot\\-{subcommand}(1)")),
+            "{subcommand} is missing"
+        );
+    }
+    assert!(page.contains("a\\-notifications.json"));
+    assert!(page.contains("ANOT_PROFILE"));
+}
+
+#[test]
+fn man_check_renders_every_page() {
+    let output = run_anot(&["man", "--check"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout(&output).ends_with("manpages render.\n"));
+}
+
+#[test]
+fn out_dir_gets_a_page_per_command() {
+    let dir = std::env::temp_dir().join(format!("anot-man-{}", std::process::id()));
+    let output = run_anot(&["man", "--out-dir", dir.to_str().unwrap()]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    for page in ["anot.1", "anot-init-claude.1", "anot-config-set.1"] {
+        assert!(dir.join(page).is_file(), "{page} is missing");
+    }
+    let init_claude = std::fs::read_to_string(dir.join("anot-init-claude.1")).unwrap();
+    assert!(init_claude.contains("\\-\\-matcher"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn completions_name_the_binary() {
+    let output = run_anot(&["completions", "bash"]);
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("_anot()"));
+}
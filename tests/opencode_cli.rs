@@ -2,18 +2,9 @@ use std::io::Write;
 use std::path::PathBuf;
 use std::process::{Command, Output, Stdio};
 
-fn temp_config_path(test_name: &str) -> PathBuf {
-    let pid = std::process::id();
-    let nanos = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .expect("time went backwards")
-        .as_nanos();
+mod common;
 
-    std::env::temp_dir()
-        .join(format!("anot-tests-{pid}-{nanos}"))
-        .join(test_name)
-        .join("a-notifications.json")
-}
+use common::temp_config_path;
 
 fn run_anot_with_stdin(args: &[&str], stdin: &str, config_path: &PathBuf) -> Output {
     let exe = env!("CARGO_BIN_EXE_anot");
@@ -0,0 +1,110 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+use serde_json::{Value, json};
+
+mod common;
+
+use common::write_config;
+
+/// A config that only writes the status file, so tests don't need a notification daemon.
+const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false},
+    "codex": {"pretend": false, "sound": false},
+    "backends": ["statusfile"],
+    "mute_mode": "defer",
+    "profiles": {"focus": {"claude": {"sound": false}}}
+}"#;
+
+fn run_anot(args: &[&str], config_path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .env_remove("ANOT_PROFILE")
+        .output()
+        .expect("failed to run anot")
+}
+
+fn json_stdout(output: &Output) -> Value {
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    serde_json::from_slice(&output.stdout).expect("stdout is not JSON")
+}
+
+#[test]
+fn utility_commands_print_json() {
+    let config_path = write_config("output-json", CONFIG);
+
+    let output = run_anot(&["--output", "json", "mute", "1h"], &config_path);
+    let muted = json_stdout(&output);
+    let until = muted["until"].as_u64().expect("until is a timestamp");
+    assert_eq!(
+        muted,
+        json!({"muted": true, "until": until, "dnd": false, "deferred": 0})
+    );
+
+    let output = run_anot(&["status", "--output", "json"], &config_path);
+    assert_eq!(json_stdout(&output), muted);
+
+    let output = run_anot(&["unmute", "--output", "json"], &config_path);
+    assert_eq!(json_stdout(&output), json!({"sent": 0}));
+
+    let output = run_anot(&["history", "--output", "json"], &config_path);
+    assert_eq!(json_stdout(&output), json!([]));
+
+    let output = run_anot(&["--output", "json", "profile", "list"], &config_path);
+    assert_eq!(
+        json_stdout(&output),
+        json!({"profiles": ["focus"], "selected": null})
+    );
+
+    let output = run_anot(
+        &["--output", "json", "profile", "use", "focus"],
+        &config_path,
+    );
+    assert_eq!(json_stdout(&output), json!({"active_profile": "focus"}));
+}
+
+#[test]
+fn errors_are_json_on_stderr() {
+    let config_path = write_config("output-error", CONFIG);
+
+    let output = run_anot(&["--output", "json", "replay"], &config_path);
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let error: Value = serde_json::from_slice(&output.stderr).expect("stderr is not JSON");
+    assert_eq!(error["error"]["code"], "error");
+    assert!(
+        error["error"]["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Nothing to replay yet")
+    );
+
+    let output = run_anot(
+        &[
+            "--output",
+            "json",
+            "replay",
+            "--agent",
+            "claude",
+            "--file",
+            "/nonexistent/payload.json",
+        ],
+        &config_path,
+    );
+    let error: Value = serde_json::from_slice(&output.stderr).expect("stderr is not JSON");
+    assert_eq!(error["error"]["code"], "not_found");
+
+    let output = run_anot(&["status"], &config_path);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stdout),
+        "Notifications are not muted.\n",
+        "text stays the default"
+    );
+}
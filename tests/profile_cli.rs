@@ -0,0 +1,71 @@
+use std::path::Path;
+use std::process::{Command, Output};
+
+mod common;
+
+use common::write_config;
+
+const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false},
+    "codex": {"pretend": false, "sound": false},
+    "profiles": {
+        "focus": {"claude": {"post_tool_use": "off", "sound": false}},
+        "verbose": {"dedupe_window_secs": 0}
+    }
+}"#;
+
+fn run_anot(args: &[&str], config_path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .env_remove("ANOT_PROFILE")
+        .output()
+        .expect("failed to run anot")
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn profile_use_persists_the_active_profile() {
+    let config_path = write_config("profile-use", CONFIG);
+
+    let output = run_anot(&["profile", "use", "verbose"], &config_path);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let config: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&config_path).unwrap()).unwrap();
+    assert_eq!(config["active_profile"], "verbose");
+    assert_eq!(config["profiles"]["focus"]["claude"]["sound"], false);
+
+    let output = run_anot(&["profile", "list"], &config_path);
+    assert_eq!(stdout(&output), "  focus\n* verbose\n");
+
+    let output = run_anot(&["--profile", "focus", "profile", "list"], &config_path);
+    assert_eq!(stdout(&output), "* focus\n  verbose\n");
+}
+
+#[test]
+fn unknown_profiles_are_rejected() {
+    let config_path = write_config("profile-unknown", CONFIG);
+
+    let output = run_anot(&["profile", "use", "quiet"], &config_path);
+    assert!(!output.status.success());
+    assert!(
+        String::from_utf8_lossy(&output.stderr)
+            .contains("Unknown profile `quiet`. Available profiles: focus, verbose")
+    );
+
+    let output = run_anot(&["--profile", "quiet", "history"], &config_path);
+    assert!(!output.status.success());
+
+    let output = run_anot(&["profile", "show", "focus"], &config_path);
+    let focus: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(focus["claude"]["post_tool_use"], "off");
+}
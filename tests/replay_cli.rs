@@ -0,0 +1,98 @@
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+mod common;
+
+use common::{CONFIG, write_config};
+
+fn run_anot(args: &[&str], stdin: &str, config_path: &Path) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to spawn anot");
+    child
+        .stdin
+        .take()
+        .expect("failed to open stdin")
+        .write_all(stdin.as_bytes())
+        .expect("failed to write stdin");
+
+    child.wait_with_output().expect("failed to wait on anot")
+}
+
+fn status(config_path: &Path) -> String {
+    std::fs::read_to_string(config_path.with_file_name("status")).expect("no status file")
+}
+
+#[test]
+fn replays_the_last_recorded_payload() {
+    let config_path = write_config("replay-last", CONFIG);
+    let output = run_anot(
+        &["claude"],
+        r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#,
+        &config_path,
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        status(&config_path),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+
+    let output = run_anot(&["replay"], "", &config_path);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(
+        status(&config_path),
+        "Claude Code · Finished (replay): The agent has stopped responding.\n"
+    );
+}
+
+#[test]
+fn replays_a_payload_from_a_file() {
+    let config_path = write_config("replay-file", CONFIG);
+    let payload = config_path.with_file_name("payload.json");
+    std::fs::write(
+        &payload,
+        r#"{"type":"agent-turn-complete","last-assistant-message":"Tests pass"}"#,
+    )
+    .unwrap();
+
+    let output = run_anot(
+        &[
+            "replay",
+            "--agent",
+            "codex",
+            "--file",
+            payload.to_str().unwrap(),
+        ],
+        "",
+        &config_path,
+    );
+
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(status(&config_path).starts_with("Codex · Turn complete"));
+    assert!(status(&config_path).contains("(replay)"));
+}
+
+#[test]
+fn replay_without_history_exits_nonzero() {
+    let config_path = write_config("replay-empty", CONFIG);
+    let output = run_anot(&["replay"], "", &config_path);
+
+    assert!(!output.status.success());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Nothing to replay yet"), "stderr: {stderr}");
+}
@@ -0,0 +1,83 @@
+use std::path::Path;
+use std::process::{Command, Output, Stdio};
+
+use serde_json::{Value, json};
+
+mod common;
+
+use common::write_config;
+
+const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false, "sound": false},
+    "codex": {"pretend": false, "sound": false, "title_includes_prompt": true},
+    "backends": ["statusfile"],
+    "dedupe_window_secs": 30
+}"#;
+
+fn run_anot(args: &[&str], config_path: &Path) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_anot"))
+        .arg("--config")
+        .arg(config_path)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run anot")
+}
+
+fn read_config(path: &Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+#[test]
+fn resetting_a_section_keeps_the_others() {
+    let config_path = write_config("reset-codex", CONFIG);
+    let output = run_anot(&["reset", "codex", "--yes"], &config_path);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let config = read_config(&config_path);
+    assert_eq!(config["codex"]["sound"], true);
+    assert_eq!(config["codex"]["title_includes_prompt"], false);
+    assert_eq!(config["claude"], json!({"pretend": false, "sound": false}));
+    assert_eq!(config["dedupe_window_secs"], 30);
+    assert_eq!(
+        std::fs::read_to_string(config_path.with_extension("json.bak")).unwrap(),
+        CONFIG
+    );
+}
+
+#[test]
+fn full_reset_restores_the_defaults() {
+    let config_path = write_config("reset-all", CONFIG);
+    let output = run_anot(&["--output", "json", "reset", "--yes"], &config_path);
+    assert!(output.status.success());
+
+    let report: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(
+        report["backup"],
+        config_path.with_extension("json.bak").to_str().unwrap()
+    );
+    let config = read_config(&config_path);
+    assert_eq!(config["dedupe_window_secs"], 10);
+    assert_eq!(config["backends"], json!(["desktop"]));
+}
+
+#[test]
+fn reset_asks_before_changing_anything() {
+    let config_path = write_config("reset-confirm", CONFIG);
+
+    // Without a terminal to answer on, the confirmation fails and nothing is written.
+    let output = run_anot(&["reset"], &config_path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("-  \"dedupe_window_secs\": 30"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), CONFIG);
+
+    let output = run_anot(&["--output", "json", "reset", "claude"], &config_path);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("pass --yes"));
+    assert_eq!(std::fs::read_to_string(&config_path).unwrap(), CONFIG);
+}
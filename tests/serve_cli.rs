@@ -0,0 +1,157 @@
+#![cfg(feature = "serve")]
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpStream;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+
+mod common;
+
+use common::write_config;
+
+const CLAUDE_STOP: &str =
+    r#"{"session_id":"s","transcript_path":"/tmp/t.jsonl","hook_event_name":"Stop"}"#;
+const CODEX_TURN: &str = r#"{"type":"agent-turn-complete","last-assistant-message":"Tests pass"}"#;
+
+/// A config that only writes the status file, so tests don't need a notification daemon.
+const CONFIG: &str = r#"{
+    "version": 1,
+    "claude": {"pretend": false},
+    "codex": {"pretend": false, "sound": false},
+    "backends": ["statusfile"],
+    "dedupe_window_secs": 0,
+    "terminal_escape": {"auto_over_ssh": false}
+}"#;
+
+/// `anot serve` on an ephemeral port, killed when dropped.
+struct Server {
+    child: Child,
+    address: String,
+}
+
+impl Server {
+    fn start(config_path: &Path, token: &str) -> Server {
+        Server::start_in(config_path, token, &std::env::current_dir().unwrap())
+    }
+
+    /// Like [`Server::start`], with `dir` as the server's working directory.
+    fn start_in(config_path: &Path, token: &str, dir: &Path) -> Server {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_anot"))
+            .arg("--config")
+            .arg(config_path)
+            .args(["serve", "--bind", "127.0.0.1:0", "--token", token])
+            .current_dir(dir)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .expect("failed to spawn anot");
+        let mut line = String::new();
+        BufReader::new(child.stdout.take().unwrap())
+            .read_line(&mut line)
+            .unwrap();
+        let address = line
+            .trim()
+            .strip_prefix("Listening on http://")
+            .unwrap_or_else(|| panic!("unexpected output: {line:?}"))
+            .to_string();
+        Server { child, address }
+    }
+
+    /// Sends a raw request and returns the status code and body of the response.
+    fn send(&self, request: &[u8]) -> (u16, String) {
+        let mut stream = TcpStream::connect(&self.address).unwrap();
+        stream.write_all(request).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let status = response
+            .split_whitespace()
+            .nth(1)
+            .and_then(|status| status.parse().ok())
+            .unwrap_or_else(|| panic!("unexpected response: {response:?}"));
+        let body = response
+            .split_once("\r\n\r\n")
+            .map(|(_, body)| body.to_string())
+            .unwrap_or_default();
+        (status, body)
+    }
+
+    fn post(&self, path: &str, token: &str, body: &str) -> (u16, String) {
+        self.send(
+            format!(
+                "POST {path} HTTP/1.1\r\nHost: anot\r\nAuthorization: Bearer {token}\r\n\
+                 Content-Type: application/json\r\nContent-Length: {}\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+    }
+}
+
+impl Drop for Server {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn notifies_for_posted_claude_payloads() {
+    let config_path = write_config("serve-claude", CONFIG);
+    let server = Server::start(&config_path, "s3cret");
+
+    let (status, body) = server.post("/claude", "s3cret", CLAUDE_STOP);
+    assert_eq!(status, 200, "{body}");
+    assert_eq!(
+        std::fs::read_to_string(config_path.with_file_name("status")).unwrap(),
+        "Claude Code · Finished: The agent has stopped responding.\n"
+    );
+}
+
+#[test]
+fn notifies_for_posted_codex_payloads() {
+    let config_path = write_config("serve-codex", CONFIG);
+    let server = Server::start(&config_path, "s3cret");
+
+    assert_eq!(
+        server.post("/codex", "s3cret", CODEX_TURN),
+        (200, "{}".to_string())
+    );
+    let status = std::fs::read_to_string(config_path.with_file_name("status")).unwrap();
+    assert!(status.contains("Tests pass"), "status: {status}");
+}
+
+#[test]
+fn codex_titles_skip_the_directory_the_server_runs_in() {
+    let config_path = write_config("serve-codex-cwd", CONFIG);
+    let server_dir = config_path.with_file_name("my-api");
+    std::fs::create_dir_all(&server_dir).unwrap();
+    let server = Server::start_in(&config_path, "s3cret", &server_dir);
+
+    assert_eq!(server.post("/codex", "s3cret", CODEX_TURN).0, 200);
+    let status = std::fs::read_to_string(config_path.with_file_name("status")).unwrap();
+    let title = status.split(": ").next().unwrap();
+    assert_eq!(title, "Codex · Turn complete");
+}
+
+#[test]
+fn refuses_bad_requests() {
+    let config_path = write_config("serve-refusals", CONFIG);
+    let server = Server::start(&config_path, "s3cret");
+
+    assert_eq!(server.post("/claude", "wrong", CLAUDE_STOP).0, 401);
+    let (status, body) = server.post("/codex", "s3cret", "{not json");
+    assert_eq!(status, 400);
+    assert!(
+        body.contains("Failed to parse Codex notification JSON"),
+        "{body}"
+    );
+    assert_eq!(server.post("/opencode", "s3cret", "{}").0, 404);
+    assert_eq!(
+        server
+            .send(b"POST /claude HTTP/1.1\r\nAuthorization: Bearer s3cret\r\nContent-Length: 2000000\r\n\r\n")
+            .0,
+        413
+    );
+    assert!(!config_path.with_file_name("status").exists());
+}
@@ -0,0 +1,178 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use serde_json::{Value, json};
+
+const CLAUDE_SETTINGS: &str = r#"{
+    "model": "opus",
+    "hooks": {
+        "Notification": [
+            {"matcher": "", "hooks": [{"type": "command", "command": "\"/usr/local/bin/anot\" claude", "timeout": 10}]}
+        ],
+        "Stop": [
+            {"matcher": "", "hooks": [{"type": "command", "command": "\"/usr/local/bin/anot\" claude", "timeout": 10}]},
+            {"matcher": "", "hooks": [{"type": "command", "command": "./scripts/lint.sh", "timeout": 30}]}
+        ]
+    }
+}"#;
+
+const CODEX_CONFIG: &str = r#"model = "gpt-5"
+notify = ["/usr/local/bin/anot", "codex"]
+
+[profiles.work]
+model = "o3"
+notify = ["notify-send", "codex"]
+"#;
+
+/// A fake home with anot installed for Claude Code and Codex, and a project inside it.
+struct Home {
+    root: PathBuf,
+    project: PathBuf,
+}
+
+impl Home {
+    fn new(test_name: &str) -> Self {
+        let pid = std::process::id();
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .expect("time went backwards")
+            .as_nanos();
+        let root = std::env::temp_dir()
+            .join(format!("anot-tests-{pid}-{nanos}"))
+            .join(test_name);
+        let project = root.join("work/api");
+
+        write(&root.join(".claude/settings.json"), CLAUDE_SETTINGS);
+        write(&project.join(".claude/settings.json"), CLAUDE_SETTINGS);
+        write(&root.join(".codex/config.toml"), CODEX_CONFIG);
+        write(
+            &root.join(".config/agent_notifications/a-notifications.json"),
+            "{}",
+        );
+        write(
+            &root.join(".local/state/agent_notifications/logs/anot.log"),
+            "",
+        );
+        Home { root, project }
+    }
+
+    fn run_anot(&self, args: &[&str]) -> Output {
+        Command::new(env!("CARGO_BIN_EXE_anot"))
+            .args(args)
+            .current_dir(&self.project)
+            .env("HOME", &self.root)
+            .env_remove("XDG_CONFIG_HOME")
+            .env_remove("XDG_STATE_HOME")
+            .env_remove("XDG_DATA_HOME")
+            .env_remove("CLAUDE_CONFIG_DIR")
+            .env_remove("CODEX_HOME")
+            .stdin(Stdio::null())
+            .output()
+            .expect("failed to run anot")
+    }
+}
+
+fn write(path: &Path, contents: &str) {
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+    std::fs::write(path, contents).unwrap();
+}
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap()
+}
+
+fn assert_claude_settings_cleaned(path: &Path) {
+    let settings = read_json(path);
+    assert_eq!(settings["model"], "opus", "{}", path.display());
+    assert!(settings["hooks"].get("Notification").is_none());
+    assert_eq!(
+        settings["hooks"]["Stop"],
+        json!([{"matcher": "", "hooks": [{"type": "command", "command": "./scripts/lint.sh", "timeout": 30}]}])
+    );
+}
+
+#[test]
+fn removes_hooks_and_purges_data() {
+    let home = Home::new("uninstall-purge");
+    // Not valid JSON: reported as a failure, and the other steps still run.
+    write(
+        &home.project.join(".claude/settings.local.json"),
+        "{\"hooks\": {",
+    );
+
+    let output = home.run_anot(&["uninstall", "--yes", "--purge"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "stdout: {stdout}");
+    assert!(
+        String::from_utf8_lossy(&output.stderr).contains("1 uninstall step(s) failed"),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout
+            .lines()
+            .any(|line| line.starts_with("failed") && line.contains("settings.local.json")),
+        "stdout: {stdout}"
+    );
+
+    assert_claude_settings_cleaned(&home.root.join(".claude/settings.json"));
+    assert_claude_settings_cleaned(&home.project.join(".claude/settings.json"));
+
+    let codex: toml::Value =
+        toml::from_str(&std::fs::read_to_string(home.root.join(".codex/config.toml")).unwrap())
+            .unwrap();
+    assert!(codex.get("notify").is_none());
+    assert_eq!(codex["model"].as_str(), Some("gpt-5"));
+    assert_eq!(
+        codex["profiles"]["work"]["notify"],
+        toml::Value::try_from(["notify-send", "codex"]).unwrap(),
+        "other notify commands are kept"
+    );
+
+    assert!(!home.root.join(".config/agent_notifications").exists());
+    assert!(!home.root.join(".local/state/agent_notifications").exists());
+}
+
+#[test]
+fn keeps_data_without_purge() {
+    let home = Home::new("uninstall-keep");
+
+    let output = home.run_anot(&["--output", "json", "uninstall", "--yes"]);
+    assert!(
+        output.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let steps: Value = serde_json::from_slice(&output.stdout).unwrap();
+    let outcomes: Vec<(&str, &str)> = steps
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|step| {
+            (
+                step["step"].as_str().unwrap(),
+                step["outcome"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        outcomes,
+        [
+            ("claude hooks", "changed"),
+            ("claude hooks", "changed"),
+            ("claude hooks", "skipped"),
+            ("codex notify", "changed"),
+            ("anot config", "skipped"),
+            ("anot state and logs", "skipped"),
+        ]
+    );
+
+    assert_claude_settings_cleaned(&home.root.join(".claude/settings.json"));
+    assert!(
+        home.root
+            .join(".config/agent_notifications/a-notifications.json")
+            .exists()
+    );
+    assert!(home.root.join(".local/state/agent_notifications").exists());
+}